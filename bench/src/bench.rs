@@ -0,0 +1,256 @@
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use ink::selector_bytes;
+use subxt::{utils::AccountId32, OnlineClient, PolkadotConfig};
+use subxt_signer::sr25519::{dev, Keypair};
+use xcc_sdk::{
+    types::{Balance, ExecutionProof, XcmMessageType},
+    Call, Client, DryRun, MessageOutput, Receipt,
+};
+
+use crate::report::{Measurement, Report};
+
+/// Initial supply of the benchmarked Payment ledger, held by Alice
+const SUPPLY: Balance = 1_000_000_000_000_000;
+/// Amount of every benchmarked payment and bill share
+const SHARE: Balance = 1_000_000_000;
+/// Destination of benchmarked cross-chain payments, supported by default
+const DESTINATION: u32 = 2000;
+const MILLISECONDS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Input sizes to measure the size-dependent messages at
+pub struct Sizes {
+    /// Participants per bill
+    pub participants: Vec<u32>,
+    /// Bills and pending payments per user
+    pub history: Vec<u32>,
+}
+
+/// Contracts deployed for one benchmark run
+pub struct Bench {
+    client: Client,
+    alice: Keypair,
+    bob: Keypair,
+}
+
+impl Bench {
+    /// Deploy fresh contracts, so state left by earlier runs does not skew the costs
+    pub async fn deploy(url: &str) -> anyhow::Result<Self> {
+        let api = OnlineClient::<PolkadotConfig>::from_url(url).await?;
+        let alice = dev::alice();
+        let payment = xcc_e2e::deploy(&api, &alice, "payment", selector_bytes!("new"), SUPPLY).await?;
+        let bill_splitting = xcc_e2e::deploy(&api, &alice, "bill_splitting", selector_bytes!("new"), ()).await?;
+        let xcm_handler = xcc_e2e::deploy(&api, &alice, "xcm_handler", selector_bytes!("new"), ()).await?;
+        eprintln!("deployed payment at {payment}, bill_splitting at {bill_splitting}, xcm_handler at {xcm_handler}");
+
+        let client = Client::from_api(api)
+            .with_payment(payment)
+            .with_bill_splitting(bill_splitting)
+            .with_xcm_handler(xcm_handler);
+        Ok(Self { client, alice, bob: dev::bob() })
+    }
+
+    /// Measure every message, the size-dependent ones at each size
+    pub async fn run(&self, sizes: &Sizes) -> anyhow::Result<Report> {
+        let mut recorder = Recorder::default();
+        self.payment(&mut recorder).await?;
+        self.bill_splitting(&mut recorder, sizes).await?;
+        self.xcm_handler(&mut recorder, sizes).await?;
+        Ok(Report { measurements: recorder.measurements })
+    }
+
+    async fn payment(&self, recorder: &mut Recorder) -> anyhow::Result<()> {
+        let payment = self.client.payment();
+        let (alice, bob) = (account(&self.alice), account(&self.bob));
+
+        // Recipients without a balance yet pay for its storage
+        let send = payment.send(filler(0), SHARE).signed(&self.alice);
+        recorder.record("payment", "send_payment", None, send.dry_run().await)?;
+
+        let approve = payment.approve(bob, SHARE).signed(&self.alice);
+        recorder.record("payment", "approve", None, approve.dry_run().await)?;
+        submit(approve).await?;
+
+        let transfer_from = payment.transfer_from(alice, filler(0), SHARE).signed(&self.bob);
+        recorder.record("payment", "transfer_from", None, transfer_from.dry_run().await)?;
+
+        let initiate = payment.initiate_cross_chain(filler(0), SHARE, DESTINATION).signed(&self.alice);
+        recorder.record("payment", "initiate_cross_chain_payment", None, initiate.dry_run().await)
+    }
+
+    async fn bill_splitting(&self, recorder: &mut Recorder, sizes: &Sizes) -> anyhow::Result<()> {
+        let bills = self.client.bill_splitting();
+        let (alice, bob) = (account(&self.alice), account(&self.bob));
+
+        for &count in &sizes.participants {
+            let input = Some(format!("participants={count}"));
+
+            // Bob's share comes last, the slowest to look up
+            let mut participants: Vec<_> = (1..count).map(filler).collect();
+            participants.push(bob.clone());
+            let shares = vec![SHARE; participants.len()];
+            let create = bills
+                .create_bill(SHARE * Balance::from(count), participants, shares, deadline())
+                .signed(&self.alice);
+            recorder.record("bill_splitting", "create_bill", input.clone(), create.dry_run().await)?;
+            let bill_id = submit(create).await?.output;
+
+            let pay = bills.pay_bill(bill_id, SHARE).signed(&self.bob);
+            recorder.record("bill_splitting", "pay_bill", input.clone(), pay.dry_run().await)?;
+
+            let cancel = bills.cancel_bill(bill_id).signed(&self.alice);
+            recorder.record("bill_splitting", "cancel_bill", input, cancel.dry_run().await)?;
+        }
+
+        let max_history = sizes.history.iter().copied().max().unwrap_or(0);
+        let deposit = bills.deposit(SHARE * Balance::from(max_history + 1)).signed(&self.bob);
+        recorder.record("bill_splitting", "deposit", None, deposit.dry_run().await)?;
+        submit(deposit).await?;
+
+        let withdraw = bills.withdraw(SHARE).signed(&self.bob);
+        recorder.record("bill_splitting", "withdraw", None, withdraw.dry_run().await)?;
+
+        let enable = bills.set_auto_pay(alice.clone(), Some(SHARE)).signed(&self.bob);
+        recorder.record("bill_splitting", "set_auto_pay", None, enable.dry_run().await)?;
+
+        // Bob has no open bills yet, only those created below
+        let mut bills_created = 0;
+        for &history in &sizes.history {
+            let input = Some(format!("bills={history}"));
+            while bills_created < history {
+                submit(bills.create_bill(SHARE, vec![bob.clone()], vec![SHARE], deadline()).signed(&self.alice)).await?;
+                bills_created += 1;
+            }
+
+            let create = bills.create_bill(SHARE, vec![bob.clone()], vec![SHARE], deadline()).signed(&self.alice);
+            recorder.record("bill_splitting", "create_bill", input.clone(), create.dry_run().await)?;
+
+            // Auto-pay stays off while bills are created, so every share is open
+            submit(bills.set_auto_pay(alice.clone(), Some(SHARE)).signed(&self.bob)).await?;
+            let settle = bills.auto_settle_all().signed(&self.bob);
+            recorder.record("bill_splitting", "auto_settle_all", input, settle.dry_run().await)?;
+            submit(bills.set_auto_pay(alice.clone(), None).signed(&self.bob)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn xcm_handler(&self, recorder: &mut Recorder, sizes: &Sizes) -> anyhow::Result<()> {
+        let xcm_handler = self.client.xcm_handler();
+        let (alice, bob) = (account(&self.alice), account(&self.bob));
+        let create = || {
+            xcm_handler
+                .create_payment(bob.clone(), 0, SHARE, DESTINATION, XcmMessageType::Payment)
+                .signed(&self.alice)
+        };
+
+        let max_history = sizes.history.iter().copied().max().unwrap_or(0);
+        let deposit = xcm_handler.deposit(SHARE * Balance::from(max_history + 4)).signed(&self.alice);
+        recorder.record("xcm_handler", "deposit", None, deposit.dry_run().await)?;
+        submit(deposit).await?;
+
+        let withdraw = xcm_handler.withdraw(SHARE).signed(&self.alice);
+        recorder.record("xcm_handler", "withdraw", None, withdraw.dry_run().await)?;
+
+        // Alice relays her own payments to measure the relayer messages
+        let configure = xcm_handler.configure_chain(DESTINATION, true, Some(alice)).signed(&self.alice);
+        recorder.record("xcm_handler", "configure_chain", None, configure.dry_run().await)?;
+        submit(configure).await?;
+
+        let executed = submit(create()).await?.output;
+        let execute = xcm_handler.execute_payment(executed).signed(&self.alice);
+        recorder.record("xcm_handler", "execute_cross_chain_payment", None, execute.dry_run().await)?;
+        submit(execute).await?;
+
+        let relayed = submit(create()).await?.output;
+        let claim = xcm_handler.claim_payment(relayed).signed(&self.alice);
+        recorder.record("xcm_handler", "claim_payment", None, claim.dry_run().await)?;
+        submit(claim).await?;
+        let confirm = xcm_handler
+            .confirm_execution(relayed, ExecutionProof::Attestation)
+            .signed(&self.alice);
+        recorder.record("xcm_handler", "confirm_execution", None, confirm.dry_run().await)?;
+        submit(confirm).await?;
+
+        // Alice's payments above were all executed, none is pending
+        let mut pending = Vec::new();
+        for &history in &sizes.history {
+            let input = Some(format!("pending={history}"));
+            while pending.len() < history as usize {
+                pending.push(submit(create()).await?.output);
+            }
+
+            recorder.record("xcm_handler", "create_cross_chain_payment", input.clone(), create().dry_run().await)?;
+            // The first pending payment is swapped with the last when removed
+            if let Some(&first) = pending.first() {
+                let cancel = xcm_handler.cancel_payment(first).signed(&self.alice);
+                recorder.record("xcm_handler", "cancel_cross_chain_payment", input, cancel.dry_run().await)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Measurements of a run, in the order they were taken
+#[derive(Default)]
+struct Recorder {
+    measurements: Vec<Measurement>,
+}
+
+impl Recorder {
+    fn record<T, E: Debug>(
+        &mut self,
+        contract: &str,
+        message: &str,
+        input: Option<String>,
+        dry_run: Result<DryRun<T>, xcc_sdk::Error<E>>,
+    ) -> anyhow::Result<()> {
+        let dry_run = dry_run.map_err(|error| anyhow!("dry-running {contract}::{message}: {error}"))?;
+        let measurement = Measurement {
+            contract: contract.into(),
+            message: message.into(),
+            input,
+            ref_time: dry_run.gas_required.ref_time,
+            proof_size: dry_run.gas_required.proof_size,
+            storage_deposit: dry_run.storage_deposit,
+        };
+        eprintln!(
+            "{}: ref_time {}, proof_size {}, storage deposit {}",
+            measurement.key(),
+            measurement.ref_time,
+            measurement.proof_size,
+            measurement.storage_deposit
+        );
+        self.measurements.push(measurement);
+        Ok(())
+    }
+}
+
+/// Submit a call setting up a later measurement
+async fn submit<R: MessageOutput>(call: Call<'_, R>) -> anyhow::Result<Receipt<R::Ok>>
+where
+    R::Err: Debug,
+{
+    call.submit().await.map_err(|error| anyhow!("setting up: {error}"))
+}
+
+fn account(keypair: &Keypair) -> AccountId32 {
+    keypair.public_key().into()
+}
+
+/// An account that only appears as participant or recipient
+fn filler(index: u32) -> AccountId32 {
+    let mut id = [0xbe; 32];
+    id[..4].copy_from_slice(&index.to_le_bytes());
+    AccountId32(id)
+}
+
+fn deadline() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    now.as_millis() as u64 + MILLISECONDS_PER_DAY
+}
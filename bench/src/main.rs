@@ -0,0 +1,78 @@
+//! Gas and storage deposit benchmarks of the Payment, BillSplitting and XcmHandler contracts
+//!
+//! Deploys fresh contracts on a dev node, dry-runs every state-changing
+//! message, the size-dependent ones at several input sizes, and writes the
+//! estimated weight and storage deposit of each as a report. Given the
+//! report of an earlier run, it also lists what changed and fails when a
+//! cost grew beyond the tolerance.
+
+mod bench;
+mod report;
+
+use std::path::PathBuf;
+
+use anyhow::bail;
+use clap::Parser;
+
+use crate::{
+    bench::{Bench, Sizes},
+    report::{Comparison, Report},
+};
+
+#[derive(Parser)]
+#[command(name = "xcc-bench", version, about = "Measure the gas and storage costs of the 0xCC contracts")]
+struct Cli {
+    /// WebSocket endpoint of a dev node with pallet-contracts
+    #[arg(long, env = "XCC_URL", default_value = "ws://127.0.0.1:9944")]
+    url: String,
+
+    /// Participants per bill to measure bill messages at
+    #[arg(long, value_delimiter = ',', default_values_t = [1, 4, 16, 50])]
+    participants: Vec<u32>,
+
+    /// Bills and pending payments per user to measure at
+    #[arg(long, value_delimiter = ',', default_values_t = [0, 10, 50])]
+    history: Vec<u32>,
+
+    /// Where to write the report
+    #[arg(long, short, default_value = "bench-report.json")]
+    output: PathBuf,
+
+    /// Report of an earlier run to compare against
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Changes within this percentage of the baseline are ignored
+    #[arg(long, default_value_t = 2.0)]
+    tolerance: f64,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let baseline = cli.baseline.as_deref().map(Report::load).transpose()?;
+
+    let mut sizes = Sizes { participants: cli.participants, history: cli.history };
+    sizes.participants.sort_unstable();
+    sizes.participants.dedup();
+    sizes.history.sort_unstable();
+    sizes.history.dedup();
+    if sizes.participants.contains(&0) {
+        bail!("bills need at least one participant");
+    }
+
+    let report = Bench::deploy(&cli.url).await?.run(&sizes).await?;
+    report.save(&cli.output)?;
+    println!("{}", report.to_markdown());
+
+    if let Some(baseline) = baseline {
+        let comparison = Comparison::new(&baseline, &report, cli.tolerance);
+        println!("{}", comparison.to_markdown());
+        let regressions = comparison.regressions().count();
+        if regressions > 0 {
+            bail!("{regressions} costs grew by more than {}%", cli.tolerance);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// Cost of one message at one input size, as estimated by a dry run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub contract: String,
+    pub message: String,
+    /// Input size the message ran at, e.g. `participants=16`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    pub ref_time: u64,
+    pub proof_size: u64,
+    /// Storage deposit charged, negative when refunded
+    pub storage_deposit: i128,
+}
+
+impl Measurement {
+    /// Identifies the measurement across reports
+    pub fn key(&self) -> String {
+        match &self.input {
+            Some(input) => format!("{}::{}({input})", self.contract, self.message),
+            None => format!("{}::{}", self.contract, self.message),
+        }
+    }
+}
+
+/// All measurements of a run, saved as JSON to compare later runs against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub measurements: Vec<Measurement>,
+}
+
+impl Report {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Markdown table of the measurements
+    pub fn to_markdown(&self) -> String {
+        let mut table = String::from("| Message | ref_time | proof_size | Storage deposit |\n| --- | ---: | ---: | ---: |\n");
+        for measurement in &self.measurements {
+            let _ = writeln!(
+                table,
+                "| `{}` | {} | {} | {} |",
+                measurement.key(),
+                measurement.ref_time,
+                measurement.proof_size,
+                measurement.storage_deposit
+            );
+        }
+        table
+    }
+}
+
+/// A cost that moved by more than the tolerance since the baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub key: String,
+    pub metric: &'static str,
+    pub baseline: i128,
+    pub current: i128,
+}
+
+impl Change {
+    pub fn is_regression(&self) -> bool {
+        self.current > self.baseline
+    }
+
+    /// Relative change, in percent of the baseline
+    fn percent(&self) -> f64 {
+        if self.baseline == 0 {
+            return f64::INFINITY.copysign((self.current - self.baseline) as f64);
+        }
+        (self.current - self.baseline) as f64 * 100.0 / self.baseline.unsigned_abs() as f64
+    }
+}
+
+/// How a report differs from a baseline
+#[derive(Debug, Default)]
+pub struct Comparison {
+    pub changes: Vec<Change>,
+    /// Measured now but not in the baseline
+    pub added: Vec<String>,
+    /// In the baseline but not measured now
+    pub removed: Vec<String>,
+}
+
+impl Comparison {
+    /// Compare every metric, ignoring changes within `tolerance` percent of the baseline
+    pub fn new(baseline: &Report, current: &Report, tolerance: f64) -> Self {
+        let baseline: BTreeMap<_, _> = baseline.measurements.iter().map(|m| (m.key(), m)).collect();
+        let mut comparison = Comparison::default();
+
+        for measurement in &current.measurements {
+            let key = measurement.key();
+            let Some(before) = baseline.get(&key) else {
+                comparison.added.push(key);
+                continue;
+            };
+            let metrics = [
+                ("ref_time", before.ref_time.into(), measurement.ref_time.into()),
+                ("proof_size", before.proof_size.into(), measurement.proof_size.into()),
+                ("storage_deposit", before.storage_deposit, measurement.storage_deposit),
+            ];
+            for (metric, baseline, current) in metrics {
+                let change = Change { key: key.clone(), metric, baseline, current };
+                if baseline != current && change.percent().abs() > tolerance {
+                    comparison.changes.push(change);
+                }
+            }
+        }
+
+        let measured: Vec<_> = current.measurements.iter().map(Measurement::key).collect();
+        comparison.removed = baseline.into_keys().filter(|key| !measured.contains(key)).collect();
+        comparison
+    }
+
+    pub fn regressions(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| change.is_regression())
+    }
+
+    /// Markdown summary of the changes
+    pub fn to_markdown(&self) -> String {
+        if self.changes.is_empty() && self.added.is_empty() && self.removed.is_empty() {
+            return "No changes beyond the tolerance.\n".into();
+        }
+
+        let mut summary = String::new();
+        if !self.changes.is_empty() {
+            summary.push_str("| Message | Metric | Baseline | Current | Change |\n| --- | --- | ---: | ---: | ---: |\n");
+            for change in &self.changes {
+                let marker = if change.is_regression() { " ⚠" } else { "" };
+                let _ = writeln!(
+                    summary,
+                    "| `{}` | {} | {} | {} | {:+.1}%{marker} |",
+                    change.key,
+                    change.metric,
+                    change.baseline,
+                    change.current,
+                    change.percent()
+                );
+            }
+        }
+        for key in &self.added {
+            let _ = writeln!(summary, "\nNew: `{key}`");
+        }
+        for key in &self.removed {
+            let _ = writeln!(summary, "\nNo longer measured: `{key}`");
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(message: &str, input: Option<&str>, ref_time: u64, storage_deposit: i128) -> Measurement {
+        Measurement {
+            contract: "bill_splitting".into(),
+            message: message.into(),
+            input: input.map(Into::into),
+            ref_time,
+            proof_size: 10_000,
+            storage_deposit,
+        }
+    }
+
+    #[test]
+    fn comparison_reports_changes_beyond_the_tolerance() {
+        let baseline = Report {
+            measurements: vec![
+                measurement("create_bill", Some("participants=4"), 1_000_000, 500),
+                measurement("pay_bill", Some("participants=4"), 1_000_000, 100),
+                measurement("cancel_bill", None, 1_000_000, -200),
+            ],
+        };
+        let current = Report {
+            measurements: vec![
+                measurement("create_bill", Some("participants=4"), 1_200_000, 500),
+                measurement("pay_bill", Some("participants=4"), 1_030_000, 80),
+                measurement("withdraw", None, 1_000_000, 0),
+            ],
+        };
+
+        let comparison = Comparison::new(&baseline, &current, 5.0);
+        let changes: Vec<_> = comparison
+            .changes
+            .iter()
+            .map(|change| (change.key.as_str(), change.metric, change.is_regression()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("bill_splitting::create_bill(participants=4)", "ref_time", true),
+                ("bill_splitting::pay_bill(participants=4)", "storage_deposit", false),
+            ]
+        );
+        assert_eq!(comparison.regressions().count(), 1);
+        assert_eq!(comparison.added, vec!["bill_splitting::withdraw"]);
+        assert_eq!(comparison.removed, vec!["bill_splitting::cancel_bill"]);
+    }
+
+    #[test]
+    fn reports_round_trip_through_json() {
+        let report = Report {
+            measurements: vec![measurement("create_bill", Some("participants=1"), 42, -7)],
+        };
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed: Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.measurements, report.measurements);
+        assert!(report.to_markdown().contains("| `bill_splitting::create_bill(participants=1)` | 42 | 10000 | -7 |"));
+    }
+}
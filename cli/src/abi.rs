@@ -0,0 +1,284 @@
+//! Contract ABIs read from the metadata emitted by `cargo contract build`
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use scale_info::{form::PortableForm, PortableRegistry, PortableType, TypeDef, TypeDefPrimitive};
+use scale_value::{Composite, Value, ValueDef};
+use serde::Deserialize;
+use subxt::utils::{AccountId32, H256};
+
+#[derive(Deserialize)]
+struct Metadata {
+    spec: Spec,
+    types: Vec<PortableType>,
+}
+
+#[derive(Deserialize)]
+struct Spec {
+    messages: Vec<MessageSpec>,
+    events: Vec<EventSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessageSpec {
+    pub label: String,
+    selector: String,
+    args: Vec<ArgSpec>,
+    #[serde(rename = "returnType")]
+    return_type: Option<TypeRef>,
+    pub payable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventSpec {
+    label: String,
+    signature_topic: Option<String>,
+    args: Vec<ArgSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArgSpec {
+    label: String,
+    #[serde(rename = "type")]
+    ty: TypeRef,
+}
+
+#[derive(Debug, Deserialize)]
+struct TypeRef {
+    #[serde(rename = "type")]
+    id: u32,
+}
+
+/// A contract event decoded against its ABI
+#[derive(Debug, serde::Serialize)]
+pub struct DecodedEvent {
+    pub label: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+/// Messages, events and types of one contract
+pub struct ContractAbi {
+    messages: Vec<MessageSpec>,
+    events: Vec<EventSpec>,
+    registry: PortableRegistry,
+}
+
+impl ContractAbi {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let metadata: Metadata = serde_json::from_str(text)?;
+        Ok(Self {
+            messages: metadata.spec.messages,
+            events: metadata.spec.events,
+            registry: PortableRegistry { types: metadata.types },
+        })
+    }
+
+    pub fn message(&self, label: &str) -> anyhow::Result<&MessageSpec> {
+        self.messages
+            .iter()
+            .find(|message| message.label == label)
+            .ok_or_else(|| anyhow!("contract has no `{label}` message"))
+    }
+
+    /// Input data calling `label` with `args`, checked against the argument types
+    pub fn encode_call(&self, label: &str, args: &[Value]) -> anyhow::Result<Vec<u8>> {
+        let message = self.message(label)?;
+        if args.len() != message.args.len() {
+            return Err(anyhow!("`{label}` takes {} arguments, got {}", message.args.len(), args.len()));
+        }
+
+        let mut data = decode_hex(&message.selector)?;
+        for (arg, value) in message.args.iter().zip(args) {
+            scale_value::scale::encode_as_type(value, &arg.ty.id, &self.registry, &mut data)
+                .map_err(|e| anyhow!("argument `{}` of `{label}`: {e}", arg.label))?;
+        }
+        Ok(data)
+    }
+
+    /// Decode what `label` returned
+    pub fn decode_return(&self, label: &str, mut data: &[u8]) -> anyhow::Result<Value> {
+        let message = self.message(label)?;
+        let Some(return_type) = &message.return_type else {
+            return Ok(Value::unnamed_composite([]));
+        };
+        let value = scale_value::scale::decode_as_type(&mut data, &return_type.id, &self.registry)?;
+        Ok(self.prettify(value))
+    }
+
+    /// Decode an event emitted by this contract, if its signature topic is known
+    pub fn decode_event(&self, topics: &[H256], mut data: &[u8]) -> Option<DecodedEvent> {
+        let signature = topics.first()?;
+        let event = self.events.iter().find(|event| {
+            event
+                .signature_topic
+                .as_deref()
+                .and_then(|topic| decode_hex(topic).ok())
+                .is_some_and(|topic| topic == signature.0)
+        })?;
+
+        let mut fields = Vec::with_capacity(event.args.len());
+        for arg in &event.args {
+            let value = scale_value::scale::decode_as_type(&mut data, &arg.ty.id, &self.registry).ok()?;
+            fields.push((arg.label.clone(), self.prettify(value)));
+        }
+        Some(DecodedEvent {
+            label: event.label.clone(),
+            fields,
+        })
+    }
+
+    /// Strip type information, showing byte arrays as hex and 32-byte accounts as SS58 addresses
+    fn prettify(&self, value: Value<u32>) -> Value {
+        let ty = value.context;
+        match value.value {
+            ValueDef::Composite(composite) => match self.as_bytes(&composite) {
+                Some(bytes) => match <[u8; 32]>::try_from(bytes.as_slice()) {
+                    Ok(account) => Value::string(AccountId32(account).to_string()),
+                    Err(_) => Value::string(format!("0x{}", hex::encode(bytes))),
+                },
+                // Unwrap newtypes such as `AccountId`
+                None if self.is_newtype(ty) => match composite {
+                    Composite::Named(mut fields) => self.prettify(fields.remove(0).1),
+                    Composite::Unnamed(mut fields) => self.prettify(fields.remove(0)),
+                },
+                None => Value {
+                    value: ValueDef::Composite(self.prettify_composite(composite)),
+                    context: (),
+                },
+            },
+            ValueDef::Variant(variant) => Value::variant(variant.name, self.prettify_composite(variant.values)),
+            ValueDef::Primitive(primitive) => Value::primitive(primitive),
+            ValueDef::BitSequence(bits) => Value::bit_sequence(bits),
+        }
+    }
+
+    fn prettify_composite(&self, composite: Composite<u32>) -> Composite<()> {
+        match composite {
+            Composite::Named(fields) => Composite::Named(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, self.prettify(value)))
+                    .collect(),
+            ),
+            Composite::Unnamed(fields) => {
+                Composite::Unnamed(fields.into_iter().map(|value| self.prettify(value)).collect())
+            }
+        }
+    }
+
+    /// The bytes of a non-empty composite of `u8`s
+    fn as_bytes(&self, composite: &Composite<u32>) -> Option<Vec<u8>> {
+        let values: Vec<&Value<u32>> = composite.values().collect();
+        if values.is_empty() {
+            return None;
+        }
+        values
+            .into_iter()
+            .map(|value| {
+                let is_u8 = matches!(
+                    self.registry.resolve(value.context).map(|ty| &ty.type_def),
+                    Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+                );
+                match value.value {
+                    ValueDef::Primitive(scale_value::Primitive::U128(byte)) if is_u8 => u8::try_from(byte).ok(),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn is_newtype(&self, ty: u32) -> bool {
+        matches!(
+            self.registry.resolve(ty).map(|ty| &ty.type_def),
+            Some(TypeDef::<PortableForm>::Composite(composite)) if composite.fields.len() == 1
+        )
+    }
+}
+
+fn decode_hex(value: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(hex::decode(value.trim_start_matches("0x"))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{meta_type, Registry, TypeInfo};
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct AccountId([u8; 32]);
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Payment {
+        recipient: AccountId,
+        amount: u128,
+        memo: Vec<u8>,
+    }
+
+    fn abi() -> ContractAbi {
+        let mut registry = Registry::new();
+        let account = registry.register_type(&meta_type::<AccountId>()).id;
+        let amount = registry.register_type(&meta_type::<u128>()).id;
+        let payment = registry.register_type(&meta_type::<Option<Payment>>()).id;
+
+        let arg = |label: &str, id| ArgSpec { label: label.into(), ty: TypeRef { id } };
+        ContractAbi {
+            messages: vec![MessageSpec {
+                label: "send".into(),
+                selector: "0x01020304".into(),
+                args: vec![arg("to", account), arg("amount", amount)],
+                return_type: Some(TypeRef { id: payment }),
+                payable: false,
+            }],
+            events: vec![EventSpec {
+                label: "Sent".into(),
+                signature_topic: Some(format!("0x{}", hex::encode([0x11; 32]))),
+                args: vec![arg("to", account), arg("amount", amount)],
+            }],
+            registry: registry.into(),
+        }
+    }
+
+    #[test]
+    fn calls_encode_against_argument_types() {
+        let abi = abi();
+        let data = abi.encode_call("send", &[Value::from_bytes([7; 32]), Value::u128(5)]).unwrap();
+
+        let mut expected = vec![1, 2, 3, 4];
+        expected.extend([7; 32]);
+        expected.extend(5u128.to_le_bytes());
+        assert_eq!(data, expected);
+        assert!(abi.encode_call("send", &[Value::u128(5)]).is_err());
+        assert!(abi.encode_call("receive", &[]).is_err());
+    }
+
+    #[test]
+    fn values_decode_readably() {
+        let abi = abi();
+        let mut data = vec![1];
+        data.extend([7; 32]);
+        data.extend(5u128.to_le_bytes());
+        data.extend([8, 0xab, 0xcd]);
+
+        let value = abi.decode_return("send", &data).unwrap();
+        let account = AccountId32([7; 32]).to_string();
+        assert_eq!(
+            value.to_string(),
+            format!("Some ({{ recipient: \"{account}\", amount: 5, memo: \"0xabcd\" }})")
+        );
+
+        let mut data = vec![7; 32];
+        data.extend(5u128.to_le_bytes());
+        let event = abi.decode_event(&[H256([0x11; 32])], &data).unwrap();
+        assert_eq!(event.label, "Sent");
+        assert_eq!(event.fields[1], ("amount".into(), Value::u128(5)));
+        assert!(abi.decode_event(&[H256([0x22; 32])], &data).is_none());
+    }
+}
@@ -0,0 +1,250 @@
+//! Dry-running and submitting contract calls
+
+use anyhow::{anyhow, bail, Context};
+use codec::{Decode, Encode};
+use scale_value::Value;
+use subxt::{
+    utils::{AccountId32, H256},
+    OnlineClient, PolkadotConfig,
+};
+use subxt_signer::sr25519::Keypair;
+
+use crate::{
+    abi::{ContractAbi, DecodedEvent},
+    output::{Report, Submission},
+};
+
+/// Set by ink! when a message returned an error and its changes were reverted
+const FLAG_REVERT: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Encode, Decode, serde::Serialize)]
+pub struct Weight {
+    #[codec(compact)]
+    pub ref_time: u64,
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+#[derive(Debug, Decode)]
+enum StorageDeposit {
+    Refund(u128),
+    Charge(u128),
+}
+
+#[derive(Debug, Decode)]
+struct ExecReturnValue {
+    flags: u32,
+    data: Vec<u8>,
+}
+
+/// `sp_runtime::DispatchError`, as far as needed to describe it
+#[allow(dead_code)] // Fields are only shown through `Debug`
+#[derive(Debug, Decode)]
+enum DispatchError {
+    Other,
+    CannotLookup,
+    BadOrigin,
+    Module { index: u8, error: [u8; 4] },
+    ConsumerRemaining,
+    NoProviders,
+    TooManyConsumers,
+    Token(u8),
+    Arithmetic(u8),
+    Transactional(u8),
+    Exhausted,
+    Corruption,
+    Unavailable,
+    RootNotAllowed,
+}
+
+/// Result of the `ContractsApi_call` runtime API, up to the fields used here
+#[derive(Debug, Decode)]
+struct ContractExecResult {
+    #[allow(dead_code)]
+    gas_consumed: Weight,
+    gas_required: Weight,
+    storage_deposit: StorageDeposit,
+    #[allow(dead_code)]
+    debug_message: Vec<u8>,
+    result: Result<ExecReturnValue, DispatchError>,
+}
+
+/// A contract the session talks to
+pub struct Contract<'a> {
+    pub address: AccountId32,
+    pub abi: &'a ContractAbi,
+}
+
+/// A connection to a chain and the account acting on it
+pub struct Session {
+    api: OnlineClient<PolkadotConfig>,
+    signer: Option<Keypair>,
+    /// Contracts whose events are decoded
+    known: Vec<(AccountId32, ContractAbi)>,
+}
+
+impl Session {
+    pub async fn connect(url: &str, signer: Option<Keypair>) -> anyhow::Result<Self> {
+        let api = OnlineClient::<PolkadotConfig>::from_url(url)
+            .await
+            .with_context(|| format!("connecting to {url}"))?;
+        Ok(Self {
+            api,
+            signer,
+            known: Vec::new(),
+        })
+    }
+
+    /// Register a contract, returning its index for `contract`
+    pub fn add_contract(&mut self, address: AccountId32, abi: ContractAbi) -> usize {
+        self.known.push((address, abi));
+        self.known.len() - 1
+    }
+
+    pub fn contract(&self, index: usize) -> Contract<'_> {
+        let (address, abi) = &self.known[index];
+        Contract {
+            address: address.clone(),
+            abi,
+        }
+    }
+
+    pub fn signer_account(&self) -> Option<AccountId32> {
+        self.signer.as_ref().map(|signer| signer.public_key().into())
+    }
+
+    /// Account dry runs are made from, a zero account without a signer
+    fn origin(&self) -> AccountId32 {
+        self.signer_account().unwrap_or(AccountId32([0; 32]))
+    }
+
+    /// Run a message without submitting it, failing if it reverts
+    pub async fn query(&self, contract: &Contract<'_>, label: &str, args: &[Value]) -> anyhow::Result<Report> {
+        let report = self.dry_run(contract, label, args, 0).await?;
+        if report.reverted {
+            bail!("`{label}` failed: {}", report.result);
+        }
+        Ok(report)
+    }
+
+    /// Dry-run a message, then submit it unless `dry_run` is set
+    ///
+    /// Calls that revert in the dry run are not submitted.
+    pub async fn call(
+        &self,
+        contract: &Contract<'_>,
+        label: &str,
+        args: &[Value],
+        value: u128,
+        dry_run: bool,
+    ) -> anyhow::Result<Report> {
+        let message = contract.abi.message(label)?;
+        if value > 0 && !message.payable {
+            bail!("`{label}` does not accept a transferred value");
+        }
+
+        let mut report = self.dry_run(contract, label, args, value).await?;
+        if report.reverted {
+            bail!("`{label}` would fail: {}", report.result);
+        }
+        if dry_run {
+            return Ok(report);
+        }
+
+        let signer = self.signer.as_ref().ok_or_else(|| anyhow!("submitting needs a signer, see --suri"))?;
+        let call = subxt::dynamic::tx(
+            "Contracts",
+            "call",
+            vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(contract.address.0)]),
+                Value::u128(value),
+                Value::named_composite([
+                    ("ref_time", Value::u128(report.gas_required.ref_time.into())),
+                    ("proof_size", Value::u128(report.gas_required.proof_size.into())),
+                ]),
+                Value::unnamed_variant("None", []),
+                Value::from_bytes(contract.abi.encode_call(label, args)?),
+            ],
+        );
+
+        let events = self
+            .api
+            .tx()
+            .sign_and_submit_then_watch_default(&call, signer)
+            .await?
+            .wait_for_finalized_success()
+            .await?;
+
+        let mut decoded = Vec::new();
+        for event in events.iter() {
+            let event = event?;
+            if event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted" {
+                decoded.extend(self.decode_event(event.topics(), event.field_bytes()));
+            }
+        }
+        report.submission = Some(Submission {
+            block_hash: events.block_hash(),
+            extrinsic_hash: events.extrinsic_hash(),
+            events: decoded,
+        });
+
+        Ok(report)
+    }
+
+    async fn dry_run(&self, contract: &Contract<'_>, label: &str, args: &[Value], value: u128) -> anyhow::Result<Report> {
+        let input = contract.abi.encode_call(label, args)?;
+        let params = (
+            self.origin(),
+            contract.address.clone(),
+            value,
+            None::<Weight>,
+            None::<u128>,
+            input,
+        )
+            .encode();
+
+        let exec: ContractExecResult = self
+            .api
+            .runtime_api()
+            .at_latest()
+            .await?
+            .call_raw("ContractsApi_call", Some(&params))
+            .await?;
+
+        let output = exec.result.map_err(|error| anyhow!("`{label}` failed: {}", self.describe(error)))?;
+        let storage_deposit = match exec.storage_deposit {
+            StorageDeposit::Charge(amount) => amount as i128,
+            StorageDeposit::Refund(amount) => -(amount as i128),
+        };
+
+        Ok(Report {
+            message: label.to_string(),
+            result: contract.abi.decode_return(label, &output.data)?,
+            reverted: output.flags & FLAG_REVERT != 0,
+            gas_required: exec.gas_required,
+            storage_deposit,
+            submission: None,
+        })
+    }
+
+    /// Decode a contract event against the ABI of its emitter, if known
+    fn decode_event(&self, topics: &[H256], field_bytes: &[u8]) -> Option<DecodedEvent> {
+        let (emitter, data) = <([u8; 32], Vec<u8>)>::decode(&mut &field_bytes[..]).ok()?;
+        let (_, abi) = self.known.iter().find(|(address, _)| address.0 == emitter)?;
+        abi.decode_event(topics, &data)
+    }
+
+    fn describe(&self, error: DispatchError) -> String {
+        let DispatchError::Module { index, error } = error else {
+            return format!("{error:?}");
+        };
+        let metadata = self.api.metadata();
+        metadata
+            .pallet_by_index(index)
+            .and_then(|pallet| {
+                let variant = pallet.error_variant_by_index(error[0])?;
+                Some(format!("{}::{}", pallet.name(), variant.name))
+            })
+            .unwrap_or_else(|| format!("module error {index}:{}", error[0]))
+    }
+}
@@ -0,0 +1,319 @@
+//! Command-line client for the Payment, BillSplitting and XcmHandler contracts
+//!
+//! Every call is dry-run first; calls that would fail are reported and not
+//! submitted. Results and emitted events are decoded with the contract
+//! metadata, printed as text or, with `--json`, as JSON.
+
+mod abi;
+mod client;
+mod output;
+
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use scale_value::Value;
+use subxt::utils::AccountId32;
+use subxt_signer::{sr25519::Keypair, SecretUri};
+
+use crate::{abi::ContractAbi, client::Session};
+
+const MILLISECONDS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Parser)]
+#[command(name = "xcc-cli", version, about = "Interact with the 0xCC contracts")]
+struct Cli {
+    /// WebSocket endpoint of the node
+    #[arg(long, env = "XCC_URL", default_value = "ws://127.0.0.1:9944", global = true)]
+    url: String,
+
+    /// Secret URI of the signing account, e.g. `//Alice`
+    #[arg(long, env = "XCC_SURI", global = true, hide_env_values = true)]
+    suri: Option<String>,
+
+    /// Directory holding `payment.json`, `bill_splitting.json` and `xcm_handler.json`
+    #[arg(long, env = "XCC_METADATA_DIR", default_value = "target/ink", global = true)]
+    metadata_dir: PathBuf,
+
+    /// Address of the Payment contract
+    #[arg(long, env = "XCC_PAYMENT", global = true)]
+    payment: Option<AccountId32>,
+
+    /// Address of the BillSplitting contract
+    #[arg(long, env = "XCC_BILL_SPLITTING", global = true)]
+    bill_splitting: Option<AccountId32>,
+
+    /// Address of the XcmHandler contract
+    #[arg(long, env = "XCC_XCM_HANDLER", global = true)]
+    xcm_handler: Option<AccountId32>,
+
+    /// Estimate and show the result without submitting
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Payment ledger
+    #[command(subcommand)]
+    Pay(PayCommand),
+    /// Bill splitting
+    #[command(subcommand)]
+    Bill(BillCommand),
+    /// Cross-chain payments
+    #[command(subcommand)]
+    Xcm(XcmCommand),
+}
+
+#[derive(Subcommand)]
+enum PayCommand {
+    /// Send ledger balance to an account
+    Send {
+        #[arg(long)]
+        to: AccountId32,
+        #[arg(long)]
+        amount: u128,
+    },
+    /// Show the ledger balance of an account, the signer's by default
+    Balance { account: Option<AccountId32> },
+}
+
+#[derive(Subcommand)]
+enum BillCommand {
+    /// Create a bill split between participants
+    Create(CreateBill),
+    /// Pay the caller's share of a bill
+    Pay {
+        bill_id: u32,
+        /// Share plus any late fee
+        #[arg(long)]
+        amount: u128,
+        /// Pay from Payment ledger balance instead of transferring native funds
+        #[arg(long)]
+        ledger: bool,
+    },
+    /// Show a bill
+    Show { bill_id: u32 },
+}
+
+#[derive(Args)]
+struct CreateBill {
+    #[arg(long)]
+    total: u128,
+    /// Comma-separated participant addresses
+    #[arg(long, value_delimiter = ',', required = true)]
+    participants: Vec<AccountId32>,
+    /// Split the total evenly, the first participants covering any remainder
+    #[arg(long, conflicts_with = "amounts", required_unless_present = "amounts")]
+    equal: bool,
+    /// Comma-separated share of each participant
+    #[arg(long, value_delimiter = ',')]
+    amounts: Vec<u128>,
+    /// Days until the bill is due
+    #[arg(long, default_value_t = 7)]
+    deadline_days: u64,
+}
+
+#[derive(Subcommand)]
+enum XcmCommand {
+    /// Deposit native funds to pay cross-chain payments from
+    Deposit {
+        #[arg(long)]
+        amount: u128,
+    },
+    /// Create a cross-chain payment from the deposited balance
+    Create {
+        #[arg(long)]
+        recipient: AccountId32,
+        #[arg(long)]
+        amount: u128,
+        /// Parachain ID of the destination
+        #[arg(long)]
+        dest_chain: u32,
+        /// Asset ID, 0 for the native token
+        #[arg(long, default_value_t = 0)]
+        asset: u32,
+        #[arg(long, value_enum, default_value_t = MessageType::Payment)]
+        message_type: MessageType,
+    },
+    /// Show a cross-chain payment
+    Status { payment_id: u32 },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MessageType {
+    Payment,
+    BillSplitting,
+    TokenTransfer,
+}
+
+impl MessageType {
+    fn value(self) -> Value {
+        let name = match self {
+            MessageType::Payment => "Payment",
+            MessageType::BillSplitting => "BillSplitting",
+            MessageType::TokenTransfer => "TokenTransfer",
+        };
+        Value::unnamed_variant(name, [])
+    }
+}
+
+fn account(account: &AccountId32) -> Value {
+    Value::from_bytes(account.0)
+}
+
+/// Shares of `total` split evenly, the first ones covering the remainder
+fn equal_shares(total: u128, participants: usize) -> anyhow::Result<Vec<u128>> {
+    if participants == 0 {
+        bail!("a bill needs participants");
+    }
+    let count = participants as u128;
+    let (share, remainder) = (total / count, total % count);
+    Ok((0..count).map(|index| share + u128::from(index < remainder)).collect())
+}
+
+struct App {
+    cli: Cli,
+    session: Session,
+}
+
+impl App {
+    async fn new(cli: Cli) -> anyhow::Result<Self> {
+        let signer = cli
+            .suri
+            .as_deref()
+            .map(|suri| {
+                let uri = SecretUri::from_str(suri).map_err(|e| anyhow!("invalid --suri: {e}"))?;
+                Keypair::from_uri(&uri).map_err(|e| anyhow!("invalid --suri: {e}"))
+            })
+            .transpose()?;
+        let session = Session::connect(&cli.url, signer).await?;
+        Ok(Self { cli, session })
+    }
+
+    /// Load the ABI of a contract, returning its session index
+    fn contract(&mut self, name: &str, address: Option<AccountId32>) -> anyhow::Result<Option<usize>> {
+        let Some(address) = address else {
+            return Ok(None);
+        };
+        let abi = ContractAbi::load(&self.cli.metadata_dir.join(format!("{name}.json")))?;
+        Ok(Some(self.session.add_contract(address, abi)))
+    }
+
+    async fn run(mut self) -> anyhow::Result<()> {
+        // Every contract with an address is registered, so their events decode
+        let payment = self.contract("payment", self.cli.payment.clone())?;
+        let bill_splitting = self.contract("bill_splitting", self.cli.bill_splitting.clone())?;
+        let xcm_handler = self.contract("xcm_handler", self.cli.xcm_handler.clone())?;
+
+        let dry_run = self.cli.dry_run;
+        let session = &self.session;
+        let report = match &self.cli.command {
+            Command::Pay(command) => {
+                let payment = session.contract(payment.ok_or_else(|| anyhow!("set the Payment contract with --payment"))?);
+                match command {
+                    PayCommand::Send { to, amount } => {
+                        session
+                            .call(&payment, "send_payment", &[account(to), Value::u128(*amount)], 0, dry_run)
+                            .await?
+                    }
+                    PayCommand::Balance { account: target } => {
+                        let target = target
+                            .clone()
+                            .or_else(|| session.signer_account())
+                            .ok_or_else(|| anyhow!("give an account or --suri"))?;
+                        session.query(&payment, "balance_of", &[account(&target)]).await?
+                    }
+                }
+            }
+            Command::Bill(command) => {
+                let bills = session.contract(
+                    bill_splitting.ok_or_else(|| anyhow!("set the BillSplitting contract with --bill-splitting"))?,
+                );
+                match command {
+                    BillCommand::Create(bill) => {
+                        let amounts = if bill.equal {
+                            equal_shares(bill.total, bill.participants.len())?
+                        } else {
+                            bill.amounts.clone()
+                        };
+                        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+                        let args = [
+                            Value::u128(bill.total),
+                            Value::unnamed_composite(bill.participants.iter().map(account)),
+                            Value::unnamed_composite(amounts.into_iter().map(Value::u128)),
+                            Value::u128((now + bill.deadline_days * MILLISECONDS_PER_DAY).into()),
+                        ];
+                        session.call(&bills, "create_bill", &args, 0, dry_run).await?
+                    }
+                    BillCommand::Pay { bill_id, amount, ledger } => {
+                        let value = if *ledger { 0 } else { *amount };
+                        let args = [Value::u128((*bill_id).into()), Value::u128(*amount)];
+                        session.call(&bills, "pay_bill", &args, value, dry_run).await?
+                    }
+                    BillCommand::Show { bill_id } => {
+                        session.query(&bills, "get_bill_info", &[Value::u128((*bill_id).into())]).await?
+                    }
+                }
+            }
+            Command::Xcm(command) => {
+                let handler = session.contract(
+                    xcm_handler.ok_or_else(|| anyhow!("set the XcmHandler contract with --xcm-handler"))?,
+                );
+                match command {
+                    XcmCommand::Deposit { amount } => session.call(&handler, "deposit", &[], *amount, dry_run).await?,
+                    XcmCommand::Create {
+                        recipient,
+                        amount,
+                        dest_chain,
+                        asset,
+                        message_type,
+                    } => {
+                        let args = [
+                            account(recipient),
+                            Value::u128((*asset).into()),
+                            Value::u128(*amount),
+                            Value::u128((*dest_chain).into()),
+                            message_type.value(),
+                        ];
+                        session.call(&handler, "create_cross_chain_payment", &args, 0, dry_run).await?
+                    }
+                    XcmCommand::Status { payment_id } => {
+                        session.query(&handler, "get_payment", &[Value::u128((*payment_id).into())]).await?
+                    }
+                }
+            }
+        };
+
+        report.print(self.cli.json)
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    App::new(cli).await?.run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_shares_cover_the_total() {
+        assert_eq!(equal_shares(100, 3).unwrap(), vec![34, 33, 33]);
+        assert_eq!(equal_shares(90, 3).unwrap(), vec![30, 30, 30]);
+        assert!(equal_shares(90, 0).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use scale_value::Value;
+use serde::Serialize;
+use subxt::utils::H256;
+
+use crate::{abi::DecodedEvent, client::Weight};
+
+/// Outcome of a query or call
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub message: String,
+    /// Decoded return value
+    pub result: Value,
+    /// Whether the message failed and its changes were reverted
+    pub reverted: bool,
+    pub gas_required: Weight,
+    /// Storage deposit charged, negative when refunded
+    pub storage_deposit: i128,
+    /// Set once the call was included on chain
+    pub submission: Option<Submission>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Submission {
+    pub block_hash: H256,
+    pub extrinsic_hash: H256,
+    pub events: Vec<DecodedEvent>,
+}
+
+impl Report {
+    pub fn print(&self, json: bool) -> anyhow::Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+            return Ok(());
+        }
+
+        println!("{}: {}", self.message, self.result);
+        println!(
+            "  gas required: {} ref time, {} proof size",
+            self.gas_required.ref_time, self.gas_required.proof_size
+        );
+        if self.storage_deposit != 0 {
+            println!("  storage deposit: {}", self.storage_deposit);
+        }
+
+        if let Some(submission) = &self.submission {
+            println!("  included in block {:?}", submission.block_hash);
+            println!("  extrinsic {:?}", submission.extrinsic_hash);
+            for event in &submission.events {
+                let fields: Vec<String> = event.fields.iter().map(|(name, value)| format!("{name}: {value}")).collect();
+                println!("  {} {{ {} }}", event.label, fields.join(", "));
+            }
+        }
+
+        Ok(())
+    }
+}
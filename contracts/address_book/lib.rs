@@ -0,0 +1,301 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::address_book::{AddressBookRef, Contact, Error};
+
+#[ink::contract]
+mod address_book {
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::RecipientGuard;
+
+    /// Maximum number of contacts per account
+    pub const MAX_CONTACTS: usize = 100;
+
+    /// Maximum length of a contact label in bytes
+    pub const MAX_LABEL_LEN: usize = 64;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        ContactExists,
+        ContactNotFound,
+        TooManyContacts,
+        LabelTooLong,
+        SelfContact,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::ContactNotFound => Self::NotFound,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// An entry in an account's address book
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Contact {
+        pub account: AccountId,
+        pub label: String,
+        pub trusted: bool,
+    }
+
+    /// Per-account contact lists
+    ///
+    /// Accounts in safe mode may only be paid out to their contacts above
+    /// their threshold, which contracts moving their funds check through
+    /// `RecipientGuard::may_pay`.
+    #[ink(storage)]
+    pub struct AddressBook {
+        contacts: Mapping<(AccountId, AccountId), Contact>,
+        /// Contacts of each account, in the order they were added
+        contact_lists: Mapping<AccountId, Vec<AccountId>>,
+        /// Amount above which payments must go to a contact, for accounts in safe mode
+        safe_mode_thresholds: Mapping<AccountId, Balance>,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct ContactAdded {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        contact: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ContactUpdated {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        contact: AccountId,
+        trusted: bool,
+    }
+
+    #[ink(event)]
+    pub struct ContactRemoved {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        contact: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct SafeModeUpdated {
+        #[ink(topic)]
+        owner: AccountId,
+        threshold: Option<Balance>,
+    }
+
+    impl AddressBook {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                contacts: Mapping::default(),
+                contact_lists: Mapping::default(),
+                safe_mode_thresholds: Mapping::default(),
+            }
+        }
+
+        /// Add an account to the caller's contacts
+        #[ink(message)]
+        pub fn add_contact(&mut self, contact: AccountId, label: String, trusted: bool) -> Result<(), Error> {
+            let owner = self.env().caller();
+            if contact == owner {
+                return Err(Error::SelfContact);
+            }
+            if label.len() > MAX_LABEL_LEN {
+                return Err(Error::LabelTooLong);
+            }
+            if self.contacts.contains((owner, contact)) {
+                return Err(Error::ContactExists);
+            }
+            let mut list = self.contact_lists.get(owner).unwrap_or_default();
+            if list.len() >= MAX_CONTACTS {
+                return Err(Error::TooManyContacts);
+            }
+
+            list.push(contact);
+            self.contact_lists.insert(owner, &list);
+            self.contacts.insert(
+                (owner, contact),
+                &Contact {
+                    account: contact,
+                    label,
+                    trusted,
+                },
+            );
+
+            self.env().emit_event(ContactAdded { owner, contact });
+
+            Ok(())
+        }
+
+        /// Change the label of one of the caller's contacts
+        #[ink(message)]
+        pub fn set_label(&mut self, contact: AccountId, label: String) -> Result<(), Error> {
+            if label.len() > MAX_LABEL_LEN {
+                return Err(Error::LabelTooLong);
+            }
+            self.update_contact(contact, |entry| entry.label = label)
+        }
+
+        /// Mark one of the caller's contacts as trusted or not
+        #[ink(message)]
+        pub fn set_trusted(&mut self, contact: AccountId, trusted: bool) -> Result<(), Error> {
+            self.update_contact(contact, |entry| entry.trusted = trusted)
+        }
+
+        /// Remove an account from the caller's contacts
+        #[ink(message)]
+        pub fn remove_contact(&mut self, contact: AccountId) -> Result<(), Error> {
+            let owner = self.env().caller();
+            if self.contacts.take((owner, contact)).is_none() {
+                return Err(Error::ContactNotFound);
+            }
+
+            let mut list = self.contact_lists.get(owner).unwrap_or_default();
+            list.retain(|account| *account != contact);
+            self.contact_lists.insert(owner, &list);
+
+            self.env().emit_event(ContactRemoved { owner, contact });
+
+            Ok(())
+        }
+
+        /// Only allow payments above `threshold` to the caller's contacts, or lift the restriction
+        #[ink(message)]
+        pub fn set_safe_mode(&mut self, threshold: Option<Balance>) {
+            let owner = self.env().caller();
+
+            if let Some(threshold) = threshold {
+                self.safe_mode_thresholds.insert(owner, &threshold);
+            } else {
+                self.safe_mode_thresholds.remove(owner);
+            }
+
+            self.env().emit_event(SafeModeUpdated { owner, threshold });
+        }
+
+        /// Get one of an account's contacts
+        #[ink(message)]
+        pub fn get_contact(&self, owner: AccountId, contact: AccountId) -> Option<Contact> {
+            self.contacts.get((owner, contact))
+        }
+
+        /// Get all of an account's contacts
+        #[ink(message)]
+        pub fn get_contacts(&self, owner: AccountId) -> Vec<Contact> {
+            self.contact_lists
+                .get(owner)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|contact| self.contacts.get((owner, contact)))
+                .collect()
+        }
+
+        /// Get the safe mode threshold of an account, if it is in safe mode
+        #[ink(message)]
+        pub fn get_safe_mode(&self, owner: AccountId) -> Option<Balance> {
+            self.safe_mode_thresholds.get(owner)
+        }
+
+        fn update_contact(&mut self, contact: AccountId, update: impl FnOnce(&mut Contact)) -> Result<(), Error> {
+            let owner = self.env().caller();
+            let mut entry = self.contacts.get((owner, contact)).ok_or(Error::ContactNotFound)?;
+
+            update(&mut entry);
+            self.contacts.insert((owner, contact), &entry);
+
+            self.env().emit_event(ContactUpdated {
+                owner,
+                contact,
+                trusted: entry.trusted,
+            });
+
+            Ok(())
+        }
+    }
+
+    impl Default for AddressBook {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl RecipientGuard for AddressBook {
+        #[ink(message)]
+        fn may_pay(&self, from: AccountId, to: AccountId, amount: Balance) -> bool {
+            match self.safe_mode_thresholds.get(from) {
+                Some(threshold) if amount > threshold => self.contacts.contains((from, to)),
+                _ => true,
+            }
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn contacts_work() {
+            let mut address_book = AddressBook::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            address_book.add_contact(accounts.bob, "Bob".into(), false).unwrap();
+            address_book.add_contact(accounts.charlie, "Charlie".into(), true).unwrap();
+            assert_eq!(
+                address_book.add_contact(accounts.bob, "Bob".into(), false),
+                Err(Error::ContactExists)
+            );
+            assert_eq!(
+                address_book.add_contact(accounts.alice, "Me".into(), false),
+                Err(Error::SelfContact)
+            );
+
+            address_book.set_label(accounts.bob, "Bobby".into()).unwrap();
+            address_book.set_trusted(accounts.bob, true).unwrap();
+            assert_eq!(
+                address_book.get_contact(accounts.alice, accounts.bob),
+                Some(Contact {
+                    account: accounts.bob,
+                    label: "Bobby".into(),
+                    trusted: true,
+                })
+            );
+
+            address_book.remove_contact(accounts.charlie).unwrap();
+            assert_eq!(address_book.remove_contact(accounts.charlie), Err(Error::ContactNotFound));
+            assert_eq!(address_book.get_contacts(accounts.alice).len(), 1);
+            assert!(address_book.get_contacts(accounts.bob).is_empty());
+        }
+
+        #[ink::test]
+        fn safe_mode_works() {
+            let mut address_book = AddressBook::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            address_book.add_contact(accounts.bob, "Bob".into(), false).unwrap();
+
+            assert!(address_book.may_pay(accounts.alice, accounts.eve, 1_000));
+
+            address_book.set_safe_mode(Some(100));
+            assert_eq!(address_book.get_safe_mode(accounts.alice), Some(100));
+            assert!(address_book.may_pay(accounts.alice, accounts.eve, 100));
+            assert!(!address_book.may_pay(accounts.alice, accounts.eve, 101));
+            assert!(address_book.may_pay(accounts.alice, accounts.bob, 1_000));
+
+            // Other accounts are unaffected
+            assert!(address_book.may_pay(accounts.bob, accounts.eve, 1_000));
+
+            address_book.set_safe_mode(None);
+            assert!(address_book.may_pay(accounts.alice, accounts.eve, 1_000));
+        }
+    }
+}
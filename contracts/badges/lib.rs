@@ -0,0 +1,382 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::badges::{Badge, BadgeId, Badges, BadgesRef, Error, Milestone};
+
+#[ink::contract]
+mod badges {
+    use ink::prelude::vec;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::{Activity, ActivityRecorder};
+
+    /// Badge IDs, assigned in minting order
+    pub type BadgeId = u32;
+
+    /// Most milestones the owner can define, bounding the work of each report
+    pub const MAX_MILESTONES: u32 = 32;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        InvalidThreshold,
+        TooManyMilestones,
+        /// Badges stay with the account that earned them
+        Soulbound,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// An amount of one activity that earns a badge
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Milestone {
+        pub activity: Activity,
+        pub threshold: u32,
+    }
+
+    /// A badge earned by reaching a milestone
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Badge {
+        pub owner: AccountId,
+        /// Index of the milestone in `get_milestones`
+        pub milestone: u32,
+        /// Block the badge was minted in
+        pub minted_at: BlockNumber,
+    }
+
+    /// Non-transferable reliability badges, in the manner of PSP34
+    ///
+    /// Trusted contracts report account activity, such as the Payment ledger
+    /// reporting payments sent and BillSplitting reporting shares paid by their
+    /// deadline. Each account reaching a milestone's count of an activity is
+    /// minted one badge for it, giving groups an on-chain trust signal. Badges
+    /// can never be transferred or approved.
+    #[ink(storage)]
+    pub struct Badges {
+        owner: AccountId,
+        /// Contract trusted to report each activity
+        reporters: Mapping<Activity, AccountId>,
+        milestones: Vec<Milestone>,
+        /// Reported activity (account -> activity -> count)
+        activity_counts: Mapping<(AccountId, Activity), u32>,
+        badges: Mapping<BadgeId, Badge>,
+        /// Badge minted to an account for a milestone (account -> milestone -> badge_id)
+        account_badges: Mapping<(AccountId, u32), BadgeId>,
+        badge_counts: Mapping<AccountId, u32>,
+        /// Badges minted so far, and the ID of the next one
+        total_supply: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct BadgeMinted {
+        #[ink(topic)]
+        owner: AccountId,
+        badge_id: BadgeId,
+        milestone: u32,
+        activity: Activity,
+        threshold: u32,
+    }
+
+    #[ink(event)]
+    pub struct MilestoneAdded {
+        milestone: u32,
+        activity: Activity,
+        threshold: u32,
+    }
+
+    #[ink(event)]
+    pub struct ReporterSet {
+        activity: Activity,
+        reporter: Option<AccountId>,
+    }
+
+    impl Badges {
+        /// Constructor with the default milestones: 10 bills paid on time and 100 payments sent
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                reporters: Mapping::default(),
+                milestones: vec![
+                    Milestone {
+                        activity: Activity::BillPaidOnTime,
+                        threshold: 10,
+                    },
+                    Milestone {
+                        activity: Activity::PaymentSent,
+                        threshold: 100,
+                    },
+                ],
+                activity_counts: Mapping::default(),
+                badges: Mapping::default(),
+                account_badges: Mapping::default(),
+                badge_counts: Mapping::default(),
+                total_supply: 0,
+            }
+        }
+
+        /// Set the contract trusted to report an activity (owner only)
+        #[ink(message)]
+        pub fn set_reporter(&mut self, activity: Activity, reporter: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            match reporter {
+                Some(reporter) => {
+                    self.reporters.insert(activity, &reporter);
+                }
+                None => self.reporters.remove(activity),
+            }
+
+            self.env().emit_event(ReporterSet { activity, reporter });
+
+            Ok(())
+        }
+
+        /// Add a milestone, returning its index (owner only)
+        ///
+        /// Accounts already past the threshold earn the badge on their next
+        /// reported activity of the kind.
+        #[ink(message)]
+        pub fn add_milestone(&mut self, activity: Activity, threshold: u32) -> Result<u32, Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if threshold == 0 {
+                return Err(Error::InvalidThreshold);
+            }
+            let milestone = self.milestones.len() as u32;
+            if milestone >= MAX_MILESTONES {
+                return Err(Error::TooManyMilestones);
+            }
+
+            self.milestones.push(Milestone { activity, threshold });
+
+            self.env().emit_event(MilestoneAdded {
+                milestone,
+                activity,
+                threshold,
+            });
+
+            Ok(milestone)
+        }
+
+        /// Badges cannot be transferred
+        #[ink(message)]
+        pub fn transfer(&mut self, _to: AccountId, _id: BadgeId, _data: Vec<u8>) -> Result<(), Error> {
+            Err(Error::Soulbound)
+        }
+
+        /// Badges cannot be approved for transfer
+        #[ink(message)]
+        pub fn approve(&mut self, _operator: AccountId, _id: Option<BadgeId>, _approved: bool) -> Result<(), Error> {
+            Err(Error::Soulbound)
+        }
+
+        /// Get the holder of a badge
+        #[ink(message)]
+        pub fn owner_of(&self, id: BadgeId) -> Option<AccountId> {
+            self.badges.get(id).map(|badge| badge.owner)
+        }
+
+        /// Get the number of badges an account holds
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> u32 {
+            self.badge_counts.get(owner).unwrap_or(0)
+        }
+
+        /// Get the number of badges minted
+        #[ink(message)]
+        pub fn total_supply(&self) -> u32 {
+            self.total_supply
+        }
+
+        /// Get a badge
+        #[ink(message)]
+        pub fn get_badge(&self, id: BadgeId) -> Option<Badge> {
+            self.badges.get(id)
+        }
+
+        /// Get the badge an account earned for a milestone
+        #[ink(message)]
+        pub fn badge_of(&self, account: AccountId, milestone: u32) -> Option<BadgeId> {
+            self.account_badges.get((account, milestone))
+        }
+
+        /// Get how often an activity of an account was reported
+        #[ink(message)]
+        pub fn get_activity_count(&self, account: AccountId, activity: Activity) -> u32 {
+            self.activity_counts.get((account, activity)).unwrap_or(0)
+        }
+
+        /// Get the milestones, by index
+        #[ink(message)]
+        pub fn get_milestones(&self) -> Vec<Milestone> {
+            self.milestones.clone()
+        }
+
+        /// Get the contract trusted to report an activity
+        #[ink(message)]
+        pub fn get_reporter(&self, activity: Activity) -> Option<AccountId> {
+            self.reporters.get(activity)
+        }
+
+        /// Get the contract owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        fn mint(&mut self, owner: AccountId, milestone: u32, reached: Milestone) {
+            let badge_id = self.total_supply;
+            self.total_supply = self.total_supply.saturating_add(1);
+            self.badges.insert(
+                badge_id,
+                &Badge {
+                    owner,
+                    milestone,
+                    minted_at: self.env().block_number(),
+                },
+            );
+            self.account_badges.insert((owner, milestone), &badge_id);
+            self.badge_counts.insert(owner, &self.balance_of(owner).saturating_add(1));
+
+            self.env().emit_event(BadgeMinted {
+                owner,
+                badge_id,
+                milestone,
+                activity: reached.activity,
+                threshold: reached.threshold,
+            });
+        }
+    }
+
+    impl Default for Badges {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl ActivityRecorder for Badges {
+        /// Count the activity, minting the badges of any milestones it reaches
+        #[ink(message)]
+        fn record_activity(&mut self, account: AccountId, activity: Activity) -> Result<(), xcc_common::Error> {
+            if self.reporters.get(activity) != Some(self.env().caller()) {
+                return Err(Error::UnauthorizedAccess.into());
+            }
+
+            let count = self.get_activity_count(account, activity).saturating_add(1);
+            self.activity_counts.insert((account, activity), &count);
+
+            for (milestone, reached) in self.milestones.clone().into_iter().enumerate() {
+                let milestone = milestone as u32;
+                if reached.activity == activity
+                    && count >= reached.threshold
+                    && !self.account_badges.contains((account, milestone))
+                {
+                    self.mint(account, milestone, reached);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn milestones_mint_badges_once() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let ledger = accounts.django;
+            let mut badges = Badges::new();
+            badges.set_reporter(Activity::PaymentSent, Some(ledger)).unwrap();
+            let early = badges.add_milestone(Activity::PaymentSent, 2).unwrap();
+
+            // Only the reporter of an activity reports it
+            assert_eq!(
+                badges.record_activity(accounts.bob, Activity::PaymentSent),
+                Err(xcc_common::Error::UnauthorizedAccess)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(ledger);
+            assert_eq!(
+                badges.record_activity(accounts.bob, Activity::BillPaidOnTime),
+                Err(xcc_common::Error::UnauthorizedAccess)
+            );
+            badges.record_activity(accounts.bob, Activity::PaymentSent).unwrap();
+            assert_eq!(badges.balance_of(accounts.bob), 0);
+
+            badges.record_activity(accounts.bob, Activity::PaymentSent).unwrap();
+            badges.record_activity(accounts.bob, Activity::PaymentSent).unwrap();
+            assert_eq!(badges.get_activity_count(accounts.bob, Activity::PaymentSent), 3);
+            assert_eq!(badges.balance_of(accounts.bob), 1);
+            assert_eq!(badges.badge_of(accounts.bob, early), Some(0));
+            assert_eq!(badges.owner_of(0), Some(accounts.bob));
+            assert_eq!(badges.get_badge(0).map(|badge| badge.milestone), Some(early));
+
+            // The default milestone of 100 payments
+            for _ in 3..100 {
+                badges.record_activity(accounts.bob, Activity::PaymentSent).unwrap();
+            }
+            assert_eq!(badges.balance_of(accounts.bob), 2);
+            assert_eq!(badges.badge_of(accounts.bob, 1), Some(1));
+            assert_eq!(badges.badge_of(accounts.bob, 0), None);
+            assert_eq!(badges.total_supply(), 2);
+        }
+
+        #[ink::test]
+        fn badges_are_soulbound() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut badges = Badges::new();
+            badges.set_reporter(Activity::BillPaidOnTime, Some(accounts.alice)).unwrap();
+            for _ in 0..10 {
+                badges.record_activity(accounts.bob, Activity::BillPaidOnTime).unwrap();
+            }
+            assert_eq!(badges.owner_of(0), Some(accounts.bob));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(badges.transfer(accounts.charlie, 0, Vec::new()), Err(Error::Soulbound));
+            assert_eq!(badges.approve(accounts.charlie, Some(0), true), Err(Error::Soulbound));
+            assert_eq!(badges.owner_of(0), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn only_owner_configures() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut badges = Badges::new();
+            assert_eq!(
+                badges.add_milestone(Activity::PaymentSent, 0),
+                Err(Error::InvalidThreshold)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                badges.set_reporter(Activity::PaymentSent, Some(accounts.bob)),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(
+                badges.add_milestone(Activity::PaymentSent, 5),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(badges.get_reporter(Activity::PaymentSent), None);
+            assert_eq!(badges.get_milestones().len(), 2);
+        }
+    }
+}
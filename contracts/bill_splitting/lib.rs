@@ -1,50 +1,457 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::bill_splitting::{
+    Bill, BillCancelled, BillCategory, BillCompleted, BillCreated, BillExport, BillMetadata, BillPaid,
+    BillSplittingRef, Error, ParticipantAdded, PaymentDue,
+};
+
 #[ink::contract]
 mod bill_splitting {
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
     use ink::storage::Mapping;
 
-    /// Contract errors
+    pub use xcc_common::{XcmMessageType, BASIS_POINTS, MAX_PAGE_SIZE, MILLISECONDS_PER_DAY};
+    use xcc_common::{
+        Activity, ActivityRecorder, BillManager, Component, ComponentRegistry, Currency, IntentAsset,
+        FeeCollector, JobPage, KeeperBond, KeeperStake, Maintainable, PaymentIntent, PaymentLedger, PriceOracle,
+        SessionKey, StateChunk, XcmGateway, MAX_MEMO_LEN,
+    };
+
+    /// Maximum length of a bill description in bytes
+    pub const MAX_DESCRIPTION_LEN: usize = 128;
+
+    /// Maximum number of members in a group
+    pub const MAX_GROUP_MEMBERS: u32 = 32;
+
+    /// Default maximum number of participants in a bill
+    pub const DEFAULT_MAX_PARTICIPANTS: u32 = 50;
+
+    /// Maximum number of participants in a bill template
+    pub const MAX_TEMPLATE_PARTICIPANTS: usize = 32;
+
+    /// Maximum number of payees a bill's proceeds can be routed to
+    pub const MAX_PAYEES: usize = 8;
+
+    /// Maximum number of overdue occurrences of a series created in one interaction
+    pub const MAX_CATCH_UP_OCCURRENCES: u32 = 8;
+
+    /// Layout version of the storage written by this code
+    pub const STORAGE_VERSION: u32 = 1;
+
+    /// First error code of the contract; Payment, BillSplitting and XcmHandler
+    /// codes start at 1000, 2000 and 3000
+    pub const ERROR_CODE_BASE: u16 = 2000;
+
+    /// Bill categories for display and filtering
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum BillCategory {
+        Food,
+        Travel,
+        Rent,
+        Utilities,
+        Entertainment,
+        Subscription,
+        #[default]
+        Other,
+    }
+
+    /// Descriptive metadata attached to a bill
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct BillMetadata {
+        /// Title or description, e.g. "Dinner at Luigi's 14/03"
+        pub description: Vec<u8>,
+        /// Bill category
+        pub category: BillCategory,
+        /// Optional receipt hash (e.g. IPFS CID digest)
+        pub receipt_hash: Option<[u8; 32]>,
+    }
+
+    /// Late-fee policy applied to shares still unpaid after the deadline
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct LateFeePolicy {
+        /// Flat fee charged once the deadline has passed
+        pub flat_fee: Balance,
+        /// Interest per full day overdue, in basis points of the share
+        pub daily_bps: u32,
+    }
+
+    /// Payment history of an account across all bills
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PaymentRecord {
+        /// Shares paid by the deadline
+        pub on_time: u32,
+        /// Shares paid after the deadline
+        pub late: u32,
+        /// Shares the participant agreed to and left unpaid when their bill expired
+        pub defaulted: u32,
+    }
+
+    /// Dispute state of a bill
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum DisputeStatus {
+        #[default]
+        Open,
+        Disputed,
+        Resolved,
+    }
+
+    /// Outcome an arbiter picks when resolving a dispute
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum DisputeResolution {
+        /// Keep the bill as it is
+        Dismiss,
+        /// Change an unpaid participant's share
+        AdjustShare {
+            participant: AccountId,
+            new_share: Balance,
+        },
+        /// Void the bill and refund all escrowed contributions
+        Void,
+    }
+
+    /// Per-bill state, packed into a single storage cell
+    ///
+    /// Participants, shares and payments stay keyed per participant, and
+    /// optional per-bill settings keep their own mappings.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Bill {
+        /// Account that created the bill and receives the escrow
+        pub creator: AccountId,
+        /// Sum of all shares
+        pub total_amount: Balance,
+        /// Number of participants
+        pub participant_count: u32,
+        /// Number of settled shares
+        pub paid_count: u32,
+        /// Payment deadline
+        pub deadline: u64,
+        /// All shares were settled
+        pub completed: bool,
+        /// Cancelled by the creator or voided by an arbiter
+        pub cancelled: bool,
+        /// Expired before completing
+        pub expired: bool,
+        /// Awaiting acceptance by its participants
+        pub proposed: bool,
+        /// Re-split a rejected share instead of cancelling the proposal
+        pub resplit_on_reject: bool,
+        /// Total escrowed, released to the creator on completion
+        pub escrowed: Balance,
+        /// Dispute state
+        pub dispute: DisputeStatus,
+        /// Block timestamp the bill was created at
+        pub created_at: u64,
+    }
+
+    /// A bill and its shares, as exported by `export_bills`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct BillExport {
+        pub bill_id: u32,
+        pub bill: Bill,
+        /// Participant, share and whether it is settled, in share order
+        pub shares: Vec<(AccountId, Balance, bool)>,
+    }
+
+    /// PSP22 `transfer` selector
+    const PSP22_TRANSFER_SELECTOR: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+    /// PSP22 `transfer_from` selector
+    const PSP22_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+    /// Errors returned by PSP22 tokens
     #[derive(Debug, PartialEq, Eq)]
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Psp22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// Pairwise group debt key (group_id, debtor, creditor)
+    type GroupDebtKey = (u32, AccountId, AccountId);
+
+    /// Bill share settled by a cross-chain payment (bill_id, participant, amount)
+    type LinkedShare = (u32, AccountId, Balance);
+
+    /// Payee and its share of a bill's proceeds in basis points
+    pub type Payout = (AccountId, u32);
+
+    /// Template details (owner, label, participants, weights)
+    pub type TemplateInfo = (AccountId, Vec<u8>, Vec<AccountId>, Vec<u32>);
+
+    /// Role of a user on a bill
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum BillRole {
+        Creator,
+        Participant,
+    }
+
+    /// Contract errors
+    ///
+    /// Discriminants are part of the interface: variants are only ever
+    /// appended, never reordered or removed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
     pub enum Error {
-        BillNotFound,
-        UnauthorizedAccess,
-        InvalidAmount,
-        BillExpired,
-        BillAlreadyCompleted,
-        ParticipantNotFound,
-        AlreadyPaid,
-        InvalidParticipants,
+        BillNotFound = 0,
+        UnauthorizedAccess = 1,
+        InvalidAmount = 2,
+        BillExpired = 3,
+        BillAlreadyCompleted = 4,
+        ParticipantNotFound = 5,
+        AlreadyPaid = 6,
+        InvalidParticipants = 7,
+        BillCancelled = 8,
+        TransferFailed = 9,
+        ParticipantAlreadyExists = 10,
+        DescriptionTooLong = 11,
+        GroupNotFound = 12,
+        NotGroupMember = 13,
+        GroupFull = 14,
+        BillAlreadyInGroup = 15,
+        SeriesNotFound = 16,
+        SeriesCancelled = 17,
+        InvalidSchedule = 18,
+        BillNotExpired = 19,
+        InvalidDeadline = 20,
+        InvalidFeePolicy = 21,
+        BillNotActive = 22,
+        NotProposed = 23,
+        BillDisputed = 24,
+        NotDisputed = 25,
+        NoArbiter = 26,
+        PaymentsAlreadyMade = 27,
+        LedgerTransferFailed = 28,
+        NoXcmHandler = 29,
+        NotRemoteParticipant = 30,
+        PaymentMismatch = 31,
+        PaymentAlreadyLinked = 32,
+        PaymentNotLinked = 33,
+        PaymentNotExecuted = 34,
+        IncompatibleSettlement = 35,
+        TokenTransferFailed = 36,
+        TemplateNotFound = 37,
+        DuplicateParticipant = 38,
+        ZeroShare = 39,
+        TooManyParticipants = 40,
+        InsufficientDeposit = 41,
+        ReminderTooEarly = 42,
+        InvalidPayouts = 43,
+        UpgradeFailed = 44,
+        StorageVersionMismatch = 45,
+        AlreadyMigrated = 46,
+        FiatDenominated = 47,
+        NotFiatDenominated = 48,
+        NoPriceOracle = 49,
+        PriceUnavailable = 50,
+        SlippageExceeded = 51,
+        /// The key is the caller's own, already another account's, or expires at once
+        InvalidSessionKey = 52,
+        NoSessionKey = 53,
+        /// The session key expired or would exceed its daily cap
+        SessionLimitExceeded = 54,
+        IntentExpired = 55,
+        /// The intent's recipient or asset does not match the bill it pays
+        IntentMismatch = 56,
+        MemoTooLong = 57,
+        /// The creator and participants cannot arbitrate their own bill
+        InvalidArbiter = 58,
+        /// The bill was already paid into or disputed
+        ArbiterLocked = 59,
+        /// The bill's dispute was resolved, which is final
+        DisputeClosed = 60,
+        /// Late fees cannot change once the deadline passed or a share is overdue
+        LateFeePolicyLocked = 61,
+        /// The linked cross-chain payment may still execute
+        PaymentInFlight = 62,
+    }
+
+    impl Error {
+        /// Code identifying the error across the contracts, stable between releases
+        pub fn error_code(&self) -> u16 {
+            ERROR_CODE_BASE + *self as u16
+        }
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::InsufficientDeposit => Self::InsufficientBalance,
+                Error::BillNotFound => Self::NotFound,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
     }
 
     /// Bill splitting contract storage
     #[ink(storage)]
     pub struct BillSplitting {
-        /// Bill creators
-        bill_creators: Mapping<u32, AccountId>,
-        /// Bill amounts
-        bill_amounts: Mapping<u32, Balance>,
-        /// Bill participant counts
-        bill_participant_counts: Mapping<u32, u32>,
-        /// Bill paid counts
-        bill_paid_counts: Mapping<u32, u32>,
-        /// Bill completed status
-        bill_completed: Mapping<u32, bool>,
-        /// Bill deadlines
-        bill_deadlines: Mapping<u32, u64>,
+        /// Packed per-bill state
+        bills: Mapping<u32, Bill>,
         /// Bill participants (bill_id -> participant_index -> AccountId)
         bill_participants: Mapping<(u32, u32), AccountId>,
         /// Bill individual amounts (bill_id -> participant_index -> Balance)
         bill_individual_amounts: Mapping<(u32, u32), Balance>,
         /// Bill payments (bill_id -> participant -> paid)
         bill_payments: Mapping<(u32, AccountId), bool>,
+        /// Participant acceptances (bill_id -> participant -> accepted)
+        bill_acceptances: Mapping<(u32, AccountId), bool>,
+        /// Per-bill arbiters, overriding the global arbiter
+        bill_arbiters: Mapping<u32, AccountId>,
+        /// Hash of the reason given for the latest dispute
+        bill_dispute_reasons: Mapping<u32, [u8; 32]>,
+        /// Global arbiter used when a bill has none of its own
+        arbiter: Option<AccountId>,
+        /// Late-fee policies
+        bill_late_fees: Mapping<u32, LateFeePolicy>,
+        /// Native balances deposited for auto-pay
+        auto_pay_deposits: Mapping<AccountId, Balance>,
+        /// Per-bill auto-pay caps for the creators a user approved (user, creator) -> cap
+        auto_pay_caps: Mapping<(AccountId, AccountId), Balance>,
+        /// Session keys paying shares on their account's behalf (key -> session)
+        session_keys: Mapping<AccountId, SessionKey>,
+        /// Shares waived by the creator (bill_id -> participant -> forgiven)
+        bill_forgiven: Mapping<(u32, AccountId), bool>,
+        /// Shares settled outside the contract (bill_id -> participant -> settled)
+        bill_offline_settlements: Mapping<(u32, AccountId), bool>,
+        /// Escrowed contributions (bill_id -> participant -> Balance)
+        bill_contributions: Mapping<(u32, AccountId), Balance>,
+        /// Payment contracts settling bills on their ledger instead of natively
+        bill_payment_ledgers: Mapping<u32, AccountId>,
+        /// PSP22 tokens bills are denominated in, instead of the native token
+        bill_tokens: Mapping<u32, AccountId>,
+        /// Fiat currencies bills are denominated in, with shares in its minor units
+        bill_currencies: Mapping<u32, Currency>,
+        /// Payees and their basis-point splits of a bill's proceeds, instead of the creator
+        bill_payouts: Mapping<u32, Vec<Payout>>,
+        /// Chains of participants paying from another parachain (bill_id -> participant -> chain_id)
+        bill_remote_chains: Mapping<(u32, AccountId), u32>,
+        /// Cross-chain payments awaiting execution (bill_id -> participant -> payment_id)
+        bill_xcm_payments: Mapping<(u32, AccountId), u32>,
+        /// Bill share settled by each linked cross-chain payment
+        xcm_payment_bills: Mapping<u32, LinkedShare>,
+        /// Cross-chain payments that settled a share, never to be linked again
+        consumed_xcm_payments: Mapping<u32, ()>,
+        /// XcmHandler contract carrying cross-chain payments
+        xcm_handler: Option<AccountId>,
+        /// Registry resolving the contracts not configured directly
+        registry: Option<AccountId>,
+        /// Price oracle converting fiat-denominated shares at settlement
+        price_oracle: Option<AccountId>,
+        /// Badge contract counting the shares each account pays on time
+        badges: Option<AccountId>,
+        /// Keeper registry paying bounties for expiring bills
+        keeper_registry: Option<AccountId>,
+        /// Bonds the keeper registry pays bounties from (bill_id -> bond)
+        keeper_bonds: Mapping<u32, KeeperBond>,
+        /// Funders' shares of the keeper bonds ((bill_id, funder) -> stake)
+        keeper_stakes: Mapping<(u32, AccountId), KeeperStake>,
+        /// Treasury late fees are paid to instead of the bill's creator
+        treasury: Option<AccountId>,
+        /// Bill metadata
+        bill_metadata: Mapping<u32, BillMetadata>,
         /// User bills
         user_bills: Mapping<AccountId, u32>, // simplified to count
+        /// User bill index (user -> position -> bill_id)
+        user_bill_ids: Mapping<(AccountId, u32), u32>,
+        /// Reverse user bill index (user -> bill_id -> position)
+        user_bill_positions: Mapping<(AccountId, u32), u32>,
+        /// Group a bill is attached to (bill_id -> group_id)
+        bill_groups: Mapping<u32, u32>,
+        /// Group admins
+        group_admins: Mapping<u32, AccountId>,
+        /// Group names
+        group_names: Mapping<u32, Vec<u8>>,
+        /// Group member counts
+        group_member_counts: Mapping<u32, u32>,
+        /// Group members (group_id -> member_index -> AccountId)
+        group_members: Mapping<(u32, u32), AccountId>,
+        /// Group membership (group_id -> account -> is_member)
+        group_membership: Mapping<(u32, AccountId), bool>,
+        /// Group bill counts
+        group_bill_counts: Mapping<u32, u32>,
+        /// Pairwise group debts, netted so only one direction is non-zero
+        /// (group_id -> debtor -> creditor -> Balance)
+        group_debts: Mapping<GroupDebtKey, Balance>,
+        /// Net group balances, positive when the member is owed money
+        group_net_balances: Mapping<(u32, AccountId), i128>,
+        /// Group counter
+        group_counter: u32,
+        /// Recurring series creators
+        series_creators: Mapping<u32, AccountId>,
+        /// Bill each series was created from
+        series_template_bills: Mapping<u32, u32>,
+        /// Series intervals
+        series_intervals: Mapping<u32, u64>,
+        /// Occurrences still to be instantiated
+        series_remaining: Mapping<u32, u32>,
+        /// Timestamp at which the next occurrence becomes due
+        series_next_due: Mapping<u32, u64>,
+        /// Most recently instantiated bill of each series
+        series_latest_bills: Mapping<u32, u32>,
+        /// Series cancelled status
+        series_cancelled: Mapping<u32, bool>,
+        /// Participants of the template when the series was created
+        series_participants: Mapping<u32, Vec<AccountId>>,
+        /// Shares of the template when the series was created, by participant position
+        series_shares: Mapping<u32, Vec<Balance>>,
+        /// Late-fee policy of the template when the series was created
+        series_late_fees: Mapping<u32, LateFeePolicy>,
+        /// Group of the template when the series was created
+        series_groups: Mapping<u32, u32>,
+        /// Series a bill belongs to (bill_id -> series_id)
+        bill_series: Mapping<u32, u32>,
+        /// Series counter
+        series_counter: u32,
+        /// Template owners
+        template_owners: Mapping<u32, AccountId>,
+        /// Template labels
+        template_labels: Mapping<u32, Vec<u8>>,
+        /// Template participants
+        template_participants: Mapping<u32, Vec<AccountId>>,
+        /// Template share weights, parallel to the participants
+        template_weights: Mapping<u32, Vec<u32>>,
+        /// Template counter
+        template_counter: u32,
+        /// Maximum number of participants in a bill
+        max_participants: u32,
+        /// How long before the deadline reminders may be emitted, in milliseconds
+        reminder_window: u64,
+        /// Shares reported overdue by a reminder (bill_id -> participant -> overdue)
+        bill_overdue: Mapping<(u32, AccountId), bool>,
+        /// Number of overdue shares per user
+        user_overdue_counts: Mapping<AccountId, u32>,
+        /// Payment history per user
+        payment_records: Mapping<AccountId, PaymentRecord>,
         /// Bill counter
         bill_counter: u32,
         /// Contract owner
         owner: AccountId,
+        /// Layout version of the stored data, behind `STORAGE_VERSION` until migrated
+        version: u32,
     }
 
     /// Events
@@ -58,6 +465,18 @@ mod bill_splitting {
         participant_count: u32,
     }
 
+    #[ink(event)]
+    pub struct IntentExecuted {
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        amount: Balance,
+        asset: IntentAsset,
+        memo: String,
+        bill_id: Option<u32>,
+    }
+
     #[ink(event)]
     pub struct BillPaid {
         #[ink(topic)]
@@ -67,6 +486,84 @@ mod bill_splitting {
         amount: Balance,
     }
 
+    #[ink(event)]
+    pub struct LateFeeCharged {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        payer: AccountId,
+        fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LateFeePolicySet {
+        #[ink(topic)]
+        bill_id: u32,
+        flat_fee: Balance,
+        daily_bps: u32,
+    }
+
+    #[ink(event)]
+    pub struct SettledOffline {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        creator: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Deposited {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AutoPayConfigured {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        creator: AccountId,
+        per_bill_cap: Option<Balance>,
+    }
+
+    #[ink(event)]
+    pub struct SessionKeyAuthorized {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        key: AccountId,
+        daily_cap: Balance,
+        expires_at: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct SessionKeyRevoked {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        key: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ShareForgiven {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        amount: Balance,
+        new_total: Balance,
+    }
+
     #[ink(event)]
     pub struct BillCompleted {
         #[ink(topic)]
@@ -76,295 +573,5573 @@ mod bill_splitting {
         total_paid: Balance,
     }
 
-    impl BillSplitting {
-        /// Constructor
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            Self {
-                bill_creators: Mapping::default(),
-                bill_amounts: Mapping::default(),
-                bill_participant_counts: Mapping::default(),
-                bill_paid_counts: Mapping::default(),
-                bill_completed: Mapping::default(),
-                bill_deadlines: Mapping::default(),
-                bill_participants: Mapping::default(),
-                bill_individual_amounts: Mapping::default(),
-                bill_payments: Mapping::default(),
-                user_bills: Mapping::default(),
-                bill_counter: 0,
-                owner: Self::env().caller(),
-            }
-        }
+    #[ink(event)]
+    pub struct PayoutsConfigured {
+        #[ink(topic)]
+        bill_id: u32,
+        payouts: Vec<Payout>,
+    }
 
-        /// Default constructor
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new()
-        }
+    #[ink(event)]
+    pub struct PayoutDistributed {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        payee: AccountId,
+        amount: Balance,
+    }
 
-        /// Create a new bill
-        #[ink(message)]
-        #[allow(clippy::cast_possible_truncation)]
-        pub fn create_bill(
-            &mut self,
-            total_amount: Balance,
-            participants: ink::prelude::vec::Vec<AccountId>,
-            individual_amounts: ink::prelude::vec::Vec<Balance>,
-            deadline: u64,
-        ) -> Result<u32, Error> {
-            let creator = self.env().caller();
-            
-            if total_amount == 0 {
-                return Err(Error::InvalidAmount);
-            }
-            
-            if participants.is_empty() {
-                return Err(Error::InvalidParticipants);
-            }
-            
-            if participants.len() != individual_amounts.len() {
-                return Err(Error::InvalidParticipants);
-            }
-            
-            // Verify that sum of individual amounts equals total
-            let sum: Balance = individual_amounts.iter().sum();
-            if sum != total_amount {
-                return Err(Error::InvalidAmount);
-            }
-            
-            let bill_id = self.bill_counter;
+    #[ink(event)]
+    pub struct BillCancelled {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        creator: AccountId,
+        refunded_amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BillAccepted {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BillRejected {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        resplit: bool,
+    }
+
+    #[ink(event)]
+    pub struct BillActivated {
+        #[ink(topic)]
+        bill_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct DisputeRaised {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        raised_by: AccountId,
+        reason_hash: [u8; 32],
+    }
+
+    #[ink(event)]
+    pub struct DisputeResolved {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        arbiter: AccountId,
+        resolution: DisputeResolution,
+    }
+
+    #[ink(event)]
+    pub struct ArbiterSet {
+        #[ink(topic)]
+        bill_id: Option<u32>,
+        arbiter: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct PaymentLedgerSet {
+        #[ink(topic)]
+        bill_id: u32,
+        ledger: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct BillDenominated {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        token: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct XcmHandlerSet {
+        xcm_handler: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct RegistrySet {
+        registry: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct PriceOracleSet {
+        price_oracle: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct BadgesSet {
+        badges: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct TreasurySet {
+        treasury: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct KeeperRegistrySet {
+        keeper_registry: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct KeeperBondPosted {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct KeeperBondReclaimed {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct KeeperBountyPaid {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        keeper: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BillCurrencySet {
+        #[ink(topic)]
+        bill_id: u32,
+        currency: Currency,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct StorageMigrated {
+        from: u32,
+        to: u32,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RemoteParticipantRegistered {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        chain_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct CrossChainPaymentLinked {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        payment_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct CrossChainPaymentUnlinked {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        payment_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct BillExpired {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        creator: AccountId,
+        refunded_amount: Balance,
+        defaulted: Vec<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct DeadlineExtended {
+        #[ink(topic)]
+        bill_id: u32,
+        old_deadline: u64,
+        new_deadline: u64,
+    }
+
+    #[ink(event)]
+    pub struct ParticipantAdded {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        amount: Balance,
+        new_total: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ParticipantRemoved {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        amount: Balance,
+        new_total: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BillMetadataUpdated {
+        #[ink(topic)]
+        bill_id: u32,
+        category: BillCategory,
+        receipt_hash: Option<[u8; 32]>,
+    }
+
+    #[ink(event)]
+    pub struct GroupCreated {
+        #[ink(topic)]
+        group_id: u32,
+        #[ink(topic)]
+        admin: AccountId,
+        member_count: u32,
+    }
+
+    #[ink(event)]
+    pub struct GroupMemberAdded {
+        #[ink(topic)]
+        group_id: u32,
+        #[ink(topic)]
+        member: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BillAttachedToGroup {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        group_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct GroupDebtSettled {
+        #[ink(topic)]
+        group_id: u32,
+        #[ink(topic)]
+        debtor: AccountId,
+        #[ink(topic)]
+        creditor: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RecurringBillCreated {
+        #[ink(topic)]
+        series_id: u32,
+        #[ink(topic)]
+        template_bill_id: u32,
+        interval: u64,
+        occurrences: u32,
+    }
+
+    #[ink(event)]
+    pub struct RecurringBillInstantiated {
+        #[ink(topic)]
+        series_id: u32,
+        #[ink(topic)]
+        bill_id: u32,
+        remaining: u32,
+    }
+
+    #[ink(event)]
+    pub struct RecurringBillCancelled {
+        #[ink(topic)]
+        series_id: u32,
+        remaining: u32,
+    }
+
+    #[ink(event)]
+    pub struct PaymentDue {
+        #[ink(topic)]
+        bill_id: u32,
+        #[ink(topic)]
+        participant: AccountId,
+        amount_due: Balance,
+        deadline: u64,
+        overdue: bool,
+    }
+
+    #[ink(event)]
+    pub struct ReminderWindowSet {
+        reminder_window: u64,
+    }
+
+    #[ink(event)]
+    pub struct MaxParticipantsSet {
+        max_participants: u32,
+    }
+
+    #[ink(event)]
+    pub struct TemplateSaved {
+        #[ink(topic)]
+        template_id: u32,
+        #[ink(topic)]
+        owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct TemplateDeleted {
+        #[ink(topic)]
+        template_id: u32,
+    }
+
+    impl BillSplitting {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::with_owner(Self::env().caller())
+        }
+
+        /// Constructor for an instance administered by someone other than its deployer
+        #[ink(constructor)]
+        pub fn with_owner(owner: AccountId) -> Self {
+            Self {
+                bills: Mapping::default(),
+                bill_participants: Mapping::default(),
+                bill_individual_amounts: Mapping::default(),
+                bill_payments: Mapping::default(),
+                auto_pay_deposits: Mapping::default(),
+                auto_pay_caps: Mapping::default(),
+                session_keys: Mapping::default(),
+                bill_forgiven: Mapping::default(),
+                bill_offline_settlements: Mapping::default(),
+                bill_late_fees: Mapping::default(),
+                bill_arbiters: Mapping::default(),
+                bill_dispute_reasons: Mapping::default(),
+                arbiter: None,
+                bill_acceptances: Mapping::default(),
+                bill_contributions: Mapping::default(),
+                bill_payment_ledgers: Mapping::default(),
+                bill_tokens: Mapping::default(),
+                bill_currencies: Mapping::default(),
+                bill_payouts: Mapping::default(),
+                bill_remote_chains: Mapping::default(),
+                bill_xcm_payments: Mapping::default(),
+                xcm_payment_bills: Mapping::default(),
+                consumed_xcm_payments: Mapping::default(),
+                xcm_handler: None,
+                registry: None,
+                price_oracle: None,
+                badges: None,
+                keeper_registry: None,
+                keeper_bonds: Mapping::default(),
+                keeper_stakes: Mapping::default(),
+                treasury: None,
+                bill_metadata: Mapping::default(),
+                user_bills: Mapping::default(),
+                user_bill_ids: Mapping::default(),
+                user_bill_positions: Mapping::default(),
+                bill_groups: Mapping::default(),
+                group_admins: Mapping::default(),
+                group_names: Mapping::default(),
+                group_member_counts: Mapping::default(),
+                group_members: Mapping::default(),
+                group_membership: Mapping::default(),
+                group_bill_counts: Mapping::default(),
+                group_debts: Mapping::default(),
+                group_net_balances: Mapping::default(),
+                group_counter: 0,
+                series_creators: Mapping::default(),
+                series_template_bills: Mapping::default(),
+                series_intervals: Mapping::default(),
+                series_remaining: Mapping::default(),
+                series_next_due: Mapping::default(),
+                series_latest_bills: Mapping::default(),
+                series_cancelled: Mapping::default(),
+                series_participants: Mapping::default(),
+                series_shares: Mapping::default(),
+                series_late_fees: Mapping::default(),
+                series_groups: Mapping::default(),
+                bill_series: Mapping::default(),
+                series_counter: 0,
+                template_owners: Mapping::default(),
+                template_labels: Mapping::default(),
+                template_participants: Mapping::default(),
+                template_weights: Mapping::default(),
+                template_counter: 0,
+                max_participants: DEFAULT_MAX_PARTICIPANTS,
+                reminder_window: MILLISECONDS_PER_DAY,
+                bill_overdue: Mapping::default(),
+                user_overdue_counts: Mapping::default(),
+                payment_records: Mapping::default(),
+                bill_counter: 0,
+                owner,
+                version: STORAGE_VERSION,
+            }
+        }
+
+        /// Default constructor
+        #[ink(constructor)]
+        #[allow(clippy::should_implement_trait)]
+        pub fn default() -> Self {
+            Self::new()
+        }
+
+        /// Create a new bill
+        ///
+        /// Participants with auto-pay enabled are settled from their deposit.
+        #[ink(message)]
+        pub fn create_bill(
+            &mut self,
+            total_amount: Balance,
+            participants: ink::prelude::vec::Vec<AccountId>,
+            individual_amounts: ink::prelude::vec::Vec<Balance>,
+            deadline: u64,
+        ) -> Result<u32, Error> {
+            let bill_id = self.new_bill(total_amount, &participants, &individual_amounts, deadline)?;
+            
+            for participant in participants {
+                self.try_auto_pay(bill_id, participant)?;
+            }
+            
+            Ok(bill_id)
+        }
+
+        /// Create a bill from pre-tax shares, adding tax and tip proportionally
+        ///
+        /// `tax_bps` is charged on the subtotal. Tax and tip are spread over the
+        /// shares in proportion to their size, rounding down, and any leftover
+        /// units go one each to the first participants.
+        #[ink(message)]
+        pub fn create_bill_with_tax_and_tip(
+            &mut self,
+            participants: Vec<AccountId>,
+            base_amounts: Vec<Balance>,
+            deadline: u64,
+            tax_bps: Option<u32>,
+            tip_amount: Option<Balance>,
+        ) -> Result<u32, Error> {
+            let individual_amounts = Self::apply_tax_and_tip(
+                &base_amounts,
+                tax_bps.unwrap_or(0),
+                tip_amount.unwrap_or(0),
+            )?;
+            let total_amount: Balance = individual_amounts.iter().sum();
+            
+            self.create_bill(total_amount, participants, individual_amounts, deadline)
+        }
+
+        /// Preview the shares `create_bill_with_tax_and_tip` would create
+        #[ink(message)]
+        pub fn quote_tax_and_tip(
+            &self,
+            base_amounts: Vec<Balance>,
+            tax_bps: Option<u32>,
+            tip_amount: Option<Balance>,
+        ) -> Result<Vec<Balance>, Error> {
+            Self::apply_tax_and_tip(&base_amounts, tax_bps.unwrap_or(0), tip_amount.unwrap_or(0))
+        }
+
+        /// Create a new bill whose shares are paid in a PSP22 token
+        ///
+        /// Participants `approve` this contract on the token before paying; the
+        /// escrow is paid out to the creator in the same token on completion.
+        #[ink(message)]
+        pub fn create_token_bill(
+            &mut self,
+            total_amount: Balance,
+            participants: Vec<AccountId>,
+            individual_amounts: Vec<Balance>,
+            deadline: u64,
+            token: AccountId,
+        ) -> Result<u32, Error> {
+            let bill_id = self.new_bill(total_amount, &participants, &individual_amounts, deadline)?;
+            
+            self.bill_tokens.insert(bill_id, &token);
+            
+            self.env().emit_event(BillDenominated { bill_id, token });
+            
+            Ok(bill_id)
+        }
+
+        /// Create a new bill whose shares are set in minor units of a fiat currency
+        ///
+        /// Shares are converted into the native token, or `token` if given, at
+        /// the oracle's price when each one is paid with `pay_bill_in_currency`.
+        #[ink(message)]
+        pub fn create_fiat_bill(
+            &mut self,
+            currency: Currency,
+            total_amount: Balance,
+            participants: Vec<AccountId>,
+            individual_amounts: Vec<Balance>,
+            deadline: u64,
+            token: Option<AccountId>,
+        ) -> Result<u32, Error> {
+            let bill_id = self.new_bill(total_amount, &participants, &individual_amounts, deadline)?;
+            
+            self.bill_currencies.insert(bill_id, &currency);
+            self.env().emit_event(BillCurrencySet { bill_id, currency });
+            
+            if let Some(token) = token {
+                self.bill_tokens.insert(bill_id, &token);
+                self.env().emit_event(BillDenominated { bill_id, token });
+            }
+            
+            Ok(bill_id)
+        }
+
+        /// Create a new bill whose proceeds are split between several payees
+        ///
+        /// `payouts` pairs each payee with its share in basis points, summing to
+        /// 10 000. On completion the escrow is paid out accordingly instead of to
+        /// the creator; rounding leftovers go to the first payee.
+        #[ink(message)]
+        pub fn create_bill_with_payouts(
+            &mut self,
+            total_amount: Balance,
+            participants: Vec<AccountId>,
+            individual_amounts: Vec<Balance>,
+            deadline: u64,
+            payouts: Vec<Payout>,
+        ) -> Result<u32, Error> {
+            if payouts.is_empty() || payouts.len() > MAX_PAYEES {
+                return Err(Error::InvalidPayouts);
+            }
+            
+            let total_bps = payouts
+                .iter()
+                .fold(0u128, |sum, (_, bps)| sum.saturating_add(u128::from(*bps)));
+            if total_bps != BASIS_POINTS || payouts.iter().any(|(_, bps)| *bps == 0) {
+                return Err(Error::InvalidPayouts);
+            }
+            
+            let bill_id = self.new_bill(total_amount, &participants, &individual_amounts, deadline)?;
+            
+            self.bill_payouts.insert(bill_id, &payouts);
+            
+            self.env().emit_event(PayoutsConfigured { bill_id, payouts });
+            
+            // Payouts have to be in place before auto-pay can complete the bill
+            for participant in participants {
+                self.try_auto_pay(bill_id, participant)?;
+            }
+            
+            Ok(bill_id)
+        }
+
+        /// Propose a bill that only becomes payable once every participant accepts it
+        ///
+        /// If a participant rejects, their share is re-split evenly over the
+        /// others when `resplit_on_reject` is set; otherwise the bill is cancelled.
+        #[ink(message)]
+        pub fn propose_bill(
+            &mut self,
+            total_amount: Balance,
+            participants: Vec<AccountId>,
+            individual_amounts: Vec<Balance>,
+            deadline: u64,
+            resplit_on_reject: bool,
+        ) -> Result<u32, Error> {
+            let creator = self.env().caller();
+            let bill_id = self.new_bill(total_amount, &participants, &individual_amounts, deadline)?;
+            
+            let mut bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            bill.proposed = true;
+            bill.resplit_on_reject = resplit_on_reject;
+            self.save_bill(bill_id, &bill);
+            
+            // A creator who also participates has implicitly accepted
+            self.bill_acceptances.insert((bill_id, creator), &true);
+            
+            Ok(bill_id)
+        }
+
+        /// Accept a proposed bill as a participant
+        #[ink(message)]
+        pub fn accept_bill(&mut self, bill_id: u32) -> Result<(), Error> {
+            let participant = self.env().caller();
+            let mut bill = self.ensure_proposed(bill_id)?;
+            
+            self.find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            
+            self.bill_acceptances.insert((bill_id, participant), &true);
+            
+            self.env().emit_event(BillAccepted {
+                bill_id,
+                participant,
+            });
+            
+            self.activate_if_accepted(bill_id, &mut bill);
+            self.save_bill(bill_id, &bill);
+            
+            Ok(())
+        }
+
+        /// Reject a proposed bill, removing the caller from it
+        #[ink(message)]
+        pub fn reject_bill(&mut self, bill_id: u32) -> Result<(), Error> {
+            let participant = self.env().caller();
+            let mut bill = self.ensure_proposed(bill_id)?;
+            let creator = bill.creator;
+            
+            let index = self
+                .find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            
+            let resplit = bill.resplit_on_reject && bill.participant_count > 1;
+            
+            self.env().emit_event(BillRejected {
+                bill_id,
+                participant,
+                resplit,
+            });
+            
+            if !resplit {
+                bill.cancelled = true;
+                bill.proposed = false;
+                let (refunded_amount, _) = self.refund_contributions(bill_id, &mut bill)?;
+                self.save_bill(bill_id, &bill);
+                
+                self.env().emit_event(BillCancelled {
+                    bill_id,
+                    creator,
+                    refunded_amount,
+                });
+                
+                return Ok(());
+            }
+            
+            let share = self.detach_participant(bill_id, &mut bill, index, participant);
+            self.resplit_amount(bill_id, &mut bill, share);
+            self.activate_if_accepted(bill_id, &mut bill);
+            self.save_bill(bill_id, &bill);
+            
+            Ok(())
+        }
+
+        /// Pay a bill, escrowing the payment until the bill completes
+        ///
+        /// Bills settled through a Payment contract pull the amount from the
+        /// payer's ledger balance, which requires a prior `approve` of this
+        /// contract; all other bills expect the amount as transferred value.
+        #[ink(message, payable)]
+        pub fn pay_bill(&mut self, bill_id: u32, amount: Balance) -> Result<(), Error> {
+            let payer = self.env().caller();
+            let (mut bill, share, late_fee) = self.due_share(bill_id, payer)?;
+            if self.bill_currencies.contains(bill_id) {
+                return Err(Error::FiatDenominated);
+            }
+            
+            // Check amount, including any late fee
+            if amount != share.saturating_add(late_fee) {
+                return Err(Error::InvalidAmount);
+            }
+            
+            // Pull the share into escrow, from the ledger or the attached value
+            self.collect_funds(bill_id, payer, amount)?;
+            
+            self.record_payment(bill_id, &mut bill, payer, share, late_fee)?;
+            self.save_bill(bill_id, &bill);
+            
+            Ok(())
+        }
+
+        /// Pay a share of the account the caller is a session key of, within the key's daily cap
+        ///
+        /// Native bills are paid from the account's auto-pay deposit; others
+        /// pull the amount from the account as `pay_bill` does.
+        #[ink(message)]
+        pub fn pay_bill_via_session(&mut self, bill_id: u32, amount: Balance) -> Result<(), Error> {
+            let key = self.env().caller();
+            let mut session = self.session_keys.get(key).ok_or(Error::NoSessionKey)?;
+            let payer = session.account;
+            let (mut bill, share, late_fee) = self.due_share(bill_id, payer)?;
+            if self.bill_currencies.contains(bill_id) {
+                return Err(Error::FiatDenominated);
+            }
+            if amount != share.saturating_add(late_fee) {
+                return Err(Error::InvalidAmount);
+            }
+            if !session.spend(self.env().block_number(), self.env().block_timestamp(), amount) {
+                return Err(Error::SessionLimitExceeded);
+            }
+            
+            if self.bill_tokens.contains(bill_id) || self.bill_payment_ledgers.contains(bill_id) {
+                self.collect_funds(bill_id, payer, amount)?;
+            } else {
+                let deposit = self.auto_pay_deposits.get(payer).unwrap_or(0);
+                if amount > deposit {
+                    return Err(Error::InsufficientDeposit);
+                }
+                self.auto_pay_deposits.insert(payer, &deposit.saturating_sub(amount));
+            }
+            self.session_keys.insert(key, &session);
+            
+            self.record_payment(bill_id, &mut bill, payer, share, late_fee)?;
+            self.save_bill(bill_id, &bill);
+            
+            Ok(())
+        }
+
+        /// Validate and execute a payment intent scanned from a QR code or deeplink
+        ///
+        /// Intents with a bill ID pay the caller's share of that bill as
+        /// `pay_bill` does, and must name its creator and asset. Others pay the
+        /// recipient directly: native intents expect the amount as transferred
+        /// value, ledger and token intents pull it from the caller through this
+        /// contract's allowance.
+        #[ink(message, payable)]
+        pub fn execute_intent(&mut self, intent: PaymentIntent) -> Result<(), Error> {
+            let payer = self.env().caller();
+            if intent.expires_at.is_some_and(|expires_at| self.env().block_timestamp() >= expires_at) {
+                return Err(Error::IntentExpired);
+            }
+            if intent.memo.len() > MAX_MEMO_LEN {
+                return Err(Error::MemoTooLong);
+            }
+            if intent.amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            match intent.bill_id {
+                Some(bill_id) => {
+                    let (mut bill, share, late_fee) = self.due_share(bill_id, payer)?;
+                    if self.bill_currencies.contains(bill_id) {
+                        return Err(Error::FiatDenominated);
+                    }
+                    if intent.recipient != bill.creator || intent.asset != self.bill_asset(bill_id) {
+                        return Err(Error::IntentMismatch);
+                    }
+                    if intent.amount != share.saturating_add(late_fee) {
+                        return Err(Error::InvalidAmount);
+                    }
+                    self.collect_funds(bill_id, payer, intent.amount)?;
+                    self.record_payment(bill_id, &mut bill, payer, share, late_fee)?;
+                    self.save_bill(bill_id, &bill);
+                }
+                None => {
+                    if intent.recipient == payer {
+                        return Err(Error::InvalidParticipants);
+                    }
+                    self.transfer_intent(&intent, payer)?;
+                }
+            }
+            
+            self.env().emit_event(IntentExecuted {
+                payer,
+                recipient: intent.recipient,
+                amount: intent.amount,
+                asset: intent.asset,
+                memo: intent.memo,
+                bill_id: intent.bill_id,
+            });
+            
+            Ok(())
+        }
+
+        /// Let `key` pay the caller's shares, up to `daily_cap` a day until block `expires_at`
+        ///
+        /// Authorizing a key of the caller again replaces its cap and expiry.
+        #[ink(message)]
+        pub fn authorize_session_key(
+            &mut self,
+            key: AccountId,
+            daily_cap: Balance,
+            expires_at: BlockNumber,
+        ) -> Result<(), Error> {
+            let account = self.env().caller();
+            if key == account || expires_at <= self.env().block_number() {
+                return Err(Error::InvalidSessionKey);
+            }
+            if self.session_keys.get(key).is_some_and(|session| session.account != account) {
+                return Err(Error::InvalidSessionKey);
+            }
+            
+            self.session_keys.insert(key, &SessionKey::new(account, daily_cap, expires_at));
+            
+            self.env().emit_event(SessionKeyAuthorized {
+                account,
+                key,
+                daily_cap,
+                expires_at,
+            });
+            
+            Ok(())
+        }
+
+        /// Revoke a session key of the caller
+        #[ink(message)]
+        pub fn revoke_session_key(&mut self, key: AccountId) -> Result<(), Error> {
+            let account = self.env().caller();
+            match self.session_keys.get(key) {
+                Some(session) if session.account == account => self.session_keys.remove(key),
+                _ => return Err(Error::NoSessionKey),
+            }
+            
+            self.env().emit_event(SessionKeyRevoked { account, key });
+            
+            Ok(())
+        }
+
+        /// Get the session a key pays in
+        #[ink(message)]
+        pub fn get_session_key(&self, key: AccountId) -> Option<SessionKey> {
+            self.session_keys.get(key)
+        }
+
+        /// Pay a fiat-denominated bill, converting the share at the oracle's current price
+        ///
+        /// Fails if the converted share, late fee included, exceeds `max_amount`.
+        /// Native bills expect `max_amount` as transferred value and refund
+        /// what was not needed; others pull the converted amount from the payer.
+        #[ink(message, payable)]
+        pub fn pay_bill_in_currency(&mut self, bill_id: u32, max_amount: Balance) -> Result<Balance, Error> {
+            let payer = self.env().caller();
+            let (mut bill, fiat_share, fiat_late_fee) = self.due_share(bill_id, payer)?;
+            let currency = self.bill_currencies.get(bill_id).ok_or(Error::NotFiatDenominated)?;
+            
+            // Convert at settlement time, bounded by the payer's limit
+            let oracle = self.price_oracle.ok_or(Error::NoPriceOracle)?;
+            let oracle: ink::contract_ref!(PriceOracle) = oracle.into();
+            let asset = self
+                .bill_tokens
+                .get(bill_id)
+                .or_else(|| self.bill_payment_ledgers.get(bill_id));
+            let share = oracle
+                .convert(asset, currency, fiat_share)
+                .ok_or(Error::PriceUnavailable)?;
+            let amount = oracle
+                .convert(asset, currency, fiat_share.saturating_add(fiat_late_fee))
+                .ok_or(Error::PriceUnavailable)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if amount > max_amount {
+                return Err(Error::SlippageExceeded);
+            }
+            
+            let native = asset.is_none();
+            if native {
+                if self.env().transferred_value() != max_amount {
+                    return Err(Error::InvalidAmount);
+                }
+            } else {
+                self.collect_funds(bill_id, payer, amount)?;
+            }
+            
+            self.record_payment(bill_id, &mut bill, payer, share, amount.saturating_sub(share))?;
+            self.save_bill(bill_id, &bill);
+            
+            if native && max_amount > amount {
+                self.env()
+                    .transfer(payer, max_amount.saturating_sub(amount))
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            
+            Ok(amount)
+        }
+
+        /// Deposit native funds that auto-pay settles shares from
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<(), Error> {
+            let account = self.env().caller();
+            let amount = self.env().transferred_value();
+            
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            let deposit = self.auto_pay_deposits.get(account).unwrap_or(0);
+            self.auto_pay_deposits.insert(account, &deposit.saturating_add(amount));
+            
+            self.env().emit_event(Deposited { account, amount });
+            
+            Ok(())
+        }
+
+        /// Withdraw unused auto-pay funds
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<(), Error> {
+            let account = self.env().caller();
+            let deposit = self.auto_pay_deposits.get(account).unwrap_or(0);
+            
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if amount > deposit {
+                return Err(Error::InsufficientDeposit);
+            }
+            
+            self.auto_pay_deposits.insert(account, &deposit.saturating_sub(amount));
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.env().emit_event(Withdrawn { account, amount });
+            
+            Ok(())
+        }
+
+        /// Enable auto-pay of bills created by `creator` up to `per_bill_cap` per share, or disable it with `None`
+        ///
+        /// Only approved creators are paid, so nobody else can pull from the deposit
+        /// by adding the caller to a bill.
+        #[ink(message)]
+        pub fn set_auto_pay(&mut self, creator: AccountId, per_bill_cap: Option<Balance>) -> Result<(), Error> {
+            let account = self.env().caller();
+            
+            if let Some(cap) = per_bill_cap {
+                self.auto_pay_caps.insert((account, creator), &cap);
+            } else {
+                self.auto_pay_caps.remove((account, creator));
+            }
+            
+            self.env().emit_event(AutoPayConfigured {
+                account,
+                creator,
+                per_bill_cap,
+            });
+            
+            Ok(())
+        }
+
+        /// Settle every open share of the caller that auto-pay covers
+        ///
+        /// Returns the number of shares paid.
+        #[ink(message)]
+        pub fn auto_settle_all(&mut self) -> Result<u32, Error> {
+            let account = self.env().caller();
+            let count = self.user_bills.get(account).unwrap_or(0);
+            let mut settled: u32 = 0;
+            
+            for position in 0..count {
+                if let Some(bill_id) = self.user_bill_ids.get((account, position)) {
+                    if self.try_auto_pay(bill_id, account)? {
+                        settled = settled.saturating_add(1);
+                    }
+                }
+            }
+            
+            Ok(settled)
+        }
+
+        /// Get the auto-pay deposit of an account
+        #[ink(message)]
+        pub fn get_deposit(&self, account: AccountId) -> Balance {
+            self.auto_pay_deposits.get(account).unwrap_or(0)
+        }
+
+        /// Get the per-bill auto-pay cap an account set for bills of `creator`, if enabled
+        #[ink(message)]
+        pub fn get_auto_pay_cap(&self, account: AccountId, creator: AccountId) -> Option<Balance> {
+            self.auto_pay_caps.get((account, creator))
+        }
+
+        /// Set the late-fee policy charged on shares paid after the deadline
+        ///
+        /// Only possible before the deadline and while no share is overdue, so
+        /// fees cannot be raised on participants who are already late.
+        #[ink(message)]
+        pub fn set_late_fee_policy(
+            &mut self,
+            bill_id: u32,
+            flat_fee: Balance,
+            daily_bps: u32,
+        ) -> Result<(), Error> {
+            let bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            let overdue = (0..bill.participant_count).any(|index| {
+                self.bill_participants
+                    .get((bill_id, index))
+                    .is_some_and(|participant| self.bill_overdue.contains((bill_id, participant)))
+            });
+            if self.env().block_timestamp() > bill.deadline || overdue {
+                return Err(Error::LateFeePolicyLocked);
+            }
+            
+            if u128::from(daily_bps) > BASIS_POINTS {
+                return Err(Error::InvalidFeePolicy);
+            }
+            
+            if flat_fee == 0 && daily_bps == 0 {
+                self.bill_late_fees.remove(bill_id);
+            } else {
+                self.bill_late_fees.insert(bill_id, &LateFeePolicy { flat_fee, daily_bps });
+            }
+            
+            self.env().emit_event(LateFeePolicySet {
+                bill_id,
+                flat_fee,
+                daily_bps,
+            });
+            
+            Ok(())
+        }
+
+        /// Get the amount a participant currently owes, including late fees
+        #[ink(message)]
+        pub fn get_amount_due(&self, bill_id: u32, participant: AccountId) -> Option<Balance> {
+            let bill = self.load_bill(bill_id)?;
+            let index = self.find_participant(bill_id, &bill, participant)?;
+            if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                return Some(0);
+            }
+            
+            let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            Some(share.saturating_add(self.late_fee(bill_id, &bill, share)))
+        }
+
+        /// Get the late-fee policy of a bill
+        #[ink(message)]
+        pub fn get_late_fee_policy(&self, bill_id: u32) -> Option<LateFeePolicy> {
+            self.bill_late_fees.get(bill_id)
+        }
+
+        /// Record that a participant paid their share outside the contract
+        ///
+        /// Offline settlements count towards completion but nothing is escrowed,
+        /// and they stay distinguishable through `is_settled_offline`.
+        #[ink(message)]
+        pub fn mark_settled_offline(&mut self, bill_id: u32, participant: AccountId) -> Result<(), Error> {
+            let mut bill = self.ensure_open_bill_creator(bill_id)?;
+            let creator = bill.creator;
+            
+            if bill.proposed {
+                return Err(Error::BillNotActive);
+            }
+            
+            let index = self
+                .find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            
+            if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                return Err(Error::AlreadyPaid);
+            }
+            
+            let amount = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            self.bill_offline_settlements.insert((bill_id, participant), &true);
+            
+            self.settle_share(bill_id, &mut bill, participant, amount)?;
+            self.save_bill(bill_id, &bill);
+            
+            self.env().emit_event(SettledOffline {
+                bill_id,
+                participant,
+                creator,
+                amount,
+            });
+            
+            Ok(())
+        }
+
+        /// Waive a participant's unpaid share as the creator
+        ///
+        /// The bill total drops by the share, which then counts as settled so the
+        /// bill can complete without it.
+        #[ink(message)]
+        pub fn forgive_share(&mut self, bill_id: u32, participant: AccountId) -> Result<(), Error> {
+            let mut bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            let index = self
+                .find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            
+            if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                return Err(Error::AlreadyPaid);
+            }
+            
+            if self.bill_xcm_payments.contains((bill_id, participant)) {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            
+            let amount = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            bill.total_amount = bill.total_amount.saturating_sub(amount);
+            self.bill_individual_amounts.insert((bill_id, index), &0);
+            self.bill_forgiven.insert((bill_id, participant), &true);
+            
+            self.env().emit_event(ShareForgiven {
+                bill_id,
+                participant,
+                amount,
+                new_total: bill.total_amount,
+            });
+            
+            self.settle_share(bill_id, &mut bill, participant, amount)?;
+            self.save_bill(bill_id, &bill);
+            
+            Ok(())
+        }
+
+        /// Cancel a bill before completion, refunding escrowed contributions
+        #[ink(message)]
+        pub fn cancel_bill(&mut self, bill_id: u32) -> Result<(), Error> {
+            let mut bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            bill.cancelled = true;
+            
+            let (refunded_amount, _) = self.refund_contributions(bill_id, &mut bill)?;
+            self.save_bill(bill_id, &bill);
+            
+            self.env().emit_event(BillCancelled {
+                bill_id,
+                creator: bill.creator,
+                refunded_amount,
+            });
+            
+            Ok(())
+        }
+
+        /// Push back the deadline of an open bill
+        #[ink(message)]
+        pub fn extend_deadline(&mut self, bill_id: u32, new_deadline: u64) -> Result<(), Error> {
+            let mut bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            let old_deadline = bill.deadline;
+            if new_deadline <= old_deadline || new_deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidDeadline);
+            }
+            
+            bill.deadline = new_deadline;
+            self.save_bill(bill_id, &bill);
+            
+            self.env().emit_event(DeadlineExtended {
+                bill_id,
+                old_deadline,
+                new_deadline,
+            });
+            
+            Ok(())
+        }
+
+        /// Finalize a bill whose deadline has passed, refunding partial payments
+        ///
+        /// Callable by anyone. Participants who had not paid are reported as
+        /// defaulted in the `BillExpired` event; it counts against their payment
+        /// record only if they accepted the bill or it belongs to their group.
+        /// Disputed bills wait for their arbiter.
+        #[ink(message)]
+        pub fn expire_bill(&mut self, bill_id: u32) -> Result<Vec<AccountId>, Error> {
+            let mut bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            
+            if bill.completed {
+                return Err(Error::BillAlreadyCompleted);
+            }
+            
+            if bill.cancelled {
+                return Err(Error::BillCancelled);
+            }
+            
+            if bill.expired {
+                return Err(Error::BillExpired);
+            }
+            
+            if bill.dispute == DisputeStatus::Disputed {
+                return Err(Error::BillDisputed);
+            }
+            
+            if self.env().block_timestamp() <= bill.deadline {
+                return Err(Error::BillNotExpired);
+            }
+            
+            bill.expired = true;
+            
+            let (refunded_amount, defaulted) = self.refund_contributions(bill_id, &mut bill)?;
+            self.save_bill(bill_id, &bill);
+            
+            // Only shares the participant agreed to, by accepting the bill or
+            // through its group, count against their record
+            let agreed = self.bill_groups.contains(bill_id);
+            for participant in &defaulted {
+                if !agreed && !self.bill_acceptances.get((bill_id, *participant)).unwrap_or(false) {
+                    continue;
+                }
+                let mut record = self.payment_records.get(participant).unwrap_or_default();
+                record.defaulted = record.defaulted.saturating_add(1);
+                self.payment_records.insert(participant, &record);
+            }
+            
+            self.env().emit_event(BillExpired {
+                bill_id,
+                creator: bill.creator,
+                refunded_amount,
+                defaulted: defaulted.clone(),
+            });
+            
+            Ok(defaulted)
+        }
+
+        /// Add a participant with the given share to an open bill
+        #[ink(message)]
+        pub fn add_participant(
+            &mut self,
+            bill_id: u32,
+            participant: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let mut bill = self.ensure_open_bill_creator(bill_id)?;
+            let creator = bill.creator;
+            
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            if self.find_participant(bill_id, &bill, participant).is_some() {
+                return Err(Error::ParticipantAlreadyExists);
+            }
+            
+            if bill.participant_count >= self.max_participants {
+                return Err(Error::TooManyParticipants);
+            }
+            
+            // Group bills may only involve group members
+            let group_id = self.bill_groups.get(bill_id);
+            if let Some(group_id) = group_id {
+                if !self.group_membership.get((group_id, participant)).unwrap_or(false) {
+                    return Err(Error::NotGroupMember);
+                }
+                self.record_group_debt(group_id, participant, creator, amount);
+            }
+            
+            let participant_count = bill.participant_count;
+            self.bill_participants.insert((bill_id, participant_count), &participant);
+            self.bill_individual_amounts.insert((bill_id, participant_count), &amount);
+            self.bill_payments.insert((bill_id, participant), &false);
+            bill.participant_count = participant_count.saturating_add(1);
+            
+            let new_total = bill.total_amount.saturating_add(amount);
+            bill.total_amount = new_total;
+            self.save_bill(bill_id, &bill);
+            
+            self.index_user_bill(participant, bill_id);
+            
+            self.env().emit_event(ParticipantAdded {
+                bill_id,
+                participant,
+                amount,
+                new_total,
+            });
+            
+            self.try_auto_pay(bill_id, participant)?;
+            
+            Ok(())
+        }
+
+        /// Remove an unpaid participant from an open bill
+        #[ink(message)]
+        pub fn remove_participant(
+            &mut self,
+            bill_id: u32,
+            participant: AccountId,
+        ) -> Result<(), Error> {
+            let mut bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            let index = self
+                .find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            
+            if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                return Err(Error::AlreadyPaid);
+            }
+            
+            // A linked cross-chain payment would still pay the creator
+            if self.bill_xcm_payments.contains((bill_id, participant)) {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            
+            if bill.participant_count <= 1 {
+                return Err(Error::InvalidParticipants);
+            }
+            
+            self.detach_participant(bill_id, &mut bill, index, participant);
+            
+            if bill.proposed {
+                // The remaining participants may all have accepted already
+                self.activate_if_accepted(bill_id, &mut bill);
+            } else if bill.paid_count == bill.participant_count {
+                // Everyone left may already have paid
+                self.complete_bill(bill_id, &mut bill)?;
+            }
+            self.save_bill(bill_id, &bill);
+            
+            Ok(())
+        }
+
+        /// Emit a `PaymentDue` reminder for every unpaid share of an open bill
+        ///
+        /// Callable by anyone once the bill is within the reminder window of its
+        /// deadline. Shares still unpaid after the deadline are counted as overdue
+        /// for their participant. Returns the number of reminders emitted.
+        #[ink(message)]
+        pub fn emit_reminders(&mut self, bill_id: u32) -> Result<u32, Error> {
+            let bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            self.ensure_payable(bill_id, &bill)?;
+            
+            let now = self.env().block_timestamp();
+            let deadline = bill.deadline;
+            if now < deadline.saturating_sub(self.reminder_window) {
+                return Err(Error::ReminderTooEarly);
+            }
+            let overdue = now > deadline;
+            
+            let mut reminders: u32 = 0;
+            for index in 0..bill.participant_count {
+                let Some(participant) = self.bill_participants.get((bill_id, index)) else {
+                    continue;
+                };
+                if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                    continue;
+                }
+                
+                if overdue && !self.bill_overdue.contains((bill_id, participant)) {
+                    self.bill_overdue.insert((bill_id, participant), &true);
+                    let count = self.user_overdue_counts.get(participant).unwrap_or(0);
+                    self.user_overdue_counts.insert(participant, &count.saturating_add(1));
+                }
+                
+                let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+                self.env().emit_event(PaymentDue {
+                    bill_id,
+                    participant,
+                    amount_due: share.saturating_add(self.late_fee(bill_id, &bill, share)),
+                    deadline,
+                    overdue,
+                });
+                reminders = reminders.saturating_add(1);
+            }
+            
+            Ok(reminders)
+        }
+
+        /// Set how long before the deadline reminders may be emitted (owner only)
+        #[ink(message)]
+        pub fn set_reminder_window(&mut self, reminder_window: u64) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.reminder_window = reminder_window;
+            
+            self.env().emit_event(ReminderWindowSet { reminder_window });
+            
+            Ok(())
+        }
+
+        /// Get how long before the deadline reminders may be emitted
+        #[ink(message)]
+        pub fn get_reminder_window(&self) -> u64 {
+            self.reminder_window
+        }
+
+        /// Get the number of overdue shares of an account
+        #[ink(message)]
+        pub fn get_overdue(&self, account: AccountId) -> u32 {
+            self.user_overdue_counts.get(account).unwrap_or(0)
+        }
+
+        /// Get the reputation of an account in basis points, if it has any history
+        ///
+        /// Shares paid on time count fully, late payments half and defaults not
+        /// at all.
+        #[ink(message)]
+        pub fn get_reputation(&self, account: AccountId) -> Option<u32> {
+            let record = self.payment_records.get(account)?;
+            let shares = u128::from(record.on_time)
+                .saturating_add(u128::from(record.late))
+                .saturating_add(u128::from(record.defaulted));
+            if shares == 0 {
+                return None;
+            }
+            
+            let points = u128::from(record.on_time)
+                .saturating_mul(BASIS_POINTS)
+                .saturating_add(u128::from(record.late).saturating_mul(BASIS_POINTS / 2));
+            u32::try_from(points / shares).ok()
+        }
+
+        /// Get the payment history of an account
+        #[ink(message)]
+        pub fn get_payment_record(&self, account: AccountId) -> PaymentRecord {
+            self.payment_records.get(account).unwrap_or_default()
+        }
+
+        /// Set the maximum number of participants in new bills (owner only)
+        #[ink(message)]
+        pub fn set_max_participants(&mut self, max_participants: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if max_participants == 0 {
+                return Err(Error::InvalidParticipants);
+            }
+            
+            self.max_participants = max_participants;
+            
+            self.env().emit_event(MaxParticipantsSet { max_participants });
+            
+            Ok(())
+        }
+
+        /// Get the maximum number of participants in a bill
+        #[ink(message)]
+        pub fn get_max_participants(&self) -> u32 {
+            self.max_participants
+        }
+
+        /// Set the global arbiter used for bills without their own (owner only)
+        #[ink(message)]
+        pub fn set_arbiter(&mut self, arbiter: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.arbiter = arbiter;
+            
+            self.env().emit_event(ArbiterSet {
+                bill_id: None,
+                arbiter,
+            });
+            
+            Ok(())
+        }
+
+        /// Set the arbiter of an open bill
+        ///
+        /// Only possible before anything was paid into or disputed on the bill,
+        /// and never to the creator or a participant.
+        #[ink(message)]
+        pub fn set_bill_arbiter(&mut self, bill_id: u32, arbiter: AccountId) -> Result<(), Error> {
+            let bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            if bill.paid_count > 0 || bill.escrowed > 0 || bill.dispute != DisputeStatus::Open {
+                return Err(Error::ArbiterLocked);
+            }
+            
+            if arbiter == bill.creator || self.find_participant(bill_id, &bill, arbiter).is_some() {
+                return Err(Error::InvalidArbiter);
+            }
+            
+            self.bill_arbiters.insert(bill_id, &arbiter);
+            
+            self.env().emit_event(ArbiterSet {
+                bill_id: Some(bill_id),
+                arbiter: Some(arbiter),
+            });
+            
+            Ok(())
+        }
+
+        /// Dispute an open bill as one of its participants, freezing payments
+        #[ink(message)]
+        pub fn raise_dispute(&mut self, bill_id: u32, reason_hash: [u8; 32]) -> Result<(), Error> {
+            let raised_by = self.env().caller();
+            
+            let mut bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            
+            if self.find_participant(bill_id, &bill, raised_by).is_none() {
+                return Err(Error::ParticipantNotFound);
+            }
+            
+            if bill.completed {
+                return Err(Error::BillAlreadyCompleted);
+            }
+            
+            if bill.cancelled {
+                return Err(Error::BillCancelled);
+            }
+            
+            if bill.expired {
+                return Err(Error::BillExpired);
+            }
+            
+            if bill.dispute == DisputeStatus::Disputed {
+                return Err(Error::BillDisputed);
+            }
+            
+            // An arbiter's resolution is final
+            if bill.dispute == DisputeStatus::Resolved {
+                return Err(Error::DisputeClosed);
+            }
+            
+            if self.arbiter_of(bill_id).is_none() {
+                return Err(Error::NoArbiter);
+            }
+            
+            bill.dispute = DisputeStatus::Disputed;
+            self.save_bill(bill_id, &bill);
+            self.bill_dispute_reasons.insert(bill_id, &reason_hash);
+            
+            self.env().emit_event(DisputeRaised {
+                bill_id,
+                raised_by,
+                reason_hash,
+            });
+            
+            Ok(())
+        }
+
+        /// Resolve a pending dispute as the bill's arbiter
+        #[ink(message)]
+        pub fn resolve_dispute(&mut self, bill_id: u32, resolution: DisputeResolution) -> Result<(), Error> {
+            let arbiter = self.env().caller();
+            let mut bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            let creator = bill.creator;
+            
+            if self.arbiter_of(bill_id) != Some(arbiter) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if bill.dispute != DisputeStatus::Disputed {
+                return Err(Error::NotDisputed);
+            }
+            
+            match &resolution {
+                DisputeResolution::Dismiss => {}
+                DisputeResolution::AdjustShare { participant, new_share } => {
+                    let index = self
+                        .find_participant(bill_id, &bill, *participant)
+                        .ok_or(Error::ParticipantNotFound)?;
+                    
+                    if self.bill_payments.get((bill_id, *participant)).unwrap_or(false) {
+                        return Err(Error::AlreadyPaid);
+                    }
+                    
+                    if *new_share == 0 {
+                        return Err(Error::InvalidAmount);
+                    }
+                    
+                    let old_share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+                    self.bill_individual_amounts.insert((bill_id, index), new_share);
+                    
+                    bill.total_amount = bill
+                        .total_amount
+                        .saturating_sub(old_share)
+                        .saturating_add(*new_share);
+                    
+                    if let Some(group_id) = self.bill_groups.get(bill_id) {
+                        self.record_group_debt(group_id, creator, *participant, old_share);
+                        self.record_group_debt(group_id, *participant, creator, *new_share);
+                    }
+                }
+                DisputeResolution::Void => {
+                    bill.cancelled = true;
+                    let (refunded_amount, _) = self.refund_contributions(bill_id, &mut bill)?;
+                    
+                    self.env().emit_event(BillCancelled {
+                        bill_id,
+                        creator,
+                        refunded_amount,
+                    });
+                }
+            }
+            
+            bill.dispute = DisputeStatus::Resolved;
+            self.save_bill(bill_id, &bill);
+            
+            self.env().emit_event(DisputeResolved {
+                bill_id,
+                arbiter,
+                resolution,
+            });
+            
+            Ok(())
+        }
+
+        /// Get the dispute state of a bill
+        #[ink(message)]
+        pub fn get_dispute_status(&self, bill_id: u32) -> DisputeStatus {
+            self.load_bill(bill_id).map(|bill| bill.dispute).unwrap_or_default()
+        }
+
+        /// Get the arbiter responsible for a bill
+        #[ink(message)]
+        pub fn get_bill_arbiter(&self, bill_id: u32) -> Option<AccountId> {
+            self.arbiter_of(bill_id)
+        }
+
+        /// Settle an open bill through a Payment contract's ledger, or natively with `None`
+        ///
+        /// Can only be changed before anything was escrowed for the bill.
+        #[ink(message)]
+        pub fn set_payment_ledger(
+            &mut self,
+            bill_id: u32,
+            ledger: Option<AccountId>,
+        ) -> Result<(), Error> {
+            let bill = self.ensure_open_bill_creator(bill_id)?;
+            
+            if bill.escrowed > 0 {
+                return Err(Error::PaymentsAlreadyMade);
+            }
+            
+            if self.bill_tokens.contains(bill_id) {
+                return Err(Error::IncompatibleSettlement);
+            }
+            
+            if let Some(ledger) = ledger {
+                self.bill_payment_ledgers.insert(bill_id, &ledger);
+            } else {
+                self.bill_payment_ledgers.remove(bill_id);
+            }
+            
+            self.env().emit_event(PaymentLedgerSet { bill_id, ledger });
+            
+            Ok(())
+        }
+
+        /// Get the fiat currency a bill's shares are set in, if any
+        #[ink(message)]
+        pub fn get_bill_currency(&self, bill_id: u32) -> Option<Currency> {
+            self.bill_currencies.get(bill_id)
+        }
+
+        /// Get the PSP22 token a bill is denominated in, if any
+        #[ink(message)]
+        pub fn get_bill_token(&self, bill_id: u32) -> Option<AccountId> {
+            self.bill_tokens.get(bill_id)
+        }
+
+        /// Get the payees a bill's proceeds are split between, empty if they go to the creator
+        #[ink(message)]
+        pub fn get_bill_payouts(&self, bill_id: u32) -> Vec<Payout> {
+            self.bill_payouts.get(bill_id).unwrap_or_default()
+        }
+
+        /// Get the Payment contract a bill is settled through, if any
+        #[ink(message)]
+        pub fn get_payment_ledger(&self, bill_id: u32) -> Option<AccountId> {
+            self.bill_payment_ledgers.get(bill_id)
+        }
+
+        /// Set the XcmHandler contract carrying cross-chain bill payments (owner only)
+        #[ink(message)]
+        pub fn set_xcm_handler(&mut self, xcm_handler: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.xcm_handler = xcm_handler;
+            
+            self.env().emit_event(XcmHandlerSet { xcm_handler });
+            
+            Ok(())
+        }
+
+        /// Set the registry resolving the XcmHandler when none is set directly (owner only)
+        #[ink(message)]
+        pub fn set_registry(&mut self, registry: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.registry = registry;
+            
+            self.env().emit_event(RegistrySet { registry });
+            
+            Ok(())
+        }
+
+        /// Set the price oracle converting fiat-denominated shares (owner only)
+        #[ink(message)]
+        pub fn set_price_oracle(&mut self, price_oracle: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.price_oracle = price_oracle;
+            
+            self.env().emit_event(PriceOracleSet { price_oracle });
+            
+            Ok(())
+        }
+
+        /// Get the price oracle converting fiat-denominated shares
+        #[ink(message)]
+        pub fn get_price_oracle(&self) -> Option<AccountId> {
+            self.price_oracle
+        }
+
+        /// Set the badge contract counting the shares each account pays on time (owner only)
+        #[ink(message)]
+        pub fn set_badges(&mut self, badges: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.badges = badges;
+            
+            self.env().emit_event(BadgesSet { badges });
+            
+            Ok(())
+        }
+
+        /// Get the badge contract counting the shares each account pays on time
+        #[ink(message)]
+        pub fn get_badges(&self) -> Option<AccountId> {
+            self.badges
+        }
+
+        /// Set the treasury late fees are paid to instead of the bill's creator (owner only)
+        ///
+        /// Late fees of PSP22 bills stay with the bill, as the treasury does not hold PSP22 tokens.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.treasury = treasury;
+            
+            self.env().emit_event(TreasurySet { treasury });
+            
+            Ok(())
+        }
+
+        /// Get the treasury late fees are paid to
+        #[ink(message)]
+        pub fn get_treasury(&self) -> Option<AccountId> {
+            self.treasury
+        }
+
+        /// Set the keeper registry paying bounties for expiring bills (owner only)
+        #[ink(message)]
+        pub fn set_keeper_registry(&mut self, keeper_registry: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.keeper_registry = keeper_registry;
+            
+            self.env().emit_event(KeeperRegistrySet { keeper_registry });
+            
+            Ok(())
+        }
+
+        /// Get the keeper registry paying bounties for expiring bills
+        #[ink(message)]
+        pub fn get_keeper_registry(&self) -> Option<AccountId> {
+            self.keeper_registry
+        }
+
+        /// Bond the transferred value as bounty for keepers expiring a bill
+        ///
+        /// Anyone may add to the bond of an existing job; each funder reclaims
+        /// their share of what the keepers did not earn.
+        #[ink(message, payable)]
+        pub fn post_keeper_bond(&mut self, job_id: u32) -> Result<(), Error> {
+            let funder = self.env().caller();
+            let amount = self.env().transferred_value();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.load_bill(job_id).ok_or(Error::BillNotFound)?;
+            
+            let mut bond = self.keeper_bonds.get(job_id).unwrap_or_default();
+            let stake = bond.post(self.keeper_stakes.get((job_id, funder)).unwrap_or_default(), amount);
+            self.keeper_bonds.insert(job_id, &bond);
+            self.keeper_stakes.insert((job_id, funder), &stake);
+            
+            self.env().emit_event(KeeperBondPosted { job_id, funder, amount });
+            
+            Ok(())
+        }
+
+        /// Take back the caller's share of what is left of a job's bond
+        #[ink(message)]
+        pub fn reclaim_keeper_bond(&mut self, job_id: u32) -> Result<Balance, Error> {
+            let funder = self.env().caller();
+            let stake = self.keeper_stakes.take((job_id, funder)).ok_or(Error::UnauthorizedAccess)?;
+            
+            let mut bond = self.keeper_bonds.get(job_id).unwrap_or_default();
+            let amount = bond.reclaim(stake);
+            self.keeper_bonds.insert(job_id, &bond);
+            self.env()
+                .transfer(funder, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.env().emit_event(KeeperBondReclaimed { job_id, funder, amount });
+            
+            Ok(amount)
+        }
+
+        /// Get what is left of a job's bond
+        #[ink(message)]
+        pub fn get_keeper_bond(&self, job_id: u32) -> Option<Balance> {
+            self.keeper_bonds.get(job_id).map(|bond| bond.amount)
+        }
+
+        /// Get what a funder would reclaim from a job's bond
+        #[ink(message)]
+        pub fn get_keeper_stake(&self, job_id: u32, funder: AccountId) -> Balance {
+            let stake = self.keeper_stakes.get((job_id, funder)).unwrap_or_default();
+            self.keeper_bonds.get(job_id).map_or(0, |bond| bond.value_of(stake))
+        }
+
+        /// Replace the contract's code, keeping its storage and address (owner only)
+        ///
+        /// Code changing the storage layout must be followed by `migrate_from`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+            
+            self.env().emit_event(CodeUpgraded { code_hash });
+            
+            Ok(())
+        }
+
+        /// Migrate storage written at version `from` to `STORAGE_VERSION` (owner only)
+        ///
+        /// `from` must match the stored version, so a migration runs only once.
+        /// Layout changes bump `STORAGE_VERSION` and add their step here.
+        #[ink(message)]
+        pub fn migrate_from(&mut self, from: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if from != self.version {
+                return Err(Error::StorageVersionMismatch);
+            }
+            if from >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+            
+            self.version = STORAGE_VERSION;
+            
+            self.env().emit_event(StorageMigrated {
+                from,
+                to: STORAGE_VERSION,
+            });
+            
+            Ok(())
+        }
+
+        /// Get the configured registry
+        #[ink(message)]
+        pub fn get_registry(&self) -> Option<AccountId> {
+            self.registry
+        }
+
+        /// Get the contract owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Hand the contract over to a new owner, such as a governance contract (owner only)
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.owner = new_owner;
+            
+            self.env().emit_event(OwnershipTransferred { new_owner });
+            
+            Ok(())
+        }
+
+        /// Get the layout version of the stored data
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.version
+        }
+
+        /// Register the caller as paying their share from another parachain
+        #[ink(message)]
+        pub fn register_remote_participant(&mut self, bill_id: u32, chain_id: u32) -> Result<(), Error> {
+            let participant = self.env().caller();
+            
+            let bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            self.ensure_payable(bill_id, &bill)?;
+            self.find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                return Err(Error::AlreadyPaid);
+            }
+            if self.bill_xcm_payments.contains((bill_id, participant)) {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            
+            // Cross-chain payments are made in the native token, straight to the creator
+            if self.bill_tokens.contains(bill_id)
+                || self.bill_payouts.contains(bill_id)
+                || self.bill_currencies.contains(bill_id)
+            {
+                return Err(Error::IncompatibleSettlement);
+            }
+            
+            self.bill_remote_chains.insert((bill_id, participant), &chain_id);
+            
+            self.env().emit_event(RemoteParticipantRegistered {
+                bill_id,
+                participant,
+                chain_id,
+            });
+            
+            Ok(())
+        }
+
+        /// Link a cross-chain payment created on the XcmHandler to the caller's share
+        ///
+        /// The payment must be a `BillSplitting` payment from the participant to
+        /// the bill creator over the amount currently due, created after the bill.
+        /// The share is marked paid once the payment executes, and a payment that
+        /// settled a share can never be linked again.
+        #[ink(message)]
+        pub fn link_cross_chain_payment(&mut self, bill_id: u32, payment_id: u32) -> Result<(), Error> {
+            let participant = self.env().caller();
+            
+            let bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            self.ensure_payable(bill_id, &bill)?;
+            if !self.bill_remote_chains.contains((bill_id, participant)) {
+                return Err(Error::NotRemoteParticipant);
+            }
+            if self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                return Err(Error::AlreadyPaid);
+            }
+            if self.bill_xcm_payments.contains((bill_id, participant))
+                || self.xcm_payment_bills.contains(payment_id)
+                || self.consumed_xcm_payments.contains(payment_id)
+            {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            let xcm_handler = self.resolve_xcm_handler().ok_or(Error::NoXcmHandler)?;
+            
+            let index = self
+                .find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            let amount_due = share.saturating_add(self.late_fee(bill_id, &bill, share));
+            
+            let xcm_handler: ink::contract_ref!(XcmGateway) = xcm_handler.into();
+            let payment = xcm_handler
+                .payment_summary(payment_id)
+                .ok_or(Error::PaymentMismatch)?;
+            if payment.sender != participant
+                || payment.recipient != bill.creator
+                || payment.amount != amount_due
+                || payment.message_type != XcmMessageType::BillSplitting
+                || payment.created_at < bill.created_at
+            {
+                return Err(Error::PaymentMismatch);
+            }
+            
+            self.bill_xcm_payments.insert((bill_id, participant), &payment_id);
+            self.xcm_payment_bills.insert(payment_id, &(bill_id, participant, payment.amount));
+            
+            self.env().emit_event(CrossChainPaymentLinked {
+                bill_id,
+                participant,
+                payment_id,
+            });
+            
+            // Payments that already went through settle right away
+            if payment.executed {
+                self.settle_cross_chain_share(payment_id)?;
+            }
+            
+            Ok(())
+        }
+
+        /// Mark the share linked to an executed cross-chain payment as paid
+        ///
+        /// Called back by the XcmHandler on execution; anyone else may confirm
+        /// once the handler reports the payment as executed.
+        #[ink(message)]
+        pub fn confirm_cross_chain_payment(&mut self, payment_id: u32) -> Result<(), Error> {
+            if !self.xcm_payment_bills.contains(payment_id) {
+                return Err(Error::PaymentNotLinked);
+            }
+            let xcm_handler = self.resolve_xcm_handler().ok_or(Error::NoXcmHandler)?;
+            
+            if self.env().caller() != xcm_handler {
+                let xcm_handler: ink::contract_ref!(XcmGateway) = xcm_handler.into();
+                let executed = xcm_handler
+                    .payment_summary(payment_id)
+                    .is_some_and(|payment| payment.executed);
+                if !executed {
+                    return Err(Error::PaymentNotExecuted);
+                }
+            }
+            
+            self.settle_cross_chain_share(payment_id)
+        }
+
+        /// Unlink a cross-chain payment that failed or was returned to its sender
+        ///
+        /// Anyone may unlink once the XcmHandler reports the payment can no
+        /// longer execute, leaving the share payable again. The payment can
+        /// never be linked again.
+        #[ink(message)]
+        pub fn unlink_cross_chain_payment(&mut self, bill_id: u32, participant: AccountId) -> Result<(), Error> {
+            let payment_id = self
+                .bill_xcm_payments
+                .get((bill_id, participant))
+                .ok_or(Error::PaymentNotLinked)?;
+            let xcm_handler = self.resolve_xcm_handler().ok_or(Error::NoXcmHandler)?;
+            
+            let xcm_handler: ink::contract_ref!(XcmGateway) = xcm_handler.into();
+            let aborted = xcm_handler
+                .payment_summary(payment_id)
+                .is_some_and(|payment| payment.aborted && !payment.executed);
+            if !aborted {
+                return Err(Error::PaymentInFlight);
+            }
+            
+            self.xcm_payment_bills.remove(payment_id);
+            self.bill_xcm_payments.remove((bill_id, participant));
+            self.consumed_xcm_payments.insert(payment_id, &());
+            
+            self.env().emit_event(CrossChainPaymentUnlinked {
+                bill_id,
+                participant,
+                payment_id,
+            });
+            
+            Ok(())
+        }
+
+        /// Get the chain a participant pays their share from, if remote
+        #[ink(message)]
+        pub fn get_remote_chain(&self, bill_id: u32, participant: AccountId) -> Option<u32> {
+            self.bill_remote_chains.get((bill_id, participant))
+        }
+
+        /// Get the cross-chain payment linked to a participant's share, if any
+        #[ink(message)]
+        pub fn get_linked_payment(&self, bill_id: u32, participant: AccountId) -> Option<u32> {
+            self.bill_xcm_payments.get((bill_id, participant))
+        }
+
+        /// Set the description, category and receipt hash of a bill
+        #[ink(message)]
+        pub fn set_bill_metadata(
+            &mut self,
+            bill_id: u32,
+            description: Vec<u8>,
+            category: BillCategory,
+            receipt_hash: Option<[u8; 32]>,
+        ) -> Result<(), Error> {
+            let creator = self.load_bill(bill_id).ok_or(Error::BillNotFound)?.creator;
+            if self.env().caller() != creator {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if description.len() > MAX_DESCRIPTION_LEN {
+                return Err(Error::DescriptionTooLong);
+            }
+            
+            self.bill_metadata.insert(bill_id, &BillMetadata {
+                description,
+                category,
+                receipt_hash,
+            });
+            
+            self.env().emit_event(BillMetadataUpdated {
+                bill_id,
+                category,
+                receipt_hash,
+            });
+            
+            Ok(())
+        }
+
+        /// Turn an existing bill into the first occurrence of a recurring series
+        ///
+        /// Each further occurrence copies the template bill's participants, shares,
+        /// late-fee policy and group as they are now, with its deadline pushed back
+        /// by `interval`; later changes to the template do not carry over.
+        /// Occurrences are created lazily on the first interaction after they
+        /// become due, up to `MAX_CATCH_UP_OCCURRENCES` at a time. Cancelling the
+        /// template ends the series.
+        #[ink(message)]
+        pub fn create_recurring_bill(
+            &mut self,
+            template_bill_id: u32,
+            interval: u64,
+            occurrences: u32,
+        ) -> Result<u32, Error> {
+            let template = self.ensure_open_bill_creator(template_bill_id)?;
+            let creator = template.creator;
+            
+            if interval == 0 || occurrences < 2 {
+                return Err(Error::InvalidSchedule);
+            }
+            
+            if self.bill_series.contains(template_bill_id) {
+                return Err(Error::InvalidSchedule);
+            }
+            
+            let series_id = self.series_counter;
+            let next_due = self.env().block_timestamp().saturating_add(interval);
+            
+            self.series_creators.insert(series_id, &creator);
+            self.series_template_bills.insert(series_id, &template_bill_id);
+            self.series_intervals.insert(series_id, &interval);
+            self.series_remaining.insert(series_id, &occurrences.saturating_sub(1));
+            self.series_next_due.insert(series_id, &next_due);
+            self.series_latest_bills.insert(series_id, &template_bill_id);
+            self.bill_series.insert(template_bill_id, &series_id);
+            self.series_counter = self.series_counter.saturating_add(1);
+            
+            // Snapshot the terms each occurrence is created with
+            let mut participants = Vec::new();
+            let mut shares = Vec::new();
+            for index in 0..template.participant_count {
+                if let Some(participant) = self.bill_participants.get((template_bill_id, index)) {
+                    participants.push(participant);
+                    shares.push(self.bill_individual_amounts.get((template_bill_id, index)).unwrap_or(0));
+                }
+            }
+            self.series_participants.insert(series_id, &participants);
+            self.series_shares.insert(series_id, &shares);
+            if let Some(policy) = self.bill_late_fees.get(template_bill_id) {
+                self.series_late_fees.insert(series_id, &policy);
+            }
+            if let Some(group_id) = self.bill_groups.get(template_bill_id) {
+                self.series_groups.insert(series_id, &group_id);
+            }
+            
+            self.env().emit_event(RecurringBillCreated {
+                series_id,
+                template_bill_id,
+                interval,
+                occurrences,
+            });
+            
+            Ok(series_id)
+        }
+
+        /// Instantiate the occurrences of a series that are due
+        ///
+        /// Returns the id of the latest bill created, if any.
+        #[ink(message)]
+        pub fn advance_recurring_bill(&mut self, series_id: u32) -> Result<Option<u32>, Error> {
+            if !self.series_creators.contains(series_id) {
+                return Err(Error::SeriesNotFound);
+            }
+            
+            if self.series_cancelled.get(series_id).unwrap_or(false) {
+                return Err(Error::SeriesCancelled);
+            }
+            
+            Ok(self.instantiate_due_occurrences(series_id))
+        }
+
+        /// Stop a recurring series from creating further bills
+        #[ink(message)]
+        pub fn cancel_recurring_bill(&mut self, series_id: u32) -> Result<(), Error> {
+            let creator = self.series_creators.get(series_id).ok_or(Error::SeriesNotFound)?;
+            if self.env().caller() != creator {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if self.series_cancelled.get(series_id).unwrap_or(false) {
+                return Err(Error::SeriesCancelled);
+            }
+            
+            self.end_series(series_id);
+            
+            Ok(())
+        }
+
+        /// Get recurring series details (template bill, latest bill, interval, remaining, next due, cancelled)
+        #[ink(message)]
+        pub fn get_recurring_bill(&self, series_id: u32) -> Option<(u32, u32, u64, u32, u64, bool)> {
+            let template_bill_id = self.series_template_bills.get(series_id)?;
+            let latest_bill_id = self.series_latest_bills.get(series_id).unwrap_or(template_bill_id);
+            let interval = self.series_intervals.get(series_id).unwrap_or(0);
+            let remaining = self.series_remaining.get(series_id).unwrap_or(0);
+            let next_due = self.series_next_due.get(series_id).unwrap_or(0);
+            let cancelled = self.series_cancelled.get(series_id).unwrap_or(false);
+            
+            Some((template_bill_id, latest_bill_id, interval, remaining, next_due, cancelled))
+        }
+
+        /// Save a reusable participant list with share weights
+        #[ink(message)]
+        pub fn save_template(
+            &mut self,
+            participants: Vec<AccountId>,
+            weights: Vec<u32>,
+            label: Vec<u8>,
+        ) -> Result<u32, Error> {
+            let owner = self.env().caller();
+            let template_id = self.template_counter;
+            
+            self.store_template(template_id, owner, participants, weights, label)?;
+            self.template_counter = self.template_counter.saturating_add(1);
+            
+            Ok(template_id)
+        }
+
+        /// Replace the participants, weights and label of an owned template
+        #[ink(message)]
+        pub fn update_template(
+            &mut self,
+            template_id: u32,
+            participants: Vec<AccountId>,
+            weights: Vec<u32>,
+            label: Vec<u8>,
+        ) -> Result<(), Error> {
+            let owner = self.ensure_template_owner(template_id)?;
+            self.store_template(template_id, owner, participants, weights, label)
+        }
+
+        /// Delete an owned template
+        #[ink(message)]
+        pub fn delete_template(&mut self, template_id: u32) -> Result<(), Error> {
+            self.ensure_template_owner(template_id)?;
+            
+            self.template_owners.remove(template_id);
+            self.template_labels.remove(template_id);
+            self.template_participants.remove(template_id);
+            self.template_weights.remove(template_id);
+            
+            self.env().emit_event(TemplateDeleted { template_id });
+            
+            Ok(())
+        }
+
+        /// Create a bill splitting `total_amount` by an owned template's weights
+        ///
+        /// Shares are rounded down and leftover units go one each to the first
+        /// participants.
+        #[ink(message)]
+        pub fn create_bill_from_template(
+            &mut self,
+            template_id: u32,
+            total_amount: Balance,
+            deadline: u64,
+        ) -> Result<u32, Error> {
+            self.ensure_template_owner(template_id)?;
+            
+            let participants = self.template_participants.get(template_id).unwrap_or_default();
+            let weights = self.template_weights.get(template_id).unwrap_or_default();
+            let total_weight: Balance = weights.iter().map(|weight| Balance::from(*weight)).sum();
+            
+            let mut individual_amounts = Vec::new();
+            let mut distributed: Balance = 0;
+            for weight in &weights {
+                let share = total_amount
+                    .checked_mul(Balance::from(*weight))
+                    .ok_or(Error::InvalidAmount)?
+                    / total_weight;
+                distributed = distributed.saturating_add(share);
+                individual_amounts.push(share);
+            }
+            
+            let mut remainder = total_amount.saturating_sub(distributed);
+            for share in individual_amounts.iter_mut() {
+                if remainder == 0 {
+                    break;
+                }
+                *share = share.saturating_add(1);
+                remainder = remainder.saturating_sub(1);
+            }
+            
+            self.create_bill(total_amount, participants, individual_amounts, deadline)
+        }
+
+        /// Get template details (owner, label, participants, weights)
+        #[ink(message)]
+        pub fn get_template(&self, template_id: u32) -> Option<TemplateInfo> {
+            let owner = self.template_owners.get(template_id)?;
+            let label = self.template_labels.get(template_id).unwrap_or_default();
+            let participants = self.template_participants.get(template_id).unwrap_or_default();
+            let weights = self.template_weights.get(template_id).unwrap_or_default();
+            
+            Some((owner, label, participants, weights))
+        }
+
+        /// Create a group of members that bills can be attached to
+        #[ink(message)]
+        #[allow(clippy::cast_possible_truncation)]
+        pub fn create_group(&mut self, name: Vec<u8>, members: Vec<AccountId>) -> Result<u32, Error> {
+            let admin = self.env().caller();
+            
+            if name.len() > MAX_DESCRIPTION_LEN {
+                return Err(Error::DescriptionTooLong);
+            }
+            
+            let group_id = self.group_counter;
+            self.group_admins.insert(group_id, &admin);
+            self.group_names.insert(group_id, &name);
+            self.group_counter = self.group_counter.saturating_add(1);
+            
+            self.insert_group_member(group_id, admin)?;
+            for member in members {
+                self.insert_group_member(group_id, member)?;
+            }
+            
+            self.env().emit_event(GroupCreated {
+                group_id,
+                admin,
+                member_count: self.group_member_counts.get(group_id).unwrap_or(0),
+            });
+            
+            Ok(group_id)
+        }
+
+        /// Add a member to a group
+        #[ink(message)]
+        pub fn add_group_member(&mut self, group_id: u32, member: AccountId) -> Result<(), Error> {
+            let admin = self.group_admins.get(group_id).ok_or(Error::GroupNotFound)?;
+            if self.env().caller() != admin {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if self.group_membership.get((group_id, member)).unwrap_or(false) {
+                return Err(Error::ParticipantAlreadyExists);
+            }
+            
+            self.insert_group_member(group_id, member)
+        }
+
+        /// Attach an open bill to a group, adding unpaid shares to the group tab
+        #[ink(message)]
+        pub fn attach_bill_to_group(&mut self, bill_id: u32, group_id: u32) -> Result<(), Error> {
+            let bill = self.ensure_open_bill_creator(bill_id)?;
+            let creator = bill.creator;
+            
+            if !self.group_admins.contains(group_id) {
+                return Err(Error::GroupNotFound);
+            }
+            
+            if self.bill_groups.contains(bill_id) {
+                return Err(Error::BillAlreadyInGroup);
+            }
+            
+            // Group tabs are settled in the native token, between members and the creator
+            if self.bill_tokens.contains(bill_id)
+                || self.bill_payouts.contains(bill_id)
+                || self.bill_currencies.contains(bill_id)
+            {
+                return Err(Error::IncompatibleSettlement);
+            }
+            
+            if !self.group_membership.get((group_id, creator)).unwrap_or(false) {
+                return Err(Error::NotGroupMember);
+            }
+            
+            let participant_count = bill.participant_count;
+            for index in 0..participant_count {
+                if let Some(participant) = self.bill_participants.get((bill_id, index)) {
+                    if !self.group_membership.get((group_id, participant)).unwrap_or(false) {
+                        return Err(Error::NotGroupMember);
+                    }
+                }
+            }
+            
+            self.add_bill_to_group(bill_id, group_id, creator, participant_count);
+            
+            Ok(())
+        }
+
+        /// Settle part of the caller's group tab by paying another member directly
+        #[ink(message, payable)]
+        pub fn settle_group_debt(&mut self, group_id: u32, creditor: AccountId) -> Result<(), Error> {
+            let debtor = self.env().caller();
+            let amount = self.env().transferred_value();
+            
+            if !self.group_admins.contains(group_id) {
+                return Err(Error::GroupNotFound);
+            }
+            
+            if !self.group_membership.get((group_id, debtor)).unwrap_or(false)
+                || !self.group_membership.get((group_id, creditor)).unwrap_or(false)
+            {
+                return Err(Error::NotGroupMember);
+            }
+            
+            if amount == 0 || debtor == creditor {
+                return Err(Error::InvalidAmount);
+            }
+            
+            self.env()
+                .transfer(creditor, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.record_group_debt(group_id, creditor, debtor, amount);
+            
+            self.env().emit_event(GroupDebtSettled {
+                group_id,
+                debtor,
+                creditor,
+                amount,
+            });
+            
+            Ok(())
+        }
+
+        /// Compute a minimal-ish set of transfers that settles every group balance
+        ///
+        /// Greedily matches the largest debtor with the largest creditor, which
+        /// needs at most `members - 1` transfers. Returns `(from, to, amount)`.
+        #[ink(message)]
+        #[allow(clippy::cast_sign_loss)]
+        pub fn simplify_debts(&self, group_id: u32) -> Vec<(AccountId, AccountId, Balance)> {
+            let mut creditors: Vec<(AccountId, Balance)> = Vec::new();
+            let mut debtors: Vec<(AccountId, Balance)> = Vec::new();
+            
+            for member in self.get_group_members(group_id) {
+                let net = self.group_net_balances.get((group_id, member)).unwrap_or(0);
+                if net > 0 {
+                    creditors.push((member, net as Balance));
+                } else if net < 0 {
+                    debtors.push((member, net.unsigned_abs()));
+                }
+            }
+            
+            let mut transfers = Vec::new();
+            while let (Some(debtor_index), Some(creditor_index)) = (
+                Self::largest_position(&debtors),
+                Self::largest_position(&creditors),
+            ) {
+                let (debtor, owed) = debtors[debtor_index];
+                let (creditor, due) = creditors[creditor_index];
+                let amount = owed.min(due);
+                
+                transfers.push((debtor, creditor, amount));
+                
+                debtors[debtor_index].1 = owed.saturating_sub(amount);
+                creditors[creditor_index].1 = due.saturating_sub(amount);
+                debtors.retain(|(_, balance)| *balance > 0);
+                creditors.retain(|(_, balance)| *balance > 0);
+            }
+            
+            transfers
+        }
+
+        /// Get group details (admin, name, member count, bill count)
+        #[ink(message)]
+        pub fn get_group_info(&self, group_id: u32) -> Option<(AccountId, Vec<u8>, u32, u32)> {
+            let admin = self.group_admins.get(group_id)?;
+            let name = self.group_names.get(group_id).unwrap_or_default();
+            let member_count = self.group_member_counts.get(group_id).unwrap_or(0);
+            let bill_count = self.group_bill_counts.get(group_id).unwrap_or(0);
+            
+            Some((admin, name, member_count, bill_count))
+        }
+
+        /// Get the members of a group
+        #[ink(message)]
+        pub fn get_group_members(&self, group_id: u32) -> Vec<AccountId> {
+            let member_count = self.group_member_counts.get(group_id).unwrap_or(0);
+            (0..member_count)
+                .filter_map(|index| self.group_members.get((group_id, index)))
+                .collect()
+        }
+
+        /// Get a member's net group balance, positive when they are owed money
+        #[ink(message)]
+        pub fn get_group_balance(&self, group_id: u32, member: AccountId) -> i128 {
+            self.group_net_balances.get((group_id, member)).unwrap_or(0)
+        }
+
+        /// Get how much one group member owes another
+        #[ink(message)]
+        pub fn get_group_debt(&self, group_id: u32, debtor: AccountId, creditor: AccountId) -> Balance {
+            self.group_debts.get((group_id, debtor, creditor)).unwrap_or(0)
+        }
+
+        /// Get bill details
+        #[ink(message)]
+        pub fn get_bill_info(&self, bill_id: u32) -> Option<(AccountId, Balance, u32, u32, bool, u64, BillMetadata)> {
+            let bill = self.load_bill(bill_id)?;
+            let metadata = self.bill_metadata.get(bill_id).unwrap_or_default();
+            
+            Some((
+                bill.creator,
+                bill.total_amount,
+                bill.participant_count,
+                bill.paid_count,
+                bill.completed,
+                bill.deadline,
+                metadata,
+            ))
+        }
+
+        /// Get the participants of a bill, in the order of their shares
+        #[ink(message)]
+        pub fn get_bill_participants(&self, bill_id: u32) -> Option<Vec<AccountId>> {
+            let bill = self.load_bill(bill_id)?;
+            Some(
+                (0..bill.participant_count)
+                    .filter_map(|index| self.bill_participants.get((bill_id, index)))
+                    .collect(),
+            )
+        }
+
+        /// Check if a bill was cancelled
+        #[ink(message)]
+        pub fn is_bill_cancelled(&self, bill_id: u32) -> bool {
+            self.load_bill(bill_id).is_some_and(|bill| bill.cancelled)
+        }
+
+        /// Check if a participant's share was settled outside the contract
+        #[ink(message)]
+        pub fn is_settled_offline(&self, bill_id: u32, participant: AccountId) -> bool {
+            self.bill_offline_settlements.get((bill_id, participant)).unwrap_or(false)
+        }
+
+        /// Check if the creator waived a participant's share
+        #[ink(message)]
+        pub fn is_share_forgiven(&self, bill_id: u32, participant: AccountId) -> bool {
+            self.bill_forgiven.get((bill_id, participant)).unwrap_or(false)
+        }
+
+        /// Check if a bill is still awaiting acceptance
+        #[ink(message)]
+        pub fn is_bill_proposed(&self, bill_id: u32) -> bool {
+            self.load_bill(bill_id).is_some_and(|bill| bill.proposed)
+        }
+
+        /// Check if a participant accepted a proposed bill
+        #[ink(message)]
+        pub fn has_accepted(&self, bill_id: u32, participant: AccountId) -> bool {
+            self.bill_acceptances.get((bill_id, participant)).unwrap_or(false)
+        }
+
+        /// Check if a bill expired without completing
+        #[ink(message)]
+        pub fn is_bill_expired(&self, bill_id: u32) -> bool {
+            self.load_bill(bill_id).is_some_and(|bill| bill.expired)
+        }
+
+        /// Get user bill count
+        #[ink(message)]
+        pub fn get_user_bill_count(&self, user: AccountId) -> u32 {
+            self.user_bills.get(user).unwrap_or(0)
+        }
+
+        /// Get a page of bill ids a user is involved in, with their role on each
+        #[ink(message)]
+        pub fn get_user_bills(&self, user: AccountId, offset: u32, limit: u32) -> Vec<(u32, BillRole)> {
+            let count = self.user_bills.get(user).unwrap_or(0);
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+            
+            (offset..end)
+                .filter_map(|position| self.user_bill_ids.get((user, position)))
+                .map(|bill_id| {
+                    let role = if self.load_bill(bill_id).is_some_and(|bill| bill.creator == user) {
+                        BillRole::Creator
+                    } else {
+                        BillRole::Participant
+                    };
+                    (bill_id, role)
+                })
+                .collect()
+        }
+
+        /// Get contract statistics
+        #[ink(message)]
+        pub fn get_stats(&self) -> u32 {
+            self.bill_counter
+        }
+
+        /// Export a page of bills by id, a SCALE-encoded `Vec<BillExport>`
+        ///
+        /// Optional per-bill settings, such as the asset or arbiter, are
+        /// left to their own getters.
+        #[ink(message)]
+        pub fn export_bills(&self, offset: u32, limit: u32) -> StateChunk {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.bill_counter);
+            let bills: Vec<BillExport> = (offset..end)
+                .filter_map(|bill_id| {
+                    let bill = self.load_bill(bill_id)?;
+                    let shares = (0..bill.participant_count)
+                        .filter_map(|index| {
+                            let participant = self.bill_participants.get((bill_id, index))?;
+                            let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+                            let paid = self.bill_payments.get((bill_id, participant)).unwrap_or(false);
+                            Some((participant, share, paid))
+                        })
+                        .collect();
+                    Some(BillExport { bill_id, bill, shares })
+                })
+                .collect();
+            let next_offset = (end < self.bill_counter).then_some(end);
+            StateChunk::new(&bills, next_offset)
+        }
+
+        /// Append a bill to a user's index unless it is already there
+        fn index_user_bill(&mut self, user: AccountId, bill_id: u32) {
+            if self.user_bill_positions.contains((user, bill_id)) {
+                return;
+            }
+            
+            let count = self.user_bills.get(user).unwrap_or(0);
+            self.user_bill_ids.insert((user, count), &bill_id);
+            self.user_bill_positions.insert((user, bill_id), &count);
+            self.user_bills.insert(user, &count.saturating_add(1));
+        }
+
+        /// Remove a bill from a user's index, moving the last entry into its slot
+        fn unindex_user_bill(&mut self, user: AccountId, bill_id: u32) {
+            let Some(position) = self.user_bill_positions.take((user, bill_id)) else {
+                return;
+            };
+            
+            let last_position = self.user_bills.get(user).unwrap_or(0).saturating_sub(1);
+            if position != last_position {
+                if let Some(last_bill_id) = self.user_bill_ids.get((user, last_position)) {
+                    self.user_bill_ids.insert((user, position), &last_bill_id);
+                    self.user_bill_positions.insert((user, last_bill_id), &position);
+                }
+            }
+            self.user_bill_ids.remove((user, last_position));
+            self.user_bills.insert(user, &last_position);
+        }
+
+        /// Create the bills of a series whose due time has passed, a bounded number at a time
+        fn instantiate_due_occurrences(&mut self, series_id: u32) -> Option<u32> {
+            let mut latest_bill_id = None;
+            for _ in 0..MAX_CATCH_UP_OCCURRENCES {
+                let Some(bill_id) = self.instantiate_due_occurrence(series_id) else {
+                    break;
+                };
+                latest_bill_id = Some(bill_id);
+            }
+            latest_bill_id
+        }
+
+        /// Create the next bill of a series if its due time has passed
+        fn instantiate_due_occurrence(&mut self, series_id: u32) -> Option<u32> {
+            if self.series_cancelled.get(series_id).unwrap_or(false) {
+                return None;
+            }
+            
+            let remaining = self.series_remaining.get(series_id).unwrap_or(0);
+            let next_due = self.series_next_due.get(series_id).unwrap_or(u64::MAX);
+            if remaining == 0 || self.env().block_timestamp() < next_due {
+                return None;
+            }
+            
+            let creator = self.series_creators.get(series_id)?;
+            let template_bill_id = self.series_template_bills.get(series_id)?;
+            let latest_bill_id = self.series_latest_bills.get(series_id).unwrap_or(template_bill_id);
+            let interval = self.series_intervals.get(series_id).unwrap_or(0);
+            
+            // A cancelled template ends the series; an expired one only missed its own payments
+            if self.load_bill(template_bill_id)?.cancelled {
+                self.end_series(series_id);
+                return None;
+            }
+            let participants = self.series_participants.get(series_id).unwrap_or_default();
+            let individual_amounts = self.series_shares.get(series_id).unwrap_or_default();
+            let total_amount: Balance = individual_amounts.iter().sum();
+            let deadline = self
+                .load_bill(latest_bill_id)
+                .map(|bill| bill.deadline)
+                .unwrap_or(0)
+                .saturating_add(interval);
+            
+            let bill_id = self.store_bill(creator, total_amount, &participants, &individual_amounts, deadline);
+            let remaining = remaining.saturating_sub(1);
+            
+            if let Some(ledger) = self.bill_payment_ledgers.get(template_bill_id) {
+                self.bill_payment_ledgers.insert(bill_id, &ledger);
+            }
+            if let Some(token) = self.bill_tokens.get(template_bill_id) {
+                self.bill_tokens.insert(bill_id, &token);
+            }
+            if let Some(currency) = self.bill_currencies.get(template_bill_id) {
+                self.bill_currencies.insert(bill_id, &currency);
+            }
+            if let Some(payouts) = self.bill_payouts.get(template_bill_id) {
+                self.bill_payouts.insert(bill_id, &payouts);
+            }
+            if let Some(policy) = self.series_late_fees.get(series_id) {
+                self.bill_late_fees.insert(bill_id, &policy);
+            }
+            if let Some(group_id) = self.series_groups.get(series_id) {
+                self.add_bill_to_group(bill_id, group_id, creator, participants.len() as u32);
+            }
+            
+            self.bill_series.insert(bill_id, &series_id);
+            self.series_latest_bills.insert(series_id, &bill_id);
+            self.series_remaining.insert(series_id, &remaining);
+            self.series_next_due.insert(series_id, &next_due.saturating_add(interval));
+            
+            self.env().emit_event(RecurringBillInstantiated {
+                series_id,
+                bill_id,
+                remaining,
+            });
+            
+            Some(bill_id)
+        }
+
+        /// Stop a series from creating further bills
+        fn end_series(&mut self, series_id: u32) {
+            self.series_cancelled.insert(series_id, &true);
+            let remaining = self.series_remaining.take(series_id).unwrap_or(0);
+            
+            self.env().emit_event(RecurringBillCancelled {
+                series_id,
+                remaining,
+            });
+        }
+
+        /// Validate and store a bill created by the caller
+        fn new_bill(
+            &mut self,
+            total_amount: Balance,
+            participants: &[AccountId],
+            individual_amounts: &[Balance],
+            deadline: u64,
+        ) -> Result<u32, Error> {
+            let creator = self.env().caller();
+            
+            if total_amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            if participants.is_empty() {
+                return Err(Error::InvalidParticipants);
+            }
+            
+            if participants.len() != individual_amounts.len() {
+                return Err(Error::InvalidParticipants);
+            }
+            
+            if participants.len() > self.max_participants as usize {
+                return Err(Error::TooManyParticipants);
+            }
+            
+            // A repeated participant could only ever pay one of their shares
+            for (position, participant) in participants.iter().enumerate() {
+                if participants[..position].contains(participant) {
+                    return Err(Error::DuplicateParticipant);
+                }
+            }
+            
+            if individual_amounts.contains(&0) {
+                return Err(Error::ZeroShare);
+            }
+            
+            // Verify that sum of individual amounts equals total
+            let sum: Balance = individual_amounts.iter().sum();
+            if sum != total_amount {
+                return Err(Error::InvalidAmount);
+            }
+            
+            let bill_id = self.store_bill(creator, total_amount, participants, individual_amounts, deadline);
+            
+            Ok(bill_id)
+        }
+
+        /// Persist a validated bill and index it for every involved user
+        #[allow(clippy::cast_possible_truncation)]
+        fn store_bill(
+            &mut self,
+            creator: AccountId,
+            total_amount: Balance,
+            participants: &[AccountId],
+            individual_amounts: &[Balance],
+            deadline: u64,
+        ) -> u32 {
+            let bill_id = self.bill_counter;
+            
+            // Store bill information
+            self.bills.insert(bill_id, &Bill {
+                creator,
+                total_amount,
+                participant_count: participants.len() as u32,
+                paid_count: 0,
+                deadline,
+                completed: false,
+                cancelled: false,
+                expired: false,
+                proposed: false,
+                resplit_on_reject: false,
+                escrowed: 0,
+                dispute: DisputeStatus::Open,
+                created_at: self.env().block_timestamp(),
+            });
+            
+            // Store participants and amounts
+            for (index, (participant, amount)) in participants.iter().zip(individual_amounts.iter()).enumerate() {
+                let idx = index as u32;
+                self.bill_participants.insert((bill_id, idx), participant);
+                self.bill_individual_amounts.insert((bill_id, idx), amount);
+                self.bill_payments.insert((bill_id, *participant), &false);
+            }
+            
+            self.bill_counter = self.bill_counter.saturating_add(1);
+            
+            // Update user bill indexes
+            self.index_user_bill(creator, bill_id);
+            
+            for participant in participants {
+                self.index_user_bill(*participant, bill_id);
+            }
+            
+            self.env().emit_event(BillCreated {
+                bill_id,
+                creator,
+                total_amount,
+                participant_count: participants.len() as u32,
+            });
+            
+            bill_id
+        }
+
+        /// Attach a bill to a group and add its unpaid shares to the group tab
+        fn add_bill_to_group(&mut self, bill_id: u32, group_id: u32, creator: AccountId, participant_count: u32) {
+            self.bill_groups.insert(bill_id, &group_id);
+            let bill_count = self.group_bill_counts.get(group_id).unwrap_or(0);
+            self.group_bill_counts.insert(group_id, &bill_count.saturating_add(1));
+            
+            for index in 0..participant_count {
+                if let Some(participant) = self.bill_participants.get((bill_id, index)) {
+                    if !self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                        let amount = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+                        self.record_group_debt(group_id, participant, creator, amount);
+                    }
+                }
+            }
+            
+            self.env().emit_event(BillAttachedToGroup {
+                bill_id,
+                group_id,
+            });
+        }
+
+        /// Add a member to a group's member list
+        fn insert_group_member(&mut self, group_id: u32, member: AccountId) -> Result<(), Error> {
+            if self.group_membership.get((group_id, member)).unwrap_or(false) {
+                return Ok(());
+            }
+            
+            let member_count = self.group_member_counts.get(group_id).unwrap_or(0);
+            if member_count >= MAX_GROUP_MEMBERS {
+                return Err(Error::GroupFull);
+            }
+            
+            self.group_members.insert((group_id, member_count), &member);
+            self.group_membership.insert((group_id, member), &true);
+            self.group_member_counts.insert(group_id, &member_count.saturating_add(1));
+            
+            self.env().emit_event(GroupMemberAdded {
+                group_id,
+                member,
+            });
+            
+            Ok(())
+        }
+
+        /// Record that `debtor` owes `creditor` a further `amount` within a group
+        ///
+        /// The pairwise entry is netted against any debt in the opposite
+        /// direction, and both members' net balances are updated.
+        #[allow(clippy::cast_possible_wrap)]
+        fn record_group_debt(&mut self, group_id: u32, debtor: AccountId, creditor: AccountId, amount: Balance) {
+            if debtor == creditor || amount == 0 {
+                return;
+            }
+            
+            let reverse = self.group_debts.get((group_id, creditor, debtor)).unwrap_or(0);
+            if reverse >= amount {
+                self.group_debts.insert((group_id, creditor, debtor), &reverse.saturating_sub(amount));
+            } else {
+                self.group_debts.remove((group_id, creditor, debtor));
+                let forward = self.group_debts.get((group_id, debtor, creditor)).unwrap_or(0);
+                let added = amount.saturating_sub(reverse);
+                self.group_debts.insert((group_id, debtor, creditor), &forward.saturating_add(added));
+            }
+            
+            let delta = amount.min(i128::MAX as Balance) as i128;
+            let debtor_net = self.group_net_balances.get((group_id, debtor)).unwrap_or(0);
+            self.group_net_balances.insert((group_id, debtor), &debtor_net.saturating_sub(delta));
+            let creditor_net = self.group_net_balances.get((group_id, creditor)).unwrap_or(0);
+            self.group_net_balances.insert((group_id, creditor), &creditor_net.saturating_add(delta));
+        }
+
+        /// Spread tax and tip over pre-tax shares in proportion to their size
+        fn apply_tax_and_tip(
+            base_amounts: &[Balance],
+            tax_bps: u32,
+            tip_amount: Balance,
+        ) -> Result<Vec<Balance>, Error> {
+            if u128::from(tax_bps) > BASIS_POINTS {
+                return Err(Error::InvalidAmount);
+            }
+            
+            let subtotal: Balance = base_amounts.iter().sum();
+            if subtotal == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            let tax = subtotal
+                .checked_mul(u128::from(tax_bps))
+                .ok_or(Error::InvalidAmount)?
+                / BASIS_POINTS;
+            let extra = tax.checked_add(tip_amount).ok_or(Error::InvalidAmount)?;
+            
+            let mut amounts = Vec::new();
+            let mut distributed: Balance = 0;
+            for base in base_amounts {
+                let portion = extra.checked_mul(*base).ok_or(Error::InvalidAmount)? / subtotal;
+                distributed = distributed.saturating_add(portion);
+                amounts.push(base.saturating_add(portion));
+            }
+            
+            // Rounding leaves fewer units than there are shares
+            let mut remainder = extra.saturating_sub(distributed);
+            for amount in amounts.iter_mut() {
+                if remainder == 0 {
+                    break;
+                }
+                *amount = amount.saturating_add(1);
+                remainder = remainder.saturating_sub(1);
+            }
+            
+            Ok(amounts)
+        }
+
+        /// Validate and write a template
+        fn store_template(
+            &mut self,
+            template_id: u32,
+            owner: AccountId,
+            participants: Vec<AccountId>,
+            weights: Vec<u32>,
+            label: Vec<u8>,
+        ) -> Result<(), Error> {
+            if label.len() > MAX_DESCRIPTION_LEN {
+                return Err(Error::DescriptionTooLong);
+            }
+            
+            if participants.is_empty()
+                || participants.len() > MAX_TEMPLATE_PARTICIPANTS
+                || participants.len() != weights.len()
+            {
+                return Err(Error::InvalidParticipants);
+            }
+            
+            // A zero weight would give its participant a zero share
+            if weights.contains(&0) {
+                return Err(Error::ZeroShare);
+            }
+            
+            self.template_owners.insert(template_id, &owner);
+            self.template_labels.insert(template_id, &label);
+            self.template_participants.insert(template_id, &participants);
+            self.template_weights.insert(template_id, &weights);
+            
+            self.env().emit_event(TemplateSaved { template_id, owner });
+            
+            Ok(())
+        }
+
+        /// Ensure the caller owns the template
+        fn ensure_template_owner(&self, template_id: u32) -> Result<AccountId, Error> {
+            let owner = self.template_owners.get(template_id).ok_or(Error::TemplateNotFound)?;
+            if self.env().caller() != owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(owner)
+        }
+
+        /// Position of the entry with the largest balance
+        fn largest_position(entries: &[(AccountId, Balance)]) -> Option<usize> {
+            entries
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, (_, balance))| *balance)
+                .map(|(position, _)| position)
+        }
+
+        /// Ensure a bill currently accepts payments
+        fn ensure_payable(&self, bill_id: u32, bill: &Bill) -> Result<(), Error> {
+            // Check if bill is already completed
+            if bill.completed {
+                return Err(Error::BillAlreadyCompleted);
+            }
+            
+            // Check if bill was cancelled
+            if bill.cancelled {
+                return Err(Error::BillCancelled);
+            }
+            
+            // Proposed bills only accept payments once every participant agreed
+            if bill.proposed {
+                return Err(Error::BillNotActive);
+            }
+            
+            // Payments are frozen while a dispute is pending
+            if bill.dispute == DisputeStatus::Disputed {
+                return Err(Error::BillDisputed);
+            }
+            
+            // Check if bill has expired; bills with a late-fee policy stay
+            // payable past the deadline until someone expires them
+            if bill.expired
+                || (self.env().block_timestamp() > bill.deadline && !self.bill_late_fees.contains(bill_id))
+            {
+                return Err(Error::BillExpired);
+            }
+            
+            Ok(())
+        }
+
+        /// The configured XcmHandler, or the registry's if none is set
+        fn resolve_xcm_handler(&self) -> Option<AccountId> {
+            self.xcm_handler.or_else(|| {
+                let registry: ink::contract_ref!(ComponentRegistry) = self.registry?.into();
+                registry.resolve(Component::XcmHandler)
+            })
+        }
+
+        /// Find the index of a participant in a bill
+        fn find_participant(&self, bill_id: u32, bill: &Bill, account: AccountId) -> Option<u32> {
+            (0..bill.participant_count).find(|index| {
+                self.bill_participants.get((bill_id, *index)) == Some(account)
+            })
+        }
+
+        /// Ensure the caller created the bill and it is still open
+        fn ensure_open_bill_creator(&self, bill_id: u32) -> Result<Bill, Error> {
+            let bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            if self.env().caller() != bill.creator {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if bill.completed {
+                return Err(Error::BillAlreadyCompleted);
+            }
+            
+            if bill.cancelled {
+                return Err(Error::BillCancelled);
+            }
+            
+            if bill.expired {
+                return Err(Error::BillExpired);
+            }
+            
+            if bill.dispute == DisputeStatus::Disputed {
+                return Err(Error::BillDisputed);
+            }
+            
+            Ok(bill)
+        }
+
+        /// Arbiter of a bill, falling back to the global arbiter
+        fn arbiter_of(&self, bill_id: u32) -> Option<AccountId> {
+            self.bill_arbiters.get(bill_id).or(self.arbiter)
+        }
+
+        /// Ensure a bill exists and is still awaiting acceptance
+        fn ensure_proposed(&self, bill_id: u32) -> Result<Bill, Error> {
+            let bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            
+            if !bill.proposed || bill.cancelled {
+                return Err(Error::NotProposed);
+            }
+            
+            Ok(bill)
+        }
+
+        /// Refund every escrowed contribution of a bill that will not complete
+        ///
+        /// Outstanding group debts for unpaid shares are reversed. Returns the
+        /// refunded total and the participants who had not paid.
+        fn refund_contributions(
+            &mut self,
+            bill_id: u32,
+            bill: &mut Bill,
+        ) -> Result<(Balance, Vec<AccountId>), Error> {
+            let group_id = self.bill_groups.get(bill_id);
+            let mut refunded_amount: Balance = 0;
+            let mut unpaid = Vec::new();
+            
+            for index in 0..bill.participant_count {
+                if let Some(participant) = self.bill_participants.get((bill_id, index)) {
+                    let contribution = self
+                        .bill_contributions
+                        .take((bill_id, participant))
+                        .unwrap_or(0);
+                    if contribution > 0 {
+                        self.release_funds(bill_id, participant, contribution)?;
+                        refunded_amount = refunded_amount.saturating_add(contribution);
+                    }
+                    
+                    if !self.bill_payments.get((bill_id, participant)).unwrap_or(false) {
+                        self.clear_overdue(bill_id, participant);
+                        if let Some(group_id) = group_id {
+                            let amount = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+                            self.record_group_debt(group_id, bill.creator, participant, amount);
+                        }
+                        unpaid.push(participant);
+                    }
+                }
+            }
+            
+            bill.escrowed = 0;
+            
+            Ok((refunded_amount, unpaid))
+        }
+
+        /// Remove the participant at `index` from a bill and rebalance its total
+        ///
+        /// Returns the removed share.
+        fn detach_participant(
+            &mut self,
+            bill_id: u32,
+            bill: &mut Bill,
+            index: u32,
+            participant: AccountId,
+        ) -> Balance {
+            let amount = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            
+            // Move the last participant into the freed slot to keep indexes dense
+            let last_index = bill.participant_count.saturating_sub(1);
+            if index != last_index {
+                if let Some(last_participant) = self.bill_participants.get((bill_id, last_index)) {
+                    let last_amount = self.bill_individual_amounts.get((bill_id, last_index)).unwrap_or(0);
+                    self.bill_participants.insert((bill_id, index), &last_participant);
+                    self.bill_individual_amounts.insert((bill_id, index), &last_amount);
+                }
+            }
+            self.bill_participants.remove((bill_id, last_index));
+            self.bill_individual_amounts.remove((bill_id, last_index));
+            self.bill_payments.remove((bill_id, participant));
+            self.bill_acceptances.remove((bill_id, participant));
+            bill.participant_count = last_index;
+            self.clear_overdue(bill_id, participant);
+            
+            bill.total_amount = bill.total_amount.saturating_sub(amount);
+            
+            if let Some(group_id) = self.bill_groups.get(bill_id) {
+                self.record_group_debt(group_id, bill.creator, participant, amount);
+            }
+            
+            // The creator keeps the bill in their index
+            if participant != bill.creator {
+                self.unindex_user_bill(participant, bill_id);
+            }
+            
+            self.env().emit_event(ParticipantRemoved {
+                bill_id,
+                participant,
+                amount,
+                new_total: bill.total_amount,
+            });
+            
+            amount
+        }
+
+        /// Spread an amount evenly over a bill's participants, earliest first for the remainder
+        fn resplit_amount(&mut self, bill_id: u32, bill: &mut Bill, amount: Balance) {
+            let participant_count = bill.participant_count;
+            if participant_count == 0 {
+                return;
+            }
+            
+            let base = amount / Balance::from(participant_count);
+            let remainder = amount % Balance::from(participant_count);
+            let group_id = self.bill_groups.get(bill_id);
+            
+            for index in 0..participant_count {
+                let extra = if Balance::from(index) < remainder {
+                    base.saturating_add(1)
+                } else {
+                    base
+                };
+                let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+                self.bill_individual_amounts.insert((bill_id, index), &share.saturating_add(extra));
+                
+                if let Some(participant) = self.bill_participants.get((bill_id, index)) {
+                    // Shares changed, so everyone has to accept again
+                    if participant != bill.creator {
+                        self.bill_acceptances.remove((bill_id, participant));
+                    }
+                    if let Some(group_id) = group_id {
+                        self.record_group_debt(group_id, participant, bill.creator, extra);
+                    }
+                }
+            }
+            
+            bill.total_amount = bill.total_amount.saturating_add(amount);
+        }
+
+        /// Activate a proposed bill once every participant has accepted it
+        fn activate_if_accepted(&mut self, bill_id: u32, bill: &mut Bill) {
+            let all_accepted = (0..bill.participant_count).all(|index| {
+                self.bill_participants
+                    .get((bill_id, index))
+                    .map(|participant| self.bill_acceptances.get((bill_id, participant)).unwrap_or(false))
+                    .unwrap_or(false)
+            });
+            
+            if all_accepted {
+                bill.proposed = false;
+                self.env().emit_event(BillActivated { bill_id });
+            }
+        }
+
+        /// Late fee currently accrued on a share
+        ///
+        /// The flat fee applies as soon as the deadline passes; interest accrues
+        /// per full day overdue.
+        fn late_fee(&self, bill_id: u32, bill: &Bill, share: Balance) -> Balance {
+            let Some(policy) = self.bill_late_fees.get(bill_id) else {
+                return 0;
+            };
+            
+            let deadline = bill.deadline;
+            let now = self.env().block_timestamp();
+            if now <= deadline {
+                return 0;
+            }
+            
+            let days_late = u128::from(now.saturating_sub(deadline) / MILLISECONDS_PER_DAY);
+            let interest = share
+                .saturating_mul(u128::from(policy.daily_bps))
+                .saturating_mul(days_late)
+                / BASIS_POINTS;
+            
+            policy.flat_fee.saturating_add(interest)
+        }
+
+        /// Mark a participant's share as paid and complete the bill once all are in
+        fn settle_share(
+            &mut self,
+            bill_id: u32,
+            bill: &mut Bill,
+            participant: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.bill_payments.insert((bill_id, participant), &true);
+            self.clear_overdue(bill_id, participant);
+            
+            // Paying the share clears the matching group debt
+            if let Some(group_id) = self.bill_groups.get(bill_id) {
+                self.record_group_debt(group_id, bill.creator, participant, amount);
+            }
+            
+            bill.paid_count = bill.paid_count.saturating_add(1);
+            
+            // Check if all participants have paid
+            if bill.paid_count == bill.participant_count {
+                self.complete_bill(bill_id, bill)?;
+            }
+            
+            Ok(())
+        }
+
+        /// Count a share paid on time by `payer` towards their badges, ignoring failures
+        fn notify_badges(&self, payer: AccountId) {
+            if let Some(badges) = self.badges {
+                let mut badges: ink::contract_ref!(ActivityRecorder) = badges.into();
+                let _ = badges
+                    .call_mut()
+                    .record_activity(payer, Activity::BillPaidOnTime)
+                    .try_invoke();
+            }
+        }
+
+        /// Stop counting a share as overdue once it is settled or dropped
+        fn clear_overdue(&mut self, bill_id: u32, participant: AccountId) {
+            if self.bill_overdue.take((bill_id, participant)).is_some() {
+                let count = self.user_overdue_counts.get(participant).unwrap_or(0);
+                self.user_overdue_counts.insert(participant, &count.saturating_sub(1));
+            }
+        }
+
+        /// Record an escrowed payment of a share plus late fee, mark it paid and
+        /// update the payer's history
+        ///
+        /// With a treasury set, the late fee is paid to it rather than escrowed,
+        /// and is not refunded if the bill does not complete.
+        fn record_payment(
+            &mut self,
+            bill_id: u32,
+            bill: &mut Bill,
+            payer: AccountId,
+            share: Balance,
+            late_fee: Balance,
+        ) -> Result<(), Error> {
+            let amount = share.saturating_add(late_fee);
+            let escrowed = match self.treasury {
+                Some(treasury) if late_fee > 0 && !self.bill_tokens.contains(bill_id) => {
+                    self.pay_treasury(bill_id, treasury, late_fee)?;
+                    share
+                }
+                _ => amount,
+            };
+            self.bill_contributions.insert((bill_id, payer), &escrowed);
+            bill.escrowed = bill.escrowed.saturating_add(escrowed);
+            
+            let mut record = self.payment_records.get(payer).unwrap_or_default();
+            if self.env().block_timestamp() > bill.deadline {
+                record.late = record.late.saturating_add(1);
+            } else {
+                record.on_time = record.on_time.saturating_add(1);
+                self.notify_badges(payer);
+            }
+            self.payment_records.insert(payer, &record);
+            
+            self.settle_share(bill_id, bill, payer, share)?;
+            
+            if late_fee > 0 {
+                self.env().emit_event(LateFeeCharged {
+                    bill_id,
+                    payer,
+                    fee: late_fee,
+                });
+            }
+            
+            self.env().emit_event(BillPaid {
+                bill_id,
+                payer,
+                amount,
+            });
+            
+            Ok(())
+        }
+
+        /// Pay a participant's share from their deposit if auto-pay covers it
+        ///
+        /// Returns whether the share was paid. Only bills of creators the participant
+        /// approved are paid; token and ledger bills are skipped since deposits are
+        /// held in the native token.
+        fn try_auto_pay(&mut self, bill_id: u32, participant: AccountId) -> Result<bool, Error> {
+            let deposit = self.auto_pay_deposits.get(participant).unwrap_or(0);
+            if deposit == 0 {
+                return Ok(false);
+            }
+            let Some(mut bill) = self.load_bill(bill_id) else {
+                return Ok(false);
+            };
+            let Some(cap) = self.auto_pay_caps.get((participant, bill.creator)) else {
+                return Ok(false);
+            };
+            
+            if self.bill_tokens.contains(bill_id)
+                || self.bill_payment_ledgers.contains(bill_id)
+                || self.bill_currencies.contains(bill_id)
+                || self.ensure_payable(bill_id, &bill).is_err()
+                || self.bill_payments.get((bill_id, participant)).unwrap_or(false)
+                || self.bill_xcm_payments.contains((bill_id, participant))
+            {
+                return Ok(false);
+            }
+            
+            let Some(index) = self.find_participant(bill_id, &bill, participant) else {
+                return Ok(false);
+            };
+            let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            let late_fee = self.late_fee(bill_id, &bill, share);
+            let amount = share.saturating_add(late_fee);
+            if amount > cap || amount > deposit {
+                return Ok(false);
+            }
+            
+            self.auto_pay_deposits.insert(participant, &deposit.saturating_sub(amount));
+            self.record_payment(bill_id, &mut bill, participant, share, late_fee)?;
+            self.save_bill(bill_id, &bill);
+            
+            Ok(true)
+        }
+
+        /// Mark the share paid by an executed cross-chain payment
+        ///
+        /// The funds went to the creator through the XcmHandler, so nothing is escrowed.
+        fn settle_cross_chain_share(&mut self, payment_id: u32) -> Result<(), Error> {
+            let (bill_id, participant, amount) = self
+                .xcm_payment_bills
+                .get(payment_id)
+                .ok_or(Error::PaymentNotLinked)?;
+            let mut bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            
+            if bill.completed {
+                return Err(Error::BillAlreadyCompleted);
+            }
+            if bill.cancelled {
+                return Err(Error::BillCancelled);
+            }
+            if bill.expired {
+                return Err(Error::BillExpired);
+            }
+            
+            let index = self
+                .find_participant(bill_id, &bill, participant)
+                .ok_or(Error::ParticipantNotFound)?;
+            let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            
+            self.xcm_payment_bills.remove(payment_id);
+            self.bill_xcm_payments.remove((bill_id, participant));
+            self.consumed_xcm_payments.insert(payment_id, &());
+            self.settle_share(bill_id, &mut bill, participant, share)?;
+            self.save_bill(bill_id, &bill);
+            
+            self.env().emit_event(BillPaid {
+                bill_id,
+                payer: participant,
+                amount,
+            });
+            
+            Ok(())
+        }
+
+        /// The unpaid share and late fee a participant owes on a payable bill
+        fn due_share(&mut self, bill_id: u32, payer: AccountId) -> Result<(Bill, Balance, Balance), Error> {
+            let bill = self.load_bill(bill_id).ok_or(Error::BillNotFound)?;
+            
+            // Roll recurring series forward on first interaction after the interval
+            if let Some(series_id) = self.bill_series.get(bill_id) {
+                self.instantiate_due_occurrences(series_id);
+            }
+            
+            self.ensure_payable(bill_id, &bill)?;
+            
+            if self.bill_payments.get((bill_id, payer)).unwrap_or(false) {
+                return Err(Error::AlreadyPaid);
+            }
+            
+            // The share is being paid cross-chain until the link settles or is removed
+            if self.bill_xcm_payments.contains((bill_id, payer)) {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            
+            let index = self
+                .find_participant(bill_id, &bill, payer)
+                .ok_or(Error::ParticipantNotFound)?;
+            let share = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
+            let late_fee = self.late_fee(bill_id, &bill, share);
+            
+            Ok((bill, share, late_fee))
+        }
+
+        /// Asset a bill's shares are paid in
+        fn bill_asset(&self, bill_id: u32) -> IntentAsset {
+            if let Some(token) = self.bill_tokens.get(bill_id) {
+                IntentAsset::Psp22(token)
+            } else if let Some(ledger) = self.bill_payment_ledgers.get(bill_id) {
+                IntentAsset::Ledger(ledger)
+            } else {
+                IntentAsset::Native
+            }
+        }
+
+        /// Pay a plain intent from `payer` straight to its recipient
+        fn transfer_intent(&mut self, intent: &PaymentIntent, payer: AccountId) -> Result<(), Error> {
+            let transferred = self.env().transferred_value();
+            match intent.asset {
+                IntentAsset::Native => {
+                    if transferred != intent.amount {
+                        return Err(Error::InvalidAmount);
+                    }
+                    self.env()
+                        .transfer(intent.recipient, intent.amount)
+                        .map_err(|_| Error::TransferFailed)
+                }
+                _ if transferred != 0 => Err(Error::InvalidAmount),
+                IntentAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger
+                        .call_mut()
+                        .transfer_from(payer, intent.recipient, intent.amount)
+                        .try_invoke()
+                    {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                IntentAsset::Psp22(token) => {
+                    let result = build_call::<Environment>()
+                        .call(token)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                                .push_arg(payer)
+                                .push_arg(intent.recipient)
+                                .push_arg(intent.amount)
+                                .push_arg(Vec::<u8>::new()),
+                        )
+                        .returns::<Result<(), Psp22Error>>()
+                        .try_invoke();
+                    match result {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::TokenTransferFailed),
+                    }
+                }
+            }
+        }
+
+        /// Move a payment into this contract's escrow
+        ///
+        /// Token and ledger-backed bills take the amount from the payer's balance
+        /// through their allowance and must not carry transferred value.
+        fn collect_funds(&mut self, bill_id: u32, payer: AccountId, amount: Balance) -> Result<(), Error> {
+            let transferred = self.env().transferred_value();
+            
+            if let Some(token) = self.bill_tokens.get(bill_id) {
+                if transferred != 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                let escrow = self.env().account_id();
+                let result = build_call::<Environment>()
+                    .call(token)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                            .push_arg(payer)
+                            .push_arg(escrow)
+                            .push_arg(amount)
+                            .push_arg(Vec::<u8>::new()),
+                    )
+                    .returns::<Result<(), Psp22Error>>()
+                    .try_invoke();
+                return match result {
+                    Ok(Ok(Ok(()))) => Ok(()),
+                    _ => Err(Error::TokenTransferFailed),
+                };
+            }
+            
+            match self.bill_payment_ledgers.get(bill_id) {
+                Some(ledger) => {
+                    if transferred != 0 {
+                        return Err(Error::InvalidAmount);
+                    }
+                    let escrow = self.env().account_id();
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer_from(payer, escrow, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                None if transferred != amount => Err(Error::InvalidAmount),
+                None => Ok(()),
+            }
+        }
+
+        /// Pay escrowed funds out in the bill's token, on its ledger or natively
+        fn release_funds(&mut self, bill_id: u32, to: AccountId, amount: Balance) -> Result<(), Error> {
+            if let Some(token) = self.bill_tokens.get(bill_id) {
+                let result = build_call::<Environment>()
+                    .call(token)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(PSP22_TRANSFER_SELECTOR))
+                            .push_arg(to)
+                            .push_arg(amount)
+                            .push_arg(Vec::<u8>::new()),
+                    )
+                    .returns::<Result<(), Psp22Error>>()
+                    .try_invoke();
+                return match result {
+                    Ok(Ok(Ok(()))) => Ok(()),
+                    _ => Err(Error::TokenTransferFailed),
+                };
+            }
+            
+            match self.bill_payment_ledgers.get(bill_id) {
+                Some(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer(to, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                None => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::TransferFailed),
+            }
+        }
+
+        /// Pay a late fee to the treasury natively or on the bill's ledger
+        ///
+        /// Ledger fees are approved for the treasury to pull.
+        fn pay_treasury(&mut self, bill_id: u32, treasury: AccountId, amount: Balance) -> Result<(), Error> {
+            let (asset, value) = match self.bill_payment_ledgers.get(bill_id) {
+                Some(ledger) => {
+                    let mut payment_ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match payment_ledger.call_mut().approve(treasury, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => (IntentAsset::Ledger(ledger), 0),
+                        _ => return Err(Error::LedgerTransferFailed),
+                    }
+                }
+                None => (IntentAsset::Native, amount),
+            };
+            
+            let mut collector: ink::contract_ref!(FeeCollector) = treasury.into();
+            match collector.call_mut().collect_fees(asset, amount).transferred_value(value).try_invoke() {
+                Ok(Ok(Ok(_))) => Ok(()),
+                _ => Err(Error::TransferFailed),
+            }
+        }
+
+        /// Mark a bill completed and release its escrow to the creator or its payees
+        fn complete_bill(&mut self, bill_id: u32, bill: &mut Bill) -> Result<(), Error> {
+            bill.completed = true;
+            
+            // Release escrow to the creator or the payees
+            let escrowed = core::mem::take(&mut bill.escrowed);
+            if let Some(payouts) = self.bill_payouts.get(bill_id) {
+                self.distribute_payouts(bill_id, &payouts, escrowed)?;
+            } else if escrowed > 0 {
+                self.release_funds(bill_id, bill.creator, escrowed)?;
+            }
+            
+            self.env().emit_event(BillCompleted {
+                bill_id,
+                creator: bill.creator,
+                total_paid: bill.total_amount,
+            });
+            
+            Ok(())
+        }
+
+        /// Split released escrow between a bill's payees by their basis points
+        ///
+        /// Shares are rounded down and the leftover goes to the first payee.
+        fn distribute_payouts(
+            &mut self,
+            bill_id: u32,
+            payouts: &[Payout],
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let amounts: Vec<Balance> = payouts
+                .iter()
+                .map(|(_, bps)| amount.saturating_mul(u128::from(*bps)) / BASIS_POINTS)
+                .collect();
+            let leftover = amount.saturating_sub(amounts.iter().sum());
+            
+            for (position, ((payee, _), payout)) in payouts.iter().zip(amounts).enumerate() {
+                let payout = if position == 0 {
+                    payout.saturating_add(leftover)
+                } else {
+                    payout
+                };
+                if payout == 0 {
+                    continue;
+                }
+                
+                self.release_funds(bill_id, *payee, payout)?;
+                
+                self.env().emit_event(PayoutDistributed {
+                    bill_id,
+                    payee: *payee,
+                    amount: payout,
+                });
+            }
+            
+            Ok(())
+        }
+
+        /// Read a bill's packed state
+        fn load_bill(&self, bill_id: u32) -> Option<Bill> {
+            self.bills.get(bill_id)
+        }
+
+        /// Write a bill's packed state back
+        fn save_bill(&mut self, bill_id: u32, bill: &Bill) {
+            self.bills.insert(bill_id, bill);
+        }
+    }
+
+    impl BillManager for BillSplitting {
+        #[ink(message)]
+        fn on_payment_executed(&mut self, payment_id: u32) -> Result<(), xcc_common::Error> {
+            self.confirm_cross_chain_payment(payment_id).map_err(Into::into)
+        }
+    }
+
+    /// Expiring a bill past its deadline is the maintenance job, one per bill id
+    impl Maintainable for BillSplitting {
+        #[ink(message)]
+        fn due_jobs(&self, offset: u32, limit: u32) -> JobPage {
+            let now = self.env().block_timestamp();
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.bill_counter);
+            let jobs = (offset..end)
+                .filter(|&bill_id| {
+                    self.load_bill(bill_id).is_some_and(|bill| {
+                        !bill.completed
+                            && !bill.cancelled
+                            && !bill.expired
+                            && bill.dispute != DisputeStatus::Disputed
+                            && now > bill.deadline
+                    })
+                })
+                .collect();
+            
+            JobPage {
+                jobs,
+                next_offset: (end < self.bill_counter).then_some(end),
+            }
+        }
+
+        #[ink(message)]
+        fn run_job(&mut self, job_id: u32) -> Result<(), xcc_common::Error> {
+            self.expire_bill(job_id).map(|_| ()).map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn pay_keeper_bounty(&mut self, keeper: AccountId, job_id: u32, amount: Balance) -> Result<Balance, xcc_common::Error> {
+            if self.keeper_registry != Some(self.env().caller()) {
+                return Err(xcc_common::Error::UnauthorizedAccess);
+            }
+            
+            let Some(mut bond) = self.keeper_bonds.get(job_id) else {
+                return Ok(0);
+            };
+            let amount = bond.draw(amount);
+            if amount == 0 {
+                return Ok(0);
+            }
+            
+            self.keeper_bonds.insert(job_id, &bond);
+            self.env()
+                .transfer(keeper, amount)
+                .map_err(|_| xcc_common::Error::TransferFailed)?;
+            
+            self.env().emit_event(KeeperBountyPaid { job_id, keeper, amount });
+            
+            Ok(amount)
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    #[allow(clippy::bool_assert_comparison)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn default_works() {
+            let bill_splitting = BillSplitting::default();
+            assert_eq!(bill_splitting.get_stats(), 0);
+        }
+
+        #[ink::test]
+        fn error_codes_are_stable() {
+            assert_eq!(Error::BillNotFound.error_code(), 2000);
+            assert_eq!(Error::MemoTooLong.error_code(), 2057);
+            assert_eq!(Error::InvalidArbiter.error_code(), 2058);
+            assert_eq!(Error::ArbiterLocked.error_code(), 2059);
+            assert_eq!(Error::DisputeClosed.error_code(), 2060);
+            assert_eq!(Error::LateFeePolicyLocked.error_code(), 2061);
+            assert_eq!(Error::PaymentInFlight.error_code(), 2062);
+            assert_eq!(ink::scale::Encode::encode(&Error::TransferFailed), ink::prelude::vec![9]);
+        }
+
+        #[ink::test]
+        fn with_owner_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut bill_splitting = BillSplitting::with_owner(accounts.bob);
+            assert_eq!(bill_splitting.get_owner(), accounts.bob);
+            
+            // The deployer has no admin rights
+            assert_eq!(
+                bill_splitting.set_max_participants(10),
+                Err(Error::UnauthorizedAccess)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.set_max_participants(10).unwrap();
+        }
+
+        #[ink::test]
+        fn upgrade_and_migration_are_owner_only() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(bill_splitting.get_storage_version(), STORAGE_VERSION);
+            
+            // Freshly deployed storage is already current
+            assert_eq!(
+                bill_splitting.migrate_from(STORAGE_VERSION - 1),
+                Err(Error::StorageVersionMismatch)
+            );
+            assert_eq!(bill_splitting.migrate_from(STORAGE_VERSION), Err(Error::AlreadyMigrated));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.upgrade(Hash::from([1; 32])),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(
+                bill_splitting.migrate_from(STORAGE_VERSION),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(
+                bill_splitting.transfer_ownership(accounts.bob),
+                Err(Error::UnauthorizedAccess)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(bill_splitting.get_owner(), accounts.bob);
+        }
+
+        #[ink::test]
+        fn create_bill_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let participants = ink::prelude::vec![accounts.alice, accounts.bob];
+            let individual_amounts = ink::prelude::vec![500, 300];
+            let total_amount = 800;
+            
+            let bill_id = bill_splitting.create_bill(
+                total_amount,
+                participants,
+                individual_amounts,
+                1000000000, // future deadline
+            ).unwrap();
+            
+            assert_eq!(bill_id, 0);
+            
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.1, total_amount); // total_amount
+            assert_eq!(bill_info.2, 2); // participant_count
+            assert_eq!(bill_info.4, false); // completed
+            assert_eq!(
+                bill_splitting.get_bill_participants(bill_id),
+                Some(ink::prelude::vec![accounts.alice, accounts.bob])
+            );
+            assert_eq!(bill_splitting.get_bill_participants(1), None);
+        }
+
+        #[ink::test]
+        fn pay_bill_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let participants = ink::prelude::vec![accounts.alice, accounts.bob];
+            let individual_amounts = ink::prelude::vec![500, 300];
+            let bill_id = bill_splitting.create_bill(
+                800,
+                participants,
+                individual_amounts,
+                1000000000,
+            ).unwrap();
+            
+            // Alice pays her share
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.3, 1); // paid_count
+            assert_eq!(bill_info.4, false); // completed
+            
+            // Switch to Bob and pay his share
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(300);
+            bill_splitting.pay_bill(bill_id, 300).unwrap();
+            
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.4, true); // completed
+            assert_eq!(bill_info.3, 2); // paid_count
+        }
+
+        #[ink::test]
+        fn export_bills_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let participants = ink::prelude::vec![accounts.alice, accounts.bob];
+            let bill_id = bill_splitting.create_bill(800, participants, ink::prelude::vec![500, 300], 1000000000).unwrap();
+            bill_splitting.create_bill(100, ink::prelude::vec![accounts.charlie], ink::prelude::vec![100], 1000000000).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            let chunk = bill_splitting.export_bills(0, 1);
+            assert_eq!((chunk.entries, chunk.next_offset), (1, Some(1)));
+            let bills: Vec<BillExport> = ink::scale::Decode::decode(&mut &chunk.data[..]).unwrap();
+            assert_eq!(bills[0].bill, bill_splitting.load_bill(bill_id).unwrap());
+            assert_eq!(
+                bills[0].shares,
+                ink::prelude::vec![(accounts.alice, 500, true), (accounts.bob, 300, false)]
+            );
+            
+            let chunk = bill_splitting.export_bills(1, 10);
+            assert_eq!((chunk.entries, chunk.next_offset), (1, None));
+            let bills: Vec<BillExport> = ink::scale::Decode::decode(&mut &chunk.data[..]).unwrap();
+            assert_eq!(bills[0].shares, ink::prelude::vec![(accounts.charlie, 100, false)]);
+        }
+
+        #[ink::test]
+        fn invalid_bill_creation_fails() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Mismatched participants and amounts should fail
+            let result = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.alice, accounts.bob],
+                ink::prelude::vec![800], // Only one amount for two participants
+                1000000000,
+            );
+            assert_eq!(result, Err(Error::InvalidParticipants));
+            
+            // Sum mismatch should fail
+            let result = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.alice, accounts.bob],
+                ink::prelude::vec![400, 300], // Sum is 700, not 800
+                1000000000,
+            );
+            assert_eq!(result, Err(Error::InvalidAmount));
+        }
+
+        #[ink::test]
+        fn cancel_bill_refunds_contributions() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000000000,
+            ).unwrap();
+            
+            // Bob pays his share into escrow
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            // Only the creator may cancel
+            assert_eq!(bill_splitting.cancel_bill(bill_id), Err(Error::UnauthorizedAccess));
+            
+            let bob_balance = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.cancel_bill(bill_id).unwrap();
+            
+            assert_eq!(bill_splitting.is_bill_cancelled(bill_id), true);
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob).unwrap(),
+                bob_balance + 500
+            );
+            
+            // Cancelled bills no longer accept payments
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(300);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 300), Err(Error::BillCancelled));
+        }
+
+        #[ink::test]
+        fn add_and_remove_participants_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000000000,
+            ).unwrap();
+            
+            // Add Django with a 200 share
+            bill_splitting.add_participant(bill_id, accounts.django, 200).unwrap();
+            assert_eq!(
+                bill_splitting.add_participant(bill_id, accounts.django, 200),
+                Err(Error::ParticipantAlreadyExists)
+            );
+            
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.1, 1000); // total_amount
+            assert_eq!(bill_info.2, 3); // participant_count
+            
+            // Bob pays, so he can no longer be removed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(bill_splitting.remove_participant(bill_id, accounts.bob), Err(Error::AlreadyPaid));
+            
+            // Removing Charlie moves Django into his slot
+            bill_splitting.remove_participant(bill_id, accounts.charlie).unwrap();
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.1, 700); // total_amount
+            assert_eq!(bill_info.2, 2); // participant_count
+            assert_eq!(bill_splitting.get_user_bill_count(accounts.charlie), 0);
+            
+            // Removing the last unpaid participant completes the bill
+            bill_splitting.remove_participant(bill_id, accounts.django).unwrap();
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.1, 500); // total_amount
+            assert_eq!(bill_info.4, true); // completed
+        }
+
+        #[ink::test]
+        fn bill_metadata_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000000000,
+            ).unwrap();
+            
+            // Bills start without metadata
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.6, BillMetadata::default());
+            
+            bill_splitting.set_bill_metadata(
+                bill_id,
+                b"Dinner at Luigi's 14/03".to_vec(),
+                BillCategory::Food,
+                Some([7; 32]),
+            ).unwrap();
+            
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.6.description, b"Dinner at Luigi's 14/03".to_vec());
+            assert_eq!(bill_info.6.category, BillCategory::Food);
+            assert_eq!(bill_info.6.receipt_hash, Some([7; 32]));
+            
+            // Descriptions are bounded
+            let result = bill_splitting.set_bill_metadata(
+                bill_id,
+                ink::prelude::vec![0; MAX_DESCRIPTION_LEN + 1],
+                BillCategory::Food,
+                None,
+            );
+            assert_eq!(result, Err(Error::DescriptionTooLong));
+            
+            // Only the creator may edit metadata
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let result = bill_splitting.set_bill_metadata(bill_id, Vec::new(), BillCategory::Other, None);
+            assert_eq!(result, Err(Error::UnauthorizedAccess));
+        }
+
+        #[ink::test]
+        fn get_user_bills_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Alice creates and participates in the first bill
+            bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.alice, accounts.bob],
+                ink::prelude::vec![500, 300],
+                1000000000,
+            ).unwrap();
+            
+            // Bob creates the second bill with Alice as participant
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.create_bill(
+                100,
+                ink::prelude::vec![accounts.alice],
+                ink::prelude::vec![100],
+                1000000000,
+            ).unwrap();
+            
+            assert_eq!(bill_splitting.get_user_bill_count(accounts.alice), 2);
+            assert_eq!(
+                bill_splitting.get_user_bills(accounts.alice, 0, 10),
+                ink::prelude::vec![(0, BillRole::Creator), (1, BillRole::Participant)]
+            );
+            assert_eq!(
+                bill_splitting.get_user_bills(accounts.bob, 1, 10),
+                ink::prelude::vec![(1, BillRole::Creator)]
+            );
+            assert!(bill_splitting.get_user_bills(accounts.bob, 2, 10).is_empty());
+        }
+
+        #[ink::test]
+        fn group_debt_simplification_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let group_id = bill_splitting.create_group(
+                b"Flatmates".to_vec(),
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+            ).unwrap();
+            assert_eq!(bill_splitting.get_group_info(group_id).unwrap().2, 3);
+            
+            // Alice paid for dinner: Bob and Charlie owe her 300 each
+            let dinner = bill_splitting.create_bill(
+                600,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![300, 300],
+                1000000000,
+            ).unwrap();
+            bill_splitting.attach_bill_to_group(dinner, group_id).unwrap();
+            
+            // Bob paid for groceries: Charlie owes him 200
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let groceries = bill_splitting.create_bill(
+                200,
+                ink::prelude::vec![accounts.charlie],
+                ink::prelude::vec![200],
+                1000000000,
+            ).unwrap();
+            bill_splitting.attach_bill_to_group(groceries, group_id).unwrap();
+            
+            assert_eq!(bill_splitting.get_group_balance(group_id, accounts.alice), 600);
+            assert_eq!(bill_splitting.get_group_balance(group_id, accounts.bob), -100);
+            assert_eq!(bill_splitting.get_group_balance(group_id, accounts.charlie), -500);
+            
+            // Three pairwise debts collapse into two transfers
+            assert_eq!(
+                bill_splitting.simplify_debts(group_id),
+                ink::prelude::vec![
+                    (accounts.charlie, accounts.alice, 500),
+                    (accounts.bob, accounts.alice, 100),
+                ]
+            );
+            
+            // Charlie settles directly with Alice
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.settle_group_debt(group_id, accounts.alice).unwrap();
+            assert_eq!(bill_splitting.get_group_balance(group_id, accounts.charlie), 0);
+            assert_eq!(
+                bill_splitting.simplify_debts(group_id),
+                ink::prelude::vec![(accounts.bob, accounts.alice, 100)]
+            );
+            
+            // Non-members cannot be put on group bills
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let outsider_bill = bill_splitting.create_bill(
+                100,
+                ink::prelude::vec![accounts.django],
+                ink::prelude::vec![100],
+                1000000000,
+            ).unwrap();
+            assert_eq!(
+                bill_splitting.attach_bill_to_group(outsider_bill, group_id),
+                Err(Error::NotGroupMember)
+            );
+        }
+
+        #[ink::test]
+        fn recurring_bill_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let rent = bill_splitting.create_bill(
+                2000,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![1000, 1000],
+                1000,
+            ).unwrap();
+            let series_id = bill_splitting.create_recurring_bill(rent, 100, 3).unwrap();
+            
+            // Nothing is due before the interval elapses
+            assert_eq!(bill_splitting.advance_recurring_bill(series_id), Ok(None));
+            
+            // The first interaction after the interval creates the next bill
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(100);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            bill_splitting.pay_bill(rent, 1000).unwrap();
+            
+            let (_, latest, _, remaining, _, _) = bill_splitting.get_recurring_bill(series_id).unwrap();
+            assert_eq!(latest, 1);
+            assert_eq!(remaining, 1);
+            
+            let bill_info = bill_splitting.get_bill_info(latest).unwrap();
+            assert_eq!(bill_info.0, accounts.alice); // creator
+            assert_eq!(bill_info.1, 2000); // total_amount
+            assert_eq!(bill_info.5, 1100); // deadline
+            
+            // Cancelling stops further occurrences
+            assert_eq!(bill_splitting.cancel_recurring_bill(series_id), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.cancel_recurring_bill(series_id).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(200);
+            assert_eq!(bill_splitting.advance_recurring_bill(series_id), Err(Error::SeriesCancelled));
+            
+            // Missed occurrences are all created at once, within a bound
+            let gym = bill_splitting.create_bill(
+                200,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![100, 100],
+                1000,
+            ).unwrap();
+            let series_id = bill_splitting.create_recurring_bill(gym, 100, 20).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(200 + 3 * 100);
+            let latest = bill_splitting.advance_recurring_bill(series_id).unwrap().unwrap();
+            let (_, _, _, remaining, next_due, _) = bill_splitting.get_recurring_bill(series_id).unwrap();
+            assert_eq!(remaining, 16);
+            assert_eq!(next_due, 200 + 4 * 100);
+            assert_eq!(bill_splitting.get_bill_info(latest).unwrap().5, 1000 + 3 * 100);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(200 + 100 * 100);
+            bill_splitting.advance_recurring_bill(series_id).unwrap();
+            let (_, _, _, remaining, _, _) = bill_splitting.get_recurring_bill(series_id).unwrap();
+            assert_eq!(remaining, 16 - MAX_CATCH_UP_OCCURRENCES);
+            
+            // Cancelling the template ends the series
+            bill_splitting.cancel_bill(gym).unwrap();
+            assert_eq!(bill_splitting.advance_recurring_bill(series_id), Ok(None));
+            let (_, _, _, remaining, _, cancelled) = bill_splitting.get_recurring_bill(series_id).unwrap();
+            assert_eq!((remaining, cancelled), (0, true));
+        }
+
+        #[ink::test]
+        fn recurring_bill_keeps_its_terms() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let members = ink::prelude::vec![accounts.bob, accounts.charlie, accounts.django];
+            let group_id = bill_splitting.create_group(b"flat".to_vec(), members.clone()).unwrap();
+            let rent = bill_splitting
+                .create_bill(3000, members, ink::prelude::vec![1000, 1000, 1000], 1000)
+                .unwrap();
+            bill_splitting.set_late_fee_policy(rent, 50, 0).unwrap();
+            bill_splitting.attach_bill_to_group(rent, group_id).unwrap();
+            let series_id = bill_splitting.create_recurring_bill(rent, 100, 3).unwrap();
+            
+            // Changing the template's shares, or letting it expire, leaves the series as created
+            bill_splitting.forgive_share(rent, accounts.bob).unwrap();
+            bill_splitting.remove_participant(rent, accounts.charlie).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1001);
+            bill_splitting.expire_bill(rent).unwrap();
+            
+            let latest = bill_splitting.advance_recurring_bill(series_id).unwrap().unwrap();
+            let (_, _, _, remaining, _, cancelled) = bill_splitting.get_recurring_bill(series_id).unwrap();
+            assert_eq!((remaining, cancelled), (0, false));
+            assert_eq!(bill_splitting.get_bill_info(latest).unwrap().1, 3000);
+            assert_eq!(
+                bill_splitting.get_bill_participants(latest).unwrap(),
+                ink::prelude::vec![accounts.bob, accounts.charlie, accounts.django]
+            );
+            assert_eq!(
+                bill_splitting.get_late_fee_policy(latest),
+                Some(LateFeePolicy { flat_fee: 50, daily_bps: 0 })
+            );
+            
+            // Both occurrences went on the group tab
+            assert_eq!(bill_splitting.get_group_info(group_id).unwrap().3, 3);
+            assert_eq!(bill_splitting.get_group_debt(group_id, accounts.bob, accounts.alice), 2000);
+        }
+
+        #[ink::test]
+        fn expire_bill_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                100,
+            ).unwrap();
+            
+            // The creator pushes the deadline back
+            assert_eq!(bill_splitting.extend_deadline(bill_id, 50), Err(Error::InvalidDeadline));
+            bill_splitting.extend_deadline(bill_id, 200).unwrap();
+            assert_eq!(bill_splitting.get_bill_info(bill_id).unwrap().5, 200);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            // Bills cannot be expired early
+            assert_eq!(bill_splitting.expire_bill(bill_id), Err(Error::BillNotExpired));
+            
+            // Anyone can expire the bill once the deadline passed
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(201);
+            let bob_balance = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            let defaulted = bill_splitting.expire_bill(bill_id).unwrap();
+            
+            assert_eq!(defaulted, ink::prelude::vec![accounts.charlie]);
+            assert_eq!(bill_splitting.is_bill_expired(bill_id), true);
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob).unwrap(),
+                bob_balance + 500
+            );
+            assert_eq!(bill_splitting.expire_bill(bill_id), Err(Error::BillExpired));
+        }
+
+        #[ink::test]
+        fn set_treasury_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            bill_splitting.set_treasury(Some(accounts.eve)).unwrap();
+            assert_eq!(bill_splitting.get_treasury(), Some(accounts.eve));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(bill_splitting.set_treasury(None), Err(Error::UnauthorizedAccess));
+        }
+
+        #[ink::test]
+        fn keeper_jobs_work() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let early = bill_splitting.create_bill(100, ink::prelude::vec![accounts.bob], ink::prelude::vec![100], 100).unwrap();
+            let late = bill_splitting.create_bill(100, ink::prelude::vec![accounts.bob], ink::prelude::vec![100], 300).unwrap();
+            assert_eq!(bill_splitting.due_jobs(0, 10), JobPage::default());
+            
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(200);
+            assert_eq!(bill_splitting.due_jobs(0, 10).jobs, ink::prelude::vec![early]);
+            assert_eq!(bill_splitting.due_jobs(0, 1).next_offset, Some(1));
+            assert_eq!(bill_splitting.run_job(late), Err(xcc_common::Error::Other(Error::BillNotExpired as u8)));
+            
+            // Keepers run the job like anyone expiring the bill
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            bill_splitting.run_job(early).unwrap();
+            assert_eq!(bill_splitting.is_bill_expired(early), true);
+            assert!(bill_splitting.due_jobs(0, 10).jobs.is_empty());
+            
+            // Only the keeper registry pays bounties, and no more than the job's bond holds
+            assert_eq!(bill_splitting.set_keeper_registry(Some(accounts.eve)), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.set_keeper_registry(Some(accounts.eve)).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            bill_splitting.post_keeper_bond(early).unwrap();
+            assert_eq!(bill_splitting.post_keeper_bond(late + 1), Err(Error::BillNotFound));
+            assert_eq!(
+                bill_splitting.pay_keeper_bounty(accounts.django, early, 20),
+                Err(xcc_common::Error::UnauthorizedAccess)
+            );
+            
+            // Anyone may add to the bond
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            bill_splitting.post_keeper_bond(early).unwrap();
+            assert_eq!(bill_splitting.get_keeper_bond(early), Some(40));
+            assert_eq!(bill_splitting.get_keeper_stake(early, accounts.bob), 10);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            let django_balance = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.django).unwrap();
+            assert_eq!(bill_splitting.pay_keeper_bounty(accounts.django, late, 20), Ok(0));
+            assert_eq!(bill_splitting.pay_keeper_bounty(accounts.django, early, 30), Ok(30));
+            assert_eq!(bill_splitting.pay_keeper_bounty(accounts.django, early, 20), Ok(10));
+            assert_eq!(bill_splitting.pay_keeper_bounty(accounts.django, early, 20), Ok(0));
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.django).unwrap(),
+                django_balance + 40
+            );
+            assert_eq!(bill_splitting.get_keeper_stake(early, accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn mark_settled_offline_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000000000,
+            ).unwrap();
+            
+            // Bob paid in cash
+            bill_splitting.mark_settled_offline(bill_id, accounts.bob).unwrap();
+            assert!(bill_splitting.is_settled_offline(bill_id, accounts.bob));
+            assert_eq!(
+                bill_splitting.mark_settled_offline(bill_id, accounts.bob),
+                Err(Error::AlreadyPaid)
+            );
+            
+            // Charlie pays on-chain and completes the bill
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(300);
+            bill_splitting.pay_bill(bill_id, 300).unwrap();
+            
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.3, 2); // paid_count
+            assert_eq!(bill_info.4, true); // completed
+            assert!(!bill_splitting.is_settled_offline(bill_id, accounts.charlie));
+        }
+
+        #[ink::test]
+        fn late_fees_work() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000,
+            ).unwrap();
+            
+            // 10 flat plus 1% per day
+            bill_splitting.set_late_fee_policy(bill_id, 10, 100).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(500));
+            
+            // Two full days late
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000 + 2 * MILLISECONDS_PER_DAY + 1);
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(520));
+            
+            // The policy is fixed once the deadline passed
+            assert_eq!(bill_splitting.set_late_fee_policy(bill_id, 100, 100), Err(Error::LateFeePolicyLocked));
+            
+            // Paying only the share is rejected
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 500), Err(Error::InvalidAmount));
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(520);
+            bill_splitting.pay_bill(bill_id, 520).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(0));
+            
+            // Bills without a policy still close at the deadline
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(0);
+            let other = bill_splitting.create_bill(
+                100,
+                ink::prelude::vec![accounts.bob],
+                ink::prelude::vec![100],
+                1000,
+            ).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(bill_splitting.pay_bill(other, 100), Err(Error::BillExpired));
+        }
+
+        #[ink::test]
+        fn proposed_bill_acceptance_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.propose_bill(
+                900,
+                ink::prelude::vec![accounts.bob, accounts.charlie, accounts.django],
+                ink::prelude::vec![300, 300, 300],
+                1000000000,
+                true,
+            ).unwrap();
+            assert_eq!(bill_splitting.is_bill_proposed(bill_id), true);
+            
+            // No payments before everyone agreed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.accept_bill(bill_id).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(300);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 300), Err(Error::BillNotActive));
+            
+            // Django rejects and his share is re-split over Bob and Charlie
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            bill_splitting.reject_bill(bill_id).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(450));
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.charlie), Some(450));
+            assert_eq!(bill_splitting.get_bill_info(bill_id).unwrap().2, 2);
+            assert!(!bill_splitting.has_accepted(bill_id, accounts.bob));
+            
+            // Both accept the new split and the bill becomes payable
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.accept_bill(bill_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            bill_splitting.accept_bill(bill_id).unwrap();
+            assert_eq!(bill_splitting.is_bill_proposed(bill_id), false);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(450);
+            bill_splitting.pay_bill(bill_id, 450).unwrap();
+        }
+
+        #[ink::test]
+        fn rejecting_proposal_cancels_bill() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.propose_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000000000,
+                false,
+            ).unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.reject_bill(bill_id).unwrap();
+            
+            assert_eq!(bill_splitting.is_bill_cancelled(bill_id), true);
+            assert_eq!(bill_splitting.accept_bill(bill_id), Err(Error::NotProposed));
+        }
+
+        #[ink::test]
+        fn dispute_flow_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000000000,
+            ).unwrap();
+            
+            // Disputes need an arbiter
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(bill_splitting.raise_dispute(bill_id, [1; 32]), Err(Error::NoArbiter));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.set_arbiter(Some(accounts.eve)).unwrap();
+            
+            // Bob disputes his share, which freezes payments
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.raise_dispute(bill_id, [1; 32]).unwrap();
+            assert_eq!(bill_splitting.get_dispute_status(bill_id), DisputeStatus::Disputed);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 500), Err(Error::BillDisputed));
+            
+            // Only the arbiter resolves
+            let resolution = DisputeResolution::AdjustShare {
+                participant: accounts.bob,
+                new_share: 400,
+            };
+            assert_eq!(
+                bill_splitting.resolve_dispute(bill_id, resolution.clone()),
+                Err(Error::UnauthorizedAccess)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            bill_splitting.resolve_dispute(bill_id, resolution).unwrap();
+            
+            assert_eq!(bill_splitting.get_dispute_status(bill_id), DisputeStatus::Resolved);
+            assert_eq!(bill_splitting.get_bill_info(bill_id).unwrap().1, 700);
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(400));
+            
+            // The resolution is final
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(bill_splitting.raise_dispute(bill_id, [2; 32]), Err(Error::DisputeClosed));
+            
+            // A bill's own arbiter is fixed before anyone pays, and is no party to it
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let other_bill_id = bill_splitting.create_bill(
+                800,
+                ink::prelude::vec![accounts.bob, accounts.charlie],
+                ink::prelude::vec![500, 300],
+                1000,
+            ).unwrap();
+            assert_eq!(bill_splitting.set_bill_arbiter(other_bill_id, accounts.alice), Err(Error::InvalidArbiter));
+            assert_eq!(bill_splitting.set_bill_arbiter(other_bill_id, accounts.bob), Err(Error::InvalidArbiter));
+            bill_splitting.set_bill_arbiter(other_bill_id, accounts.frank).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(300);
+            bill_splitting.pay_bill(other_bill_id, 300).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(bill_splitting.set_bill_arbiter(other_bill_id, accounts.django), Err(Error::ArbiterLocked));
+            
+            // A pending dispute holds off expiry, and the arbiter may void the bill
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.raise_dispute(other_bill_id, [3; 32]).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1001);
+            assert_eq!(bill_splitting.expire_bill(other_bill_id), Err(Error::BillDisputed));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            bill_splitting.resolve_dispute(other_bill_id, DisputeResolution::Void).unwrap();
+            assert_eq!(bill_splitting.is_bill_cancelled(other_bill_id), true);
+        }
+
+        #[ink::test]
+        fn payment_ledger_configuration_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let ledger = accounts.frank;
+            
+            let bill_id = bill_splitting
+                .create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                )
+                .unwrap();
+            assert_eq!(bill_splitting.get_payment_ledger(bill_id), None);
+            
+            // Only the creator may configure the ledger
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.set_payment_ledger(bill_id, Some(ledger)),
+                Err(Error::UnauthorizedAccess)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.set_payment_ledger(bill_id, Some(ledger)).unwrap();
+            assert_eq!(bill_splitting.get_payment_ledger(bill_id), Some(ledger));
+            
+            // Ledger-backed bills reject native value
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 500), Err(Error::InvalidAmount));
+            
+            // Switching back to native settlement
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            bill_splitting.set_payment_ledger(bill_id, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            // The ledger is locked once something is escrowed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                bill_splitting.set_payment_ledger(bill_id, Some(ledger)),
+                Err(Error::PaymentsAlreadyMade)
+            );
+        }
+
+        #[ink::test]
+        fn remote_participant_registration_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let bill_id = bill_splitting
+                .create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                )
+                .unwrap();
+            
+            // Only participants can register themselves
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                bill_splitting.register_remote_participant(bill_id, 2000),
+                Err(Error::ParticipantNotFound)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.link_cross_chain_payment(bill_id, 0),
+                Err(Error::NotRemoteParticipant)
+            );
+            bill_splitting.register_remote_participant(bill_id, 2000).unwrap();
+            assert_eq!(bill_splitting.get_remote_chain(bill_id, accounts.bob), Some(2000));
+            assert_eq!(bill_splitting.get_remote_chain(bill_id, accounts.charlie), None);
+            
+            // Linking needs a configured XcmHandler
+            assert_eq!(
+                bill_splitting.link_cross_chain_payment(bill_id, 0),
+                Err(Error::NoXcmHandler)
+            );
+            assert_eq!(bill_splitting.get_linked_payment(bill_id, accounts.bob), None);
+            assert_eq!(
+                bill_splitting.confirm_cross_chain_payment(0),
+                Err(Error::PaymentNotLinked)
+            );
+            assert_eq!(
+                bill_splitting.on_payment_executed(0),
+                Err(Error::PaymentNotLinked.into())
+            );
+            
+            // Only the owner configures the XcmHandler
+            assert_eq!(
+                bill_splitting.set_xcm_handler(Some(accounts.eve)),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(
+                bill_splitting.set_registry(Some(accounts.eve)),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(bill_splitting.get_registry(), None);
+        }
+
+        #[ink::test]
+        fn settled_cross_chain_payment_cannot_be_linked_again() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            bill_splitting.set_xcm_handler(Some(accounts.eve)).unwrap();
+            
+            let participants = ink::prelude::vec![accounts.bob, accounts.charlie];
+            let bill_id = bill_splitting
+                .create_bill(800, participants.clone(), ink::prelude::vec![400, 400], 1000)
+                .unwrap();
+            let other = bill_splitting
+                .create_bill(800, participants, ink::prelude::vec![400, 400], 1000)
+                .unwrap();
+            for caller in [accounts.bob, accounts.charlie] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(caller);
+                bill_splitting.register_remote_participant(bill_id, 2000).unwrap();
+                bill_splitting.register_remote_participant(other, 2000).unwrap();
+            }
+            
+            // Bob's payment is linked and executed
+            let payment_id = 7;
+            bill_splitting.bill_xcm_payments.insert((bill_id, accounts.bob), &payment_id);
+            bill_splitting.xcm_payment_bills.insert(payment_id, &(bill_id, accounts.bob, 400));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            bill_splitting.confirm_cross_chain_payment(payment_id).unwrap();
+            assert!(bill_splitting.bill_payments.get((bill_id, accounts.bob)).unwrap());
+            assert_eq!(bill_splitting.get_linked_payment(bill_id, accounts.bob), None);
+            
+            // The settled payment pays no other share, on this bill or another
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                bill_splitting.link_cross_chain_payment(bill_id, payment_id),
+                Err(Error::PaymentAlreadyLinked)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.link_cross_chain_payment(other, payment_id),
+                Err(Error::PaymentAlreadyLinked)
+            );
+        }
+
+        #[ink::test]
+        fn linked_share_cannot_be_paid_or_removed() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let participants = ink::prelude::vec![accounts.bob, accounts.charlie];
+            let bill_id = bill_splitting
+                .create_bill(800, participants, ink::prelude::vec![400, 400], 1000)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.register_remote_participant(bill_id, 2000).unwrap();
+            
+            // Bob's share is linked to a cross-chain payment still in flight
+            let payment_id = 7;
+            bill_splitting.bill_xcm_payments.insert((bill_id, accounts.bob), &payment_id);
+            bill_splitting.xcm_payment_bills.insert(payment_id, &(bill_id, accounts.bob, 400));
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(400);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 400), Err(Error::PaymentAlreadyLinked));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                bill_splitting.remove_participant(bill_id, accounts.bob),
+                Err(Error::PaymentAlreadyLinked)
+            );
+            
+            // Unlinking asks the XcmHandler whether the payment can still execute
+            assert_eq!(
+                bill_splitting.unlink_cross_chain_payment(bill_id, accounts.charlie),
+                Err(Error::PaymentNotLinked)
+            );
+            assert_eq!(
+                bill_splitting.unlink_cross_chain_payment(bill_id, accounts.bob),
+                Err(Error::NoXcmHandler)
+            );
+            
+            // An expired bill takes no late settlement
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1001);
+            bill_splitting.expire_bill(bill_id).unwrap();
+            bill_splitting.set_xcm_handler(Some(accounts.eve)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(bill_splitting.confirm_cross_chain_payment(payment_id), Err(Error::BillExpired));
+        }
+
+        #[ink::test]
+        fn token_bill_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let token = accounts.frank;
+            
+            let bill_id = bill_splitting
+                .create_token_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                    token,
+                )
+                .unwrap();
+            assert_eq!(bill_splitting.get_bill_token(bill_id), Some(token));
+            
+            // Token bills settle neither on a ledger nor through group tabs
+            assert_eq!(
+                bill_splitting.set_payment_ledger(bill_id, Some(accounts.eve)),
+                Err(Error::IncompatibleSettlement)
+            );
+            let group_id = bill_splitting
+                .create_group(b"Trip".to_vec(), ink::prelude::vec![accounts.bob, accounts.charlie])
+                .unwrap();
+            assert_eq!(
+                bill_splitting.attach_bill_to_group(bill_id, group_id),
+                Err(Error::IncompatibleSettlement)
+            );
+            
+            // Shares are pulled from the token, not attached as native value
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 500), Err(Error::InvalidAmount));
+            assert_eq!(
+                bill_splitting.register_remote_participant(bill_id, 2000),
+                Err(Error::IncompatibleSettlement)
+            );
+        }
+
+        #[ink::test]
+        fn fiat_bill_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Store bill information
-            self.bill_creators.insert(bill_id, &creator);
-            self.bill_amounts.insert(bill_id, &total_amount);
-            self.bill_participant_counts.insert(bill_id, &(participants.len() as u32));
-            self.bill_paid_counts.insert(bill_id, &0);
-            self.bill_completed.insert(bill_id, &false);
-            self.bill_deadlines.insert(bill_id, &deadline);
+            let bill_id = bill_splitting
+                .create_fiat_bill(
+                    *b"EUR",
+                    5000,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![2500, 2500],
+                    1000,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(bill_splitting.get_bill_currency(bill_id), Some(*b"EUR"));
             
-            // Store participants and amounts
-            for (index, (participant, amount)) in participants.iter().zip(individual_amounts.iter()).enumerate() {
-                let idx = index as u32;
-                self.bill_participants.insert((bill_id, idx), participant);
-                self.bill_individual_amounts.insert((bill_id, idx), amount);
-                self.bill_payments.insert((bill_id, *participant), &false);
-            }
+            // Fiat shares settle neither as native amounts nor through group tabs
+            let group_id = bill_splitting
+                .create_group(b"Trip".to_vec(), ink::prelude::vec![accounts.bob, accounts.charlie])
+                .unwrap();
+            assert_eq!(
+                bill_splitting.attach_bill_to_group(bill_id, group_id),
+                Err(Error::IncompatibleSettlement)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(2500);
+            assert_eq!(bill_splitting.pay_bill(bill_id, 2500), Err(Error::FiatDenominated));
+            assert_eq!(bill_splitting.pay_bill_in_currency(bill_id, 2500), Err(Error::NoPriceOracle));
             
-            self.bill_counter = self.bill_counter.saturating_add(1);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(bill_splitting.set_price_oracle(Some(accounts.django)), Err(Error::UnauthorizedAccess));
+            assert_eq!(bill_splitting.set_badges(Some(accounts.django)), Err(Error::UnauthorizedAccess));
+            assert_eq!(bill_splitting.get_badges(), None);
+        }
+
+        #[ink::test]
+        fn tax_and_tip_split_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Update user bill counts
-            let user_bill_count = self.user_bills.get(creator).unwrap_or(0);
-            self.user_bills.insert(creator, &user_bill_count.saturating_add(1));
+            // 10% tax on 1000 plus a 100 tip, split 600/300/100
+            assert_eq!(
+                bill_splitting.quote_tax_and_tip(ink::prelude::vec![600, 300, 100], Some(1000), Some(100)),
+                Ok(ink::prelude::vec![720, 360, 120])
+            );
             
-            for participant in &participants {
-                let user_bill_count = self.user_bills.get(participant).unwrap_or(0);
-                self.user_bills.insert(participant, &user_bill_count.saturating_add(1));
-            }
+            // Leftover rounding units go to the first participants
+            assert_eq!(
+                bill_splitting.quote_tax_and_tip(ink::prelude::vec![100, 100, 100], None, Some(100)),
+                Ok(ink::prelude::vec![134, 133, 133])
+            );
             
-            self.env().emit_event(BillCreated {
-                bill_id,
-                creator,
-                total_amount,
-                participant_count: participants.len() as u32,
-            });
+            assert_eq!(
+                bill_splitting.quote_tax_and_tip(ink::prelude::vec![100], Some(10_001), None),
+                Err(Error::InvalidAmount)
+            );
             
-            Ok(bill_id)
+            let bill_id = bill_splitting
+                .create_bill_with_tax_and_tip(
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                    Some(1000),
+                    Some(20),
+                )
+                .unwrap();
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.1, 900);
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(563));
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.charlie), Some(337));
         }
 
-        /// Pay a bill
-        #[ink(message)]
-        pub fn pay_bill(&mut self, bill_id: u32, amount: Balance) -> Result<(), Error> {
-            let payer = self.env().caller();
+        #[ink::test]
+        fn bill_templates_work() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Check if bill exists
-            if !self.bill_creators.contains(bill_id) {
-                return Err(Error::BillNotFound);
-            }
+            let template_id = bill_splitting
+                .save_template(
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![2, 1],
+                    b"Lunch".to_vec(),
+                )
+                .unwrap();
+            let template = bill_splitting.get_template(template_id).unwrap();
+            assert_eq!(template.0, accounts.alice);
+            assert_eq!(template.1, b"Lunch".to_vec());
             
-            // Check if bill is already completed
-            if self.bill_completed.get(bill_id).unwrap_or(false) {
-                return Err(Error::BillAlreadyCompleted);
-            }
+            // 1000 split 2:1 leaves one unit for the first participant
+            let bill_id = bill_splitting.create_bill_from_template(template_id, 1000, 1000).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(667));
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.charlie), Some(333));
             
-            // Check if bill has expired
-            let deadline = self.bill_deadlines.get(bill_id).unwrap_or(0);
-            if self.env().block_timestamp() > deadline {
-                return Err(Error::BillExpired);
-            }
+            // Templates belong to their owner
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.create_bill_from_template(template_id, 1000, 1000),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(bill_splitting.delete_template(template_id), Err(Error::UnauthorizedAccess));
             
-            // Check if already paid
-            if self.bill_payments.get((bill_id, payer)).unwrap_or(false) {
-                return Err(Error::AlreadyPaid);
-            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                bill_splitting.update_template(
+                    template_id,
+                    ink::prelude::vec![accounts.bob],
+                    ink::prelude::vec![1, 1],
+                    b"Lunch".to_vec(),
+                ),
+                Err(Error::InvalidParticipants)
+            );
+            assert_eq!(
+                bill_splitting.update_template(
+                    template_id,
+                    ink::prelude::vec![accounts.bob, accounts.django],
+                    ink::prelude::vec![1, 0],
+                    b"Lunch".to_vec(),
+                ),
+                Err(Error::ZeroShare)
+            );
+            bill_splitting
+                .update_template(
+                    template_id,
+                    ink::prelude::vec![accounts.bob, accounts.django],
+                    ink::prelude::vec![1, 1],
+                    b"Lunch".to_vec(),
+                )
+                .unwrap();
+            let bill_id = bill_splitting.create_bill_from_template(template_id, 600, 1000).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.django), Some(300));
             
-            // Find participant and check amount
-            let participant_count = self.bill_participant_counts.get(bill_id).unwrap_or(0);
-            let mut participant_found = false;
+            bill_splitting.delete_template(template_id).unwrap();
+            assert_eq!(bill_splitting.get_template(template_id), None);
+            assert_eq!(
+                bill_splitting.create_bill_from_template(template_id, 600, 1000),
+                Err(Error::TemplateNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn bill_validation_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            for index in 0..participant_count {
-                if let Some(participant) = self.bill_participants.get((bill_id, index)) {
-                    if participant == payer {
-                        participant_found = true;
-                        let expected_amount = self.bill_individual_amounts.get((bill_id, index)).unwrap_or(0);
-                        if amount != expected_amount {
-                            return Err(Error::InvalidAmount);
-                        }
-                        break;
-                    }
-                }
-            }
+            assert_eq!(
+                bill_splitting.create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.bob],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                ),
+                Err(Error::DuplicateParticipant)
+            );
+            assert_eq!(
+                bill_splitting.create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![800, 0],
+                    1000,
+                ),
+                Err(Error::ZeroShare)
+            );
             
-            if !participant_found {
-                return Err(Error::ParticipantNotFound);
-            }
+            // Only the owner changes the participant cap
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(bill_splitting.set_max_participants(1), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(bill_splitting.get_max_participants(), DEFAULT_MAX_PARTICIPANTS);
+            assert_eq!(bill_splitting.set_max_participants(0), Err(Error::InvalidParticipants));
+            bill_splitting.set_max_participants(2).unwrap();
             
-            // Mark as paid
-            self.bill_payments.insert((bill_id, payer), &true);
+            assert_eq!(
+                bill_splitting.create_bill(
+                    900,
+                    ink::prelude::vec![accounts.bob, accounts.charlie, accounts.django],
+                    ink::prelude::vec![300, 300, 300],
+                    1000
+                ),
+                Err(Error::TooManyParticipants)
+            );
             
-            let paid_count = self.bill_paid_counts.get(bill_id).unwrap_or(0);
-            let new_paid_count = paid_count.saturating_add(1);
-            self.bill_paid_counts.insert(bill_id, &new_paid_count);
+            let bill_id = bill_splitting
+                .create_bill(
+                    600,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![300, 300],
+                    1000,
+                )
+                .unwrap();
+            assert_eq!(
+                bill_splitting.add_participant(bill_id, accounts.django, 300),
+                Err(Error::TooManyParticipants)
+            );
+        }
+
+        #[ink::test]
+        fn forgive_share_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Check if all participants have paid
-            if new_paid_count == participant_count {
-                self.bill_completed.insert(bill_id, &true);
-                
-                let creator = self.bill_creators.get(bill_id).unwrap();
-                let total_amount = self.bill_amounts.get(bill_id).unwrap_or(0);
-                
-                self.env().emit_event(BillCompleted {
-                    bill_id,
-                    creator,
-                    total_paid: total_amount,
-                });
-            }
+            let bill_id = bill_splitting
+                .create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                )
+                .unwrap();
             
-            self.env().emit_event(BillPaid {
-                bill_id,
-                payer,
-                amount,
-            });
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.forgive_share(bill_id, accounts.charlie),
+                Err(Error::UnauthorizedAccess)
+            );
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
             
-            Ok(())
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(bill_splitting.forgive_share(bill_id, accounts.bob), Err(Error::AlreadyPaid));
+            bill_splitting.forgive_share(bill_id, accounts.charlie).unwrap();
+            
+            assert!(bill_splitting.is_share_forgiven(bill_id, accounts.charlie));
+            assert!(!bill_splitting.is_share_forgiven(bill_id, accounts.bob));
+            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
+            assert_eq!(bill_info.1, 500); // total reduced
+            assert_eq!(bill_info.4, true); // completed
         }
 
-        /// Get bill details
-        #[ink(message)]
-        pub fn get_bill_info(&self, bill_id: u32) -> Option<(AccountId, Balance, u32, u32, bool, u64)> {
-            if !self.bill_creators.contains(bill_id) {
-                return None;
-            }
+        #[ink::test]
+        fn auto_pay_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            bill_splitting.deposit().unwrap();
+            bill_splitting.set_auto_pay(accounts.alice, Some(600)).unwrap();
+            assert_eq!(bill_splitting.get_deposit(accounts.bob), 1000);
+            assert_eq!(bill_splitting.get_auto_pay_cap(accounts.bob, accounts.alice), Some(600));
+            
+            // Bob's share is paid as soon as the bill is created
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            let bill_id = bill_splitting
+                .create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    1000,
+                )
+                .unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(0));
+            assert_eq!(bill_splitting.get_deposit(accounts.bob), 500);
+            
+            // Shares above the cap are left alone
+            let bill_id = bill_splitting
+                .create_bill(700, ink::prelude::vec![accounts.bob], ink::prelude::vec![700], 1000)
+                .unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(700));
             
-            let creator = self.bill_creators.get(bill_id)?;
-            let total_amount = self.bill_amounts.get(bill_id).unwrap_or(0);
-            let participant_count = self.bill_participant_counts.get(bill_id).unwrap_or(0);
-            let paid_count = self.bill_paid_counts.get(bill_id).unwrap_or(0);
-            let completed = self.bill_completed.get(bill_id).unwrap_or(false);
-            let deadline = self.bill_deadlines.get(bill_id).unwrap_or(0);
+            let bill_id = bill_splitting.create_bill(
+                400,
+                ink::prelude::vec![accounts.charlie],
+                ink::prelude::vec![400],
+                1000,
+            ).unwrap();
+            bill_splitting.add_participant(bill_id, accounts.bob, 400).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(0));
+            
+            // Raising the cap and topping up lets auto_settle_all catch up
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.set_auto_pay(accounts.alice, Some(1000)).unwrap();
+            assert_eq!(bill_splitting.auto_settle_all(), Ok(0));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(600);
+            bill_splitting.deposit().unwrap();
+            assert_eq!(bill_splitting.auto_settle_all(), Ok(1));
+            assert_eq!(bill_splitting.get_deposit(accounts.bob), 0);
             
-            Some((creator, total_amount, participant_count, paid_count, completed, deadline))
+            assert_eq!(bill_splitting.withdraw(1), Err(Error::InsufficientDeposit));
         }
 
-        /// Get user bill count
-        #[ink(message)]
-        pub fn get_user_bill_count(&self, user: AccountId) -> u32 {
-            self.user_bills.get(user).unwrap_or(0)
+        #[ink::test]
+        fn auto_pay_skips_unapproved_creators() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            bill_splitting.deposit().unwrap();
+            bill_splitting.set_auto_pay(accounts.alice, Some(1000)).unwrap();
+            
+            // A creator Bob never approved cannot pull from his deposit
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            let bill_id = bill_splitting
+                .create_bill(500, ink::prelude::vec![accounts.bob], ink::prelude::vec![500], 1000)
+                .unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(500));
+            
+            let other = bill_splitting.create_bill(100, ink::prelude::vec![accounts.charlie], ink::prelude::vec![100], 1000).unwrap();
+            bill_splitting.add_participant(other, accounts.bob, 400).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(other, accounts.bob), Some(400));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(bill_splitting.auto_settle_all(), Ok(0));
+            assert_eq!(bill_splitting.get_deposit(accounts.bob), 1000);
+            assert_eq!(bill_splitting.get_auto_pay_cap(accounts.bob, accounts.eve), None);
+            
+            // Revoking the approval stops auto-pay for that creator too
+            bill_splitting.set_auto_pay(accounts.alice, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let bill_id = bill_splitting
+                .create_bill(500, ink::prelude::vec![accounts.bob], ink::prelude::vec![500], 1000)
+                .unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(500));
         }
 
-        /// Get contract statistics
-        #[ink(message)]
-        pub fn get_stats(&self) -> u32 {
-            self.bill_counter
+        #[ink::test]
+        fn session_keys_pay_bills() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let key = accounts.frank;
+            let first = bill_splitting.create_bill(
+                300,
+                ink::prelude::vec![accounts.bob],
+                ink::prelude::vec![300],
+                1000,
+            ).unwrap();
+            let second = bill_splitting.create_bill(
+                300,
+                ink::prelude::vec![accounts.bob],
+                ink::prelude::vec![300],
+                1000,
+            ).unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            bill_splitting.deposit().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(bill_splitting.authorize_session_key(key, 400, 0), Err(Error::InvalidSessionKey));
+            bill_splitting.authorize_session_key(key, 400, 10).unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(key);
+            assert_eq!(bill_splitting.pay_bill_via_session(first, 200), Err(Error::InvalidAmount));
+            bill_splitting.pay_bill_via_session(first, 300).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(first, accounts.bob), Some(0));
+            assert_eq!(bill_splitting.get_deposit(accounts.bob), 700);
+            
+            // The second share would go past the day's cap
+            assert_eq!(
+                bill_splitting.pay_bill_via_session(second, 300),
+                Err(Error::SessionLimitExceeded)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            bill_splitting.revoke_session_key(key).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(key);
+            assert_eq!(bill_splitting.pay_bill_via_session(second, 300), Err(Error::NoSessionKey));
         }
-    }
-
-    /// Unit tests
-    #[cfg(test)]
-    mod tests {
-        use super::*;
 
         #[ink::test]
-        fn default_works() {
-            let bill_splitting = BillSplitting::default();
-            assert_eq!(bill_splitting.get_stats(), 0);
+        fn payment_intents_work() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]), 10_000_000);
+            let bill_id = bill_splitting.create_bill(
+                300,
+                ink::prelude::vec![accounts.bob],
+                ink::prelude::vec![300],
+                1000,
+            ).unwrap();
+            let intent = PaymentIntent {
+                recipient: accounts.alice,
+                amount: 300,
+                asset: IntentAsset::Native,
+                memo: "dinner".into(),
+                expires_at: Some(500),
+                bill_id: Some(bill_id),
+            };
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(300);
+            let wrong_asset = PaymentIntent { asset: IntentAsset::Ledger(accounts.django), ..intent.clone() };
+            assert_eq!(bill_splitting.execute_intent(wrong_asset), Err(Error::IntentMismatch));
+            let too_long = PaymentIntent { memo: "x".repeat(MAX_MEMO_LEN + 1), ..intent.clone() };
+            assert_eq!(bill_splitting.execute_intent(too_long), Err(Error::MemoTooLong));
+            bill_splitting.execute_intent(intent.clone()).unwrap();
+            assert_eq!(bill_splitting.get_amount_due(bill_id, accounts.bob), Some(0));
+            assert_eq!(bill_splitting.execute_intent(intent.clone()), Err(Error::BillAlreadyCompleted));
+            
+            // A plain intent pays the recipient straight away
+            let plain = PaymentIntent { recipient: accounts.eve, bill_id: None, ..intent };
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.eve).unwrap();
+            bill_splitting.execute_intent(plain.clone()).unwrap();
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.eve),
+                Ok(before + 300)
+            );
+            
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(500);
+            assert_eq!(bill_splitting.execute_intent(plain), Err(Error::IntentExpired));
         }
 
         #[ink::test]
-        fn create_bill_works() {
+        fn reminders_work() {
             let mut bill_splitting = BillSplitting::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let deadline = 10 * MILLISECONDS_PER_DAY;
             
-            let participants = ink::prelude::vec![accounts.alice, accounts.bob];
-            let individual_amounts = ink::prelude::vec![500, 300];
-            let total_amount = 800;
+            let bill_id = bill_splitting
+                .create_bill(
+                    800,
+                    ink::prelude::vec![accounts.bob, accounts.charlie],
+                    ink::prelude::vec![500, 300],
+                    deadline,
+                )
+                .unwrap();
+            bill_splitting.set_late_fee_policy(bill_id, 10, 0).unwrap();
             
-            let bill_id = bill_splitting.create_bill(
-                total_amount,
-                participants,
-                individual_amounts,
-                1000000000, // future deadline
-            ).unwrap();
+            // Too early before the deadline
+            assert_eq!(bill_splitting.emit_reminders(bill_id), Err(Error::ReminderTooEarly));
             
-            assert_eq!(bill_id, 0);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(deadline - MILLISECONDS_PER_DAY);
+            assert_eq!(bill_splitting.emit_reminders(bill_id), Ok(2));
+            assert_eq!(bill_splitting.get_overdue(accounts.bob), 0);
             
-            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
-            assert_eq!(bill_info.1, total_amount); // total_amount
-            assert_eq!(bill_info.2, 2); // participant_count
-            assert_eq!(bill_info.4, false); // completed
+            // Past the deadline unpaid shares become overdue, counted once
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(deadline + 1);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(bill_splitting.emit_reminders(bill_id), Ok(2));
+            assert_eq!(bill_splitting.emit_reminders(bill_id), Ok(2));
+            assert_eq!(bill_splitting.get_overdue(accounts.bob), 1);
+            assert_eq!(bill_splitting.get_overdue(accounts.charlie), 1);
+            
+            // Paying clears the overdue share
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(510);
+            bill_splitting.pay_bill(bill_id, 510).unwrap();
+            assert_eq!(bill_splitting.get_overdue(accounts.bob), 0);
+            assert_eq!(bill_splitting.emit_reminders(bill_id), Ok(1));
+            
+            // Expiring drops the remaining overdue share
+            bill_splitting.expire_bill(bill_id).unwrap();
+            assert_eq!(bill_splitting.get_overdue(accounts.charlie), 0);
         }
 
         #[ink::test]
-        fn pay_bill_works() {
+        fn packed_bill_storage_works() {
+            type E = ink::env::DefaultEnvironment;
             let mut bill_splitting = BillSplitting::new();
-            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let accounts = ink::env::test::default_accounts::<E>();
+            let contract = ink::env::test::callee::<E>();
+            let storage_rw = || ink::env::test::get_contract_storage_rw::<E>(&contract);
             
+            // Measured storage accesses; the unpacked layout took 8 reads and 21
+            // writes to create a two-participant bill, 19 and 5 for the first
+            // payment, 24 and 7 for the completing payment and 8 to read the bill
+            let (reads, writes) = storage_rw();
             let participants = ink::prelude::vec![accounts.alice, accounts.bob];
-            let individual_amounts = ink::prelude::vec![500, 300];
-            let bill_id = bill_splitting.create_bill(
-                800,
-                participants,
-                individual_amounts,
-                1000000000,
-            ).unwrap();
+            let bill_id = bill_splitting
+                .create_bill(800, participants, ink::prelude::vec![500, 300], 1000000000)
+                .unwrap();
+            let (create_reads, create_writes) = storage_rw();
+            assert_eq!((create_reads - reads, create_writes - writes), (7, 13));
             
-            // Alice pays her share
+            ink::env::test::set_value_transferred::<E>(500);
             bill_splitting.pay_bill(bill_id, 500).unwrap();
+            let (pay_reads, pay_writes) = storage_rw();
+            assert_eq!((pay_reads - create_reads, pay_writes - create_writes), (12, 5));
             
-            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
-            assert_eq!(bill_info.3, 1); // paid_count
-            assert_eq!(bill_info.4, false); // completed
+            ink::env::test::set_caller::<E>(accounts.bob);
+            ink::env::test::set_value_transferred::<E>(300);
+            bill_splitting.pay_bill(bill_id, 300).unwrap();
+            let (complete_reads, complete_writes) = storage_rw();
+            assert_eq!((complete_reads - pay_reads, complete_writes - pay_writes), (16, 5));
             
-            // Switch to Bob and pay his share
+            bill_splitting.get_bill_info(bill_id).unwrap();
+            let (info_reads, _) = storage_rw();
+            assert_eq!(info_reads - complete_reads, 2);
+        }
+
+        #[ink::test]
+        fn reputation_works() {
+            let mut bill_splitting = BillSplitting::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(bill_splitting.get_reputation(accounts.bob), None);
+            
+            let participants = ink::prelude::vec![accounts.bob, accounts.charlie];
+            let first = bill_splitting
+                .propose_bill(200, participants.clone(), ink::prelude::vec![100, 100], 1000, false)
+                .unwrap();
+            for participant in [accounts.bob, accounts.charlie] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(participant);
+                bill_splitting.accept_bill(first).unwrap();
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let second = bill_splitting
+                .create_bill(200, participants, ink::prelude::vec![100, 100], 2000)
+                .unwrap();
+            bill_splitting.set_late_fee_policy(second, 10, 0).unwrap();
+            
+            // Bob pays the first bill on time
             ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
-            bill_splitting.pay_bill(bill_id, 300).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            bill_splitting.pay_bill(first, 100).unwrap();
+            assert_eq!(bill_splitting.get_reputation(accounts.bob), Some(10_000));
             
-            let bill_info = bill_splitting.get_bill_info(bill_id).unwrap();
-            assert_eq!(bill_info.4, true); // completed
-            assert_eq!(bill_info.3, 2); // paid_count
+            // Both deadlines pass; Bob pays the second bill late
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(3000);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(110);
+            bill_splitting.pay_bill(second, 110).unwrap();
+            assert_eq!(
+                bill_splitting.get_payment_record(accounts.bob),
+                PaymentRecord { on_time: 1, late: 1, defaulted: 0 },
+            );
+            assert_eq!(bill_splitting.get_reputation(accounts.bob), Some(7_500));
+            
+            // Charlie leaves both unpaid, but only accepted the first
+            bill_splitting.expire_bill(first).unwrap();
+            bill_splitting.expire_bill(second).unwrap();
+            assert_eq!(bill_splitting.get_payment_record(accounts.charlie).defaulted, 1);
+            assert_eq!(bill_splitting.get_reputation(accounts.charlie), Some(0));
+            assert_eq!(bill_splitting.get_reputation(accounts.bob), Some(7_500));
         }
 
         #[ink::test]
-        fn invalid_bill_creation_fails() {
+        fn payout_routing_works() {
             let mut bill_splitting = BillSplitting::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let participants = ink::prelude::vec![accounts.bob, accounts.charlie];
+            let balance_of = |account| {
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(account).unwrap()
+            };
             
-            // Mismatched participants and amounts should fail
-            let result = bill_splitting.create_bill(
-                800,
-                ink::prelude::vec![accounts.alice, accounts.bob],
-                ink::prelude::vec![800], // Only one amount for two participants
-                1000000000,
+            // Splits must cover exactly the whole bill
+            assert_eq!(
+                bill_splitting.create_bill_with_payouts(
+                    1001,
+                    participants.clone(),
+                    ink::prelude::vec![500, 501],
+                    1000000000,
+                    ink::prelude::vec![(accounts.django, 9_000), (accounts.eve, 900)],
+                ),
+                Err(Error::InvalidPayouts)
             );
-            assert_eq!(result, Err(Error::InvalidParticipants));
             
-            // Sum mismatch should fail
-            let result = bill_splitting.create_bill(
-                800,
-                ink::prelude::vec![accounts.alice, accounts.bob],
-                ink::prelude::vec![400, 300], // Sum is 700, not 800
-                1000000000,
+            let bill_id = bill_splitting
+                .create_bill_with_payouts(
+                    1001,
+                    participants,
+                    ink::prelude::vec![500, 501],
+                    1000000000,
+                    ink::prelude::vec![(accounts.django, 9_000), (accounts.eve, 1_000)],
+                )
+                .unwrap();
+            assert_eq!(bill_splitting.get_bill_payouts(bill_id).len(), 2);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                bill_splitting.register_remote_participant(bill_id, 2000),
+                Err(Error::IncompatibleSettlement)
             );
-            assert_eq!(result, Err(Error::InvalidAmount));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            bill_splitting.pay_bill(bill_id, 500).unwrap();
+            
+            let django_before = balance_of(accounts.django);
+            let eve_before = balance_of(accounts.eve);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(501);
+            bill_splitting.pay_bill(bill_id, 501).unwrap();
+            assert!(bill_splitting.get_bill_info(bill_id).unwrap().4);
+            
+            // 90% rounds down to 900, the leftover unit goes to the first payee
+            let django_after = balance_of(accounts.django);
+            let eve_after = balance_of(accounts.eve);
+            assert_eq!(django_after - django_before, 901);
+            assert_eq!(eve_after - eve_before, 100);
+        }
+    }
+
+    /// Property tests of bill and escrow accounting over random sequences of calls
+    #[cfg(all(test, feature = "std"))]
+    mod invariants {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use proptest::prelude::*;
+
+        /// Native balance of every actor at the start
+        const FUNDS: Balance = 10_000_000;
+        /// Existential deposit of the off-chain environment, which the contract keeps
+        const EXISTENTIAL_DEPOSIT: Balance = 1_000_000;
+        const DEADLINE: u64 = 1_000_000;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Create { creator: usize, shares: Vec<(usize, Balance)> },
+            Pay { payer: usize, bill: u32, exact: bool },
+            Deposit { account: usize, amount: Balance },
+            Withdraw { account: usize, amount: Balance },
+            SetAutoPay { account: usize, creator: usize, cap: Option<Balance> },
+            AutoSettle { account: usize },
+            Cancel { caller: usize, bill: u32 },
+            SettleOffline { caller: usize, bill: u32, participant: usize },
+            Forgive { caller: usize, bill: u32, participant: usize },
+        }
+
+        fn actors() -> [AccountId; 5] {
+            let accounts = test::default_accounts::<DefaultEnvironment>();
+            [accounts.alice, accounts.bob, accounts.charlie, accounts.django, accounts.eve]
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            let actor = || 0..5usize;
+            let bill = || 0..8u32;
+            let amount = || 1..2_000u128;
+            prop_oneof![
+                2 => (actor(), prop::collection::vec((actor(), amount()), 1..4))
+                    .prop_map(|(creator, shares)| Op::Create { creator, shares }),
+                4 => (actor(), bill(), prop::bool::weighted(0.9))
+                    .prop_map(|(payer, bill, exact)| Op::Pay { payer, bill, exact }),
+                1 => (actor(), amount()).prop_map(|(account, amount)| Op::Deposit { account, amount }),
+                1 => (actor(), amount()).prop_map(|(account, amount)| Op::Withdraw { account, amount }),
+                1 => (actor(), actor(), prop::option::of(amount()))
+                    .prop_map(|(account, creator, cap)| Op::SetAutoPay { account, creator, cap }),
+                1 => actor().prop_map(|account| Op::AutoSettle { account }),
+                1 => (actor(), bill()).prop_map(|(caller, bill)| Op::Cancel { caller, bill }),
+                1 => (actor(), bill(), actor())
+                    .prop_map(|(caller, bill, participant)| Op::SettleOffline { caller, bill, participant }),
+                1 => (actor(), bill(), actor())
+                    .prop_map(|(caller, bill, participant)| Op::Forgive { caller, bill, participant }),
+            ]
+        }
+
+        fn balance(account: AccountId) -> Balance {
+            test::get_account_balance::<DefaultEnvironment>(account).unwrap_or(0)
+        }
+
+        /// Call a payable message, moving `value` into the contract as the chain would
+        ///
+        /// The transfer is undone when the message fails, as its revert would.
+        fn call_with_value<T>(caller: AccountId, value: Balance, message: impl FnOnce() -> Result<T, Error>) {
+            let contract = test::callee::<DefaultEnvironment>();
+            if balance(caller) < value {
+                return;
+            }
+            test::set_account_balance::<DefaultEnvironment>(caller, balance(caller) - value);
+            test::set_account_balance::<DefaultEnvironment>(contract, balance(contract) + value);
+            test::set_caller::<DefaultEnvironment>(caller);
+            test::set_value_transferred::<DefaultEnvironment>(value);
+
+            if message().is_err() {
+                test::set_account_balance::<DefaultEnvironment>(contract, balance(contract) - value);
+                test::set_account_balance::<DefaultEnvironment>(caller, balance(caller) + value);
+            }
+            test::set_value_transferred::<DefaultEnvironment>(0);
+        }
+
+        fn check_invariants(bill_splitting: &BillSplitting, actors: &[AccountId]) {
+            let mut escrowed: Balance = 0;
+            for bill_id in 0..bill_splitting.bill_counter {
+                let bill = bill_splitting.load_bill(bill_id).unwrap();
+                let participants = bill_splitting.get_bill_participants(bill_id).unwrap();
+                let settled = participants
+                    .iter()
+                    .filter(|participant| bill_splitting.bill_payments.get((bill_id, **participant)).unwrap_or(false))
+                    .count();
+
+                assert!(bill.paid_count <= bill.participant_count, "bill {bill_id} overpaid");
+                assert_eq!(bill.paid_count as usize, settled, "bill {bill_id} miscounted");
+                assert_eq!(bill.completed, bill.paid_count == bill.participant_count);
+                if bill.completed || bill.cancelled {
+                    assert_eq!(bill.escrowed, 0, "bill {bill_id} kept escrow after closing");
+                } else {
+                    let contributed: Balance = participants
+                        .iter()
+                        .map(|participant| bill_splitting.bill_contributions.get((bill_id, *participant)).unwrap_or(0))
+                        .sum();
+                    assert_eq!(bill.escrowed, contributed, "bill {bill_id} escrow differs from its contributions");
+                    escrowed += bill.escrowed;
+                }
+            }
+
+            // The contract holds exactly the open escrow and the auto-pay deposits
+            let deposits: Balance = actors.iter().map(|account| bill_splitting.get_deposit(*account)).sum();
+            let contract = test::callee::<DefaultEnvironment>();
+            assert_eq!(balance(contract), EXISTENTIAL_DEPOSIT + escrowed + deposits, "escrow not fully backed");
+
+            // Native funds only move between the actors and the contract
+            let total: Balance = actors.iter().map(|account| balance(*account)).sum();
+            assert_eq!(total + balance(contract), FUNDS * actors.len() as Balance + EXISTENTIAL_DEPOSIT);
+        }
+
+        proptest! {
+            /// Shares are settled at most once each, escrow matches the contributions
+            /// of open bills and the contract's balance backs all escrow and deposits
+            #[test]
+            fn escrow_is_fully_backed(ops in prop::collection::vec(op(), 1..60)) {
+                test::run_test::<DefaultEnvironment, _>(|_| {
+                    let actors = actors();
+                    for account in actors {
+                        test::set_account_balance::<DefaultEnvironment>(account, FUNDS);
+                    }
+                    let contract = AccountId::from([0xc0; 32]);
+                    test::set_callee::<DefaultEnvironment>(contract);
+                    test::set_account_balance::<DefaultEnvironment>(contract, EXISTENTIAL_DEPOSIT);
+                    let mut bill_splitting = BillSplitting::new();
+
+                    for op in ops {
+                        // Bills are picked among the existing ones
+                        let bill_id = |bill: u32| bill % bill_splitting.bill_counter.max(1);
+                        match op {
+                            Op::Create { creator, ref shares } => {
+                                test::set_caller::<DefaultEnvironment>(actors[creator]);
+                                let participants = shares.iter().map(|(participant, _)| actors[*participant]).collect();
+                                let amounts: Vec<Balance> = shares.iter().map(|(_, amount)| *amount).collect();
+                                let total = amounts.iter().sum();
+                                let _ = bill_splitting.create_bill(total, participants, amounts, DEADLINE);
+                            }
+                            Op::Pay { payer, bill, exact } => {
+                                let bill_id = bill_id(bill);
+                                let due = bill_splitting.get_amount_due(bill_id, actors[payer]).unwrap_or(1);
+                                let amount = if exact { due } else { due + 1 };
+                                call_with_value(actors[payer], amount, || bill_splitting.pay_bill(bill_id, amount));
+                            }
+                            Op::Deposit { account, amount } => {
+                                call_with_value(actors[account], amount, || bill_splitting.deposit());
+                            }
+                            Op::Withdraw { account, amount } => {
+                                test::set_caller::<DefaultEnvironment>(actors[account]);
+                                let _ = bill_splitting.withdraw(amount);
+                            }
+                            Op::SetAutoPay { account, creator, cap } => {
+                                test::set_caller::<DefaultEnvironment>(actors[account]);
+                                bill_splitting.set_auto_pay(actors[creator], cap).unwrap();
+                            }
+                            Op::AutoSettle { account } => {
+                                test::set_caller::<DefaultEnvironment>(actors[account]);
+                                let _ = bill_splitting.auto_settle_all();
+                            }
+                            Op::Cancel { caller, bill } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = bill_splitting.cancel_bill(bill_id(bill));
+                            }
+                            Op::SettleOffline { caller, bill, participant } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = bill_splitting.mark_settled_offline(bill_id(bill), actors[participant]);
+                            }
+                            Op::Forgive { caller, bill, participant } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = bill_splitting.forgive_share(bill_id(bill), actors[participant]);
+                            }
+                        }
+
+                        check_invariants(&bill_splitting, &actors);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            }
         }
     }
-}
\ No newline at end of file
+}
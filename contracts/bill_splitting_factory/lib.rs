@@ -0,0 +1,279 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::bill_splitting_factory::{BillSplittingFactoryRef, Error};
+
+#[ink::contract]
+mod bill_splitting_factory {
+    use bill_splitting::BillSplittingRef;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::MAX_PAGE_SIZE;
+
+    /// Maximum number of instances a single account can deploy
+    pub const MAX_INSTANCES_PER_CREATOR: usize = 32;
+
+    /// Layout version of the storage written by this code
+    pub const STORAGE_VERSION: u32 = 1;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        TooManyInstances,
+        InstantiationFailed,
+        UpgradeFailed,
+        StorageVersionMismatch,
+        AlreadyMigrated,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Deploys isolated BillSplitting instances for groups
+    ///
+    /// Every instance is administered by the account named at deployment, not
+    /// by the factory, so groups stay in full control of their own instance.
+    #[ink(storage)]
+    pub struct BillSplittingFactory {
+        /// Code hash new instances are instantiated from
+        bill_splitting_code_hash: Hash,
+        /// Instances deployed by each creator, in deployment order
+        creator_instances: Mapping<AccountId, Vec<AccountId>>,
+        /// All deployed instances by index
+        instances: Mapping<u32, AccountId>,
+        instance_counter: u32,
+        owner: AccountId,
+        /// Layout version of the stored data, behind `STORAGE_VERSION` until migrated
+        version: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct InstanceDeployed {
+        #[ink(topic)]
+        creator: AccountId,
+        #[ink(topic)]
+        instance: AccountId,
+        #[ink(topic)]
+        admin: AccountId,
+        index: u32,
+    }
+
+    #[ink(event)]
+    pub struct CodeHashUpdated {
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct StorageMigrated {
+        from: u32,
+        to: u32,
+    }
+
+    impl BillSplittingFactory {
+        /// Constructor taking the code hash of the uploaded BillSplitting contract
+        #[ink(constructor)]
+        pub fn new(bill_splitting_code_hash: Hash) -> Self {
+            Self {
+                bill_splitting_code_hash,
+                creator_instances: Mapping::default(),
+                instances: Mapping::default(),
+                instance_counter: 0,
+                owner: Self::env().caller(),
+                version: STORAGE_VERSION,
+            }
+        }
+
+        /// Deploy a BillSplitting instance administered by `admin`, or the caller
+        ///
+        /// Returns the address of the new instance.
+        #[ink(message)]
+        pub fn deploy(&mut self, admin: Option<AccountId>) -> Result<AccountId, Error> {
+            let creator = self.env().caller();
+            let admin = admin.unwrap_or(creator);
+
+            let mut created = self.creator_instances.get(creator).unwrap_or_default();
+            if created.len() >= MAX_INSTANCES_PER_CREATOR {
+                return Err(Error::TooManyInstances);
+            }
+
+            let index = self.instance_counter;
+            let contract = BillSplittingRef::with_owner(admin)
+                .code_hash(self.bill_splitting_code_hash)
+                .endowment(0)
+                .salt_bytes(index.to_le_bytes())
+                .try_instantiate()
+                .map_err(|_| Error::InstantiationFailed)?
+                .map_err(|_| Error::InstantiationFailed)?;
+            let instance = ink::ToAccountId::to_account_id(&contract);
+
+            created.push(instance);
+            self.creator_instances.insert(creator, &created);
+            self.instances.insert(index, &instance);
+            self.instance_counter = index.saturating_add(1);
+
+            self.env().emit_event(InstanceDeployed {
+                creator,
+                instance,
+                admin,
+                index,
+            });
+
+            Ok(instance)
+        }
+
+        /// Point new deployments at an upgraded BillSplitting code hash (owner only)
+        ///
+        /// Instances that are already deployed keep running their code.
+        #[ink(message)]
+        pub fn set_code_hash(&mut self, code_hash: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.bill_splitting_code_hash = code_hash;
+
+            self.env().emit_event(CodeHashUpdated { code_hash });
+
+            Ok(())
+        }
+
+        /// Get the code hash new instances are instantiated from
+        #[ink(message)]
+        pub fn get_code_hash(&self) -> Hash {
+            self.bill_splitting_code_hash
+        }
+
+        /// Get the instances deployed by a creator
+        #[ink(message)]
+        pub fn get_instances_of(&self, creator: AccountId) -> Vec<AccountId> {
+            self.creator_instances.get(creator).unwrap_or_default()
+        }
+
+        /// Get a page of all deployed instances, in deployment order
+        #[ink(message)]
+        pub fn get_instances(&self, offset: u32, limit: u32) -> Vec<AccountId> {
+            let end = offset
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.instance_counter);
+            (offset..end).filter_map(|index| self.instances.get(index)).collect()
+        }
+
+        /// Get the number of deployed instances
+        #[ink(message)]
+        pub fn get_instance_count(&self) -> u32 {
+            self.instance_counter
+        }
+
+        /// Get the owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Get the layout version of the stored data
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.version
+        }
+
+        /// Replace the contract's code, keeping its storage and address (owner only)
+        ///
+        /// Code changing the storage layout must be followed by `migrate_from`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+
+            self.env().emit_event(CodeUpgraded { code_hash });
+
+            Ok(())
+        }
+
+        /// Migrate storage written at version `from` to `STORAGE_VERSION` (owner only)
+        ///
+        /// `from` must match the stored version, so a migration runs only once.
+        /// Layout changes bump `STORAGE_VERSION` and add their step here.
+        #[ink(message)]
+        pub fn migrate_from(&mut self, from: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if from != self.version {
+                return Err(Error::StorageVersionMismatch);
+            }
+            if from >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+
+            self.version = STORAGE_VERSION;
+
+            self.env().emit_event(StorageMigrated {
+                from,
+                to: STORAGE_VERSION,
+            });
+
+            Ok(())
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn new_works() {
+            let factory = BillSplittingFactory::new(Hash::from([1; 32]));
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            assert_eq!(factory.get_code_hash(), Hash::from([1; 32]));
+            assert_eq!(factory.get_instance_count(), 0);
+            assert!(factory.get_instances_of(accounts.alice).is_empty());
+            assert!(factory.get_instances(0, 10).is_empty());
+        }
+
+        #[ink::test]
+        fn set_code_hash_works() {
+            let mut factory = BillSplittingFactory::new(Hash::from([1; 32]));
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            factory.set_code_hash(Hash::from([2; 32])).unwrap();
+            assert_eq!(factory.get_code_hash(), Hash::from([2; 32]));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                factory.set_code_hash(Hash::from([3; 32])),
+                Err(Error::UnauthorizedAccess)
+            );
+        }
+
+        #[ink::test]
+        fn upgrade_and_migration_are_owner_only() {
+            let mut contract = BillSplittingFactory::new(Hash::from([1; 32]));
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate_from(STORAGE_VERSION), Err(Error::AlreadyMigrated));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.upgrade(Hash::from([1; 32])), Err(Error::UnauthorizedAccess));
+        }
+    }
+}
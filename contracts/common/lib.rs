@@ -0,0 +1,492 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Types, constants and interfaces shared by the 0xCC contracts
+
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// Identifier of a cross-chain payment
+pub type PaymentId = u32;
+
+/// Identifier of a bill
+pub type BillId = u32;
+
+/// Parachain id of a chain
+pub type ChainId = u32;
+
+/// Identifier of an asset; `NATIVE_ASSET` is the chain's native token
+pub type AssetId = u32;
+
+/// Balance of an account, as on the default ink! environment
+pub type Balance = <ink::env::DefaultEnvironment as ink::env::Environment>::Balance;
+
+/// ISO 4217 code of a fiat currency, like `*b"EUR"`
+pub type Currency = [u8; 3];
+
+/// Asset id of the native token
+pub const NATIVE_ASSET: AssetId = 0;
+
+/// Basis points denominator
+pub const BASIS_POINTS: u128 = 10_000;
+
+/// Maximum number of entries returned by a single paginated query
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Longest memo a payment intent may carry, in bytes
+pub const MAX_MEMO_LEN: usize = 64;
+
+/// Milliseconds in a day
+pub const MILLISECONDS_PER_DAY: u64 = 86_400_000;
+
+/// Scale of oracle prices, which give an asset's smallest units per fiat minor unit
+pub const PRICE_PRECISION: u128 = 1_000_000_000;
+
+/// XCM message types for cross-chain payments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum XcmMessageType {
+    Payment,
+    BillSplitting,
+    TokenTransfer,
+    /// Returns an executed payment to its sender
+    Refund,
+}
+
+impl XcmMessageType {
+    /// Compact code of the message type, as stored by the contracts
+    pub fn code(self) -> u8 {
+        match self {
+            XcmMessageType::Payment => 0,
+            XcmMessageType::BillSplitting => 1,
+            XcmMessageType::TokenTransfer => 2,
+            XcmMessageType::Refund => 3,
+        }
+    }
+
+    /// The message type with the given code
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(XcmMessageType::Payment),
+            1 => Some(XcmMessageType::BillSplitting),
+            2 => Some(XcmMessageType::TokenTransfer),
+            3 => Some(XcmMessageType::Refund),
+            _ => None,
+        }
+    }
+}
+
+/// Contracts whose canonical addresses are kept in the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum Component {
+    Payment,
+    BillSplitting,
+    XcmHandler,
+}
+
+/// Account activity counted towards reliability milestones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum Activity {
+    /// Sent a payment on a Payment ledger
+    PaymentSent,
+    /// Paid a bill share by its deadline
+    BillPaidOnTime,
+}
+
+/// Errors shared across contract boundaries
+///
+/// Each contract converts its own errors into these, so callers of another
+/// contract can handle the common cases without knowing its error enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum Error {
+    UnauthorizedAccess,
+    InvalidAmount,
+    InsufficientBalance,
+    InvalidChain,
+    NotFound,
+    TransferFailed,
+    /// A contract-specific error, by its index in that contract's error enum
+    Other(u8),
+}
+
+/// A cross-chain payment as seen by the contracts it concerns
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct PaymentSummary {
+    pub sender: AccountId,
+    pub recipient: AccountId,
+    pub amount: Balance,
+    pub message_type: XcmMessageType,
+    /// Whether the payment was executed on its destination chain
+    pub executed: bool,
+    /// Whether the payment failed or was returned to the sender, so it can never execute
+    pub aborted: bool,
+    /// Block timestamp the payment was created at
+    pub created_at: u64,
+}
+
+/// A page of contract state, as returned by the `export_*` messages
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct StateChunk {
+    /// SCALE encoding of the page as a `Vec` of the entry type its message documents
+    pub data: Vec<u8>,
+    /// Number of entries in `data`
+    pub entries: u32,
+    /// Offset to continue from, `None` on the last page
+    pub next_offset: Option<u32>,
+}
+
+impl StateChunk {
+    /// A chunk holding `entries`
+    pub fn new<T: ink::scale::Encode>(entries: &[T], next_offset: Option<u32>) -> Self {
+        Self {
+            data: ink::scale::Encode::encode(entries),
+            entries: entries.len() as u32,
+            next_offset,
+        }
+    }
+}
+
+/// Due maintenance jobs, with the job id to continue scanning from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct JobPage {
+    pub jobs: Vec<u32>,
+    pub next_offset: Option<u32>,
+}
+
+/// Bond keepers are paid bounties from for one job, pooled from any number of funders
+///
+/// Funders hold shares of what is left, so bounties are drawn from everyone
+/// in proportion to what they put in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct KeeperBond {
+    /// Left to pay bounties from
+    pub amount: Balance,
+    /// Shares outstanding among the funders
+    pub shares: Balance,
+    /// Bumped when bounties use up the bond, voiding the shares held until then
+    pub round: u32,
+}
+
+/// A funder's shares of a keeper bond
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct KeeperStake {
+    pub shares: Balance,
+    /// Round of the bond the shares were minted in
+    pub round: u32,
+}
+
+impl KeeperBond {
+    /// Add `amount` to the bond, returning `stake` with the shares minted for it
+    pub fn post(&mut self, stake: KeeperStake, amount: Balance) -> KeeperStake {
+        let held = if stake.round == self.round { stake.shares } else { 0 };
+        let minted = if self.shares == 0 {
+            amount
+        } else {
+            amount.saturating_mul(self.shares) / self.amount
+        };
+        self.amount = self.amount.saturating_add(amount);
+        self.shares = self.shares.saturating_add(minted);
+        KeeperStake {
+            shares: held.saturating_add(minted),
+            round: self.round,
+        }
+    }
+
+    /// What `stake` is currently worth
+    pub fn value_of(&self, stake: KeeperStake) -> Balance {
+        if stake.round != self.round || self.shares == 0 {
+            return 0;
+        }
+        self.amount.saturating_mul(stake.shares) / self.shares
+    }
+
+    /// Take `stake` out of the bond, returning what it was worth
+    pub fn reclaim(&mut self, stake: KeeperStake) -> Balance {
+        let value = self.value_of(stake);
+        if stake.round == self.round {
+            self.amount = self.amount.saturating_sub(value);
+            self.shares = self.shares.saturating_sub(stake.shares);
+        }
+        value
+    }
+
+    /// Take up to `amount` out of the bond for a bounty, returning what was taken
+    pub fn draw(&mut self, amount: Balance) -> Balance {
+        let amount = amount.min(self.amount);
+        self.amount = self.amount.saturating_sub(amount);
+        if self.amount == 0 {
+            self.shares = 0;
+            self.round = self.round.wrapping_add(1);
+        }
+        amount
+    }
+}
+
+/// Asset a payment intent is paid in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum IntentAsset {
+    Native,
+    /// Balance on a Payment ledger
+    Ledger(AccountId),
+    /// PSP22 token
+    Psp22(AccountId),
+}
+
+/// A request to pay, as shown in QR codes and deeplinks
+///
+/// The `xcc-intent` crate defines its text encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct PaymentIntent {
+    pub recipient: AccountId,
+    #[codec(compact)]
+    pub amount: Balance,
+    pub asset: IntentAsset,
+    /// Free text for the payer, at most `MAX_MEMO_LEN` bytes
+    pub memo: String,
+    /// Time from which the intent can no longer be executed, if it expires
+    pub expires_at: Option<u64>,
+    /// Bill whose share of the payer the intent settles, instead of a plain payment
+    pub bill_id: Option<BillId>,
+}
+
+/// A secondary key an account lets spend on its behalf, up to a daily cap until a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct SessionKey {
+    /// Account the key spends for
+    pub account: AccountId,
+    /// Most the key may spend per day
+    pub daily_cap: Balance,
+    /// Block from which the key can no longer be used
+    pub expires_at: u32,
+    /// Day of the last spend, counted from the Unix epoch
+    pub day: u64,
+    /// Spent on `day`
+    pub spent: Balance,
+}
+
+impl SessionKey {
+    /// A fresh key of `account`, with nothing spent yet
+    pub fn new(account: AccountId, daily_cap: Balance, expires_at: u32) -> Self {
+        Self {
+            account,
+            daily_cap,
+            expires_at,
+            day: 0,
+            spent: 0,
+        }
+    }
+
+    /// Count `amount` against the day's cap at block `block` and time `now`
+    ///
+    /// Returns false, counting nothing, if the key has expired or the cap
+    /// would be exceeded.
+    pub fn spend(&mut self, block: u32, now: u64, amount: Balance) -> bool {
+        if block >= self.expires_at {
+            return false;
+        }
+        let day = now / MILLISECONDS_PER_DAY;
+        let spent = if day == self.day { self.spent } else { 0 };
+        let Some(spent) = spent.checked_add(amount).filter(|spent| *spent <= self.daily_cap) else {
+            return false;
+        };
+        self.day = day;
+        self.spent = spent;
+        true
+    }
+}
+
+/// Interface of a ledger that moves balances between accounts
+#[ink::trait_definition]
+pub trait PaymentLedger {
+    /// Balance of an account on the ledger
+    #[ink(message)]
+    fn balance_of(&self, account: AccountId) -> Balance;
+
+    /// Transfer from the caller's balance
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, amount: Balance) -> Result<(), Error>;
+
+    /// Transfer from another account's balance within the caller's allowance
+    #[ink(message)]
+    fn transfer_from(&mut self, from: AccountId, to: AccountId, amount: Balance) -> Result<(), Error>;
+
+    /// Let `spender` transfer up to `amount` from the caller's balance
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, amount: Balance) -> Result<(), Error>;
+}
+
+/// Interface of a contract that settles bills
+#[ink::trait_definition]
+pub trait BillManager {
+    /// Settle whatever is linked to an executed cross-chain payment
+    #[ink(message)]
+    fn on_payment_executed(&mut self, payment_id: PaymentId) -> Result<(), Error>;
+}
+
+/// Interface of a contract with maintenance jobs anyone may run, for the keeper registry
+#[ink::trait_definition]
+pub trait Maintainable {
+    /// Jobs due among the `limit` job ids from `offset`
+    #[ink(message)]
+    fn due_jobs(&self, offset: u32, limit: u32) -> JobPage;
+
+    /// Run a due job
+    #[ink(message)]
+    fn run_job(&mut self, job_id: u32) -> Result<(), Error>;
+
+    /// Pay a keeper up to `amount` from the bond posted for `job_id`, returning what was paid (keeper registry only)
+    ///
+    /// Jobs without a bond pay nothing, so creating jobs cannot drain the contract.
+    #[ink(message)]
+    fn pay_keeper_bounty(&mut self, keeper: AccountId, job_id: u32, amount: Balance) -> Result<Balance, Error>;
+}
+
+/// Interface of the treasury collecting the fees of the suite's contracts
+#[ink::trait_definition]
+pub trait FeeCollector {
+    /// Book `amount` of fees paid to the treasury under the calling contract's source
+    ///
+    /// Native fees are the transferred value, which must match `amount`;
+    /// ledger fees are pulled from the caller, which must have approved the
+    /// treasury for them.
+    #[ink(message, payable)]
+    fn collect_fees(&mut self, asset: IntentAsset, amount: Balance) -> Result<Balance, Error>;
+}
+
+/// Interface of a contract that sends payments over XCM
+#[ink::trait_definition]
+pub trait XcmGateway {
+    /// Summary of a payment, if it exists
+    #[ink(message)]
+    fn payment_summary(&self, payment_id: PaymentId) -> Option<PaymentSummary>;
+
+    /// Fees charged on top of a native payment of `amount` to a chain
+    #[ink(message)]
+    fn quote_fee(&self, destination_chain: u32, amount: Balance) -> Balance;
+
+    /// Send a native payment from the caller, funded by the transferred value
+    ///
+    /// The value must cover the amount and the quoted fees.
+    #[ink(message, payable)]
+    fn send_payment(&mut self, recipient: AccountId, amount: Balance, destination_chain: u32) -> Result<PaymentId, Error>;
+}
+
+/// Interface of a registry of the canonical contract addresses
+#[ink::trait_definition]
+pub trait ComponentRegistry {
+    /// Address of the latest registered version of a component
+    #[ink(message)]
+    fn resolve(&self, component: Component) -> Option<AccountId>;
+}
+
+/// Interface of a registry of human-readable handles
+#[ink::trait_definition]
+pub trait HandleResolver {
+    /// Account currently holding a handle
+    #[ink(message)]
+    fn resolve_handle(&self, handle: String) -> Option<AccountId>;
+}
+
+/// Interface of a contract restricting whom an account may pay
+#[ink::trait_definition]
+pub trait RecipientGuard {
+    /// Whether `from` allows paying `amount` to `to`
+    #[ink(message)]
+    fn may_pay(&self, from: AccountId, to: AccountId, amount: Balance) -> bool;
+}
+
+/// Interface of a contract notified of payments settled on a ledger
+#[ink::trait_definition]
+pub trait PaymentObserver {
+    /// Record a settled payment of `amount` from `from` to `to`, as the ledger
+    #[ink(message)]
+    fn on_payment_settled(&mut self, from: AccountId, to: AccountId, amount: Balance) -> Result<(), Error>;
+}
+
+/// Interface of a contract counting account activity reported by other contracts
+#[ink::trait_definition]
+pub trait ActivityRecorder {
+    /// Count one `activity` of `account`, as a contract trusted to report it
+    #[ink(message)]
+    fn record_activity(&mut self, account: AccountId, activity: Activity) -> Result<(), Error>;
+}
+
+/// Interface of a price oracle for fiat-denominated amounts
+#[ink::trait_definition]
+pub trait PriceOracle {
+    /// Amount of `asset` worth `fiat_amount` minor units of `currency`, if a fresh price is known
+    ///
+    /// `asset` is the token contract, or `None` for the native token.
+    #[ink(message)]
+    fn convert(&self, asset: Option<AccountId>, currency: Currency, fiat_amount: Balance) -> Option<Balance>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_type_codes_round_trip() {
+        for message_type in [
+            XcmMessageType::Payment,
+            XcmMessageType::BillSplitting,
+            XcmMessageType::TokenTransfer,
+            XcmMessageType::Refund,
+        ] {
+            assert_eq!(XcmMessageType::from_code(message_type.code()), Some(message_type));
+        }
+        assert_eq!(XcmMessageType::from_code(4), None);
+    }
+
+    #[test]
+    fn session_key_cap_resets_daily() {
+        let mut key = SessionKey::new(AccountId::from([1; 32]), 100, 50);
+        assert!(key.spend(1, 0, 60));
+        assert!(!key.spend(1, 1_000, 41));
+        assert!(key.spend(1, 1_000, 40));
+
+        // The next day starts from nothing spent, until the key expires
+        assert!(key.spend(2, MILLISECONDS_PER_DAY, 100));
+        assert!(!key.spend(50, 2 * MILLISECONDS_PER_DAY, 1));
+    }
+
+    #[test]
+    fn keeper_bond_shares_bounties() {
+        let mut bond = KeeperBond::default();
+        let first = bond.post(KeeperStake::default(), 30);
+        let second = bond.post(KeeperStake::default(), 10);
+        assert_eq!(bond.draw(20), 20);
+
+        // Funders joining later only share in later bounties
+        let third = bond.post(KeeperStake::default(), 20);
+        assert_eq!(
+            (bond.value_of(first), bond.value_of(second), bond.value_of(third)),
+            (15, 5, 20)
+        );
+        assert_eq!(bond.reclaim(first), 15);
+
+        // Using up the bond voids the shares held until then
+        assert_eq!(bond.draw(100), 25);
+        let second = bond.post(second, 10);
+        assert_eq!(bond.value_of(second), 10);
+        assert_eq!(bond.reclaim(third), 0);
+    }
+}
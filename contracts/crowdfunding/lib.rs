@@ -0,0 +1,525 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::crowdfunding::{Campaign, CrowdfundingRef, Error, Tier};
+
+#[ink::contract]
+mod crowdfunding {
+    use ink::codegen::TraitCallBuilder;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::PaymentLedger;
+
+    /// Maximum number of reward tiers per campaign
+    pub const MAX_TIERS: usize = 8;
+
+    /// Maximum length of a tier name in bytes
+    pub const MAX_TIER_NAME_LEN: usize = 32;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        CampaignNotFound,
+        InvalidAmount,
+        InvalidDeadline,
+        InvalidTiers,
+        CampaignEnded,
+        CampaignActive,
+        GoalNotReached,
+        GoalReached,
+        NothingToClaim,
+        NothingToRefund,
+        TransferFailed,
+        LedgerTransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::CampaignNotFound => Self::NotFound,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// A fundraising campaign
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Campaign {
+        pub creator: AccountId,
+        /// Account the funds are paid out to
+        pub beneficiary: AccountId,
+        pub goal: Balance,
+        /// Contributions are accepted until this time
+        pub deadline: Timestamp,
+        /// Whether donors are refunded when the goal is missed
+        pub all_or_nothing: bool,
+        /// Payment ledger the campaign is funded on, or `None` for native funds
+        pub ledger: Option<AccountId>,
+        pub raised: Balance,
+        /// Amount already paid out to the beneficiary
+        pub withdrawn: Balance,
+    }
+
+    /// A reward level donors reach by contributing at least its amount
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Tier {
+        pub name: String,
+        pub min_amount: Balance,
+    }
+
+    /// Donation campaigns funded natively or on a Payment ledger
+    #[ink(storage)]
+    pub struct Crowdfunding {
+        campaigns: Mapping<u32, Campaign>,
+        /// Reward tiers of each campaign, by increasing amount
+        campaign_tiers: Mapping<u32, Vec<Tier>>,
+        /// Total contributed by each donor (campaign_id -> donor -> amount)
+        contributions: Mapping<(u32, AccountId), Balance>,
+        campaign_counter: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct CampaignCreated {
+        #[ink(topic)]
+        campaign_id: u32,
+        #[ink(topic)]
+        creator: AccountId,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        goal: Balance,
+        deadline: Timestamp,
+        all_or_nothing: bool,
+    }
+
+    #[ink(event)]
+    pub struct ContributionReceived {
+        #[ink(topic)]
+        campaign_id: u32,
+        #[ink(topic)]
+        donor: AccountId,
+        amount: Balance,
+        raised: Balance,
+    }
+
+    #[ink(event)]
+    pub struct GoalReached {
+        #[ink(topic)]
+        campaign_id: u32,
+        raised: Balance,
+    }
+
+    #[ink(event)]
+    pub struct FundsClaimed {
+        #[ink(topic)]
+        campaign_id: u32,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ContributionRefunded {
+        #[ink(topic)]
+        campaign_id: u32,
+        #[ink(topic)]
+        donor: AccountId,
+        amount: Balance,
+    }
+
+    impl Crowdfunding {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                campaigns: Mapping::default(),
+                campaign_tiers: Mapping::default(),
+                contributions: Mapping::default(),
+                campaign_counter: 0,
+            }
+        }
+
+        /// Start a campaign
+        ///
+        /// `tiers` must be ordered by strictly increasing amount. With a
+        /// `ledger`, contributions are pulled from donors' ledger balances
+        /// through their allowance instead of being transferred natively.
+        #[ink(message)]
+        pub fn create_campaign(
+            &mut self,
+            beneficiary: AccountId,
+            goal: Balance,
+            deadline: Timestamp,
+            all_or_nothing: bool,
+            ledger: Option<AccountId>,
+            tiers: Vec<Tier>,
+        ) -> Result<u32, Error> {
+            let creator = self.env().caller();
+            if goal == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidDeadline);
+            }
+            if !Self::valid_tiers(&tiers) {
+                return Err(Error::InvalidTiers);
+            }
+
+            let campaign_id = self.campaign_counter;
+            self.campaigns.insert(
+                campaign_id,
+                &Campaign {
+                    creator,
+                    beneficiary,
+                    goal,
+                    deadline,
+                    all_or_nothing,
+                    ledger,
+                    raised: 0,
+                    withdrawn: 0,
+                },
+            );
+            if !tiers.is_empty() {
+                self.campaign_tiers.insert(campaign_id, &tiers);
+            }
+            self.campaign_counter = campaign_id.saturating_add(1);
+
+            self.env().emit_event(CampaignCreated {
+                campaign_id,
+                creator,
+                beneficiary,
+                goal,
+                deadline,
+                all_or_nothing,
+            });
+
+            Ok(campaign_id)
+        }
+
+        /// Contribute to a running campaign
+        ///
+        /// Native campaigns take the transferred value, which must equal `amount`.
+        #[ink(message, payable)]
+        pub fn contribute(&mut self, campaign_id: u32, amount: Balance) -> Result<(), Error> {
+            let donor = self.env().caller();
+            let mut campaign = self.campaigns.get(campaign_id).ok_or(Error::CampaignNotFound)?;
+            if self.env().block_timestamp() >= campaign.deadline {
+                return Err(Error::CampaignEnded);
+            }
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let transferred = self.env().transferred_value();
+            match campaign.ledger {
+                Some(ledger) => {
+                    if transferred != 0 {
+                        return Err(Error::InvalidAmount);
+                    }
+                    let escrow = self.env().account_id();
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer_from(donor, escrow, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => {}
+                        _ => return Err(Error::LedgerTransferFailed),
+                    }
+                }
+                None if transferred != amount => return Err(Error::InvalidAmount),
+                None => {}
+            }
+
+            let goal_was_reached = campaign.raised >= campaign.goal;
+            campaign.raised = campaign.raised.saturating_add(amount);
+            self.campaigns.insert(campaign_id, &campaign);
+            let contributed = self.get_contribution(campaign_id, donor);
+            self.contributions
+                .insert((campaign_id, donor), &contributed.saturating_add(amount));
+
+            self.env().emit_event(ContributionReceived {
+                campaign_id,
+                donor,
+                amount,
+                raised: campaign.raised,
+            });
+            if !goal_was_reached && campaign.raised >= campaign.goal {
+                self.env().emit_event(GoalReached {
+                    campaign_id,
+                    raised: campaign.raised,
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Pay the funds raised so far out to the beneficiary
+        ///
+        /// All-or-nothing campaigns pay out only once their goal is reached.
+        /// Callable by the creator or the beneficiary.
+        #[ink(message)]
+        pub fn claim(&mut self, campaign_id: u32) -> Result<Balance, Error> {
+            let mut campaign = self.campaigns.get(campaign_id).ok_or(Error::CampaignNotFound)?;
+            let caller = self.env().caller();
+            if caller != campaign.creator && caller != campaign.beneficiary {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if campaign.all_or_nothing && campaign.raised < campaign.goal {
+                return Err(Error::GoalNotReached);
+            }
+            let amount = campaign.raised.saturating_sub(campaign.withdrawn);
+            if amount == 0 {
+                return Err(Error::NothingToClaim);
+            }
+
+            campaign.withdrawn = campaign.raised;
+            self.campaigns.insert(campaign_id, &campaign);
+            self.pay_out(&campaign, campaign.beneficiary, amount)?;
+
+            self.env().emit_event(FundsClaimed {
+                campaign_id,
+                beneficiary: campaign.beneficiary,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Return the caller's contribution to an all-or-nothing campaign that missed its goal
+        #[ink(message)]
+        pub fn refund(&mut self, campaign_id: u32) -> Result<Balance, Error> {
+            let mut campaign = self.campaigns.get(campaign_id).ok_or(Error::CampaignNotFound)?;
+            if !campaign.all_or_nothing || campaign.raised >= campaign.goal {
+                return Err(Error::GoalReached);
+            }
+            if self.env().block_timestamp() < campaign.deadline {
+                return Err(Error::CampaignActive);
+            }
+            let donor = self.env().caller();
+            let amount = self
+                .contributions
+                .take((campaign_id, donor))
+                .ok_or(Error::NothingToRefund)?;
+
+            campaign.raised = campaign.raised.saturating_sub(amount);
+            self.campaigns.insert(campaign_id, &campaign);
+            self.pay_out(&campaign, donor, amount)?;
+
+            self.env().emit_event(ContributionRefunded {
+                campaign_id,
+                donor,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Get a campaign
+        #[ink(message)]
+        pub fn get_campaign(&self, campaign_id: u32) -> Option<Campaign> {
+            self.campaigns.get(campaign_id)
+        }
+
+        /// Get the reward tiers of a campaign
+        #[ink(message)]
+        pub fn get_tiers(&self, campaign_id: u32) -> Vec<Tier> {
+            self.campaign_tiers.get(campaign_id).unwrap_or_default()
+        }
+
+        /// Get the total a donor contributed to a campaign
+        #[ink(message)]
+        pub fn get_contribution(&self, campaign_id: u32, donor: AccountId) -> Balance {
+            self.contributions.get((campaign_id, donor)).unwrap_or(0)
+        }
+
+        /// Get the highest tier a donor reached in a campaign
+        #[ink(message)]
+        pub fn get_donor_tier(&self, campaign_id: u32, donor: AccountId) -> Option<Tier> {
+            let contributed = self.get_contribution(campaign_id, donor);
+            self.get_tiers(campaign_id)
+                .into_iter()
+                .rev()
+                .find(|tier| tier.min_amount <= contributed)
+        }
+
+        /// Get the number of campaigns
+        #[ink(message)]
+        pub fn get_campaign_count(&self) -> u32 {
+            self.campaign_counter
+        }
+
+        /// Send campaign funds on its ledger or natively
+        fn pay_out(&self, campaign: &Campaign, to: AccountId, amount: Balance) -> Result<(), Error> {
+            match campaign.ledger {
+                Some(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer(to, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                None => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::TransferFailed),
+            }
+        }
+
+        fn valid_tiers(tiers: &[Tier]) -> bool {
+            tiers.len() <= MAX_TIERS
+                && tiers
+                    .iter()
+                    .all(|tier| tier.min_amount > 0 && tier.name.len() <= MAX_TIER_NAME_LEN)
+                && tiers.windows(2).all(|pair| pair[0].min_amount < pair[1].min_amount)
+        }
+    }
+
+    impl Default for Crowdfunding {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use xcc_common::MILLISECONDS_PER_DAY as DAY;
+
+        fn setup() -> (Crowdfunding, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from([0x42; 32]),
+                100_000_000,
+            );
+            (Crowdfunding::new(), accounts)
+        }
+
+        fn contribute_as(
+            crowdfunding: &mut Crowdfunding,
+            donor: AccountId,
+            campaign_id: u32,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(donor);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(amount);
+            let result = crowdfunding.contribute(campaign_id, amount);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            result
+        }
+
+        fn tiers() -> Vec<Tier> {
+            ink::prelude::vec![
+                Tier {
+                    name: "Supporter".into(),
+                    min_amount: 1_000_000,
+                },
+                Tier {
+                    name: "Patron".into(),
+                    min_amount: 5_000_000,
+                },
+            ]
+        }
+
+        #[ink::test]
+        fn funded_campaign_pays_out() {
+            let (mut crowdfunding, accounts) = setup();
+            let campaign_id = crowdfunding
+                .create_campaign(accounts.frank, 6_000_000, DAY, true, None, tiers())
+                .unwrap();
+
+            contribute_as(&mut crowdfunding, accounts.bob, campaign_id, 1_000_000).unwrap();
+            assert_eq!(crowdfunding.get_donor_tier(campaign_id, accounts.bob).unwrap().name, "Supporter");
+
+            // All-or-nothing campaigns hold the funds until the goal is reached
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(crowdfunding.claim(campaign_id), Err(Error::GoalNotReached));
+
+            contribute_as(&mut crowdfunding, accounts.charlie, campaign_id, 5_000_000).unwrap();
+            assert_eq!(crowdfunding.get_donor_tier(campaign_id, accounts.charlie).unwrap().name, "Patron");
+            assert_eq!(crowdfunding.get_campaign(campaign_id).unwrap().raised, 6_000_000);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(crowdfunding.claim(campaign_id), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(crowdfunding.claim(campaign_id), Ok(6_000_000));
+            assert_eq!(crowdfunding.claim(campaign_id), Err(Error::NothingToClaim));
+
+            // No refunds once the goal is reached, no contributions after the deadline
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DAY);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(crowdfunding.refund(campaign_id), Err(Error::GoalReached));
+            assert_eq!(
+                contribute_as(&mut crowdfunding, accounts.bob, campaign_id, 1_000_000),
+                Err(Error::CampaignEnded)
+            );
+        }
+
+        #[ink::test]
+        fn missed_goal_refunds_donors() {
+            let (mut crowdfunding, accounts) = setup();
+            let campaign_id = crowdfunding
+                .create_campaign(accounts.frank, 6_000_000, DAY, true, None, Vec::new())
+                .unwrap();
+            contribute_as(&mut crowdfunding, accounts.bob, campaign_id, 2_000_000).unwrap();
+
+            assert_eq!(crowdfunding.refund(campaign_id), Err(Error::CampaignActive));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DAY);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob)
+                .unwrap();
+            assert_eq!(crowdfunding.refund(campaign_id), Ok(2_000_000));
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob),
+                Ok(before + 2_000_000)
+            );
+            assert_eq!(crowdfunding.refund(campaign_id), Err(Error::NothingToRefund));
+        }
+
+        #[ink::test]
+        fn keep_it_all_campaign_pays_out_any_time() {
+            let (mut crowdfunding, accounts) = setup();
+            let campaign_id = crowdfunding
+                .create_campaign(accounts.frank, 6_000_000, DAY, false, None, Vec::new())
+                .unwrap();
+            contribute_as(&mut crowdfunding, accounts.bob, campaign_id, 2_000_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(crowdfunding.claim(campaign_id), Ok(2_000_000));
+            contribute_as(&mut crowdfunding, accounts.bob, campaign_id, 1_000_000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(crowdfunding.claim(campaign_id), Ok(1_000_000));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DAY);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(crowdfunding.refund(campaign_id), Err(Error::GoalReached));
+        }
+
+        #[ink::test]
+        fn create_campaign_validates() {
+            let (mut crowdfunding, accounts) = setup();
+
+            assert_eq!(
+                crowdfunding.create_campaign(accounts.frank, 0, DAY, true, None, Vec::new()),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                crowdfunding.create_campaign(accounts.frank, 100, 0, true, None, Vec::new()),
+                Err(Error::InvalidDeadline)
+            );
+            let mut unordered = tiers();
+            unordered.reverse();
+            assert_eq!(
+                crowdfunding.create_campaign(accounts.frank, 100, DAY, true, None, unordered),
+                Err(Error::InvalidTiers)
+            );
+        }
+    }
+}
@@ -0,0 +1,688 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::governance::{Error, GovernanceRef, Proposal, ProposalState, VotingMode};
+
+#[ink::contract]
+mod governance {
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::CallFlags;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::PaymentLedger;
+
+    /// Maximum number of council members
+    pub const MAX_COUNCIL_SIZE: usize = 32;
+
+    /// Maximum size of the encoded arguments of a proposed call
+    pub const MAX_INPUT_LEN: usize = 1024;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        ProposalNotFound,
+        NoVotingWeight,
+        VotingClosed,
+        AlreadyVoted,
+        ProposalNotReady,
+        ProposalClosed,
+        ExecutionFailed,
+        InvalidCouncil,
+        InputTooLong,
+        /// Token-weighted voting is not enabled
+        NotTokenWeighted,
+        /// The tokens back a vote on a proposal still open
+        TokensLocked,
+        InsufficientLockedTokens,
+        LedgerTransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::ProposalNotFound => Self::NotFound,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// How voting weight is assigned
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VotingMode {
+        /// One vote per council member
+        Council,
+        /// Votes weighted by tokens of a payment ledger locked in this contract
+        TokenWeighted(AccountId),
+    }
+
+    /// A call to be made by the governance contract once approved
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Proposal {
+        pub proposer: AccountId,
+        /// Contract the call is made to
+        pub target: AccountId,
+        /// Selector of the message called
+        pub selector: [u8; 4],
+        /// SCALE-encoded arguments of the message
+        pub input: Vec<u8>,
+        pub voting_ends: Timestamp,
+        pub votes_for: Balance,
+        pub votes_against: Balance,
+        pub executed: bool,
+        pub cancelled: bool,
+    }
+
+    /// Stage of a proposal
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum ProposalState {
+        /// Open for votes
+        Active,
+        /// Rejected or short of the quorum
+        Defeated,
+        /// Approved, waiting out the timelock
+        Queued,
+        /// Approved and executable
+        Ready,
+        Executed,
+        Cancelled,
+    }
+
+    /// Arguments of a proposed call, appended to its selector as they are
+    struct CallInput<'a>(&'a [u8]);
+
+    impl ink::scale::Encode for CallInput<'_> {
+        fn encode_to<T: ink::scale::Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(self.0);
+        }
+    }
+
+    /// Governance contract owning the admin messages of the 0xCC contracts
+    ///
+    /// Contracts are handed over with their `transfer_ownership`, after which
+    /// their owner-only messages are reached through approved proposals.
+    /// Proposals may call any message returning `Result<(), Error>`, including
+    /// this contract's own configuration messages.
+    #[ink(storage)]
+    pub struct Governance {
+        proposals: Mapping<u32, Proposal>,
+        /// Whether an account voted on a proposal
+        votes: Mapping<(u32, AccountId), bool>,
+        /// Tokens locked as voting weight (ledger, account) -> amount
+        locked_tokens: Mapping<(AccountId, AccountId), Balance>,
+        /// End of the latest vote an account cast, until which its tokens stay locked
+        locked_until: Mapping<AccountId, Timestamp>,
+        council: Vec<AccountId>,
+        voting_mode: VotingMode,
+        /// How long a proposal is open for votes, in milliseconds
+        voting_period: u64,
+        /// Delay between the end of voting and execution, in milliseconds
+        timelock: u64,
+        /// Votes in favour a proposal needs to pass
+        quorum: Balance,
+        proposal_counter: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        proposer: AccountId,
+        #[ink(topic)]
+        target: AccountId,
+        selector: [u8; 4],
+        voting_ends: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct VoteCast {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        voter: AccountId,
+        approve: bool,
+        weight: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TokensLocked {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TokensUnlocked {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        proposal_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct ProposalCancelled {
+        #[ink(topic)]
+        proposal_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct CouncilUpdated {
+        council: Vec<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct VotingModeUpdated {
+        voting_mode: VotingMode,
+    }
+
+    #[ink(event)]
+    pub struct ParametersUpdated {
+        voting_period: u64,
+        timelock: u64,
+        quorum: Balance,
+    }
+
+    impl Governance {
+        /// Constructor with a voting council
+        #[ink(constructor)]
+        pub fn new(council: Vec<AccountId>, voting_period: u64, timelock: u64, quorum: Balance) -> Self {
+            assert!(Self::valid_council(&council), "invalid council");
+            Self {
+                proposals: Mapping::default(),
+                votes: Mapping::default(),
+                locked_tokens: Mapping::default(),
+                locked_until: Mapping::default(),
+                council,
+                voting_mode: VotingMode::Council,
+                voting_period,
+                timelock,
+                quorum,
+                proposal_counter: 0,
+            }
+        }
+
+        /// Propose a call to `selector` on `target` with SCALE-encoded `input`
+        ///
+        /// Anyone with voting weight can propose.
+        #[ink(message)]
+        pub fn propose(&mut self, target: AccountId, selector: [u8; 4], input: Vec<u8>) -> Result<u32, Error> {
+            let proposer = self.env().caller();
+            if self.voting_weight(proposer) == 0 {
+                return Err(Error::NoVotingWeight);
+            }
+            if input.len() > MAX_INPUT_LEN {
+                return Err(Error::InputTooLong);
+            }
+
+            let proposal_id = self.proposal_counter;
+            let voting_ends = self.env().block_timestamp().saturating_add(self.voting_period);
+            self.proposals.insert(
+                proposal_id,
+                &Proposal {
+                    proposer,
+                    target,
+                    selector,
+                    input,
+                    voting_ends,
+                    votes_for: 0,
+                    votes_against: 0,
+                    executed: false,
+                    cancelled: false,
+                },
+            );
+            self.proposal_counter = proposal_id.saturating_add(1);
+
+            self.env().emit_event(ProposalCreated {
+                proposal_id,
+                proposer,
+                target,
+                selector,
+                voting_ends,
+            });
+
+            Ok(proposal_id)
+        }
+
+        /// Vote on an active proposal
+        #[ink(message)]
+        pub fn vote(&mut self, proposal_id: u32, approve: bool) -> Result<(), Error> {
+            let voter = self.env().caller();
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::ProposalNotFound)?;
+            if self.state_of(&proposal) != ProposalState::Active {
+                return Err(Error::VotingClosed);
+            }
+            if self.votes.contains((proposal_id, voter)) {
+                return Err(Error::AlreadyVoted);
+            }
+            let weight = self.voting_weight(voter);
+            if weight == 0 {
+                return Err(Error::NoVotingWeight);
+            }
+
+            if approve {
+                proposal.votes_for = proposal.votes_for.saturating_add(weight);
+            } else {
+                proposal.votes_against = proposal.votes_against.saturating_add(weight);
+            }
+            self.proposals.insert(proposal_id, &proposal);
+            self.votes.insert((proposal_id, voter), &approve);
+
+            // The weight counted must not be moved and voted with again
+            if self.locked_until.get(voter).unwrap_or(0) < proposal.voting_ends {
+                self.locked_until.insert(voter, &proposal.voting_ends);
+            }
+
+            self.env().emit_event(VoteCast {
+                proposal_id,
+                voter,
+                approve,
+                weight,
+            });
+
+            Ok(())
+        }
+
+        /// Lock tokens of the voting ledger as voting weight
+        ///
+        /// The tokens are taken with `transfer_from`, so the caller approves
+        /// this contract on the ledger first.
+        #[ink(message)]
+        pub fn lock_tokens(&mut self, amount: Balance) -> Result<(), Error> {
+            let VotingMode::TokenWeighted(ledger) = self.voting_mode else {
+                return Err(Error::NotTokenWeighted);
+            };
+            let account = self.env().caller();
+
+            let mut payment_ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+            match payment_ledger
+                .call_mut()
+                .transfer_from(account, self.env().account_id(), amount)
+                .try_invoke()
+            {
+                Ok(Ok(Ok(()))) => {}
+                _ => return Err(Error::LedgerTransferFailed),
+            }
+
+            let locked = self.locked_tokens.get((ledger, account)).unwrap_or(0);
+            self.locked_tokens.insert((ledger, account), &locked.saturating_add(amount));
+
+            self.env().emit_event(TokensLocked { account, amount });
+
+            Ok(())
+        }
+
+        /// Return locked tokens of `ledger` once no open vote counts them
+        ///
+        /// Tokens locked under an earlier voting ledger can still be returned.
+        #[ink(message)]
+        pub fn unlock_tokens(&mut self, ledger: AccountId, amount: Balance) -> Result<(), Error> {
+            let account = self.env().caller();
+            if self.env().block_timestamp() < self.locked_until.get(account).unwrap_or(0) {
+                return Err(Error::TokensLocked);
+            }
+            let locked = self.locked_tokens.get((ledger, account)).unwrap_or(0);
+            if amount > locked {
+                return Err(Error::InsufficientLockedTokens);
+            }
+
+            self.locked_tokens.insert((ledger, account), &(locked - amount));
+
+            let mut payment_ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+            match payment_ledger.call_mut().transfer(account, amount).try_invoke() {
+                Ok(Ok(Ok(()))) => {}
+                _ => return Err(Error::LedgerTransferFailed),
+            }
+
+            self.env().emit_event(TokensUnlocked { account, amount });
+
+            Ok(())
+        }
+
+        /// Make the call of an approved proposal once its timelock has passed
+        ///
+        /// Anyone may execute; a failing call reverts and leaves the proposal
+        /// executable.
+        #[ink(message)]
+        pub fn execute(&mut self, proposal_id: u32) -> Result<(), Error> {
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::ProposalNotFound)?;
+            if self.state_of(&proposal) != ProposalState::Ready {
+                return Err(Error::ProposalNotReady);
+            }
+
+            // Marked first so the call cannot execute the proposal again
+            proposal.executed = true;
+            self.proposals.insert(proposal_id, &proposal);
+
+            // Configuration messages of this contract are reached through itself
+            let call_flags = if proposal.target == self.env().account_id() {
+                CallFlags::ALLOW_REENTRY
+            } else {
+                CallFlags::empty()
+            };
+            let result = build_call::<Environment>()
+                .call(proposal.target)
+                .call_flags(call_flags)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(proposal.selector))
+                        .push_arg(CallInput(&proposal.input)),
+                )
+                .returns::<Result<(), u8>>()
+                .try_invoke();
+            if !matches!(result, Ok(Ok(Ok(())))) {
+                return Err(Error::ExecutionFailed);
+            }
+
+            self.env().emit_event(ProposalExecuted { proposal_id });
+
+            Ok(())
+        }
+
+        /// Withdraw a proposal that has not been executed (proposer only)
+        #[ink(message)]
+        pub fn cancel(&mut self, proposal_id: u32) -> Result<(), Error> {
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::ProposalNotFound)?;
+            if self.env().caller() != proposal.proposer {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if proposal.executed || proposal.cancelled {
+                return Err(Error::ProposalClosed);
+            }
+
+            proposal.cancelled = true;
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(ProposalCancelled { proposal_id });
+
+            Ok(())
+        }
+
+        /// Replace the council (through a proposal only)
+        #[ink(message)]
+        pub fn set_council(&mut self, council: Vec<AccountId>) -> Result<(), Error> {
+            self.ensure_self()?;
+            if !Self::valid_council(&council) {
+                return Err(Error::InvalidCouncil);
+            }
+
+            self.council = council.clone();
+
+            self.env().emit_event(CouncilUpdated { council });
+
+            Ok(())
+        }
+
+        /// Switch between council and token-weighted voting (through a proposal only)
+        #[ink(message)]
+        pub fn set_voting_mode(&mut self, voting_mode: VotingMode) -> Result<(), Error> {
+            self.ensure_self()?;
+
+            self.voting_mode = voting_mode;
+
+            self.env().emit_event(VotingModeUpdated { voting_mode });
+
+            Ok(())
+        }
+
+        /// Set the voting period, timelock and quorum (through a proposal only)
+        #[ink(message)]
+        pub fn set_parameters(&mut self, voting_period: u64, timelock: u64, quorum: Balance) -> Result<(), Error> {
+            self.ensure_self()?;
+
+            self.voting_period = voting_period;
+            self.timelock = timelock;
+            self.quorum = quorum;
+
+            self.env().emit_event(ParametersUpdated {
+                voting_period,
+                timelock,
+                quorum,
+            });
+
+            Ok(())
+        }
+
+        /// Get a proposal
+        #[ink(message)]
+        pub fn get_proposal(&self, proposal_id: u32) -> Option<Proposal> {
+            self.proposals.get(proposal_id)
+        }
+
+        /// Get the stage of a proposal
+        #[ink(message)]
+        pub fn get_proposal_state(&self, proposal_id: u32) -> Option<ProposalState> {
+            self.proposals.get(proposal_id).map(|proposal| self.state_of(&proposal))
+        }
+
+        /// Get how an account voted on a proposal, if it did
+        #[ink(message)]
+        pub fn get_vote(&self, proposal_id: u32, voter: AccountId) -> Option<bool> {
+            self.votes.get((proposal_id, voter))
+        }
+
+        /// Get the council
+        #[ink(message)]
+        pub fn get_council(&self) -> Vec<AccountId> {
+            self.council.clone()
+        }
+
+        /// Get the voting mode
+        #[ink(message)]
+        pub fn get_voting_mode(&self) -> VotingMode {
+            self.voting_mode
+        }
+
+        /// Get the voting period, timelock and quorum
+        #[ink(message)]
+        pub fn get_parameters(&self) -> (u64, u64, Balance) {
+            (self.voting_period, self.timelock, self.quorum)
+        }
+
+        /// Get the tokens of `ledger` an account locked
+        #[ink(message)]
+        pub fn get_locked_tokens(&self, ledger: AccountId, account: AccountId) -> Balance {
+            self.locked_tokens.get((ledger, account)).unwrap_or(0)
+        }
+
+        /// Get the voting weight of an account
+        #[ink(message)]
+        pub fn voting_weight(&self, account: AccountId) -> Balance {
+            match self.voting_mode {
+                VotingMode::Council => Balance::from(self.council.contains(&account)),
+                VotingMode::TokenWeighted(ledger) => self.locked_tokens.get((ledger, account)).unwrap_or(0),
+            }
+        }
+
+        fn state_of(&self, proposal: &Proposal) -> ProposalState {
+            let now = self.env().block_timestamp();
+            if proposal.cancelled {
+                ProposalState::Cancelled
+            } else if proposal.executed {
+                ProposalState::Executed
+            } else if now < proposal.voting_ends {
+                ProposalState::Active
+            } else if proposal.votes_for < self.quorum || proposal.votes_for <= proposal.votes_against {
+                ProposalState::Defeated
+            } else if now < proposal.voting_ends.saturating_add(self.timelock) {
+                ProposalState::Queued
+            } else {
+                ProposalState::Ready
+            }
+        }
+
+        fn ensure_self(&self) -> Result<(), Error> {
+            if self.env().caller() != self.env().account_id() {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(())
+        }
+
+        fn valid_council(council: &[AccountId]) -> bool {
+            !council.is_empty()
+                && council.len() <= MAX_COUNCIL_SIZE
+                && council
+                    .iter()
+                    .enumerate()
+                    .all(|(i, member)| !council[..i].contains(member))
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const HOUR: u64 = 3_600_000;
+
+        fn council() -> (Governance, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            let governance = Governance::new(
+                ink::prelude::vec![accounts.alice, accounts.bob, accounts.charlie],
+                HOUR,
+                2 * HOUR,
+                2,
+            );
+            (governance, accounts)
+        }
+
+        #[ink::test]
+        fn proposal_lifecycle_works() {
+            let (mut governance, accounts) = council();
+            let target = accounts.frank;
+
+            let proposal_id = governance.propose(target, [1, 2, 3, 4], ink::prelude::vec![7]).unwrap();
+            assert_eq!(governance.get_proposal_state(proposal_id), Some(ProposalState::Active));
+
+            governance.vote(proposal_id, true).unwrap();
+            assert_eq!(governance.vote(proposal_id, true), Err(Error::AlreadyVoted));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            governance.vote(proposal_id, true).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            governance.vote(proposal_id, false).unwrap();
+            assert_eq!(governance.get_vote(proposal_id, accounts.charlie), Some(false));
+
+            // Approved proposals wait out the timelock
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(HOUR);
+            assert_eq!(governance.vote(proposal_id, true), Err(Error::VotingClosed));
+            assert_eq!(governance.get_proposal_state(proposal_id), Some(ProposalState::Queued));
+            assert_eq!(governance.execute(proposal_id), Err(Error::ProposalNotReady));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(3 * HOUR);
+            assert_eq!(governance.get_proposal_state(proposal_id), Some(ProposalState::Ready));
+        }
+
+        #[ink::test]
+        fn quorum_and_majority_are_required() {
+            let (mut governance, accounts) = council();
+
+            // A single vote in favour is short of the quorum
+            let short = governance.propose(accounts.frank, [0; 4], Vec::new()).unwrap();
+            governance.vote(short, true).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(3 * HOUR);
+            assert_eq!(governance.get_proposal_state(short), Some(ProposalState::Defeated));
+            assert_eq!(governance.execute(short), Err(Error::ProposalNotReady));
+
+            // Meeting the quorum is not enough without a majority
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            governance.set_parameters(HOUR, 2 * HOUR, 1).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let tied = governance.propose(accounts.frank, [0; 4], Vec::new()).unwrap();
+            governance.vote(tied, true).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            governance.vote(tied, false).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(6 * HOUR);
+            assert_eq!(governance.get_proposal_state(tied), Some(ProposalState::Defeated));
+        }
+
+        #[ink::test]
+        fn only_council_members_take_part() {
+            let (mut governance, accounts) = council();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                governance.propose(accounts.frank, [0; 4], Vec::new()),
+                Err(Error::NoVotingWeight)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let proposal_id = governance.propose(accounts.frank, [0; 4], Vec::new()).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(governance.vote(proposal_id, true), Err(Error::NoVotingWeight));
+            assert_eq!(governance.cancel(proposal_id), Err(Error::UnauthorizedAccess));
+
+            // The proposer can withdraw their proposal
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            governance.cancel(proposal_id).unwrap();
+            assert_eq!(governance.get_proposal_state(proposal_id), Some(ProposalState::Cancelled));
+            assert_eq!(governance.cancel(proposal_id), Err(Error::ProposalClosed));
+        }
+
+        #[ink::test]
+        fn configuration_goes_through_proposals() {
+            let (mut governance, accounts) = council();
+
+            // Not even council members can change the configuration directly
+            assert_eq!(
+                governance.set_council(ink::prelude::vec![accounts.alice]),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(
+                governance.set_voting_mode(VotingMode::TokenWeighted(accounts.frank)),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(governance.set_parameters(0, 0, 1), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            assert_eq!(
+                governance.set_council(ink::prelude::vec![accounts.alice, accounts.alice]),
+                Err(Error::InvalidCouncil)
+            );
+            governance.set_council(ink::prelude::vec![accounts.django]).unwrap();
+            governance.set_parameters(HOUR, 0, 1).unwrap();
+            assert_eq!(governance.get_parameters(), (HOUR, 0, 1));
+            assert_eq!(governance.voting_weight(accounts.django), 1);
+            assert_eq!(governance.voting_weight(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn locked_tokens_cannot_vote_twice() {
+            let (mut governance, accounts) = council();
+            let ledger = accounts.frank;
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            governance.set_voting_mode(VotingMode::TokenWeighted(ledger)).unwrap();
+            governance.locked_tokens.insert((ledger, accounts.alice), &5);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(governance.voting_weight(accounts.alice), 5);
+            let proposal_id = governance.propose(accounts.frank, [0; 4], Vec::new()).unwrap();
+            governance.vote(proposal_id, true).unwrap();
+            assert_eq!(governance.get_proposal(proposal_id).unwrap().votes_for, 5);
+
+            // The tokens cannot move to another account while the vote counts
+            assert_eq!(governance.unlock_tokens(ledger, 5), Err(Error::TokensLocked));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(governance.vote(proposal_id, true), Err(Error::NoVotingWeight));
+
+            // Ledger balances outside the lock carry no weight
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(HOUR);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(governance.unlock_tokens(ledger, 6), Err(Error::InsufficientLockedTokens));
+            assert_eq!(governance.get_locked_tokens(ledger, accounts.alice), 5);
+        }
+    }
+}
@@ -0,0 +1,402 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::identity::{Error, HandleRecord, IdentityRef};
+
+#[ink::contract]
+mod identity {
+    use ink::prelude::string::String;
+    use ink::storage::Mapping;
+    use xcc_common::{HandleResolver, MILLISECONDS_PER_DAY};
+
+    /// Shortest handle that can be registered
+    pub const MIN_HANDLE_LEN: usize = 3;
+
+    /// Longest handle that can be registered
+    pub const MAX_HANDLE_LEN: usize = 32;
+
+    /// Default time a registration or renewal lasts
+    pub const DEFAULT_REGISTRATION_PERIOD: u64 = 365 * MILLISECONDS_PER_DAY;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        InvalidHandle,
+        HandleTaken,
+        HandleNotFound,
+        WrongDeposit,
+        TransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::HandleNotFound => Self::NotFound,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// A registered handle
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct HandleRecord {
+        pub owner: AccountId,
+        /// Deposit locked by the registration, returned on release or takeover
+        pub deposit: Balance,
+        pub expires_at: Timestamp,
+    }
+
+    /// Registry of human-readable handles for accounts
+    ///
+    /// Registering locks a deposit to discourage squatting. Handles expire
+    /// unless renewed; an expired handle no longer resolves and can be
+    /// registered by anyone, which returns the previous deposit to its owner.
+    #[ink(storage)]
+    pub struct Identity {
+        handles: Mapping<String, HandleRecord>,
+        /// Handle each account is known by, for reverse lookups
+        primary_handles: Mapping<AccountId, String>,
+        /// Deposit new registrations lock
+        deposit: Balance,
+        /// Time a registration or renewal lasts
+        registration_period: u64,
+        owner: AccountId,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct HandleRegistered {
+        #[ink(topic)]
+        owner: AccountId,
+        handle: String,
+        expires_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct HandleRenewed {
+        #[ink(topic)]
+        owner: AccountId,
+        handle: String,
+        expires_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct HandleTransferred {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        handle: String,
+    }
+
+    #[ink(event)]
+    pub struct HandleReleased {
+        #[ink(topic)]
+        owner: AccountId,
+        handle: String,
+    }
+
+    #[ink(event)]
+    pub struct RegistrationTermsUpdated {
+        deposit: Balance,
+        registration_period: u64,
+    }
+
+    impl Identity {
+        /// Constructor taking the registration deposit
+        #[ink(constructor)]
+        pub fn new(deposit: Balance) -> Self {
+            Self {
+                handles: Mapping::default(),
+                primary_handles: Mapping::default(),
+                deposit,
+                registration_period: DEFAULT_REGISTRATION_PERIOD,
+                owner: Self::env().caller(),
+            }
+        }
+
+        /// Register a handle for the caller, transferring exactly the deposit
+        ///
+        /// Handles are 3 to 32 characters of lowercase letters, digits and `_`.
+        #[ink(message, payable)]
+        pub fn register(&mut self, handle: String) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if !Self::valid_handle(&handle) {
+                return Err(Error::InvalidHandle);
+            }
+            if self.env().transferred_value() != self.deposit {
+                return Err(Error::WrongDeposit);
+            }
+
+            let now = self.env().block_timestamp();
+            if let Some(previous) = self.handles.get(&handle) {
+                if previous.expires_at > now {
+                    return Err(Error::HandleTaken);
+                }
+                // Expired: the previous holder gets their deposit back
+                self.clear_primary(previous.owner, &handle);
+                self.refund(previous.owner, previous.deposit)?;
+            }
+
+            let expires_at = now.saturating_add(self.registration_period);
+            self.handles.insert(
+                &handle,
+                &HandleRecord {
+                    owner: caller,
+                    deposit: self.deposit,
+                    expires_at,
+                },
+            );
+            if !self.primary_handles.contains(caller) {
+                self.primary_handles.insert(caller, &handle);
+            }
+
+            self.env().emit_event(HandleRegistered {
+                owner: caller,
+                handle,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Extend a handle by another registration period from now
+        #[ink(message)]
+        pub fn renew(&mut self, handle: String) -> Result<(), Error> {
+            let mut record = self.owned_handle(&handle)?;
+
+            record.expires_at = self.env().block_timestamp().saturating_add(self.registration_period);
+            self.handles.insert(&handle, &record);
+
+            self.env().emit_event(HandleRenewed {
+                owner: record.owner,
+                handle,
+                expires_at: record.expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Hand a handle, along with its deposit, over to another account
+        #[ink(message)]
+        pub fn transfer(&mut self, handle: String, to: AccountId) -> Result<(), Error> {
+            let mut record = self.owned_handle(&handle)?;
+            let from = record.owner;
+
+            record.owner = to;
+            self.handles.insert(&handle, &record);
+            self.clear_primary(from, &handle);
+            if !self.primary_handles.contains(to) {
+                self.primary_handles.insert(to, &handle);
+            }
+
+            self.env().emit_event(HandleTransferred { from, to, handle });
+
+            Ok(())
+        }
+
+        /// Give up a handle and get its deposit back
+        #[ink(message)]
+        pub fn release(&mut self, handle: String) -> Result<(), Error> {
+            let record = self.owned_handle(&handle)?;
+
+            self.handles.remove(&handle);
+            self.clear_primary(record.owner, &handle);
+            self.refund(record.owner, record.deposit)?;
+
+            self.env().emit_event(HandleReleased {
+                owner: record.owner,
+                handle,
+            });
+
+            Ok(())
+        }
+
+        /// Choose which of the caller's handles reverse lookups return
+        #[ink(message)]
+        pub fn set_primary_handle(&mut self, handle: String) -> Result<(), Error> {
+            let record = self.owned_handle(&handle)?;
+            self.primary_handles.insert(record.owner, &handle);
+            Ok(())
+        }
+
+        /// Set the deposit and registration period of new registrations (owner only)
+        ///
+        /// Existing registrations keep the deposit they locked.
+        #[ink(message)]
+        pub fn set_registration_terms(&mut self, deposit: Balance, registration_period: u64) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.deposit = deposit;
+            self.registration_period = registration_period;
+
+            self.env().emit_event(RegistrationTermsUpdated {
+                deposit,
+                registration_period,
+            });
+
+            Ok(())
+        }
+
+        /// Get a handle's registration, expired or not
+        #[ink(message)]
+        pub fn get_handle(&self, handle: String) -> Option<HandleRecord> {
+            self.handles.get(&handle)
+        }
+
+        /// Get the handle an account is known by, if it is still registered to it
+        #[ink(message)]
+        pub fn handle_of(&self, account: AccountId) -> Option<String> {
+            let handle = self.primary_handles.get(account)?;
+            (self.resolve_handle(handle.clone()) == Some(account)).then_some(handle)
+        }
+
+        /// Get the deposit and registration period of new registrations
+        #[ink(message)]
+        pub fn get_registration_terms(&self) -> (Balance, u64) {
+            (self.deposit, self.registration_period)
+        }
+
+        /// The unexpired record of a handle held by the caller
+        fn owned_handle(&self, handle: &String) -> Result<HandleRecord, Error> {
+            let record = self.handles.get(handle).ok_or(Error::HandleNotFound)?;
+            if record.expires_at <= self.env().block_timestamp() {
+                return Err(Error::HandleNotFound);
+            }
+            if record.owner != self.env().caller() {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(record)
+        }
+
+        fn clear_primary(&mut self, account: AccountId, handle: &String) {
+            if self.primary_handles.get(account).as_ref() == Some(handle) {
+                self.primary_handles.remove(account);
+            }
+        }
+
+        fn refund(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            if amount == 0 {
+                return Ok(());
+            }
+            self.env().transfer(to, amount).map_err(|_| Error::TransferFailed)
+        }
+
+        fn valid_handle(handle: &str) -> bool {
+            (MIN_HANDLE_LEN..=MAX_HANDLE_LEN).contains(&handle.len())
+                && handle
+                    .bytes()
+                    .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_')
+        }
+    }
+
+    impl HandleResolver for Identity {
+        #[ink(message)]
+        fn resolve_handle(&self, handle: String) -> Option<AccountId> {
+            self.handles
+                .get(&handle)
+                .filter(|record| record.expires_at > self.env().block_timestamp())
+                .map(|record| record.owner)
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const DEPOSIT: Balance = 1_000_000;
+
+        fn identity() -> (Identity, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from([0x42; 32]),
+                10 * DEPOSIT,
+            );
+            (Identity::new(DEPOSIT), accounts)
+        }
+
+        fn register_as(identity: &mut Identity, account: AccountId, handle: &str) -> Result<(), Error> {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(account);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(DEPOSIT);
+            let result = identity.register(handle.into());
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            result
+        }
+
+        #[ink::test]
+        fn register_and_resolve_works() {
+            let (mut identity, accounts) = identity();
+
+            register_as(&mut identity, accounts.alice, "alice").unwrap();
+            assert_eq!(identity.resolve_handle("alice".into()), Some(accounts.alice));
+            assert_eq!(identity.handle_of(accounts.alice), Some("alice".into()));
+            assert_eq!(
+                register_as(&mut identity, accounts.bob, "alice"),
+                Err(Error::HandleTaken)
+            );
+
+            // Handles are validated and need the exact deposit
+            assert_eq!(register_as(&mut identity, accounts.bob, "Bob"), Err(Error::InvalidHandle));
+            assert_eq!(register_as(&mut identity, accounts.bob, "bo"), Err(Error::InvalidHandle));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(DEPOSIT - 1);
+            assert_eq!(identity.register("bob".into()), Err(Error::WrongDeposit));
+        }
+
+        #[ink::test]
+        fn expired_handles_can_be_taken_over() {
+            let (mut identity, accounts) = identity();
+            register_as(&mut identity, accounts.alice, "alice").unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DEFAULT_REGISTRATION_PERIOD);
+            assert_eq!(identity.resolve_handle("alice".into()), None);
+            assert_eq!(identity.handle_of(accounts.alice), None);
+            assert_eq!(identity.renew("alice".into()), Err(Error::HandleNotFound));
+
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.alice)
+                .unwrap();
+            register_as(&mut identity, accounts.bob, "alice").unwrap();
+            assert_eq!(identity.resolve_handle("alice".into()), Some(accounts.bob));
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.alice),
+                Ok(before + DEPOSIT)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_and_release_work() {
+            let (mut identity, accounts) = identity();
+            register_as(&mut identity, accounts.alice, "alice").unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                identity.transfer("alice".into(), accounts.bob),
+                Err(Error::UnauthorizedAccess)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            identity.transfer("alice".into(), accounts.bob).unwrap();
+            assert_eq!(identity.handle_of(accounts.alice), None);
+            assert_eq!(identity.handle_of(accounts.bob), Some("alice".into()));
+
+            // The deposit moves with the handle
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob)
+                .unwrap();
+            identity.release("alice".into()).unwrap();
+            assert_eq!(identity.get_handle("alice".into()), None);
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob),
+                Ok(before + DEPOSIT)
+            );
+        }
+    }
+}
@@ -0,0 +1,331 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::keepers::{Error, JobSource, KeeperRegistry, KeeperRegistryRef, KeeperStats};
+
+#[ink::contract]
+mod keepers {
+    use ink::codegen::TraitCallBuilder;
+    use ink::storage::Mapping;
+    use xcc_common::{JobPage, Maintainable};
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        SourceNotFound,
+        SourceInactive,
+        /// The job was run less than the source's cooldown ago
+        RecentlyRun,
+        /// The source contract rejected the job, usually because it is not due
+        JobFailed(xcc_common::Error),
+        /// The source contract could not be called
+        CallFailed,
+    }
+
+    /// A contract whose maintenance jobs keepers are paid to run
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct JobSource {
+        /// Contract implementing `Maintainable`
+        pub contract: AccountId,
+        /// Paid from the job's bond for each run, or less when the bond runs low
+        pub bounty: Balance,
+        /// Time before the same job can be run again
+        pub cooldown: u64,
+        pub active: bool,
+    }
+
+    /// Work done by a keeper
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct KeeperStats {
+        pub jobs_run: u32,
+        pub bounties_earned: Balance,
+    }
+
+    /// Registry of the suite's maintenance jobs, paying keepers who run them
+    ///
+    /// Each source is a contract such as BillSplitting (expiring bills),
+    /// XcmHandler (refunding timed-out payments) or Subscriptions (charging
+    /// due subscriptions) that lists its due jobs and lets anyone run them.
+    /// Running a job through the registry pays the source's bounty from the
+    /// bond posted for that job on the source contract, and the same job
+    /// cannot be run again until the source's cooldown has passed.
+    #[ink(storage)]
+    pub struct KeeperRegistry {
+        owner: AccountId,
+        sources: Mapping<u32, JobSource>,
+        source_counter: u32,
+        /// Time each job was last run (source_id -> job_id -> timestamp)
+        last_runs: Mapping<(u32, u32), Timestamp>,
+        keeper_stats: Mapping<AccountId, KeeperStats>,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct SourceAdded {
+        #[ink(topic)]
+        source_id: u32,
+        contract: AccountId,
+        bounty: Balance,
+        cooldown: u64,
+    }
+
+    #[ink(event)]
+    pub struct SourceUpdated {
+        #[ink(topic)]
+        source_id: u32,
+        bounty: Balance,
+        cooldown: u64,
+        active: bool,
+    }
+
+    #[ink(event)]
+    pub struct JobRun {
+        #[ink(topic)]
+        source_id: u32,
+        job_id: u32,
+        #[ink(topic)]
+        keeper: AccountId,
+        bounty: Balance,
+    }
+
+    impl KeeperRegistry {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                sources: Mapping::default(),
+                source_counter: 0,
+                last_runs: Mapping::default(),
+                keeper_stats: Mapping::default(),
+            }
+        }
+
+        /// Register a contract's maintenance jobs (owner only)
+        ///
+        /// The contract pays bounties only once it names this registry as its keeper registry.
+        #[ink(message)]
+        pub fn add_source(&mut self, contract: AccountId, bounty: Balance, cooldown: u64) -> Result<u32, Error> {
+            self.ensure_owner()?;
+
+            let source_id = self.source_counter;
+            self.sources.insert(
+                source_id,
+                &JobSource {
+                    contract,
+                    bounty,
+                    cooldown,
+                    active: true,
+                },
+            );
+            self.source_counter = source_id.saturating_add(1);
+
+            self.env().emit_event(SourceAdded {
+                source_id,
+                contract,
+                bounty,
+                cooldown,
+            });
+
+            Ok(source_id)
+        }
+
+        /// Change a source's bounty and cooldown, or pause it (owner only)
+        #[ink(message)]
+        pub fn update_source(&mut self, source_id: u32, bounty: Balance, cooldown: u64, active: bool) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let mut source = self.sources.get(source_id).ok_or(Error::SourceNotFound)?;
+
+            source.bounty = bounty;
+            source.cooldown = cooldown;
+            source.active = active;
+            self.sources.insert(source_id, &source);
+
+            self.env().emit_event(SourceUpdated {
+                source_id,
+                bounty,
+                cooldown,
+                active,
+            });
+
+            Ok(())
+        }
+
+        /// Jobs of a source due among the `limit` job ids from `offset`
+        ///
+        /// Jobs still in their cooldown are left out.
+        #[ink(message)]
+        pub fn due_jobs(&self, source_id: u32, offset: u32, limit: u32) -> Result<JobPage, Error> {
+            let source = self.ensure_active(source_id)?;
+            let target: ink::contract_ref!(Maintainable) = source.contract.into();
+            let mut page = target
+                .call()
+                .due_jobs(offset, limit)
+                .try_invoke()
+                .map_err(|_| Error::CallFailed)?
+                .map_err(|_| Error::CallFailed)?;
+
+            let now = self.env().block_timestamp();
+            page.jobs.retain(|&job_id| !self.in_cooldown(source_id, &source, job_id, now));
+
+            Ok(page)
+        }
+
+        /// Run a due job, returning the bounty paid to the caller
+        ///
+        /// The job runs even when its bond cannot pay the bounty.
+        #[ink(message)]
+        pub fn run_job(&mut self, source_id: u32, job_id: u32) -> Result<Balance, Error> {
+            let keeper = self.env().caller();
+            let source = self.ensure_active(source_id)?;
+            let now = self.env().block_timestamp();
+            if self.in_cooldown(source_id, &source, job_id, now) {
+                return Err(Error::RecentlyRun);
+            }
+
+            let mut target: ink::contract_ref!(Maintainable) = source.contract.into();
+            match target.call_mut().run_job(job_id).try_invoke() {
+                Ok(Ok(Ok(()))) => {}
+                Ok(Ok(Err(error))) => return Err(Error::JobFailed(error)),
+                _ => return Err(Error::CallFailed),
+            }
+            self.last_runs.insert((source_id, job_id), &now);
+
+            let bounty = if source.bounty > 0 {
+                match target.call_mut().pay_keeper_bounty(keeper, job_id, source.bounty).try_invoke() {
+                    Ok(Ok(Ok(paid))) => paid,
+                    _ => 0,
+                }
+            } else {
+                0
+            };
+
+            let mut stats = self.get_keeper_stats(keeper);
+            stats.jobs_run = stats.jobs_run.saturating_add(1);
+            stats.bounties_earned = stats.bounties_earned.saturating_add(bounty);
+            self.keeper_stats.insert(keeper, &stats);
+
+            self.env().emit_event(JobRun {
+                source_id,
+                job_id,
+                keeper,
+                bounty,
+            });
+
+            Ok(bounty)
+        }
+
+        /// Get a source
+        #[ink(message)]
+        pub fn get_source(&self, source_id: u32) -> Option<JobSource> {
+            self.sources.get(source_id)
+        }
+
+        /// Get the number of sources
+        #[ink(message)]
+        pub fn get_source_count(&self) -> u32 {
+            self.source_counter
+        }
+
+        /// Get the time a job was last run through the registry
+        #[ink(message)]
+        pub fn get_last_run(&self, source_id: u32, job_id: u32) -> Option<Timestamp> {
+            self.last_runs.get((source_id, job_id))
+        }
+
+        /// Get the work done by a keeper
+        #[ink(message)]
+        pub fn get_keeper_stats(&self, keeper: AccountId) -> KeeperStats {
+            self.keeper_stats.get(keeper).unwrap_or_default()
+        }
+
+        /// Get the contract owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(())
+        }
+
+        fn ensure_active(&self, source_id: u32) -> Result<JobSource, Error> {
+            let source = self.sources.get(source_id).ok_or(Error::SourceNotFound)?;
+            if !source.active {
+                return Err(Error::SourceInactive);
+            }
+            Ok(source)
+        }
+
+        fn in_cooldown(&self, source_id: u32, source: &JobSource, job_id: u32, now: Timestamp) -> bool {
+            self.last_runs
+                .get((source_id, job_id))
+                .is_some_and(|last_run| now < last_run.saturating_add(source.cooldown))
+        }
+    }
+
+    impl Default for KeeperRegistry {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn sources_work() {
+            let mut registry = KeeperRegistry::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            let source_id = registry.add_source(accounts.django, 10, 1_000).unwrap();
+            assert_eq!(
+                registry.get_source(source_id),
+                Some(JobSource {
+                    contract: accounts.django,
+                    bounty: 10,
+                    cooldown: 1_000,
+                    active: true,
+                })
+            );
+            assert_eq!(registry.get_source_count(), 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(registry.add_source(accounts.bob, 10, 0), Err(Error::UnauthorizedAccess));
+            assert_eq!(registry.update_source(source_id, 0, 0, false), Err(Error::UnauthorizedAccess));
+            assert_eq!(registry.run_job(1, 0), Err(Error::SourceNotFound));
+
+            // Paused sources cannot be run or listed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            registry.update_source(source_id, 5, 0, false).unwrap();
+            assert_eq!(registry.run_job(source_id, 0), Err(Error::SourceInactive));
+            assert_eq!(registry.due_jobs(source_id, 0, 10), Err(Error::SourceInactive));
+            assert_eq!(registry.get_keeper_stats(accounts.alice), KeeperStats::default());
+        }
+
+        #[ink::test]
+        fn recently_run_jobs_are_rate_limited() {
+            let mut registry = KeeperRegistry::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let source_id = registry.add_source(accounts.django, 10, 1_000).unwrap();
+            registry.last_runs.insert((source_id, 3), &500);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_499);
+            assert_eq!(registry.run_job(source_id, 3), Err(Error::RecentlyRun));
+            assert!(!registry.in_cooldown(source_id, &registry.get_source(source_id).unwrap(), 4, 1_499));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_500);
+            assert!(!registry.in_cooldown(source_id, &registry.get_source(source_id).unwrap(), 3, 1_500));
+        }
+    }
+}
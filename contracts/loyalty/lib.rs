@@ -0,0 +1,322 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::loyalty::{Error, LoyaltyRef, Pool};
+
+#[ink::contract]
+mod loyalty {
+    use ink::codegen::TraitCallBuilder;
+    use ink::storage::Mapping;
+    use xcc_common::{PaymentLedger, PaymentObserver, BASIS_POINTS};
+
+    /// Highest cashback rate a merchant can offer, in basis points
+    pub const MAX_CASHBACK_BPS: u32 = 2_000;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        InvalidAmount,
+        InvalidRate,
+        InsufficientPoints,
+        InsufficientPool,
+        LedgerTransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::InsufficientPoints | Error::InsufficientPool => Self::InsufficientBalance,
+                Error::LedgerTransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Cashback a merchant funds for payments it receives
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Pool {
+        /// Ledger balance left to back new points
+        pub funds: Balance,
+        /// Share of each payment to the merchant accrued to the payer, in basis points
+        pub cashback_bps: u32,
+    }
+
+    /// Cashback points accrued on payments settled on a Payment ledger
+    ///
+    /// The ledger notifies this contract of every payment. Payers to a merchant
+    /// with a funded pool accrue points worth its cashback rate, each backed by
+    /// one unit moved out of the pool, so points can always be redeemed back
+    /// into ledger balance. Points cannot be transferred.
+    #[ink(storage)]
+    pub struct Loyalty {
+        /// Payment ledger the points are backed by and redeemed on
+        ledger: AccountId,
+        pools: Mapping<AccountId, Pool>,
+        points: Mapping<AccountId, Balance>,
+        /// Points accrued and not yet redeemed
+        outstanding_points: Balance,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct PoolFunded {
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: Balance,
+        funds: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PoolWithdrawn {
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CashbackRateSet {
+        #[ink(topic)]
+        merchant: AccountId,
+        cashback_bps: u32,
+    }
+
+    #[ink(event)]
+    pub struct PointsAccrued {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+        points: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PointsRedeemed {
+        #[ink(topic)]
+        account: AccountId,
+        points: Balance,
+    }
+
+    impl Loyalty {
+        /// Constructor taking the Payment ledger to observe
+        #[ink(constructor)]
+        pub fn new(ledger: AccountId) -> Self {
+            Self {
+                ledger,
+                pools: Mapping::default(),
+                points: Mapping::default(),
+                outstanding_points: 0,
+            }
+        }
+
+        /// Move ledger balance from the caller into their cashback pool
+        ///
+        /// Requires a prior `approve` of this contract on the ledger.
+        #[ink(message)]
+        pub fn fund_pool(&mut self, amount: Balance) -> Result<(), Error> {
+            let merchant = self.env().caller();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let escrow = self.env().account_id();
+            let mut ledger: ink::contract_ref!(PaymentLedger) = self.ledger.into();
+            match ledger.call_mut().transfer_from(merchant, escrow, amount).try_invoke() {
+                Ok(Ok(Ok(()))) => {}
+                _ => return Err(Error::LedgerTransferFailed),
+            }
+
+            let mut pool = self.get_pool(merchant);
+            pool.funds = pool.funds.saturating_add(amount);
+            self.pools.insert(merchant, &pool);
+
+            self.env().emit_event(PoolFunded {
+                merchant,
+                amount,
+                funds: pool.funds,
+            });
+
+            Ok(())
+        }
+
+        /// Take unused funds out of the caller's cashback pool
+        #[ink(message)]
+        pub fn withdraw_pool(&mut self, amount: Balance) -> Result<(), Error> {
+            let merchant = self.env().caller();
+            let mut pool = self.get_pool(merchant);
+            if amount > pool.funds {
+                return Err(Error::InsufficientPool);
+            }
+
+            pool.funds = pool.funds.saturating_sub(amount);
+            self.pools.insert(merchant, &pool);
+            self.pay_out(merchant, amount)?;
+
+            self.env().emit_event(PoolWithdrawn { merchant, amount });
+
+            Ok(())
+        }
+
+        /// Set the cashback rate of payments to the caller, in basis points
+        #[ink(message)]
+        pub fn set_cashback_rate(&mut self, cashback_bps: u32) -> Result<(), Error> {
+            let merchant = self.env().caller();
+            if cashback_bps > MAX_CASHBACK_BPS {
+                return Err(Error::InvalidRate);
+            }
+
+            let mut pool = self.get_pool(merchant);
+            pool.cashback_bps = cashback_bps;
+            self.pools.insert(merchant, &pool);
+
+            self.env().emit_event(CashbackRateSet { merchant, cashback_bps });
+
+            Ok(())
+        }
+
+        /// Turn the caller's points into the same amount of ledger balance
+        #[ink(message)]
+        pub fn redeem(&mut self, points: Balance) -> Result<(), Error> {
+            let account = self.env().caller();
+            let balance = self.get_points(account);
+            if points == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if points > balance {
+                return Err(Error::InsufficientPoints);
+            }
+
+            self.points.insert(account, &balance.saturating_sub(points));
+            self.outstanding_points = self.outstanding_points.saturating_sub(points);
+            self.pay_out(account, points)?;
+
+            self.env().emit_event(PointsRedeemed { account, points });
+
+            Ok(())
+        }
+
+        /// Get the points of an account
+        #[ink(message)]
+        pub fn get_points(&self, account: AccountId) -> Balance {
+            self.points.get(account).unwrap_or(0)
+        }
+
+        /// Get a merchant's cashback pool
+        #[ink(message)]
+        pub fn get_pool(&self, merchant: AccountId) -> Pool {
+            self.pools.get(merchant).unwrap_or_default()
+        }
+
+        /// Get the points accrued and not yet redeemed across all accounts
+        #[ink(message)]
+        pub fn get_outstanding_points(&self) -> Balance {
+            self.outstanding_points
+        }
+
+        /// Get the observed Payment ledger
+        #[ink(message)]
+        pub fn get_ledger(&self) -> AccountId {
+            self.ledger
+        }
+
+        fn pay_out(&self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            let mut ledger: ink::contract_ref!(PaymentLedger) = self.ledger.into();
+            match ledger.call_mut().transfer(to, amount).try_invoke() {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::LedgerTransferFailed),
+            }
+        }
+    }
+
+    impl PaymentObserver for Loyalty {
+        /// Accrue cashback points to the payer, as far as the merchant's pool covers them
+        #[ink(message)]
+        fn on_payment_settled(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), xcc_common::Error> {
+            if self.env().caller() != self.ledger {
+                return Err(Error::UnauthorizedAccess.into());
+            }
+
+            let Some(mut pool) = self.pools.get(to) else {
+                return Ok(());
+            };
+            let points = (amount.saturating_mul(u128::from(pool.cashback_bps)) / BASIS_POINTS).min(pool.funds);
+            if points == 0 {
+                return Ok(());
+            }
+
+            pool.funds = pool.funds.saturating_sub(points);
+            self.pools.insert(to, &pool);
+            self.points.insert(from, &self.get_points(from).saturating_add(points));
+            self.outstanding_points = self.outstanding_points.saturating_add(points);
+
+            self.env().emit_event(PointsAccrued {
+                account: from,
+                merchant: to,
+                points,
+            });
+
+            Ok(())
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn points_accrue_from_merchant_pools() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let ledger = accounts.django;
+            let mut loyalty = Loyalty::new(ledger);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            loyalty.set_cashback_rate(500).unwrap();
+            assert_eq!(loyalty.set_cashback_rate(MAX_CASHBACK_BPS + 1), Err(Error::InvalidRate));
+            // Funded directly, as pulling from the ledger needs a live contract
+            loyalty.pools.insert(
+                accounts.frank,
+                &Pool {
+                    funds: 70,
+                    cashback_bps: 500,
+                },
+            );
+
+            // Only the ledger reports payments
+            assert_eq!(
+                loyalty.on_payment_settled(accounts.bob, accounts.frank, 1_000),
+                Err(xcc_common::Error::UnauthorizedAccess)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(ledger);
+            loyalty.on_payment_settled(accounts.bob, accounts.frank, 1_000).unwrap();
+            assert_eq!(loyalty.get_points(accounts.bob), 50);
+
+            // Accrual stops when the pool runs dry, and payments to others earn nothing
+            loyalty.on_payment_settled(accounts.bob, accounts.frank, 1_000).unwrap();
+            loyalty.on_payment_settled(accounts.bob, accounts.eve, 1_000).unwrap();
+            assert_eq!(loyalty.get_points(accounts.bob), 70);
+            assert_eq!(loyalty.get_pool(accounts.frank).funds, 0);
+            assert_eq!(loyalty.get_outstanding_points(), 70);
+        }
+
+        #[ink::test]
+        fn redeem_needs_points() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut loyalty = Loyalty::new(accounts.django);
+
+            assert_eq!(loyalty.redeem(0), Err(Error::InvalidAmount));
+            assert_eq!(loyalty.redeem(1), Err(Error::InsufficientPoints));
+            assert_eq!(loyalty.withdraw_pool(1), Err(Error::InsufficientPool));
+        }
+    }
+}
@@ -0,0 +1,514 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::multisig::{Error, MultisigRef, Transaction};
+
+#[ink::contract]
+mod multisig {
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::CallFlags;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+
+    /// Maximum number of owners
+    pub const MAX_OWNERS: usize = 32;
+
+    /// Maximum size of the encoded arguments of a submitted call
+    pub const MAX_INPUT_LEN: usize = 1024;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        TransactionNotFound,
+        AlreadyApproved,
+        NotApproved,
+        ThresholdNotReached,
+        TransactionClosed,
+        ExecutionFailed,
+        InsufficientBalance,
+        InvalidOwners,
+        InvalidThreshold,
+        InputTooLong,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::TransactionNotFound => Self::NotFound,
+                Error::InsufficientBalance => Self::InsufficientBalance,
+                Error::ExecutionFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// A transfer or contract call awaiting approval by the owners
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Transaction {
+        pub submitter: AccountId,
+        /// Account receiving the value, or contract called
+        pub target: AccountId,
+        /// Selector of the message called, or `None` for a plain transfer
+        pub selector: Option<[u8; 4]>,
+        /// SCALE-encoded arguments of the message
+        pub input: Vec<u8>,
+        /// Native funds sent with the transfer or call
+        pub value: Balance,
+        pub approvals: u32,
+        pub executed: bool,
+        pub cancelled: bool,
+    }
+
+    /// Arguments of a submitted call, appended to its selector as they are
+    struct CallInput<'a>(&'a [u8]);
+
+    impl ink::scale::Encode for CallInput<'_> {
+        fn encode_to<T: ink::scale::Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(self.0);
+        }
+    }
+
+    /// Successful result of a call, kept encoded
+    struct CallOutput(Vec<u8>);
+
+    impl ink::scale::Decode for CallOutput {
+        fn decode<I: ink::scale::Input>(input: &mut I) -> Result<Self, ink::scale::Error> {
+            let len = input.remaining_len()?.ok_or("unknown call output length")?;
+            let mut output = ink::prelude::vec![0; len];
+            input.read(&mut output)?;
+            Ok(Self(output))
+        }
+    }
+
+    /// M-of-N multisig wallet holding native funds
+    ///
+    /// Calls must target messages returning a `Result` with a fieldless error,
+    /// like those of the 0xCC contracts, so failed calls can be told apart.
+    /// Owners and threshold are changed through transactions on the wallet itself.
+    #[ink(storage)]
+    pub struct Multisig {
+        owners: Vec<AccountId>,
+        /// Approvals a transaction needs to execute
+        threshold: u32,
+        transactions: Mapping<u32, Transaction>,
+        /// Owners that approved each transaction
+        approvals: Mapping<(u32, AccountId), ()>,
+        transaction_counter: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct Deposited {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TransactionSubmitted {
+        #[ink(topic)]
+        transaction_id: u32,
+        #[ink(topic)]
+        submitter: AccountId,
+        #[ink(topic)]
+        target: AccountId,
+        selector: Option<[u8; 4]>,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TransactionApproved {
+        #[ink(topic)]
+        transaction_id: u32,
+        #[ink(topic)]
+        owner: AccountId,
+        approvals: u32,
+    }
+
+    #[ink(event)]
+    pub struct ApprovalRevoked {
+        #[ink(topic)]
+        transaction_id: u32,
+        #[ink(topic)]
+        owner: AccountId,
+        approvals: u32,
+    }
+
+    #[ink(event)]
+    pub struct TransactionExecuted {
+        #[ink(topic)]
+        transaction_id: u32,
+        /// Encoded value returned by the call, empty for plain transfers
+        output: Vec<u8>,
+    }
+
+    #[ink(event)]
+    pub struct TransactionCancelled {
+        #[ink(topic)]
+        transaction_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct OwnersUpdated {
+        owners: Vec<AccountId>,
+        threshold: u32,
+    }
+
+    impl Multisig {
+        /// Constructor with the owners and the number of approvals required
+        #[ink(constructor)]
+        pub fn new(owners: Vec<AccountId>, threshold: u32) -> Self {
+            assert!(Self::valid_owners(&owners), "invalid owners");
+            assert!(Self::valid_threshold(owners.len(), threshold), "invalid threshold");
+            Self {
+                owners,
+                threshold,
+                transactions: Mapping::default(),
+                approvals: Mapping::default(),
+                transaction_counter: 0,
+            }
+        }
+
+        /// Add native funds to the wallet
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) {
+            let from = self.env().caller();
+            let amount = self.env().transferred_value();
+
+            if amount > 0 {
+                self.env().emit_event(Deposited { from, amount });
+            }
+        }
+
+        /// Submit a transfer or call, approved by the submitting owner
+        ///
+        /// Returns the transaction id.
+        #[ink(message)]
+        pub fn submit(
+            &mut self,
+            target: AccountId,
+            selector: Option<[u8; 4]>,
+            input: Vec<u8>,
+            value: Balance,
+        ) -> Result<u32, Error> {
+            let submitter = self.ensure_owner()?;
+            if input.len() > MAX_INPUT_LEN {
+                return Err(Error::InputTooLong);
+            }
+
+            let transaction_id = self.transaction_counter;
+            self.transactions.insert(
+                transaction_id,
+                &Transaction {
+                    submitter,
+                    target,
+                    selector,
+                    input,
+                    value,
+                    approvals: 0,
+                    executed: false,
+                    cancelled: false,
+                },
+            );
+            self.transaction_counter = transaction_id.saturating_add(1);
+
+            self.env().emit_event(TransactionSubmitted {
+                transaction_id,
+                submitter,
+                target,
+                selector,
+                value,
+            });
+
+            self.approve(transaction_id)?;
+
+            Ok(transaction_id)
+        }
+
+        /// Approve a pending transaction
+        #[ink(message)]
+        pub fn approve(&mut self, transaction_id: u32) -> Result<(), Error> {
+            let owner = self.ensure_owner()?;
+            let mut transaction = self.open_transaction(transaction_id)?;
+            if self.approvals.contains((transaction_id, owner)) {
+                return Err(Error::AlreadyApproved);
+            }
+
+            transaction.approvals = transaction.approvals.saturating_add(1);
+            self.transactions.insert(transaction_id, &transaction);
+            self.approvals.insert((transaction_id, owner), &());
+
+            self.env().emit_event(TransactionApproved {
+                transaction_id,
+                owner,
+                approvals: transaction.approvals,
+            });
+
+            Ok(())
+        }
+
+        /// Withdraw an approval of a pending transaction
+        #[ink(message)]
+        pub fn revoke(&mut self, transaction_id: u32) -> Result<(), Error> {
+            let owner = self.ensure_owner()?;
+            let mut transaction = self.open_transaction(transaction_id)?;
+            if !self.approvals.contains((transaction_id, owner)) {
+                return Err(Error::NotApproved);
+            }
+
+            transaction.approvals = transaction.approvals.saturating_sub(1);
+            self.transactions.insert(transaction_id, &transaction);
+            self.approvals.remove((transaction_id, owner));
+
+            self.env().emit_event(ApprovalRevoked {
+                transaction_id,
+                owner,
+                approvals: transaction.approvals,
+            });
+
+            Ok(())
+        }
+
+        /// Execute a transaction with enough approvals
+        ///
+        /// Anyone may execute; a failing transfer or call reverts and leaves the
+        /// transaction pending.
+        #[ink(message)]
+        pub fn execute(&mut self, transaction_id: u32) -> Result<(), Error> {
+            let mut transaction = self.open_transaction(transaction_id)?;
+            if transaction.approvals < self.threshold {
+                return Err(Error::ThresholdNotReached);
+            }
+            if transaction.value > self.env().balance() {
+                return Err(Error::InsufficientBalance);
+            }
+
+            // Marked first so the call cannot execute the transaction again
+            transaction.executed = true;
+            self.transactions.insert(transaction_id, &transaction);
+
+            let output = match transaction.selector {
+                None => {
+                    self.env()
+                        .transfer(transaction.target, transaction.value)
+                        .map_err(|_| Error::ExecutionFailed)?;
+                    Vec::new()
+                }
+                Some(selector) => {
+                    // Owner management is reached through the wallet itself
+                    let call_flags = if transaction.target == self.env().account_id() {
+                        CallFlags::ALLOW_REENTRY
+                    } else {
+                        CallFlags::empty()
+                    };
+                    let result = build_call::<Environment>()
+                        .call(transaction.target)
+                        .call_flags(call_flags)
+                        .transferred_value(transaction.value)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(selector))
+                                .push_arg(CallInput(&transaction.input)),
+                        )
+                        .returns::<Result<CallOutput, u8>>()
+                        .try_invoke();
+                    match result {
+                        Ok(Ok(Ok(CallOutput(output)))) => output,
+                        _ => return Err(Error::ExecutionFailed),
+                    }
+                }
+            };
+
+            self.env().emit_event(TransactionExecuted {
+                transaction_id,
+                output,
+            });
+
+            Ok(())
+        }
+
+        /// Drop a pending transaction (submitter only)
+        #[ink(message)]
+        pub fn cancel(&mut self, transaction_id: u32) -> Result<(), Error> {
+            let mut transaction = self.open_transaction(transaction_id)?;
+            if self.env().caller() != transaction.submitter {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            transaction.cancelled = true;
+            self.transactions.insert(transaction_id, &transaction);
+
+            self.env().emit_event(TransactionCancelled { transaction_id });
+
+            Ok(())
+        }
+
+        /// Replace the owners and threshold (through a transaction only)
+        ///
+        /// Approvals given by removed owners on pending transactions still count.
+        #[ink(message)]
+        pub fn set_owners(&mut self, owners: Vec<AccountId>, threshold: u32) -> Result<(), Error> {
+            if self.env().caller() != self.env().account_id() {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if !Self::valid_owners(&owners) {
+                return Err(Error::InvalidOwners);
+            }
+            if !Self::valid_threshold(owners.len(), threshold) {
+                return Err(Error::InvalidThreshold);
+            }
+
+            self.owners = owners.clone();
+            self.threshold = threshold;
+
+            self.env().emit_event(OwnersUpdated { owners, threshold });
+
+            Ok(())
+        }
+
+        /// Get a transaction
+        #[ink(message)]
+        pub fn get_transaction(&self, transaction_id: u32) -> Option<Transaction> {
+            self.transactions.get(transaction_id)
+        }
+
+        /// Check whether an owner approved a transaction
+        #[ink(message)]
+        pub fn has_approved(&self, transaction_id: u32, owner: AccountId) -> bool {
+            self.approvals.contains((transaction_id, owner))
+        }
+
+        /// Get the owners
+        #[ink(message)]
+        pub fn get_owners(&self) -> Vec<AccountId> {
+            self.owners.clone()
+        }
+
+        /// Get the number of approvals a transaction needs
+        #[ink(message)]
+        pub fn get_threshold(&self) -> u32 {
+            self.threshold
+        }
+
+        /// Get the number of submitted transactions
+        #[ink(message)]
+        pub fn get_transaction_count(&self) -> u32 {
+            self.transaction_counter
+        }
+
+        fn ensure_owner(&self) -> Result<AccountId, Error> {
+            let caller = self.env().caller();
+            if !self.owners.contains(&caller) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(caller)
+        }
+
+        fn open_transaction(&self, transaction_id: u32) -> Result<Transaction, Error> {
+            let transaction = self
+                .transactions
+                .get(transaction_id)
+                .ok_or(Error::TransactionNotFound)?;
+            if transaction.executed || transaction.cancelled {
+                return Err(Error::TransactionClosed);
+            }
+            Ok(transaction)
+        }
+
+        fn valid_owners(owners: &[AccountId]) -> bool {
+            !owners.is_empty()
+                && owners.len() <= MAX_OWNERS
+                && owners
+                    .iter()
+                    .enumerate()
+                    .all(|(i, owner)| !owners[..i].contains(owner))
+        }
+
+        fn valid_threshold(owner_count: usize, threshold: u32) -> bool {
+            threshold > 0 && threshold as usize <= owner_count
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn wallet() -> (Multisig, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from([0x42; 32]),
+                10_000_000,
+            );
+            let multisig = Multisig::new(ink::prelude::vec![accounts.alice, accounts.bob, accounts.charlie], 2);
+            (multisig, accounts)
+        }
+
+        #[ink::test]
+        fn transfer_needs_threshold() {
+            let (mut multisig, accounts) = wallet();
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.frank)
+                .unwrap_or(0);
+
+            let transaction_id = multisig.submit(accounts.frank, None, Vec::new(), 1_000).unwrap();
+            assert!(multisig.has_approved(transaction_id, accounts.alice));
+            assert_eq!(multisig.execute(transaction_id), Err(Error::ThresholdNotReached));
+            assert_eq!(multisig.approve(transaction_id), Err(Error::AlreadyApproved));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            multisig.approve(transaction_id).unwrap();
+            multisig.execute(transaction_id).unwrap();
+
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.frank)
+                .unwrap();
+            assert_eq!(after, before + 1_000);
+            assert!(multisig.get_transaction(transaction_id).unwrap().executed);
+            assert_eq!(multisig.execute(transaction_id), Err(Error::TransactionClosed));
+        }
+
+        #[ink::test]
+        fn revoke_and_cancel_work() {
+            let (mut multisig, accounts) = wallet();
+            let transaction_id = multisig.submit(accounts.frank, None, Vec::new(), 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(multisig.revoke(transaction_id), Err(Error::NotApproved));
+            multisig.approve(transaction_id).unwrap();
+            multisig.revoke(transaction_id).unwrap();
+            assert_eq!(multisig.get_transaction(transaction_id).unwrap().approvals, 1);
+            assert_eq!(multisig.cancel(transaction_id), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            multisig.cancel(transaction_id).unwrap();
+            assert_eq!(multisig.approve(transaction_id), Err(Error::TransactionClosed));
+        }
+
+        #[ink::test]
+        fn only_owners_take_part() {
+            let (mut multisig, accounts) = wallet();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                multisig.submit(accounts.eve, None, Vec::new(), 1_000),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(
+                multisig.set_owners(ink::prelude::vec![accounts.eve], 1),
+                Err(Error::UnauthorizedAccess)
+            );
+
+            // Owner changes come from the wallet itself
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            assert_eq!(
+                multisig.set_owners(ink::prelude::vec![accounts.eve], 2),
+                Err(Error::InvalidThreshold)
+            );
+            multisig.set_owners(ink::prelude::vec![accounts.eve], 1).unwrap();
+            assert_eq!(multisig.get_owners(), ink::prelude::vec![accounts.eve]);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::oracle::{Error, OracleRef, PricePoint};
+
+#[ink::contract]
+mod oracle {
+    use ink::storage::Mapping;
+    use xcc_common::{Currency, PriceOracle, PRICE_PRECISION};
+
+    /// Default age after which a price is no longer used
+    pub const DEFAULT_MAX_AGE: u64 = 60 * 60 * 1000;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        InvalidPrice,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Price feed key (asset, currency), with `None` for the native token
+    type FeedKey = (Option<AccountId>, Currency);
+
+    /// Latest price of an asset in a fiat currency
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PricePoint {
+        /// Smallest units of the asset per fiat minor unit, times `PRICE_PRECISION`
+        pub price: Balance,
+        pub updated_at: Timestamp,
+        pub feeder: AccountId,
+    }
+
+    /// Fiat prices of native and token assets pushed by trusted feeders
+    ///
+    /// Prices older than the maximum age are ignored by `convert`, so
+    /// settlements fail rather than use a stale rate.
+    #[ink(storage)]
+    pub struct Oracle {
+        /// Latest price of each feed
+        prices: Mapping<FeedKey, PricePoint>,
+        /// Accounts allowed to push prices besides the owner
+        feeders: Mapping<AccountId, ()>,
+        /// Age after which a price is stale
+        max_age: u64,
+        owner: AccountId,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct PriceUpdated {
+        #[ink(topic)]
+        asset: Option<AccountId>,
+        #[ink(topic)]
+        currency: Currency,
+        price: Balance,
+        #[ink(topic)]
+        feeder: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct FeederUpdated {
+        #[ink(topic)]
+        feeder: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct MaxAgeUpdated {
+        max_age: u64,
+    }
+
+    impl Oracle {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                prices: Mapping::default(),
+                feeders: Mapping::default(),
+                max_age: DEFAULT_MAX_AGE,
+                owner: Self::env().caller(),
+            }
+        }
+
+        /// Push the current price of an asset in a currency (owner or feeders)
+        #[ink(message)]
+        pub fn submit_price(&mut self, asset: Option<AccountId>, currency: Currency, price: Balance) -> Result<(), Error> {
+            let feeder = self.env().caller();
+            if feeder != self.owner && !self.feeders.contains(feeder) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if price == 0 {
+                return Err(Error::InvalidPrice);
+            }
+
+            self.prices.insert(
+                (asset, currency),
+                &PricePoint {
+                    price,
+                    updated_at: self.env().block_timestamp(),
+                    feeder,
+                },
+            );
+
+            self.env().emit_event(PriceUpdated {
+                asset,
+                currency,
+                price,
+                feeder,
+            });
+
+            Ok(())
+        }
+
+        /// Allow or disallow an account to push prices (owner only)
+        #[ink(message)]
+        pub fn set_feeder(&mut self, feeder: AccountId, allowed: bool) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            if allowed {
+                self.feeders.insert(feeder, &());
+            } else {
+                self.feeders.remove(feeder);
+            }
+
+            self.env().emit_event(FeederUpdated { feeder, allowed });
+
+            Ok(())
+        }
+
+        /// Set the age after which prices are stale (owner only)
+        #[ink(message)]
+        pub fn set_max_age(&mut self, max_age: u64) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            self.max_age = max_age;
+
+            self.env().emit_event(MaxAgeUpdated { max_age });
+
+            Ok(())
+        }
+
+        /// Get the latest price of an asset in a currency, stale or not
+        #[ink(message)]
+        pub fn get_price(&self, asset: Option<AccountId>, currency: Currency) -> Option<PricePoint> {
+            self.prices.get((asset, currency))
+        }
+
+        /// Get the latest price of an asset in a currency, if it is fresh
+        #[ink(message)]
+        pub fn get_fresh_price(&self, asset: Option<AccountId>, currency: Currency) -> Option<PricePoint> {
+            let point = self.prices.get((asset, currency))?;
+            let age = self.env().block_timestamp().saturating_sub(point.updated_at);
+            (age <= self.max_age).then_some(point)
+        }
+
+        /// Check whether an account may push prices
+        #[ink(message)]
+        pub fn is_feeder(&self, account: AccountId) -> bool {
+            account == self.owner || self.feeders.contains(account)
+        }
+
+        /// Get the age after which prices are stale
+        #[ink(message)]
+        pub fn get_max_age(&self) -> u64 {
+            self.max_age
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(())
+        }
+    }
+
+    impl Default for Oracle {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl PriceOracle for Oracle {
+        #[ink(message)]
+        fn convert(&self, asset: Option<AccountId>, currency: Currency, fiat_amount: Balance) -> Option<Balance> {
+            let point = self.get_fresh_price(asset, currency)?;
+            Some(fiat_amount.saturating_mul(point.price) / PRICE_PRECISION)
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const EUR: Currency = *b"EUR";
+
+        #[ink::test]
+        fn convert_uses_fresh_prices() {
+            let mut oracle = Oracle::new();
+            assert_eq!(oracle.convert(None, EUR, 2_500), None);
+
+            // 0.5 units of the native token per euro cent
+            oracle.submit_price(None, EUR, PRICE_PRECISION / 2).unwrap();
+            assert_eq!(oracle.convert(None, EUR, 2_500), Some(1_250));
+            assert_eq!(oracle.convert(None, *b"USD", 2_500), None);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DEFAULT_MAX_AGE + 1);
+            assert_eq!(oracle.convert(None, EUR, 2_500), None);
+            assert!(oracle.get_price(None, EUR).is_some());
+        }
+
+        #[ink::test]
+        fn only_feeders_push_prices() {
+            let mut oracle = Oracle::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(oracle.submit_price(None, EUR, 0), Err(Error::InvalidPrice));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(oracle.submit_price(None, EUR, 1), Err(Error::UnauthorizedAccess));
+            assert_eq!(oracle.set_feeder(accounts.bob, true), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            oracle.set_feeder(accounts.bob, true).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            oracle.submit_price(Some(accounts.django), EUR, 7).unwrap();
+            assert_eq!(oracle.get_price(Some(accounts.django), EUR).unwrap().feeder, accounts.bob);
+        }
+    }
+}
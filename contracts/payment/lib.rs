@@ -1,16 +1,117 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::payment::{CrossChainPaymentInitiated, Error, PaymentRef, PaymentSent, Recovery, RecoveryConfig};
+
 #[ink::contract]
 mod payment {
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
     use ink::storage::Mapping;
+    use ink::codegen::TraitCallBuilder;
+    use xcc_common::{
+        Activity, ActivityRecorder, Currency, HandleResolver, PaymentLedger, PaymentObserver, PriceOracle,
+        RecipientGuard, SessionKey, StateChunk, MAX_PAGE_SIZE, MILLISECONDS_PER_DAY,
+    };
+
+    /// Layout version of the storage written by this code
+    pub const STORAGE_VERSION: u32 = 1;
+
+    /// First error code of the contract; Payment, BillSplitting and XcmHandler
+    /// codes start at 1000, 2000 and 3000
+    pub const ERROR_CODE_BASE: u16 = 1000;
+
+    /// Most guardians an account can nominate
+    pub const MAX_GUARDIANS: u32 = 10;
+
+    /// Time between guardians approving a recovery and its execution, in milliseconds,
+    /// during which the original key can cancel it
+    pub const RECOVERY_DELAY: u64 = 3 * MILLISECONDS_PER_DAY;
 
     /// Contract errors
-    #[derive(Debug, PartialEq, Eq)]
+    ///
+    /// Discriminants are part of the interface: variants are only ever
+    /// appended, never reordered or removed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
     pub enum Error {
-        InsufficientBalance,
-        InvalidAmount,
-        SelfPayment,
+        InsufficientBalance = 0,
+        InvalidAmount = 1,
+        SelfPayment = 2,
+        InsufficientAllowance = 3,
+        UnauthorizedAccess = 4,
+        UpgradeFailed = 5,
+        StorageVersionMismatch = 6,
+        AlreadyMigrated = 7,
+        NoIdentityRegistry = 8,
+        HandleNotFound = 9,
+        RecipientNotAllowed = 10,
+        NoPriceOracle = 11,
+        PriceUnavailable = 12,
+        SlippageExceeded = 13,
+        InvalidGuardians = 14,
+        NotGuardian = 15,
+        AlreadyVoted = 16,
+        /// No longer returned: guardians may vote for different accounts
+        RecoveryMismatch = 17,
+        NoRecovery = 18,
+        /// The recovery lacks guardian votes or is still in its challenge period
+        RecoveryNotReady = 19,
+        /// The key is the caller's own, already another account's, or expires at once
+        InvalidSessionKey = 20,
+        NoSessionKey = 21,
+        /// The session key expired or would exceed its daily cap
+        SessionLimitExceeded = 22,
+    }
+
+    impl Error {
+        /// Code identifying the error across the contracts, stable between releases
+        pub fn error_code(&self) -> u16 {
+            ERROR_CODE_BASE + *self as u16
+        }
+    }
+
+    /// Guardians nominated to recover an account, and how many must agree
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RecoveryConfig {
+        pub guardians: Vec<AccountId>,
+        pub threshold: u32,
+    }
+
+    /// A recovery of an account's balance voted for by its guardians
+    ///
+    /// Each guardian votes for one account to recover to and may change or
+    /// withdraw their vote; the first account to reach the threshold receives
+    /// the balance unless it drops below it again.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Recovery {
+        /// Guardians who voted and the account each voted for
+        pub votes: Vec<(AccountId, AccountId)>,
+        /// Account to receive the balance, once enough guardians voted for it
+        pub new_account: Option<AccountId>,
+        /// When the challenge period ends, once enough guardians voted
+        pub executable_at: Option<Timestamp>,
+    }
+
+    impl Recovery {
+        /// Number of guardians voting for `new_account`
+        pub fn approvals(&self, new_account: AccountId) -> u32 {
+            self.votes.iter().filter(|(_, candidate)| *candidate == new_account).count() as u32
+        }
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientBalance => Self::InsufficientBalance,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                error => Self::Other(error as u8),
+            }
+        }
     }
 
     /// Payment contract storage
@@ -18,6 +119,8 @@ mod payment {
     pub struct Payment {
         /// User balances
         balances: Mapping<AccountId, Balance>,
+        /// Spending allowances (owner -> spender -> Balance)
+        allowances: Mapping<(AccountId, AccountId), Balance>,
         /// Transaction counter
         transaction_counter: u32,
         /// Request counter
@@ -26,6 +129,30 @@ mod payment {
         total_supply: Balance,
         /// Contract owner
         owner: AccountId,
+        /// Layout version of the stored data, behind `STORAGE_VERSION` until migrated
+        version: u32,
+        /// Identity registry resolving handles for `send_payment_to_handle`
+        identity_registry: Option<AccountId>,
+        /// Address book consulted before paying out from accounts in safe mode
+        address_book: Option<AccountId>,
+        /// Price oracle converting fiat-denominated payments
+        price_oracle: Option<AccountId>,
+        /// Loyalty program notified of every settled payment
+        loyalty_program: Option<AccountId>,
+        /// Badge contract counting the payments each account sends
+        badges: Option<AccountId>,
+        /// Guardians each account opted into social recovery with
+        recovery_configs: Mapping<AccountId, RecoveryConfig>,
+        /// Recoveries in progress (lost account -> recovery)
+        recoveries: Mapping<AccountId, Recovery>,
+        /// Session keys spending on their account's behalf (key -> session)
+        session_keys: Mapping<AccountId, SessionKey>,
+        /// Accounts ever credited, in order, for `export_balances`
+        holders: Mapping<u32, AccountId>,
+        /// Position of each account in `holders`
+        holder_indexes: Mapping<AccountId, u32>,
+        /// Number of accounts ever credited
+        holder_count: u32,
     }
 
     /// Events
@@ -39,6 +166,15 @@ mod payment {
         transaction_id: u32,
     }
 
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        amount: Balance,
+    }
+
     #[ink(event)]
     pub struct PaymentRequestCreated {
         #[ink(topic)]
@@ -72,6 +208,109 @@ mod payment {
         destination_chain: u32,
     }
 
+    #[ink(event)]
+    pub struct GuardiansSet {
+        #[ink(topic)]
+        account: AccountId,
+        guardians: Vec<AccountId>,
+        threshold: u32,
+    }
+
+    #[ink(event)]
+    pub struct RecoveryVoted {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        guardian: AccountId,
+        new_account: AccountId,
+        approvals: u32,
+        /// Set once the vote reaches the threshold
+        executable_at: Option<Timestamp>,
+    }
+
+    #[ink(event)]
+    pub struct RecoveryVoteWithdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        guardian: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RecoveryCancelled {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AccountRecovered {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        new_account: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SessionKeyAuthorized {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        key: AccountId,
+        daily_cap: Balance,
+        expires_at: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct SessionKeyRevoked {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        key: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct StorageMigrated {
+        from: u32,
+        to: u32,
+    }
+
+    #[ink(event)]
+    pub struct IdentityRegistrySet {
+        identity_registry: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct AddressBookSet {
+        address_book: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct PriceOracleSet {
+        price_oracle: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct LoyaltyProgramSet {
+        loyalty_program: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct BadgesSet {
+        badges: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
     impl Payment {
         /// Constructor that initializes the contract
         #[ink(constructor)]
@@ -80,17 +319,33 @@ mod payment {
             let mut balances = Mapping::default();
             balances.insert(caller, &initial_supply);
 
-            Self {
+            let mut instance = Self {
                 balances,
+                allowances: Mapping::default(),
                 transaction_counter: 0,
                 request_counter: 0,
                 total_supply: initial_supply,
                 owner: caller,
-            }
+                version: STORAGE_VERSION,
+                identity_registry: None,
+                address_book: None,
+                price_oracle: None,
+                loyalty_program: None,
+                badges: None,
+                recovery_configs: Mapping::default(),
+                recoveries: Mapping::default(),
+                session_keys: Mapping::default(),
+                holders: Mapping::default(),
+                holder_indexes: Mapping::default(),
+                holder_count: 0,
+            };
+            instance.index_holder(caller);
+            instance
         }
 
         /// Default constructor
         #[ink(constructor)]
+        #[allow(clippy::should_implement_trait)]
         pub fn default() -> Self {
             Self::new(1_000_000_000_000) // 1 million tokens with 6 decimals
         }
@@ -103,39 +358,146 @@ mod payment {
             amount: Balance,
         ) -> Result<u32, Error> {
             let from = self.env().caller();
-            
-            if from == to {
-                return Err(Error::SelfPayment);
+            self.transfer_from_to(from, to, amount)
+        }
+
+        /// Send payment from the account the caller is a session key of, within its daily cap
+        #[ink(message)]
+        pub fn send_payment_via_session(&mut self, to: AccountId, amount: Balance) -> Result<u32, Error> {
+            let key = self.env().caller();
+            let mut session = self.session_keys.get(key).ok_or(Error::NoSessionKey)?;
+            if !session.spend(self.env().block_number(), self.env().block_timestamp(), amount) {
+                return Err(Error::SessionLimitExceeded);
             }
 
-            if amount == 0 {
-                return Err(Error::InvalidAmount);
+            let transaction_id = self.transfer_from_to(session.account, to, amount)?;
+            self.session_keys.insert(key, &session);
+
+            Ok(transaction_id)
+        }
+
+        /// Let `key` send payments from the caller's balance, up to `daily_cap` a day until block `expires_at`
+        ///
+        /// Authorizing a key of the caller again replaces its cap and expiry.
+        #[ink(message)]
+        pub fn authorize_session_key(
+            &mut self,
+            key: AccountId,
+            daily_cap: Balance,
+            expires_at: BlockNumber,
+        ) -> Result<(), Error> {
+            let account = self.env().caller();
+            if key == account || expires_at <= self.env().block_number() {
+                return Err(Error::InvalidSessionKey);
+            }
+            if self.session_keys.get(key).is_some_and(|session| session.account != account) {
+                return Err(Error::InvalidSessionKey);
             }
 
-            let from_balance = self.balances.get(from).unwrap_or_default();
-            if from_balance < amount {
-                return Err(Error::InsufficientBalance);
+            self.session_keys.insert(key, &SessionKey::new(account, daily_cap, expires_at));
+
+            self.env().emit_event(SessionKeyAuthorized {
+                account,
+                key,
+                daily_cap,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Revoke a session key of the caller
+        #[ink(message)]
+        pub fn revoke_session_key(&mut self, key: AccountId) -> Result<(), Error> {
+            let account = self.env().caller();
+            match self.session_keys.get(key) {
+                Some(session) if session.account == account => self.session_keys.remove(key),
+                _ => return Err(Error::NoSessionKey),
             }
 
-            // Update balances
-            self.balances.insert(from, &(from_balance.saturating_sub(amount)));
-            let to_balance = self.balances.get(to).unwrap_or_default();
-            self.balances.insert(to, &(to_balance.saturating_add(amount)));
+            self.env().emit_event(SessionKeyRevoked { account, key });
 
-            let transaction_id = self.transaction_counter;
-            self.transaction_counter = self.transaction_counter.saturating_add(1);
+            Ok(())
+        }
 
-            // Emit event
-            self.env().emit_event(PaymentSent {
-                from,
-                to,
+        /// Get the session a key spends in
+        #[ink(message)]
+        pub fn get_session_key(&self, key: AccountId) -> Option<SessionKey> {
+            self.session_keys.get(key)
+        }
+
+        /// Send payment to the account currently holding a handle
+        #[ink(message)]
+        pub fn send_payment_to_handle(&mut self, handle: String, amount: Balance) -> Result<u32, Error> {
+            let registry = self.identity_registry.ok_or(Error::NoIdentityRegistry)?;
+            let registry: ink::contract_ref!(HandleResolver) = registry.into();
+            let to = registry.resolve_handle(handle).ok_or(Error::HandleNotFound)?;
+            self.send_payment(to, amount)
+        }
+
+        /// Send a payment of `fiat_amount` minor units of `currency`, converted at the oracle's price
+        ///
+        /// Fails if the converted amount exceeds `max_amount`.
+        #[ink(message)]
+        pub fn send_payment_in_currency(
+            &mut self,
+            to: AccountId,
+            currency: Currency,
+            fiat_amount: Balance,
+            max_amount: Balance,
+        ) -> Result<u32, Error> {
+            let oracle = self.price_oracle.ok_or(Error::NoPriceOracle)?;
+            let oracle: ink::contract_ref!(PriceOracle) = oracle.into();
+            let amount = oracle
+                .convert(Some(self.env().account_id()), currency, fiat_amount)
+                .ok_or(Error::PriceUnavailable)?;
+            if amount > max_amount {
+                return Err(Error::SlippageExceeded);
+            }
+            self.send_payment(to, amount)
+        }
+
+        /// Allow `spender` to move up to `amount` from the caller's balance
+        #[ink(message)]
+        pub fn approve(&mut self, spender: AccountId, amount: Balance) -> Result<(), Error> {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), &amount);
+
+            self.env().emit_event(Approval {
+                owner,
+                spender,
                 amount,
-                transaction_id,
             });
 
+            Ok(())
+        }
+
+        /// Send payment on behalf of `from` using the caller's allowance
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<u32, Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowances.get((from, spender)).unwrap_or_default();
+            if allowance < amount {
+                return Err(Error::InsufficientAllowance);
+            }
+
+            let transaction_id = self.transfer_from_to(from, to, amount)?;
+            self.allowances.insert((from, spender), &(allowance.saturating_sub(amount)));
+
             Ok(transaction_id)
         }
 
+        /// Get the amount `spender` may still move from `owner`'s balance
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
         /// Initiate cross-chain payment
         #[ink(message)]
         pub fn initiate_cross_chain_payment(
@@ -159,6 +521,8 @@ mod payment {
                 return Err(Error::InsufficientBalance);
             }
 
+            self.ensure_recipient_allowed(from, to, amount)?;
+
             // Lock funds for cross-chain transfer
             self.balances.insert(from, &(from_balance.saturating_sub(amount)));
 
@@ -177,12 +541,188 @@ mod payment {
             Ok(transaction_id)
         }
 
+        /// Nominate the guardians able to recover the caller's balance, `threshold` of whom must agree
+        ///
+        /// Replaces any earlier guardians and cancels a pending recovery. An
+        /// empty list with a zero threshold opts out of recovery.
+        #[ink(message)]
+        pub fn set_guardians(&mut self, guardians: Vec<AccountId>, threshold: u32) -> Result<(), Error> {
+            let account = self.env().caller();
+            let count = guardians.len() as u32;
+            if guardians.is_empty() {
+                if threshold != 0 {
+                    return Err(Error::InvalidGuardians);
+                }
+                self.recovery_configs.remove(account);
+            } else {
+                if threshold == 0 || threshold > count || count > MAX_GUARDIANS {
+                    return Err(Error::InvalidGuardians);
+                }
+                for (i, guardian) in guardians.iter().enumerate() {
+                    if *guardian == account || guardians[..i].contains(guardian) {
+                        return Err(Error::InvalidGuardians);
+                    }
+                }
+                self.recovery_configs.insert(
+                    account,
+                    &RecoveryConfig {
+                        guardians: guardians.clone(),
+                        threshold,
+                    },
+                );
+            }
+            self.cancel_pending_recovery(account);
+
+            self.env().emit_event(GuardiansSet {
+                account,
+                guardians,
+                threshold,
+            });
+
+            Ok(())
+        }
+
+        /// Vote, as one of its guardians, to move the balance of `account` to `new_account`
+        ///
+        /// Replaces the guardian's earlier vote. The vote bringing an account
+        /// to the threshold starts the challenge period, after which anyone
+        /// can execute the recovery.
+        #[ink(message)]
+        pub fn vote_recovery(&mut self, account: AccountId, new_account: AccountId) -> Result<(), Error> {
+            let guardian = self.env().caller();
+            let config = self.recovery_configs.get(account).ok_or(Error::NotGuardian)?;
+            if !config.guardians.contains(&guardian) {
+                return Err(Error::NotGuardian);
+            }
+            if new_account == account {
+                return Err(Error::SelfPayment);
+            }
+
+            let mut recovery = self.recoveries.get(account).unwrap_or(Recovery {
+                votes: Vec::new(),
+                new_account: None,
+                executable_at: None,
+            });
+            match recovery.votes.iter_mut().find(|(voter, _)| *voter == guardian) {
+                Some((_, candidate)) if *candidate == new_account => return Err(Error::AlreadyVoted),
+                Some((_, candidate)) => *candidate = new_account,
+                None => recovery.votes.push((guardian, new_account)),
+            }
+            self.tally_recovery(&mut recovery, config.threshold, Some(new_account));
+            self.recoveries.insert(account, &recovery);
+
+            self.env().emit_event(RecoveryVoted {
+                account,
+                guardian,
+                new_account,
+                approvals: recovery.approvals(new_account),
+                executable_at: recovery.executable_at,
+            });
+
+            Ok(())
+        }
+
+        /// Withdraw the caller's vote, as one of its guardians, on the recovery of `account`
+        #[ink(message)]
+        pub fn withdraw_recovery_vote(&mut self, account: AccountId) -> Result<(), Error> {
+            let guardian = self.env().caller();
+            let mut recovery = self.recoveries.get(account).ok_or(Error::NoRecovery)?;
+            let Some(position) = recovery.votes.iter().position(|(voter, _)| *voter == guardian) else {
+                return Err(Error::NotGuardian);
+            };
+
+            recovery.votes.swap_remove(position);
+            if recovery.votes.is_empty() {
+                self.recoveries.remove(account);
+            } else {
+                let threshold = self.recovery_configs.get(account).map_or(0, |config| config.threshold);
+                self.tally_recovery(&mut recovery, threshold, None);
+                self.recoveries.insert(account, &recovery);
+            }
+
+            self.env().emit_event(RecoveryVoteWithdrawn { account, guardian });
+
+            Ok(())
+        }
+
+        /// Cancel a recovery of the caller's balance, proving the key is not lost
+        #[ink(message)]
+        pub fn cancel_recovery(&mut self) -> Result<(), Error> {
+            let account = self.env().caller();
+            if !self.cancel_pending_recovery(account) {
+                return Err(Error::NoRecovery);
+            }
+            Ok(())
+        }
+
+        /// Move the whole balance of `account` to the account its guardians voted for
+        ///
+        /// Anyone can execute a recovery once its challenge period has passed.
+        #[ink(message)]
+        pub fn execute_recovery(&mut self, account: AccountId) -> Result<(), Error> {
+            let recovery = self.recoveries.get(account).ok_or(Error::NoRecovery)?;
+            match recovery.executable_at {
+                Some(executable_at) if self.env().block_timestamp() >= executable_at => {}
+                _ => return Err(Error::RecoveryNotReady),
+            }
+
+            let Some(new_account) = recovery.new_account else {
+                return Err(Error::RecoveryNotReady);
+            };
+            self.recoveries.remove(account);
+            let amount = self.balances.take(account).unwrap_or_default();
+            let balance = self.balances.get(new_account).unwrap_or_default();
+            self.balances.insert(new_account, &balance.saturating_add(amount));
+            self.index_holder(new_account);
+
+            self.env().emit_event(AccountRecovered {
+                account,
+                new_account,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Get the guardians an account nominated
+        #[ink(message)]
+        pub fn get_recovery_config(&self, account: AccountId) -> Option<RecoveryConfig> {
+            self.recovery_configs.get(account)
+        }
+
+        /// Get the pending recovery of an account
+        #[ink(message)]
+        pub fn get_recovery(&self, account: AccountId) -> Option<Recovery> {
+            self.recoveries.get(account)
+        }
+
         /// Get balance of account
         #[ink(message)]
         pub fn balance_of(&self, account: AccountId) -> Balance {
             self.balances.get(account).unwrap_or_default()
         }
 
+        /// Export a page of balances, a SCALE-encoded `Vec<(AccountId, Balance)>`
+        ///
+        /// Covers every account ever credited, in the order they were first
+        /// credited, including those whose balance has since dropped to zero.
+        #[ink(message)]
+        pub fn export_balances(&self, offset: u32, limit: u32) -> StateChunk {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.holder_count);
+            let balances: Vec<(AccountId, Balance)> = (offset..end)
+                .filter_map(|index| self.holders.get(index))
+                .map(|account| (account, self.balance_of(account)))
+                .collect();
+            let next_offset = (end < self.holder_count).then_some(end);
+            StateChunk::new(&balances, next_offset)
+        }
+
+        /// Get the number of accounts ever credited
+        #[ink(message)]
+        pub fn get_holder_count(&self) -> u32 {
+            self.holder_count
+        }
+
         /// Get transaction counter
         #[ink(message)]
         pub fn get_transaction_counter(&self) -> u32 {
@@ -206,6 +746,317 @@ mod payment {
         pub fn get_owner(&self) -> AccountId {
             self.owner
         }
+
+        /// Set the identity registry payments to handles are resolved against (owner only)
+        #[ink(message)]
+        pub fn set_identity_registry(&mut self, identity_registry: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.identity_registry = identity_registry;
+
+            self.env().emit_event(IdentityRegistrySet { identity_registry });
+
+            Ok(())
+        }
+
+        /// Get the identity registry payments to handles are resolved against
+        #[ink(message)]
+        pub fn get_identity_registry(&self) -> Option<AccountId> {
+            self.identity_registry
+        }
+
+        /// Set the address book enforcing its users' safe mode on payments (owner only)
+        #[ink(message)]
+        pub fn set_address_book(&mut self, address_book: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.address_book = address_book;
+
+            self.env().emit_event(AddressBookSet { address_book });
+
+            Ok(())
+        }
+
+        /// Get the address book enforcing its users' safe mode on payments
+        #[ink(message)]
+        pub fn get_address_book(&self) -> Option<AccountId> {
+            self.address_book
+        }
+
+        /// Set the price oracle converting fiat-denominated payments (owner only)
+        #[ink(message)]
+        pub fn set_price_oracle(&mut self, price_oracle: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.price_oracle = price_oracle;
+
+            self.env().emit_event(PriceOracleSet { price_oracle });
+
+            Ok(())
+        }
+
+        /// Get the price oracle converting fiat-denominated payments
+        #[ink(message)]
+        pub fn get_price_oracle(&self) -> Option<AccountId> {
+            self.price_oracle
+        }
+
+        /// Set the loyalty program notified of every settled payment (owner only)
+        #[ink(message)]
+        pub fn set_loyalty_program(&mut self, loyalty_program: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.loyalty_program = loyalty_program;
+
+            self.env().emit_event(LoyaltyProgramSet { loyalty_program });
+
+            Ok(())
+        }
+
+        /// Get the loyalty program notified of every settled payment
+        #[ink(message)]
+        pub fn get_loyalty_program(&self) -> Option<AccountId> {
+            self.loyalty_program
+        }
+
+        /// Set the badge contract counting the payments each account sends (owner only)
+        #[ink(message)]
+        pub fn set_badges(&mut self, badges: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.badges = badges;
+
+            self.env().emit_event(BadgesSet { badges });
+
+            Ok(())
+        }
+
+        /// Get the badge contract counting the payments each account sends
+        #[ink(message)]
+        pub fn get_badges(&self) -> Option<AccountId> {
+            self.badges
+        }
+
+        /// Hand the contract over to a new owner, such as a governance contract (owner only)
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.owner = new_owner;
+
+            self.env().emit_event(OwnershipTransferred { new_owner });
+
+            Ok(())
+        }
+
+        /// Get the layout version of the stored data
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.version
+        }
+
+        /// Replace the contract's code, keeping its storage and address (owner only)
+        ///
+        /// Code changing the storage layout must be followed by `migrate_from`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+
+            self.env().emit_event(CodeUpgraded { code_hash });
+
+            Ok(())
+        }
+
+        /// Migrate storage written at version `from` to `STORAGE_VERSION` (owner only)
+        ///
+        /// `from` must match the stored version, so a migration runs only once.
+        /// Layout changes bump `STORAGE_VERSION` and add their step here.
+        #[ink(message)]
+        pub fn migrate_from(&mut self, from: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if from != self.version {
+                return Err(Error::StorageVersionMismatch);
+            }
+            if from >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+
+            self.version = STORAGE_VERSION;
+
+            self.env().emit_event(StorageMigrated {
+                from,
+                to: STORAGE_VERSION,
+            });
+
+            Ok(())
+        }
+
+        /// Move balance between two accounts and record the transaction
+        fn transfer_from_to(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<u32, Error> {
+            if from == to {
+                return Err(Error::SelfPayment);
+            }
+
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let from_balance = self.balances.get(from).unwrap_or_default();
+            if from_balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.ensure_recipient_allowed(from, to, amount)?;
+
+            // Update balances
+            self.balances.insert(from, &(from_balance.saturating_sub(amount)));
+            let to_balance = self.balances.get(to).unwrap_or_default();
+            self.balances.insert(to, &(to_balance.saturating_add(amount)));
+            self.index_holder(to);
+
+            let transaction_id = self.transaction_counter;
+            self.transaction_counter = self.transaction_counter.saturating_add(1);
+
+            // Emit event
+            self.env().emit_event(PaymentSent {
+                from,
+                to,
+                amount,
+                transaction_id,
+            });
+
+            self.notify_loyalty_program(from, to, amount);
+            self.notify_badges(from);
+
+            Ok(transaction_id)
+        }
+
+        /// Pick the account a recovery goes to after its votes changed
+        ///
+        /// The chosen account keeps its challenge period while it has enough
+        /// votes; otherwise `voted_for` starts a new one if it reached the threshold.
+        fn tally_recovery(&self, recovery: &mut Recovery, threshold: u32, voted_for: Option<AccountId>) {
+            if recovery
+                .new_account
+                .is_some_and(|new_account| recovery.approvals(new_account) >= threshold)
+            {
+                return;
+            }
+
+            recovery.new_account = voted_for.filter(|&candidate| recovery.approvals(candidate) >= threshold);
+            recovery.executable_at = recovery
+                .new_account
+                .map(|_| self.env().block_timestamp().saturating_add(RECOVERY_DELAY));
+        }
+
+        /// Drop a pending recovery of `account`, returning whether there was one
+        fn cancel_pending_recovery(&mut self, account: AccountId) -> bool {
+            if self.recoveries.take(account).is_none() {
+                return false;
+            }
+            self.env().emit_event(RecoveryCancelled { account });
+            true
+        }
+
+        /// Let the loyalty program accrue points for a settled payment
+        ///
+        /// Its failures, including calls made while it is itself moving funds
+        /// on this ledger, never hold up the payment.
+        fn notify_loyalty_program(&self, from: AccountId, to: AccountId, amount: Balance) {
+            if let Some(loyalty_program) = self.loyalty_program {
+                let mut loyalty_program: ink::contract_ref!(PaymentObserver) = loyalty_program.into();
+                let _ = loyalty_program
+                    .call_mut()
+                    .on_payment_settled(from, to, amount)
+                    .try_invoke();
+            }
+        }
+
+        /// Count a payment sent by `from` towards their badges, ignoring failures
+        fn notify_badges(&self, from: AccountId) {
+            if let Some(badges) = self.badges {
+                let mut badges: ink::contract_ref!(ActivityRecorder) = badges.into();
+                let _ = badges
+                    .call_mut()
+                    .record_activity(from, Activity::PaymentSent)
+                    .try_invoke();
+            }
+        }
+
+        /// Add an account to `holders` when first credited
+        fn index_holder(&mut self, account: AccountId) {
+            if self.holder_indexes.contains(account) {
+                return;
+            }
+            self.holders.insert(self.holder_count, &account);
+            self.holder_indexes.insert(account, &self.holder_count);
+            self.holder_count = self.holder_count.saturating_add(1);
+        }
+
+        /// Check the address book lets `from` pay `amount` to `to`
+        fn ensure_recipient_allowed(&self, from: AccountId, to: AccountId, amount: Balance) -> Result<(), Error> {
+            if let Some(address_book) = self.address_book {
+                let address_book: ink::contract_ref!(RecipientGuard) = address_book.into();
+                if !address_book.may_pay(from, to, amount) {
+                    return Err(Error::RecipientNotAllowed);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl PaymentLedger for Payment {
+        #[ink(message)]
+        fn balance_of(&self, account: AccountId) -> Balance {
+            Payment::balance_of(self, account)
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, amount: Balance) -> Result<(), xcc_common::Error> {
+            self.send_payment(to, amount).map(|_| ()).map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), xcc_common::Error> {
+            Payment::transfer_from(self, from, to, amount)
+                .map(|_| ())
+                .map_err(Into::into)
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, amount: Balance) -> Result<(), xcc_common::Error> {
+            Payment::approve(self, spender, amount).map_err(Into::into)
+        }
     }
 
     /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
@@ -224,6 +1075,14 @@ mod payment {
             assert_eq!(payment.balance_of(owner), 1_000_000_000_000);
         }
 
+        #[ink::test]
+        fn error_codes_are_stable() {
+            assert_eq!(Error::InsufficientBalance.error_code(), 1000);
+            assert_eq!(Error::SessionLimitExceeded.error_code(), 1022);
+            // Discriminants double as SCALE indexes
+            assert_eq!(ink::scale::Encode::encode(&Error::NoRecovery), vec![18]);
+        }
+
         /// We test a simple payment scenario.
         #[ink::test]
         fn send_payment_works() {
@@ -277,6 +1136,331 @@ mod payment {
             let result = payment.send_payment(accounts.alice, 100);
             assert_eq!(result, Err(Error::SelfPayment));
         }
+
+        /// We test spending through an allowance.
+        #[ink::test]
+        fn transfer_from_works() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            payment.approve(accounts.bob, 300).unwrap();
+            assert_eq!(payment.allowance(accounts.alice, accounts.bob), 300);
+            
+            // Bob spends part of Alice's allowance
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            payment.transfer_from(accounts.alice, accounts.charlie, 200).unwrap();
+            assert_eq!(payment.balance_of(accounts.alice), 800);
+            assert_eq!(payment.balance_of(accounts.charlie), 200);
+            assert_eq!(payment.allowance(accounts.alice, accounts.bob), 100);
+            
+            // The remaining allowance is not enough
+            let result = payment.transfer_from(accounts.alice, accounts.charlie, 200);
+            assert_eq!(result, Err(Error::InsufficientAllowance));
+        }
+
+        #[ink::test]
+        fn export_balances_works() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            payment.send_payment(accounts.bob, 100).unwrap();
+            payment.send_payment(accounts.charlie, 50).unwrap();
+            payment.send_payment(accounts.bob, 100).unwrap();
+            assert_eq!(payment.get_holder_count(), 3);
+            
+            let chunk = payment.export_balances(0, 2);
+            assert_eq!((chunk.entries, chunk.next_offset), (2, Some(2)));
+            let balances: Vec<(AccountId, Balance)> = ink::scale::Decode::decode(&mut &chunk.data[..]).unwrap();
+            assert_eq!(balances, vec![(accounts.alice, 750), (accounts.bob, 200)]);
+            
+            let chunk = payment.export_balances(2, 2);
+            assert_eq!((chunk.entries, chunk.next_offset), (1, None));
+            let balances: Vec<(AccountId, Balance)> = ink::scale::Decode::decode(&mut &chunk.data[..]).unwrap();
+            assert_eq!(balances, vec![(accounts.charlie, 50)]);
+        }
+
+        /// We test guardians recovering a balance after the challenge period.
+        #[ink::test]
+        fn social_recovery_works() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let guardians = vec![accounts.bob, accounts.charlie, accounts.django];
+
+            assert_eq!(payment.set_guardians(guardians.clone(), 4), Err(Error::InvalidGuardians));
+            assert_eq!(
+                payment.set_guardians(vec![accounts.bob, accounts.bob], 1),
+                Err(Error::InvalidGuardians)
+            );
+            payment.set_guardians(guardians, 2).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(payment.vote_recovery(accounts.alice, accounts.eve), Err(Error::NotGuardian));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            payment.vote_recovery(accounts.alice, accounts.eve).unwrap();
+            assert_eq!(payment.vote_recovery(accounts.alice, accounts.eve), Err(Error::AlreadyVoted));
+            assert_eq!(payment.execute_recovery(accounts.alice), Err(Error::RecoveryNotReady));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            payment.vote_recovery(accounts.alice, accounts.eve).unwrap();
+            let recovery = payment.get_recovery(accounts.alice).unwrap();
+            assert_eq!(recovery.new_account, Some(accounts.eve));
+            assert_eq!(recovery.executable_at, Some(RECOVERY_DELAY));
+
+            // Still in the challenge period
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY - 1);
+            assert_eq!(payment.execute_recovery(accounts.alice), Err(Error::RecoveryNotReady));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY);
+            payment.execute_recovery(accounts.alice).unwrap();
+            assert_eq!(payment.balance_of(accounts.alice), 0);
+            assert_eq!(payment.balance_of(accounts.eve), 1000);
+            assert_eq!(payment.get_recovery(accounts.alice), None);
+        }
+
+        /// We test the original key cancelling a recovery during the challenge period.
+        #[ink::test]
+        fn original_key_cancels_recovery() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            payment.set_guardians(vec![accounts.bob], 1).unwrap();
+            assert_eq!(payment.cancel_recovery(), Err(Error::NoRecovery));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            payment.vote_recovery(accounts.alice, accounts.bob).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            payment.cancel_recovery().unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY);
+            assert_eq!(payment.execute_recovery(accounts.alice), Err(Error::NoRecovery));
+            assert_eq!(payment.balance_of(accounts.alice), 1000);
+
+            // Opting out leaves the guardians without a vote
+            payment.set_guardians(Vec::new(), 0).unwrap();
+            assert_eq!(payment.get_recovery_config(accounts.alice), None);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(payment.vote_recovery(accounts.alice, accounts.bob), Err(Error::NotGuardian));
+        }
+
+        /// We test a guardian voting first for their own account not blocking recovery.
+        #[ink::test]
+        fn malicious_guardian_cannot_block_recovery() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            payment
+                .set_guardians(vec![accounts.bob, accounts.charlie, accounts.django], 2)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            payment.vote_recovery(accounts.alice, accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            payment.vote_recovery(accounts.alice, accounts.eve).unwrap();
+            assert_eq!(payment.get_recovery(accounts.alice).unwrap().new_account, None);
+            assert_eq!(payment.execute_recovery(accounts.alice), Err(Error::RecoveryNotReady));
+
+            // Honest guardians outvote the first one
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            payment.vote_recovery(accounts.alice, accounts.eve).unwrap();
+            assert_eq!(payment.get_recovery(accounts.alice).unwrap().new_account, Some(accounts.eve));
+
+            // Withdrawing a vote below the threshold stops the challenge period
+            payment.withdraw_recovery_vote(accounts.alice).unwrap();
+            assert_eq!(payment.withdraw_recovery_vote(accounts.alice), Err(Error::NotGuardian));
+            assert_eq!(payment.get_recovery(accounts.alice).unwrap().executable_at, None);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            payment.vote_recovery(accounts.alice, accounts.eve).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY);
+            payment.execute_recovery(accounts.alice).unwrap();
+            assert_eq!(payment.balance_of(accounts.eve), 1000);
+        }
+
+        /// We test a session key spending within its cap until revoked.
+        #[ink::test]
+        fn session_keys_work() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let key = accounts.frank;
+
+            assert_eq!(payment.authorize_session_key(accounts.alice, 100, 10), Err(Error::InvalidSessionKey));
+            assert_eq!(payment.authorize_session_key(key, 100, 0), Err(Error::InvalidSessionKey));
+            payment.authorize_session_key(key, 100, 10).unwrap();
+
+            // Nobody else can claim or revoke the key
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(payment.authorize_session_key(key, 500, 10), Err(Error::InvalidSessionKey));
+            assert_eq!(payment.revoke_session_key(key), Err(Error::NoSessionKey));
+            assert_eq!(payment.send_payment_via_session(accounts.charlie, 10), Err(Error::NoSessionKey));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(key);
+            payment.send_payment_via_session(accounts.charlie, 70).unwrap();
+            assert_eq!(payment.balance_of(accounts.alice), 930);
+            assert_eq!(payment.balance_of(accounts.charlie), 70);
+            assert_eq!(
+                payment.send_payment_via_session(accounts.charlie, 31),
+                Err(Error::SessionLimitExceeded)
+            );
+
+            // The cap resets the next day
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MILLISECONDS_PER_DAY);
+            payment.send_payment_via_session(accounts.charlie, 100).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            payment.revoke_session_key(key).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(key);
+            assert_eq!(payment.send_payment_via_session(accounts.charlie, 1), Err(Error::NoSessionKey));
+        }
+
+        /// We test only the owner upgrades, and fresh storage needs no migration.
+        #[ink::test]
+        fn upgrade_and_migration_are_owner_only() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(payment.get_storage_version(), STORAGE_VERSION);
+            assert_eq!(payment.migrate_from(STORAGE_VERSION), Err(Error::AlreadyMigrated));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(payment.upgrade(Hash::from([1; 32])), Err(Error::UnauthorizedAccess));
+            assert_eq!(payment.transfer_ownership(accounts.bob), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            payment.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(payment.get_owner(), accounts.bob);
+        }
+
+        /// We test paying a handle needs an identity registry.
+        #[ink::test]
+        fn send_payment_to_handle_needs_registry() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            let result = payment.send_payment_to_handle("bob".into(), 100);
+            assert_eq!(result, Err(Error::NoIdentityRegistry));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let result = payment.set_identity_registry(Some(accounts.django));
+            assert_eq!(result, Err(Error::UnauthorizedAccess));
+            assert_eq!(payment.get_identity_registry(), None);
+            let result = payment.set_address_book(Some(accounts.django));
+            assert_eq!(result, Err(Error::UnauthorizedAccess));
+            assert_eq!(payment.get_address_book(), None);
+            let result = payment.set_loyalty_program(Some(accounts.django));
+            assert_eq!(result, Err(Error::UnauthorizedAccess));
+            assert_eq!(payment.get_loyalty_program(), None);
+            let result = payment.set_badges(Some(accounts.django));
+            assert_eq!(result, Err(Error::UnauthorizedAccess));
+            assert_eq!(payment.get_badges(), None);
+        }
+
+        #[ink::test]
+        fn send_payment_in_currency_needs_oracle() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            let result = payment.send_payment_in_currency(accounts.bob, *b"EUR", 2_500, 100);
+            assert_eq!(result, Err(Error::NoPriceOracle));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let result = payment.set_price_oracle(Some(accounts.django));
+            assert_eq!(result, Err(Error::UnauthorizedAccess));
+            assert_eq!(payment.get_price_oracle(), None);
+        }
+
+        /// We test the ledger interface maps onto the payment messages.
+        #[ink::test]
+        fn payment_ledger_works() {
+            let mut payment = Payment::new(1000);
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            PaymentLedger::transfer(&mut payment, accounts.bob, 100).unwrap();
+            assert_eq!(PaymentLedger::balance_of(&payment, accounts.bob), 100);
+            
+            // Errors surface as the shared error type
+            let result = PaymentLedger::transfer_from(&mut payment, accounts.bob, accounts.charlie, 50);
+            assert_eq!(result, Err(xcc_common::Error::Other(3)));
+        }
+    }
+
+    /// Property tests of the ledger's accounting over random sequences of calls
+    #[cfg(all(test, feature = "std"))]
+    mod invariants {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use proptest::prelude::*;
+
+        const SUPPLY: Balance = 1_000_000;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Send { from: usize, to: usize, amount: Balance },
+            Approve { owner: usize, spender: usize, amount: Balance },
+            TransferFrom { spender: usize, from: usize, to: usize, amount: Balance },
+            CrossChain { from: usize, to: usize, amount: Balance },
+        }
+
+        fn actors() -> [AccountId; 4] {
+            let accounts = test::default_accounts::<DefaultEnvironment>();
+            [accounts.alice, accounts.bob, accounts.charlie, accounts.django]
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            let actor = || 0..4usize;
+            // Mostly amounts the callers hold, and some they do not
+            let amount = || prop_oneof![3 => 0..1_000u128, 1 => 0..=SUPPLY];
+            prop_oneof![
+                (actor(), actor(), amount()).prop_map(|(from, to, amount)| Op::Send { from, to, amount }),
+                (actor(), actor(), amount()).prop_map(|(owner, spender, amount)| Op::Approve { owner, spender, amount }),
+                (actor(), actor(), actor(), amount())
+                    .prop_map(|(spender, from, to, amount)| Op::TransferFrom { spender, from, to, amount }),
+                (actor(), actor(), amount()).prop_map(|(from, to, amount)| Op::CrossChain { from, to, amount }),
+            ]
+        }
+
+        proptest! {
+            /// Balances plus funds locked for other chains always add up to the supply,
+            /// and allowances shrink by exactly what was spent through them
+            #[test]
+            fn balances_are_conserved(ops in prop::collection::vec(op(), 1..50)) {
+                test::run_test::<DefaultEnvironment, _>(|_| {
+                    let actors = actors();
+                    let mut payment = Payment::new(SUPPLY);
+                    let mut locked: Balance = 0;
+
+                    for op in ops {
+                        match op {
+                            Op::Send { from, to, amount } => {
+                                test::set_caller::<DefaultEnvironment>(actors[from]);
+                                let _ = payment.send_payment(actors[to], amount);
+                            }
+                            Op::Approve { owner, spender, amount } => {
+                                test::set_caller::<DefaultEnvironment>(actors[owner]);
+                                payment.approve(actors[spender], amount).unwrap();
+                            }
+                            Op::TransferFrom { spender, from, to, amount } => {
+                                let allowance = payment.allowance(actors[from], actors[spender]);
+                                test::set_caller::<DefaultEnvironment>(actors[spender]);
+                                let spent = match payment.transfer_from(actors[from], actors[to], amount) {
+                                    Ok(_) => amount,
+                                    Err(_) => 0,
+                                };
+                                assert_eq!(payment.allowance(actors[from], actors[spender]), allowance - spent);
+                            }
+                            Op::CrossChain { from, to, amount } => {
+                                test::set_caller::<DefaultEnvironment>(actors[from]);
+                                if payment.initiate_cross_chain_payment(actors[to], amount, 2000).is_ok() {
+                                    locked += amount;
+                                }
+                            }
+                        }
+
+                        let held: Balance = actors.iter().map(|account| payment.balance_of(*account)).sum();
+                        assert_eq!(held + locked, payment.get_total_supply(), "after {op:?}");
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            }
+        }
     }
 
 
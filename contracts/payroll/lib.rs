@@ -0,0 +1,542 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::payroll::{Employee, Error, PayrollRef, Payslip, Withholding};
+
+#[ink::contract]
+mod payroll {
+    use ink::codegen::TraitCallBuilder;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::{XcmGateway, BASIS_POINTS};
+
+    /// Maximum number of employees paid by one payroll run
+    pub const MAX_EMPLOYEES: usize = 100;
+
+    /// Maximum number of withholding splits per employee
+    pub const MAX_WITHHOLDINGS: usize = 4;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        EmployeeExists,
+        EmployeeNotFound,
+        TooManyEmployees,
+        InvalidAmount,
+        InvalidWithholdings,
+        InsufficientFunds,
+        NoXcmHandler,
+        CrossChainPaymentFailed,
+        TransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::EmployeeNotFound => Self::NotFound,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::InsufficientFunds => Self::InsufficientBalance,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Share of a salary paid to another account, like a tax authority or pension fund
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Withholding {
+        pub recipient: AccountId,
+        /// Share of the gross salary in basis points
+        pub bps: u32,
+    }
+
+    /// An employee on the payroll
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Employee {
+        /// Gross salary per pay period
+        pub salary: Balance,
+        /// Chain the net salary is sent to through the XcmHandler, or `None` to pay locally
+        pub destination_chain: Option<u32>,
+        pub withholdings: Vec<Withholding>,
+    }
+
+    /// Record of an employee's pay for one period
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Payslip {
+        pub gross: Balance,
+        /// Total paid to withholding recipients
+        pub withheld: Balance,
+        /// Amount paid to the employee
+        pub net: Balance,
+        /// XcmHandler payment carrying the net salary, for cross-chain payouts
+        pub cross_chain_payment: Option<u32>,
+        pub paid_at: Timestamp,
+    }
+
+    /// Salary distribution funded by an employer
+    ///
+    /// The employer funds the contract and calls `run_payroll` once per pay
+    /// period, which pays every employee in one go. Withholdings are paid
+    /// locally; the net salary goes out locally or through the XcmHandler, whose
+    /// fees come out of the funded budget.
+    #[ink(storage)]
+    pub struct Payroll {
+        employer: AccountId,
+        employees: Mapping<AccountId, Employee>,
+        /// Employees in the order they were added
+        employee_list: Vec<AccountId>,
+        /// Payslips by pay period and employee
+        payslips: Mapping<(u32, AccountId), Payslip>,
+        /// Funds available to pay salaries and fees
+        budget: Balance,
+        /// Number of completed payroll runs
+        period: u32,
+        xcm_handler: Option<AccountId>,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct PayrollFunded {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+        budget: Balance,
+    }
+
+    #[ink(event)]
+    pub struct EmployeeUpdated {
+        #[ink(topic)]
+        employee: AccountId,
+        salary: Balance,
+        destination_chain: Option<u32>,
+    }
+
+    #[ink(event)]
+    pub struct EmployeeRemoved {
+        #[ink(topic)]
+        employee: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct SalaryPaid {
+        #[ink(topic)]
+        period: u32,
+        #[ink(topic)]
+        employee: AccountId,
+        gross: Balance,
+        net: Balance,
+        cross_chain_payment: Option<u32>,
+    }
+
+    #[ink(event)]
+    pub struct PayrollCompleted {
+        #[ink(topic)]
+        period: u32,
+        employees: u32,
+        total: Balance,
+    }
+
+    #[ink(event)]
+    pub struct XcmHandlerSet {
+        xcm_handler: Option<AccountId>,
+    }
+
+    impl Payroll {
+        /// Constructor; the caller becomes the employer
+        #[ink(constructor)]
+        pub fn new(xcm_handler: Option<AccountId>) -> Self {
+            Self {
+                employer: Self::env().caller(),
+                employees: Mapping::default(),
+                employee_list: Vec::new(),
+                payslips: Mapping::default(),
+                budget: 0,
+                period: 0,
+                xcm_handler,
+            }
+        }
+
+        /// Add native funds to the payroll budget
+        #[ink(message, payable)]
+        pub fn fund(&mut self) {
+            let from = self.env().caller();
+            let amount = self.env().transferred_value();
+
+            if amount > 0 {
+                self.budget = self.budget.saturating_add(amount);
+                self.env().emit_event(PayrollFunded {
+                    from,
+                    amount,
+                    budget: self.budget,
+                });
+            }
+        }
+
+        /// Take unused funds out of the budget (employer only)
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<(), Error> {
+            self.ensure_employer()?;
+            if amount > self.budget {
+                return Err(Error::InsufficientFunds);
+            }
+
+            self.budget = self.budget.saturating_sub(amount);
+            self.env()
+                .transfer(self.employer, amount)
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        /// Put an account on the payroll (employer only)
+        #[ink(message)]
+        pub fn add_employee(
+            &mut self,
+            employee: AccountId,
+            salary: Balance,
+            destination_chain: Option<u32>,
+        ) -> Result<(), Error> {
+            self.ensure_employer()?;
+            if self.employees.contains(employee) {
+                return Err(Error::EmployeeExists);
+            }
+            if self.employee_list.len() >= MAX_EMPLOYEES {
+                return Err(Error::TooManyEmployees);
+            }
+
+            self.employee_list.push(employee);
+            self.store_employee(
+                employee,
+                Employee {
+                    salary,
+                    destination_chain,
+                    withholdings: Vec::new(),
+                },
+            )
+        }
+
+        /// Change an employee's salary and payout destination (employer only)
+        #[ink(message)]
+        pub fn update_employee(
+            &mut self,
+            employee: AccountId,
+            salary: Balance,
+            destination_chain: Option<u32>,
+        ) -> Result<(), Error> {
+            self.ensure_employer()?;
+            let mut record = self.employees.get(employee).ok_or(Error::EmployeeNotFound)?;
+
+            record.salary = salary;
+            record.destination_chain = destination_chain;
+            self.store_employee(employee, record)
+        }
+
+        /// Replace the withholding splits of an employee's salary (employer only)
+        ///
+        /// Splits may take at most the whole salary together.
+        #[ink(message)]
+        pub fn set_withholdings(&mut self, employee: AccountId, withholdings: Vec<Withholding>) -> Result<(), Error> {
+            self.ensure_employer()?;
+            let mut record = self.employees.get(employee).ok_or(Error::EmployeeNotFound)?;
+            let total_bps = withholdings
+                .iter()
+                .fold(0u128, |total, withholding| total.saturating_add(u128::from(withholding.bps)));
+            if withholdings.len() > MAX_WITHHOLDINGS || total_bps > BASIS_POINTS {
+                return Err(Error::InvalidWithholdings);
+            }
+
+            record.withholdings = withholdings;
+            self.store_employee(employee, record)
+        }
+
+        /// Take an account off the payroll (employer only)
+        #[ink(message)]
+        pub fn remove_employee(&mut self, employee: AccountId) -> Result<(), Error> {
+            self.ensure_employer()?;
+            if self.employees.take(employee).is_none() {
+                return Err(Error::EmployeeNotFound);
+            }
+
+            self.employee_list.retain(|account| *account != employee);
+
+            self.env().emit_event(EmployeeRemoved { employee });
+
+            Ok(())
+        }
+
+        /// Set the XcmHandler used for cross-chain payouts (employer only)
+        #[ink(message)]
+        pub fn set_xcm_handler(&mut self, xcm_handler: Option<AccountId>) -> Result<(), Error> {
+            self.ensure_employer()?;
+
+            self.xcm_handler = xcm_handler;
+
+            self.env().emit_event(XcmHandlerSet { xcm_handler });
+
+            Ok(())
+        }
+
+        /// Pay every employee for the current period and record their payslips (employer only)
+        ///
+        /// Fails as a whole if the budget does not cover all salaries and fees.
+        /// Returns the total paid out, fees included.
+        #[ink(message)]
+        pub fn run_payroll(&mut self) -> Result<Balance, Error> {
+            self.ensure_employer()?;
+
+            let period = self.period;
+            let budget_before = self.budget;
+            for employee in self.employee_list.clone() {
+                let Some(record) = self.employees.get(employee) else {
+                    continue;
+                };
+                let payslip = self.pay(employee, &record)?;
+
+                self.env().emit_event(SalaryPaid {
+                    period,
+                    employee,
+                    gross: payslip.gross,
+                    net: payslip.net,
+                    cross_chain_payment: payslip.cross_chain_payment,
+                });
+                self.payslips.insert((period, employee), &payslip);
+            }
+
+            let total = budget_before.saturating_sub(self.budget);
+            self.period = period.saturating_add(1);
+
+            self.env().emit_event(PayrollCompleted {
+                period,
+                employees: self.employee_list.len() as u32,
+                total,
+            });
+
+            Ok(total)
+        }
+
+        /// Get an employee's payroll record
+        #[ink(message)]
+        pub fn get_employee(&self, employee: AccountId) -> Option<Employee> {
+            self.employees.get(employee)
+        }
+
+        /// Get all employees
+        #[ink(message)]
+        pub fn get_employees(&self) -> Vec<AccountId> {
+            self.employee_list.clone()
+        }
+
+        /// Get an employee's payslip for a pay period
+        #[ink(message)]
+        pub fn get_payslip(&self, period: u32, employee: AccountId) -> Option<Payslip> {
+            self.payslips.get((period, employee))
+        }
+
+        /// Get the total gross salary of one payroll run, fees excluded
+        #[ink(message)]
+        pub fn get_payroll_cost(&self) -> Balance {
+            self.employee_list
+                .iter()
+                .filter_map(|employee| self.employees.get(employee))
+                .fold(0, |total: Balance, record| total.saturating_add(record.salary))
+        }
+
+        /// Get the funds available for payroll
+        #[ink(message)]
+        pub fn get_budget(&self) -> Balance {
+            self.budget
+        }
+
+        /// Get the number of completed payroll runs, which is the id of the next period
+        #[ink(message)]
+        pub fn get_period(&self) -> u32 {
+            self.period
+        }
+
+        /// Get the employer
+        #[ink(message)]
+        pub fn get_employer(&self) -> AccountId {
+            self.employer
+        }
+
+        /// Get the XcmHandler used for cross-chain payouts
+        #[ink(message)]
+        pub fn get_xcm_handler(&self) -> Option<AccountId> {
+            self.xcm_handler
+        }
+
+        /// Pay one employee's withholdings and net salary out of the budget
+        fn pay(&mut self, employee: AccountId, record: &Employee) -> Result<Payslip, Error> {
+            let mut withheld: Balance = 0;
+            for withholding in &record.withholdings {
+                let amount = record.salary.saturating_mul(u128::from(withholding.bps)) / BASIS_POINTS;
+                self.spend(amount)?;
+                self.transfer(withholding.recipient, amount)?;
+                withheld = withheld.saturating_add(amount);
+            }
+
+            let net = record.salary.saturating_sub(withheld);
+            let cross_chain_payment = match record.destination_chain {
+                Some(chain) if net > 0 => Some(self.pay_cross_chain(employee, net, chain)?),
+                _ => {
+                    self.spend(net)?;
+                    self.transfer(employee, net)?;
+                    None
+                }
+            };
+
+            Ok(Payslip {
+                gross: record.salary,
+                withheld,
+                net,
+                cross_chain_payment,
+                paid_at: self.env().block_timestamp(),
+            })
+        }
+
+        /// Send a net salary through the XcmHandler, paying its fees from the budget
+        fn pay_cross_chain(&mut self, employee: AccountId, amount: Balance, chain: u32) -> Result<u32, Error> {
+            let xcm_handler = self.xcm_handler.ok_or(Error::NoXcmHandler)?;
+            let mut gateway: ink::contract_ref!(XcmGateway) = xcm_handler.into();
+            let value = amount.saturating_add(gateway.quote_fee(chain, amount));
+            self.spend(value)?;
+
+            match gateway
+                .call_mut()
+                .send_payment(employee, amount, chain)
+                .transferred_value(value)
+                .try_invoke()
+            {
+                Ok(Ok(Ok(payment_id))) => Ok(payment_id),
+                _ => Err(Error::CrossChainPaymentFailed),
+            }
+        }
+
+        fn spend(&mut self, amount: Balance) -> Result<(), Error> {
+            self.budget = self.budget.checked_sub(amount).ok_or(Error::InsufficientFunds)?;
+            Ok(())
+        }
+
+        fn transfer(&self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            if amount == 0 {
+                return Ok(());
+            }
+            self.env().transfer(to, amount).map_err(|_| Error::TransferFailed)
+        }
+
+        fn store_employee(&mut self, employee: AccountId, record: Employee) -> Result<(), Error> {
+            self.employees.insert(employee, &record);
+
+            self.env().emit_event(EmployeeUpdated {
+                employee,
+                salary: record.salary,
+                destination_chain: record.destination_chain,
+            });
+
+            Ok(())
+        }
+
+        fn ensure_employer(&self) -> Result<(), Error> {
+            if self.env().caller() != self.employer {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(())
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn funded_payroll(budget: Balance) -> (Payroll, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from([0x42; 32]),
+                budget,
+            );
+            let mut payroll = Payroll::new(None);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(budget);
+            payroll.fund();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            (payroll, accounts)
+        }
+
+        fn balance(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(account).unwrap_or(0)
+        }
+
+        #[ink::test]
+        fn run_payroll_pays_everyone() {
+            let (mut payroll, accounts) = funded_payroll(10_000_000);
+            payroll.add_employee(accounts.bob, 3_000_000, None).unwrap();
+            payroll.add_employee(accounts.charlie, 2_000_000, None).unwrap();
+            payroll
+                .set_withholdings(
+                    accounts.bob,
+                    ink::prelude::vec![Withholding {
+                        recipient: accounts.frank,
+                        bps: 2_000,
+                    }],
+                )
+                .unwrap();
+            assert_eq!(payroll.get_payroll_cost(), 5_000_000);
+
+            let (bob, charlie, frank) = (balance(accounts.bob), balance(accounts.charlie), balance(accounts.frank));
+            assert_eq!(payroll.run_payroll(), Ok(5_000_000));
+            assert_eq!(balance(accounts.bob), bob + 2_400_000);
+            assert_eq!(balance(accounts.charlie), charlie + 2_000_000);
+            assert_eq!(balance(accounts.frank), frank + 600_000);
+
+            let payslip = payroll.get_payslip(0, accounts.bob).unwrap();
+            assert_eq!((payslip.gross, payslip.withheld, payslip.net), (3_000_000, 600_000, 2_400_000));
+            assert_eq!(payroll.get_period(), 1);
+            assert_eq!(payroll.get_budget(), 5_000_000);
+        }
+
+        #[ink::test]
+        fn run_payroll_needs_funds_and_handler() {
+            let (mut payroll, accounts) = funded_payroll(1_000_000);
+            payroll.add_employee(accounts.bob, 2_000_000, None).unwrap();
+            assert_eq!(payroll.run_payroll(), Err(Error::InsufficientFunds));
+
+            payroll.update_employee(accounts.bob, 500_000, Some(2000)).unwrap();
+            assert_eq!(payroll.run_payroll(), Err(Error::NoXcmHandler));
+        }
+
+        #[ink::test]
+        fn employer_manages_payroll() {
+            let (mut payroll, accounts) = funded_payroll(1_000_000);
+            payroll.add_employee(accounts.bob, 100, None).unwrap();
+            assert_eq!(payroll.add_employee(accounts.bob, 100, None), Err(Error::EmployeeExists));
+            assert_eq!(
+                payroll.set_withholdings(
+                    accounts.bob,
+                    ink::prelude::vec![Withholding {
+                        recipient: accounts.frank,
+                        bps: 10_001,
+                    }],
+                ),
+                Err(Error::InvalidWithholdings)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(payroll.run_payroll(), Err(Error::UnauthorizedAccess));
+            assert_eq!(payroll.update_employee(accounts.bob, 1_000, None), Err(Error::UnauthorizedAccess));
+            assert_eq!(payroll.withdraw(1), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            payroll.remove_employee(accounts.bob).unwrap();
+            assert!(payroll.get_employees().is_empty());
+            payroll.withdraw(1_000_000).unwrap();
+            assert_eq!(payroll.get_budget(), 0);
+        }
+    }
+}
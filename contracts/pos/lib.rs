@@ -0,0 +1,754 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::pos::{Error, Order, OrderAsset, OrderId, OrderStatus, PointOfSaleRef, SettlementBatch};
+
+#[ink::contract]
+mod pos {
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::{PaymentLedger, XcmGateway, XcmMessageType, MAX_PAGE_SIZE, MILLISECONDS_PER_DAY};
+
+    /// Order IDs, assigned in creation order
+    pub type OrderId = u32;
+
+    /// A merchant and a day counted from the Unix epoch
+    type MerchantDay = (AccountId, u64);
+
+    /// PSP22 `transfer` selector
+    const PSP22_TRANSFER_SELECTOR: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+    /// PSP22 `transfer_from` selector
+    const PSP22_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+    /// Errors returned by PSP22 tokens
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Psp22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        OrderNotFound,
+        InvalidAmount,
+        InvalidExpiry,
+        OrderExpired,
+        /// The order is not in the state the call needs, e.g. already paid
+        InvalidOrderStatus,
+        /// Settlement of a day opens once the day is over
+        DayNotOver,
+        NoXcmHandler,
+        /// Cross-chain payments settle native orders only
+        IncompatibleAsset,
+        PaymentMismatch,
+        PaymentAlreadyLinked,
+        PaymentNotLinked,
+        PaymentNotExecuted,
+        TransferFailed,
+        LedgerTransferFailed,
+        TokenTransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::OrderNotFound => Self::NotFound,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Asset an order is paid in
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum OrderAsset {
+        /// Native token, paid locally or cross-chain through the XcmHandler
+        Native,
+        /// Balance on a Payment ledger
+        Ledger(AccountId),
+        /// PSP22 token
+        Psp22(AccountId),
+    }
+
+    /// Lifecycle of an order
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum OrderStatus {
+        Open,
+        /// Paid into escrow, awaiting the merchant's settlement
+        Paid,
+        /// Paid out to the merchant, in a settlement batch or straight through the XcmHandler
+        Settled,
+        Refunded,
+        Cancelled,
+    }
+
+    /// An order a merchant asks a customer to pay
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Order {
+        pub merchant: AccountId,
+        pub amount: Balance,
+        pub asset: OrderAsset,
+        /// Time from which the order can no longer be paid
+        pub expires_at: Timestamp,
+        pub status: OrderStatus,
+        pub customer: Option<AccountId>,
+        pub paid_at: Option<Timestamp>,
+        /// XcmHandler payment linked to pay the order
+        pub cross_chain_payment: Option<u32>,
+    }
+
+    /// Escrowed orders of one merchant and day paid out so far
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SettlementBatch {
+        /// Orders paid out
+        pub orders: u32,
+        /// Amount paid out per asset
+        pub totals: Vec<(OrderAsset, Balance)>,
+        /// Position in the day's paid orders to continue from
+        pub cursor: u32,
+    }
+
+    /// Point-of-sale orders paid by customers and settled to merchants in daily batches
+    ///
+    /// Merchants create orders, which customers pay by ID on a Payment ledger,
+    /// in a PSP22 token or natively, with native orders also payable from
+    /// another chain through the XcmHandler. Local payments stay in escrow
+    /// until the merchant settles the day they were paid on, so an order can
+    /// be refunded from escrow until then, and from the merchant's funds after.
+    #[ink(storage)]
+    pub struct PointOfSale {
+        owner: AccountId,
+        orders: Mapping<OrderId, Order>,
+        order_counter: OrderId,
+        /// Orders paid per merchant and day (merchant -> day -> position -> order_id)
+        day_orders: Mapping<(MerchantDay, u32), OrderId>,
+        day_order_counts: Mapping<MerchantDay, u32>,
+        settlements: Mapping<MerchantDay, SettlementBatch>,
+        /// Order each linked cross-chain payment pays
+        xcm_payment_orders: Mapping<u32, OrderId>,
+        xcm_handler: Option<AccountId>,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct OrderCreated {
+        #[ink(topic)]
+        order_id: OrderId,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: Balance,
+        asset: OrderAsset,
+        expires_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct OrderPaid {
+        #[ink(topic)]
+        order_id: OrderId,
+        #[ink(topic)]
+        merchant: AccountId,
+        #[ink(topic)]
+        customer: AccountId,
+        amount: Balance,
+        cross_chain_payment: Option<u32>,
+    }
+
+    #[ink(event)]
+    pub struct OrderCancelled {
+        #[ink(topic)]
+        order_id: OrderId,
+    }
+
+    #[ink(event)]
+    pub struct OrderRefunded {
+        #[ink(topic)]
+        order_id: OrderId,
+        #[ink(topic)]
+        customer: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CrossChainPaymentLinked {
+        #[ink(topic)]
+        order_id: OrderId,
+        payment_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct DaySettled {
+        #[ink(topic)]
+        merchant: AccountId,
+        day: u64,
+        orders: u32,
+        totals: Vec<(OrderAsset, Balance)>,
+    }
+
+    #[ink(event)]
+    pub struct XcmHandlerSet {
+        xcm_handler: Option<AccountId>,
+    }
+
+    impl PointOfSale {
+        /// Constructor; the caller becomes the owner
+        #[ink(constructor)]
+        pub fn new(xcm_handler: Option<AccountId>) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                orders: Mapping::default(),
+                order_counter: 0,
+                day_orders: Mapping::default(),
+                day_order_counts: Mapping::default(),
+                settlements: Mapping::default(),
+                xcm_payment_orders: Mapping::default(),
+                xcm_handler,
+            }
+        }
+
+        /// Create an order payable until `expires_at`, returning its ID
+        #[ink(message)]
+        pub fn create_order(
+            &mut self,
+            amount: Balance,
+            asset: OrderAsset,
+            expires_at: Timestamp,
+        ) -> Result<OrderId, Error> {
+            let merchant = self.env().caller();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if expires_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+
+            let order_id = self.order_counter;
+            self.order_counter = self.order_counter.saturating_add(1);
+            self.orders.insert(
+                order_id,
+                &Order {
+                    merchant,
+                    amount,
+                    asset,
+                    expires_at,
+                    status: OrderStatus::Open,
+                    customer: None,
+                    paid_at: None,
+                    cross_chain_payment: None,
+                },
+            );
+
+            self.env().emit_event(OrderCreated {
+                order_id,
+                merchant,
+                amount,
+                asset,
+                expires_at,
+            });
+
+            Ok(order_id)
+        }
+
+        /// Pay an order into escrow
+        ///
+        /// Native orders expect the amount as transferred value; others pull it
+        /// from the caller, which requires a prior `approve` of this contract.
+        #[ink(message, payable)]
+        pub fn pay_order(&mut self, order_id: OrderId) -> Result<(), Error> {
+            let customer = self.env().caller();
+            let mut order = self.payable_order(order_id)?;
+
+            self.collect(order.asset, customer, order.amount)?;
+            self.mark_paid(order_id, &mut order, customer, OrderStatus::Paid);
+
+            Ok(())
+        }
+
+        /// Link a cross-chain payment created on the XcmHandler to pay a native order
+        ///
+        /// The payment must be a `Payment` from the caller to the merchant over
+        /// the order amount. It goes straight to the merchant, so the order is
+        /// settled, not escrowed, once the payment executes.
+        #[ink(message)]
+        pub fn link_cross_chain_payment(&mut self, order_id: OrderId, payment_id: u32) -> Result<(), Error> {
+            let customer = self.env().caller();
+            let mut order = self.payable_order(order_id)?;
+            if order.asset != OrderAsset::Native {
+                return Err(Error::IncompatibleAsset);
+            }
+            if self.xcm_payment_orders.contains(payment_id) {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            let xcm_handler = self.xcm_handler.ok_or(Error::NoXcmHandler)?;
+
+            let xcm_handler: ink::contract_ref!(XcmGateway) = xcm_handler.into();
+            let payment = xcm_handler
+                .payment_summary(payment_id)
+                .ok_or(Error::PaymentMismatch)?;
+            if payment.sender != customer
+                || payment.recipient != order.merchant
+                || payment.amount != order.amount
+                || payment.message_type != XcmMessageType::Payment
+            {
+                return Err(Error::PaymentMismatch);
+            }
+
+            order.cross_chain_payment = Some(payment_id);
+            order.customer = Some(customer);
+            self.orders.insert(order_id, &order);
+            self.xcm_payment_orders.insert(payment_id, &order_id);
+
+            self.env().emit_event(CrossChainPaymentLinked { order_id, payment_id });
+
+            // Payments that already went through settle right away
+            if payment.executed {
+                self.mark_paid(order_id, &mut order, customer, OrderStatus::Settled);
+            }
+
+            Ok(())
+        }
+
+        /// Mark the order linked to an executed cross-chain payment as settled
+        ///
+        /// Anyone may confirm once the XcmHandler reports the payment as executed.
+        #[ink(message)]
+        pub fn confirm_cross_chain_payment(&mut self, payment_id: u32) -> Result<(), Error> {
+            let order_id = self.xcm_payment_orders.get(payment_id).ok_or(Error::PaymentNotLinked)?;
+            let mut order = self.orders.get(order_id).ok_or(Error::OrderNotFound)?;
+            if order.status != OrderStatus::Open {
+                return Err(Error::InvalidOrderStatus);
+            }
+            let xcm_handler = self.xcm_handler.ok_or(Error::NoXcmHandler)?;
+
+            let xcm_handler: ink::contract_ref!(XcmGateway) = xcm_handler.into();
+            let executed = xcm_handler
+                .payment_summary(payment_id)
+                .is_some_and(|payment| payment.executed);
+            if !executed {
+                return Err(Error::PaymentNotExecuted);
+            }
+
+            let customer = order.customer.ok_or(Error::PaymentNotLinked)?;
+            self.mark_paid(order_id, &mut order, customer, OrderStatus::Settled);
+
+            Ok(())
+        }
+
+        /// Cancel an open order (merchant only)
+        #[ink(message)]
+        pub fn cancel_order(&mut self, order_id: OrderId) -> Result<(), Error> {
+            let mut order = self.merchant_order(order_id)?;
+            if order.status != OrderStatus::Open {
+                return Err(Error::InvalidOrderStatus);
+            }
+            if order.cross_chain_payment.is_some() {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+
+            order.status = OrderStatus::Cancelled;
+            self.orders.insert(order_id, &order);
+
+            self.env().emit_event(OrderCancelled { order_id });
+
+            Ok(())
+        }
+
+        /// Refund a paid order to its customer (merchant only)
+        ///
+        /// Escrowed orders are refunded from escrow. Settled orders are refunded
+        /// from the merchant: native ones expect the amount as transferred
+        /// value, others pull it from the merchant like `pay_order`.
+        #[ink(message, payable)]
+        pub fn refund_order(&mut self, order_id: OrderId) -> Result<(), Error> {
+            let mut order = self.merchant_order(order_id)?;
+            let customer = order.customer.ok_or(Error::InvalidOrderStatus)?;
+            match order.status {
+                OrderStatus::Paid => {
+                    if self.env().transferred_value() != 0 {
+                        return Err(Error::InvalidAmount);
+                    }
+                }
+                OrderStatus::Settled => self.collect(order.asset, order.merchant, order.amount)?,
+                _ => return Err(Error::InvalidOrderStatus),
+            }
+
+            order.status = OrderStatus::Refunded;
+            self.orders.insert(order_id, &order);
+            self.release(order.asset, customer, order.amount)?;
+
+            self.env().emit_event(OrderRefunded {
+                order_id,
+                customer,
+                amount: order.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Pay out the caller's escrowed orders paid on `day`, counted from the Unix epoch
+        ///
+        /// Settles up to `MAX_PAGE_SIZE` orders per call, one transfer per
+        /// asset; call again to continue a busy day. Returns the number of
+        /// orders paid out.
+        #[ink(message)]
+        pub fn settle_day(&mut self, day: u64) -> Result<u32, Error> {
+            let merchant = self.env().caller();
+            if day >= self.env().block_timestamp() / MILLISECONDS_PER_DAY {
+                return Err(Error::DayNotOver);
+            }
+
+            let mut batch = self.settlements.get((merchant, day)).unwrap_or_default();
+            let count = self.day_order_counts.get((merchant, day)).unwrap_or(0);
+            let end = count.min(batch.cursor.saturating_add(MAX_PAGE_SIZE));
+            let mut totals: Vec<(OrderAsset, Balance)> = Vec::new();
+            let mut orders: u32 = 0;
+
+            for position in batch.cursor..end {
+                let Some(order_id) = self.day_orders.get(((merchant, day), position)) else {
+                    continue;
+                };
+                let Some(mut order) = self.orders.get(order_id) else {
+                    continue;
+                };
+                if order.status != OrderStatus::Paid {
+                    continue;
+                }
+                order.status = OrderStatus::Settled;
+                self.orders.insert(order_id, &order);
+                Self::add_total(&mut totals, order.asset, order.amount);
+                orders = orders.saturating_add(1);
+            }
+
+            for (asset, amount) in &totals {
+                self.release(*asset, merchant, *amount)?;
+                Self::add_total(&mut batch.totals, *asset, *amount);
+            }
+            batch.orders = batch.orders.saturating_add(orders);
+            batch.cursor = end;
+            self.settlements.insert((merchant, day), &batch);
+
+            self.env().emit_event(DaySettled {
+                merchant,
+                day,
+                orders,
+                totals,
+            });
+
+            Ok(orders)
+        }
+
+        /// Set the XcmHandler carrying cross-chain payments (owner only)
+        #[ink(message)]
+        pub fn set_xcm_handler(&mut self, xcm_handler: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.xcm_handler = xcm_handler;
+
+            self.env().emit_event(XcmHandlerSet { xcm_handler });
+
+            Ok(())
+        }
+
+        /// Get an order
+        #[ink(message)]
+        pub fn get_order(&self, order_id: OrderId) -> Option<Order> {
+            self.orders.get(order_id)
+        }
+
+        /// Get the number of a merchant's orders paid on a day
+        #[ink(message)]
+        pub fn get_paid_order_count(&self, merchant: AccountId, day: u64) -> u32 {
+            self.day_order_counts.get((merchant, day)).unwrap_or(0)
+        }
+
+        /// Get a page of a merchant's orders paid on a day, in payment order
+        ///
+        /// Returns at most `MAX_PAGE_SIZE` orders.
+        #[ink(message)]
+        pub fn get_paid_orders(&self, merchant: AccountId, day: u64, offset: u32, limit: u32) -> Vec<(OrderId, Order)> {
+            let count = self.get_paid_order_count(merchant, day);
+            let end = count.min(offset.saturating_add(limit.min(MAX_PAGE_SIZE)));
+            (offset..end)
+                .filter_map(|position| self.day_orders.get(((merchant, day), position)))
+                .filter_map(|order_id| self.orders.get(order_id).map(|order| (order_id, order)))
+                .collect()
+        }
+
+        /// Get what was settled of a merchant's orders paid on a day
+        #[ink(message)]
+        pub fn get_settlement(&self, merchant: AccountId, day: u64) -> Option<SettlementBatch> {
+            self.settlements.get((merchant, day))
+        }
+
+        /// Get the XcmHandler carrying cross-chain payments
+        #[ink(message)]
+        pub fn get_xcm_handler(&self) -> Option<AccountId> {
+            self.xcm_handler
+        }
+
+        /// Get the contract owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// An open, unexpired order no cross-chain payment is linked to
+        fn payable_order(&self, order_id: OrderId) -> Result<Order, Error> {
+            let order = self.orders.get(order_id).ok_or(Error::OrderNotFound)?;
+            if order.status != OrderStatus::Open {
+                return Err(Error::InvalidOrderStatus);
+            }
+            if self.env().block_timestamp() >= order.expires_at {
+                return Err(Error::OrderExpired);
+            }
+            if order.cross_chain_payment.is_some() {
+                return Err(Error::PaymentAlreadyLinked);
+            }
+            Ok(order)
+        }
+
+        /// An order of the caller
+        fn merchant_order(&self, order_id: OrderId) -> Result<Order, Error> {
+            let order = self.orders.get(order_id).ok_or(Error::OrderNotFound)?;
+            if order.merchant != self.env().caller() {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(order)
+        }
+
+        /// Record an order as paid today, in the merchant's paid orders of the day
+        fn mark_paid(&mut self, order_id: OrderId, order: &mut Order, customer: AccountId, status: OrderStatus) {
+            let now = self.env().block_timestamp();
+            order.status = status;
+            order.customer = Some(customer);
+            order.paid_at = Some(now);
+            self.orders.insert(order_id, order);
+
+            let key = (order.merchant, now / MILLISECONDS_PER_DAY);
+            let position = self.day_order_counts.get(key).unwrap_or(0);
+            self.day_orders.insert((key, position), &order_id);
+            self.day_order_counts.insert(key, &position.saturating_add(1));
+
+            self.env().emit_event(OrderPaid {
+                order_id,
+                merchant: order.merchant,
+                customer,
+                amount: order.amount,
+                cross_chain_payment: order.cross_chain_payment,
+            });
+        }
+
+        fn add_total(totals: &mut Vec<(OrderAsset, Balance)>, asset: OrderAsset, amount: Balance) {
+            match totals.iter_mut().find(|(total_asset, _)| *total_asset == asset) {
+                Some((_, total)) => *total = total.saturating_add(amount),
+                None => totals.push((asset, amount)),
+            }
+        }
+
+        /// Move a payment into this contract's escrow
+        fn collect(&mut self, asset: OrderAsset, from: AccountId, amount: Balance) -> Result<(), Error> {
+            let transferred = self.env().transferred_value();
+            if asset != OrderAsset::Native && transferred != 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let escrow = self.env().account_id();
+
+            match asset {
+                OrderAsset::Native if transferred != amount => Err(Error::InvalidAmount),
+                OrderAsset::Native => Ok(()),
+                OrderAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer_from(from, escrow, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                OrderAsset::Psp22(token) => {
+                    let result = build_call::<Environment>()
+                        .call(token)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                                .push_arg(from)
+                                .push_arg(escrow)
+                                .push_arg(amount)
+                                .push_arg(Vec::<u8>::new()),
+                        )
+                        .returns::<Result<(), Psp22Error>>()
+                        .try_invoke();
+                    match result {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::TokenTransferFailed),
+                    }
+                }
+            }
+        }
+
+        /// Pay escrowed funds out of this contract
+        fn release(&mut self, asset: OrderAsset, to: AccountId, amount: Balance) -> Result<(), Error> {
+            match asset {
+                OrderAsset::Native => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::TransferFailed),
+                OrderAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer(to, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                OrderAsset::Psp22(token) => {
+                    let result = build_call::<Environment>()
+                        .call(token)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(PSP22_TRANSFER_SELECTOR))
+                                .push_arg(to)
+                                .push_arg(amount)
+                                .push_arg(Vec::<u8>::new()),
+                        )
+                        .returns::<Result<(), Psp22Error>>()
+                        .try_invoke();
+                    match result {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::TokenTransferFailed),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const PRICE: Balance = 5_000_000;
+
+        fn point_of_sale() -> (PointOfSale, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from([0x42; 32]),
+                10 * PRICE,
+            );
+            (PointOfSale::new(None), accounts)
+        }
+
+        fn balance(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(account).unwrap()
+        }
+
+        /// Native order of `PRICE` by Eve, paid by Bob
+        fn paid_order(pos: &mut PointOfSale, accounts: &ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) -> OrderId {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            let order_id = pos.create_order(PRICE, OrderAsset::Native, 1_000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(PRICE);
+            pos.pay_order(order_id).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            order_id
+        }
+
+        #[ink::test]
+        fn orders_settle_in_daily_batches() {
+            let (mut pos, accounts) = point_of_sale();
+            let first = paid_order(&mut pos, &accounts);
+            let second = paid_order(&mut pos, &accounts);
+
+            let order = pos.get_order(first).unwrap();
+            assert_eq!(order.status, OrderStatus::Paid);
+            assert_eq!(order.customer, Some(accounts.bob));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(PRICE);
+            assert_eq!(pos.pay_order(first), Err(Error::InvalidOrderStatus));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(pos.get_paid_order_count(accounts.eve, 0), 2);
+            let paid: Vec<OrderId> = pos.get_paid_orders(accounts.eve, 0, 0, 10).into_iter().map(|(id, _)| id).collect();
+            assert_eq!(paid, [first, second]);
+
+            // Day 0 settles once it is over, paying Eve both orders at once
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(pos.settle_day(0), Err(Error::DayNotOver));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MILLISECONDS_PER_DAY);
+            let before = balance(accounts.eve);
+            assert_eq!(pos.settle_day(0), Ok(2));
+            assert_eq!(balance(accounts.eve), before + 2 * PRICE);
+            assert_eq!(pos.get_order(second).unwrap().status, OrderStatus::Settled);
+            assert_eq!(
+                pos.get_settlement(accounts.eve, 0).unwrap().totals,
+                vec![(OrderAsset::Native, 2 * PRICE)]
+            );
+            assert_eq!(pos.settle_day(0), Ok(0));
+
+            // Orders cannot be paid once expired
+            let expired = pos.create_order(PRICE, OrderAsset::Native, MILLISECONDS_PER_DAY + 1).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MILLISECONDS_PER_DAY + 1);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(PRICE);
+            assert_eq!(pos.pay_order(expired), Err(Error::OrderExpired));
+        }
+
+        #[ink::test]
+        fn refunds_come_from_escrow_then_the_merchant() {
+            let (mut pos, accounts) = point_of_sale();
+            let escrowed = paid_order(&mut pos, &accounts);
+            let settled = paid_order(&mut pos, &accounts);
+
+            assert_eq!(pos.refund_order(escrowed), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            let before = balance(accounts.bob);
+            pos.refund_order(escrowed).unwrap();
+            assert_eq!(balance(accounts.bob), before + PRICE);
+            assert_eq!(pos.refund_order(escrowed), Err(Error::InvalidOrderStatus));
+
+            // The refunded order is left out of the settlement
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MILLISECONDS_PER_DAY);
+            assert_eq!(pos.settle_day(0), Ok(1));
+
+            // Settled orders are refunded with the merchant's funds
+            assert_eq!(pos.refund_order(settled), Err(Error::InvalidAmount));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(PRICE);
+            pos.refund_order(settled).unwrap();
+            assert_eq!(pos.get_order(settled).unwrap().status, OrderStatus::Refunded);
+        }
+
+        #[ink::test]
+        fn cancelled_and_cross_chain_orders() {
+            let (mut pos, accounts) = point_of_sale();
+            let order_id = pos.create_order(PRICE, OrderAsset::Native, 1_000).unwrap();
+            let ledger_order = pos.create_order(PRICE, OrderAsset::Ledger(accounts.django), 1_000).unwrap();
+            assert_eq!(pos.create_order(PRICE, OrderAsset::Native, 0), Err(Error::InvalidExpiry));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(pos.link_cross_chain_payment(order_id, 0), Err(Error::NoXcmHandler));
+            assert_eq!(pos.link_cross_chain_payment(ledger_order, 0), Err(Error::IncompatibleAsset));
+            assert_eq!(pos.confirm_cross_chain_payment(0), Err(Error::PaymentNotLinked));
+            assert_eq!(pos.cancel_order(order_id), Err(Error::UnauthorizedAccess));
+            assert_eq!(pos.set_xcm_handler(Some(accounts.django)), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            pos.cancel_order(order_id).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(PRICE);
+            assert_eq!(pos.pay_order(order_id), Err(Error::InvalidOrderStatus));
+        }
+    }
+}
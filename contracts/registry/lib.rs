@@ -0,0 +1,299 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::registry::{Error, RegistryRef};
+
+#[ink::contract]
+mod registry {
+    use ink::storage::Mapping;
+    pub use xcc_common::Component;
+    use xcc_common::ComponentRegistry;
+
+    /// Layout version of the storage written by this code
+    pub const STORAGE_VERSION: u32 = 1;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        VersionNotFound,
+        UpgradeFailed,
+        StorageVersionMismatch,
+        AlreadyMigrated,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::VersionNotFound => Self::NotFound,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Registry of the canonical addresses of the 0xCC contracts
+    ///
+    /// Every registration adds a new version of a component; earlier versions
+    /// stay resolvable so integrations can pin the one they were built against.
+    #[ink(storage)]
+    pub struct Registry {
+        /// Address of each registered version of a component
+        addresses: Mapping<(Component, u32), AccountId>,
+        /// Number of versions registered for each component
+        version_counts: Mapping<Component, u32>,
+        /// Version each component currently resolves to, starting at 1
+        current_versions: Mapping<Component, u32>,
+        owner: AccountId,
+        /// Layout version of the stored data, behind `STORAGE_VERSION` until migrated
+        version: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct ComponentRegistered {
+        #[ink(topic)]
+        component: Component,
+        version: u32,
+        address: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ComponentRolledBack {
+        #[ink(topic)]
+        component: Component,
+        version: u32,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct StorageMigrated {
+        from: u32,
+        to: u32,
+    }
+
+    impl Registry {
+        /// Constructor that makes the caller the owner
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                addresses: Mapping::default(),
+                version_counts: Mapping::default(),
+                current_versions: Mapping::default(),
+                owner: Self::env().caller(),
+                version: STORAGE_VERSION,
+            }
+        }
+
+        /// Record a new version of a component and return its version number (owner only)
+        #[ink(message)]
+        pub fn register(&mut self, component: Component, address: AccountId) -> Result<u32, Error> {
+            self.ensure_owner()?;
+
+            let version = self.version_counts.get(component).unwrap_or(0).saturating_add(1);
+            self.addresses.insert((component, version), &address);
+            self.version_counts.insert(component, &version);
+            self.current_versions.insert(component, &version);
+
+            self.env().emit_event(ComponentRegistered {
+                component,
+                version,
+                address,
+            });
+
+            Ok(version)
+        }
+
+        /// Make an earlier version of a component the one it resolves to (owner only)
+        ///
+        /// Later versions stay recorded and can be restored the same way.
+        #[ink(message)]
+        pub fn roll_back(&mut self, component: Component, version: u32) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if !self.addresses.contains((component, version)) {
+                return Err(Error::VersionNotFound);
+            }
+
+            self.current_versions.insert(component, &version);
+
+            self.env().emit_event(ComponentRolledBack { component, version });
+
+            Ok(())
+        }
+
+        /// Hand the registry over to a new owner
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            self.owner = new_owner;
+
+            self.env().emit_event(OwnershipTransferred { new_owner });
+
+            Ok(())
+        }
+
+        /// Get the address of a component, at its current version
+        #[ink(message)]
+        pub fn get_address(&self, component: Component) -> Option<AccountId> {
+            self.addresses.get((component, self.current_versions.get(component)?))
+        }
+
+        /// Get the address of a specific version of a component
+        #[ink(message)]
+        pub fn get_address_at(&self, component: Component, version: u32) -> Option<AccountId> {
+            self.addresses.get((component, version))
+        }
+
+        /// Get the version a component resolves to, 0 if it was never registered
+        #[ink(message)]
+        pub fn current_version(&self, component: Component) -> u32 {
+            self.current_versions.get(component).unwrap_or(0)
+        }
+
+        /// Get the owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Get the layout version of the stored data
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.version
+        }
+
+        /// Replace the contract's code, keeping its storage and address (owner only)
+        ///
+        /// Code changing the storage layout must be followed by `migrate_from`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+
+            self.env().emit_event(CodeUpgraded { code_hash });
+
+            Ok(())
+        }
+
+        /// Migrate storage written at version `from` to `STORAGE_VERSION` (owner only)
+        ///
+        /// `from` must match the stored version, so a migration runs only once.
+        /// Layout changes bump `STORAGE_VERSION` and add their step here.
+        #[ink(message)]
+        pub fn migrate_from(&mut self, from: u32) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if from != self.version {
+                return Err(Error::StorageVersionMismatch);
+            }
+            if from >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+
+            self.version = STORAGE_VERSION;
+
+            self.env().emit_event(StorageMigrated {
+                from,
+                to: STORAGE_VERSION,
+            });
+
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(())
+        }
+    }
+
+    impl Default for Registry {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl ComponentRegistry for Registry {
+        #[ink(message)]
+        fn resolve(&self, component: Component) -> Option<AccountId> {
+            self.get_address(component)
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn register_works() {
+            let mut registry = Registry::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            assert_eq!(registry.resolve(Component::XcmHandler), None);
+            assert_eq!(registry.register(Component::XcmHandler, accounts.bob), Ok(1));
+            assert_eq!(registry.register(Component::XcmHandler, accounts.charlie), Ok(2));
+            assert_eq!(registry.register(Component::Payment, accounts.django), Ok(1));
+
+            assert_eq!(registry.resolve(Component::XcmHandler), Some(accounts.charlie));
+            assert_eq!(registry.get_address_at(Component::XcmHandler, 1), Some(accounts.bob));
+            assert_eq!(registry.current_version(Component::BillSplitting), 0);
+
+            // Earlier versions can be restored
+            assert_eq!(registry.roll_back(Component::XcmHandler, 3), Err(Error::VersionNotFound));
+            registry.roll_back(Component::XcmHandler, 1).unwrap();
+            assert_eq!(registry.resolve(Component::XcmHandler), Some(accounts.bob));
+            assert_eq!(registry.current_version(Component::XcmHandler), 1);
+
+            // New registrations never overwrite a rolled back version
+            assert_eq!(registry.register(Component::XcmHandler, accounts.eve), Ok(3));
+            assert_eq!(registry.get_address_at(Component::XcmHandler, 2), Some(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn only_owner_registers() {
+            let mut registry = Registry::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                registry.register(Component::Payment, accounts.bob),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(registry.transfer_ownership(accounts.bob), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            registry.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(registry.get_owner(), accounts.bob);
+            assert_eq!(
+                registry.register(Component::Payment, accounts.bob),
+                Err(Error::UnauthorizedAccess)
+            );
+        }
+
+        #[ink::test]
+        fn upgrade_and_migration_are_owner_only() {
+            let mut contract = Registry::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate_from(STORAGE_VERSION), Err(Error::AlreadyMigrated));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(contract.upgrade(Hash::from([1; 32])), Err(Error::UnauthorizedAccess));
+        }
+    }
+}
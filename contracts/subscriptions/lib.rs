@@ -0,0 +1,788 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::subscriptions::{CancellationReason, Error, Plan, Subscription, SubscriptionsRef};
+
+#[ink::contract]
+mod subscriptions {
+    use ink::codegen::TraitCallBuilder;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::{JobPage, KeeperBond, KeeperStake, Maintainable, PaymentLedger, MAX_PAGE_SIZE, MILLISECONDS_PER_DAY};
+
+    /// Maximum number of subscriptions charged in one `charge_due` call
+    pub const MAX_CHARGE_BATCH: usize = 50;
+
+    /// Shortest billing interval a plan can have
+    pub const MIN_INTERVAL: u64 = MILLISECONDS_PER_DAY;
+
+    /// Default time a failing subscription is retried before it is cancelled
+    pub const DEFAULT_GRACE_PERIOD: u64 = 3 * MILLISECONDS_PER_DAY;
+
+    /// Default number of failed charges after which a subscription past its grace period is cancelled
+    pub const DEFAULT_MAX_FAILURES: u32 = 3;
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        PlanNotFound,
+        PlanInactive,
+        SubscriptionNotFound,
+        SubscriptionInactive,
+        InvalidAmount,
+        InvalidInterval,
+        BatchTooLarge,
+        /// The subscription cannot be charged yet
+        NotDue,
+        TransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::PlanNotFound | Error::SubscriptionNotFound => Self::NotFound,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// A recurring payment offered by a merchant
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Plan {
+        pub merchant: AccountId,
+        /// Amount charged every interval
+        pub amount: Balance,
+        /// Time between charges
+        pub interval: u64,
+        /// Payment ledger the plan is billed on
+        pub ledger: AccountId,
+        /// Whether new subscriptions are accepted and existing ones charged
+        pub active: bool,
+    }
+
+    /// A subscriber's authorization to be charged for a plan
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Subscription {
+        pub subscriber: AccountId,
+        pub plan_id: u32,
+        /// Most the subscription may charge in total
+        pub cap: Balance,
+        /// Amount charged so far
+        pub charged: Balance,
+        /// Time the next charge becomes due
+        pub next_charge_at: Timestamp,
+        /// Failed charges since the last successful one
+        pub failures: u32,
+        /// Time of the last failed charge, after which the next retry waits out the retry window
+        pub last_attempt_at: Option<Timestamp>,
+        pub active: bool,
+    }
+
+    /// Why a subscription ended
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum CancellationReason {
+        Subscriber,
+        Merchant,
+        CapReached,
+        PaymentFailed,
+        PlanInactive,
+    }
+
+    /// Merchant plans and recurring pulls from subscribers' Payment ledger balances
+    ///
+    /// Subscribers approve this contract on the plan's ledger for at least the
+    /// charged amounts. Anyone may act as keeper and call `charge_due`; failing
+    /// charges are retried until the grace period has passed and the maximum
+    /// number of failures is reached, then the subscription is cancelled.
+    /// Retries are spread evenly over the grace period, at most one per
+    /// retry window, so a keeper cannot pile up failures in a single block.
+    /// Keepers working through the keeper registry are paid from the bond posted
+    /// for the subscription they charge.
+    #[ink(storage)]
+    pub struct Subscriptions {
+        plans: Mapping<u32, Plan>,
+        subscriptions: Mapping<u32, Subscription>,
+        plan_counter: u32,
+        subscription_counter: u32,
+        /// Time after a missed due date during which failed charges are retried
+        grace_period: u64,
+        /// Failed charges after which a subscription past its grace period is cancelled
+        max_failures: u32,
+        /// Keeper registry paying bounties for charging due subscriptions
+        keeper_registry: Option<AccountId>,
+        /// Bonds the keeper registry pays bounties from (subscription_id -> bond)
+        keeper_bonds: Mapping<u32, KeeperBond>,
+        /// Funders' shares of the keeper bonds ((subscription_id, funder) -> stake)
+        keeper_stakes: Mapping<(u32, AccountId), KeeperStake>,
+        owner: AccountId,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct PlanCreated {
+        #[ink(topic)]
+        plan_id: u32,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: Balance,
+        interval: u64,
+        ledger: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct PlanDeactivated {
+        #[ink(topic)]
+        plan_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct Subscribed {
+        #[ink(topic)]
+        subscription_id: u32,
+        #[ink(topic)]
+        plan_id: u32,
+        #[ink(topic)]
+        subscriber: AccountId,
+        cap: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SubscriptionCharged {
+        #[ink(topic)]
+        subscription_id: u32,
+        amount: Balance,
+        next_charge_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct ChargeFailed {
+        #[ink(topic)]
+        subscription_id: u32,
+        failures: u32,
+    }
+
+    #[ink(event)]
+    pub struct SubscriptionCancelled {
+        #[ink(topic)]
+        subscription_id: u32,
+        reason: CancellationReason,
+    }
+
+    #[ink(event)]
+    pub struct RetryPolicyUpdated {
+        grace_period: u64,
+        max_failures: u32,
+    }
+
+    #[ink(event)]
+    pub struct KeeperRegistrySet {
+        keeper_registry: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct KeeperBondPosted {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct KeeperBondReclaimed {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct KeeperBountyPaid {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        keeper: AccountId,
+        amount: Balance,
+    }
+
+    impl Subscriptions {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                plans: Mapping::default(),
+                subscriptions: Mapping::default(),
+                plan_counter: 0,
+                subscription_counter: 0,
+                grace_period: DEFAULT_GRACE_PERIOD,
+                max_failures: DEFAULT_MAX_FAILURES,
+                keeper_registry: None,
+                keeper_bonds: Mapping::default(),
+                keeper_stakes: Mapping::default(),
+                owner: Self::env().caller(),
+            }
+        }
+
+        /// Offer a plan charging `amount` on `ledger` every `interval`
+        #[ink(message)]
+        pub fn create_plan(&mut self, amount: Balance, interval: u64, ledger: AccountId) -> Result<u32, Error> {
+            let merchant = self.env().caller();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if interval < MIN_INTERVAL {
+                return Err(Error::InvalidInterval);
+            }
+
+            let plan_id = self.plan_counter;
+            self.plans.insert(
+                plan_id,
+                &Plan {
+                    merchant,
+                    amount,
+                    interval,
+                    ledger,
+                    active: true,
+                },
+            );
+            self.plan_counter = plan_id.saturating_add(1);
+
+            self.env().emit_event(PlanCreated {
+                plan_id,
+                merchant,
+                amount,
+                interval,
+                ledger,
+            });
+
+            Ok(plan_id)
+        }
+
+        /// Stop offering a plan (merchant only)
+        ///
+        /// Its subscriptions are cancelled the next time they fall due.
+        #[ink(message)]
+        pub fn deactivate_plan(&mut self, plan_id: u32) -> Result<(), Error> {
+            let mut plan = self.plans.get(plan_id).ok_or(Error::PlanNotFound)?;
+            if self.env().caller() != plan.merchant {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if !plan.active {
+                return Err(Error::PlanInactive);
+            }
+
+            plan.active = false;
+            self.plans.insert(plan_id, &plan);
+
+            self.env().emit_event(PlanDeactivated { plan_id });
+
+            Ok(())
+        }
+
+        /// Subscribe the caller to a plan, authorizing charges up to `cap` in total
+        ///
+        /// The first charge is due immediately.
+        #[ink(message)]
+        pub fn subscribe(&mut self, plan_id: u32, cap: Balance) -> Result<u32, Error> {
+            let subscriber = self.env().caller();
+            let plan = self.plans.get(plan_id).ok_or(Error::PlanNotFound)?;
+            if !plan.active {
+                return Err(Error::PlanInactive);
+            }
+            if cap < plan.amount {
+                return Err(Error::InvalidAmount);
+            }
+
+            let subscription_id = self.subscription_counter;
+            self.subscriptions.insert(
+                subscription_id,
+                &Subscription {
+                    subscriber,
+                    plan_id,
+                    cap,
+                    charged: 0,
+                    next_charge_at: self.env().block_timestamp(),
+                    failures: 0,
+                    last_attempt_at: None,
+                    active: true,
+                },
+            );
+            self.subscription_counter = subscription_id.saturating_add(1);
+
+            self.env().emit_event(Subscribed {
+                subscription_id,
+                plan_id,
+                subscriber,
+                cap,
+            });
+
+            Ok(subscription_id)
+        }
+
+        /// End a subscription (subscriber or merchant)
+        #[ink(message)]
+        pub fn cancel(&mut self, subscription_id: u32) -> Result<(), Error> {
+            let mut subscription = self
+                .subscriptions
+                .get(subscription_id)
+                .ok_or(Error::SubscriptionNotFound)?;
+            if !subscription.active {
+                return Err(Error::SubscriptionInactive);
+            }
+            let caller = self.env().caller();
+            let reason = if caller == subscription.subscriber {
+                CancellationReason::Subscriber
+            } else if self
+                .plans
+                .get(subscription.plan_id)
+                .is_some_and(|plan| plan.merchant == caller)
+            {
+                CancellationReason::Merchant
+            } else {
+                return Err(Error::UnauthorizedAccess);
+            };
+
+            self.end(subscription_id, &mut subscription, reason);
+
+            Ok(())
+        }
+
+        /// Charge every listed subscription that is due
+        ///
+        /// Subscriptions that are inactive or not yet due are skipped. Returns
+        /// the number of successful charges.
+        #[ink(message)]
+        pub fn charge_due(&mut self, subscription_ids: Vec<u32>) -> Result<u32, Error> {
+            if subscription_ids.len() > MAX_CHARGE_BATCH {
+                return Err(Error::BatchTooLarge);
+            }
+
+            let now = self.env().block_timestamp();
+            let mut charged = 0u32;
+            for subscription_id in subscription_ids {
+                let Some(mut subscription) = self.subscriptions.get(subscription_id) else {
+                    continue;
+                };
+                if !self.due(&subscription, now) {
+                    continue;
+                }
+                if self.charge(subscription_id, &mut subscription, now) {
+                    charged = charged.saturating_add(1);
+                }
+            }
+
+            Ok(charged)
+        }
+
+        /// Set how long and how often failed charges are retried (owner only)
+        ///
+        /// A failed charge is retried once per `grace_period / max_failures`.
+        #[ink(message)]
+        pub fn set_retry_policy(&mut self, grace_period: u64, max_failures: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.grace_period = grace_period;
+            self.max_failures = max_failures.max(1);
+
+            self.env().emit_event(RetryPolicyUpdated {
+                grace_period,
+                max_failures: self.max_failures,
+            });
+
+            Ok(())
+        }
+
+        /// Set the keeper registry paying bounties for charging due subscriptions (owner only)
+        #[ink(message)]
+        pub fn set_keeper_registry(&mut self, keeper_registry: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+
+            self.keeper_registry = keeper_registry;
+
+            self.env().emit_event(KeeperRegistrySet { keeper_registry });
+
+            Ok(())
+        }
+
+        /// Bond the transferred value as bounty for keepers charging a subscription
+        ///
+        /// Anyone may add to the bond of an existing job; each funder reclaims
+        /// their share of what the keepers did not earn.
+        #[ink(message, payable)]
+        pub fn post_keeper_bond(&mut self, job_id: u32) -> Result<(), Error> {
+            let funder = self.env().caller();
+            let amount = self.env().transferred_value();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if !self.subscriptions.contains(job_id) {
+                return Err(Error::SubscriptionNotFound);
+            }
+
+            let mut bond = self.keeper_bonds.get(job_id).unwrap_or_default();
+            let stake = bond.post(self.keeper_stakes.get((job_id, funder)).unwrap_or_default(), amount);
+            self.keeper_bonds.insert(job_id, &bond);
+            self.keeper_stakes.insert((job_id, funder), &stake);
+
+            self.env().emit_event(KeeperBondPosted { job_id, funder, amount });
+
+            Ok(())
+        }
+
+        /// Take back the caller's share of what is left of a job's bond
+        #[ink(message)]
+        pub fn reclaim_keeper_bond(&mut self, job_id: u32) -> Result<Balance, Error> {
+            let funder = self.env().caller();
+            let stake = self.keeper_stakes.take((job_id, funder)).ok_or(Error::UnauthorizedAccess)?;
+
+            let mut bond = self.keeper_bonds.get(job_id).unwrap_or_default();
+            let amount = bond.reclaim(stake);
+            self.keeper_bonds.insert(job_id, &bond);
+            self.env().transfer(funder, amount).map_err(|_| Error::TransferFailed)?;
+
+            self.env().emit_event(KeeperBondReclaimed { job_id, funder, amount });
+
+            Ok(amount)
+        }
+
+        /// Get the keeper registry paying bounties for charging due subscriptions
+        #[ink(message)]
+        pub fn get_keeper_registry(&self) -> Option<AccountId> {
+            self.keeper_registry
+        }
+
+        /// Get what is left of a job's bond
+        #[ink(message)]
+        pub fn get_keeper_bond(&self, job_id: u32) -> Option<Balance> {
+            self.keeper_bonds.get(job_id).map(|bond| bond.amount)
+        }
+
+        /// Get what a funder would reclaim from a job's bond
+        #[ink(message)]
+        pub fn get_keeper_stake(&self, job_id: u32, funder: AccountId) -> Balance {
+            let stake = self.keeper_stakes.get((job_id, funder)).unwrap_or_default();
+            self.keeper_bonds.get(job_id).map_or(0, |bond| bond.value_of(stake))
+        }
+
+        /// Get a plan
+        #[ink(message)]
+        pub fn get_plan(&self, plan_id: u32) -> Option<Plan> {
+            self.plans.get(plan_id)
+        }
+
+        /// Get a subscription
+        #[ink(message)]
+        pub fn get_subscription(&self, subscription_id: u32) -> Option<Subscription> {
+            self.subscriptions.get(subscription_id)
+        }
+
+        /// Check whether a subscription can be charged now
+        #[ink(message)]
+        pub fn is_due(&self, subscription_id: u32) -> bool {
+            self.subscriptions
+                .get(subscription_id)
+                .is_some_and(|subscription| self.due(&subscription, self.env().block_timestamp()))
+        }
+
+        /// Get the grace period and maximum number of failures
+        #[ink(message)]
+        pub fn get_retry_policy(&self) -> (u64, u32) {
+            (self.grace_period, self.max_failures)
+        }
+
+        /// Get the number of plans
+        #[ink(message)]
+        pub fn get_plan_count(&self) -> u32 {
+            self.plan_counter
+        }
+
+        /// Get the number of subscriptions
+        #[ink(message)]
+        pub fn get_subscription_count(&self) -> u32 {
+            self.subscription_counter
+        }
+
+        /// Charge one due subscription, returning whether it was paid
+        fn charge(&mut self, subscription_id: u32, subscription: &mut Subscription, now: Timestamp) -> bool {
+            let plan = match self.plans.get(subscription.plan_id) {
+                Some(plan) if plan.active => plan,
+                _ => {
+                    self.end(subscription_id, subscription, CancellationReason::PlanInactive);
+                    return false;
+                }
+            };
+            if subscription.charged.saturating_add(plan.amount) > subscription.cap {
+                self.end(subscription_id, subscription, CancellationReason::CapReached);
+                return false;
+            }
+
+            let mut ledger: ink::contract_ref!(PaymentLedger) = plan.ledger.into();
+            let paid = matches!(
+                ledger
+                    .call_mut()
+                    .transfer_from(subscription.subscriber, plan.merchant, plan.amount)
+                    .try_invoke(),
+                Ok(Ok(Ok(())))
+            );
+
+            if paid {
+                subscription.charged = subscription.charged.saturating_add(plan.amount);
+                subscription.next_charge_at = subscription.next_charge_at.saturating_add(plan.interval);
+                subscription.failures = 0;
+                subscription.last_attempt_at = None;
+                self.subscriptions.insert(subscription_id, subscription);
+
+                self.env().emit_event(SubscriptionCharged {
+                    subscription_id,
+                    amount: plan.amount,
+                    next_charge_at: subscription.next_charge_at,
+                });
+                return true;
+            }
+
+            subscription.failures = subscription.failures.saturating_add(1);
+            subscription.last_attempt_at = Some(now);
+            self.subscriptions.insert(subscription_id, subscription);
+            self.env().emit_event(ChargeFailed {
+                subscription_id,
+                failures: subscription.failures,
+            });
+
+            let grace_ends = subscription.next_charge_at.saturating_add(self.grace_period);
+            if subscription.failures >= self.max_failures && now >= grace_ends {
+                self.end(subscription_id, subscription, CancellationReason::PaymentFailed);
+            }
+            false
+        }
+
+        /// Whether a subscription may be charged at `now`: active, past its due
+        /// time and, after a failed charge, past the retry window
+        fn due(&self, subscription: &Subscription, now: Timestamp) -> bool {
+            let retry_at = subscription.last_attempt_at.map_or(0, |attempted_at| {
+                attempted_at.saturating_add(self.grace_period / u64::from(self.max_failures))
+            });
+            subscription.active && subscription.next_charge_at <= now && retry_at <= now
+        }
+
+        fn end(&mut self, subscription_id: u32, subscription: &mut Subscription, reason: CancellationReason) {
+            subscription.active = false;
+            self.subscriptions.insert(subscription_id, subscription);
+
+            self.env().emit_event(SubscriptionCancelled {
+                subscription_id,
+                reason,
+            });
+        }
+    }
+
+    /// Charging a due subscription is the maintenance job, one per subscription id
+    impl Maintainable for Subscriptions {
+        #[ink(message)]
+        fn due_jobs(&self, offset: u32, limit: u32) -> JobPage {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.subscription_counter);
+
+            JobPage {
+                jobs: (offset..end).filter(|&subscription_id| self.is_due(subscription_id)).collect(),
+                next_offset: (end < self.subscription_counter).then_some(end),
+            }
+        }
+
+        /// A failed charge still counts as run, moving the subscription towards cancellation
+        #[ink(message)]
+        fn run_job(&mut self, job_id: u32) -> Result<(), xcc_common::Error> {
+            let mut subscription = self.subscriptions.get(job_id).ok_or(Error::SubscriptionNotFound)?;
+            if !self.is_due(job_id) {
+                return Err(Error::NotDue.into());
+            }
+
+            self.charge(job_id, &mut subscription, self.env().block_timestamp());
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn pay_keeper_bounty(&mut self, keeper: AccountId, job_id: u32, amount: Balance) -> Result<Balance, xcc_common::Error> {
+            if self.keeper_registry != Some(self.env().caller()) {
+                return Err(Error::UnauthorizedAccess.into());
+            }
+
+            let Some(mut bond) = self.keeper_bonds.get(job_id) else {
+                return Ok(0);
+            };
+            let amount = bond.draw(amount);
+            if amount == 0 {
+                return Ok(0);
+            }
+
+            self.keeper_bonds.insert(job_id, &bond);
+            self.env().transfer(keeper, amount).map_err(|_| Error::TransferFailed)?;
+
+            self.env().emit_event(KeeperBountyPaid { job_id, keeper, amount });
+
+            Ok(amount)
+        }
+    }
+
+    impl Default for Subscriptions {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn plans_and_subscriptions_work() {
+            let mut subscriptions = Subscriptions::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            assert_eq!(
+                subscriptions.create_plan(100, MIN_INTERVAL - 1, accounts.django),
+                Err(Error::InvalidInterval)
+            );
+            let plan_id = subscriptions.create_plan(100, MIN_INTERVAL, accounts.django).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(subscriptions.subscribe(plan_id, 99), Err(Error::InvalidAmount));
+            let subscription_id = subscriptions.subscribe(plan_id, 1_000).unwrap();
+            assert!(subscriptions.is_due(subscription_id));
+            assert_eq!(subscriptions.deactivate_plan(plan_id), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(subscriptions.cancel(subscription_id), Err(Error::UnauthorizedAccess));
+
+            // The merchant can end subscriptions to its plans
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            subscriptions.cancel(subscription_id).unwrap();
+            assert!(!subscriptions.is_due(subscription_id));
+            assert_eq!(subscriptions.cancel(subscription_id), Err(Error::SubscriptionInactive));
+
+            subscriptions.deactivate_plan(plan_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(subscriptions.subscribe(plan_id, 1_000), Err(Error::PlanInactive));
+        }
+
+        #[ink::test]
+        fn charge_due_ends_unchargeable_subscriptions() {
+            let mut subscriptions = Subscriptions::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let plan_id = subscriptions.create_plan(100, MIN_INTERVAL, accounts.django).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let subscription_id = subscriptions.subscribe(plan_id, 1_000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            subscriptions.deactivate_plan(plan_id).unwrap();
+
+            // Unknown ids are skipped; a deactivated plan cancels instead of charging
+            assert_eq!(subscriptions.charge_due(ink::prelude::vec![subscription_id, 7]), Ok(0));
+            assert!(!subscriptions.get_subscription(subscription_id).unwrap().active);
+            assert_eq!(
+                subscriptions.charge_due(ink::prelude::vec![0; MAX_CHARGE_BATCH + 1]),
+                Err(Error::BatchTooLarge)
+            );
+        }
+
+        #[ink::test]
+        fn keeper_jobs_work() {
+            let mut subscriptions = Subscriptions::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let plan_id = subscriptions.create_plan(100, MIN_INTERVAL, accounts.django).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let due = subscriptions.subscribe(plan_id, 1_000).unwrap();
+            let cancelled = subscriptions.subscribe(plan_id, 1_000).unwrap();
+            subscriptions.cancel(cancelled).unwrap();
+            assert_eq!(subscriptions.due_jobs(0, 10).jobs, ink::prelude::vec![due]);
+            assert_eq!(subscriptions.due_jobs(0, 1).next_offset, Some(1));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            subscriptions.deactivate_plan(plan_id).unwrap();
+            assert_eq!(subscriptions.run_job(cancelled), Err(xcc_common::Error::Other(Error::NotDue as u8)));
+            assert_eq!(subscriptions.run_job(7), Err(xcc_common::Error::NotFound));
+            subscriptions.run_job(due).unwrap();
+            assert!(subscriptions.due_jobs(0, 10).jobs.is_empty());
+
+            // Only the keeper registry pays bounties, and only from the job's own bond
+            subscriptions.set_keeper_registry(Some(accounts.eve)).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            subscriptions.post_keeper_bond(due).unwrap();
+            assert_eq!(
+                subscriptions.pay_keeper_bounty(accounts.django, due, 20),
+                Err(xcc_common::Error::UnauthorizedAccess)
+            );
+            assert_eq!(subscriptions.post_keeper_bond(7), Err(Error::SubscriptionNotFound));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            subscriptions.post_keeper_bond(due).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(subscriptions.reclaim_keeper_bond(due), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(subscriptions.set_keeper_registry(None), Err(Error::UnauthorizedAccess));
+            assert_eq!(subscriptions.pay_keeper_bounty(accounts.django, cancelled, 20), Ok(0));
+            assert_eq!(subscriptions.pay_keeper_bounty(accounts.django, due, 20), Ok(20));
+            assert_eq!(subscriptions.get_keeper_bond(due), Some(20));
+
+            // Each funder takes back their share of what the keepers did not earn
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(subscriptions.reclaim_keeper_bond(due), Ok(15));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(subscriptions.get_keeper_stake(due, accounts.bob), 5);
+            assert_eq!(subscriptions.reclaim_keeper_bond(due), Ok(5));
+            assert_eq!(subscriptions.get_keeper_bond(due), Some(0));
+        }
+
+        #[ink::test]
+        fn failed_charges_wait_for_the_retry_window() {
+            let mut subscriptions = Subscriptions::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            subscriptions.set_retry_policy(3 * MILLISECONDS_PER_DAY, 3).unwrap();
+            let plan_id = subscriptions.create_plan(100, MIN_INTERVAL, accounts.django).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let subscription_id = subscriptions.subscribe(plan_id, 1_000).unwrap();
+
+            // A failed charge counts once per day of the three-day grace period
+            let mut subscription = subscriptions.get_subscription(subscription_id).unwrap();
+            subscription.failures = 1;
+            subscription.last_attempt_at = Some(0);
+            subscriptions.subscriptions.insert(subscription_id, &subscription);
+            assert!(!subscriptions.is_due(subscription_id));
+            assert!(subscriptions.due_jobs(0, 10).jobs.is_empty());
+            assert_eq!(subscriptions.charge_due(ink::prelude::vec![subscription_id]), Ok(0));
+            assert_eq!(subscriptions.get_subscription(subscription_id).unwrap().failures, 1);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MILLISECONDS_PER_DAY);
+            assert!(subscriptions.is_due(subscription_id));
+        }
+
+        #[ink::test]
+        fn retry_policy_is_owner_only() {
+            let mut subscriptions = Subscriptions::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+
+            subscriptions.set_retry_policy(MILLISECONDS_PER_DAY, 0).unwrap();
+            assert_eq!(subscriptions.get_retry_policy(), (MILLISECONDS_PER_DAY, 1));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(subscriptions.set_retry_policy(0, 1), Err(Error::UnauthorizedAccess));
+        }
+    }
+}
@@ -0,0 +1,459 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::treasury::{Error, Spend, SpendStatus, Treasury, TreasuryRef};
+
+#[ink::contract]
+mod treasury {
+    use ink::codegen::TraitCallBuilder;
+    use ink::storage::Mapping;
+    use xcc_common::{Component, FeeCollector, IntentAsset, PaymentLedger};
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        /// The caller is not a registered fee source
+        UnknownSource,
+        /// PSP22 tokens are not held by the treasury
+        UnsupportedAsset,
+        InvalidAmount,
+        InsufficientBalance,
+        SpendNotFound,
+        SpendClosed,
+        TransferFailed,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::InsufficientBalance => Self::InsufficientBalance,
+                Error::SpendNotFound => Self::NotFound,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Stage of a spend proposal
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum SpendStatus {
+        Proposed,
+        Executed,
+        Rejected,
+    }
+
+    /// A proposed payment out of the treasury
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Spend {
+        pub proposer: AccountId,
+        pub asset: IntentAsset,
+        pub beneficiary: AccountId,
+        pub amount: Balance,
+        pub status: SpendStatus,
+    }
+
+    /// Protocol fees of the suite, booked by the contract they came from
+    ///
+    /// Registered contracts (BillSplitting's late fees, the XcmHandler's
+    /// delivery fees) pay natively or on a Payment ledger through
+    /// `collect_fees`. Anyone may propose a spend; only the spend authority,
+    /// meant to be the governance contract or a multisig, approves or rejects
+    /// spends and manages the sources.
+    #[ink(storage)]
+    pub struct Treasury {
+        spend_authority: AccountId,
+        /// Source each fee-paying contract is booked under
+        sources: Mapping<AccountId, Component>,
+        /// Fees collected (source -> asset -> total)
+        collected: Mapping<(Component, IntentAsset), Balance>,
+        /// Funds held per asset
+        balances: Mapping<IntentAsset, Balance>,
+        /// Funds spent per asset
+        spent: Mapping<IntentAsset, Balance>,
+        spends: Mapping<u32, Spend>,
+        spend_counter: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct FeesCollected {
+        #[ink(topic)]
+        source: Component,
+        asset: IntentAsset,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SpendProposed {
+        #[ink(topic)]
+        spend_id: u32,
+        #[ink(topic)]
+        proposer: AccountId,
+        asset: IntentAsset,
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SpendExecuted {
+        #[ink(topic)]
+        spend_id: u32,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        asset: IntentAsset,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SpendRejected {
+        #[ink(topic)]
+        spend_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct SourceSet {
+        #[ink(topic)]
+        contract: AccountId,
+        source: Option<Component>,
+    }
+
+    #[ink(event)]
+    pub struct SpendAuthoritySet {
+        spend_authority: AccountId,
+    }
+
+    impl Treasury {
+        /// Constructor with the governance contract or multisig approving spends
+        #[ink(constructor)]
+        pub fn new(spend_authority: AccountId) -> Self {
+            Self {
+                spend_authority,
+                sources: Mapping::default(),
+                collected: Mapping::default(),
+                balances: Mapping::default(),
+                spent: Mapping::default(),
+                spends: Mapping::default(),
+                spend_counter: 0,
+            }
+        }
+
+        /// Book the fees a contract pays under a source, or stop accepting them with `None` (spend authority only)
+        #[ink(message)]
+        pub fn set_source(&mut self, contract: AccountId, source: Option<Component>) -> Result<(), Error> {
+            self.ensure_spend_authority()?;
+
+            match source {
+                Some(source) => self.sources.insert(contract, &source),
+                None => {
+                    self.sources.remove(contract);
+                    None
+                }
+            };
+
+            self.env().emit_event(SourceSet { contract, source });
+
+            Ok(())
+        }
+
+        /// Hand spend approval to another governance contract or multisig (spend authority only)
+        #[ink(message)]
+        pub fn set_spend_authority(&mut self, spend_authority: AccountId) -> Result<(), Error> {
+            self.ensure_spend_authority()?;
+
+            self.spend_authority = spend_authority;
+
+            self.env().emit_event(SpendAuthoritySet { spend_authority });
+
+            Ok(())
+        }
+
+        /// Propose paying `amount` of an asset to a beneficiary
+        #[ink(message)]
+        pub fn propose_spend(&mut self, asset: IntentAsset, beneficiary: AccountId, amount: Balance) -> Result<u32, Error> {
+            if matches!(asset, IntentAsset::Psp22(_)) {
+                return Err(Error::UnsupportedAsset);
+            }
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let proposer = self.env().caller();
+            let spend_id = self.spend_counter;
+            self.spends.insert(
+                spend_id,
+                &Spend {
+                    proposer,
+                    asset,
+                    beneficiary,
+                    amount,
+                    status: SpendStatus::Proposed,
+                },
+            );
+            self.spend_counter = spend_id.saturating_add(1);
+
+            self.env().emit_event(SpendProposed {
+                spend_id,
+                proposer,
+                asset,
+                beneficiary,
+                amount,
+            });
+
+            Ok(spend_id)
+        }
+
+        /// Pay out a proposed spend (spend authority only)
+        #[ink(message)]
+        pub fn approve_spend(&mut self, spend_id: u32) -> Result<(), Error> {
+            self.ensure_spend_authority()?;
+            let mut spend = self.open_spend(spend_id)?;
+            let balance = self.get_balance(spend.asset);
+            if spend.amount > balance {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.balances.insert(spend.asset, &balance.saturating_sub(spend.amount));
+            let spent = self.get_spent(spend.asset);
+            self.spent.insert(spend.asset, &spent.saturating_add(spend.amount));
+            spend.status = SpendStatus::Executed;
+            self.spends.insert(spend_id, &spend);
+
+            match spend.asset {
+                IntentAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer(spend.beneficiary, spend.amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => {}
+                        _ => return Err(Error::TransferFailed),
+                    }
+                }
+                _ => self
+                    .env()
+                    .transfer(spend.beneficiary, spend.amount)
+                    .map_err(|_| Error::TransferFailed)?,
+            }
+
+            self.env().emit_event(SpendExecuted {
+                spend_id,
+                beneficiary: spend.beneficiary,
+                asset: spend.asset,
+                amount: spend.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Close a proposed spend without paying it (spend authority only)
+        #[ink(message)]
+        pub fn reject_spend(&mut self, spend_id: u32) -> Result<(), Error> {
+            self.ensure_spend_authority()?;
+            let mut spend = self.open_spend(spend_id)?;
+
+            spend.status = SpendStatus::Rejected;
+            self.spends.insert(spend_id, &spend);
+
+            self.env().emit_event(SpendRejected { spend_id });
+
+            Ok(())
+        }
+
+        /// Get the funds held in an asset
+        #[ink(message)]
+        pub fn get_balance(&self, asset: IntentAsset) -> Balance {
+            self.balances.get(asset).unwrap_or(0)
+        }
+
+        /// Get the fees ever collected from a source in an asset
+        #[ink(message)]
+        pub fn get_collected(&self, source: Component, asset: IntentAsset) -> Balance {
+            self.collected.get((source, asset)).unwrap_or(0)
+        }
+
+        /// Get the funds ever spent in an asset
+        #[ink(message)]
+        pub fn get_spent(&self, asset: IntentAsset) -> Balance {
+            self.spent.get(asset).unwrap_or(0)
+        }
+
+        /// Get a spend proposal
+        #[ink(message)]
+        pub fn get_spend(&self, spend_id: u32) -> Option<Spend> {
+            self.spends.get(spend_id)
+        }
+
+        /// Get the number of spend proposals
+        #[ink(message)]
+        pub fn get_spend_count(&self) -> u32 {
+            self.spend_counter
+        }
+
+        /// Get the source a contract's fees are booked under
+        #[ink(message)]
+        pub fn get_source(&self, contract: AccountId) -> Option<Component> {
+            self.sources.get(contract)
+        }
+
+        /// Get the governance contract or multisig approving spends
+        #[ink(message)]
+        pub fn get_spend_authority(&self) -> AccountId {
+            self.spend_authority
+        }
+
+        fn ensure_spend_authority(&self) -> Result<(), Error> {
+            if self.env().caller() != self.spend_authority {
+                return Err(Error::UnauthorizedAccess);
+            }
+            Ok(())
+        }
+
+        fn open_spend(&self, spend_id: u32) -> Result<Spend, Error> {
+            let spend = self.spends.get(spend_id).ok_or(Error::SpendNotFound)?;
+            if spend.status != SpendStatus::Proposed {
+                return Err(Error::SpendClosed);
+            }
+            Ok(spend)
+        }
+    }
+
+    impl FeeCollector for Treasury {
+        /// Ledger fees are pulled through the source's allowance, so transfers
+        /// to the treasury from anyone else are never booked as fees
+        #[ink(message, payable)]
+        fn collect_fees(&mut self, asset: IntentAsset, amount: Balance) -> Result<Balance, xcc_common::Error> {
+            let caller = self.env().caller();
+            let source = self.sources.get(caller).ok_or(Error::UnknownSource)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount.into());
+            }
+            match asset {
+                IntentAsset::Native => {
+                    if self.env().transferred_value() != amount {
+                        return Err(Error::InvalidAmount.into());
+                    }
+                }
+                IntentAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger
+                        .call_mut()
+                        .transfer_from(caller, self.env().account_id(), amount)
+                        .try_invoke()
+                    {
+                        Ok(Ok(Ok(()))) => {}
+                        _ => return Err(Error::TransferFailed.into()),
+                    }
+                }
+                IntentAsset::Psp22(_) => return Err(Error::UnsupportedAsset.into()),
+            }
+
+            let balance = self.get_balance(asset);
+
+            self.balances.insert(asset, &balance.saturating_add(amount));
+            let collected = self.get_collected(source, asset);
+            self.collected.insert((source, asset), &collected.saturating_add(amount));
+
+            self.env().emit_event(FeesCollected { source, asset, amount });
+
+            Ok(amount)
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn fees_are_booked_per_source() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut treasury = Treasury::new(accounts.alice);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(
+                treasury.collect_fees(IntentAsset::Native, 100),
+                Err(xcc_common::Error::Other(Error::UnknownSource as u8))
+            );
+            assert_eq!(treasury.set_source(accounts.bob, Some(Component::BillSplitting)), Err(Error::UnauthorizedAccess));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            treasury.set_source(accounts.bob, Some(Component::BillSplitting)).unwrap();
+            treasury.set_source(accounts.charlie, Some(Component::XcmHandler)).unwrap();
+            assert_eq!(treasury.get_source(accounts.bob), Some(Component::BillSplitting));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(treasury.collect_fees(IntentAsset::Native, 100), Ok(100));
+            assert_eq!(
+                treasury.collect_fees(IntentAsset::Psp22(accounts.django), 100),
+                Err(xcc_common::Error::Other(Error::UnsupportedAsset as u8))
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(treasury.collect_fees(IntentAsset::Native, 20), Err(xcc_common::Error::InvalidAmount));
+            assert_eq!(treasury.collect_fees(IntentAsset::Native, 30), Ok(30));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(treasury.collect_fees(IntentAsset::Native, 0), Err(xcc_common::Error::InvalidAmount));
+
+            assert_eq!(treasury.get_collected(Component::BillSplitting, IntentAsset::Native), 100);
+            assert_eq!(treasury.get_collected(Component::XcmHandler, IntentAsset::Native), 30);
+            assert_eq!(treasury.get_collected(Component::Payment, IntentAsset::Native), 0);
+            assert_eq!(treasury.get_balance(IntentAsset::Native), 130);
+
+            // Removed sources can no longer pay in
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            treasury.set_source(accounts.charlie, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(
+                treasury.collect_fees(IntentAsset::Native, 30),
+                Err(xcc_common::Error::Other(Error::UnknownSource as u8))
+            );
+        }
+
+        #[ink::test]
+        fn spends_need_the_spend_authority() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut treasury = Treasury::new(accounts.alice);
+            treasury.set_source(accounts.bob, Some(Component::XcmHandler)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            treasury.collect_fees(IntentAsset::Native, 100).unwrap();
+
+            assert_eq!(treasury.propose_spend(IntentAsset::Native, accounts.eve, 0), Err(Error::InvalidAmount));
+            assert_eq!(
+                treasury.propose_spend(IntentAsset::Psp22(accounts.django), accounts.eve, 10),
+                Err(Error::UnsupportedAsset)
+            );
+            let spend_id = treasury.propose_spend(IntentAsset::Native, accounts.eve, 60).unwrap();
+            let too_large = treasury.propose_spend(IntentAsset::Native, accounts.eve, 50).unwrap();
+            let rejected = treasury.propose_spend(IntentAsset::Native, accounts.eve, 10).unwrap();
+            assert_eq!(treasury.approve_spend(spend_id), Err(Error::UnauthorizedAccess));
+            assert_eq!(treasury.get_spend_count(), 3);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let eve_balance = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.eve).unwrap();
+            treasury.approve_spend(spend_id).unwrap();
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.eve).unwrap(),
+                eve_balance + 60
+            );
+            assert_eq!(treasury.get_spend(spend_id).unwrap().status, SpendStatus::Executed);
+            assert_eq!(treasury.approve_spend(spend_id), Err(Error::SpendClosed));
+            assert_eq!(treasury.approve_spend(too_large), Err(Error::InsufficientBalance));
+            assert_eq!((treasury.get_balance(IntentAsset::Native), treasury.get_spent(IntentAsset::Native)), (40, 60));
+
+            treasury.reject_spend(rejected).unwrap();
+            assert_eq!(treasury.approve_spend(rejected), Err(Error::SpendClosed));
+
+            // The authority can hand over to another governance contract
+            treasury.set_spend_authority(accounts.frank).unwrap();
+            assert_eq!(treasury.reject_spend(too_large), Err(Error::UnauthorizedAccess));
+            assert_eq!(treasury.get_spend_authority(), accounts.frank);
+        }
+    }
+}
@@ -0,0 +1,573 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::vesting::{Error, Schedule, VestingAsset, VestingRef};
+
+#[ink::contract]
+mod vesting {
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use xcc_common::PaymentLedger;
+
+    /// Maximum number of accepted schedules per beneficiary
+    pub const MAX_SCHEDULES_PER_BENEFICIARY: usize = 32;
+
+    /// PSP22 `transfer` selector
+    const PSP22_TRANSFER_SELECTOR: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+    /// PSP22 `transfer_from` selector
+    const PSP22_TRANSFER_FROM_SELECTOR: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+    /// Errors returned by PSP22 tokens
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Psp22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// Contract errors
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        UnauthorizedAccess,
+        ScheduleNotFound,
+        InvalidAmount,
+        InvalidSchedule,
+        TooManySchedules,
+        NotRevocable,
+        AlreadyRevoked,
+        NothingToClaim,
+        TransferFailed,
+        LedgerTransferFailed,
+        TokenTransferFailed,
+        /// The beneficiary has not accepted the schedule yet
+        NotAccepted,
+        AlreadyAccepted,
+    }
+
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::ScheduleNotFound => Self::NotFound,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
+    }
+
+    /// Asset a schedule vests
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VestingAsset {
+        Native,
+        /// Balance on a Payment ledger
+        Ledger(AccountId),
+        /// PSP22 token
+        Psp22(AccountId),
+    }
+
+    /// Cliff and linear vesting of an amount locked by a grantor
+    ///
+    /// Nothing vests before the cliff; from then on the amount vests linearly
+    /// from `start`, so the share accrued during the cliff unlocks at once.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Schedule {
+        pub grantor: AccountId,
+        pub beneficiary: AccountId,
+        pub asset: VestingAsset,
+        /// Amount vesting over the schedule; reduced to the vested amount on revocation
+        pub total: Balance,
+        /// Amount the beneficiary claimed so far
+        pub claimed: Balance,
+        pub start: Timestamp,
+        /// Time before which nothing vests
+        pub cliff: Timestamp,
+        /// Time at which everything has vested
+        pub end: Timestamp,
+        /// Whether the grantor may take back the unvested amount
+        pub revocable: bool,
+        pub revoked: bool,
+        /// Whether the beneficiary accepted the schedule
+        pub accepted: bool,
+    }
+
+    /// Vesting schedules over native funds, Payment ledger balances or PSP22 tokens
+    #[ink(storage)]
+    pub struct Vesting {
+        schedules: Mapping<u32, Schedule>,
+        /// Accepted schedules of each beneficiary, in acceptance order
+        beneficiary_schedules: Mapping<AccountId, Vec<u32>>,
+        schedule_counter: u32,
+    }
+
+    /// Events
+    #[ink(event)]
+    pub struct ScheduleCreated {
+        #[ink(topic)]
+        schedule_id: u32,
+        #[ink(topic)]
+        grantor: AccountId,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        asset: VestingAsset,
+        total: Balance,
+        cliff: Timestamp,
+        end: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct ScheduleAccepted {
+        #[ink(topic)]
+        schedule_id: u32,
+        #[ink(topic)]
+        beneficiary: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct VestedClaimed {
+        #[ink(topic)]
+        schedule_id: u32,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ScheduleRevoked {
+        #[ink(topic)]
+        schedule_id: u32,
+        /// Unvested amount returned to the grantor
+        returned: Balance,
+    }
+
+    impl Vesting {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                schedules: Mapping::default(),
+                beneficiary_schedules: Mapping::default(),
+                schedule_counter: 0,
+            }
+        }
+
+        /// Lock `total` from the caller, vesting to `beneficiary` between `start` and `end`
+        ///
+        /// Native schedules take the transferred value, which must equal `total`;
+        /// ledger and PSP22 schedules pull it through the caller's allowance.
+        /// The schedule takes one of the beneficiary's slots once they accept it.
+        #[ink(message, payable)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_schedule(
+            &mut self,
+            beneficiary: AccountId,
+            asset: VestingAsset,
+            total: Balance,
+            start: Timestamp,
+            cliff: Timestamp,
+            end: Timestamp,
+            revocable: bool,
+        ) -> Result<u32, Error> {
+            let grantor = self.env().caller();
+            if total == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if !(start <= cliff && cliff <= end && start < end) {
+                return Err(Error::InvalidSchedule);
+            }
+
+            self.collect(asset, grantor, total)?;
+
+            let schedule_id = self.schedule_counter;
+            self.schedules.insert(
+                schedule_id,
+                &Schedule {
+                    grantor,
+                    beneficiary,
+                    asset,
+                    total,
+                    claimed: 0,
+                    start,
+                    cliff,
+                    end,
+                    revocable,
+                    revoked: false,
+                    accepted: false,
+                },
+            );
+            self.schedule_counter = schedule_id.saturating_add(1);
+
+            self.env().emit_event(ScheduleCreated {
+                schedule_id,
+                grantor,
+                beneficiary,
+                asset,
+                total,
+                cliff,
+                end,
+            });
+
+            Ok(schedule_id)
+        }
+
+        /// Accept a schedule granted to the caller
+        #[ink(message)]
+        pub fn accept_schedule(&mut self, schedule_id: u32) -> Result<(), Error> {
+            let mut schedule = self.schedules.get(schedule_id).ok_or(Error::ScheduleNotFound)?;
+            let beneficiary = self.env().caller();
+            if beneficiary != schedule.beneficiary {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if schedule.accepted {
+                return Err(Error::AlreadyAccepted);
+            }
+            if schedule.revoked {
+                return Err(Error::AlreadyRevoked);
+            }
+            let mut ids = self.beneficiary_schedules.get(beneficiary).unwrap_or_default();
+            if ids.len() >= MAX_SCHEDULES_PER_BENEFICIARY {
+                return Err(Error::TooManySchedules);
+            }
+
+            schedule.accepted = true;
+            self.schedules.insert(schedule_id, &schedule);
+            ids.push(schedule_id);
+            self.beneficiary_schedules.insert(beneficiary, &ids);
+
+            self.env().emit_event(ScheduleAccepted { schedule_id, beneficiary });
+
+            Ok(())
+        }
+
+        /// Pay out everything vested and not yet claimed (beneficiary only)
+        #[ink(message)]
+        pub fn claim_vested(&mut self, schedule_id: u32) -> Result<Balance, Error> {
+            let mut schedule = self.schedules.get(schedule_id).ok_or(Error::ScheduleNotFound)?;
+            if self.env().caller() != schedule.beneficiary {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if !schedule.accepted {
+                return Err(Error::NotAccepted);
+            }
+            let amount = self
+                .vested_at(&schedule, self.env().block_timestamp())
+                .saturating_sub(schedule.claimed);
+            if amount == 0 {
+                return Err(Error::NothingToClaim);
+            }
+
+            schedule.claimed = schedule.claimed.saturating_add(amount);
+            self.schedules.insert(schedule_id, &schedule);
+            self.release(schedule.asset, schedule.beneficiary, amount)?;
+
+            self.env().emit_event(VestedClaimed {
+                schedule_id,
+                beneficiary: schedule.beneficiary,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Stop a revocable schedule and return its unvested amount (grantor only)
+        ///
+        /// What has vested so far stays claimable by the beneficiary. Schedules
+        /// not accepted yet can always be withdrawn in full.
+        #[ink(message)]
+        pub fn revoke(&mut self, schedule_id: u32) -> Result<Balance, Error> {
+            let mut schedule = self.schedules.get(schedule_id).ok_or(Error::ScheduleNotFound)?;
+            if self.env().caller() != schedule.grantor {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if schedule.revoked {
+                return Err(Error::AlreadyRevoked);
+            }
+            if schedule.accepted && !schedule.revocable {
+                return Err(Error::NotRevocable);
+            }
+
+            let vested = if schedule.accepted {
+                self.vested_at(&schedule, self.env().block_timestamp())
+            } else {
+                0
+            };
+            let returned = schedule.total.saturating_sub(vested);
+            schedule.total = vested;
+            schedule.revoked = true;
+            self.schedules.insert(schedule_id, &schedule);
+            if returned > 0 {
+                self.release(schedule.asset, schedule.grantor, returned)?;
+            }
+
+            self.env().emit_event(ScheduleRevoked { schedule_id, returned });
+
+            Ok(returned)
+        }
+
+        /// Get a schedule
+        #[ink(message)]
+        pub fn get_schedule(&self, schedule_id: u32) -> Option<Schedule> {
+            self.schedules.get(schedule_id)
+        }
+
+        /// Get the accepted schedules of a beneficiary with their ids
+        #[ink(message)]
+        pub fn get_schedules(&self, beneficiary: AccountId) -> Vec<(u32, Schedule)> {
+            self.beneficiary_schedules
+                .get(beneficiary)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|schedule_id| Some((schedule_id, self.schedules.get(schedule_id)?)))
+                .collect()
+        }
+
+        /// Get the amount of a schedule vested so far, claimed or not
+        #[ink(message)]
+        pub fn get_vested(&self, schedule_id: u32) -> Balance {
+            self.schedules
+                .get(schedule_id)
+                .map_or(0, |schedule| self.vested_at(&schedule, self.env().block_timestamp()))
+        }
+
+        /// Get the amount of a schedule the beneficiary can claim now
+        #[ink(message)]
+        pub fn get_claimable(&self, schedule_id: u32) -> Balance {
+            self.schedules.get(schedule_id).map_or(0, |schedule| {
+                self.vested_at(&schedule, self.env().block_timestamp())
+                    .saturating_sub(schedule.claimed)
+            })
+        }
+
+        /// Get the number of schedules
+        #[ink(message)]
+        pub fn get_schedule_count(&self) -> u32 {
+            self.schedule_counter
+        }
+
+        fn vested_at(&self, schedule: &Schedule, now: Timestamp) -> Balance {
+            if schedule.revoked || now >= schedule.end {
+                return schedule.total;
+            }
+            if now < schedule.cliff {
+                return 0;
+            }
+            let elapsed = u128::from(now.saturating_sub(schedule.start));
+            let duration = u128::from(schedule.end.saturating_sub(schedule.start));
+            schedule.total.saturating_mul(elapsed) / duration
+        }
+
+        /// Move a schedule's funds from the grantor into this contract
+        fn collect(&mut self, asset: VestingAsset, from: AccountId, amount: Balance) -> Result<(), Error> {
+            let transferred = self.env().transferred_value();
+            if asset != VestingAsset::Native && transferred != 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let escrow = self.env().account_id();
+
+            match asset {
+                VestingAsset::Native if transferred != amount => Err(Error::InvalidAmount),
+                VestingAsset::Native => Ok(()),
+                VestingAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer_from(from, escrow, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                VestingAsset::Psp22(token) => {
+                    let result = build_call::<Environment>()
+                        .call(token)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM_SELECTOR))
+                                .push_arg(from)
+                                .push_arg(escrow)
+                                .push_arg(amount)
+                                .push_arg(Vec::<u8>::new()),
+                        )
+                        .returns::<Result<(), Psp22Error>>()
+                        .try_invoke();
+                    match result {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::TokenTransferFailed),
+                    }
+                }
+            }
+        }
+
+        /// Pay a schedule's funds out of this contract
+        fn release(&mut self, asset: VestingAsset, to: AccountId, amount: Balance) -> Result<(), Error> {
+            match asset {
+                VestingAsset::Native => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::TransferFailed),
+                VestingAsset::Ledger(ledger) => {
+                    let mut ledger: ink::contract_ref!(PaymentLedger) = ledger.into();
+                    match ledger.call_mut().transfer(to, amount).try_invoke() {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::LedgerTransferFailed),
+                    }
+                }
+                VestingAsset::Psp22(token) => {
+                    let result = build_call::<Environment>()
+                        .call(token)
+                        .exec_input(
+                            ExecutionInput::new(Selector::new(PSP22_TRANSFER_SELECTOR))
+                                .push_arg(to)
+                                .push_arg(amount)
+                                .push_arg(Vec::<u8>::new()),
+                        )
+                        .returns::<Result<(), Psp22Error>>()
+                        .try_invoke();
+                    match result {
+                        Ok(Ok(Ok(()))) => Ok(()),
+                        _ => Err(Error::TokenTransferFailed),
+                    }
+                }
+            }
+        }
+    }
+
+    impl Default for Vesting {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const TOTAL: Balance = 12_000_000;
+
+        fn vesting() -> (Vesting, ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>) {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0x42; 32]));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from([0x42; 32]),
+                10 * TOTAL,
+            );
+            (Vesting::new(), accounts)
+        }
+
+        /// Native schedule over 1200 ms with a 300 ms cliff, granted by Alice and accepted
+        fn grant(vesting: &mut Vesting, beneficiary: AccountId, revocable: bool) -> u32 {
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(TOTAL);
+            let schedule_id = vesting
+                .create_schedule(beneficiary, VestingAsset::Native, TOTAL, 0, 300, 1_200, revocable)
+                .unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(beneficiary);
+            vesting.accept_schedule(schedule_id).unwrap();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            schedule_id
+        }
+
+        #[ink::test]
+        fn cliff_and_linear_vesting_works() {
+            let (mut vesting, accounts) = vesting();
+            let schedule_id = grant(&mut vesting, accounts.bob, false);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(299);
+            assert_eq!(vesting.claim_vested(schedule_id), Err(Error::NothingToClaim));
+
+            // The share accrued during the cliff unlocks at once
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(300);
+            assert_eq!(vesting.claim_vested(schedule_id), Ok(TOTAL / 4));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(600);
+            assert_eq!(vesting.get_claimable(schedule_id), TOTAL / 4);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            assert_eq!(vesting.claim_vested(schedule_id), Ok(TOTAL * 3 / 4));
+            assert_eq!(vesting.get_schedule(schedule_id).unwrap().claimed, TOTAL);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(vesting.claim_vested(schedule_id), Err(Error::UnauthorizedAccess));
+            assert_eq!(vesting.revoke(schedule_id), Err(Error::NotRevocable));
+        }
+
+        #[ink::test]
+        fn revoke_returns_unvested() {
+            let (mut vesting, accounts) = vesting();
+            let schedule_id = grant(&mut vesting, accounts.bob, true);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(600);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.alice)
+                .unwrap();
+            assert_eq!(vesting.revoke(schedule_id), Ok(TOTAL / 2));
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.alice),
+                Ok(before + TOTAL / 2)
+            );
+            assert_eq!(vesting.revoke(schedule_id), Err(Error::AlreadyRevoked));
+
+            // The vested half stays claimable
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(vesting.claim_vested(schedule_id), Ok(TOTAL / 2));
+        }
+
+        #[ink::test]
+        fn schedules_are_enumerable() {
+            let (mut vesting, accounts) = vesting();
+            grant(&mut vesting, accounts.bob, false);
+            grant(&mut vesting, accounts.charlie, false);
+            grant(&mut vesting, accounts.bob, true);
+
+            let schedules = vesting.get_schedules(accounts.bob);
+            assert_eq!(schedules.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [0, 2]);
+            assert!(schedules[1].1.revocable);
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(TOTAL);
+            assert_eq!(
+                vesting.create_schedule(accounts.bob, VestingAsset::Native, TOTAL, 100, 50, 200, false),
+                Err(Error::InvalidSchedule)
+            );
+            assert_eq!(
+                vesting.create_schedule(accounts.bob, VestingAsset::Native, TOTAL - 1, 0, 0, 200, false),
+                Err(Error::InvalidAmount)
+            );
+        }
+
+        #[ink::test]
+        fn unaccepted_schedules_take_no_slots() {
+            let (mut vesting, accounts) = vesting();
+
+            // A stranger's dust schedules do not crowd out the real grantor
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1);
+            for _ in 0..MAX_SCHEDULES_PER_BENEFICIARY {
+                vesting
+                    .create_schedule(accounts.bob, VestingAsset::Native, 1, 0, 0, 1_200, false)
+                    .unwrap();
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let schedule_id = grant(&mut vesting, accounts.bob, false);
+            assert_eq!(vesting.get_schedules(accounts.bob).len(), 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(vesting.accept_schedule(schedule_id), Err(Error::AlreadyAccepted));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            assert_eq!(vesting.claim_vested(0), Err(Error::NotAccepted));
+
+            // Grantors can withdraw offers never accepted, revocable or not
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(vesting.revoke(0), Ok(1));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(vesting.accept_schedule(0), Err(Error::AlreadyRevoked));
+        }
+    }
+}
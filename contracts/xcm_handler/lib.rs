@@ -1,31 +1,803 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-#[ink::contract]
+pub use self::xcm_handler::{
+    AssetLocation, AssetMetadata, AttesterKey, Beneficiary, ChainConfig, ChainStats, CrossChainPaymentCancelled,
+    CrossChainPaymentCreated, CrossChainPaymentExecuted, CrossChainPaymentFailed,
+    CrossChainPaymentRefunded, DeliveryFee, Destination, DestinationJunction, Error, EthereumTransfer, ExecutionProof,
+    FailureReason, InboundPaymentReceived, PaymentClaim, PaymentFilter, PaymentStatus,
+    PaymentStatusChanged, QueryOutcome, RateLimit, RateUsage, TransferMode, VerificationMode, VersionedDestination,
+    XcmDispatchPath, XcmHandlerRef, XcmMessageType, XcmPaymentInfo, XcmWeight, ASSET_HUB_CHAIN_ID, USDC_ASSET_ID,
+    USDT_ASSET_ID,
+};
+
+use ink::env::{DefaultEnvironment, Environment};
+
+/// Chain extension of runtimes exposing pallet-xcm to contracts directly
+///
+/// A faster and cheaper alternative to the contracts pallet's XCM host
+/// functions for runtimes willing to add it. Messages are passed as
+/// SCALE-encoded `VersionedXcm<()>`, destinations as `VersionedLocation`.
+#[ink::chain_extension(extension = 0x0CC0)]
+pub trait XcmExtension {
+    type ErrorCode = XcmExtensionError;
+
+    /// Execute a message as the contract, returning the weight it used
+    #[ink(function = 1)]
+    fn xcm_execute(message: &[u8]) -> XcmWeight;
+
+    /// Send a message to a destination as the contract, returning the message hash
+    #[ink(function = 2)]
+    fn xcm_send(destination: &[u8], message: &[u8]) -> [u8; 32];
+
+    /// Weight executing a message would use
+    #[ink(function = 3)]
+    fn query_xcm_weight(message: &[u8]) -> XcmWeight;
+}
+
+/// Status codes of the XCM chain extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum XcmExtensionError {
+    /// The message could not be decoded in any supported XCM version
+    BadVersion,
+    /// The message failed to execute or could not be weighed
+    ExecutionFailed,
+    /// The message could not be delivered to its destination
+    SendFailed,
+    /// A status code this contract does not know of
+    Unknown,
+}
+
+impl ink::env::chain_extension::FromStatusCode for XcmExtensionError {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            1 => Err(Self::BadVersion),
+            2 => Err(Self::ExecutionFailed),
+            3 => Err(Self::SendFailed),
+            _ => Err(Self::Unknown),
+        }
+    }
+}
+
+/// ink!'s default environment with the XCM chain extension
+///
+/// Runtimes without the extension can still run the contract, as long as
+/// its dispatch path stays on the host functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(TypeInfo)]
+pub enum XcmEnvironment {}
+
+impl Environment for XcmEnvironment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <DefaultEnvironment as Environment>::AccountId;
+    type Balance = <DefaultEnvironment as Environment>::Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+
+    type ChainExtension = XcmExtension;
+}
+
+#[ink::contract(env = crate::XcmEnvironment)]
 mod xcm_handler {
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::hash::{Blake2x256, HashOutput};
+    use ink::prelude::vec::Vec;
     use ink::storage::Mapping;
     
-    /// XCM message types for cross-chain payments
-    #[derive(Debug, PartialEq, Eq)]
+    pub use xcc_common::{XcmMessageType, BASIS_POINTS, MAX_PAGE_SIZE, NATIVE_ASSET};
+    use xcc_common::{
+        BillManager, Component, ComponentRegistry, FeeCollector, IntentAsset, JobPage, KeeperBond, KeeperStake, Maintainable, PaymentSummary, StateChunk, XcmGateway,
+    };
+    
+    /// Time after which a pending payment can be refunded, unless its chain sets its own
+    pub const DEFAULT_PAYMENT_TIMEOUT: u64 = 7 * 86_400_000;
+    
+    /// Token decimals assumed for chains without their own configuration
+    pub const DEFAULT_DECIMALS: u8 = 12;
+    
+    /// Maximum number of junctions in a destination
+    pub const MAX_JUNCTIONS: usize = 4;
+    
+    /// Layout version of the storage written by this code
+    pub const STORAGE_VERSION: u32 = 1;
+    
+    /// First error code of the contract; Payment, BillSplitting and XcmHandler
+    /// codes start at 1000, 2000 and 3000
+    pub const ERROR_CODE_BASE: u16 = 3000;
+    
+    /// Oldest XCM version outbound messages can be encoded in
+    pub const MIN_XCM_VERSION: u32 = 3;
+    
+    /// XCM version outbound messages are built in
+    pub const LATEST_XCM_VERSION: u32 = 4;
+    
+    /// Time settled payments are kept before they may be pruned (30 days)
+    pub const PRUNE_GRACE_PERIOD: u64 = 30 * 24 * 3_600_000;
+    
+    /// Chain id recorded as the source of payments unless configured otherwise
+    pub const DEFAULT_CHAIN_ID: u32 = 1000;
+    
+    /// Time after which a relayer's unconfirmed claim on a payment can be released
+    pub const CLAIM_TIMEOUT: u64 = 3_600_000;
+    
+    /// Maximum number of relayers per chain
+    pub const MAX_RELAYERS: usize = 16;
+    
+    /// Maximum number of payment ids examined by a single filtered query
+    pub const MAX_SCAN: u32 = 500;
+    
+    /// Maximum number of payments created or executed in one batch
+    pub const MAX_BATCH_SIZE: usize = 32;
+    
+    /// Default share of referees' delivery fees paid to their referrer, in basis points
+    pub const DEFAULT_REFERRAL_SHARE_BPS: u32 = 1_000;
+    
+    /// Gas the Snowbridge gateway may spend on a transfer, unless its chain sets its own
+    pub const DEFAULT_DISPATCH_GAS: u64 = 100_000;
+    
+    /// Parachain id of Asset Hub
+    pub const ASSET_HUB_CHAIN_ID: u32 = 1000;
+    
+    /// Index of the assets pallet in Asset Hub's runtime
+    pub const ASSET_HUB_ASSETS_PALLET: u8 = 50;
+    
+    /// Asset Hub id of USDT
+    pub const USDT_ASSET_ID: u32 = 1984;
+    
+    /// Asset Hub id of USDC
+    pub const USDC_ASSET_ID: u32 = 1337;
+    
+    /// Payment details: sender, recipient, amount, source chain, destination chain, executed
+    pub type PaymentDetails = (AccountId, AccountId, Balance, u32, u32, bool);
+    
+    /// An Ethereum account or contract address
+    pub type H160 = [u8; 20];
+
+    /// Fee schedule covering delivery of payments to a chain, paid into the fee pot
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DeliveryFee {
+        /// Fee per payment
+        pub base: Balance,
+        /// Fee in basis points of native payment amounts
+        pub bps: u32,
+        /// Upper bound on the fee, 0 for none
+        pub max: Balance,
+    }
+    
+    impl DeliveryFee {
+        /// The fee for a payment of `amount`, which only counts for native payments
+        pub fn quote(&self, native_amount: Balance) -> Balance {
+            let fee = self
+                .base
+                .saturating_add(native_amount.saturating_mul(u128::from(self.bps)) / BASIS_POINTS);
+            if self.max > 0 { fee.min(self.max) } else { fee }
+        }
+    }
+    
+    /// One step of a destination path, mirroring the XCM v4 junctions in use
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum DestinationJunction {
+        Parachain(u32),
+        AccountId32([u8; 32]),
+        AccountKey20([u8; 20]),
+        PalletInstance(u8),
+        GeneralIndex(u128),
+        /// `GlobalConsensus(Ethereum { chain_id })`, the network bridged by Snowbridge
+        Ethereum { chain_id: u64 },
+    }
+    
+    /// An XCM location relative to this chain: `parents` hops up, then down `interior`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Destination {
+        pub parents: u8,
+        pub interior: Vec<DestinationJunction>,
+    }
+    
+    impl Destination {
+        /// The sibling parachain with `para_id`
+        pub fn sibling(para_id: u32) -> Self {
+            Self {
+                parents: 1,
+                interior: ink::prelude::vec![DestinationJunction::Parachain(para_id)],
+            }
+        }
+        
+        /// The Ethereum network `chain_id`, as seen from a parachain
+        pub fn ethereum(chain_id: u64) -> Self {
+            Self {
+                parents: 2,
+                interior: ink::prelude::vec![DestinationJunction::Ethereum { chain_id }],
+            }
+        }
+        
+        /// The chain id of the Ethereum network the location is on, if any
+        pub fn ethereum_chain_id(&self) -> Option<u64> {
+            match self.interior.first() {
+                Some(DestinationJunction::Ethereum { chain_id }) => Some(*chain_id),
+                _ => None,
+            }
+        }
+        
+        /// This location extended by `junction`
+        pub fn join(&self, junction: DestinationJunction) -> Self {
+            let mut interior = self.interior.clone();
+            interior.push(junction);
+            Self {
+                parents: self.parents,
+                interior,
+            }
+        }
+        
+        /// Whether the location fits in `MAX_JUNCTIONS`
+        pub fn is_valid(&self) -> bool {
+            self.interior.len() <= MAX_JUNCTIONS
+        }
+        
+        /// The equivalent XCM v4 location
+        #[cfg(feature = "xcm-dispatch")]
+        fn to_xcm(&self) -> Result<xcm::v4::Location, Error> {
+            use xcm::v4::{Junction, Junctions, Location};
+            
+            let mut location = Location::new(self.parents, Junctions::Here);
+            for junction in self.interior.iter() {
+                let junction = match *junction {
+                    DestinationJunction::Parachain(id) => Junction::Parachain(id),
+                    DestinationJunction::AccountId32(id) => Junction::AccountId32 { network: None, id },
+                    DestinationJunction::AccountKey20(key) => Junction::AccountKey20 { network: None, key },
+                    DestinationJunction::PalletInstance(index) => Junction::PalletInstance(index),
+                    DestinationJunction::GeneralIndex(index) => Junction::GeneralIndex(index),
+                    DestinationJunction::Ethereum { chain_id } => {
+                        Junction::GlobalConsensus(xcm::v4::NetworkId::Ethereum { chain_id })
+                    }
+                };
+                location
+                    .push_interior(junction)
+                    .map_err(|_| Error::InvalidDestination)?;
+            }
+            Ok(location)
+        }
+    }
+    
+    /// A destination tagged with its XCM version
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VersionedDestination {
+        V4(Destination),
+    }
+    
+    impl VersionedDestination {
+        /// The destination in the latest version
+        pub fn as_latest(&self) -> &Destination {
+            match self {
+                VersionedDestination::V4(destination) => destination,
+            }
+        }
+    }
+    
+    /// Account receiving a payment on its destination chain
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum Beneficiary {
+        /// A 32-byte Substrate account
+        AccountId32(AccountId),
+        /// A 20-byte account on an EVM parachain
+        AccountKey20([u8; 20]),
+    }
+    
+    impl Beneficiary {
+        /// The account tracking the payment locally
+        ///
+        /// EVM addresses map to the address followed by twelve `0xEE` bytes,
+        /// the convention used by pallet-revive.
+        pub fn local_account(&self) -> AccountId {
+            match self {
+                Beneficiary::AccountId32(account) => *account,
+                Beneficiary::AccountKey20(key) => {
+                    let mut account = [0xEE; 32];
+                    account[..20].copy_from_slice(key);
+                    AccountId::from(account)
+                }
+            }
+        }
+        
+        /// The junction addressing the beneficiary on its chain
+        pub fn junction(&self) -> DestinationJunction {
+            match self {
+                Beneficiary::AccountId32(account) => DestinationJunction::AccountId32(*account.as_ref()),
+                Beneficiary::AccountKey20(key) => DestinationJunction::AccountKey20(*key),
+            }
+        }
+    }
+    
+    /// Snowbridge outbound message of a payment to Ethereum, delivered by relayers
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct EthereumTransfer {
+        /// Chain id of the Ethereum network
+        pub chain_id: u64,
+        /// ERC-20 contract of the transferred asset
+        pub token: H160,
+        /// H160 account receiving the tokens
+        pub recipient: H160,
+        pub amount: Balance,
+        /// Gas the gateway may spend dispatching the message
+        pub max_dispatch_gas: u64,
+        /// Delivery fee paid for the bridge, in the payment's fee asset
+        pub fee: Balance,
+    }
+    
+    impl EthereumTransfer {
+        /// ABI encoding of the gateway's `AgentExecuteCommand.TransferToken`
+        ///
+        /// That is `abi.encode(uint8(0), abi.encode(token, recipient, uint128(amount)))`,
+        /// executed by this chain's agent on the gateway.
+        pub fn outbound_payload(&self) -> Vec<u8> {
+            fn word(bytes: &[u8]) -> [u8; 32] {
+                let mut word = [0; 32];
+                word[32 - bytes.len()..].copy_from_slice(bytes);
+                word
+            }
+            
+            let params = [
+                word(&self.token),
+                word(&self.recipient),
+                word(&self.amount.to_be_bytes()),
+            ];
+            let mut payload = Vec::with_capacity(6 * 32);
+            payload.extend_from_slice(&word(&[0]));
+            payload.extend_from_slice(&word(&[0x40]));
+            payload.extend_from_slice(&word(&[(params.len() * 32) as u8]));
+            for param in params.iter() {
+                payload.extend_from_slice(param);
+            }
+            payload
+        }
+    }
+    
+    /// A relayer's claim on delivering a payment
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PaymentClaim {
+        pub relayer: AccountId,
+        pub claimed_at: u64,
+    }
+    
+    /// Evidence a claimed payment executed, matching its chain's verification mode
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum ExecutionProof {
+        /// The relayer's attestation, for chains in `Trusted` mode
+        Attestation,
+        /// The chain attester's signature, for chains in `Signed` mode
+        Signed { block_hash: Hash, signature: Vec<u8> },
+        /// A Merkle proof, for chains in `StateProof` mode
+        StateProof { block_hash: Hash, proof: Vec<Hash> },
+    }
+    
+    /// Why a destination chain did not execute a payment
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum FailureReason {
+        DestinationRejected,
+        BeneficiaryInvalid,
+        AssetNotAccepted,
+        Other,
+    }
+    
+    /// Outcome of an outbound message, as reported by an XCM `QueryResponse`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum QueryOutcome {
+        /// The message executed completely
+        Complete,
+        /// Execution stopped with an error at the instruction with this index
+        Failed { instruction: u32 },
+    }
+    
+    /// Payment counters of a destination chain
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ChainStats {
+        pub created: u32,
+        pub executed: u32,
+        pub failed: u32,
+        pub refunded: u32,
+        pub cancelled: u32,
+        /// Native amount of all payments created
+        pub volume: Balance,
+    }
+    
+    /// Velocity cap on transfers; zero limits are not enforced
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RateLimit {
+        /// Window length in milliseconds
+        pub window: u64,
+        /// Maximum amount sent per window, in the native token unless the limit is set for an asset
+        pub max_amount: Balance,
+        /// Maximum number of payments per window
+        pub max_count: u32,
+    }
+    
+    /// Transfers counted against a rate limit in the current window
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RateUsage {
+        /// When the current window started
+        pub window_start: u64,
+        /// Amount sent in the window
+        pub amount: Balance,
+        /// Payments created in the window
+        pub count: u32,
+    }
+    
+    impl RateUsage {
+        /// Usage after adding a payment at `now`, or `None` if it would exceed `limit`
+        ///
+        /// A new window starts with the first payment after the previous one elapsed.
+        pub fn record(self, limit: &RateLimit, now: u64, amount: Balance) -> Option<Self> {
+            let mut usage = if now >= self.window_start.saturating_add(limit.window) {
+                RateUsage { window_start: now, ..Default::default() }
+            } else {
+                self
+            };
+            usage.amount = usage.amount.saturating_add(amount);
+            usage.count = usage.count.saturating_add(1);
+            
+            let amount_exceeded = limit.max_amount > 0 && usage.amount > limit.max_amount;
+            let count_exceeded = limit.max_count > 0 && usage.count > limit.max_count;
+            if amount_exceeded || count_exceeded {
+                return None;
+            }
+            Some(usage)
+        }
+    }
+    
+    /// Weight bought for executing a message on its destination
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct XcmWeight {
+        pub ref_time: u64,
+        pub proof_size: u64,
+    }
+    
+    /// How funds move to a destination chain
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum TransferMode {
+        /// This chain keeps the funds in reserve and the destination mints derivatives
+        #[default]
+        ReserveTransfer,
+        /// Funds are burnt here and minted on the destination; native token only
+        Teleport,
+    }
+    
+    /// How dispatched messages reach pallet-xcm, chosen per deployment
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum XcmDispatchPath {
+        /// The contracts pallet's XCM host functions, available on every runtime
+        #[default]
+        HostFunctions,
+        /// The `XcmExtension` chain extension, on runtimes that add it
+        ChainExtension,
+    }
+    
+    /// Metadata and constraints of a destination chain
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ChainConfig {
+        /// Location XCM messages to the chain are addressed to
+        pub location: VersionedDestination,
+        /// Blake2-256 hash of the chain's human-readable name
+        pub name_hash: Hash,
+        /// Decimals of the chain's native token
+        pub decimals: u8,
+        /// Smallest amount that may be sent to the chain
+        pub min_transfer_amount: Balance,
+        /// Fee paid to the relayer executing a payment
+        pub fee: RelayerFee,
+        /// Fee covering delivery, paid into the fee pot
+        pub delivery_fee: DeliveryFee,
+        /// Whether new payments to the chain and their execution are blocked; refunds still work
+        pub paused: bool,
+        /// Weight limit of messages to the chain; `None` buys unlimited weight
+        pub weight_limit: Option<XcmWeight>,
+        /// XCM version the chain accepts
+        pub xcm_version: u32,
+        /// How funds move to the chain
+        pub transfer_mode: TransferMode,
+    }
+    
+    impl ChainConfig {
+        /// Configuration of a chain nobody configured: the sibling with its chain id as para id, no limits
+        pub fn default_for(chain_id: u32) -> Self {
+            Self {
+                location: VersionedDestination::V4(Destination::sibling(chain_id)),
+                name_hash: Hash::default(),
+                decimals: DEFAULT_DECIMALS,
+                min_transfer_amount: 0,
+                fee: RelayerFee::default(),
+                delivery_fee: DeliveryFee::default(),
+                paused: false,
+                weight_limit: None,
+                xcm_version: LATEST_XCM_VERSION,
+                transfer_mode: TransferMode::ReserveTransfer,
+            }
+        }
+    }
+    
+    /// Lifecycle of a cross-chain payment
+    ///
+    /// Payments start `Pending`, or `InTransit` once handed to XCM, and end
+    /// in exactly one of the remaining states.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum PaymentStatus {
+        /// Funds are held by the contract awaiting execution
+        Pending,
+        /// Funds were dispatched through XCM, or a relayer claimed the payment, awaiting confirmation
+        InTransit,
+        /// Execution was confirmed
+        Executed,
+        /// Dispatched funds were not confirmed in time
+        Failed,
+        /// Funds were returned to the sender after timing out
+        Refunded,
+        /// Funds were returned to the sender on their request
+        Cancelled,
+    }
+    
+    impl PaymentStatus {
+        /// Whether a payment may move from this status to `next`
+        pub fn can_transition_to(self, next: PaymentStatus) -> bool {
+            use PaymentStatus::*;
+            matches!(
+                (self, next),
+                (Pending, InTransit | Executed | Refunded | Cancelled)
+                    | (InTransit, Pending | Executed | Failed | Refunded)
+            )
+        }
+    }
+    
+    /// Location of a pallet-assets asset, relative to the chain holding it
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AssetLocation {
+        /// Index of the assets pallet in the chain's runtime
+        pub pallet_instance: u8,
+        /// Asset id within the pallet
+        pub general_index: u128,
+    }
+    
+    impl AssetLocation {
+        /// The Asset Hub asset with `asset_id`, such as `USDT_ASSET_ID`
+        pub fn asset_hub(asset_id: u32) -> Self {
+            Self {
+                pallet_instance: ASSET_HUB_ASSETS_PALLET,
+                general_index: u128::from(asset_id),
+            }
+        }
+    }
+    
+    /// Registry entry of an asset, in the asset's own units
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AssetMetadata {
+        pub decimals: u8,
+        /// Smallest amount that may be sent to any chain
+        pub min_transfer_amount: Balance,
+        /// Flat delivery fee when the asset pays for delivery, `None` if it cannot
+        pub delivery_fee: Option<Balance>,
+    }
+    
+    /// A cross-chain payment as returned by queries
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct XcmPaymentInfo {
+        pub payment_id: u32,
+        pub sender: AccountId,
+        pub recipient: AccountId,
+        pub beneficiary: Beneficiary,
+        pub asset_id: u32,
+        pub amount: Balance,
+        pub relayer_fee: Balance,
+        pub delivery_fee: Balance,
+        pub destination: Option<VersionedDestination>,
+        pub source_chain: u32,
+        pub destination_chain: u32,
+        pub message_type: Option<XcmMessageType>,
+        pub status: PaymentStatus,
+        pub created_at: u64,
+    }
+    
+    /// Criteria a payment must match to be returned by `get_payments`; unset fields match all
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct PaymentFilter {
+        pub sender: Option<AccountId>,
+        pub recipient: Option<AccountId>,
+        pub destination_chain: Option<u32>,
+        pub status: Option<PaymentStatus>,
+    }
+    
+    impl PaymentFilter {
+        /// Whether a payment matches every set criterion
+        pub fn matches(&self, payment: &XcmPaymentInfo) -> bool {
+            self.sender.is_none_or(|sender| sender == payment.sender)
+                && self.recipient.is_none_or(|recipient| recipient == payment.recipient)
+                && self.destination_chain.is_none_or(|chain| chain == payment.destination_chain)
+                && self.status.is_none_or(|status| status == payment.status)
+        }
+    }
+    
+    /// A page of payments, with the payment id to continue from if more remain
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct PaymentPage {
+        pub payments: Vec<XcmPaymentInfo>,
+        pub next_offset: Option<u32>,
+    }
+    
+    /// How relayers prove a payment to a chain executed
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VerificationMode {
+        /// Relayer attestations are trusted up to the chain's threshold
+        #[default]
+        Trusted,
+        /// The chain's attester must sign the execution
+        Signed,
+        /// A Merkle proof against a stored destination-chain state root is required
+        StateProof,
+    }
+    
+    /// Public key of the party signing executions on a destination chain
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AttesterKey {
+        Sr25519([u8; 32]),
+        Ecdsa([u8; 33]),
+    }
+    
+    /// What a referrer brought in through its referees
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ReferralStats {
+        /// Accounts that named this referrer
+        pub referees: u32,
+        /// Delivery fees paid on the referees' executed payments
+        pub fees_generated: Balance,
+        /// Rewards accrued from those fees, claimed or not
+        pub rewards_earned: Balance,
+    }
+    
+    /// Fee paid to the relayer executing a payment, charged to the sender on top of the amount
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    pub enum XcmMessageType {
-        Payment,
-        BillSplitting,
-        TokenTransfer,
-        Refund,
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RelayerFee {
+        /// Flat fee per payment
+        pub flat: Balance,
+        /// Fee in basis points of the payment amount
+        pub bps: u32,
     }
     
     /// Contract errors
-    #[derive(Debug, PartialEq, Eq)]
+    ///
+    /// Discriminants are part of the interface: variants are only ever
+    /// appended, never reordered or removed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
     pub enum Error {
-        PaymentNotFound,
-        UnauthorizedAccess,
-        InvalidAmount,
-        InvalidChain,
-        AlreadyExecuted,
-        InsufficientBalance,
-        XcmExecutionFailed,
-        InvalidDestination,
+        PaymentNotFound = 0,
+        UnauthorizedAccess = 1,
+        InvalidAmount = 2,
+        InvalidChain = 3,
+        AlreadyExecuted = 4,
+        InsufficientBalance = 5,
+        XcmExecutionFailed = 6,
+        InvalidDestination = 7,
+        PaymentCancelled = 8,
+        AlreadyDispatched = 9,
+        PaymentFailed = 10,
+        PaymentNotTimedOut = 11,
+        AlreadyAttested = 12,
+        InvalidThreshold = 13,
+        TooManyRelayers = 14,
+        InvalidFee = 15,
+        TransferFailed = 16,
+        ChainPaused = 17,
+        AmountBelowMinimum = 18,
+        InvalidBatch = 19,
+        RateLimitExceeded = 20,
+        PaymentClaimed = 21,
+        NotClaimed = 22,
+        ClaimNotExpired = 23,
+        UnknownQuery = 24,
+        UnsupportedXcmVersion = 25,
+        InvalidReason = 26,
+        PaymentNotSettled = 27,
+        UnsupportedTransferMode = 28,
+        RelayerNotFound = 29,
+        GracePeriodActive = 30,
+        InvalidMessageType = 31,
+        PaymentNotExecuted = 32,
+        PaymentRefunded = 33,
+        MessageAlreadyProcessed = 34,
+        InvalidMessageHash = 35,
+        UnsupportedAsset = 36,
+        ProofRequired = 37,
+        WrongVerificationMode = 38,
+        InvalidSignature = 39,
+        InvalidProof = 40,
+        UnknownStateRoot = 41,
+        UpgradeFailed = 42,
+        StorageVersionMismatch = 43,
+        AlreadyMigrated = 44,
+        ReferrerAlreadySet = 45,
+        SelfReferral = 46,
+        InvalidFeeAsset = 47,
+        /// A dispatched payment was not confirmed before its chain's timeout
+        DeliveryTimedOut = 48,
+        /// No treasury is configured to sweep fees to
+        NoTreasury = 49,
+        /// The XCM chain extension is not the configured dispatch path
+        ChainExtensionDisabled = 50,
+        /// The runtime could not weigh the message
+        WeightUnavailable = 51,
+        /// The account already relays for the chain
+        RelayerExists = 52,
+        /// Amount caps are set but the asset has no rate limit of its own
+        AssetRateLimitUnset = 53,
+    }
+    
+    impl Error {
+        /// Code identifying the error across the contracts, stable between releases
+        pub fn error_code(&self) -> u16 {
+            ERROR_CODE_BASE + *self as u16
+        }
+    }
+    
+    impl From<Error> for xcc_common::Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::UnauthorizedAccess => Self::UnauthorizedAccess,
+                Error::InvalidAmount => Self::InvalidAmount,
+                Error::InsufficientBalance => Self::InsufficientBalance,
+                Error::InvalidChain => Self::InvalidChain,
+                Error::PaymentNotFound => Self::NotFound,
+                Error::TransferFailed => Self::TransferFailed,
+                error => Self::Other(error as u8),
+            }
+        }
     }
     
     /// XCM handler contract storage
@@ -43,8 +815,10 @@ mod xcm_handler {
         payment_destination_chains: Mapping<u32, u32>,
         /// Payment types
         payment_types: Mapping<u32, u8>, // Encoded XcmMessageType
-        /// Payment execution status
-        payment_executed: Mapping<u32, bool>,
+        /// Payment lifecycle statuses
+        payment_statuses: Mapping<u32, PaymentStatus>,
+        /// Per-chain timeouts in milliseconds, overriding the default
+        chain_timeouts: Mapping<u32, u64>,
         /// Payment creation timestamps
         payment_timestamps: Mapping<u32, u64>,
         /// Chain configurations (chain_id -> is_supported)
@@ -56,7 +830,161 @@ mod xcm_handler {
         /// Contract owner
         owner: AccountId,
         /// Relayer addresses for each chain
-        relayers: Mapping<u32, AccountId>,
+        relayers: Mapping<u32, Vec<AccountId>>,
+        /// Attestations needed to execute a payment per chain, one by default
+        relayer_thresholds: Mapping<u32, u32>,
+        /// Operators that attested a payment's execution (payment_id -> operator -> attested)
+        payment_attestations: Mapping<(u32, AccountId), bool>,
+        /// Number of attestations per payment
+        attestation_counts: Mapping<u32, u32>,
+        /// Operators that attested an inbound message (message_hash -> operator -> attested)
+        inbound_attestations: Mapping<(Hash, AccountId), bool>,
+        /// Number of attestations per inbound message
+        inbound_attestation_counts: Mapping<Hash, u32>,
+        /// Chain metadata, absent for chains using `ChainConfig::default_for`
+        chain_configs: Mapping<u32, ChainConfig>,
+        /// Relayer fee charged for each payment
+        payment_relayer_fees: Mapping<u32, Balance>,
+        /// Delivery fee charged for each payment
+        payment_delivery_fees: Mapping<u32, Balance>,
+        /// Delivery fees of executed payments not yet paid out to relayers
+        fee_pot: Balance,
+        /// Fees earned by relayers and not yet claimed
+        relayer_rewards: Mapping<AccountId, Balance>,
+        /// Account that referred each user, set once
+        referrers: Mapping<AccountId, AccountId>,
+        /// Share of referees' delivery fees paid to their referrer, in basis points
+        referral_share_bps: u32,
+        /// Referral rewards not yet claimed
+        referral_rewards: Mapping<AccountId, Balance>,
+        /// Statistics per referrer
+        referral_stats: Mapping<AccountId, ReferralStats>,
+        /// Funds the contract holds for pending payments
+        pending_collateral: Balance,
+        /// Native balances and relayer and referral rewards owed to accounts
+        liabilities: Balance,
+        /// Number of pending payments per user, as sender or recipient
+        user_pending_counts: Mapping<AccountId, u32>,
+        /// Pending payment ids per user, (user, index) -> payment id
+        user_pending_payments: Mapping<(AccountId, u32), u32>,
+        /// Index of a pending payment in its user's list, (user, payment id) -> index
+        user_pending_indexes: Mapping<(AccountId, u32), u32>,
+        /// Hashes of inbound messages already credited
+        processed_messages: Mapping<Hash, bool>,
+        /// Inbound nonces already used per source chain, (chain_id, nonce)
+        used_inbound_nonces: Mapping<(u32, u64), bool>,
+        /// How executions to each chain are verified
+        verification_modes: Mapping<u32, VerificationMode>,
+        /// Keys signing executions per chain
+        chain_attesters: Mapping<u32, AttesterKey>,
+        /// Destination-chain state roots, (chain_id, block_hash) -> root
+        state_roots: Mapping<(u32, Hash), Hash>,
+        /// Non-native asset balances, (account, asset_id) -> balance
+        asset_balances: Mapping<(AccountId, u32), Balance>,
+        /// Registered assets and their location on this chain
+        assets: Mapping<u32, AssetLocation>,
+        /// Representations of registered assets per chain, (asset_id, chain_id)
+        asset_representations: Mapping<(u32, u32), AssetLocation>,
+        /// Full destination of each payment, the beneficiary on its chain
+        payment_destinations: Mapping<u32, VersionedDestination>,
+        /// Beneficiaries of payments to EVM accounts, absent for 32-byte accounts
+        payment_beneficiaries: Mapping<u32, Beneficiary>,
+        /// Payment assets, absent for the native token
+        payment_assets: Mapping<u32, u32>,
+        /// ERC-20 contracts of assets on Ethereum chains, (asset_id, chain_id) -> token
+        erc20_tokens: Mapping<(u32, u32), H160>,
+        /// Gas limits of transfers per Ethereum chain, overriding the default
+        dispatch_gas_limits: Mapping<u32, u64>,
+        /// Outbound Snowbridge messages of payments to Ethereum
+        ethereum_transfers: Mapping<u32, EthereumTransfer>,
+        /// Decimals, minimums and delivery fees of registered assets
+        asset_metadata: Mapping<u32, AssetMetadata>,
+        /// Assets paying the delivery fee of payments, absent for the native token
+        payment_fee_assets: Mapping<u32, u32>,
+        /// Delivery fees collected per non-native asset and not yet paid out
+        asset_fee_pots: Mapping<u32, Balance>,
+        /// Delivery fees of unsettled payments per asset, refunded or moved to a fee pot once they settle
+        pending_delivery_fees: Mapping<u32, Balance>,
+        /// Balances ever credited, (account, asset_id) in order, for `export_balances`
+        balance_holders: Mapping<u32, (AccountId, u32)>,
+        /// Position of each balance in `balance_holders`
+        balance_holder_indexes: Mapping<(AccountId, u32), u32>,
+        /// Number of balances ever credited
+        balance_holder_count: u32,
+        /// Number of payment batches created
+        batch_counter: u32,
+        /// Payment ids of each batch
+        batches: Mapping<u32, Vec<u32>>,
+        /// Batch each batched payment belongs to
+        payment_batches: Mapping<u32, u32>,
+        /// Rate limit applying to each sender, if any
+        account_rate_limit: Option<RateLimit>,
+        /// Rate limits applying to all payments to a chain
+        chain_rate_limits: Mapping<u32, RateLimit>,
+        /// Usage of the account rate limit per sender
+        account_rate_usage: Mapping<AccountId, RateUsage>,
+        /// Usage of the chain rate limits per chain
+        chain_rate_usage: Mapping<u32, RateUsage>,
+        /// Rate limits in a non-native asset's own units, applying to each sender and each chain
+        asset_rate_limits: Mapping<u32, RateLimit>,
+        /// Usage of the asset rate limits per (sender, asset_id)
+        asset_account_rate_usage: Mapping<(AccountId, u32), RateUsage>,
+        /// Usage of the asset rate limits per (chain_id, asset_id)
+        asset_chain_rate_usage: Mapping<(u32, u32), RateUsage>,
+        /// Relayer claims on payments awaiting confirmation
+        payment_claims: Mapping<u32, PaymentClaim>,
+        /// Reasons reported for failed payments
+        payment_failure_reasons: Mapping<u32, FailureReason>,
+        /// Next XCM query id
+        query_counter: u64,
+        /// Payment whose outcome each outstanding query reports
+        query_payments: Mapping<u64, u32>,
+        /// Outstanding query of each dispatched payment
+        payment_queries: Mapping<u32, u64>,
+        /// Refund payment returning each refunded payment
+        payment_refunds: Mapping<u32, u32>,
+        /// Payment returned by each refund payment
+        refund_origins: Mapping<u32, u32>,
+        /// Account relaying XCM query responses to the contract
+        query_responder: Option<AccountId>,
+        /// Time after which a relayer key of a chain stops being accepted
+        relayer_expiries: Mapping<(u32, AccountId), u64>,
+        /// First key of the operator a rotated relayer key belongs to, so all its keys attest once
+        relayer_operators: Mapping<(u32, AccountId), AccountId>,
+        /// Account allowed to manage relayer keys besides the owner
+        relayer_manager: Option<AccountId>,
+        /// Account allowed to pause chains besides the owner
+        pauser: Option<AccountId>,
+        /// Payment counters of each destination chain
+        chain_stats: Mapping<u32, ChainStats>,
+        /// Time each payment reached its final status
+        payment_settled_at: Mapping<u32, u64>,
+        /// Blake2-256 hash of the details of each pruned payment
+        payment_archives: Mapping<u32, Hash>,
+        /// Native funds returned by `recover_trapped_assets`
+        total_recovered: Balance,
+        /// Number of trapped asset recoveries
+        recovery_counter: u32,
+        /// Parachain id of the chain this contract is deployed on
+        own_chain_id: u32,
+        /// BillSplitting contract notified when bill payments execute
+        bill_splitting: Option<AccountId>,
+        /// Registry resolving the contracts not configured directly
+        registry: Option<AccountId>,
+        /// Keeper registry paid bounties for refunding timed-out payments
+        keeper_registry: Option<AccountId>,
+        /// Bonds the keeper registry pays bounties from (payment_id -> bond)
+        keeper_bonds: Mapping<u32, KeeperBond>,
+        /// Funders' shares of the keeper bonds ((payment_id, funder) -> stake)
+        keeper_stakes: Mapping<(u32, AccountId), KeeperStake>,
+        /// Sum of all keeper bonds
+        keeper_bonded: Balance,
+        /// Treasury the fee pot is swept to
+        treasury: Option<AccountId>,
+        /// How dispatched messages reach pallet-xcm
+        dispatch_path: XcmDispatchPath,
+        /// Layout version of the stored data, behind `STORAGE_VERSION` until migrated
+        version: u32,
     }
     
     /// Events
@@ -68,8 +996,16 @@ mod xcm_handler {
         sender: AccountId,
         #[ink(topic)]
         recipient: AccountId,
+        beneficiary: Beneficiary,
+        asset_id: u32,
         amount: Balance,
+        message_type: XcmMessageType,
+        source_chain: u32,
         destination_chain: u32,
+        relayer_fee: Balance,
+        delivery_fee: Balance,
+        created_at: u64,
+        status: PaymentStatus,
     }
     
     #[ink(event)]
@@ -80,393 +1016,6166 @@ mod xcm_handler {
         sender: AccountId,
         #[ink(topic)]
         recipient: AccountId,
+        beneficiary: Beneficiary,
+        asset_id: u32,
         amount: Balance,
+        message_type: XcmMessageType,
+        source_chain: u32,
+        destination_chain: u32,
         executor: AccountId,
+        relayer_fee: Balance,
+        executed_at: u64,
+        status: PaymentStatus,
     }
     
+    /// Emitted on every payment status change after creation
     #[ink(event)]
-    pub struct ChainConfigured {
+    pub struct PaymentStatusChanged {
         #[ink(topic)]
-        chain_id: u32,
-        supported: bool,
-        relayer: Option<AccountId>,
+        payment_id: u32,
+        from: PaymentStatus,
+        to: PaymentStatus,
+        timestamp: u64,
     }
     
     #[ink(event)]
-    pub struct BalanceDeposited {
+    pub struct CrossChainPaymentCancelled {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        sender: AccountId,
+        amount: Balance,
+        status: PaymentStatus,
+    }
+    
+    #[ink(event)]
+    pub struct CrossChainPaymentRefunded {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        sender: AccountId,
+        amount: Balance,
+        status: PaymentStatus,
+    }
+    
+    #[ink(event)]
+    pub struct CrossChainPaymentFailed {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        sender: AccountId,
+        amount: Balance,
+        status: PaymentStatus,
+        /// `Error::error_code` of why the payment failed
+        error_code: u16,
+    }
+    
+    #[ink(event)]
+    pub struct RefundPaymentCreated {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        original_payment_id: u32,
+        sender: AccountId,
+    }
+    
+    #[ink(event)]
+    pub struct PaymentReturned {
+        #[ink(topic)]
+        original_payment_id: u32,
+        #[ink(topic)]
+        refund_payment_id: u32,
+        #[ink(topic)]
+        sender: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct TrappedAssetsRecovered {
+        #[ink(topic)]
+        recovery_id: u32,
         #[ink(topic)]
         account: AccountId,
         amount: Balance,
+        #[ink(topic)]
+        reason_hash: Hash,
+        timestamp: u64,
     }
     
-    impl XcmHandler {
-        /// Constructor
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            let caller = Self::env().caller();
-            let mut instance = Self {
-                payment_senders: Mapping::default(),
-                payment_recipients: Mapping::default(),
-                payment_amounts: Mapping::default(),
-                payment_source_chains: Mapping::default(),
-                payment_destination_chains: Mapping::default(),
-                payment_types: Mapping::default(),
-                payment_executed: Mapping::default(),
-                payment_timestamps: Mapping::default(),
-                supported_chains: Mapping::default(),
-                balances: Mapping::default(),
-                payment_counter: 0,
-                owner: caller,
-                relayers: Mapping::default(),
-            };
-            
-            // Initialize with some default supported chains
-            instance.supported_chains.insert(1000, &true); // Rococo
-            instance.supported_chains.insert(2000, &true); // Westend
-            instance.supported_chains.insert(3000, &true); // Kusama
-            
+    #[ink(event)]
+    pub struct PauserSet {
+        pauser: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct ChainPauseUpdated {
+        #[ink(topic)]
+        chain_id: u32,
+        paused: bool,
+        by: AccountId,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerManagerSet {
+        manager: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerExpirySet {
+        #[ink(topic)]
+        chain_id: u32,
+        #[ink(topic)]
+        relayer: AccountId,
+        expires_at: Option<u64>,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerRotated {
+        #[ink(topic)]
+        chain_id: u32,
+        #[ink(topic)]
+        old: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+        old_expires_at: Option<u64>,
+    }
+    
+    #[ink(event)]
+    pub struct PaymentPruned {
+        #[ink(topic)]
+        payment_id: u32,
+        archive_hash: Hash,
+    }
+    
+    #[ink(event)]
+    pub struct OwnChainIdSet {
+        chain_id: u32,
+    }
+    
+    #[ink(event)]
+    pub struct ChainTimeoutSet {
+        #[ink(topic)]
+        chain_id: u32,
+        timeout: u64,
+    }
+    
+    #[ink(event)]
+    pub struct ChainConfigured {
+        #[ink(topic)]
+        chain_id: u32,
+        supported: bool,
+        relayer: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct ExecutionAttested {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        relayer: AccountId,
+        attestations: u32,
+        threshold: u32,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerUpdated {
+        #[ink(topic)]
+        chain_id: u32,
+        #[ink(topic)]
+        relayer: AccountId,
+        active: bool,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerThresholdSet {
+        #[ink(topic)]
+        chain_id: u32,
+        threshold: u32,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerFeeSet {
+        #[ink(topic)]
+        chain_id: u32,
+        fee: RelayerFee,
+    }
+    
+    #[ink(event)]
+    pub struct ChainConfigUpdated {
+        #[ink(topic)]
+        chain_id: u32,
+        config: ChainConfig,
+    }
+    
+    #[ink(event)]
+    pub struct DispatchPathSet {
+        dispatch_path: XcmDispatchPath,
+    }
+    
+    #[ink(event)]
+    pub struct DeliveryFeeSet {
+        #[ink(topic)]
+        chain_id: u32,
+        fee: DeliveryFee,
+    }
+    
+    #[ink(event)]
+    pub struct FeePotPaidOut {
+        #[ink(topic)]
+        relayer: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct TreasurySet {
+        treasury: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct FeePotSwept {
+        #[ink(topic)]
+        treasury: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct KeeperRegistrySet {
+        keeper_registry: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct KeeperBondPosted {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct KeeperBondReclaimed {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        funder: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct KeeperBountyPaid {
+        #[ink(topic)]
+        job_id: u32,
+        #[ink(topic)]
+        keeper: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct RelayerRewardsClaimed {
+        #[ink(topic)]
+        relayer: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct ReferrerSet {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        referrer: AccountId,
+    }
+    
+    #[ink(event)]
+    pub struct ReferralRewardAccrued {
+        #[ink(topic)]
+        referrer: AccountId,
+        #[ink(topic)]
+        payment_id: u32,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct ReferralRewardsClaimed {
+        #[ink(topic)]
+        referrer: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct ReferralShareSet {
+        share_bps: u32,
+    }
+    
+    #[ink(event)]
+    pub struct BillSplittingConfigured {
+        bill_splitting: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct RegistryConfigured {
+        registry: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: Hash,
+    }
+    
+    #[ink(event)]
+    pub struct StorageMigrated {
+        from: u32,
+        to: u32,
+    }
+    
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+    
+    #[ink(event)]
+    pub struct BalanceDeposited {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct BalanceWithdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct InboundPaymentAttested {
+        #[ink(topic)]
+        message_hash: Hash,
+        #[ink(topic)]
+        relayer: AccountId,
+        attestations: u32,
+        threshold: u32,
+    }
+    
+    #[ink(event)]
+    pub struct InboundPaymentReceived {
+        #[ink(topic)]
+        source_chain: u32,
+        #[ink(topic)]
+        message_hash: Hash,
+        sender: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        asset_id: u32,
+        amount: Balance,
+        nonce: u64,
+        relayer: AccountId,
+    }
+    
+    #[ink(event)]
+    pub struct VerificationConfigured {
+        #[ink(topic)]
+        chain_id: u32,
+        mode: VerificationMode,
+        attester: Option<AttesterKey>,
+    }
+    
+    #[ink(event)]
+    pub struct StateRootSubmitted {
+        #[ink(topic)]
+        chain_id: u32,
+        block_hash: Hash,
+        state_root: Hash,
+    }
+    
+    #[ink(event)]
+    pub struct ExecutionVerified {
+        #[ink(topic)]
+        payment_id: u32,
+        mode: VerificationMode,
+        block_hash: Hash,
+    }
+    
+    #[ink(event)]
+    pub struct AssetRegistered {
+        #[ink(topic)]
+        asset_id: u32,
+        location: AssetLocation,
+    }
+    
+    #[ink(event)]
+    pub struct AssetRepresentationSet {
+        #[ink(topic)]
+        asset_id: u32,
+        #[ink(topic)]
+        chain_id: u32,
+        location: Option<AssetLocation>,
+    }
+    
+    #[ink(event)]
+    pub struct AssetMetadataSet {
+        #[ink(topic)]
+        asset_id: u32,
+        metadata: AssetMetadata,
+    }
+    
+    #[ink(event)]
+    pub struct AssetFeePotPaidOut {
+        #[ink(topic)]
+        asset_id: u32,
+        #[ink(topic)]
+        relayer: AccountId,
+        amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct Erc20TokenSet {
+        #[ink(topic)]
+        asset_id: u32,
+        #[ink(topic)]
+        chain_id: u32,
+        token: Option<H160>,
+    }
+    
+    #[ink(event)]
+    pub struct DispatchGasSet {
+        #[ink(topic)]
+        chain_id: u32,
+        gas: u64,
+    }
+    
+    #[ink(event)]
+    pub struct CrossChainBatchCreated {
+        #[ink(topic)]
+        batch_id: u32,
+        #[ink(topic)]
+        sender: AccountId,
+        destination_chain: u32,
+        payment_ids: Vec<u32>,
+        total_amount: Balance,
+    }
+    
+    #[ink(event)]
+    pub struct RateLimitSet {
+        /// The chain limited, `None` for the per-account and asset limits
+        #[ink(topic)]
+        chain_id: Option<u32>,
+        limit: Option<RateLimit>,
+        /// The asset limited, `None` for native limits
+        #[ink(topic)]
+        asset_id: Option<u32>,
+    }
+    
+    #[ink(event)]
+    pub struct PaymentClaimed {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        relayer: AccountId,
+        status: PaymentStatus,
+    }
+    
+    #[ink(event)]
+    pub struct PaymentClaimReleased {
+        #[ink(topic)]
+        payment_id: u32,
+        relayer: AccountId,
+        status: PaymentStatus,
+    }
+    
+    #[ink(event)]
+    pub struct PaymentFailureReported {
+        #[ink(topic)]
+        payment_id: u32,
+        #[ink(topic)]
+        relayer: AccountId,
+        reason: FailureReason,
+        refunded: Balance,
+        status: PaymentStatus,
+    }
+    
+    #[ink(event)]
+    pub struct QueryResponderSet {
+        responder: Option<AccountId>,
+    }
+    
+    #[ink(event)]
+    pub struct QueryResponseReceived {
+        #[ink(topic)]
+        query_id: u64,
+        #[ink(topic)]
+        payment_id: u32,
+        outcome: QueryOutcome,
+        status: PaymentStatus,
+    }
+    
+    impl XcmHandler {
+        /// Constructor
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let caller = Self::env().caller();
+            let mut instance = Self {
+                payment_senders: Mapping::default(),
+                payment_recipients: Mapping::default(),
+                payment_amounts: Mapping::default(),
+                payment_source_chains: Mapping::default(),
+                payment_destination_chains: Mapping::default(),
+                payment_types: Mapping::default(),
+                payment_statuses: Mapping::default(),
+                chain_timeouts: Mapping::default(),
+                payment_timestamps: Mapping::default(),
+                supported_chains: Mapping::default(),
+                balances: Mapping::default(),
+                payment_counter: 0,
+                owner: caller,
+                relayers: Mapping::default(),
+                relayer_thresholds: Mapping::default(),
+                payment_attestations: Mapping::default(),
+                attestation_counts: Mapping::default(),
+                inbound_attestations: Mapping::default(),
+                inbound_attestation_counts: Mapping::default(),
+                chain_configs: Mapping::default(),
+                payment_relayer_fees: Mapping::default(),
+                payment_delivery_fees: Mapping::default(),
+                fee_pot: 0,
+                relayer_rewards: Mapping::default(),
+                referrers: Mapping::default(),
+                referral_share_bps: DEFAULT_REFERRAL_SHARE_BPS,
+                referral_rewards: Mapping::default(),
+                referral_stats: Mapping::default(),
+                pending_collateral: 0,
+                liabilities: 0,
+                user_pending_counts: Mapping::default(),
+                user_pending_payments: Mapping::default(),
+                user_pending_indexes: Mapping::default(),
+                processed_messages: Mapping::default(),
+                used_inbound_nonces: Mapping::default(),
+                verification_modes: Mapping::default(),
+                chain_attesters: Mapping::default(),
+                state_roots: Mapping::default(),
+                asset_balances: Mapping::default(),
+                assets: Mapping::default(),
+                asset_representations: Mapping::default(),
+                payment_destinations: Mapping::default(),
+                payment_beneficiaries: Mapping::default(),
+                payment_assets: Mapping::default(),
+                erc20_tokens: Mapping::default(),
+                dispatch_gas_limits: Mapping::default(),
+                ethereum_transfers: Mapping::default(),
+                asset_metadata: Mapping::default(),
+                payment_fee_assets: Mapping::default(),
+                asset_fee_pots: Mapping::default(),
+                pending_delivery_fees: Mapping::default(),
+                balance_holders: Mapping::default(),
+                balance_holder_indexes: Mapping::default(),
+                balance_holder_count: 0,
+                batch_counter: 0,
+                account_rate_limit: None,
+                chain_rate_limits: Mapping::default(),
+                account_rate_usage: Mapping::default(),
+                chain_rate_usage: Mapping::default(),
+                asset_rate_limits: Mapping::default(),
+                asset_account_rate_usage: Mapping::default(),
+                asset_chain_rate_usage: Mapping::default(),
+                payment_claims: Mapping::default(),
+                payment_failure_reasons: Mapping::default(),
+                query_counter: 0,
+                query_payments: Mapping::default(),
+                payment_queries: Mapping::default(),
+                payment_refunds: Mapping::default(),
+                refund_origins: Mapping::default(),
+                query_responder: None,
+                batches: Mapping::default(),
+                payment_batches: Mapping::default(),
+                relayer_expiries: Mapping::default(),
+                relayer_operators: Mapping::default(),
+                relayer_manager: None,
+                pauser: None,
+                chain_stats: Mapping::default(),
+                payment_settled_at: Mapping::default(),
+                payment_archives: Mapping::default(),
+                total_recovered: 0,
+                recovery_counter: 0,
+                own_chain_id: DEFAULT_CHAIN_ID,
+                bill_splitting: None,
+                registry: None,
+                keeper_registry: None,
+                keeper_bonds: Mapping::default(),
+                keeper_stakes: Mapping::default(),
+                keeper_bonded: 0,
+                treasury: None,
+                dispatch_path: XcmDispatchPath::default(),
+                version: STORAGE_VERSION,
+            };
+            
+            // Initialize with some default supported chains
+            instance.supported_chains.insert(1000, &true); // Rococo
+            instance.supported_chains.insert(2000, &true); // Westend
+            instance.supported_chains.insert(3000, &true); // Kusama
+            
             instance
         }
         
-        /// Default constructor
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new()
+        /// Constructor for a deployment on the parachain `own_chain_id`
+        #[ink(constructor)]
+        pub fn new_on_chain(own_chain_id: u32) -> Self {
+            let mut instance = Self::new();
+            instance.own_chain_id = own_chain_id;
+            instance
+        }
+        
+        /// Default constructor
+        #[ink(constructor)]
+        #[allow(clippy::should_implement_trait)]
+        pub fn default() -> Self {
+            Self::new()
+        }
+        
+        /// Create a cross-chain payment request
+        #[ink(message)]
+        pub fn create_cross_chain_payment(
+            &mut self,
+            recipient: AccountId,
+            asset_id: u32,
+            amount: Balance,
+            destination_chain: u32,
+            message_type: XcmMessageType,
+        ) -> Result<u32, Error> {
+            self.create_cross_chain_payment_to(
+                Beneficiary::AccountId32(recipient),
+                asset_id,
+                amount,
+                destination_chain,
+                message_type,
+            )
+        }
+        
+        /// Create a cross-chain payment request to any kind of beneficiary
+        ///
+        /// Use this for 20-byte recipients on EVM parachains.
+        #[ink(message)]
+        pub fn create_cross_chain_payment_to(
+            &mut self,
+            beneficiary: Beneficiary,
+            asset_id: u32,
+            amount: Balance,
+            destination_chain: u32,
+            message_type: XcmMessageType,
+        ) -> Result<u32, Error> {
+            self.create_cross_chain_payment_with_fee_asset(
+                beneficiary,
+                asset_id,
+                amount,
+                destination_chain,
+                message_type,
+                NATIVE_ASSET,
+            )
+        }
+        
+        /// Create a cross-chain payment whose delivery fee is paid in `fee_asset`
+        ///
+        /// Non-native fee assets pay the flat delivery fee set in their
+        /// registry entry, so stablecoin senders need no native balance
+        /// unless the chain charges a relayer fee.
+        #[ink(message)]
+        pub fn create_cross_chain_payment_with_fee_asset(
+            &mut self,
+            beneficiary: Beneficiary,
+            asset_id: u32,
+            amount: Balance,
+            destination_chain: u32,
+            message_type: XcmMessageType,
+            fee_asset: u32,
+        ) -> Result<u32, Error> {
+            // Refunds must reference the payment they return
+            if message_type == XcmMessageType::Refund {
+                return Err(Error::InvalidMessageType);
+            }
+            
+            self.create_payment(beneficiary, asset_id, amount, destination_chain, message_type, fee_asset)
+        }
+        
+        /// Send an executed payment back to its sender on its source chain, as its recipient
+        ///
+        /// Creates a `Refund` payment of the same asset and amount; executing
+        /// it credits the original sender. A payment is refunded at most once,
+        /// unless its refund is cancelled or fails.
+        #[ink(message)]
+        pub fn create_refund_payment(&mut self, original_payment_id: u32) -> Result<u32, Error> {
+            let sender = self.payment_senders.get(original_payment_id).ok_or(Error::PaymentNotFound)?;
+            if self.payment_recipients.get(original_payment_id) != Some(self.env().caller()) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if self.get_payment_status(original_payment_id) != Some(PaymentStatus::Executed) {
+                return Err(Error::PaymentNotExecuted);
+            }
+            if self.payment_refunds.contains(original_payment_id) {
+                return Err(Error::PaymentRefunded);
+            }
+            
+            let payment_id = self.create_payment(
+                Beneficiary::AccountId32(sender),
+                self.get_payment_asset(original_payment_id),
+                self.payment_amounts.get(original_payment_id).unwrap_or(0),
+                self.payment_source_chains.get(original_payment_id).unwrap_or(0),
+                XcmMessageType::Refund,
+                NATIVE_ASSET,
+            )?;
+            self.payment_refunds.insert(original_payment_id, &payment_id);
+            self.refund_origins.insert(payment_id, &original_payment_id);
+            
+            self.env().emit_event(RefundPaymentCreated {
+                payment_id,
+                original_payment_id,
+                sender,
+            });
+            
+            Ok(payment_id)
+        }
+        
+        /// Create a payment of any message type from the caller
+        fn create_payment(
+            &mut self,
+            beneficiary: Beneficiary,
+            asset_id: u32,
+            amount: Balance,
+            destination_chain: u32,
+            message_type: XcmMessageType,
+            fee_asset: u32,
+        ) -> Result<u32, Error> {
+            let sender = self.env().caller();
+            let recipient = beneficiary.local_account();
+            
+            // Validate amount
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            // Check if destination chain is supported and accepts the amount
+            let chain_config = self.get_chain_config(destination_chain).ok_or(Error::InvalidChain)?;
+            if chain_config.paused {
+                return Err(Error::ChainPaused);
+            }
+            // Chain minimums are in the native token, other assets set theirs in the registry
+            let min_transfer_amount = if asset_id == NATIVE_ASSET {
+                chain_config.min_transfer_amount
+            } else {
+                self.asset_metadata.get(asset_id).map_or(0, |metadata| metadata.min_transfer_amount)
+            };
+            if amount < min_transfer_amount {
+                return Err(Error::AmountBelowMinimum);
+            }
+            
+            let native_amount = if asset_id == NATIVE_ASSET { amount } else { 0 };
+            self.consume_rate_limits(sender, destination_chain, asset_id, amount)?;
+            
+            // Snowbridge carries ERC-20 tokens to 20-byte accounts only
+            let ethereum = match chain_config.location.as_latest().ethereum_chain_id() {
+                Some(chain_id) => {
+                    let Beneficiary::AccountKey20(recipient) = beneficiary else {
+                        return Err(Error::InvalidDestination);
+                    };
+                    let token = self
+                        .erc20_tokens
+                        .get((asset_id, destination_chain))
+                        .ok_or(Error::UnsupportedAsset)?;
+                    Some((chain_id, token, recipient))
+                }
+                None => None,
+            };
+            
+            // Non-native assets need a representation on the destination
+            // chain, and can only move there by reserve transfer
+            if asset_id != NATIVE_ASSET && ethereum.is_none() {
+                if !self.asset_representations.contains((asset_id, destination_chain)) {
+                    return Err(Error::UnsupportedAsset);
+                }
+                if chain_config.transfer_mode == TransferMode::Teleport {
+                    return Err(Error::UnsupportedTransferMode);
+                }
+            }
+            
+            // The relayer fee is paid in the native token, the delivery fee
+            // in the fee asset
+            let relayer_fee = self.quote_relayer_fee(destination_chain, native_amount);
+            let delivery_fee = if fee_asset == NATIVE_ASSET {
+                chain_config.delivery_fee.quote(native_amount)
+            } else {
+                self.asset_metadata
+                    .get(fee_asset)
+                    .and_then(|metadata| metadata.delivery_fee)
+                    .ok_or(Error::InvalidFeeAsset)?
+            };
+            
+            // Check sender balances cover the amount and the fees
+            let due = |asset| {
+                let mut due = if asset == asset_id { amount } else { 0 };
+                if asset == NATIVE_ASSET {
+                    due = due.saturating_add(relayer_fee);
+                }
+                if asset == fee_asset {
+                    due = due.saturating_add(delivery_fee);
+                }
+                due
+            };
+            for asset in [asset_id, fee_asset, NATIVE_ASSET] {
+                if self.get_asset_balance(sender, asset) < due(asset) {
+                    return Err(Error::InsufficientBalance);
+                }
+            }
+            
+            let source_chain = self.own_chain_id;
+            
+            let payment_id = self.payment_counter;
+            
+            // Store payment information
+            self.payment_senders.insert(payment_id, &sender);
+            self.payment_recipients.insert(payment_id, &recipient);
+            self.payment_amounts.insert(payment_id, &amount);
+            self.payment_source_chains.insert(payment_id, &source_chain);
+            self.payment_destination_chains.insert(payment_id, &destination_chain);
+            let destination = chain_config.location.as_latest().join(beneficiary.junction());
+            if !destination.is_valid() {
+                return Err(Error::InvalidDestination);
+            }
+            self.payment_destinations.insert(payment_id, &VersionedDestination::V4(destination));
+            if let Beneficiary::AccountKey20(_) = beneficiary {
+                self.payment_beneficiaries.insert(payment_id, &beneficiary);
+            }
+            if asset_id != NATIVE_ASSET {
+                self.payment_assets.insert(payment_id, &asset_id);
+            }
+            if let Some((chain_id, token, recipient)) = ethereum {
+                let transfer = EthereumTransfer {
+                    chain_id,
+                    token,
+                    recipient,
+                    amount,
+                    max_dispatch_gas: self.get_dispatch_gas(destination_chain),
+                    fee: delivery_fee,
+                };
+                self.ethereum_transfers.insert(payment_id, &transfer);
+            }
+            
+            self.payment_types.insert(payment_id, &message_type.code());
+            
+            self.payment_statuses.insert(payment_id, &PaymentStatus::Pending);
+            self.index_pending(sender, payment_id);
+            self.index_pending(recipient, payment_id);
+            self.payment_timestamps.insert(payment_id, &self.env().block_timestamp());
+            
+            // Deduct from sender balances
+            self.debit(sender, asset_id, amount);
+            self.debit(sender, NATIVE_ASSET, relayer_fee);
+            self.debit(sender, fee_asset, delivery_fee);
+            if relayer_fee > 0 {
+                self.payment_relayer_fees.insert(payment_id, &relayer_fee);
+            }
+            if fee_asset != NATIVE_ASSET {
+                self.payment_fee_assets.insert(payment_id, &fee_asset);
+            }
+            if delivery_fee > 0 {
+                self.payment_delivery_fees.insert(payment_id, &delivery_fee);
+                let pending = self.get_pending_delivery_fees(fee_asset).saturating_add(delivery_fee);
+                self.pending_delivery_fees.insert(fee_asset, &pending);
+            }
+            self.pending_collateral = self.pending_collateral.saturating_add(self.held_collateral(payment_id));
+            self.payment_counter = self.payment_counter.saturating_add(1);
+            
+            let mut stats = self.get_chain_stats(destination_chain);
+            stats.created = stats.created.saturating_add(1);
+            stats.volume = stats.volume.saturating_add(native_amount);
+            self.chain_stats.insert(destination_chain, &stats);
+            
+            self.env().emit_event(CrossChainPaymentCreated {
+                payment_id,
+                sender,
+                recipient,
+                beneficiary,
+                asset_id,
+                amount,
+                message_type,
+                source_chain,
+                destination_chain,
+                relayer_fee,
+                delivery_fee,
+                created_at: self.env().block_timestamp(),
+                status: PaymentStatus::Pending,
+            });
+            
+            // Hand the funds to pallet-xcm for delivery to the destination
+            // chain; relayers carry payments to Ethereum over the bridge
+            #[cfg(feature = "xcm-dispatch")]
+            if ethereum.is_none() {
+                let query_id = self.register_query(payment_id);
+                self.dispatch_transfer(payment_id, asset_id, amount, query_id)?;
+                self.transition(payment_id, PaymentStatus::InTransit)?;
+            }
+            
+            Ok(payment_id)
+        }
+        
+        /// Create native payments to several recipients on one chain, linked as a batch
+        ///
+        /// The sender's balance is checked against the batch total, fees
+        /// included, before any payment is created.
+        #[ink(message)]
+        pub fn create_cross_chain_batch(
+            &mut self,
+            payments: Vec<(AccountId, Balance)>,
+            destination_chain: u32,
+        ) -> Result<Vec<u32>, Error> {
+            let sender = self.env().caller();
+            
+            if payments.is_empty() || payments.len() > MAX_BATCH_SIZE {
+                return Err(Error::InvalidBatch);
+            }
+            
+            let mut total_amount: Balance = 0;
+            let mut total_due: Balance = 0;
+            for (_, amount) in payments.iter() {
+                total_amount = total_amount.saturating_add(*amount);
+                total_due = total_due
+                    .saturating_add(*amount)
+                    .saturating_add(self.quote_cross_chain_fee(destination_chain, *amount));
+            }
+            if self.get_balance(sender) < total_due {
+                return Err(Error::InsufficientBalance);
+            }
+            
+            let batch_id = self.batch_counter;
+            let mut payment_ids = Vec::with_capacity(payments.len());
+            for (recipient, amount) in payments {
+                let payment_id = self.create_cross_chain_payment(
+                    recipient,
+                    NATIVE_ASSET,
+                    amount,
+                    destination_chain,
+                    XcmMessageType::Payment,
+                )?;
+                self.payment_batches.insert(payment_id, &batch_id);
+                payment_ids.push(payment_id);
+            }
+            
+            self.batches.insert(batch_id, &payment_ids);
+            self.batch_counter = self.batch_counter.saturating_add(1);
+            
+            self.env().emit_event(CrossChainBatchCreated {
+                batch_id,
+                sender,
+                destination_chain,
+                payment_ids: payment_ids.clone(),
+                total_amount,
+            });
+            
+            Ok(payment_ids)
+        }
+        
+        /// Execute a cross-chain payment (called by relayer)
+        ///
+        /// The owner executes directly on `Trusted` chains; a relayer's call
+        /// counts as its attestation, see `attest_execution`.
+        #[ink(message)]
+        pub fn execute_cross_chain_payment(
+            &mut self,
+            payment_id: u32,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            
+            // Check if payment exists and is still pending
+            if !self.payment_senders.contains(payment_id) {
+                return Err(Error::PaymentNotFound);
+            }
+            self.ensure_pending(payment_id)?;
+            
+            if caller == self.owner {
+                // Chains with verification enabled need a proof from the owner too
+                let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+                if self.get_verification_mode(destination_chain) != VerificationMode::Trusted {
+                    return Err(Error::ProofRequired);
+                }
+                return self.finalize_execution(payment_id, caller);
+            }
+            
+            self.attest_execution(payment_id)
+        }
+        
+        /// Execute several payments in one call, as `execute_cross_chain_payment` would each
+        ///
+        /// Returns each payment's result in order; a failing payment does not
+        /// affect the others.
+        #[ink(message)]
+        pub fn execute_cross_chain_payments(
+            &mut self,
+            payment_ids: Vec<u32>,
+        ) -> Result<Vec<Result<(), Error>>, Error> {
+            if payment_ids.is_empty() || payment_ids.len() > MAX_BATCH_SIZE {
+                return Err(Error::InvalidBatch);
+            }
+            
+            Ok(payment_ids
+                .into_iter()
+                .map(|payment_id| self.execute_cross_chain_payment(payment_id))
+                .collect())
+        }
+        
+        /// Attest a payment's execution as a relayer of its destination chain
+        ///
+        /// The payment executes once the chain's threshold of relayers attested.
+        #[ink(message)]
+        pub fn attest_execution(&mut self, payment_id: u32) -> Result<(), Error> {
+            let relayer = self.env().caller();
+            
+            if !self.payment_senders.contains(payment_id) {
+                return Err(Error::PaymentNotFound);
+            }
+            self.ensure_pending(payment_id)?;
+            
+            // Verify caller is authorized relayer for the destination chain
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            if !self.is_relayer(destination_chain, relayer) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            // Chains with verification enabled do not take relayers' word for it
+            if self.get_verification_mode(destination_chain) != VerificationMode::Trusted {
+                return Err(Error::ProofRequired);
+            }
+            
+            self.record_attestation(payment_id, relayer, destination_chain)
+        }
+        
+        /// Execute a payment with the destination chain attester's signature
+        ///
+        /// The signature covers `execution_message(payment_id, block_hash)`;
+        /// ECDSA signatures are over its Blake2-256 hash.
+        #[ink(message)]
+        pub fn execute_with_attestation(
+            &mut self,
+            payment_id: u32,
+            block_hash: Hash,
+            signature: Vec<u8>,
+        ) -> Result<(), Error> {
+            let destination_chain = self.ensure_verifiable(payment_id, VerificationMode::Signed)?;
+            self.verify_signature(payment_id, destination_chain, block_hash, signature)?;
+            self.finalize_execution(payment_id, self.env().caller())
+        }
+        
+        /// Execute a payment with a Merkle proof of its receipt under a stored state root
+        ///
+        /// The leaf is `execution_leaf(payment_id)`; each proof step hashes the
+        /// current node and its sibling in ascending order.
+        #[ink(message)]
+        pub fn execute_with_proof(
+            &mut self,
+            payment_id: u32,
+            block_hash: Hash,
+            proof: Vec<Hash>,
+        ) -> Result<(), Error> {
+            let destination_chain = self.ensure_verifiable(payment_id, VerificationMode::StateProof)?;
+            self.verify_state_proof(payment_id, destination_chain, block_hash, proof)?;
+            self.finalize_execution(payment_id, self.env().caller())
+        }
+        
+        /// Set the account relaying XCM query responses (owner only)
+        ///
+        /// pallet-xcm receives the `QueryResponse` of each dispatched payment;
+        /// the responder forwards its outcome through `on_query_response`.
+        #[ink(message)]
+        pub fn set_query_responder(&mut self, responder: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.query_responder = responder;
+            self.env().emit_event(QueryResponderSet { responder });
+            
+            Ok(())
+        }
+        
+        /// Settle a dispatched payment from the XCM outcome reported under its query (responder only)
+        ///
+        /// A complete execution marks the payment executed, rewarding the
+        /// responder with its relayer fee; an error marks it failed and
+        /// returns its fees to the sender, the dispatched amount being
+        /// trapped on the destination.
+        #[ink(message)]
+        pub fn on_query_response(&mut self, query_id: u64, outcome: QueryOutcome) -> Result<(), Error> {
+            let responder = self.env().caller();
+            if self.query_responder != Some(responder) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            let payment_id = self.query_payments.take(query_id).ok_or(Error::UnknownQuery)?;
+            self.payment_queries.remove(payment_id);
+            
+            let status = match outcome {
+                QueryOutcome::Complete => {
+                    self.finalize_execution(payment_id, responder)?;
+                    PaymentStatus::Executed
+                }
+                QueryOutcome::Failed { .. } => {
+                    let sender = self.payment_senders.get(payment_id).ok_or(Error::PaymentNotFound)?;
+                    self.transition(payment_id, PaymentStatus::Failed)?;
+                    self.refund_undispatched(payment_id, sender);
+                    self.env().emit_event(CrossChainPaymentFailed {
+                        payment_id,
+                        sender,
+                        amount: self.payment_amounts.get(payment_id).unwrap_or(0),
+                        status: PaymentStatus::Failed,
+                        error_code: Error::XcmExecutionFailed.error_code(),
+                    });
+                    PaymentStatus::Failed
+                }
+            };
+            
+            self.env().emit_event(QueryResponseReceived {
+                query_id,
+                payment_id,
+                outcome,
+                status,
+            });
+            
+            Ok(())
+        }
+        
+        /// Claim a pending payment for delivery as a relayer of its destination chain
+        ///
+        /// The payment stays `InTransit` until the claimant confirms its
+        /// execution or reports its failure; claims not settled within
+        /// `CLAIM_TIMEOUT` can be released by anyone.
+        #[ink(message)]
+        pub fn claim_payment(&mut self, payment_id: u32) -> Result<(), Error> {
+            let relayer = self.env().caller();
+            
+            if !self.payment_senders.contains(payment_id) {
+                return Err(Error::PaymentNotFound);
+            }
+            
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            if !self.is_relayer(destination_chain, relayer) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            self.ensure_chain_active(destination_chain)?;
+            
+            if self.payment_claims.contains(payment_id) {
+                return Err(Error::PaymentClaimed);
+            }
+            
+            self.transition(payment_id, PaymentStatus::InTransit)?;
+            self.payment_claims.insert(
+                payment_id,
+                &PaymentClaim {
+                    relayer,
+                    claimed_at: self.env().block_timestamp(),
+                },
+            );
+            
+            self.env().emit_event(PaymentClaimed {
+                payment_id,
+                relayer,
+                status: PaymentStatus::InTransit,
+            });
+            
+            Ok(())
+        }
+        
+        /// Confirm a claimed payment executed on its destination chain, as its claimant
+        ///
+        /// The proof must match the chain's verification mode; on `Trusted`
+        /// chains the confirmation counts as the claimant's attestation.
+        #[ink(message)]
+        pub fn confirm_execution(&mut self, payment_id: u32, proof: ExecutionProof) -> Result<(), Error> {
+            let relayer = self.env().caller();
+            let destination_chain = self.ensure_claimant(payment_id, relayer)?;
+            
+            match (self.get_verification_mode(destination_chain), proof) {
+                (VerificationMode::Trusted, ExecutionProof::Attestation) => {
+                    self.record_attestation(payment_id, relayer, destination_chain)
+                }
+                (VerificationMode::Signed, ExecutionProof::Signed { block_hash, signature }) => {
+                    self.verify_signature(payment_id, destination_chain, block_hash, signature)?;
+                    self.finalize_execution(payment_id, relayer)
+                }
+                (VerificationMode::StateProof, ExecutionProof::StateProof { block_hash, proof }) => {
+                    self.verify_state_proof(payment_id, destination_chain, block_hash, proof)?;
+                    self.finalize_execution(payment_id, relayer)
+                }
+                _ => Err(Error::WrongVerificationMode),
+            }
+        }
+        
+        /// Report that a claimed payment failed on its destination chain, as its claimant
+        ///
+        /// The payment is marked failed and its funds return to the sender.
+        #[ink(message)]
+        pub fn report_failure(&mut self, payment_id: u32, reason: FailureReason) -> Result<(), Error> {
+            let relayer = self.env().caller();
+            self.ensure_claimant(payment_id, relayer)?;
+            let sender = self.payment_senders.get(payment_id).ok_or(Error::PaymentNotFound)?;
+            
+            self.transition(payment_id, PaymentStatus::Failed)?;
+            self.payment_claims.remove(payment_id);
+            self.payment_failure_reasons.insert(payment_id, &reason);
+            let refunded = self.refund_sender(payment_id, sender);
+            
+            self.env().emit_event(PaymentFailureReported {
+                payment_id,
+                relayer,
+                reason,
+                refunded,
+                status: PaymentStatus::Failed,
+            });
+            
+            Ok(())
+        }
+        
+        /// Release a claim its relayer did not settle within `CLAIM_TIMEOUT`, returning the payment to pending
+        #[ink(message)]
+        pub fn release_claim(&mut self, payment_id: u32) -> Result<(), Error> {
+            let claim = self.payment_claims.get(payment_id).ok_or(Error::NotClaimed)?;
+            if self.env().block_timestamp() < claim.claimed_at.saturating_add(CLAIM_TIMEOUT) {
+                return Err(Error::ClaimNotExpired);
+            }
+            
+            self.transition(payment_id, PaymentStatus::Pending)?;
+            self.payment_claims.remove(payment_id);
+            
+            self.env().emit_event(PaymentClaimReleased {
+                payment_id,
+                relayer: claim.relayer,
+                status: PaymentStatus::Pending,
+            });
+            
+            Ok(())
+        }
+        
+        /// Cancel a pending payment as its sender, restoring their balance
+        #[ink(message)]
+        pub fn cancel_cross_chain_payment(&mut self, payment_id: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            
+            let sender = self.payment_senders.get(payment_id).ok_or(Error::PaymentNotFound)?;
+            if caller != sender {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.ensure_refundable(payment_id)?;
+            
+            self.transition(payment_id, PaymentStatus::Cancelled)?;
+            let amount = self.refund_sender(payment_id, sender);
+            
+            self.env().emit_event(CrossChainPaymentCancelled {
+                payment_id,
+                sender,
+                amount,
+                status: PaymentStatus::Cancelled,
+            });
+            
+            Ok(())
+        }
+        
+        /// Refund a payment still pending after its chain's timeout
+        ///
+        /// Payments already dispatched through XCM are marked failed instead.
+        /// Callable by anyone, so funds cannot be stranded by an offline relayer.
+        #[ink(message)]
+        pub fn refund_timed_out_payment(&mut self, payment_id: u32) -> Result<(), Error> {
+            let sender = self.payment_senders.get(payment_id).ok_or(Error::PaymentNotFound)?;
+            let status = self.ensure_pending(payment_id)?;
+            
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            let created_at = self.payment_timestamps.get(payment_id).unwrap_or(0);
+            if self.env().block_timestamp() < created_at.saturating_add(self.get_chain_timeout(destination_chain)) {
+                return Err(Error::PaymentNotTimedOut);
+            }
+            
+            // Dispatched funds left the chain and can only be marked failed;
+            // claimed payments still hold theirs
+            let claimed = self.payment_claims.take(payment_id).is_some();
+            if status == PaymentStatus::InTransit && !claimed {
+                self.transition(payment_id, PaymentStatus::Failed)?;
+                self.env().emit_event(CrossChainPaymentFailed {
+                    payment_id,
+                    sender,
+                    amount: self.payment_amounts.get(payment_id).unwrap_or(0),
+                    status: PaymentStatus::Failed,
+                    error_code: Error::DeliveryTimedOut.error_code(),
+                });
+                return Ok(());
+            }
+            
+            self.transition(payment_id, PaymentStatus::Refunded)?;
+            let amount = self.refund_sender(payment_id, sender);
+            
+            self.env().emit_event(CrossChainPaymentRefunded {
+                payment_id,
+                sender,
+                amount,
+                status: PaymentStatus::Refunded,
+            });
+            
+            Ok(())
+        }
+        
+        /// Register a pallet-assets asset of this chain under a local asset id (owner only)
+        #[ink(message)]
+        pub fn register_asset(&mut self, asset_id: u32, location: AssetLocation) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if asset_id == NATIVE_ASSET {
+                return Err(Error::UnsupportedAsset);
+            }
+            
+            self.assets.insert(asset_id, &location);
+            
+            self.env().emit_event(AssetRegistered { asset_id, location });
+            
+            Ok(())
+        }
+        
+        /// Set or clear how a registered asset is represented on a chain (owner only)
+        #[ink(message)]
+        pub fn set_asset_representation(
+            &mut self,
+            asset_id: u32,
+            chain_id: u32,
+            location: Option<AssetLocation>,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if !self.assets.contains(asset_id) {
+                return Err(Error::UnsupportedAsset);
+            }
+            
+            if let Some(location) = location {
+                self.asset_representations.insert((asset_id, chain_id), &location);
+            } else {
+                self.asset_representations.remove((asset_id, chain_id));
+            }
+            
+            self.env().emit_event(AssetRepresentationSet {
+                asset_id,
+                chain_id,
+                location,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set or clear the ERC-20 contract of an asset on an Ethereum chain (owner only)
+        #[ink(message)]
+        pub fn set_erc20_token(&mut self, asset_id: u32, chain_id: u32, token: Option<H160>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if asset_id != NATIVE_ASSET && !self.assets.contains(asset_id) {
+                return Err(Error::UnsupportedAsset);
+            }
+            
+            if let Some(token) = token {
+                self.erc20_tokens.insert((asset_id, chain_id), &token);
+            } else {
+                self.erc20_tokens.remove((asset_id, chain_id));
+            }
+            
+            self.env().emit_event(Erc20TokenSet {
+                asset_id,
+                chain_id,
+                token,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set the gas the gateway may spend on transfers to an Ethereum chain (owner only)
+        #[ink(message)]
+        pub fn set_dispatch_gas(&mut self, chain_id: u32, gas: u64) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if gas == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            self.dispatch_gas_limits.insert(chain_id, &gas);
+            
+            self.env().emit_event(DispatchGasSet { chain_id, gas });
+            
+            Ok(())
+        }
+        
+        /// Set the decimals, transfer minimum and delivery fee of an asset (owner only)
+        #[ink(message)]
+        pub fn set_asset_metadata(&mut self, asset_id: u32, metadata: AssetMetadata) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            // Native amounts and fees are set per chain
+            if !self.assets.contains(asset_id) {
+                return Err(Error::UnsupportedAsset);
+            }
+            
+            self.asset_metadata.insert(asset_id, &metadata);
+            
+            self.env().emit_event(AssetMetadataSet { asset_id, metadata });
+            
+            Ok(())
+        }
+        
+        /// Register an Asset Hub asset under its Asset Hub id, sendable to Asset Hub (owner only)
+        ///
+        /// Stablecoins are registered with `USDT_ASSET_ID` or `USDC_ASSET_ID`.
+        #[ink(message)]
+        pub fn register_asset_hub_asset(&mut self, asset_id: u32, metadata: AssetMetadata) -> Result<(), Error> {
+            let location = AssetLocation::asset_hub(asset_id);
+            self.register_asset(asset_id, location)?;
+            self.set_asset_representation(asset_id, ASSET_HUB_CHAIN_ID, Some(location))?;
+            self.set_asset_metadata(asset_id, metadata)
+        }
+        
+        /// Set how executions to a chain are verified, and who signs them (owner only)
+        #[ink(message)]
+        pub fn set_verification_mode(
+            &mut self,
+            chain_id: u32,
+            mode: VerificationMode,
+            attester: Option<AttesterKey>,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if mode == VerificationMode::Signed && attester.is_none() {
+                return Err(Error::WrongVerificationMode);
+            }
+            
+            self.verification_modes.insert(chain_id, &mode);
+            if let Some(key) = attester {
+                self.chain_attesters.insert(chain_id, &key);
+            } else {
+                self.chain_attesters.remove(chain_id);
+            }
+            
+            self.env().emit_event(VerificationConfigured {
+                chain_id,
+                mode,
+                attester,
+            });
+            
+            Ok(())
+        }
+        
+        /// Record a destination-chain state root proofs are checked against (owner only)
+        #[ink(message)]
+        pub fn submit_state_root(&mut self, chain_id: u32, block_hash: Hash, state_root: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.state_roots.insert((chain_id, block_hash), &state_root);
+            
+            self.env().emit_event(StateRootSubmitted {
+                chain_id,
+                block_hash,
+                state_root,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set how long payments to a chain may stay pending before they can be refunded
+        #[ink(message)]
+        pub fn set_chain_timeout(&mut self, chain_id: u32, timeout: u64) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.chain_timeouts.insert(chain_id, &timeout);
+            
+            self.env().emit_event(ChainTimeoutSet { chain_id, timeout });
+            
+            Ok(())
+        }
+        
+        /// Set the parachain id recorded as the source of new payments (owner only)
+        #[ink(message)]
+        pub fn set_own_chain_id(&mut self, chain_id: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if chain_id == 0 {
+                return Err(Error::InvalidChain);
+            }
+            
+            self.own_chain_id = chain_id;
+            self.env().emit_event(OwnChainIdSet { chain_id });
+            
+            Ok(())
+        }
+        
+        /// Configure the BillSplitting contract notified of executed bill payments
+        #[ink(message)]
+        pub fn set_bill_splitting(&mut self, bill_splitting: Option<AccountId>) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.bill_splitting = bill_splitting;
+            
+            self.env().emit_event(BillSplittingConfigured { bill_splitting });
+            
+            Ok(())
+        }
+        
+        /// Configure the registry resolving the BillSplitting contract when none is set directly
+        #[ink(message)]
+        pub fn set_registry(&mut self, registry: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.registry = registry;
+            
+            self.env().emit_event(RegistryConfigured { registry });
+            
+            Ok(())
+        }
+        
+        /// Set the treasury the fee pot is swept to (owner only)
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.treasury = treasury;
+            
+            self.env().emit_event(TreasurySet { treasury });
+            
+            Ok(())
+        }
+        
+        /// Set the keeper registry paid bounties for refunding timed-out payments (owner only)
+        #[ink(message)]
+        pub fn set_keeper_registry(&mut self, keeper_registry: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.keeper_registry = keeper_registry;
+            
+            self.env().emit_event(KeeperRegistrySet { keeper_registry });
+            
+            Ok(())
+        }
+        
+        /// Bond the transferred value as bounty for keepers refunding a payment
+        ///
+        /// Anyone may add to the bond of an existing job; each funder reclaims
+        /// their share of what the keepers did not earn.
+        #[ink(message, payable)]
+        pub fn post_keeper_bond(&mut self, job_id: u32) -> Result<(), Error> {
+            let funder = self.env().caller();
+            let amount = self.env().transferred_value();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if !self.payment_senders.contains(job_id) {
+                return Err(Error::PaymentNotFound);
+            }
+            
+            let mut bond = self.keeper_bonds.get(job_id).unwrap_or_default();
+            let stake = bond.post(self.keeper_stakes.get((job_id, funder)).unwrap_or_default(), amount);
+            self.keeper_bonds.insert(job_id, &bond);
+            self.keeper_stakes.insert((job_id, funder), &stake);
+            self.keeper_bonded = self.keeper_bonded.saturating_add(amount);
+            
+            self.env().emit_event(KeeperBondPosted { job_id, funder, amount });
+            
+            Ok(())
+        }
+        
+        /// Take back the caller's share of what is left of a job's bond
+        #[ink(message)]
+        pub fn reclaim_keeper_bond(&mut self, job_id: u32) -> Result<Balance, Error> {
+            let funder = self.env().caller();
+            let stake = self.keeper_stakes.take((job_id, funder)).ok_or(Error::UnauthorizedAccess)?;
+            
+            let mut bond = self.keeper_bonds.get(job_id).unwrap_or_default();
+            let amount = bond.reclaim(stake);
+            self.keeper_bonds.insert(job_id, &bond);
+            self.keeper_bonded = self.keeper_bonded.saturating_sub(amount);
+            self.env()
+                .transfer(funder, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.env().emit_event(KeeperBondReclaimed { job_id, funder, amount });
+            
+            Ok(amount)
+        }
+        
+        /// Replace the contract's code, keeping its storage and address
+        ///
+        /// Code changing the storage layout must be followed by `migrate_from`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+            
+            self.env().emit_event(CodeUpgraded { code_hash });
+            
+            Ok(())
+        }
+        
+        /// Migrate storage written at version `from` to `STORAGE_VERSION`
+        ///
+        /// `from` must match the stored version, so a migration runs only once.
+        /// Each layout change bumps `STORAGE_VERSION` and adds its step below.
+        #[ink(message)]
+        pub fn migrate_from(&mut self, from: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            if from != self.version {
+                return Err(Error::StorageVersionMismatch);
+            }
+            if from >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+            
+            self.version = STORAGE_VERSION;
+            
+            self.env().emit_event(StorageMigrated {
+                from,
+                to: STORAGE_VERSION,
+            });
+            
+            Ok(())
+        }
+        
+        /// Configure supported chains, adding `relayer` to the chain's relayers
+        #[ink(message)]
+        pub fn configure_chain(
+            &mut self,
+            chain_id: u32,
+            supported: bool,
+            relayer: Option<AccountId>,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.supported_chains.insert(chain_id, &supported);
+            
+            if let Some(relayer_address) = relayer {
+                self.insert_relayer(chain_id, relayer_address)?;
+            }
+            
+            self.env().emit_event(ChainConfigured {
+                chain_id,
+                supported,
+                relayer,
+            });
+            
+            Ok(())
+        }
+        
+        /// Add a relayer to a chain (owner only)
+        #[ink(message)]
+        pub fn add_relayer(&mut self, chain_id: u32, relayer: AccountId) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.insert_relayer(chain_id, relayer)
+        }
+        
+        /// Remove a relayer from a chain (owner only)
+        ///
+        /// Fails if fewer operators with unexpired keys than the threshold would
+        /// remain. Attestations the relayer already made still count.
+        #[ink(message)]
+        pub fn remove_relayer(&mut self, chain_id: u32, relayer: AccountId) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            let mut relayers = self.relayers.get(chain_id).unwrap_or_default();
+            let Some(position) = relayers.iter().position(|account| *account == relayer) else {
+                return Ok(());
+            };
+            if !self.is_key_expired(chain_id, relayer)
+                && self.active_operator_count(chain_id, Some(relayer)) < self.get_relayer_threshold(chain_id)
+            {
+                return Err(Error::InvalidThreshold);
+            }
+            
+            relayers.swap_remove(position);
+            self.relayers.insert(chain_id, &relayers);
+            self.relayer_expiries.remove((chain_id, relayer));
+            self.relayer_operators.remove((chain_id, relayer));
+            
+            self.env().emit_event(RelayerUpdated {
+                chain_id,
+                relayer,
+                active: false,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set the account allowed to pause chains besides the owner (owner only)
+        #[ink(message)]
+        pub fn set_pauser(&mut self, pauser: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.pauser = pauser;
+            self.env().emit_event(PauserSet { pauser });
+            
+            Ok(())
+        }
+        
+        /// Block new payments to a chain and their execution (owner or pauser)
+        ///
+        /// Pending payments can still be cancelled or refunded.
+        #[ink(message)]
+        pub fn pause_chain(&mut self, chain_id: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.owner && self.pauser != Some(caller) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.set_chain_paused(chain_id, true, caller)
+        }
+        
+        /// Lift a chain's pause (owner only)
+        #[ink(message)]
+        pub fn unpause_chain(&mut self, chain_id: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.set_chain_paused(chain_id, false, caller)
+        }
+        
+        /// Set the account allowed to manage relayer keys besides the owner (owner only)
+        #[ink(message)]
+        pub fn set_relayer_manager(&mut self, manager: Option<AccountId>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.relayer_manager = manager;
+            self.env().emit_event(RelayerManagerSet { manager });
+            
+            Ok(())
+        }
+        
+        /// Set when a relayer key of a chain stops being accepted (owner or relayer manager)
+        #[ink(message)]
+        pub fn set_relayer_expiry(
+            &mut self,
+            chain_id: u32,
+            relayer: AccountId,
+            expires_at: Option<u64>,
+        ) -> Result<(), Error> {
+            self.ensure_relayer_manager()?;
+            
+            if !self.get_relayers(chain_id).contains(&relayer) {
+                return Err(Error::RelayerNotFound);
+            }
+            
+            if let Some(expires_at) = expires_at {
+                self.relayer_expiries.insert((chain_id, relayer), &expires_at);
+            } else {
+                self.relayer_expiries.remove((chain_id, relayer));
+            }
+            
+            self.env().emit_event(RelayerExpirySet {
+                chain_id,
+                relayer,
+                expires_at,
+            });
+            
+            Ok(())
+        }
+        
+        /// Replace a relayer key of a chain (owner or relayer manager)
+        ///
+        /// The new key is accepted right away. The old key stays valid until
+        /// `old_expires_at`, so in-flight work can finish, or is removed at once
+        /// when no expiry is given. Both keys belong to the same operator, so
+        /// what one of them attested the other cannot attest again.
+        #[ink(message)]
+        pub fn rotate_relayer(
+            &mut self,
+            chain_id: u32,
+            old: AccountId,
+            new: AccountId,
+            old_expires_at: Option<u64>,
+        ) -> Result<(), Error> {
+            self.ensure_relayer_manager()?;
+            
+            let mut relayers = self.get_relayers(chain_id);
+            let Some(position) = relayers.iter().position(|account| *account == old) else {
+                return Err(Error::RelayerNotFound);
+            };
+            if relayers.contains(&new) {
+                return Err(Error::RelayerExists);
+            }
+            let operator = self.operator_of(chain_id, old);
+            
+            if let Some(expires_at) = old_expires_at {
+                self.relayer_expiries.insert((chain_id, old), &expires_at);
+            } else {
+                relayers.swap_remove(position);
+                self.relayers.insert(chain_id, &relayers);
+                self.relayer_expiries.remove((chain_id, old));
+                self.relayer_operators.remove((chain_id, old));
+                self.env().emit_event(RelayerUpdated {
+                    chain_id,
+                    relayer: old,
+                    active: false,
+                });
+            }
+            self.insert_relayer(chain_id, new)?;
+            self.relayer_operators.insert((chain_id, new), &operator);
+            
+            self.env().emit_event(RelayerRotated {
+                chain_id,
+                old,
+                new,
+                old_expires_at,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set the fee relayers earn for executing payments to a chain (owner only)
+        #[ink(message)]
+        pub fn set_relayer_fee(&mut self, chain_id: u32, flat: Balance, bps: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if u128::from(bps) > BASIS_POINTS {
+                return Err(Error::InvalidFee);
+            }
+            
+            let fee = RelayerFee { flat, bps };
+            let mut config = self.stored_chain_config(chain_id);
+            config.fee = fee;
+            self.chain_configs.insert(chain_id, &config);
+            
+            self.env().emit_event(RelayerFeeSet { chain_id, fee });
+            
+            Ok(())
+        }
+        
+        /// Set the delivery fee schedule of a chain (owner only)
+        #[ink(message)]
+        pub fn set_delivery_fee(&mut self, chain_id: u32, fee: DeliveryFee) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if u128::from(fee.bps) > BASIS_POINTS {
+                return Err(Error::InvalidFee);
+            }
+            
+            let mut config = self.stored_chain_config(chain_id);
+            config.delivery_fee = fee;
+            self.chain_configs.insert(chain_id, &config);
+            
+            self.env().emit_event(DeliveryFeeSet { chain_id, fee });
+            
+            Ok(())
+        }
+        
+        /// Set the weight limit and XCM version of messages to a chain (owner only)
+        #[ink(message)]
+        pub fn set_chain_xcm_settings(
+            &mut self,
+            chain_id: u32,
+            weight_limit: Option<XcmWeight>,
+            xcm_version: u32,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if !(MIN_XCM_VERSION..=LATEST_XCM_VERSION).contains(&xcm_version) {
+                return Err(Error::UnsupportedXcmVersion);
+            }
+            
+            let mut config = self.stored_chain_config(chain_id);
+            config.weight_limit = weight_limit;
+            config.xcm_version = xcm_version;
+            self.chain_configs.insert(chain_id, &config);
+            
+            self.env().emit_event(ChainConfigUpdated { chain_id, config });
+            
+            Ok(())
+        }
+        
+        /// Set how dispatched messages reach pallet-xcm (owner only)
+        ///
+        /// Only runtimes with the `XcmExtension` chain extension support `ChainExtension`.
+        #[ink(message)]
+        pub fn set_dispatch_path(&mut self, dispatch_path: XcmDispatchPath) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.dispatch_path = dispatch_path;
+            
+            self.env().emit_event(DispatchPathSet { dispatch_path });
+            
+            Ok(())
+        }
+        
+        /// Set how funds move to a chain (owner only)
+        #[ink(message)]
+        pub fn set_chain_transfer_mode(&mut self, chain_id: u32, transfer_mode: TransferMode) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            let mut config = self.stored_chain_config(chain_id);
+            config.transfer_mode = transfer_mode;
+            self.chain_configs.insert(chain_id, &config);
+            
+            self.env().emit_event(ChainConfigUpdated { chain_id, config });
+            
+            Ok(())
+        }
+        
+        /// Pay a relayer from the fee pot, claimable with `claim_relayer_rewards` (owner only)
+        #[ink(message)]
+        pub fn pay_from_fee_pot(&mut self, relayer: AccountId, amount: Balance) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if amount == 0 || amount > self.fee_pot {
+                return Err(Error::InvalidAmount);
+            }
+            
+            self.fee_pot = self.fee_pot.saturating_sub(amount);
+            let rewards = self.relayer_rewards.get(relayer).unwrap_or(0);
+            self.relayer_rewards.insert(relayer, &rewards.saturating_add(amount));
+            self.liabilities = self.liabilities.saturating_add(amount);
+            
+            self.env().emit_event(FeePotPaidOut { relayer, amount });
+            
+            Ok(())
+        }
+        
+        /// Send native delivery fees from the fee pot to the treasury (owner only)
+        #[ink(message)]
+        pub fn sweep_fee_pot(&mut self, amount: Balance) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            let treasury = self.treasury.ok_or(Error::NoTreasury)?;
+            if amount == 0 || amount > self.fee_pot {
+                return Err(Error::InvalidAmount);
+            }
+            
+            self.fee_pot = self.fee_pot.saturating_sub(amount);
+            let mut collector: ink::contract_ref!(FeeCollector) = treasury.into();
+            match collector
+                .call_mut()
+                .collect_fees(IntentAsset::Native, amount)
+                .transferred_value(amount)
+                .try_invoke()
+            {
+                Ok(Ok(Ok(_))) => {}
+                _ => return Err(Error::TransferFailed),
+            }
+            
+            self.env().emit_event(FeePotSwept { treasury, amount });
+            
+            Ok(())
+        }
+        
+        /// Pay a relayer from the delivery fees collected in a non-native asset, credited to its asset balance (owner only)
+        #[ink(message)]
+        pub fn pay_from_asset_fee_pot(&mut self, asset_id: u32, relayer: AccountId, amount: Balance) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            let pot = self.get_asset_fee_pot(asset_id);
+            if asset_id == NATIVE_ASSET || amount == 0 || amount > pot {
+                return Err(Error::InvalidAmount);
+            }
+            
+            self.asset_fee_pots.insert(asset_id, &pot.saturating_sub(amount));
+            self.credit(relayer, asset_id, amount);
+            
+            self.env().emit_event(AssetFeePotPaidOut {
+                asset_id,
+                relayer,
+                amount,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set or clear the rate limit applying to each sender (owner only)
+        #[ink(message)]
+        pub fn set_account_rate_limit(&mut self, limit: Option<RateLimit>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.account_rate_limit = limit;
+            
+            self.env().emit_event(RateLimitSet {
+                chain_id: None,
+                limit,
+                asset_id: None,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set or clear the rate limit applying to all payments to a chain (owner only)
+        #[ink(message)]
+        pub fn set_chain_rate_limit(&mut self, chain_id: u32, limit: Option<RateLimit>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if let Some(limit) = limit {
+                self.chain_rate_limits.insert(chain_id, &limit);
+            } else {
+                self.chain_rate_limits.remove(chain_id);
+            }
+            
+            self.env().emit_event(RateLimitSet {
+                chain_id: Some(chain_id),
+                limit,
+                asset_id: None,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set or clear the rate limit of a non-native asset, in its own units (owner only)
+        ///
+        /// It applies to each sender and each chain separately.
+        #[ink(message)]
+        pub fn set_asset_rate_limit(&mut self, asset_id: u32, limit: Option<RateLimit>) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if asset_id == NATIVE_ASSET {
+                return Err(Error::UnsupportedAsset);
+            }
+            if let Some(limit) = limit {
+                self.asset_rate_limits.insert(asset_id, &limit);
+            } else {
+                self.asset_rate_limits.remove(asset_id);
+            }
+            
+            self.env().emit_event(RateLimitSet {
+                chain_id: None,
+                limit,
+                asset_id: Some(asset_id),
+            });
+            
+            Ok(())
+        }
+        
+        /// Set a chain's metadata and constraints (owner only)
+        #[ink(message)]
+        pub fn set_chain_config(&mut self, chain_id: u32, config: ChainConfig) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if u128::from(config.fee.bps) > BASIS_POINTS || u128::from(config.delivery_fee.bps) > BASIS_POINTS {
+                return Err(Error::InvalidFee);
+            }
+            
+            // Leave room for the beneficiary payments append
+            if config.location.as_latest().interior.len() >= MAX_JUNCTIONS {
+                return Err(Error::InvalidDestination);
+            }
+            
+            if !(MIN_XCM_VERSION..=LATEST_XCM_VERSION).contains(&config.xcm_version) {
+                return Err(Error::UnsupportedXcmVersion);
+            }
+            
+            self.chain_configs.insert(chain_id, &config);
+            
+            self.env().emit_event(ChainConfigUpdated { chain_id, config });
+            
+            Ok(())
+        }
+        
+        /// Withdraw the fees the caller earned as a relayer
+        #[ink(message)]
+        pub fn claim_relayer_rewards(&mut self) -> Result<Balance, Error> {
+            let relayer = self.env().caller();
+            let amount = self.relayer_rewards.take(relayer).unwrap_or(0);
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.liabilities = self.liabilities.saturating_sub(amount);
+            
+            self.env()
+                .transfer(relayer, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.env().emit_event(RelayerRewardsClaimed { relayer, amount });
+            
+            Ok(amount)
+        }
+        
+        /// Name the account that referred the caller; can only be done once
+        ///
+        /// Two accounts cannot refer each other.
+        #[ink(message)]
+        pub fn set_referrer(&mut self, referrer: AccountId) -> Result<(), Error> {
+            let account = self.env().caller();
+            if self.referrers.contains(account) {
+                return Err(Error::ReferrerAlreadySet);
+            }
+            if referrer == account || self.referrers.get(referrer) == Some(account) {
+                return Err(Error::SelfReferral);
+            }
+            
+            self.referrers.insert(account, &referrer);
+            let mut stats = self.get_referral_stats(referrer);
+            stats.referees = stats.referees.saturating_add(1);
+            self.referral_stats.insert(referrer, &stats);
+            
+            self.env().emit_event(ReferrerSet { account, referrer });
+            
+            Ok(())
+        }
+        
+        /// Withdraw the referral rewards the caller earned
+        #[ink(message)]
+        pub fn claim_referral_rewards(&mut self) -> Result<Balance, Error> {
+            let referrer = self.env().caller();
+            let amount = self.referral_rewards.take(referrer).unwrap_or(0);
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.liabilities = self.liabilities.saturating_sub(amount);
+            
+            self.env()
+                .transfer(referrer, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.env().emit_event(ReferralRewardsClaimed { referrer, amount });
+            
+            Ok(amount)
+        }
+        
+        /// Set the share of referees' delivery fees paid to their referrer (owner only)
+        #[ink(message)]
+        pub fn set_referral_share(&mut self, share_bps: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if u128::from(share_bps) > BASIS_POINTS {
+                return Err(Error::InvalidFee);
+            }
+            
+            self.referral_share_bps = share_bps;
+            
+            self.env().emit_event(ReferralShareSet { share_bps });
+            
+            Ok(())
+        }
+        
+        /// Set how many relayers of a chain must attest a payment (owner only)
+        ///
+        /// Keys of the same operator count once, expired keys not at all.
+        #[ink(message)]
+        pub fn set_relayer_threshold(&mut self, chain_id: u32, threshold: u32) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if threshold == 0 || threshold > self.active_operator_count(chain_id, None) {
+                return Err(Error::InvalidThreshold);
+            }
+            
+            self.relayer_thresholds.insert(chain_id, &threshold);
+            
+            self.env().emit_event(RelayerThresholdSet { chain_id, threshold });
+            
+            Ok(())
+        }
+        
+        /// Deposit balance for cross-chain transfers
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) {
+            let caller = self.env().caller();
+            let amount = self.env().transferred_value();
+            
+            if amount > 0 {
+                self.credit(caller, NATIVE_ASSET, amount);
+                
+                self.env().emit_event(BalanceDeposited {
+                    account: caller,
+                    amount,
+                });
+            }
+        }
+        
+        /// Credit a payment sent to this chain, as a relayer of its source chain
+        ///
+        /// The message hash must match `inbound_message_hash` of the other
+        /// arguments; each hash and each nonce of a chain is credited once.
+        /// The proof must match the source chain's verification mode: on
+        /// `Trusted` chains the payment is credited once the chain's relayer
+        /// threshold attested it, otherwise it takes the chain attester's
+        /// signature over `inbound_message` or a Merkle proof of the message
+        /// hash under a stored state root of the source chain.
+        #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn receive_cross_chain_payment(
+            &mut self,
+            source_chain: u32,
+            message_hash: Hash,
+            sender: AccountId,
+            recipient: AccountId,
+            asset_id: u32,
+            amount: Balance,
+            nonce: u64,
+            proof: ExecutionProof,
+        ) -> Result<(), Error> {
+            let relayer = self.env().caller();
+            
+            if !self.is_relayer(source_chain, relayer) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.ensure_chain_active(source_chain)?;
+            
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            if asset_id != NATIVE_ASSET && !self.assets.contains(asset_id) {
+                return Err(Error::UnsupportedAsset);
+            }
+            
+            if message_hash != self.inbound_message_hash(source_chain, sender, recipient, asset_id, amount, nonce) {
+                return Err(Error::InvalidMessageHash);
+            }
+            
+            if self.processed_messages.contains(message_hash)
+                || self.used_inbound_nonces.contains((source_chain, nonce))
+            {
+                return Err(Error::MessageAlreadyProcessed);
+            }
+            
+            match (self.get_verification_mode(source_chain), proof) {
+                (VerificationMode::Trusted, ExecutionProof::Attestation) => {
+                    if !self.attest_inbound(source_chain, message_hash, relayer)? {
+                        return Ok(());
+                    }
+                }
+                (VerificationMode::Signed, ExecutionProof::Signed { block_hash, signature }) => {
+                    let message = self.inbound_message(message_hash, block_hash);
+                    self.verify_attester_signature(source_chain, &message, signature)?;
+                }
+                (VerificationMode::StateProof, ExecutionProof::StateProof { block_hash, proof }) => {
+                    self.verify_merkle_proof(source_chain, block_hash, message_hash, proof)?;
+                }
+                _ => return Err(Error::WrongVerificationMode),
+            }
+            
+            self.processed_messages.insert(message_hash, &true);
+            self.used_inbound_nonces.insert((source_chain, nonce), &true);
+            
+            self.credit(recipient, asset_id, amount);
+            
+            self.env().emit_event(InboundPaymentReceived {
+                source_chain,
+                message_hash,
+                sender,
+                recipient,
+                asset_id,
+                amount,
+                nonce,
+                relayer,
+            });
+            
+            Ok(())
+        }
+        
+        /// Get how executions to a chain are verified
+        #[ink(message)]
+        pub fn get_verification_mode(&self, chain_id: u32) -> VerificationMode {
+            self.verification_modes.get(chain_id).unwrap_or_default()
+        }
+        
+        /// Get the key signing executions on a chain
+        #[ink(message)]
+        pub fn get_chain_attester(&self, chain_id: u32) -> Option<AttesterKey> {
+            self.chain_attesters.get(chain_id)
+        }
+        
+        /// Get a stored destination-chain state root
+        #[ink(message)]
+        pub fn get_state_root(&self, chain_id: u32, block_hash: Hash) -> Option<Hash> {
+            self.state_roots.get((chain_id, block_hash))
+        }
+        
+        /// Get the message an attester signs to confirm a payment executed
+        ///
+        /// It names this contract and the destination chain, so a signature
+        /// cannot be replayed on another deployment or chain.
+        #[ink(message)]
+        pub fn execution_message(&self, payment_id: u32, block_hash: Hash) -> Vec<u8> {
+            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            ink::scale::Encode::encode(&(
+                b"xcc/execute",
+                self.env().account_id(),
+                destination_chain,
+                payment_id,
+                block_hash,
+                amount,
+            ))
+        }
+        
+        /// Get the message an attester signs to confirm an inbound payment was sent
+        ///
+        /// It names this contract, so a signature cannot be replayed on another
+        /// deployment; the message hash already names the source chain.
+        #[ink(message)]
+        pub fn inbound_message(&self, message_hash: Hash, block_hash: Hash) -> Vec<u8> {
+            ink::scale::Encode::encode(&(b"xcc/inbound", self.env().account_id(), message_hash, block_hash))
+        }
+        
+        /// Get the Merkle leaf proving a payment executed on its destination chain
+        #[ink(message)]
+        pub fn execution_leaf(&self, payment_id: u32) -> Hash {
+            let recipient = self.payment_recipients.get(payment_id);
+            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(&(payment_id, recipient, amount), &mut output);
+            Hash::from(output)
+        }
+        
+        /// Compute the hash identifying an inbound payment message
+        #[ink(message)]
+        pub fn inbound_message_hash(
+            &self,
+            source_chain: u32,
+            sender: AccountId,
+            recipient: AccountId,
+            asset_id: u32,
+            amount: Balance,
+            nonce: u64,
+        ) -> Hash {
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(
+                &(source_chain, sender, recipient, asset_id, amount, nonce),
+                &mut output,
+            );
+            Hash::from(output)
+        }
+        
+        /// Check if an inbound message was already credited
+        #[ink(message)]
+        pub fn is_message_processed(&self, message_hash: Hash) -> bool {
+            self.processed_messages.contains(message_hash)
+        }
+        
+        /// Check if an inbound nonce of a chain was already used
+        #[ink(message)]
+        pub fn is_inbound_nonce_used(&self, source_chain: u32, nonce: u64) -> bool {
+            self.used_inbound_nonces.contains((source_chain, nonce))
+        }
+        
+        /// Withdraw unspent deposited balance back to the caller
+        ///
+        /// Amounts of pending payments were already deducted from the sender's
+        /// balance; the contract additionally refuses withdrawals that would
+        /// leave it unable to cover the funds it holds for others.
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            
+            let balance = self.balances.get(caller).unwrap_or(0);
+            if balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            
+            // The withdrawn amount leaves the held funds along with the balance
+            let held = self.held_funds().saturating_sub(amount);
+            if self.env().balance().saturating_sub(amount) < held {
+                return Err(Error::InsufficientBalance);
+            }
+            
+            self.debit(caller, NATIVE_ASSET, amount);
+            self.env()
+                .transfer(caller, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            self.env().emit_event(BalanceWithdrawn {
+                account: caller,
+                amount,
+            });
+            
+            Ok(())
+        }
+        
+        /// Return native funds the contract holds without an owner mapping (owner only)
+        ///
+        /// For assets stranded by partially failed XCM executions. Funds held
+        /// for pending payments, the fee pot, balances and unclaimed rewards
+        /// cannot be recovered, and every recovery must name the hash of an
+        /// off-chain incident report.
+        #[ink(message)]
+        pub fn recover_trapped_assets(
+            &mut self,
+            account: AccountId,
+            amount: Balance,
+            reason_hash: Hash,
+        ) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if reason_hash == Hash::default() {
+                return Err(Error::InvalidReason);
+            }
+            
+            if self.env().balance().saturating_sub(amount) < self.held_funds() {
+                return Err(Error::InsufficientBalance);
+            }
+            
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            
+            let recovery_id = self.recovery_counter;
+            self.recovery_counter = self.recovery_counter.saturating_add(1);
+            self.total_recovered = self.total_recovered.saturating_add(amount);
+            
+            self.env().emit_event(TrappedAssetsRecovered {
+                recovery_id,
+                account,
+                amount,
+                reason_hash,
+                timestamp: self.env().block_timestamp(),
+            });
+            
+            Ok(())
+        }
+        
+        /// Clear a settled payment's storage, keeping only a hash of its details
+        ///
+        /// Callable by the sender or the owner once `PRUNE_GRACE_PERIOD` has
+        /// passed since the payment settled. The freed storage deposit goes to
+        /// the caller; the archive hash is `blake2_256` of the encoded
+        /// `XcmPaymentInfo` the payment had.
+        #[ink(message)]
+        pub fn prune_payment(&mut self, payment_id: u32) -> Result<Hash, Error> {
+            let info = self.get_payment(payment_id).ok_or(Error::PaymentNotFound)?;
+            let caller = self.env().caller();
+            if caller != info.sender && caller != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            let settled_at = self.payment_settled_at.get(payment_id).ok_or(Error::PaymentNotSettled)?;
+            if self.env().block_timestamp() < settled_at.saturating_add(PRUNE_GRACE_PERIOD) {
+                return Err(Error::GracePeriodActive);
+            }
+            
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(&info, &mut output);
+            let archive_hash = Hash::from(output);
+            
+            for relayer in self.get_relayers(info.destination_chain) {
+                let operator = self.operator_of(info.destination_chain, relayer);
+                self.payment_attestations.remove((payment_id, operator));
+            }
+            if let Some(query_id) = self.payment_queries.take(payment_id) {
+                self.query_payments.remove(query_id);
+            }
+            self.payment_senders.remove(payment_id);
+            self.payment_recipients.remove(payment_id);
+            self.payment_amounts.remove(payment_id);
+            self.payment_source_chains.remove(payment_id);
+            self.payment_destination_chains.remove(payment_id);
+            self.payment_types.remove(payment_id);
+            self.payment_statuses.remove(payment_id);
+            self.payment_timestamps.remove(payment_id);
+            self.attestation_counts.remove(payment_id);
+            self.payment_relayer_fees.remove(payment_id);
+            self.payment_delivery_fees.remove(payment_id);
+            self.payment_destinations.remove(payment_id);
+            self.payment_beneficiaries.remove(payment_id);
+            self.payment_assets.remove(payment_id);
+            self.ethereum_transfers.remove(payment_id);
+            self.payment_fee_assets.remove(payment_id);
+            self.payment_batches.remove(payment_id);
+            self.payment_claims.remove(payment_id);
+            self.payment_failure_reasons.remove(payment_id);
+            self.payment_refunds.remove(payment_id);
+            self.refund_origins.remove(payment_id);
+            self.payment_settled_at.remove(payment_id);
+            self.payment_archives.insert(payment_id, &archive_hash);
+            
+            self.env().emit_event(PaymentPruned {
+                payment_id,
+                archive_hash,
+            });
+            
+            Ok(archive_hash)
+        }
+        
+        /// Get the payment counters of a destination chain
+        #[ink(message)]
+        pub fn get_chain_stats(&self, chain_id: u32) -> ChainStats {
+            self.chain_stats.get(chain_id).unwrap_or_default()
+        }
+        
+        /// Get the archive hash of a pruned payment
+        #[ink(message)]
+        pub fn get_payment_archive(&self, payment_id: u32) -> Option<Hash> {
+            self.payment_archives.get(payment_id)
+        }
+        
+        /// Get the native funds returned through trapped asset recovery
+        #[ink(message)]
+        pub fn get_total_recovered(&self) -> Balance {
+            self.total_recovered
+        }
+        
+        /// Get the funds the contract holds for pending payments
+        #[ink(message)]
+        pub fn get_pending_collateral(&self) -> Balance {
+            self.pending_collateral
+        }
+        
+        /// Get payment details
+        #[ink(message)]
+        pub fn get_payment_info(&self, payment_id: u32) -> Option<PaymentDetails> {
+            if !self.payment_senders.contains(payment_id) {
+                return None;
+            }
+            
+            let sender = self.payment_senders.get(payment_id)?;
+            let recipient = self.payment_recipients.get(payment_id)?;
+            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
+            let source_chain = self.payment_source_chains.get(payment_id).unwrap_or(0);
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            let executed = self.payment_statuses.get(payment_id) == Some(PaymentStatus::Executed);
+            
+            Some((sender, recipient, amount, source_chain, destination_chain, executed))
+        }
+        
+        /// Get a payment as a typed record
+        #[ink(message)]
+        pub fn get_payment(&self, payment_id: u32) -> Option<XcmPaymentInfo> {
+            Some(XcmPaymentInfo {
+                payment_id,
+                sender: self.payment_senders.get(payment_id)?,
+                recipient: self.payment_recipients.get(payment_id)?,
+                beneficiary: self.get_payment_beneficiary(payment_id)?,
+                asset_id: self.get_payment_asset(payment_id),
+                amount: self.payment_amounts.get(payment_id).unwrap_or(0),
+                relayer_fee: self.payment_relayer_fees.get(payment_id).unwrap_or(0),
+                delivery_fee: self.payment_delivery_fees.get(payment_id).unwrap_or(0),
+                destination: self.payment_destinations.get(payment_id),
+                source_chain: self.payment_source_chains.get(payment_id).unwrap_or(0),
+                destination_chain: self.payment_destination_chains.get(payment_id).unwrap_or(0),
+                message_type: self.get_payment_type(payment_id),
+                status: self.payment_statuses.get(payment_id)?,
+                created_at: self.payment_timestamps.get(payment_id).unwrap_or(0),
+            })
+        }
+        
+        /// Get up to `limit` payments matching `filter`, starting at payment id `offset`
+        ///
+        /// At most `MAX_SCAN` payment ids are examined per call; continue from
+        /// `next_offset` until it is `None`.
+        #[ink(message)]
+        pub fn get_payments(&self, filter: PaymentFilter, offset: u32, limit: u32) -> PaymentPage {
+            let limit = limit.min(MAX_PAGE_SIZE) as usize;
+            let end = offset.saturating_add(MAX_SCAN).min(self.payment_counter);
+            let mut page = PaymentPage::default();
+            
+            let mut payment_id = offset;
+            while payment_id < end && page.payments.len() < limit {
+                if let Some(payment) = self.get_payment(payment_id) {
+                    if filter.matches(&payment) {
+                        page.payments.push(payment);
+                    }
+                }
+                payment_id = payment_id.saturating_add(1);
+            }
+            
+            if payment_id < self.payment_counter {
+                page.next_offset = Some(payment_id);
+            }
+            
+            page
+        }
+        
+        /// Export a page of payments by id, a SCALE-encoded `Vec<XcmPaymentInfo>`
+        ///
+        /// Pruned payments are skipped; their archive hashes remain queryable.
+        #[ink(message)]
+        pub fn export_payments(&self, offset: u32, limit: u32) -> StateChunk {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.payment_counter);
+            let payments: Vec<XcmPaymentInfo> = (offset..end)
+                .filter_map(|payment_id| self.get_payment(payment_id))
+                .collect();
+            let next_offset = (end < self.payment_counter).then_some(end);
+            StateChunk::new(&payments, next_offset)
+        }
+        
+        /// Export a page of balances, a SCALE-encoded `Vec<(AccountId, u32, Balance)>` of account, asset and balance
+        ///
+        /// Covers every balance ever credited, in the order first credited,
+        /// including those since withdrawn to zero.
+        #[ink(message)]
+        pub fn export_balances(&self, offset: u32, limit: u32) -> StateChunk {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.balance_holder_count);
+            let balances: Vec<(AccountId, u32, Balance)> = (offset..end)
+                .filter_map(|index| self.balance_holders.get(index))
+                .map(|(account, asset_id)| (account, asset_id, self.get_asset_balance(account, asset_id)))
+                .collect();
+            let next_offset = (end < self.balance_holder_count).then_some(end);
+            StateChunk::new(&balances, next_offset)
+        }
+        
+        /// Get the message type of a payment
+        #[ink(message)]
+        pub fn get_payment_type(&self, payment_id: u32) -> Option<XcmMessageType> {
+            XcmMessageType::from_code(self.payment_types.get(payment_id)?)
+        }
+        
+        /// Get the payment ids of a batch
+        #[ink(message)]
+        pub fn get_batch(&self, batch_id: u32) -> Option<Vec<u32>> {
+            self.batches.get(batch_id)
+        }
+        
+        /// Get the batch a payment was created in
+        #[ink(message)]
+        pub fn get_payment_batch(&self, payment_id: u32) -> Option<u32> {
+            self.payment_batches.get(payment_id)
+        }
+        
+        /// Get the full destination of a payment, ending in its beneficiary
+        #[ink(message)]
+        pub fn get_payment_destination(&self, payment_id: u32) -> Option<VersionedDestination> {
+            self.payment_destinations.get(payment_id)
+        }
+        
+        /// Get the account a payment is delivered to on its destination chain
+        #[ink(message)]
+        pub fn get_payment_beneficiary(&self, payment_id: u32) -> Option<Beneficiary> {
+            if let Some(beneficiary) = self.payment_beneficiaries.get(payment_id) {
+                return Some(beneficiary);
+            }
+            self.payment_recipients.get(payment_id).map(Beneficiary::AccountId32)
+        }
+        
+        /// Get the refund payment returning a payment, if any
+        #[ink(message)]
+        pub fn get_payment_refund(&self, payment_id: u32) -> Option<u32> {
+            self.payment_refunds.get(payment_id)
+        }
+        
+        /// Get the payment a refund payment returns
+        #[ink(message)]
+        pub fn get_refund_origin(&self, payment_id: u32) -> Option<u32> {
+            self.refund_origins.get(payment_id)
+        }
+        
+        /// Get the account relaying XCM query responses
+        #[ink(message)]
+        pub fn get_query_responder(&self) -> Option<AccountId> {
+            self.query_responder
+        }
+        
+        /// Get the outstanding XCM query of a dispatched payment
+        #[ink(message)]
+        pub fn get_payment_query(&self, payment_id: u32) -> Option<u64> {
+            self.payment_queries.get(payment_id)
+        }
+        
+        /// Get the relayer claim on a payment awaiting confirmation
+        #[ink(message)]
+        pub fn get_payment_claim(&self, payment_id: u32) -> Option<PaymentClaim> {
+            self.payment_claims.get(payment_id)
+        }
+        
+        /// Get why a payment failed, if its relayer reported a reason
+        #[ink(message)]
+        pub fn get_failure_reason(&self, payment_id: u32) -> Option<FailureReason> {
+            self.payment_failure_reasons.get(payment_id)
+        }
+        
+        /// Get the Snowbridge message of a payment to Ethereum
+        #[ink(message)]
+        pub fn get_ethereum_transfer(&self, payment_id: u32) -> Option<EthereumTransfer> {
+            self.ethereum_transfers.get(payment_id)
+        }
+        
+        /// Get the ABI-encoded gateway payload of a payment to Ethereum, as relayers submit it
+        #[ink(message)]
+        pub fn get_ethereum_payload(&self, payment_id: u32) -> Option<Vec<u8>> {
+            self.ethereum_transfers
+                .get(payment_id)
+                .map(|transfer| transfer.outbound_payload())
+        }
+        
+        /// Get the asset of a payment
+        #[ink(message)]
+        pub fn get_payment_asset(&self, payment_id: u32) -> u32 {
+            self.payment_assets.get(payment_id).unwrap_or(NATIVE_ASSET)
+        }
+        
+        /// Get the asset a payment's delivery fee was paid in
+        #[ink(message)]
+        pub fn get_payment_fee_asset(&self, payment_id: u32) -> u32 {
+            self.payment_fee_assets.get(payment_id).unwrap_or(NATIVE_ASSET)
+        }
+        
+        /// Get the lifecycle status of a payment
+        #[ink(message)]
+        pub fn get_payment_status(&self, payment_id: u32) -> Option<PaymentStatus> {
+            self.payment_statuses.get(payment_id)
+        }
+        
+        /// Check if a payment was cancelled by its sender
+        #[ink(message)]
+        pub fn is_payment_cancelled(&self, payment_id: u32) -> bool {
+            self.get_payment_status(payment_id) == Some(PaymentStatus::Cancelled)
+        }
+        
+        /// Check if a payment timed out, whether refunded or failed in transit
+        #[ink(message)]
+        pub fn is_payment_failed(&self, payment_id: u32) -> bool {
+            matches!(
+                self.get_payment_status(payment_id),
+                Some(PaymentStatus::Failed | PaymentStatus::Refunded)
+            )
+        }
+        
+        /// Get how long payments to a chain may stay pending
+        #[ink(message)]
+        pub fn get_chain_timeout(&self, chain_id: u32) -> u64 {
+            self.chain_timeouts.get(chain_id).unwrap_or(DEFAULT_PAYMENT_TIMEOUT)
+        }
+        
+        /// Get the relayer fee of a chain
+        #[ink(message)]
+        pub fn get_relayer_fee(&self, chain_id: u32) -> RelayerFee {
+            self.stored_chain_config(chain_id).fee
+        }
+        
+        /// Get how dispatched messages reach pallet-xcm
+        #[ink(message)]
+        pub fn get_dispatch_path(&self) -> XcmDispatchPath {
+            self.dispatch_path
+        }
+        
+        /// Weigh a SCALE-encoded `VersionedXcm` through the chain extension
+        ///
+        /// Helps pick the weight limits of `set_chain_xcm_settings`.
+        #[ink(message)]
+        pub fn estimate_xcm_weight(&self, message: Vec<u8>) -> Result<XcmWeight, Error> {
+            if self.dispatch_path != XcmDispatchPath::ChainExtension {
+                return Err(Error::ChainExtensionDisabled);
+            }
+            
+            self.env()
+                .extension()
+                .query_xcm_weight(&message)
+                .map_err(|_| Error::WeightUnavailable)
+        }
+        
+        /// Get the metadata and constraints of a supported chain
+        #[ink(message)]
+        pub fn get_chain_config(&self, chain_id: u32) -> Option<ChainConfig> {
+            if !self.is_chain_supported(chain_id) {
+                return None;
+            }
+            Some(self.stored_chain_config(chain_id))
+        }
+        
+        /// Get the relayer fee charged on top of a payment of `amount` to a chain
+        #[ink(message)]
+        pub fn quote_relayer_fee(&self, chain_id: u32, amount: Balance) -> Balance {
+            let fee = self.get_relayer_fee(chain_id);
+            fee.flat
+                .saturating_add(amount.saturating_mul(u128::from(fee.bps)) / BASIS_POINTS)
+        }
+        
+        /// Get all fees charged on top of a native payment of `amount` to a chain
+        ///
+        /// This is the relayer fee plus the delivery fee.
+        #[ink(message)]
+        pub fn quote_cross_chain_fee(&self, destination_chain: u32, amount: Balance) -> Balance {
+            let delivery_fee = self.stored_chain_config(destination_chain).delivery_fee.quote(amount);
+            self.quote_relayer_fee(destination_chain, amount).saturating_add(delivery_fee)
+        }
+        
+        /// Get the rate limit applying to each sender
+        #[ink(message)]
+        pub fn get_account_rate_limit(&self) -> Option<RateLimit> {
+            self.account_rate_limit
+        }
+        
+        /// Get the rate limit applying to all payments to a chain
+        #[ink(message)]
+        pub fn get_chain_rate_limit(&self, chain_id: u32) -> Option<RateLimit> {
+            self.chain_rate_limits.get(chain_id)
+        }
+        
+        /// Get a sender's usage of the account rate limit
+        #[ink(message)]
+        pub fn get_account_rate_usage(&self, account: AccountId) -> RateUsage {
+            self.account_rate_usage.get(account).unwrap_or_default()
+        }
+        
+        /// Get a chain's usage of its rate limit
+        #[ink(message)]
+        pub fn get_chain_rate_usage(&self, chain_id: u32) -> RateUsage {
+            self.chain_rate_usage.get(chain_id).unwrap_or_default()
+        }
+        
+        /// Get the rate limit of a non-native asset, if any
+        #[ink(message)]
+        pub fn get_asset_rate_limit(&self, asset_id: u32) -> Option<RateLimit> {
+            self.asset_rate_limits.get(asset_id)
+        }
+        
+        /// Get a sender's usage of an asset's rate limit
+        #[ink(message)]
+        pub fn get_asset_account_rate_usage(&self, account: AccountId, asset_id: u32) -> RateUsage {
+            self.asset_account_rate_usage.get((account, asset_id)).unwrap_or_default()
+        }
+        
+        /// Get a chain's usage of an asset's rate limit
+        #[ink(message)]
+        pub fn get_asset_chain_rate_usage(&self, chain_id: u32, asset_id: u32) -> RateUsage {
+            self.asset_chain_rate_usage.get((chain_id, asset_id)).unwrap_or_default()
+        }
+        
+        /// Get the delivery fees collected and not yet paid out
+        #[ink(message)]
+        pub fn get_fee_pot(&self) -> Balance {
+            self.fee_pot
+        }
+        
+        /// Get the delivery fees collected in a non-native asset and not yet paid out
+        #[ink(message)]
+        pub fn get_asset_fee_pot(&self, asset_id: u32) -> Balance {
+            self.asset_fee_pots.get(asset_id).unwrap_or(0)
+        }
+        
+        /// Get the delivery fees in an asset held for payments that have not settled yet
+        #[ink(message)]
+        pub fn get_pending_delivery_fees(&self, asset_id: u32) -> Balance {
+            self.pending_delivery_fees.get(asset_id).unwrap_or(0)
+        }
+        
+        /// Get the unclaimed fees of a relayer
+        #[ink(message)]
+        pub fn get_relayer_rewards(&self, relayer: AccountId) -> Balance {
+            self.relayer_rewards.get(relayer).unwrap_or(0)
+        }
+        
+        /// Get the account that referred a user
+        #[ink(message)]
+        pub fn get_referrer(&self, account: AccountId) -> Option<AccountId> {
+            self.referrers.get(account)
+        }
+        
+        /// Get the unclaimed referral rewards of a referrer
+        #[ink(message)]
+        pub fn get_referral_rewards(&self, referrer: AccountId) -> Balance {
+            self.referral_rewards.get(referrer).unwrap_or(0)
+        }
+        
+        /// Get what a referrer brought in through its referees
+        #[ink(message)]
+        pub fn get_referral_stats(&self, referrer: AccountId) -> ReferralStats {
+            self.referral_stats.get(referrer).unwrap_or_default()
+        }
+        
+        /// Get the share of referees' delivery fees paid to their referrer, in basis points
+        #[ink(message)]
+        pub fn get_referral_share(&self) -> u32 {
+            self.referral_share_bps
+        }
+        
+        /// Get the relayers of a chain
+        #[ink(message)]
+        pub fn get_relayers(&self, chain_id: u32) -> Vec<AccountId> {
+            self.relayers.get(chain_id).unwrap_or_default()
+        }
+        
+        /// Check if an account relays payments to a chain with an unexpired key
+        #[ink(message)]
+        pub fn is_relayer(&self, chain_id: u32, account: AccountId) -> bool {
+            !self.is_key_expired(chain_id, account) && self.relayers.get(chain_id).unwrap_or_default().contains(&account)
+        }
+        
+        /// Get when a relayer key of a chain stops being accepted, if ever
+        #[ink(message)]
+        pub fn get_relayer_expiry(&self, chain_id: u32, relayer: AccountId) -> Option<u64> {
+            self.relayer_expiries.get((chain_id, relayer))
+        }
+        
+        /// Get the account allowed to pause chains besides the owner
+        #[ink(message)]
+        pub fn get_pauser(&self) -> Option<AccountId> {
+            self.pauser
+        }
+        
+        /// Get the account allowed to manage relayer keys besides the owner
+        #[ink(message)]
+        pub fn get_relayer_manager(&self) -> Option<AccountId> {
+            self.relayer_manager
+        }
+        
+        /// Get how many relayers of a chain must attest a payment
+        #[ink(message)]
+        pub fn get_relayer_threshold(&self, chain_id: u32) -> u32 {
+            self.relayer_thresholds.get(chain_id).unwrap_or(1)
+        }
+        
+        /// Get the number of relayers that attested a payment
+        #[ink(message)]
+        pub fn get_attestation_count(&self, payment_id: u32) -> u32 {
+            self.attestation_counts.get(payment_id).unwrap_or(0)
+        }
+        
+        /// Get the parachain id of the chain this contract is deployed on
+        #[ink(message)]
+        pub fn get_own_chain_id(&self) -> u32 {
+            self.own_chain_id
+        }
+        
+        /// Get the configured BillSplitting contract
+        #[ink(message)]
+        pub fn get_bill_splitting(&self) -> Option<AccountId> {
+            self.bill_splitting
+        }
+        
+        /// Get the configured registry
+        #[ink(message)]
+        pub fn get_registry(&self) -> Option<AccountId> {
+            self.registry
+        }
+        
+        /// Get the treasury the fee pot is swept to
+        #[ink(message)]
+        pub fn get_treasury(&self) -> Option<AccountId> {
+            self.treasury
+        }
+        
+        /// Get the keeper registry paid bounties for refunding timed-out payments
+        #[ink(message)]
+        pub fn get_keeper_registry(&self) -> Option<AccountId> {
+            self.keeper_registry
+        }
+        
+        /// Get what is left of a job's bond
+        #[ink(message)]
+        pub fn get_keeper_bond(&self, job_id: u32) -> Option<Balance> {
+            self.keeper_bonds.get(job_id).map(|bond| bond.amount)
+        }
+        
+        /// Get what a funder would reclaim from a job's bond
+        #[ink(message)]
+        pub fn get_keeper_stake(&self, job_id: u32, funder: AccountId) -> Balance {
+            let stake = self.keeper_stakes.get((job_id, funder)).unwrap_or_default();
+            self.keeper_bonds.get(job_id).map_or(0, |bond| bond.value_of(stake))
+        }
+        
+        /// Get the layout version of the stored data
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.version
+        }
+        
+        /// Get the contract owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+    
+        /// Hand the contract over to a new owner, such as a governance contract (owner only)
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            self.owner = new_owner;
+            
+            self.env().emit_event(OwnershipTransferred { new_owner });
+            
+            Ok(())
+        }
+        
+        /// Get user balance
+        #[ink(message)]
+        pub fn get_balance(&self, account: AccountId) -> Balance {
+            self.balances.get(account).unwrap_or(0)
+        }
+        
+        /// Get user balance of an asset
+        #[ink(message)]
+        pub fn get_asset_balance(&self, account: AccountId, asset_id: u32) -> Balance {
+            if asset_id == NATIVE_ASSET {
+                return self.get_balance(account);
+            }
+            self.asset_balances.get((account, asset_id)).unwrap_or(0)
+        }
+        
+        /// Get the location of a registered asset on this chain
+        #[ink(message)]
+        pub fn get_asset(&self, asset_id: u32) -> Option<AssetLocation> {
+            self.assets.get(asset_id)
+        }
+        
+        /// Get the representation of a registered asset on a chain
+        #[ink(message)]
+        pub fn get_asset_representation(&self, asset_id: u32, chain_id: u32) -> Option<AssetLocation> {
+            self.asset_representations.get((asset_id, chain_id))
+        }
+        
+        /// Get the registry entry of an asset
+        #[ink(message)]
+        pub fn get_asset_metadata(&self, asset_id: u32) -> Option<AssetMetadata> {
+            self.asset_metadata.get(asset_id)
+        }
+        
+        /// Get the ERC-20 contract of an asset on an Ethereum chain
+        #[ink(message)]
+        pub fn get_erc20_token(&self, asset_id: u32, chain_id: u32) -> Option<H160> {
+            self.erc20_tokens.get((asset_id, chain_id))
+        }
+        
+        /// Get the gas the gateway may spend on transfers to an Ethereum chain
+        #[ink(message)]
+        pub fn get_dispatch_gas(&self, chain_id: u32) -> u64 {
+            self.dispatch_gas_limits.get(chain_id).unwrap_or(DEFAULT_DISPATCH_GAS)
+        }
+        
+        /// Check if chain is supported
+        #[ink(message)]
+        pub fn is_chain_supported(&self, chain_id: u32) -> bool {
+            self.supported_chains.get(chain_id).unwrap_or(false)
+        }
+        
+        /// Get pending payments count for a user
+        #[ink(message)]
+        pub fn get_pending_payments_count(&self, user: AccountId) -> u32 {
+            self.user_pending_counts.get(user).unwrap_or(0)
+        }
+        
+        /// Get a page of a user's pending payments, as sender or recipient
+        #[ink(message)]
+        pub fn get_pending_payments(&self, user: AccountId, offset: u32, limit: u32) -> Vec<(u32, PaymentDetails)> {
+            let count = self.get_pending_payments_count(user);
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+            
+            (offset..end)
+                .filter_map(|index| {
+                    let payment_id = self.user_pending_payments.get((user, index))?;
+                    Some((payment_id, self.get_payment_info(payment_id)?))
+                })
+                .collect()
+        }
+        
+        /// Mark a payment executed, credit its recipient and reward the executor
+        fn finalize_execution(&mut self, payment_id: u32, executor: AccountId) -> Result<(), Error> {
+            // Paused corridors execute nothing until they resume
+            self.ensure_chain_active(self.payment_destination_chains.get(payment_id).unwrap_or(0))?;
+
+            // Mark as executed
+            self.transition(payment_id, PaymentStatus::Executed)?;
+            self.payment_claims.remove(payment_id);
+            
+            // Add balance to recipient (on destination chain); dispatched
+            // transfers were already delivered by XCM
+            let recipient = self.payment_recipients.get(payment_id).unwrap();
+            let asset_id = self.get_payment_asset(payment_id);
+            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
+            if !cfg!(feature = "xcm-dispatch") {
+                self.credit(recipient, asset_id, amount);
+            }
+            
+            let sender = self.payment_senders.get(payment_id).unwrap();
+            
+            // The executing relayer earns the fee
+            let relayer_fee = self.payment_relayer_fees.get(payment_id).unwrap_or(0);
+            if relayer_fee > 0 {
+                let rewards = self.relayer_rewards.get(executor).unwrap_or(0);
+                self.relayer_rewards.insert(executor, &rewards.saturating_add(relayer_fee));
+                self.liabilities = self.liabilities.saturating_add(relayer_fee);
+            }
+            self.release_collateral(self.held_collateral(payment_id));
+            
+            // The delivery fee is earned once the payment is delivered
+            let fee_asset = self.get_payment_fee_asset(payment_id);
+            let delivery_fee = self.release_delivery_fee(payment_id);
+            self.add_to_fee_pot(fee_asset, delivery_fee);
+            self.accrue_referral_reward(payment_id, sender);
+            
+            self.env().emit_event(CrossChainPaymentExecuted {
+                payment_id,
+                sender,
+                recipient,
+                beneficiary: self.get_payment_beneficiary(payment_id).unwrap_or(Beneficiary::AccountId32(recipient)),
+                asset_id,
+                amount,
+                message_type: self.get_payment_type(payment_id).unwrap_or(XcmMessageType::Payment),
+                source_chain: self.payment_source_chains.get(payment_id).unwrap_or(0),
+                destination_chain: self.payment_destination_chains.get(payment_id).unwrap_or(0),
+                executor,
+                relayer_fee,
+                executed_at: self.env().block_timestamp(),
+                status: PaymentStatus::Executed,
+            });
+            
+            // Route the message to its handler: BillSplitting marks the share
+            // as paid, and a refund completes the original payment's round trip
+            match self.get_payment_type(payment_id) {
+                Some(XcmMessageType::BillSplitting) => self.notify_bill_splitting(payment_id),
+                Some(XcmMessageType::Refund) => {
+                    if let Some(original_payment_id) = self.refund_origins.get(payment_id) {
+                        self.env().emit_event(PaymentReturned {
+                            original_payment_id,
+                            refund_payment_id: payment_id,
+                            sender: recipient,
+                            amount,
+                        });
+                    }
+                }
+                _ => {}
+            }
+            
+            Ok(())
+        }
+        
+        /// Add a relayer to a chain's relayers unless it is already there
+        fn insert_relayer(&mut self, chain_id: u32, relayer: AccountId) -> Result<(), Error> {
+            // Re-adding an expiring key makes it permanent again
+            self.relayer_expiries.remove((chain_id, relayer));
+            
+            let mut relayers = self.relayers.get(chain_id).unwrap_or_default();
+            if relayers.contains(&relayer) {
+                return Ok(());
+            }
+            
+            // Expired keys make room for new ones
+            if relayers.len() >= MAX_RELAYERS {
+                let (expired, active) = relayers
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|&key| self.is_key_expired(chain_id, key));
+                for key in expired {
+                    self.relayer_expiries.remove((chain_id, key));
+                    self.relayer_operators.remove((chain_id, key));
+                    self.env().emit_event(RelayerUpdated {
+                        chain_id,
+                        relayer: key,
+                        active: false,
+                    });
+                }
+                relayers = active;
+            }
+            if relayers.len() >= MAX_RELAYERS {
+                return Err(Error::TooManyRelayers);
+            }
+            
+            relayers.push(relayer);
+            self.relayers.insert(chain_id, &relayers);
+            
+            self.env().emit_event(RelayerUpdated {
+                chain_id,
+                relayer,
+                active: true,
+            });
+            
+            Ok(())
+        }
+        
+        /// Check if a relayer key of a chain has passed its expiry
+        fn is_key_expired(&self, chain_id: u32, relayer: AccountId) -> bool {
+            self.relayer_expiries
+                .get((chain_id, relayer))
+                .is_some_and(|expires_at| self.env().block_timestamp() >= expires_at)
+        }
+        
+        /// The operator a relayer key of a chain attests for: the first key it was rotated from
+        fn operator_of(&self, chain_id: u32, relayer: AccountId) -> AccountId {
+            self.relayer_operators.get((chain_id, relayer)).unwrap_or(relayer)
+        }
+        
+        /// Number of operators holding an unexpired key of a chain, leaving out the key `excluded`
+        fn active_operator_count(&self, chain_id: u32, excluded: Option<AccountId>) -> u32 {
+            let mut operators: Vec<AccountId> = Vec::new();
+            for relayer in self.get_relayers(chain_id) {
+                if Some(relayer) == excluded || self.is_key_expired(chain_id, relayer) {
+                    continue;
+                }
+                let operator = self.operator_of(chain_id, relayer);
+                if !operators.contains(&operator) {
+                    operators.push(operator);
+                }
+            }
+            operators.len() as u32
+        }
+        
+        /// Ensure a payment awaits execution, returning its status
+        fn ensure_pending(&self, payment_id: u32) -> Result<PaymentStatus, Error> {
+            let status = self.payment_statuses.get(payment_id).ok_or(Error::PaymentNotFound)?;
+            match status {
+                PaymentStatus::Pending | PaymentStatus::InTransit => Ok(status),
+                _ => Err(Self::status_error(status)),
+            }
+        }
+        
+        /// Ensure a pending payment's funds are still held here
+        fn ensure_refundable(&self, payment_id: u32) -> Result<(), Error> {
+            match self.ensure_pending(payment_id)? {
+                _ if self.payment_claims.contains(payment_id) => Err(Error::PaymentClaimed),
+                // Dispatched transfers left the chain on creation
+                PaymentStatus::InTransit => Err(Error::AlreadyDispatched),
+                _ => Ok(()),
+            }
+        }
+        
+        /// Ensure `relayer` holds the claim on a payment awaiting confirmation, returning its chain
+        fn ensure_claimant(&self, payment_id: u32, relayer: AccountId) -> Result<u32, Error> {
+            let claim = self.payment_claims.get(payment_id).ok_or(Error::NotClaimed)?;
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            // Claims of keys that expired since are left to time out
+            if claim.relayer != relayer || !self.is_relayer(destination_chain, relayer) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            Ok(destination_chain)
+        }
+        
+        /// Ensure a chain is not paused
+        fn ensure_chain_active(&self, chain_id: u32) -> Result<(), Error> {
+            if self.stored_chain_config(chain_id).paused {
+                return Err(Error::ChainPaused);
+            }
+            
+            Ok(())
+        }
+        
+        /// Store a chain's pause flag
+        fn set_chain_paused(&mut self, chain_id: u32, paused: bool, by: AccountId) -> Result<(), Error> {
+            let mut config = self.get_chain_config(chain_id).ok_or(Error::InvalidChain)?;
+            config.paused = paused;
+            self.chain_configs.insert(chain_id, &config);
+            
+            self.env().emit_event(ChainPauseUpdated { chain_id, paused, by });
+            
+            Ok(())
+        }
+        
+        /// Ensure the caller is the owner or the relayer manager
+        fn ensure_relayer_manager(&self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.owner && self.relayer_manager != Some(caller) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            Ok(())
+        }
+        
+        /// Count a relayer's attestation, executing the payment once the chain's threshold is met
+        fn record_attestation(&mut self, payment_id: u32, relayer: AccountId, destination_chain: u32) -> Result<(), Error> {
+            self.ensure_chain_active(destination_chain)?;
+            let operator = self.operator_of(destination_chain, relayer);
+            if self.payment_attestations.contains((payment_id, operator)) {
+                return Err(Error::AlreadyAttested);
+            }
+            
+            self.payment_attestations.insert((payment_id, operator), &true);
+            let attestations = self.attestation_counts.get(payment_id).unwrap_or(0).saturating_add(1);
+            self.attestation_counts.insert(payment_id, &attestations);
+            let threshold = self.get_relayer_threshold(destination_chain);
+            
+            self.env().emit_event(ExecutionAttested {
+                payment_id,
+                relayer,
+                attestations,
+                threshold,
+            });
+            
+            if attestations >= threshold {
+                self.finalize_execution(payment_id, relayer)?;
+            }
+            
+            Ok(())
+        }
+        
+        /// Count a relayer's attestation of an inbound message, returning whether the chain's threshold is met
+        fn attest_inbound(&mut self, source_chain: u32, message_hash: Hash, relayer: AccountId) -> Result<bool, Error> {
+            let operator = self.operator_of(source_chain, relayer);
+            if self.inbound_attestations.contains((message_hash, operator)) {
+                return Err(Error::AlreadyAttested);
+            }
+            
+            self.inbound_attestations.insert((message_hash, operator), &true);
+            let attestations = self
+                .inbound_attestation_counts
+                .get(message_hash)
+                .unwrap_or(0)
+                .saturating_add(1);
+            self.inbound_attestation_counts.insert(message_hash, &attestations);
+            let threshold = self.get_relayer_threshold(source_chain);
+            
+            self.env().emit_event(InboundPaymentAttested {
+                message_hash,
+                relayer,
+                attestations,
+                threshold,
+            });
+            
+            Ok(attestations >= threshold)
+        }
+        
+        /// Verify the chain attester signed a payment's execution at `block_hash`
+        ///
+        /// The signature covers `execution_message(payment_id, block_hash)`.
+        fn verify_signature(
+            &self,
+            payment_id: u32,
+            destination_chain: u32,
+            block_hash: Hash,
+            signature: Vec<u8>,
+        ) -> Result<(), Error> {
+            let message = self.execution_message(payment_id, block_hash);
+            self.verify_attester_signature(destination_chain, &message, signature)?;
+            
+            self.env().emit_event(ExecutionVerified {
+                payment_id,
+                mode: VerificationMode::Signed,
+                block_hash,
+            });
+            
+            Ok(())
+        }
+        
+        /// Verify a chain's attester signed `message`
+        ///
+        /// ECDSA signatures are over its Blake2-256 hash.
+        fn verify_attester_signature(&self, chain_id: u32, message: &[u8], signature: Vec<u8>) -> Result<(), Error> {
+            let attester = self.chain_attesters.get(chain_id).ok_or(Error::InvalidSignature)?;
+            
+            let valid = match attester {
+                AttesterKey::Sr25519(public_key) => {
+                    let signature: [u8; 64] = signature.try_into().map_err(|_| Error::InvalidSignature)?;
+                    ink::env::sr25519_verify(&signature, message, &public_key).is_ok()
+                }
+                AttesterKey::Ecdsa(public_key) => {
+                    let signature: [u8; 65] = signature.try_into().map_err(|_| Error::InvalidSignature)?;
+                    let mut message_hash = <Blake2x256 as HashOutput>::Type::default();
+                    ink::env::hash_bytes::<Blake2x256>(message, &mut message_hash);
+                    let mut recovered = [0u8; 33];
+                    ink::env::ecdsa_recover(&signature, &message_hash, &mut recovered).is_ok()
+                        && recovered == public_key
+                }
+            };
+            if !valid {
+                return Err(Error::InvalidSignature);
+            }
+            
+            Ok(())
+        }
+        
+        /// Verify a Merkle proof of a payment's receipt under a stored state root
+        ///
+        /// The leaf is `execution_leaf(payment_id)`; each proof step hashes the
+        /// current node and its sibling in ascending order.
+        fn verify_state_proof(
+            &self,
+            payment_id: u32,
+            destination_chain: u32,
+            block_hash: Hash,
+            proof: Vec<Hash>,
+        ) -> Result<(), Error> {
+            self.verify_merkle_proof(destination_chain, block_hash, self.execution_leaf(payment_id), proof)?;
+            
+            self.env().emit_event(ExecutionVerified {
+                payment_id,
+                mode: VerificationMode::StateProof,
+                block_hash,
+            });
+            
+            Ok(())
+        }
+        
+        /// Verify a Merkle proof of `leaf` under a stored state root of a chain
+        ///
+        /// Each proof step hashes the current node and its sibling in ascending order.
+        fn verify_merkle_proof(&self, chain_id: u32, block_hash: Hash, leaf: Hash, proof: Vec<Hash>) -> Result<(), Error> {
+            let state_root = self
+                .state_roots
+                .get((chain_id, block_hash))
+                .ok_or(Error::UnknownStateRoot)?;
+            
+            let computed = proof
+                .iter()
+                .fold(leaf, |node, sibling| Self::hash_pair(node, *sibling));
+            if computed != state_root {
+                return Err(Error::InvalidProof);
+            }
+            
+            Ok(())
+        }
+        
+        /// Ensure a pending payment may be executed under `mode` by the caller, returning its chain
+        fn ensure_verifiable(&self, payment_id: u32, mode: VerificationMode) -> Result<u32, Error> {
+            if !self.payment_senders.contains(payment_id) {
+                return Err(Error::PaymentNotFound);
+            }
+            self.ensure_pending(payment_id)?;
+            
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            if !self.is_relayer(destination_chain, self.env().caller()) {
+                return Err(Error::UnauthorizedAccess);
+            }
+            
+            if self.get_verification_mode(destination_chain) != mode {
+                return Err(Error::WrongVerificationMode);
+            }
+            
+            Ok(destination_chain)
+        }
+        
+        /// Hash two Merkle nodes in ascending order
+        fn hash_pair(a: Hash, b: Hash) -> Hash {
+            let pair = if a <= b { (a, b) } else { (b, a) };
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(&pair, &mut output);
+            Hash::from(output)
+        }
+        
+        /// Move a payment to `next`, rejecting transitions its status does not allow
+        fn transition(&mut self, payment_id: u32, next: PaymentStatus) -> Result<(), Error> {
+            let status = self.payment_statuses.get(payment_id).ok_or(Error::PaymentNotFound)?;
+            if !status.can_transition_to(next) {
+                return Err(Self::status_error(status));
+            }
+            
+            self.payment_statuses.insert(payment_id, &next);
+            self.env().emit_event(PaymentStatusChanged {
+                payment_id,
+                from: status,
+                to: next,
+                timestamp: self.env().block_timestamp(),
+            });
+            
+            // Payments leaving the pending states settle and drop out of
+            // their users' indexes
+            if !matches!(next, PaymentStatus::Pending | PaymentStatus::InTransit) {
+                self.payment_settled_at.insert(payment_id, &self.env().block_timestamp());
+                self.record_settlement(payment_id, next);
+                if let Some(sender) = self.payment_senders.get(payment_id) {
+                    self.unindex_pending(sender, payment_id);
+                }
+                if let Some(recipient) = self.payment_recipients.get(payment_id) {
+                    self.unindex_pending(recipient, payment_id);
+                }
+            }
+            
+            Ok(())
+        }
+        
+        /// Count a settled payment in its destination chain's stats
+        fn record_settlement(&mut self, payment_id: u32, status: PaymentStatus) {
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            let mut stats = self.get_chain_stats(destination_chain);
+            let counter = match status {
+                PaymentStatus::Executed => &mut stats.executed,
+                PaymentStatus::Failed => &mut stats.failed,
+                PaymentStatus::Refunded => &mut stats.refunded,
+                PaymentStatus::Cancelled => &mut stats.cancelled,
+                PaymentStatus::Pending | PaymentStatus::InTransit => return,
+            };
+            *counter = counter.saturating_add(1);
+            self.chain_stats.insert(destination_chain, &stats);
+        }
+        
+        /// Add a payment to a user's pending payments
+        fn index_pending(&mut self, user: AccountId, payment_id: u32) {
+            if self.user_pending_indexes.contains((user, payment_id)) {
+                return;
+            }
+            
+            let count = self.get_pending_payments_count(user);
+            self.user_pending_payments.insert((user, count), &payment_id);
+            self.user_pending_indexes.insert((user, payment_id), &count);
+            self.user_pending_counts.insert(user, &count.saturating_add(1));
+        }
+        
+        /// Remove a payment from a user's pending payments, moving the last one into its slot
+        fn unindex_pending(&mut self, user: AccountId, payment_id: u32) {
+            let Some(index) = self.user_pending_indexes.take((user, payment_id)) else {
+                return;
+            };
+            
+            let last = self.get_pending_payments_count(user).saturating_sub(1);
+            if index != last {
+                if let Some(moved) = self.user_pending_payments.get((user, last)) {
+                    self.user_pending_payments.insert((user, index), &moved);
+                    self.user_pending_indexes.insert((user, moved), &index);
+                }
+            }
+            self.user_pending_payments.remove((user, last));
+            self.user_pending_counts.insert(user, &last);
+        }
+        
+        /// The error reported when a payment's status forbids an action
+        fn status_error(status: PaymentStatus) -> Error {
+            match status {
+                PaymentStatus::Executed => Error::AlreadyExecuted,
+                PaymentStatus::Cancelled => Error::PaymentCancelled,
+                PaymentStatus::Failed => Error::PaymentFailed,
+                PaymentStatus::Refunded => Error::PaymentRefunded,
+                PaymentStatus::InTransit => Error::AlreadyDispatched,
+                PaymentStatus::Pending => Error::PaymentNotFound,
+            }
+        }
+        
+        /// Pay the sender's referrer its share of an executed payment's delivery fee out of the fee pot
+        fn accrue_referral_reward(&mut self, payment_id: u32, sender: AccountId) {
+            let Some(referrer) = self.referrers.get(sender) else {
+                return;
+            };
+            if self.get_payment_fee_asset(payment_id) != NATIVE_ASSET {
+                return;
+            }
+            let delivery_fee = self.payment_delivery_fees.get(payment_id).unwrap_or(0);
+            let reward = (delivery_fee.saturating_mul(u128::from(self.referral_share_bps)) / BASIS_POINTS)
+                .min(self.fee_pot);
+            
+            let mut stats = self.get_referral_stats(referrer);
+            stats.fees_generated = stats.fees_generated.saturating_add(delivery_fee);
+            stats.rewards_earned = stats.rewards_earned.saturating_add(reward);
+            self.referral_stats.insert(referrer, &stats);
+            if reward == 0 {
+                return;
+            }
+            
+            self.fee_pot = self.fee_pot.saturating_sub(reward);
+            let rewards = self.get_referral_rewards(referrer);
+            self.referral_rewards.insert(referrer, &rewards.saturating_add(reward));
+            self.liabilities = self.liabilities.saturating_add(reward);
+            
+            self.env().emit_event(ReferralRewardAccrued {
+                referrer,
+                payment_id,
+                amount: reward,
+            });
+        }
+        
+        /// Return a payment's amount and fees to its sender's balances
+        fn refund_sender(&mut self, payment_id: u32, sender: AccountId) -> Balance {
+            // An unsuccessful refund lets the original payment be refunded again
+            if let Some(original_payment_id) = self.refund_origins.get(payment_id) {
+                self.payment_refunds.remove(original_payment_id);
+            }
+
+            let asset_id = self.get_payment_asset(payment_id);
+            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
+            let relayer_fee = self.payment_relayer_fees.get(payment_id).unwrap_or(0);
+            let fee_asset = self.get_payment_fee_asset(payment_id);
+            let delivery_fee = self.release_delivery_fee(payment_id);
+            self.credit(sender, asset_id, amount);
+            self.credit(sender, NATIVE_ASSET, relayer_fee);
+            self.credit(sender, fee_asset, delivery_fee);
+            self.release_collateral(self.held_collateral(payment_id));
+            amount
+        }
+        
+        /// Return what is still held here of a dispatched payment to its sender
+        ///
+        /// The amount itself only left the contract with `xcm-dispatch`.
+        fn refund_undispatched(&mut self, payment_id: u32, sender: AccountId) {
+            if !cfg!(feature = "xcm-dispatch") {
+                self.refund_sender(payment_id, sender);
+                return;
+            }
+            
+            let relayer_fee = self.payment_relayer_fees.get(payment_id).unwrap_or(0);
+            let fee_asset = self.get_payment_fee_asset(payment_id);
+            let delivery_fee = self.release_delivery_fee(payment_id);
+            self.credit(sender, NATIVE_ASSET, relayer_fee);
+            self.credit(sender, fee_asset, delivery_fee);
+            self.release_collateral(self.held_collateral(payment_id));
+        }
+        
+        /// Native funds the contract holds while a payment is pending
+        ///
+        /// Dispatched amounts leave the contract right away, and non-native
+        /// amounts are not backed by the native balance.
+        fn held_collateral(&self, payment_id: u32) -> Balance {
+            let relayer_fee = self.payment_relayer_fees.get(payment_id).unwrap_or(0);
+            if cfg!(feature = "xcm-dispatch") || self.get_payment_asset(payment_id) != NATIVE_ASSET {
+                return relayer_fee;
+            }
+            self.payment_amounts.get(payment_id).unwrap_or(0).saturating_add(relayer_fee)
+        }
+        
+        /// Add delivery fees paid in an asset to its fee pot
+        fn add_to_fee_pot(&mut self, asset_id: u32, amount: Balance) {
+            if asset_id == NATIVE_ASSET {
+                self.fee_pot = self.fee_pot.saturating_add(amount);
+            } else {
+                let pot = self.get_asset_fee_pot(asset_id).saturating_add(amount);
+                self.asset_fee_pots.insert(asset_id, &pot);
+            }
+        }
+        
+        /// Stop holding a settling payment's delivery fee, returning it
+        fn release_delivery_fee(&mut self, payment_id: u32) -> Balance {
+            let fee_asset = self.get_payment_fee_asset(payment_id);
+            let delivery_fee = self.payment_delivery_fees.get(payment_id).unwrap_or(0);
+            let pending = self.get_pending_delivery_fees(fee_asset).saturating_sub(delivery_fee);
+            self.pending_delivery_fees.insert(fee_asset, &pending);
+            delivery_fee
+        }
+        
+        /// Add to an account's balance of an asset
+        fn credit(&mut self, account: AccountId, asset_id: u32, amount: Balance) {
+            if !self.balance_holder_indexes.contains((account, asset_id)) {
+                self.balance_holders.insert(self.balance_holder_count, &(account, asset_id));
+                self.balance_holder_indexes.insert((account, asset_id), &self.balance_holder_count);
+                self.balance_holder_count = self.balance_holder_count.saturating_add(1);
+            }
+            let balance = self.get_asset_balance(account, asset_id).saturating_add(amount);
+            if asset_id == NATIVE_ASSET {
+                self.balances.insert(account, &balance);
+                self.liabilities = self.liabilities.saturating_add(amount);
+            } else {
+                self.asset_balances.insert((account, asset_id), &balance);
+            }
+        }
+        
+        /// Native funds the contract owes: open payments and their delivery fees, the fee pot, balances, rewards and keeper bonds
+        fn held_funds(&self) -> Balance {
+            self.pending_collateral
+                .saturating_add(self.get_pending_delivery_fees(NATIVE_ASSET))
+                .saturating_add(self.fee_pot)
+                .saturating_add(self.liabilities)
+                .saturating_add(self.keeper_bonded)
+        }
+        
+        /// Subtract from an account's balance of an asset, which the caller checked covers it
+        fn debit(&mut self, account: AccountId, asset_id: u32, amount: Balance) {
+            let balance = self.get_asset_balance(account, asset_id).saturating_sub(amount);
+            if asset_id == NATIVE_ASSET {
+                self.balances.insert(account, &balance);
+                self.liabilities = self.liabilities.saturating_sub(amount);
+            } else {
+                self.asset_balances.insert((account, asset_id), &balance);
+            }
+        }
+        
+        /// Count a payment against the sender's and the chain's rate limits, and its asset's
+        ///
+        /// The account and chain limits cap native amounts. A non-native asset
+        /// counts toward their payment counts, and while either caps amounts it
+        /// may only be sent under a rate limit of its own.
+        fn consume_rate_limits(
+            &mut self,
+            sender: AccountId,
+            chain_id: u32,
+            asset_id: u32,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let now = self.env().block_timestamp();
+            let native_amount = if asset_id == NATIVE_ASSET { amount } else { 0 };
+            let chain_limit = self.chain_rate_limits.get(chain_id);
+            
+            if asset_id != NATIVE_ASSET {
+                let amount_capped = [self.account_rate_limit, chain_limit]
+                    .iter()
+                    .flatten()
+                    .any(|limit| limit.max_amount > 0);
+                match self.asset_rate_limits.get(asset_id) {
+                    Some(limit) => {
+                        let account_usage = self
+                            .get_asset_account_rate_usage(sender, asset_id)
+                            .record(&limit, now, amount)
+                            .ok_or(Error::RateLimitExceeded)?;
+                        let chain_usage = self
+                            .get_asset_chain_rate_usage(chain_id, asset_id)
+                            .record(&limit, now, amount)
+                            .ok_or(Error::RateLimitExceeded)?;
+                        self.asset_account_rate_usage.insert((sender, asset_id), &account_usage);
+                        self.asset_chain_rate_usage.insert((chain_id, asset_id), &chain_usage);
+                    }
+                    None if amount_capped => return Err(Error::AssetRateLimitUnset),
+                    None => {}
+                }
+            }
+            
+            if let Some(limit) = self.account_rate_limit {
+                let usage = self
+                    .get_account_rate_usage(sender)
+                    .record(&limit, now, native_amount)
+                    .ok_or(Error::RateLimitExceeded)?;
+                self.account_rate_usage.insert(sender, &usage);
+            }
+            
+            if let Some(limit) = chain_limit {
+                let usage = self
+                    .get_chain_rate_usage(chain_id)
+                    .record(&limit, now, native_amount)
+                    .ok_or(Error::RateLimitExceeded)?;
+                self.chain_rate_usage.insert(chain_id, &usage);
+            }
+            
+            Ok(())
+        }
+        
+        /// A chain's configuration, whether or not the chain is supported
+        fn stored_chain_config(&self, chain_id: u32) -> ChainConfig {
+            self.chain_configs
+                .get(chain_id)
+                .unwrap_or_else(|| ChainConfig::default_for(chain_id))
+        }
+        
+        /// Allocate the XCM query reporting a dispatched payment's outcome
+        #[cfg_attr(not(feature = "xcm-dispatch"), allow(dead_code))]
+        fn register_query(&mut self, payment_id: u32) -> u64 {
+            let query_id = self.query_counter;
+            self.query_counter = self.query_counter.saturating_add(1);
+            self.query_payments.insert(query_id, &payment_id);
+            self.payment_queries.insert(payment_id, &query_id);
+            query_id
+        }
+        
+        /// Stop holding funds for a payment that is no longer pending
+        fn release_collateral(&mut self, amount: Balance) {
+            self.pending_collateral = self.pending_collateral.saturating_sub(amount);
+        }
+        
+        /// Execute a reserve transfer or teleport of `amount` of an asset to a payment's destination
+        ///
+        /// The contract pays from its own account, which holds the deposits.
+        /// The destination reports the outcome to this chain under `query_id`.
+        #[cfg(feature = "xcm-dispatch")]
+        fn dispatch_transfer(
+            &mut self,
+            payment_id: u32,
+            asset_id: u32,
+            amount: Balance,
+            query_id: u64,
+        ) -> Result<(), Error> {
+            use xcm::v4::prelude::*;
+            
+            // The beneficiary is the last junction of the payment's destination
+            let mut destination = self
+                .payment_destinations
+                .get(payment_id)
+                .ok_or(Error::InvalidDestination)?
+                .as_latest()
+                .clone();
+            let beneficiary = destination.interior.pop().ok_or(Error::InvalidDestination)?;
+            let dest = destination.to_xcm()?;
+            let beneficiary = Destination {
+                parents: 0,
+                interior: ink::prelude::vec![beneficiary],
+            }
+            .to_xcm()?;
+            
+            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            let config = self.stored_chain_config(destination_chain);
+            let weight_limit = match config.weight_limit {
+                Some(weight) => Limited(Weight::from_parts(weight.ref_time, weight.proof_size)),
+                None => Unlimited,
+            };
+            
+            // This chain and the asset as a sibling destination sees them
+            let source_chain = self.payment_source_chains.get(payment_id).unwrap_or(0);
+            let (local_asset, remote_asset) = match self.assets.get(asset_id) {
+                Some(location) => (
+                    Location::new(
+                        0,
+                        [
+                            PalletInstance(location.pallet_instance),
+                            GeneralIndex(location.general_index),
+                        ],
+                    ),
+                    Location::new(
+                        1,
+                        [
+                            Parachain(source_chain),
+                            PalletInstance(location.pallet_instance),
+                            GeneralIndex(location.general_index),
+                        ],
+                    ),
+                ),
+                None => (Location::here(), Location::new(1, [Parachain(source_chain)])),
+            };
+            
+            // Executed on the destination once the funds arrive
+            let remote_xcm = Xcm(ink::prelude::vec![
+                BuyExecution {
+                    fees: (remote_asset, amount).into(),
+                    weight_limit,
+                },
+                // Report the outcome back to this chain, whether or not the deposit succeeds
+                SetAppendix(Xcm(ink::prelude::vec![ReportError(QueryResponseInfo {
+                    destination: Location::new(1, [Parachain(source_chain)]),
+                    query_id,
+                    max_weight: Weight::zero(),
+                })])),
+                DepositAsset {
+                    assets: Wild(AllCounted(1)),
+                    beneficiary,
+                },
+            ]);
+            let transfer = match config.transfer_mode {
+                TransferMode::ReserveTransfer => DepositReserveAsset {
+                    assets: Wild(AllCounted(1)),
+                    dest,
+                    xcm: remote_xcm,
+                },
+                TransferMode::Teleport => InitiateTeleport {
+                    assets: Wild(AllCounted(1)),
+                    dest,
+                    xcm: remote_xcm,
+                },
+            };
+            let message: Xcm<()> = Xcm(ink::prelude::vec![
+                WithdrawAsset(Asset::from((local_asset, amount)).into()),
+                transfer,
+            ]);
+            
+            // Older destinations need the message in the version they accept
+            let message = xcm::IntoVersion::into_version(xcm::VersionedXcm::V4(message), config.xcm_version)
+                .map_err(|_| Error::UnsupportedXcmVersion)?;
+            
+            match self.dispatch_path {
+                XcmDispatchPath::HostFunctions => self
+                    .env()
+                    .xcm_execute(&message)
+                    .map_err(|_| Error::XcmExecutionFailed),
+                XcmDispatchPath::ChainExtension => self
+                    .env()
+                    .extension()
+                    .xcm_execute(&ink::scale::Encode::encode(&message))
+                    .map(|_| ())
+                    .map_err(|_| Error::XcmExecutionFailed),
+            }
+        }
+        
+        /// The configured BillSplitting contract, or the registry's if none is set
+        fn resolve_bill_splitting(&self) -> Option<AccountId> {
+            self.bill_splitting.or_else(|| {
+                let registry: ink::contract_ref!(ComponentRegistry) = self.registry?.into();
+                registry.resolve(Component::BillSplitting)
+            })
+        }
+        
+        /// Confirm an executed bill payment on the BillSplitting contract
+        ///
+        /// Best effort: a failed callback does not revert the execution, since
+        /// anyone can still confirm the payment on BillSplitting directly.
+        fn notify_bill_splitting(&self, payment_id: u32) {
+            if let Some(bill_splitting) = self.resolve_bill_splitting() {
+                let mut bill_manager: ink::contract_ref!(BillManager) = bill_splitting.into();
+                let _ = bill_manager.call_mut().on_payment_executed(payment_id).try_invoke();
+            }
+        }
+    }
+    
+    impl XcmGateway for XcmHandler {
+        #[ink(message)]
+        fn payment_summary(&self, payment_id: u32) -> Option<PaymentSummary> {
+            Some(PaymentSummary {
+                sender: self.payment_senders.get(payment_id)?,
+                recipient: self.payment_recipients.get(payment_id)?,
+                amount: self.payment_amounts.get(payment_id).unwrap_or(0),
+                message_type: self.get_payment_type(payment_id)?,
+                executed: self.payment_statuses.get(payment_id) == Some(PaymentStatus::Executed),
+                aborted: matches!(
+                    self.payment_statuses.get(payment_id),
+                    Some(PaymentStatus::Failed | PaymentStatus::Refunded | PaymentStatus::Cancelled)
+                ),
+                created_at: self.payment_timestamps.get(payment_id).unwrap_or(0),
+            })
+        }
+        
+        #[ink(message)]
+        fn quote_fee(&self, destination_chain: u32, amount: Balance) -> Balance {
+            self.quote_cross_chain_fee(destination_chain, amount)
+        }
+        
+        #[ink(message, payable)]
+        fn send_payment(
+            &mut self,
+            recipient: AccountId,
+            amount: Balance,
+            destination_chain: u32,
+        ) -> Result<u32, xcc_common::Error> {
+            self.deposit();
+            self.create_cross_chain_payment(recipient, NATIVE_ASSET, amount, destination_chain, XcmMessageType::Payment)
+                .map_err(Into::into)
+        }
+    }
+    
+    /// Refunding a payment past its chain's timeout is the maintenance job, one per payment id
+    impl Maintainable for XcmHandler {
+        #[ink(message)]
+        fn due_jobs(&self, offset: u32, limit: u32) -> JobPage {
+            let now = self.env().block_timestamp();
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(self.payment_counter);
+            let jobs = (offset..end)
+                .filter(|&payment_id| {
+                    let timeout = self.payment_destination_chains.get(payment_id)
+                        .map_or(DEFAULT_PAYMENT_TIMEOUT, |chain_id| self.get_chain_timeout(chain_id));
+                    self.ensure_pending(payment_id).is_ok()
+                        && now >= self.payment_timestamps.get(payment_id).unwrap_or(0).saturating_add(timeout)
+                })
+                .collect();
+            
+            JobPage {
+                jobs,
+                next_offset: (end < self.payment_counter).then_some(end),
+            }
+        }
+        
+        #[ink(message)]
+        fn run_job(&mut self, job_id: u32) -> Result<(), xcc_common::Error> {
+            self.refund_timed_out_payment(job_id).map_err(Into::into)
+        }
+        
+        #[ink(message)]
+        fn pay_keeper_bounty(&mut self, keeper: AccountId, job_id: u32, amount: Balance) -> Result<Balance, xcc_common::Error> {
+            if self.keeper_registry != Some(self.env().caller()) {
+                return Err(xcc_common::Error::UnauthorizedAccess);
+            }
+            
+            // Fees and collateral back pending refunds; only the job's bond pays
+            let Some(mut bond) = self.keeper_bonds.get(job_id) else {
+                return Ok(0);
+            };
+            let amount = bond.draw(amount);
+            if amount == 0 {
+                return Ok(0);
+            }
+            
+            self.keeper_bonds.insert(job_id, &bond);
+            self.keeper_bonded = self.keeper_bonded.saturating_sub(amount);
+            self.env()
+                .transfer(keeper, amount)
+                .map_err(|_| xcc_common::Error::TransferFailed)?;
+            
+            self.env().emit_event(KeeperBountyPaid { job_id, keeper, amount });
+            
+            Ok(amount)
+        }
+    }
+    
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        
+        #[ink::test]
+        fn default_works() {
+            let xcm_handler = XcmHandler::default();
+            assert_eq!(xcm_handler.payment_counter, 0);
+            assert!(xcm_handler.is_chain_supported(1000)); // Rococo
+            assert!(xcm_handler.is_chain_supported(2000)); // Westend
+        }
+        
+        #[ink::test]
+        fn error_codes_are_stable() {
+            assert_eq!(Error::PaymentNotFound.error_code(), 3000);
+            assert_eq!(Error::DeliveryTimedOut.error_code(), 3048);
+            assert_eq!(Error::WeightUnavailable.error_code(), 3051);
+            assert_eq!(Error::RelayerExists.error_code(), 3052);
+            assert_eq!(Error::AssetRateLimitUnset.error_code(), 3053);
+            assert_eq!(ink::scale::Encode::encode(&Error::XcmExecutionFailed), vec![6]);
+        }
+        
+        #[ink::test]
+        fn deposit_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Set transferred value
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            
+            xcm_handler.deposit();
+            
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 1000);
+        }
+        
+        #[ink::test]
+        fn create_payment_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Deposit funds first
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            
+            // Create cross-chain payment
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000, // Westend
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            assert_eq!(payment_id, 0);
+            
+            let payment = xcm_handler.get_payment_info(payment_id).unwrap();
+            assert_eq!(payment.0, accounts.alice); // sender
+            assert_eq!(payment.1, accounts.bob); // recipient
+            assert_eq!(payment.2, 1000); // amount
+            assert_eq!(payment.4, 2000); // destination_chain
+            assert!(!payment.5); // executed
+            
+            // Check balance was deducted
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 4000);
+        }
+        
+        #[ink::test]
+        fn execute_payment_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Configure relayer
+            xcm_handler.configure_chain(2000, true, Some(accounts.charlie)).unwrap();
+            
+            // Deposit funds
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            
+            // Create payment
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            // Switch to relayer
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            
+            // Execute payment
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            
+            let payment = xcm_handler.get_payment_info(payment_id).unwrap();
+            assert!(payment.5); // executed
+            
+            // Check recipient received funds
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+        }
+        
+        #[ink::test]
+        fn invalid_chain_fails() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Deposit funds
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            
+            // Try to send to unsupported chain
+            let result = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                9999, // Unsupported chain
+                XcmMessageType::Payment,
+            );
+            
+            assert_eq!(result, Err(Error::InvalidChain));
+        }
+        
+        #[ink::test]
+        fn insufficient_balance_fails() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Try to send without depositing
+            let result = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            );
+            
+            assert_eq!(result, Err(Error::InsufficientBalance));
+        }
+        
+        #[ink::test]
+        fn bill_payment_without_bill_splitting_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Only the owner may configure the BillSplitting contract
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                xcm_handler.set_bill_splitting(Some(accounts.django)),
+                Err(Error::UnauthorizedAccess)
+            );
+            assert_eq!(xcm_handler.set_registry(Some(accounts.django)), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(xcm_handler.get_bill_splitting(), None);
+            assert_eq!(xcm_handler.get_registry(), None);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::BillSplitting,
+            ).unwrap();
+            assert_eq!(xcm_handler.get_payment_type(payment_id), Some(XcmMessageType::BillSplitting));
+            
+            // Without a configured contract execution skips the callback
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+        }
+        
+        #[ink::test]
+        fn cancel_payment_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.alice), 1);
+            
+            // Only the sender may cancel
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                xcm_handler.cancel_cross_chain_payment(payment_id),
+                Err(Error::UnauthorizedAccess)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.cancel_cross_chain_payment(payment_id).unwrap();
+            assert!(xcm_handler.is_payment_cancelled(payment_id));
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 5000);
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.alice), 0);
+            
+            // Cancelled payments can be neither cancelled again nor executed
+            assert_eq!(
+                xcm_handler.cancel_cross_chain_payment(payment_id),
+                Err(Error::PaymentCancelled)
+            );
+            assert_eq!(
+                xcm_handler.execute_cross_chain_payment(payment_id),
+                Err(Error::PaymentCancelled)
+            );
+        }
+        
+        #[ink::test]
+        fn timed_out_payment_refund_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Only the owner may change timeouts
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_chain_timeout(2000, 1000), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.set_chain_timeout(2000, 1000).unwrap();
+            assert_eq!(xcm_handler.get_chain_timeout(3000), DEFAULT_PAYMENT_TIMEOUT);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            assert_eq!(
+                xcm_handler.refund_timed_out_payment(payment_id),
+                Err(Error::PaymentNotTimedOut)
+            );
+            
+            // Anyone may refund once the timeout passed
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            xcm_handler.refund_timed_out_payment(payment_id).unwrap();
+            assert!(xcm_handler.is_payment_failed(payment_id));
+            assert_eq!(xcm_handler.get_payment_status(payment_id), Some(PaymentStatus::Refunded));
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 5000);
+            
+            assert_eq!(
+                xcm_handler.refund_timed_out_payment(payment_id),
+                Err(Error::PaymentRefunded)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                xcm_handler.execute_cross_chain_payment(payment_id),
+                Err(Error::PaymentRefunded)
+            );
+        }
+        
+        /// Runtime side of `XcmExtension`, weighing every message by its length
+        struct MockXcmExtension;
+        
+        impl ink::env::test::ChainExtension for MockXcmExtension {
+            fn ext_id(&self) -> u16 {
+                0x0CC0
+            }
+            
+            fn call(&mut self, func_id: u16, input: &[u8], output: &mut Vec<u8>) -> u32 {
+                // The off-chain engine wraps the encoded arguments in a byte vector
+                let input: Vec<u8> = ink::scale::Decode::decode(&mut &input[..]).unwrap();
+                let message: Vec<u8> = ink::scale::Decode::decode(&mut &input[..]).unwrap();
+                if func_id != 3 || message.is_empty() {
+                    return 2;
+                }
+                let weight = XcmWeight { ref_time: message.len() as u64 * 1_000, proof_size: 64 };
+                ink::scale::Encode::encode_to(&weight, output);
+                0
+            }
+        }
+        
+        #[ink::test]
+        fn chain_extension_dispatch_path_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::register_chain_extension(MockXcmExtension);
+            
+            assert_eq!(xcm_handler.get_dispatch_path(), XcmDispatchPath::HostFunctions);
+            assert_eq!(xcm_handler.estimate_xcm_weight(vec![4; 10]), Err(Error::ChainExtensionDisabled));
+            
+            xcm_handler.set_dispatch_path(XcmDispatchPath::ChainExtension).unwrap();
+            assert_eq!(
+                xcm_handler.estimate_xcm_weight(vec![4; 10]),
+                Ok(XcmWeight { ref_time: 10_000, proof_size: 64 })
+            );
+            assert_eq!(xcm_handler.estimate_xcm_weight(Vec::new()), Err(Error::WeightUnavailable));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                xcm_handler.set_dispatch_path(XcmDispatchPath::HostFunctions),
+                Err(Error::UnauthorizedAccess)
+            );
+        }
+        
+        #[ink::test]
+        fn sweep_fee_pot_needs_a_treasury() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            xcm_handler.fee_pot = 30;
+            
+            assert_eq!(xcm_handler.sweep_fee_pot(10), Err(Error::NoTreasury));
+            xcm_handler.set_treasury(Some(accounts.eve)).unwrap();
+            assert_eq!(xcm_handler.get_treasury(), Some(accounts.eve));
+            assert_eq!(xcm_handler.sweep_fee_pot(31), Err(Error::InvalidAmount));
+            assert_eq!(xcm_handler.sweep_fee_pot(0), Err(Error::InvalidAmount));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_treasury(None), Err(Error::UnauthorizedAccess));
+            assert_eq!(xcm_handler.sweep_fee_pot(10), Err(Error::UnauthorizedAccess));
+            assert_eq!(xcm_handler.get_fee_pot(), 30);
+        }
+        
+        #[ink::test]
+        fn keeper_jobs_work() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            xcm_handler.set_chain_timeout(2000, 1000).unwrap();
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let timed_out = xcm_handler.create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 1000, 2000, XcmMessageType::Payment).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(500);
+            let recent = xcm_handler.create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 1000, 2000, XcmMessageType::Payment).unwrap();
+            assert!(xcm_handler.due_jobs(0, 10).jobs.is_empty());
+            
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            assert_eq!(xcm_handler.due_jobs(0, 10).jobs, ink::prelude::vec![timed_out]);
+            assert_eq!(
+                xcm_handler.run_job(recent),
+                Err(xcc_common::Error::Other(Error::PaymentNotTimedOut as u8))
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            xcm_handler.run_job(timed_out).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(timed_out), Some(PaymentStatus::Refunded));
+            assert!(xcm_handler.due_jobs(0, 10).jobs.is_empty());
+            
+            // Bounties come out of the job's bond, paid to the keeper registry's keepers only
+            assert_eq!(xcm_handler.set_keeper_registry(Some(accounts.eve)), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.set_keeper_registry(Some(accounts.eve)).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            xcm_handler.post_keeper_bond(timed_out).unwrap();
+            assert_eq!(xcm_handler.post_keeper_bond(recent + 1), Err(Error::PaymentNotFound));
+            assert_eq!(
+                xcm_handler.pay_keeper_bounty(accounts.django, timed_out, 20),
+                Err(xcc_common::Error::UnauthorizedAccess)
+            );
+            
+            // The fee pot backing pending refunds is never touched
+            let fee_pot = xcm_handler.get_fee_pot();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(xcm_handler.pay_keeper_bounty(accounts.django, recent, 20), Ok(0));
+            assert_eq!(xcm_handler.pay_keeper_bounty(accounts.django, timed_out, 20), Ok(20));
+            assert_eq!(xcm_handler.pay_keeper_bounty(accounts.django, timed_out, 20), Ok(10));
+            assert_eq!(xcm_handler.get_keeper_bond(timed_out), Some(0));
+            assert_eq!(xcm_handler.get_fee_pot(), fee_pot);
+        }
+        
+        #[ink::test]
+        fn relayer_quorum_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            xcm_handler.add_relayer(2000, accounts.django).unwrap();
+            xcm_handler.add_relayer(2000, accounts.eve).unwrap();
+            assert_eq!(xcm_handler.set_relayer_threshold(2000, 4), Err(Error::InvalidThreshold));
+            xcm_handler.set_relayer_threshold(2000, 2).unwrap();
+            assert_eq!(xcm_handler.get_relayers(2000).len(), 3);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            // Only relayers of the destination chain may attest, once each
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.attest_execution(payment_id), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.attest_execution(payment_id).unwrap();
+            assert_eq!(xcm_handler.attest_execution(payment_id), Err(Error::AlreadyAttested));
+            assert!(!xcm_handler.get_payment_info(payment_id).unwrap().5);
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 0);
+            
+            // The second attestation meets the quorum
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_attestation_count(payment_id), 2);
+            assert!(xcm_handler.get_payment_info(payment_id).unwrap().5);
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+            
+            // Relayers can only be removed while the quorum stays reachable
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.remove_relayer(2000, accounts.eve).unwrap();
+            assert_eq!(
+                xcm_handler.remove_relayer(2000, accounts.django),
+                Err(Error::InvalidThreshold)
+            );
+            assert!(!xcm_handler.is_relayer(2000, accounts.eve));
+        }
+        
+        #[ink::test]
+        fn relayer_fee_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.configure_chain(2000, true, Some(accounts.charlie)).unwrap();
+            assert_eq!(xcm_handler.set_relayer_fee(2000, 10, 10_001), Err(Error::InvalidFee));
+            xcm_handler.set_relayer_fee(2000, 10, 100).unwrap();
+            assert_eq!(xcm_handler.quote_relayer_fee(2000, 1000), 20);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            // The fee is charged to the sender on top of the amount
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 3980);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+            assert_eq!(xcm_handler.get_relayer_rewards(accounts.charlie), 20);
+            
+            assert_eq!(xcm_handler.claim_relayer_rewards(), Ok(20));
+            assert_eq!(xcm_handler.get_relayer_rewards(accounts.charlie), 0);
+            assert_eq!(xcm_handler.claim_relayer_rewards(), Err(Error::InvalidAmount));
+            
+            // Cancelled payments refund the fee as well
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            xcm_handler.cancel_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 3980);
+        }
+        
+        #[ink::test]
+        fn withdraw_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = ink::env::account_id::<ink::env::DefaultEnvironment>();
+            
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 5_000_000);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5_000_000);
+            xcm_handler.deposit();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                3_000_000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            assert_eq!(xcm_handler.get_pending_collateral(), 3_000_000);
+            
+            // Only the unspent balance can be withdrawn
+            assert_eq!(xcm_handler.withdraw(0), Err(Error::InvalidAmount));
+            assert_eq!(xcm_handler.withdraw(2_000_001), Err(Error::InsufficientBalance));
+            assert_eq!(xcm_handler.withdraw(1_500_000), Ok(()));
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 500_000);
+            
+            // Pending payments stay collateralized
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 3_200_000);
+            assert_eq!(xcm_handler.withdraw(500_000), Err(Error::InsufficientBalance));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 3_500_000);
+            
+            xcm_handler.cancel_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_pending_collateral(), 0);
+            assert_eq!(xcm_handler.withdraw(3_500_000), Ok(()));
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 0);
+        }
+        
+        #[ink::test]
+        fn payment_status_works() {
+            use PaymentStatus::*;
+            
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            assert!(Pending.can_transition_to(Executed));
+            assert!(InTransit.can_transition_to(Failed));
+            assert!(!InTransit.can_transition_to(Cancelled));
+            assert!(!Executed.can_transition_to(Refunded));
+            assert!(!Cancelled.can_transition_to(Pending));
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            assert_eq!(xcm_handler.get_payment_status(0), None);
+            
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(payment_id), Some(Pending));
+            
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(payment_id), Some(Executed));
+            
+            // Executed payments are final
+            assert_eq!(
+                xcm_handler.cancel_cross_chain_payment(payment_id),
+                Err(Error::AlreadyExecuted)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DEFAULT_PAYMENT_TIMEOUT);
+            assert_eq!(
+                xcm_handler.refund_timed_out_payment(payment_id),
+                Err(Error::AlreadyExecuted)
+            );
+            assert_eq!(xcm_handler.get_payment_status(payment_id), Some(Executed));
+        }
+        
+        #[ink::test]
+        fn pending_payment_index_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let mut payment_ids = Vec::new();
+            for recipient in [accounts.bob, accounts.charlie, accounts.bob] {
+                payment_ids.push(xcm_handler.create_cross_chain_payment(
+                    recipient,
+                    NATIVE_ASSET,
+                    100,
+                    2000,
+                    XcmMessageType::Payment,
+                ).unwrap());
+            }
+            
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.alice), 3);
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.bob), 2);
+            
+            let page = xcm_handler.get_pending_payments(accounts.alice, 1, 5);
+            assert_eq!(page.len(), 2);
+            assert_eq!(page[0].0, payment_ids[1]);
+            assert_eq!(page[0].1.1, accounts.charlie);
+            assert!(xcm_handler.get_pending_payments(accounts.alice, 3, 5).is_empty());
+            
+            // Settled payments leave the index, the last one takes their slot
+            xcm_handler.execute_cross_chain_payment(payment_ids[0]).unwrap();
+            xcm_handler.cancel_cross_chain_payment(payment_ids[1]).unwrap();
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.alice), 1);
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.bob), 1);
+            assert_eq!(xcm_handler.get_pending_payments_count(accounts.charlie), 0);
+            assert_eq!(xcm_handler.get_pending_payments(accounts.alice, 0, 5)[0].0, payment_ids[2]);
+            assert_eq!(xcm_handler.get_pending_payments(accounts.bob, 0, 5)[0].0, payment_ids[2]);
+        }
+        
+        #[ink::test]
+        fn payment_queries_work() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.configure_chain(3000, true, None).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            for (recipient, chain) in [
+                (accounts.bob, 2000),
+                (accounts.charlie, 3000),
+                (accounts.bob, 3000),
+                (accounts.bob, 2000),
+            ] {
+                xcm_handler.create_cross_chain_payment(
+                    recipient,
+                    NATIVE_ASSET,
+                    100,
+                    chain,
+                    XcmMessageType::Payment,
+                ).unwrap();
+            }
+            xcm_handler.execute_cross_chain_payment(2).unwrap();
+            
+            let payment = xcm_handler.get_payment(1).unwrap();
+            assert_eq!(payment.recipient, accounts.charlie);
+            assert_eq!(payment.destination_chain, 3000);
+            assert_eq!(payment.message_type, Some(XcmMessageType::Payment));
+            assert_eq!(payment.status, PaymentStatus::Pending);
+            assert_eq!(xcm_handler.get_payment(4), None);
+            
+            let all = xcm_handler.get_payments(PaymentFilter::default(), 0, 10);
+            assert_eq!(all.payments.len(), 4);
+            assert_eq!(all.next_offset, None);
+            
+            let filter = PaymentFilter {
+                recipient: Some(accounts.bob),
+                ..Default::default()
+            };
+            let page = xcm_handler.get_payments(filter.clone(), 0, 2);
+            let ids: Vec<u32> = page.payments.iter().map(|p| p.payment_id).collect();
+            assert_eq!(ids, [0, 2]);
+            assert_eq!(page.next_offset, Some(3));
+            let page = xcm_handler.get_payments(filter, 3, 2);
+            assert_eq!(page.payments[0].payment_id, 3);
+            assert_eq!(page.next_offset, None);
+            
+            let filter = PaymentFilter {
+                destination_chain: Some(3000),
+                status: Some(PaymentStatus::Pending),
+                ..Default::default()
+            };
+            let page = xcm_handler.get_payments(filter, 0, 10);
+            assert_eq!(page.payments.len(), 1);
+            assert_eq!(page.payments[0].payment_id, 1);
+        }
+        
+        #[ink::test]
+        fn inbound_payment_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let receive = |xcm_handler: &mut XcmHandler, hash, recipient, amount, proof| {
+                xcm_handler.receive_cross_chain_payment(2000, hash, accounts.django, recipient, NATIVE_ASSET, amount, 1, proof)
+            };
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            let hash = xcm_handler.inbound_message_hash(2000, accounts.django, accounts.bob, NATIVE_ASSET, 700, 1);
+            
+            // Only relayers of the source chain may deliver
+            assert_eq!(
+                receive(&mut xcm_handler, hash, accounts.bob, 700, ExecutionProof::Attestation),
+                Err(Error::UnauthorizedAccess)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                receive(&mut xcm_handler, hash, accounts.bob, 701, ExecutionProof::Attestation),
+                Err(Error::InvalidMessageHash)
+            );
+            receive(&mut xcm_handler, hash, accounts.bob, 700, ExecutionProof::Attestation).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 700);
+            assert!(xcm_handler.is_message_processed(hash));
+            assert!(xcm_handler.is_inbound_nonce_used(2000, 1));
+            
+            // The same message is never credited twice
+            assert_eq!(
+                receive(&mut xcm_handler, hash, accounts.bob, 700, ExecutionProof::Attestation),
+                Err(Error::MessageAlreadyProcessed)
+            );
+            let hash = xcm_handler.inbound_message_hash(2000, accounts.django, accounts.eve, NATIVE_ASSET, 700, 1);
+            assert_eq!(
+                receive(&mut xcm_handler, hash, accounts.eve, 700, ExecutionProof::Attestation),
+                Err(Error::MessageAlreadyProcessed)
+            );
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 700);
+        }
+        
+        #[ink::test]
+        fn inbound_payment_is_verified() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let receive = |xcm_handler: &mut XcmHandler, hash, nonce, proof| {
+                xcm_handler.receive_cross_chain_payment(2000, hash, accounts.django, accounts.bob, NATIVE_ASSET, 700, nonce, proof)
+            };
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            xcm_handler.add_relayer(2000, accounts.eve).unwrap();
+            xcm_handler.set_relayer_threshold(2000, 2).unwrap();
+            let hash = xcm_handler.inbound_message_hash(2000, accounts.django, accounts.bob, NATIVE_ASSET, 700, 1);
+            
+            // One relayer's word is not enough on a chain with a threshold of two
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            receive(&mut xcm_handler, hash, 1, ExecutionProof::Attestation).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 0);
+            assert!(!xcm_handler.is_message_processed(hash));
+            assert_eq!(
+                receive(&mut xcm_handler, hash, 1, ExecutionProof::Attestation),
+                Err(Error::AlreadyAttested)
+            );
+            
+            // Paused chains credit nothing
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.pause_chain(2000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                receive(&mut xcm_handler, hash, 1, ExecutionProof::Attestation),
+                Err(Error::ChainPaused)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.unpause_chain(2000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            receive(&mut xcm_handler, hash, 1, ExecutionProof::Attestation).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 700);
+            
+            // Chains verified by state proofs need a proof of the message hash
+            let block_hash = Hash::from([7; 32]);
+            let hash = xcm_handler.inbound_message_hash(2000, accounts.django, accounts.bob, NATIVE_ASSET, 700, 2);
+            let sibling = Hash::from([1; 32]);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.set_verification_mode(2000, VerificationMode::StateProof, None).unwrap();
+            xcm_handler
+                .submit_state_root(2000, block_hash, XcmHandler::hash_pair(hash, sibling))
+                .unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                receive(&mut xcm_handler, hash, 2, ExecutionProof::Attestation),
+                Err(Error::WrongVerificationMode)
+            );
+            let proof = |siblings| ExecutionProof::StateProof { block_hash, proof: siblings };
+            assert_eq!(
+                receive(&mut xcm_handler, hash, 2, proof(vec![Hash::from([2; 32])])),
+                Err(Error::InvalidProof)
+            );
+            receive(&mut xcm_handler, hash, 2, proof(vec![sibling])).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1400);
+        }
+        
+        #[ink::test]
+        fn signed_execution_works() {
+            use ink_e2e::subxt_signer::{ecdsa, sr25519};
+            
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let block_hash = Hash::from([7; 32]);
+            let attester = sr25519::dev::alice();
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            assert_eq!(
+                xcm_handler.set_verification_mode(2000, VerificationMode::Signed, None),
+                Err(Error::WrongVerificationMode)
+            );
+            xcm_handler.set_verification_mode(
+                2000,
+                VerificationMode::Signed,
+                Some(AttesterKey::Sr25519(attester.public_key().0)),
+            ).unwrap();
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            // Neither the owner nor relayers can execute on their own word
+            assert_eq!(xcm_handler.execute_cross_chain_payment(payment_id), Err(Error::ProofRequired));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(xcm_handler.execute_cross_chain_payment(payment_id), Err(Error::ProofRequired));
+            
+            let message = xcm_handler.execution_message(payment_id, block_hash);
+            let forged = sr25519::dev::bob().sign(&message).0.to_vec();
+            assert_eq!(
+                xcm_handler.execute_with_attestation(payment_id, block_hash, forged),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(
+                xcm_handler.execute_with_proof(payment_id, block_hash, Vec::new()),
+                Err(Error::WrongVerificationMode)
+            );
+            
+            let signature = attester.sign(&message).0.to_vec();
+            xcm_handler.execute_with_attestation(payment_id, block_hash, signature).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(payment_id), Some(PaymentStatus::Executed));
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+            
+            // ECDSA attesters sign the message hash
+            let attester = ecdsa::dev::alice();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.set_verification_mode(
+                2000,
+                VerificationMode::Signed,
+                Some(AttesterKey::Ecdsa(attester.public_key().0)),
+            ).unwrap();
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            let message = xcm_handler.execution_message(payment_id, block_hash);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            let signature = attester.sign(&message).0.to_vec();
+            xcm_handler.execute_with_attestation(payment_id, block_hash, signature).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 2000);
+        }
+        
+        #[ink::test]
+        fn state_proof_execution_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let block_hash = Hash::from([7; 32]);
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            xcm_handler.set_verification_mode(2000, VerificationMode::StateProof, None).unwrap();
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler.create_cross_chain_payment(
+                accounts.bob,
+                NATIVE_ASSET,
+                1000,
+                2000,
+                XcmMessageType::Payment,
+            ).unwrap();
+            
+            // Root of a two-leaf tree holding the payment's receipt
+            let sibling = Hash::from([1; 32]);
+            let root = XcmHandler::hash_pair(xcm_handler.execution_leaf(payment_id), sibling);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                xcm_handler.execute_with_proof(payment_id, block_hash, vec![sibling]),
+                Err(Error::UnknownStateRoot)
+            );
+            assert_eq!(
+                xcm_handler.submit_state_root(2000, block_hash, root),
+                Err(Error::UnauthorizedAccess)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.submit_state_root(2000, block_hash, root).unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                xcm_handler.execute_with_proof(payment_id, block_hash, vec![Hash::from([2; 32])]),
+                Err(Error::InvalidProof)
+            );
+            xcm_handler.execute_with_proof(payment_id, block_hash, vec![sibling]).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+            assert_eq!(
+                xcm_handler.execute_with_proof(payment_id, block_hash, vec![sibling]),
+                Err(Error::AlreadyExecuted)
+            );
         }
         
-        /// Create a cross-chain payment request
-        #[ink(message)]
-        pub fn create_cross_chain_payment(
-            &mut self,
-            recipient: AccountId,
-            amount: Balance,
-            destination_chain: u32,
-            message_type: XcmMessageType,
-        ) -> Result<u32, Error> {
-            let sender = self.env().caller();
+        #[ink::test]
+        fn multi_asset_payment_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let usdt = AssetLocation { pallet_instance: 50, general_index: 1984 };
             
-            // Validate amount
-            if amount == 0 {
-                return Err(Error::InvalidAmount);
-            }
+            assert_eq!(xcm_handler.register_asset(NATIVE_ASSET, usdt), Err(Error::UnsupportedAsset));
+            assert_eq!(
+                xcm_handler.set_asset_representation(1, 2000, Some(usdt)),
+                Err(Error::UnsupportedAsset)
+            );
+            xcm_handler.register_asset(1, usdt).unwrap();
+            xcm_handler.add_relayer(3000, accounts.charlie).unwrap();
             
-            // Check if destination chain is supported
-            if !self.supported_chains.get(destination_chain).unwrap_or(false) {
-                return Err(Error::InvalidChain);
-            }
+            // Bridged assets arrive as inbound payments
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            let hash = xcm_handler.inbound_message_hash(3000, accounts.django, accounts.alice, 1, 700, 1);
+            xcm_handler.receive_cross_chain_payment(3000, hash, accounts.django, accounts.alice, 1, 700, 1, ExecutionProof::Attestation).unwrap();
+            assert_eq!(xcm_handler.get_asset_balance(accounts.alice, 1), 700);
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 0);
             
-            // Check sender balance
-            let sender_balance = self.balances.get(sender).unwrap_or(0);
-            if sender_balance < amount {
-                return Err(Error::InsufficientBalance);
-            }
+            // Assets move only to chains they are represented on
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, 1, 500, 2000, XcmMessageType::Payment),
+                Err(Error::UnsupportedAsset)
+            );
+            xcm_handler.set_asset_representation(1, 2000, Some(usdt)).unwrap();
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, 1, 800, 2000, XcmMessageType::Payment),
+                Err(Error::InsufficientBalance)
+            );
             
-            // Get source chain (simplified - in real implementation would detect actual chain)
-            let source_chain = 1000; // Default to Rococo
+            // Only the native token teleports
+            xcm_handler.set_chain_transfer_mode(2000, TransferMode::Teleport).unwrap();
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, 1, 500, 2000, XcmMessageType::Payment),
+                Err(Error::UnsupportedTransferMode)
+            );
+            xcm_handler.set_chain_transfer_mode(2000, TransferMode::ReserveTransfer).unwrap();
             
-            let payment_id = self.payment_counter;
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, 1, 500, 2000, XcmMessageType::Payment)
+                .unwrap();
+            assert_eq!(xcm_handler.get_asset_balance(accounts.alice, 1), 200);
+            assert_eq!(xcm_handler.get_payment(payment_id).unwrap().asset_id, 1);
+            assert_eq!(xcm_handler.get_pending_collateral(), 0);
             
-            // Store payment information
-            self.payment_senders.insert(payment_id, &sender);
-            self.payment_recipients.insert(payment_id, &recipient);
-            self.payment_amounts.insert(payment_id, &amount);
-            self.payment_source_chains.insert(payment_id, &source_chain);
-            self.payment_destination_chains.insert(payment_id, &destination_chain);
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_asset_balance(accounts.bob, 1), 500);
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 0);
             
-            // Encode message type as u8
-            let msg_type = match message_type {
-                XcmMessageType::Payment => 0,
-                XcmMessageType::BillSplitting => 1,
-                XcmMessageType::TokenTransfer => 2,
-                XcmMessageType::Refund => 3,
+            // Cancelled asset payments return the asset
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, 1, 200, 2000, XcmMessageType::Payment)
+                .unwrap();
+            xcm_handler.cancel_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_asset_balance(accounts.alice, 1), 200);
+        }
+        
+        #[ink::test]
+        fn chain_config_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Supported chains without configuration use the defaults
+            assert_eq!(xcm_handler.get_chain_config(2000), Some(ChainConfig::default_for(2000)));
+            assert_eq!(xcm_handler.get_chain_config(4000), None);
+            
+            let config = ChainConfig {
+                location: VersionedDestination::V4(Destination::sibling(2004)),
+                name_hash: Hash::from([9; 32]),
+                decimals: 10,
+                min_transfer_amount: 100,
+                fee: RelayerFee { flat: 5, bps: 0 },
+                delivery_fee: DeliveryFee::default(),
+                paused: false,
+                weight_limit: None,
+                xcm_version: LATEST_XCM_VERSION,
+                transfer_mode: TransferMode::ReserveTransfer,
             };
-            self.payment_types.insert(payment_id, &msg_type);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_chain_config(2000, config.clone()), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.set_chain_config(2000, config.clone()).unwrap();
+            assert_eq!(xcm_handler.get_chain_config(2000), Some(config.clone()));
+            assert_eq!(xcm_handler.get_relayer_fee(2000), RelayerFee { flat: 5, bps: 0 });
             
-            self.payment_executed.insert(payment_id, &false);
-            self.payment_timestamps.insert(payment_id, &self.env().block_timestamp());
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 99, 2000, XcmMessageType::Payment),
+                Err(Error::AmountBelowMinimum)
+            );
+            xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                .unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 4895);
             
-            // Deduct from sender balance
-            self.balances.insert(sender, &sender_balance.saturating_sub(amount));
+            // Fee updates keep the rest of the configuration
+            xcm_handler.set_relayer_fee(2000, 0, 0).unwrap();
+            assert_eq!(xcm_handler.get_chain_config(2000).unwrap().location, config.location);
             
-            self.payment_counter = self.payment_counter.saturating_add(1);
+            xcm_handler.set_chain_config(2000, ChainConfig { paused: true, ..config }).unwrap();
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment),
+                Err(Error::ChainPaused)
+            );
+        }
+        
+        #[ink::test]
+        fn delivery_fee_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            self.env().emit_event(CrossChainPaymentCreated {
-                payment_id,
-                sender,
-                recipient,
-                amount,
-                destination_chain,
+            let fee = DeliveryFee { base: 10, bps: 100, max: 25 };
+            assert_eq!(
+                xcm_handler.set_delivery_fee(2000, DeliveryFee { bps: 10_001, ..fee }),
+                Err(Error::InvalidFee)
+            );
+            xcm_handler.set_delivery_fee(2000, fee).unwrap();
+            xcm_handler.set_relayer_fee(2000, 5, 0).unwrap();
+            assert_eq!(xcm_handler.quote_cross_chain_fee(2000, 1000), 25);
+            assert_eq!(xcm_handler.quote_cross_chain_fee(2000, 100), 16);
+            assert_eq!(xcm_handler.quote_cross_chain_fee(3000, 1000), 0);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 1000, 2000, XcmMessageType::Payment)
+                .unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 3975);
+            assert_eq!(xcm_handler.get_pending_delivery_fees(NATIVE_ASSET), 20);
+            assert_eq!(xcm_handler.get_payment(payment_id).unwrap().delivery_fee, 20);
+            
+            // Fees of unsettled payments cannot be paid out
+            assert_eq!(xcm_handler.get_fee_pot(), 0);
+            assert_eq!(xcm_handler.pay_from_fee_pot(accounts.charlie, 20), Err(Error::InvalidAmount));
+            
+            // Undelivered payments return their delivery fee in full
+            let cancelled = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 1000, 2000, XcmMessageType::Payment)
+                .unwrap();
+            xcm_handler.cancel_cross_chain_payment(cancelled).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 3975);
+            assert_eq!(xcm_handler.get_pending_delivery_fees(NATIVE_ASSET), 20);
+            
+            // Executed payments fund the pot, which funds relayers
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_pending_delivery_fees(NATIVE_ASSET), 0);
+            assert_eq!(xcm_handler.get_fee_pot(), 20);
+            assert_eq!(xcm_handler.pay_from_fee_pot(accounts.charlie, 21), Err(Error::InvalidAmount));
+            xcm_handler.pay_from_fee_pot(accounts.charlie, 20).unwrap();
+            assert_eq!(xcm_handler.get_fee_pot(), 0);
+            assert_eq!(xcm_handler.get_relayer_rewards(accounts.charlie), 20);
+        }
+        
+        #[ink::test]
+        fn batch_creation_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.set_relayer_fee(2000, 10, 0).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            
+            assert_eq!(xcm_handler.create_cross_chain_batch(Vec::new(), 2000), Err(Error::InvalidBatch));
+            
+            // The total includes every payment's fees
+            assert_eq!(
+                xcm_handler.create_cross_chain_batch(vec![(accounts.bob, 500), (accounts.charlie, 490)], 2000),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 1000);
+            
+            let payment_ids = xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 500), (accounts.charlie, 480)], 2000)
+                .unwrap();
+            assert_eq!(payment_ids, [0, 1]);
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 0);
+            assert_eq!(xcm_handler.get_batch(0), Some(payment_ids.clone()));
+            assert_eq!(xcm_handler.get_payment_batch(1), Some(0));
+            assert_eq!(xcm_handler.get_payment(1).unwrap().recipient, accounts.charlie);
+            
+            // One batch event plus one event per payment
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(events, 5);
+        }
+        
+        #[ink::test]
+        fn batch_execution_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let mut payment_ids = xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 100), (accounts.django, 200)], 2000)
+                .unwrap();
+            let other_chain = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 300, 3000, XcmMessageType::Payment)
+                .unwrap();
+            payment_ids.push(other_chain);
+            payment_ids.push(99);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(xcm_handler.execute_cross_chain_payments(Vec::new()), Err(Error::InvalidBatch));
+            let results = xcm_handler.execute_cross_chain_payments(payment_ids).unwrap();
+            assert_eq!(
+                results,
+                [Ok(()), Ok(()), Err(Error::UnauthorizedAccess), Err(Error::PaymentNotFound)]
+            );
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 100);
+            assert_eq!(xcm_handler.get_balance(accounts.django), 200);
+            assert_eq!(xcm_handler.get_payment_status(other_chain), Some(PaymentStatus::Pending));
+        }
+        
+        #[ink::test]
+        fn payment_destination_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            // Payments address their chain's location plus the beneficiary
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                .unwrap();
+            let expected = Destination::sibling(2000).join(DestinationJunction::AccountId32(*accounts.bob.as_ref()));
+            assert_eq!(
+                xcm_handler.get_payment_destination(payment_id),
+                Some(VersionedDestination::V4(expected.clone()))
+            );
+            assert_eq!(
+                xcm_handler.get_payment(payment_id).unwrap().destination,
+                Some(VersionedDestination::V4(expected))
+            );
+            
+            // Chains can sit anywhere, e.g. behind a pallet of the relay chain
+            let relay_pallet = Destination {
+                parents: 1,
+                interior: vec![DestinationJunction::PalletInstance(99)],
+            };
+            let mut config = ChainConfig::default_for(3000);
+            config.location = VersionedDestination::V4(relay_pallet.clone());
+            xcm_handler.set_chain_config(3000, config.clone()).unwrap();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 3000, XcmMessageType::Payment)
+                .unwrap();
+            let destination = xcm_handler.get_payment_destination(payment_id).unwrap();
+            assert_eq!(destination.as_latest().parents, 1);
+            assert_eq!(destination.as_latest().interior[0], DestinationJunction::PalletInstance(99));
+            
+            // Locations are bounded
+            config.location = VersionedDestination::V4(Destination {
+                parents: 1,
+                interior: vec![DestinationJunction::GeneralIndex(0); MAX_JUNCTIONS + 1],
             });
+            assert_eq!(xcm_handler.set_chain_config(3000, config), Err(Error::InvalidDestination));
+        }
+        
+        #[ink::test]
+        fn evm_beneficiary_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let evm_address = [0x42; 20];
             
-            Ok(payment_id)
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment_to(
+                    Beneficiary::AccountKey20(evm_address),
+                    NATIVE_ASSET,
+                    100,
+                    2000,
+                    XcmMessageType::Payment,
+                )
+                .unwrap();
+            
+            let payment = xcm_handler.get_payment(payment_id).unwrap();
+            assert_eq!(payment.beneficiary, Beneficiary::AccountKey20(evm_address));
+            assert_eq!(payment.recipient, Beneficiary::AccountKey20(evm_address).local_account());
+            assert_eq!(
+                payment.destination.unwrap().as_latest().interior.last(),
+                Some(&DestinationJunction::AccountKey20(evm_address))
+            );
+            
+            // 32-byte recipients keep working as before
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                .unwrap();
+            assert_eq!(
+                xcm_handler.get_payment_beneficiary(payment_id),
+                Some(Beneficiary::AccountId32(accounts.bob))
+            );
         }
         
-        /// Execute a cross-chain payment (called by relayer)
-        #[ink(message)]
-        pub fn execute_cross_chain_payment(
-            &mut self,
-            payment_id: u32,
-        ) -> Result<(), Error> {
-            let caller = self.env().caller();
+        #[ink::test]
+        fn ethereum_transfer_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let (recipient, weth) = ([0x42; 20], [0xc0; 20]);
             
-            // Check if payment exists
-            if !self.payment_senders.contains(payment_id) {
-                return Err(Error::PaymentNotFound);
-            }
+            let mut config = ChainConfig::default_for(4000);
+            config.location = VersionedDestination::V4(Destination::ethereum(11_155_111));
+            config.delivery_fee = DeliveryFee { base: 50, bps: 0, max: 0 };
+            xcm_handler.set_chain_config(4000, config).unwrap();
+            xcm_handler.configure_chain(4000, true, None).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            
+            // Only tokens with an ERC-20 contract go to 20-byte accounts
+            let pay = |xcm_handler: &mut XcmHandler, beneficiary| {
+                xcm_handler.create_cross_chain_payment_to(beneficiary, NATIVE_ASSET, 400, 4000, XcmMessageType::TokenTransfer)
+            };
+            assert_eq!(pay(&mut xcm_handler, Beneficiary::AccountKey20(recipient)), Err(Error::UnsupportedAsset));
+            assert_eq!(xcm_handler.set_erc20_token(1, 4000, Some(weth)), Err(Error::UnsupportedAsset));
+            xcm_handler.set_erc20_token(NATIVE_ASSET, 4000, Some(weth)).unwrap();
+            assert_eq!(
+                pay(&mut xcm_handler, Beneficiary::AccountId32(accounts.bob)),
+                Err(Error::InvalidDestination)
+            );
+            xcm_handler.set_dispatch_gas(4000, 120_000).unwrap();
+            
+            let payment_id = pay(&mut xcm_handler, Beneficiary::AccountKey20(recipient)).unwrap();
+            let transfer = xcm_handler.get_ethereum_transfer(payment_id).unwrap();
+            assert_eq!(
+                transfer,
+                EthereumTransfer {
+                    chain_id: 11_155_111,
+                    token: weth,
+                    recipient,
+                    amount: 400,
+                    max_dispatch_gas: 120_000,
+                    fee: 50,
+                }
+            );
+            assert_eq!(
+                xcm_handler.get_payment_destination(payment_id).unwrap().as_latest().interior,
+                vec![
+                    DestinationJunction::Ethereum { chain_id: 11_155_111 },
+                    DestinationJunction::AccountKey20(recipient),
+                ]
+            );
+            
+            let payload = xcm_handler.get_ethereum_payload(payment_id).unwrap();
+            assert_eq!(payload.len(), 6 * 32);
+            assert_eq!(payload[31], 0);
+            assert_eq!(payload[63], 0x40);
+            assert_eq!(payload[95], 96);
+            assert_eq!(&payload[108..128], &weth);
+            assert_eq!(&payload[140..160], &recipient);
+            assert_eq!(u128::from_be_bytes(payload[176..192].try_into().unwrap()), 400);
+            
+            // Delivery is tracked like any other payment
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(payment_id), Some(PaymentStatus::Executed));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_dispatch_gas(4000, 1), Err(Error::UnauthorizedAccess));
+            assert_eq!(xcm_handler.set_erc20_token(NATIVE_ASSET, 4000, None), Err(Error::UnauthorizedAccess));
+        }
+        
+        #[ink::test]
+        fn asset_hub_stablecoin_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let usdt = AssetMetadata {
+                decimals: 6,
+                min_transfer_amount: 1_000_000,
+                delivery_fee: Some(50_000),
+            };
+            
+            xcm_handler.register_asset_hub_asset(USDT_ASSET_ID, usdt).unwrap();
+            assert_eq!(
+                xcm_handler.get_asset_representation(USDT_ASSET_ID, ASSET_HUB_CHAIN_ID),
+                Some(AssetLocation { pallet_instance: 50, general_index: 1984 })
+            );
+            assert_eq!(xcm_handler.get_asset_metadata(USDT_ASSET_ID), Some(usdt));
+            xcm_handler
+                .register_asset_hub_asset(USDC_ASSET_ID, AssetMetadata { delivery_fee: None, ..usdt })
+                .unwrap();
+            assert_eq!(xcm_handler.set_asset_metadata(NATIVE_ASSET, usdt), Err(Error::UnsupportedAsset));
+            
+            // Alice holds USDT only
+            xcm_handler.add_relayer(ASSET_HUB_CHAIN_ID, accounts.charlie).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            let hash = xcm_handler.inbound_message_hash(
+                ASSET_HUB_CHAIN_ID,
+                accounts.django,
+                accounts.alice,
+                USDT_ASSET_ID,
+                5_000_000,
+                1,
+            );
+            xcm_handler
+                .receive_cross_chain_payment(
+                    ASSET_HUB_CHAIN_ID,
+                    hash,
+                    accounts.django,
+                    accounts.alice,
+                    USDT_ASSET_ID,
+                    5_000_000,
+                    1,
+                    ExecutionProof::Attestation,
+                )
+                .unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let pay = |xcm_handler: &mut XcmHandler, amount, fee_asset| {
+                xcm_handler.create_cross_chain_payment_with_fee_asset(
+                    Beneficiary::AccountId32(accounts.bob),
+                    USDT_ASSET_ID,
+                    amount,
+                    ASSET_HUB_CHAIN_ID,
+                    XcmMessageType::Payment,
+                    fee_asset,
+                )
+            };
+            assert_eq!(pay(&mut xcm_handler, 999_999, USDT_ASSET_ID), Err(Error::AmountBelowMinimum));
+            assert_eq!(pay(&mut xcm_handler, 2_000_000, USDC_ASSET_ID), Err(Error::InvalidFeeAsset));
+            assert_eq!(pay(&mut xcm_handler, 5_000_000, USDT_ASSET_ID), Err(Error::InsufficientBalance));
+            
+            // The delivery fee comes out of the USDT balance and is held in USDT
+            let payment_id = pay(&mut xcm_handler, 2_000_000, USDT_ASSET_ID).unwrap();
+            assert_eq!(xcm_handler.get_payment_fee_asset(payment_id), USDT_ASSET_ID);
+            assert_eq!(xcm_handler.get_asset_balance(accounts.alice, USDT_ASSET_ID), 2_950_000);
+            assert_eq!(xcm_handler.get_pending_delivery_fees(USDT_ASSET_ID), 50_000);
+            assert_eq!(xcm_handler.get_asset_fee_pot(USDT_ASSET_ID), 0);
+            assert_eq!(xcm_handler.get_pending_delivery_fees(NATIVE_ASSET), 0);
+            
+            // Cancelling returns the fee in the asset it was paid in
+            xcm_handler.cancel_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_asset_balance(accounts.alice, USDT_ASSET_ID), 5_000_000);
+            assert_eq!(xcm_handler.get_pending_delivery_fees(USDT_ASSET_ID), 0);
+            
+            // Execution moves it into the USDT pot
+            let payment_id = pay(&mut xcm_handler, 2_000_000, USDT_ASSET_ID).unwrap();
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_asset_fee_pot(USDT_ASSET_ID), 50_000);
+            assert_eq!(xcm_handler.get_fee_pot(), 0);
+            assert_eq!(
+                xcm_handler.pay_from_asset_fee_pot(USDT_ASSET_ID, accounts.charlie, 50_001),
+                Err(Error::InvalidAmount)
+            );
+            xcm_handler.pay_from_asset_fee_pot(USDT_ASSET_ID, accounts.charlie, 50_000).unwrap();
+            assert_eq!(xcm_handler.get_asset_balance(accounts.charlie, USDT_ASSET_ID), 50_000);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_asset_metadata(USDT_ASSET_ID, usdt), Err(Error::UnauthorizedAccess));
+            assert_eq!(
+                xcm_handler.register_asset_hub_asset(USDT_ASSET_ID, usdt),
+                Err(Error::UnauthorizedAccess)
+            );
+        }
+        
+        #[ink::test]
+        fn state_export_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Check if already executed
-            if self.payment_executed.get(payment_id).unwrap_or(false) {
-                return Err(Error::AlreadyExecuted);
+            for account in [accounts.alice, accounts.bob] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(account);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+                xcm_handler.deposit();
+                xcm_handler
+                    .create_cross_chain_payment(accounts.charlie, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                    .unwrap();
             }
             
-            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
+            let chunk = xcm_handler.export_payments(0, 1);
+            assert_eq!((chunk.entries, chunk.next_offset), (1, Some(1)));
+            let payments: Vec<XcmPaymentInfo> = ink::scale::Decode::decode(&mut &chunk.data[..]).unwrap();
+            assert_eq!(payments, vec![xcm_handler.get_payment(0).unwrap()]);
+            let chunk = xcm_handler.export_payments(1, 10);
+            assert_eq!((chunk.entries, chunk.next_offset), (1, None));
             
-            // Verify caller is authorized relayer for the destination chain
-            let authorized_relayer = self.relayers.get(destination_chain);
-            if authorized_relayer != Some(caller) && caller != self.owner {
-                return Err(Error::UnauthorizedAccess);
+            let chunk = xcm_handler.export_balances(0, 10);
+            let balances: Vec<(AccountId, u32, Balance)> = ink::scale::Decode::decode(&mut &chunk.data[..]).unwrap();
+            assert_eq!(
+                balances,
+                vec![(accounts.alice, NATIVE_ASSET, 900), (accounts.bob, NATIVE_ASSET, 900)]
+            );
+            assert_eq!(chunk.next_offset, None);
+        }
+        
+        #[ink::test]
+        fn rate_limit_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let account_limit = RateLimit { window: 1000, max_amount: 500, max_count: 0 };
+            let chain_limit = RateLimit { window: 1000, max_amount: 0, max_count: 3 };
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_account_rate_limit(Some(account_limit)), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.set_account_rate_limit(Some(account_limit)).unwrap();
+            xcm_handler.set_chain_rate_limit(2000, Some(chain_limit)).unwrap();
+            
+            for account in [accounts.alice, accounts.bob] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(account);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(2000);
+                xcm_handler.deposit();
             }
             
-            // Mark as executed
-            self.payment_executed.insert(payment_id, &true);
+            // Each sender may send 500 per window
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let pay = |xcm_handler: &mut XcmHandler, amount| {
+                xcm_handler.create_cross_chain_payment(
+                    accounts.django,
+                    NATIVE_ASSET,
+                    amount,
+                    2000,
+                    XcmMessageType::Payment,
+                )
+            };
+            pay(&mut xcm_handler, 300).unwrap();
+            assert_eq!(pay(&mut xcm_handler, 201), Err(Error::RateLimitExceeded));
+            pay(&mut xcm_handler, 200).unwrap();
+            assert_eq!(xcm_handler.get_account_rate_usage(accounts.alice).amount, 500);
             
-            // Add balance to recipient (on destination chain)
-            let recipient = self.payment_recipients.get(payment_id).unwrap();
-            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
-            let recipient_balance = self.balances.get(recipient).unwrap_or(0);
-            self.balances.insert(recipient, &recipient_balance.saturating_add(amount));
+            // The chain takes 3 payments per window across all senders
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            pay(&mut xcm_handler, 100).unwrap();
+            assert_eq!(pay(&mut xcm_handler, 100), Err(Error::RateLimitExceeded));
+            assert_eq!(xcm_handler.get_chain_rate_usage(2000).count, 3);
             
-            let sender = self.payment_senders.get(payment_id).unwrap();
+            // Both reset once the window elapsed
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            pay(&mut xcm_handler, 500).unwrap();
+            assert_eq!(xcm_handler.get_chain_rate_usage(2000).count, 1);
             
-            self.env().emit_event(CrossChainPaymentExecuted {
-                payment_id,
-                sender,
-                recipient,
-                amount,
-                executor: caller,
-            });
+            // Other assets cannot slip past the native amount caps
+            let usdt = AssetMetadata { decimals: 6, min_transfer_amount: 0, delivery_fee: None };
+            xcm_handler.register_asset_hub_asset(USDT_ASSET_ID, usdt).unwrap();
+            xcm_handler.credit(accounts.alice, USDT_ASSET_ID, 10_000);
+            let pay_usdt = |xcm_handler: &mut XcmHandler, amount| {
+                xcm_handler.create_cross_chain_payment(
+                    accounts.django,
+                    USDT_ASSET_ID,
+                    amount,
+                    ASSET_HUB_CHAIN_ID,
+                    XcmMessageType::Payment,
+                )
+            };
+            assert_eq!(pay_usdt(&mut xcm_handler, 1000), Err(Error::AssetRateLimitUnset));
+            assert_eq!(
+                xcm_handler.set_asset_rate_limit(NATIVE_ASSET, Some(account_limit)),
+                Err(Error::UnsupportedAsset)
+            );
             
-            Ok(())
+            // They are capped in their own units instead
+            let usdt_limit = RateLimit { window: 1000, max_amount: 5000, max_count: 0 };
+            xcm_handler.set_asset_rate_limit(USDT_ASSET_ID, Some(usdt_limit)).unwrap();
+            pay_usdt(&mut xcm_handler, 5000).unwrap();
+            assert_eq!(pay_usdt(&mut xcm_handler, 1), Err(Error::RateLimitExceeded));
+            assert_eq!(xcm_handler.get_asset_account_rate_usage(accounts.alice, USDT_ASSET_ID).amount, 5000);
+            assert_eq!(xcm_handler.get_asset_chain_rate_usage(ASSET_HUB_CHAIN_ID, USDT_ASSET_ID).amount, 5000);
         }
         
-        /// Configure supported chains
-        #[ink(message)]
-        pub fn configure_chain(
-            &mut self,
-            chain_id: u32,
-            supported: bool,
-            relayer: Option<AccountId>,
-        ) -> Result<(), Error> {
-            let caller = self.env().caller();
-            if caller != self.owner {
-                return Err(Error::UnauthorizedAccess);
+        #[ink::test]
+        fn two_phase_execution_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            xcm_handler.add_relayer(2000, accounts.django).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_ids = xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 100), (accounts.bob, 200), (accounts.bob, 300)], 2000)
+                .unwrap();
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(xcm_handler.confirm_execution(0, ExecutionProof::Attestation), Err(Error::NotClaimed));
+            for payment_id in payment_ids.iter() {
+                xcm_handler.claim_payment(*payment_id).unwrap();
             }
+            assert_eq!(xcm_handler.get_payment_status(0), Some(PaymentStatus::InTransit));
+            assert_eq!(xcm_handler.get_payment_claim(0).unwrap().relayer, accounts.charlie);
             
-            self.supported_chains.insert(chain_id, &supported);
+            // Claimed payments are neither claimed twice nor cancelled
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(xcm_handler.claim_payment(0), Err(Error::PaymentClaimed));
+            assert_eq!(
+                xcm_handler.confirm_execution(0, ExecutionProof::Attestation),
+                Err(Error::UnauthorizedAccess)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(xcm_handler.cancel_cross_chain_payment(0), Err(Error::PaymentClaimed));
+            
+            // The recipient is credited only on confirmation
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 0);
+            xcm_handler.confirm_execution(0, ExecutionProof::Attestation).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 100);
+            assert_eq!(xcm_handler.get_payment_claim(0), None);
+            
+            // Failures refund the sender
+            xcm_handler.report_failure(1, FailureReason::BeneficiaryInvalid).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(1), Some(PaymentStatus::Failed));
+            assert_eq!(xcm_handler.get_failure_reason(1), Some(FailureReason::BeneficiaryInvalid));
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 600);
+            
+            // Stale claims return to pending
+            assert_eq!(xcm_handler.release_claim(2), Err(Error::ClaimNotExpired));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(CLAIM_TIMEOUT);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            xcm_handler.release_claim(2).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(2), Some(PaymentStatus::Pending));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            xcm_handler.claim_payment(2).unwrap();
+        }
+        
+        #[ink::test]
+        fn query_response_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.set_relayer_fee(2000, 10, 0).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_ids = xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 100), (accounts.bob, 200)], 2000)
+                .unwrap();
             
-            if let Some(relayer_address) = relayer {
-                self.relayers.insert(chain_id, &relayer_address);
+            // Dispatch as the xcm-dispatch feature would
+            for payment_id in payment_ids.iter() {
+                xcm_handler.transition(*payment_id, PaymentStatus::InTransit).unwrap();
+                xcm_handler.register_query(*payment_id);
             }
+            assert_eq!(xcm_handler.get_payment_query(1), Some(1));
             
-            self.env().emit_event(ChainConfigured {
-                chain_id,
-                supported,
-                relayer,
-            });
+            xcm_handler.set_query_responder(Some(accounts.eve)).unwrap();
+            assert_eq!(xcm_handler.on_query_response(0, QueryOutcome::Complete), Err(Error::UnauthorizedAccess));
             
-            Ok(())
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            xcm_handler.on_query_response(0, QueryOutcome::Complete).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(0), Some(PaymentStatus::Executed));
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 100);
+            assert_eq!(xcm_handler.on_query_response(0, QueryOutcome::Complete), Err(Error::UnknownQuery));
+            
+            xcm_handler.on_query_response(1, QueryOutcome::Failed { instruction: 2 }).unwrap();
+            assert_eq!(xcm_handler.get_payment_status(1), Some(PaymentStatus::Failed));
+            assert_eq!(xcm_handler.get_payment_query(1), None);
+            // The relayer fee returns, and without xcm-dispatch the amount never left
+            let refunded = if cfg!(feature = "xcm-dispatch") { 0 } else { 200 };
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 690 + refunded);
+            assert_eq!(xcm_handler.get_pending_collateral(), 0);
         }
         
-        /// Deposit balance for cross-chain transfers
-        #[ink(message, payable)]
-        pub fn deposit(&mut self) {
-            let caller = self.env().caller();
-            let amount = self.env().transferred_value();
+        #[ink::test]
+        fn refund_payment_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            if amount > 0 {
-                let current_balance = self.balances.get(caller).unwrap_or(0);
-                self.balances.insert(caller, &current_balance.saturating_add(amount));
-                
-                self.env().emit_event(BalanceDeposited {
-                    account: caller,
-                    amount,
-                });
-            }
+            xcm_handler.add_relayer(1000, accounts.charlie).unwrap();
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 400, 2000, XcmMessageType::Refund),
+                Err(Error::InvalidMessageType)
+            );
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 400, 2000, XcmMessageType::Payment)
+                .unwrap();
+            
+            // Only executed payments can be returned, by their recipient
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.create_refund_payment(payment_id), Err(Error::PaymentNotExecuted));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.attest_execution(payment_id).unwrap();
+            assert_eq!(xcm_handler.create_refund_payment(payment_id), Err(Error::UnauthorizedAccess));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let refund_id = xcm_handler.create_refund_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.create_refund_payment(payment_id), Err(Error::PaymentRefunded));
+            assert_eq!(xcm_handler.get_payment_type(refund_id), Some(XcmMessageType::Refund));
+            assert_eq!(xcm_handler.get_payment_refund(payment_id), Some(refund_id));
+            assert_eq!(xcm_handler.get_refund_origin(refund_id), Some(payment_id));
+            let refund = xcm_handler.get_payment_info(refund_id).unwrap();
+            assert_eq!((refund.0, refund.1, refund.2, refund.4), (accounts.bob, accounts.alice, 400, 1000));
+            
+            // A cancelled refund can be retried
+            xcm_handler.cancel_cross_chain_payment(refund_id).unwrap();
+            assert_eq!(xcm_handler.get_payment_refund(payment_id), None);
+            let refund_id = xcm_handler.create_refund_payment(payment_id).unwrap();
+            
+            // Executing the refund credits the original sender
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.attest_execution(refund_id).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 1000);
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 0);
         }
         
-        /// Get payment details
-        #[ink(message)]
-        pub fn get_payment_info(&self, payment_id: u32) -> Option<(AccountId, AccountId, Balance, u32, u32, bool)> {
-            if !self.payment_senders.contains(payment_id) {
-                return None;
-            }
+        #[ink::test]
+        fn own_chain_id_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(xcm_handler.get_own_chain_id(), DEFAULT_CHAIN_ID);
+            assert_eq!(XcmHandler::new_on_chain(2004).get_own_chain_id(), 2004);
             
-            let sender = self.payment_senders.get(payment_id)?;
-            let recipient = self.payment_recipients.get(payment_id)?;
-            let amount = self.payment_amounts.get(payment_id).unwrap_or(0);
-            let source_chain = self.payment_source_chains.get(payment_id).unwrap_or(0);
-            let destination_chain = self.payment_destination_chains.get(payment_id).unwrap_or(0);
-            let executed = self.payment_executed.get(payment_id).unwrap_or(false);
+            assert_eq!(xcm_handler.set_own_chain_id(0), Err(Error::InvalidChain));
+            xcm_handler.set_own_chain_id(2004).unwrap();
             
-            Some((sender, recipient, amount, source_chain, destination_chain, executed))
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                .unwrap();
+            assert_eq!(xcm_handler.get_payment_info(payment_id).unwrap().3, 2004);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_own_chain_id(2000), Err(Error::UnauthorizedAccess));
         }
         
-        /// Get user balance
-        #[ink(message)]
-        pub fn get_balance(&self, account: AccountId) -> Balance {
-            self.balances.get(account).unwrap_or(0)
+        #[ink::test]
+        fn xcm_settings_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            let weight = XcmWeight { ref_time: 4_000_000_000, proof_size: 64 * 1024 };
+            assert_eq!(xcm_handler.set_chain_xcm_settings(2000, Some(weight), 2), Err(Error::UnsupportedXcmVersion));
+            xcm_handler.set_chain_xcm_settings(2000, Some(weight), 3).unwrap();
+            
+            let config = xcm_handler.get_chain_config(2000).unwrap();
+            assert_eq!(config.weight_limit, Some(weight));
+            assert_eq!(config.xcm_version, 3);
+            assert_eq!(
+                xcm_handler.set_chain_config(2000, ChainConfig { xcm_version: 5, ..config }),
+                Err(Error::UnsupportedXcmVersion)
+            );
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.set_chain_xcm_settings(2000, None, 4), Err(Error::UnauthorizedAccess));
         }
         
-        /// Check if chain is supported
-        #[ink(message)]
-        pub fn is_chain_supported(&self, chain_id: u32) -> bool {
-            self.supported_chains.get(chain_id).unwrap_or(false)
+        #[ink::test]
+        fn status_change_events_work() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                .unwrap();
+            let events = ink::env::test::recorded_events().count();
+            
+            // Claim, attestation and execution each report alongside the status change
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.claim_payment(payment_id).unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), events + 2);
+            xcm_handler.confirm_execution(payment_id, ExecutionProof::Attestation).unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), events + 5);
         }
         
-        /// Get pending payments count for a user
-        #[ink(message)]
-        pub fn get_pending_payments_count(&self, user: AccountId) -> u32 {
-            let mut count: u32 = 0;
-            
-            // In a real implementation, we'd have better indexing
-            for i in 0..self.payment_counter {
-                if let Some(sender) = self.payment_senders.get(i) {
-                    if let Some(recipient) = self.payment_recipients.get(i) {
-                        let executed = self.payment_executed.get(i).unwrap_or(false);
-                        if (sender == user || recipient == user) && !executed {
-                            count = count.saturating_add(1);
-                        }
-                    }
-                }
-            }
+        #[ink::test]
+        fn recover_trapped_assets_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = ink::env::account_id::<ink::env::DefaultEnvironment>();
+            let reason = Hash::from([7; 32]);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(3_000_000);
+            xcm_handler.deposit();
+            xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 3_000_000, 2000, XcmMessageType::Payment)
+                .unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 5_000_000);
+            
+            assert_eq!(
+                xcm_handler.recover_trapped_assets(accounts.django, 1_000_000, Hash::default()),
+                Err(Error::InvalidReason)
+            );
+            // Funds held for pending payments stay put
+            assert_eq!(
+                xcm_handler.recover_trapped_assets(accounts.django, 2_000_001, reason),
+                Err(Error::InsufficientBalance)
+            );
+            xcm_handler.recover_trapped_assets(accounts.django, 2_000_000, reason).unwrap();
+            assert_eq!(xcm_handler.get_total_recovered(), 2_000_000);
             
-            count
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                xcm_handler.recover_trapped_assets(accounts.bob, 1, reason),
+                Err(Error::UnauthorizedAccess)
+            );
         }
-    }
-    
-    /// Unit tests
-    #[cfg(test)]
-    mod tests {
-        use super::*;
         
         #[ink::test]
-        fn default_works() {
-            let xcm_handler = XcmHandler::default();
-            assert_eq!(xcm_handler.payment_counter, 0);
-            assert!(xcm_handler.is_chain_supported(1000)); // Rococo
-            assert!(xcm_handler.is_chain_supported(2000)); // Westend
+        fn recover_trapped_assets_leaves_user_funds() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = ink::env::account_id::<ink::env::DefaultEnvironment>();
+            let reason = Hash::from([7; 32]);
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1_000_000);
+            xcm_handler.deposit();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 1_000_000);
+            
+            // A funded deposit is owed to its owner, not trapped
+            assert_eq!(
+                xcm_handler.recover_trapped_assets(accounts.django, 1, reason),
+                Err(Error::InsufficientBalance)
+            );
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 1_500_000);
+            assert_eq!(
+                xcm_handler.recover_trapped_assets(accounts.django, 500_001, reason),
+                Err(Error::InsufficientBalance)
+            );
+            xcm_handler.recover_trapped_assets(accounts.django, 500_000, reason).unwrap();
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 1_000_000);
         }
         
         #[ink::test]
-        fn deposit_works() {
+        fn prune_payment_works() {
             let mut xcm_handler = XcmHandler::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Set transferred value
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
             ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment)
+                .unwrap();
+            assert_eq!(xcm_handler.prune_payment(payment_id), Err(Error::PaymentNotSettled));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.attest_execution(payment_id).unwrap();
+            assert_eq!(xcm_handler.prune_payment(payment_id), Err(Error::UnauthorizedAccess));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(xcm_handler.prune_payment(payment_id), Err(Error::GracePeriodActive));
+            
+            let info = xcm_handler.get_payment(payment_id).unwrap();
+            let mut expected = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Blake2x256, _>(&info, &mut expected);
+            
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(PRUNE_GRACE_PERIOD);
+            let archive_hash = xcm_handler.prune_payment(payment_id).unwrap();
+            assert_eq!(archive_hash, Hash::from(expected));
+            assert_eq!(xcm_handler.get_payment_archive(payment_id), Some(archive_hash));
+            assert_eq!(xcm_handler.get_payment(payment_id), None);
+            assert_eq!(xcm_handler.get_payment_status(payment_id), None);
+            assert_eq!(xcm_handler.prune_payment(payment_id), Err(Error::PaymentNotFound));
+            
+            // Pruning keeps the recipient's funds
+            assert_eq!(xcm_handler.get_balance(accounts.bob), 100);
+        }
+        
+        #[ink::test]
+        fn chain_stats_works() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
             xcm_handler.deposit();
+            xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 100), (accounts.bob, 200), (accounts.bob, 300)], 2000)
+                .unwrap();
+            xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 50, 3000, XcmMessageType::Payment)
+                .unwrap();
+            xcm_handler.cancel_cross_chain_payment(2).unwrap();
             
-            assert_eq!(xcm_handler.get_balance(accounts.alice), 1000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.claim_payment(1).unwrap();
+            xcm_handler.report_failure(1, FailureReason::Other).unwrap();
+            xcm_handler.attest_execution(0).unwrap();
+            
+            let stats = xcm_handler.get_chain_stats(2000);
+            assert_eq!(
+                stats,
+                ChainStats { created: 3, executed: 1, failed: 1, refunded: 0, cancelled: 1, volume: 600 }
+            );
+            assert_eq!(xcm_handler.get_chain_stats(3000).created, 1);
+            assert_eq!(xcm_handler.get_chain_stats(4000), ChainStats::default());
         }
         
         #[ink::test]
-        fn create_payment_works() {
+        fn relayer_rotation_works() {
             let mut xcm_handler = XcmHandler::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Deposit funds first
-            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            xcm_handler.add_relayer(2000, accounts.eve).unwrap();
+            xcm_handler.set_relayer_threshold(2000, 2).unwrap();
+            xcm_handler.set_relayer_manager(Some(accounts.frank)).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
             xcm_handler.deposit();
+            let payment_ids = xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 100), (accounts.bob, 200)], 2000)
+                .unwrap();
             
-            // Create cross-chain payment
-            let payment_id = xcm_handler.create_cross_chain_payment(
-                accounts.bob,
-                1000,
-                2000, // Westend
-                XcmMessageType::Payment,
-            ).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                xcm_handler.rotate_relayer(2000, accounts.charlie, accounts.django, Some(1000)),
+                Err(Error::UnauthorizedAccess)
+            );
             
-            assert_eq!(payment_id, 0);
+            // Both keys are accepted until the old one expires
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(
+                xcm_handler.rotate_relayer(2000, accounts.bob, accounts.django, None),
+                Err(Error::RelayerNotFound)
+            );
+            assert_eq!(
+                xcm_handler.rotate_relayer(2000, accounts.charlie, accounts.eve, Some(1000)),
+                Err(Error::RelayerExists)
+            );
+            xcm_handler.rotate_relayer(2000, accounts.charlie, accounts.django, Some(1000)).unwrap();
+            assert_eq!(xcm_handler.get_relayer_expiry(2000, accounts.charlie), Some(1000));
+            assert!(xcm_handler.is_relayer(2000, accounts.charlie));
+            assert!(xcm_handler.is_relayer(2000, accounts.django));
             
-            let payment = xcm_handler.get_payment_info(payment_id).unwrap();
-            assert_eq!(payment.0, accounts.alice); // sender
-            assert_eq!(payment.1, accounts.bob); // recipient
-            assert_eq!(payment.2, 1000); // amount
-            assert_eq!(payment.4, 2000); // destination_chain
-            assert!(!payment.5); // executed
+            // Old and new key belong to one operator and attest once
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(xcm_handler.set_relayer_threshold(2000, 3), Err(Error::InvalidThreshold));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.attest_execution(payment_ids[0]).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(xcm_handler.attest_execution(payment_ids[0]), Err(Error::AlreadyAttested));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            xcm_handler.attest_execution(payment_ids[0]).unwrap();
             
-            // Check balance was deducted
-            assert_eq!(xcm_handler.get_balance(accounts.alice), 4000);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert!(!xcm_handler.is_relayer(2000, accounts.charlie));
+            assert_eq!(xcm_handler.attest_execution(payment_ids[1]), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            xcm_handler.attest_execution(payment_ids[1]).unwrap();
+            
+            // Expired keys neither hold a relayer slot nor count toward the threshold
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            for seed in 0..(MAX_RELAYERS - 3) as u8 {
+                xcm_handler.add_relayer(2000, AccountId::from([seed + 10; 32])).unwrap();
+            }
+            xcm_handler.add_relayer(2000, AccountId::from([0xff; 32])).unwrap();
+            assert!(!xcm_handler.get_relayers(2000).contains(&accounts.charlie));
+            assert_eq!(
+                xcm_handler.add_relayer(2000, AccountId::from([0xfe; 32])),
+                Err(Error::TooManyRelayers)
+            );
+            for seed in 0..(MAX_RELAYERS - 3) as u8 {
+                xcm_handler.remove_relayer(2000, AccountId::from([seed + 10; 32])).unwrap();
+            }
+            xcm_handler.remove_relayer(2000, AccountId::from([0xff; 32])).unwrap();
+            assert_eq!(xcm_handler.remove_relayer(2000, accounts.eve), Err(Error::InvalidThreshold));
+            
+            // Rotating without an expiry drops the old key at once
+            let new_key = AccountId::from([0x07; 32]);
+            xcm_handler.rotate_relayer(2000, accounts.django, new_key, None).unwrap();
+            assert!(!xcm_handler.get_relayers(2000).contains(&accounts.django));
+            assert!(xcm_handler.is_relayer(2000, new_key));
         }
         
         #[ink::test]
-        fn execute_payment_works() {
+        fn pause_chain_works() {
             let mut xcm_handler = XcmHandler::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Configure relayer
-            xcm_handler.configure_chain(2000, true, Some(accounts.charlie)).unwrap();
-            
-            // Deposit funds
-            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.add_relayer(2000, accounts.charlie).unwrap();
+            xcm_handler.set_pauser(Some(accounts.frank)).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000);
             xcm_handler.deposit();
+            let payment_ids = xcm_handler
+                .create_cross_chain_batch(vec![(accounts.bob, 100), (accounts.bob, 200)], 2000)
+                .unwrap();
             
-            // Create payment
-            let payment_id = xcm_handler.create_cross_chain_payment(
-                accounts.bob,
-                1000,
-                2000,
-                XcmMessageType::Payment,
-            ).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.pause_chain(2000), Err(Error::UnauthorizedAccess));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_eq!(xcm_handler.pause_chain(4000), Err(Error::InvalidChain));
+            xcm_handler.pause_chain(2000).unwrap();
+            assert_eq!(xcm_handler.unpause_chain(2000), Err(Error::UnauthorizedAccess));
             
-            // Switch to relayer
+            // Neither creation nor execution goes through, other chains are unaffected
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                xcm_handler.create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 2000, XcmMessageType::Payment),
+                Err(Error::ChainPaused)
+            );
+            xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 100, 3000, XcmMessageType::Payment)
+                .unwrap();
             ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(xcm_handler.attest_execution(payment_ids[0]), Err(Error::ChainPaused));
+            assert_eq!(xcm_handler.claim_payment(payment_ids[0]), Err(Error::ChainPaused));
             
-            // Execute payment
-            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
-            
-            let payment = xcm_handler.get_payment_info(payment_id).unwrap();
-            assert!(payment.5); // executed
+            // Refunds still work
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.cancel_cross_chain_payment(payment_ids[1]).unwrap();
             
-            // Check recipient received funds
-            assert_eq!(xcm_handler.get_balance(accounts.bob), 1000);
+            xcm_handler.unpause_chain(2000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.attest_execution(payment_ids[0]).unwrap();
         }
         
         #[ink::test]
-        fn invalid_chain_fails() {
+        fn payment_summary_works() {
             let mut xcm_handler = XcmHandler::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             
-            // Deposit funds
             ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
             xcm_handler.deposit();
+            let payment_id = xcm_handler
+                .create_cross_chain_payment(accounts.bob, NATIVE_ASSET, 1000, 2000, XcmMessageType::BillSplitting)
+                .unwrap();
             
-            // Try to send to unsupported chain
-            let result = xcm_handler.create_cross_chain_payment(
-                accounts.bob,
-                1000,
-                9999, // Unsupported chain
-                XcmMessageType::Payment,
+            assert_eq!(
+                xcm_handler.payment_summary(payment_id),
+                Some(PaymentSummary {
+                    sender: accounts.alice,
+                    recipient: accounts.bob,
+                    amount: 1000,
+                    message_type: XcmMessageType::BillSplitting,
+                    executed: false,
+                    aborted: false,
+                    created_at: 0,
+                })
             );
+            assert_eq!(xcm_handler.payment_summary(payment_id + 1), None);
             
-            assert_eq!(result, Err(Error::InvalidChain));
+            xcm_handler.cancel_cross_chain_payment(payment_id).unwrap();
+            assert!(xcm_handler.payment_summary(payment_id).unwrap().aborted);
         }
         
         #[ink::test]
-        fn insufficient_balance_fails() {
+        fn send_payment_funds_itself() {
             let mut xcm_handler = XcmHandler::new();
             let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            xcm_handler.set_relayer_fee(2000, 10, 0).unwrap();
+            let fee = xcm_handler.quote_fee(2000, 1000);
+            assert!(fee >= 10);
             
-            // Try to send without depositing
-            let result = xcm_handler.create_cross_chain_payment(
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000 + fee);
+            let payment_id = xcm_handler.send_payment(accounts.bob, 1000, 2000).unwrap();
+            assert_eq!(xcm_handler.payment_summary(payment_id).unwrap().recipient, accounts.bob);
+            assert_eq!(xcm_handler.get_balance(accounts.alice), 0);
+            
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1000 + fee - 1);
+            assert_eq!(
+                xcm_handler.send_payment(accounts.bob, 1000, 2000),
+                Err(xcc_common::Error::InsufficientBalance)
+            );
+        }
+        
+        #[ink::test]
+        fn upgrade_and_migration_are_owner_only() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            assert_eq!(xcm_handler.get_storage_version(), STORAGE_VERSION);
+            
+            // Freshly deployed storage is already current
+            assert_eq!(xcm_handler.migrate_from(STORAGE_VERSION - 1), Err(Error::StorageVersionMismatch));
+            assert_eq!(xcm_handler.migrate_from(STORAGE_VERSION), Err(Error::AlreadyMigrated));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(xcm_handler.upgrade(Hash::from([1; 32])), Err(Error::UnauthorizedAccess));
+            assert_eq!(xcm_handler.migrate_from(STORAGE_VERSION), Err(Error::UnauthorizedAccess));
+            assert_eq!(xcm_handler.transfer_ownership(accounts.bob), Err(Error::UnauthorizedAccess));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            xcm_handler.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(xcm_handler.get_owner(), accounts.bob);
+        }
+        
+        #[ink::test]
+        fn referral_rewards_work() {
+            let mut xcm_handler = XcmHandler::new();
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            
+            xcm_handler.configure_chain(2000, true, Some(accounts.charlie)).unwrap();
+            xcm_handler.set_delivery_fee(2000, DeliveryFee { base: 100, bps: 0, max: 0 }).unwrap();
+            
+            assert_eq!(xcm_handler.set_referrer(accounts.alice), Err(Error::SelfReferral));
+            xcm_handler.set_referrer(accounts.eve).unwrap();
+            assert_eq!(xcm_handler.set_referrer(accounts.frank), Err(Error::ReferrerAlreadySet));
+            
+            // Referrers cannot in turn be referred by their referees
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(xcm_handler.set_referrer(accounts.alice), Err(Error::SelfReferral));
+            assert_eq!(xcm_handler.set_referral_share(0), Err(Error::UnauthorizedAccess));
+            
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5000);
+            xcm_handler.deposit();
+            let payment_id = xcm_handler.create_cross_chain_payment(
                 accounts.bob,
+                NATIVE_ASSET,
                 1000,
                 2000,
                 XcmMessageType::Payment,
+            ).unwrap();
+            assert_eq!(xcm_handler.get_referral_rewards(accounts.eve), 0);
+            
+            // The referrer's share of the delivery fee accrues once the payment executes
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            xcm_handler.execute_cross_chain_payment(payment_id).unwrap();
+            assert_eq!(xcm_handler.get_referral_rewards(accounts.eve), 10);
+            assert_eq!(xcm_handler.get_fee_pot(), 90);
+            assert_eq!(
+                xcm_handler.get_referral_stats(accounts.eve),
+                ReferralStats { referees: 1, fees_generated: 100, rewards_earned: 10 }
             );
             
-            assert_eq!(result, Err(Error::InsufficientBalance));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(xcm_handler.claim_referral_rewards(), Ok(10));
+            assert_eq!(xcm_handler.claim_referral_rewards(), Err(Error::InvalidAmount));
+        }
+    }
+
+    /// Property tests of payment accounting over random sequences of calls
+    ///
+    /// Dispatched payments leave the contract, so these cover the relayed flow only.
+    #[cfg(all(test, feature = "std", not(feature = "xcm-dispatch")))]
+    mod invariants {
+        use super::*;
+        use ink::env::{test, DefaultEnvironment};
+        use proptest::prelude::*;
+
+        /// Native balance of every actor at the start
+        const FUNDS: Balance = 10_000_000;
+        /// Existential deposit of the off-chain environment, which the contract keeps
+        const EXISTENTIAL_DEPOSIT: Balance = 1_000_000;
+        const DESTINATION: u32 = 2000;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Deposit { account: usize, amount: Balance },
+            Withdraw { account: usize, amount: Balance },
+            Create { sender: usize, recipient: usize, amount: Balance },
+            Execute { caller: usize, payment: u32 },
+            Claim { caller: usize, payment: u32 },
+            Confirm { caller: usize, payment: u32 },
+            Cancel { caller: usize, payment: u32 },
+            ClaimRewards { caller: usize },
+        }
+
+        /// Alice owns the contract, Charlie and Django relay to the destination
+        fn actors() -> [AccountId; 5] {
+            let accounts = test::default_accounts::<DefaultEnvironment>();
+            [accounts.alice, accounts.bob, accounts.charlie, accounts.django, accounts.eve]
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            let actor = || 0..5usize;
+            let payment = || 0..8u32;
+            let amount = || 1..5_000u128;
+            prop_oneof![
+                2 => (actor(), amount()).prop_map(|(account, amount)| Op::Deposit { account, amount }),
+                1 => (actor(), amount()).prop_map(|(account, amount)| Op::Withdraw { account, amount }),
+                3 => (actor(), actor(), amount())
+                    .prop_map(|(sender, recipient, amount)| Op::Create { sender, recipient, amount }),
+                2 => (actor(), payment()).prop_map(|(caller, payment)| Op::Execute { caller, payment }),
+                2 => (actor(), payment()).prop_map(|(caller, payment)| Op::Claim { caller, payment }),
+                2 => (actor(), payment()).prop_map(|(caller, payment)| Op::Confirm { caller, payment }),
+                1 => (actor(), payment()).prop_map(|(caller, payment)| Op::Cancel { caller, payment }),
+                1 => actor().prop_map(|caller| Op::ClaimRewards { caller }),
+            ]
+        }
+
+        fn balance(account: AccountId) -> Balance {
+            test::get_account_balance::<DefaultEnvironment>(account).unwrap_or(0)
+        }
+
+        /// Call `deposit`, moving `value` into the contract as the chain would
+        fn deposit(xcm_handler: &mut XcmHandler, caller: AccountId, value: Balance) {
+            let contract = test::callee::<DefaultEnvironment>();
+            test::set_account_balance::<DefaultEnvironment>(caller, balance(caller) - value);
+            test::set_account_balance::<DefaultEnvironment>(contract, balance(contract) + value);
+            test::set_caller::<DefaultEnvironment>(caller);
+            test::set_value_transferred::<DefaultEnvironment>(value);
+            xcm_handler.deposit();
+            test::set_value_transferred::<DefaultEnvironment>(0);
+        }
+
+        /// Number of `CrossChainPaymentExecuted` events emitted for each payment
+        fn executions(payment_count: u32) -> Vec<usize> {
+            let topic = <CrossChainPaymentExecuted as ink::env::Event>::SIGNATURE_TOPIC.unwrap();
+            let mut executions = vec![0; payment_count as usize];
+            for event in test::recorded_events() {
+                if event.topics.first().map(Vec::as_slice) == Some(&topic[..]) {
+                    let payment_id = <u32 as ink::scale::Decode>::decode(&mut &event.data[..]).unwrap();
+                    executions[payment_id as usize] += 1;
+                }
+            }
+            executions
+        }
+
+        fn check_invariants(xcm_handler: &XcmHandler, actors: &[AccountId]) {
+            let mut collateral: Balance = 0;
+            for (payment_id, executed) in (0..xcm_handler.payment_counter).zip(executions(xcm_handler.payment_counter)) {
+                let status = xcm_handler.get_payment_status(payment_id).unwrap();
+                assert!(executed <= 1, "payment {payment_id} executed {executed} times");
+                assert_eq!(executed == 1, status == PaymentStatus::Executed, "payment {payment_id} is {status:?}");
+                if matches!(status, PaymentStatus::Pending | PaymentStatus::InTransit) {
+                    collateral += xcm_handler.held_collateral(payment_id);
+                }
+            }
+            assert_eq!(xcm_handler.pending_collateral, collateral, "collateral of open payments miscounted");
+
+            // The contract holds exactly the balances, open payments, fees and rewards it owes
+            let owed: Balance = actors
+                .iter()
+                .map(|account| xcm_handler.get_balance(*account) + xcm_handler.get_relayer_rewards(*account))
+                .sum();
+            let contract = test::callee::<DefaultEnvironment>();
+            assert_eq!(
+                balance(contract),
+                EXISTENTIAL_DEPOSIT + owed + xcm_handler.pending_collateral + xcm_handler.fee_pot,
+                "escrow not fully backed"
+            );
+
+            // Native funds only move between the actors and the contract
+            let total: Balance = actors.iter().map(|account| balance(*account)).sum();
+            assert_eq!(total + balance(contract), FUNDS * actors.len() as Balance + EXISTENTIAL_DEPOSIT);
+        }
+
+        proptest! {
+            /// No payment executes twice, and deposits back every balance, open
+            /// payment and relayer reward
+            #[test]
+            fn escrow_is_fully_backed(ops in prop::collection::vec(op(), 1..60)) {
+                test::run_test::<DefaultEnvironment, _>(|_| {
+                    let actors = actors();
+                    for account in actors {
+                        test::set_account_balance::<DefaultEnvironment>(account, FUNDS);
+                    }
+                    let contract = AccountId::from([0xc0; 32]);
+                    test::set_callee::<DefaultEnvironment>(contract);
+                    test::set_account_balance::<DefaultEnvironment>(contract, EXISTENTIAL_DEPOSIT);
+
+                    test::set_caller::<DefaultEnvironment>(actors[0]);
+                    let mut xcm_handler = XcmHandler::new();
+                    xcm_handler.configure_chain(DESTINATION, true, Some(actors[2])).unwrap();
+                    xcm_handler.configure_chain(DESTINATION, true, Some(actors[3])).unwrap();
+                    xcm_handler.set_relayer_fee(DESTINATION, 3, 100).unwrap();
+
+                    for op in ops {
+                        // Payments are picked among the existing ones
+                        let payment_id = |payment: u32| payment % xcm_handler.payment_counter.max(1);
+                        match op {
+                            Op::Deposit { account, amount } => deposit(&mut xcm_handler, actors[account], amount),
+                            Op::Withdraw { account, amount } => {
+                                test::set_caller::<DefaultEnvironment>(actors[account]);
+                                let _ = xcm_handler.withdraw(amount);
+                            }
+                            Op::Create { sender, recipient, amount } => {
+                                test::set_caller::<DefaultEnvironment>(actors[sender]);
+                                let _ = xcm_handler.create_cross_chain_payment(
+                                    actors[recipient],
+                                    NATIVE_ASSET,
+                                    amount,
+                                    DESTINATION,
+                                    XcmMessageType::Payment,
+                                );
+                            }
+                            Op::Execute { caller, payment } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = xcm_handler.execute_cross_chain_payment(payment_id(payment));
+                            }
+                            Op::Claim { caller, payment } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = xcm_handler.claim_payment(payment_id(payment));
+                            }
+                            Op::Confirm { caller, payment } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = xcm_handler.confirm_execution(payment_id(payment), ExecutionProof::Attestation);
+                            }
+                            Op::Cancel { caller, payment } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = xcm_handler.cancel_cross_chain_payment(payment_id(payment));
+                            }
+                            Op::ClaimRewards { caller } => {
+                                test::set_caller::<DefaultEnvironment>(actors[caller]);
+                                let _ = xcm_handler.claim_relayer_rewards();
+                            }
+                        }
+
+                        check_invariants(&xcm_handler, &actors);
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            }
         }
     }
 }
\ No newline at end of file
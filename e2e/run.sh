@@ -0,0 +1,22 @@
+#!/bin/bash
+# Build the contracts, spawn the zombienet network and run the e2e tests against it
+#
+# Exits with the tests' status, so it can run as a CI step. Needs
+# `cargo contract`, `zombienet`, `polkadot` and `polkadot-parachain`.
+
+set -euo pipefail
+
+cd "$(dirname "$0")"
+
+for contract in payment xcm_handler; do
+    cargo contract build --release --manifest-path "../contracts/$contract/Cargo.toml"
+done
+
+zombienet spawn --provider native zombienet/network.toml > zombienet.log 2>&1 &
+NETWORK=$!
+trap 'kill $NETWORK 2>/dev/null || true' EXIT
+
+# The tests wait for both parachains to produce blocks
+export XCC_E2E_CHAIN_A="${XCC_E2E_CHAIN_A:-ws://127.0.0.1:9910}"
+export XCC_E2E_CHAIN_B="${XCC_E2E_CHAIN_B:-ws://127.0.0.1:9920}"
+cargo test --release --features e2e-tests -- --nocapture
@@ -0,0 +1,136 @@
+//! Helpers for the cross-chain end-to-end tests
+//!
+//! The tests run against the two parachains of `zombienet/network.toml`,
+//! found at `XCC_E2E_CHAIN_A` and `XCC_E2E_CHAIN_B`, and deploy the
+//! contracts built by `cargo contract build --release`.
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use codec::{Decode, Encode};
+use ink::selector_bytes;
+use subxt::{dynamic::Value, utils::AccountId32, OnlineClient, PolkadotConfig};
+use subxt_signer::sr25519::Keypair;
+use xcc_sdk::Client;
+
+/// Para ID of the first parachain
+pub const CHAIN_A: u32 = 2000;
+/// Para ID of the second parachain
+pub const CHAIN_B: u32 = 2001;
+
+/// Gas limit of instantiations, well above what the contracts' constructors need
+const INSTANTIATE_REF_TIME: u64 = 50_000_000_000;
+const INSTANTIATE_PROOF_SIZE: u64 = 1024 * 1024;
+
+/// How long to wait for a parachain to produce its first blocks
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// WebSocket endpoint of a parachain of the network
+pub fn endpoint(para_id: u32) -> String {
+    let (var, default) = match para_id {
+        CHAIN_A => ("XCC_E2E_CHAIN_A", "ws://127.0.0.1:9910"),
+        CHAIN_B => ("XCC_E2E_CHAIN_B", "ws://127.0.0.1:9920"),
+        _ => panic!("parachain {para_id} is not part of the network"),
+    };
+    std::env::var(var).unwrap_or_else(|_| default.into())
+}
+
+/// Connect to a parachain once it produces blocks
+///
+/// Collators only start authoring after the parachain is onboarded on the
+/// relay chain, which takes a few relay chain sessions.
+pub async fn connect(para_id: u32) -> anyhow::Result<OnlineClient<PolkadotConfig>> {
+    let url = endpoint(para_id);
+    let started = tokio::time::Instant::now();
+    loop {
+        if let Ok(api) = OnlineClient::<PolkadotConfig>::from_url(&url).await {
+            if api.blocks().at_latest().await?.number() > 0 {
+                return Ok(api);
+            }
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            bail!("parachain {para_id} at {url} produced no blocks");
+        }
+        tokio::time::sleep(Duration::from_secs(6)).await;
+    }
+}
+
+/// The Wasm blob of a contract built with `cargo contract build`
+///
+/// Looked up in `XCC_E2E_ARTIFACTS` when set, else in the contract's target directory.
+pub fn artifact(contract: &str) -> anyhow::Result<Vec<u8>> {
+    let path = match std::env::var_os("XCC_E2E_ARTIFACTS") {
+        Some(dir) => PathBuf::from(dir).join(format!("{contract}.wasm")),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../contracts")
+            .join(contract)
+            .join(format!("target/ink/{contract}.wasm")),
+    };
+    std::fs::read(&path).with_context(|| format!("reading {}, build the contracts first", path.display()))
+}
+
+/// Upload and instantiate a contract, returning its address
+///
+/// `selector` is the constructor's selector and `args` its arguments.
+pub async fn deploy(
+    api: &OnlineClient<PolkadotConfig>,
+    signer: &Keypair,
+    contract: &str,
+    selector: [u8; 4],
+    args: impl Encode,
+) -> anyhow::Result<AccountId32> {
+    let mut data = selector.to_vec();
+    args.encode_to(&mut data);
+    // Contracts of every test run get fresh addresses
+    let salt = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_nanos().encode();
+
+    let instantiate = subxt::dynamic::tx(
+        "Contracts",
+        "instantiate_with_code",
+        vec![
+            Value::u128(0),
+            Value::named_composite([
+                ("ref_time", Value::u128(INSTANTIATE_REF_TIME.into())),
+                ("proof_size", Value::u128(INSTANTIATE_PROOF_SIZE.into())),
+            ]),
+            Value::unnamed_variant("None", []),
+            Value::from_bytes(artifact(contract)?),
+            Value::from_bytes(data),
+            Value::from_bytes(salt),
+        ],
+    );
+    let events = api
+        .tx()
+        .sign_and_submit_then_watch_default(&instantiate, signer)
+        .await?
+        .wait_for_finalized_success()
+        .await?;
+
+    for event in events.iter() {
+        let event = event?;
+        if event.pallet_name() == "Contracts" && event.variant_name() == "Instantiated" {
+            let (_deployer, address) = <(AccountId32, AccountId32)>::decode(&mut event.field_bytes())?;
+            return Ok(address);
+        }
+    }
+    Err(anyhow!("instantiating {contract} emitted no Contracts::Instantiated event"))
+}
+
+/// A parachain with the Payment and XcmHandler contracts deployed on it
+pub struct Parachain {
+    pub para_id: u32,
+    pub client: Client,
+}
+
+impl Parachain {
+    /// Deploy fresh contracts on a parachain, owned by `owner`
+    ///
+    /// The Payment ledger starts with `initial_supply` held by `owner`.
+    pub async fn deploy(para_id: u32, owner: &Keypair, initial_supply: u128) -> anyhow::Result<Self> {
+        let api = connect(para_id).await?;
+        let payment = deploy(&api, owner, "payment", selector_bytes!("new"), initial_supply).await?;
+        let xcm_handler = deploy(&api, owner, "xcm_handler", selector_bytes!("new_on_chain"), para_id).await?;
+        let client = Client::from_api(api).with_payment(payment).with_xcm_handler(xcm_handler);
+        Ok(Self { para_id, client })
+    }
+}
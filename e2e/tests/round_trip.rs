@@ -0,0 +1,158 @@
+//! A cross-chain payment from parachain A to parachain B and back to A's ledger
+//!
+//! Alice owns the contracts on both chains, Charlie relays between them and
+//! Bob receives the payment on B.
+
+#![cfg(feature = "e2e-tests")]
+
+use subxt::utils::AccountId32;
+use subxt_signer::sr25519::{dev, Keypair};
+use xcc_e2e::{Parachain, CHAIN_A, CHAIN_B};
+use xcc_sdk::{
+    events::{CrossChainPaymentCreated, CrossChainPaymentExecuted, CrossChainPaymentInitiated, InboundPaymentReceived},
+    types::{ExecutionProof, PaymentStatus, XcmMessageType},
+};
+
+const DEPOSIT: u128 = 10_000_000_000_000;
+const AMOUNT: u128 = 1_000_000_000_000;
+
+fn account(keypair: &Keypair) -> AccountId32 {
+    keypair.public_key().into()
+}
+
+fn ink_account(keypair: &Keypair) -> ink::primitives::AccountId {
+    keypair.public_key().0.into()
+}
+
+/// Deploy the contracts on both parachains, each trusting Charlie to relay from the other
+async fn network() -> anyhow::Result<(Parachain, Parachain)> {
+    let (alice, charlie) = (dev::alice(), dev::charlie());
+    let a = Parachain::deploy(CHAIN_A, &alice, DEPOSIT).await?;
+    let b = Parachain::deploy(CHAIN_B, &alice, DEPOSIT).await?;
+    for (chain, other) in [(&a, CHAIN_B), (&b, CHAIN_A)] {
+        chain
+            .client
+            .xcm_handler()
+            .configure_chain(other, true, Some(account(&charlie)))
+            .signed(&alice)
+            .submit()
+            .await?;
+    }
+    Ok((a, b))
+}
+
+#[tokio::test]
+async fn payment_round_trip() -> anyhow::Result<()> {
+    let (alice, bob, charlie) = (dev::alice(), dev::bob(), dev::charlie());
+    let (a, b) = network().await?;
+    let (source, destination) = (a.client.xcm_handler(), b.client.xcm_handler());
+
+    // Create on A
+    source.deposit(DEPOSIT).signed(&alice).submit().await?;
+    let created = source
+        .create_payment(account(&bob), 0, AMOUNT, CHAIN_B, XcmMessageType::Payment)
+        .signed(&alice)
+        .submit()
+        .await?;
+    let payment_id = created.output;
+    let [event] = &created.events_of::<CrossChainPaymentCreated>()[..] else {
+        panic!("expected one CrossChainPaymentCreated event");
+    };
+    assert_eq!((event.payment_id, event.amount), (payment_id, AMOUNT));
+    assert_eq!((event.source_chain, event.destination_chain), (CHAIN_A, CHAIN_B));
+    assert_eq!(source.get_balance(account(&alice)).fetch().await?, DEPOSIT - AMOUNT);
+
+    // Relay: claim on A, credit on B
+    source.claim_payment(payment_id).signed(&charlie).submit().await?;
+    assert_eq!(source.get_payment_status(payment_id).fetch().await?, Some(PaymentStatus::InTransit));
+
+    let nonce = u64::from(payment_id);
+    let message_hash = destination
+        .inbound_message_hash(CHAIN_A, account(&alice), account(&bob), 0, AMOUNT, nonce)
+        .fetch()
+        .await?;
+    let received = destination
+        .receive_payment(
+            CHAIN_A,
+            message_hash,
+            account(&alice),
+            account(&bob),
+            0,
+            AMOUNT,
+            nonce,
+            ExecutionProof::Attestation,
+        )
+        .signed(&charlie)
+        .submit()
+        .await?;
+    assert_eq!(
+        received.events_of::<InboundPaymentReceived>(),
+        vec![InboundPaymentReceived {
+            source_chain: CHAIN_A,
+            message_hash,
+            sender: ink_account(&alice),
+            recipient: ink_account(&bob),
+            asset_id: 0,
+            amount: AMOUNT,
+            nonce,
+            relayer: ink_account(&charlie),
+        }]
+    );
+    assert_eq!(destination.get_balance(account(&bob)).fetch().await?, AMOUNT);
+
+    // Replaying the message credits nothing
+    let replay = destination
+        .receive_payment(
+            CHAIN_A,
+            message_hash,
+            account(&alice),
+            account(&bob),
+            0,
+            AMOUNT,
+            nonce,
+            ExecutionProof::Attestation,
+        )
+        .signed(&charlie)
+        .dry_run()
+        .await;
+    assert!(replay.is_err());
+
+    // Confirm on A
+    let confirmed = source
+        .confirm_execution(payment_id, ExecutionProof::Attestation)
+        .signed(&charlie)
+        .submit()
+        .await?;
+    let [executed] = &confirmed.events_of::<CrossChainPaymentExecuted>()[..] else {
+        panic!("expected one CrossChainPaymentExecuted event");
+    };
+    assert_eq!((executed.payment_id, executed.amount), (payment_id, AMOUNT));
+    assert_eq!(executed.executor, ink_account(&charlie));
+    assert_eq!(source.get_payment_status(payment_id).fetch().await?, Some(PaymentStatus::Executed));
+    assert_eq!(source.get_balance(account(&alice)).fetch().await?, DEPOSIT - AMOUNT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ledger_locks_cross_chain_transfers() -> anyhow::Result<()> {
+    let (alice, bob) = (dev::alice(), dev::bob());
+    let (a, _b) = network().await?;
+    let ledger = a.client.payment();
+
+    let initiated = ledger.initiate_cross_chain(account(&bob), AMOUNT, CHAIN_B).signed(&alice).submit().await?;
+    assert_eq!(
+        initiated.events_of::<CrossChainPaymentInitiated>(),
+        vec![CrossChainPaymentInitiated {
+            transaction_id: initiated.output,
+            from: ink_account(&alice),
+            to: ink_account(&bob),
+            amount: AMOUNT,
+            destination_chain: CHAIN_B,
+        }]
+    );
+    assert_eq!(ledger.balance_of(account(&alice)).fetch().await?, DEPOSIT - AMOUNT);
+    assert_eq!(ledger.balance_of(account(&bob)).fetch().await?, 0);
+
+    Ok(())
+}
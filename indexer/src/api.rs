@@ -0,0 +1,117 @@
+use std::{
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use subxt::utils::AccountId32;
+
+use crate::store::Store;
+
+/// Largest page of history returned at once
+const MAX_LIMIT: u32 = 500;
+
+type Shared = Arc<Mutex<Store>>;
+
+/// Failure of a query, answered as `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(error: rusqlite::Error) -> Self {
+        tracing::error!("query failed: {error}");
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, "query failed".into())
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+fn store(shared: &Shared) -> std::sync::MutexGuard<'_, Store> {
+    shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Normalize an address to the SS58 form the store uses
+fn account(address: &str) -> Result<String, ApiError> {
+    AccountId32::from_str(address)
+        .map(|account| account.to_string())
+        .map_err(|_| ApiError(StatusCode::BAD_REQUEST, format!("invalid address: {address}")))
+}
+
+fn found<T>(record: Option<T>, what: &str) -> ApiResult<T> {
+    record
+        .map(Json)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("{what} not found")))
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    /// Only entries of blocks before this one, for paging
+    before: Option<u32>,
+    limit: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct PendingParams {
+    sender: Option<String>,
+}
+
+async fn status(State(shared): State<Shared>) -> ApiResult<crate::store::Status> {
+    Ok(Json(store(&shared).status()?))
+}
+
+async fn history(
+    State(shared): State<Shared>,
+    Path(address): Path<String>,
+    Query(params): Query<HistoryParams>,
+) -> ApiResult<Vec<crate::store::ActivityRecord>> {
+    let account = account(&address)?;
+    let limit = params.limit.unwrap_or(100).min(MAX_LIMIT);
+    Ok(Json(store(&shared).history(&account, params.before, limit)?))
+}
+
+async fn bill(State(shared): State<Shared>, Path(bill_id): Path<u32>) -> ApiResult<crate::store::BillRecord> {
+    found(store(&shared).bill(bill_id)?, "bill")
+}
+
+async fn payment(State(shared): State<Shared>, Path(payment_id): Path<u32>) -> ApiResult<crate::store::XcmPaymentRecord> {
+    found(store(&shared).xcm_payment(payment_id)?, "payment")
+}
+
+async fn pending(
+    State(shared): State<Shared>,
+    Query(params): Query<PendingParams>,
+) -> ApiResult<Vec<crate::store::XcmPaymentRecord>> {
+    let sender = params.sender.as_deref().map(account).transpose()?;
+    Ok(Json(store(&shared).pending_xcm_payments(sender.as_deref())?))
+}
+
+fn router(shared: Shared) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/accounts/:address/history", get(history))
+        .route("/bills/:bill_id", get(bill))
+        .route("/xcm/payments/pending", get(pending))
+        .route("/xcm/payments/:payment_id", get(payment))
+        .with_state(shared)
+}
+
+/// Serve the query API on `addr`
+pub async fn serve(addr: SocketAddr, shared: Shared) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("serving queries on {addr}");
+    axum::serve(listener, router(shared)).await?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::{net::SocketAddr, path::{Path, PathBuf}};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use subxt::utils::AccountId32;
+
+use crate::decode::Role;
+
+/// Indexer configuration file
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// WebSocket RPC endpoint
+    pub endpoint: String,
+    /// SQLite database, created if missing
+    pub database: PathBuf,
+    /// Address to serve the query API on
+    pub listen: SocketAddr,
+    /// Block to index from on an empty database, the current best block by default
+    pub start_block: Option<u32>,
+    pub contracts: Contracts,
+}
+
+/// SS58 addresses of the indexed contracts
+#[derive(Debug, Clone, Deserialize)]
+pub struct Contracts {
+    pub payment: Option<AccountId32>,
+    pub bill_splitting: Option<AccountId32>,
+    pub xcm_handler: Option<AccountId32>,
+}
+
+impl Contracts {
+    /// Which contract emitted an event, if it is indexed
+    pub fn role(&self, contract: &AccountId32) -> Option<Role> {
+        [
+            (&self.payment, Role::Payment),
+            (&self.bill_splitting, Role::BillSplitting),
+            (&self.xcm_handler, Role::XcmHandler),
+        ]
+        .into_iter()
+        .find(|(address, _)| address.as_ref() == Some(contract))
+        .map(|(_, role)| role)
+    }
+}
+
+impl Config {
+    /// Read and check a configuration file
+    ///
+    /// A relative database path is resolved against the file's directory.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut config: Config = toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+
+        if config.database.is_relative() {
+            config.database = path.parent().unwrap_or(Path::new(".")).join(&config.database);
+        }
+
+        let contracts = &config.contracts;
+        if contracts.payment.is_none() && contracts.bill_splitting.is_none() && contracts.xcm_handler.is_none() {
+            bail!("no contracts configured");
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_parses() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("config.example.toml");
+        let config = Config::load(&path).unwrap();
+
+        assert_eq!(config.start_block, None);
+        assert!(config.database.starts_with(env!("CARGO_MANIFEST_DIR")));
+        let xcm_handler = config.contracts.xcm_handler.clone().unwrap();
+        assert_eq!(config.contracts.role(&xcm_handler), Some(Role::XcmHandler));
+        assert_eq!(config.contracts.role(&AccountId32([0; 32])), None);
+    }
+}
@@ -0,0 +1,266 @@
+use subxt::utils::{AccountId32, H256};
+use xcc_sdk::{
+    events::*,
+    types::{AccountId, Beneficiary},
+    EmittedEvent,
+};
+
+/// The contract an event was emitted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Payment,
+    BillSplitting,
+    XcmHandler,
+}
+
+/// A block ready to be stored
+#[derive(Debug)]
+pub struct IndexedBlock {
+    pub number: u32,
+    pub hash: H256,
+    pub parent_hash: H256,
+    pub events: Vec<IndexedEvent>,
+}
+
+/// A contract event, with the rows derived from it
+#[derive(Debug)]
+pub struct IndexedEvent {
+    /// Index of the event among all events of the block
+    pub index: u32,
+    pub emitted: EmittedEvent,
+    /// Name of the event, if known to the indexer
+    pub kind: Option<&'static str>,
+    pub rows: Vec<Row>,
+}
+
+/// A row of the query tables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row {
+    /// An entry of an account's history
+    Activity {
+        account: String,
+        kind: &'static str,
+        counterparty: Option<String>,
+        amount: u128,
+        /// Transaction, bill or payment ID
+        reference: u32,
+    },
+    BillCreated {
+        bill_id: u32,
+        creator: String,
+        total_amount: u128,
+        participant_count: u32,
+    },
+    BillPaid {
+        bill_id: u32,
+        payer: String,
+        amount: u128,
+    },
+    /// A bill was completed or cancelled
+    BillClosed {
+        bill_id: u32,
+        outcome: &'static str,
+    },
+    XcmPaymentCreated {
+        payment_id: u32,
+        sender: String,
+        recipient: String,
+        beneficiary: String,
+        asset_id: u32,
+        amount: u128,
+        source_chain: u32,
+        destination_chain: u32,
+        created_at: u64,
+    },
+    XcmStatus {
+        payment_id: u32,
+        status: String,
+    },
+}
+
+pub fn account(id: &AccountId) -> String {
+    AccountId32(*AsRef::<[u8; 32]>::as_ref(id)).to_string()
+}
+
+fn beneficiary(beneficiary: &Beneficiary) -> String {
+    match beneficiary {
+        Beneficiary::AccountId32(id) => account(id),
+        Beneficiary::AccountKey20(key) => format!("0x{}", hex::encode(key)),
+    }
+}
+
+fn activity(account_id: &AccountId, kind: &'static str, counterparty: Option<&AccountId>, amount: u128, reference: u32) -> Row {
+    Row::Activity {
+        account: account(account_id),
+        kind,
+        counterparty: counterparty.map(account),
+        amount,
+        reference,
+    }
+}
+
+/// Name the event and derive its rows, if it is one the indexer knows
+///
+/// Only events of the emitting contract's own types are decoded, so another
+/// contract emitting a lookalike event cannot forge history.
+pub fn decode(role: Role, event: &EmittedEvent) -> Option<(&'static str, Vec<Row>)> {
+    match role {
+        Role::Payment => decode_payment(event),
+        Role::BillSplitting => decode_bill(event),
+        Role::XcmHandler => decode_xcm(event),
+    }
+}
+
+fn decode_payment(event: &EmittedEvent) -> Option<(&'static str, Vec<Row>)> {
+    let sent = event.decode::<PaymentSent>()?;
+    Some((
+        "PaymentSent",
+        vec![
+            activity(&sent.from, "payment_sent", Some(&sent.to), sent.amount, sent.transaction_id),
+            activity(&sent.to, "payment_received", Some(&sent.from), sent.amount, sent.transaction_id),
+        ],
+    ))
+}
+
+fn decode_bill(event: &EmittedEvent) -> Option<(&'static str, Vec<Row>)> {
+    if let Some(created) = event.decode::<BillCreated>() {
+        return Some((
+            "BillCreated",
+            vec![
+                activity(&created.creator, "bill_created", None, created.total_amount, created.bill_id),
+                Row::BillCreated {
+                    bill_id: created.bill_id,
+                    creator: account(&created.creator),
+                    total_amount: created.total_amount,
+                    participant_count: created.participant_count,
+                },
+            ],
+        ));
+    }
+    if let Some(paid) = event.decode::<BillPaid>() {
+        return Some((
+            "BillPaid",
+            vec![
+                activity(&paid.payer, "bill_paid", None, paid.amount, paid.bill_id),
+                Row::BillPaid {
+                    bill_id: paid.bill_id,
+                    payer: account(&paid.payer),
+                    amount: paid.amount,
+                },
+            ],
+        ));
+    }
+    if let Some(completed) = event.decode::<BillCompleted>() {
+        return Some((
+            "BillCompleted",
+            vec![
+                activity(&completed.creator, "bill_completed", None, completed.total_paid, completed.bill_id),
+                Row::BillClosed {
+                    bill_id: completed.bill_id,
+                    outcome: "completed",
+                },
+            ],
+        ));
+    }
+    let cancelled = event.decode::<BillCancelled>()?;
+    Some((
+        "BillCancelled",
+        vec![
+            activity(&cancelled.creator, "bill_cancelled", None, cancelled.refunded_amount, cancelled.bill_id),
+            Row::BillClosed {
+                bill_id: cancelled.bill_id,
+                outcome: "cancelled",
+            },
+        ],
+    ))
+}
+
+fn decode_xcm(event: &EmittedEvent) -> Option<(&'static str, Vec<Row>)> {
+    if let Some(created) = event.decode::<CrossChainPaymentCreated>() {
+        let id = created.payment_id;
+        return Some((
+            "CrossChainPaymentCreated",
+            vec![
+                activity(&created.sender, "xcm_sent", Some(&created.recipient), created.amount, id),
+                Row::XcmPaymentCreated {
+                    payment_id: id,
+                    sender: account(&created.sender),
+                    recipient: account(&created.recipient),
+                    beneficiary: beneficiary(&created.beneficiary),
+                    asset_id: created.asset_id,
+                    amount: created.amount,
+                    source_chain: created.source_chain,
+                    destination_chain: created.destination_chain,
+                    created_at: created.created_at,
+                },
+                Row::XcmStatus {
+                    payment_id: id,
+                    status: format!("{:?}", created.status),
+                },
+            ],
+        ));
+    }
+    if let Some(executed) = event.decode::<CrossChainPaymentExecuted>() {
+        let id = executed.payment_id;
+        return Some((
+            "CrossChainPaymentExecuted",
+            vec![
+                activity(&executed.sender, "xcm_executed", Some(&executed.recipient), executed.amount, id),
+                activity(&executed.recipient, "xcm_received", Some(&executed.sender), executed.amount, id),
+            ],
+        ));
+    }
+    // Status changes after creation, the final ones below included
+    if let Some(changed) = event.decode::<PaymentStatusChanged>() {
+        return Some((
+            "PaymentStatusChanged",
+            vec![Row::XcmStatus {
+                payment_id: changed.payment_id,
+                status: format!("{:?}", changed.to),
+            }],
+        ));
+    }
+    if let Some(cancelled) = event.decode::<CrossChainPaymentCancelled>() {
+        let row = activity(&cancelled.sender, "xcm_cancelled", None, cancelled.amount, cancelled.payment_id);
+        return Some(("CrossChainPaymentCancelled", vec![row]));
+    }
+    if let Some(refunded) = event.decode::<CrossChainPaymentRefunded>() {
+        let row = activity(&refunded.sender, "xcm_refunded", None, refunded.amount, refunded.payment_id);
+        return Some(("CrossChainPaymentRefunded", vec![row]));
+    }
+    let failed = event.decode::<CrossChainPaymentFailed>()?;
+    let row = activity(&failed.sender, "xcm_failed", None, failed.amount, failed.payment_id);
+    Some(("CrossChainPaymentFailed", vec![row]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+    use xcc_sdk::ContractEvent;
+
+    #[test]
+    fn events_are_decoded_for_their_own_contract_only() {
+        let (alice, bob) = (AccountId::from([1; 32]), AccountId::from([2; 32]));
+        let event = EmittedEvent {
+            contract: AccountId32([9; 32]),
+            topics: vec![H256(PaymentSent::SIGNATURE_TOPIC)],
+            data: (alice, bob, 250u128, 3u32).encode(),
+        };
+
+        let (kind, rows) = decode(Role::Payment, &event).unwrap();
+        assert_eq!(kind, "PaymentSent");
+        assert_eq!(
+            rows[1],
+            Row::Activity {
+                account: account(&bob),
+                kind: "payment_received",
+                counterparty: Some(account(&alice)),
+                amount: 250,
+                reference: 3,
+            }
+        );
+        assert!(decode(Role::BillSplitting, &event).is_none());
+        assert!(decode(Role::XcmHandler, &event).is_none());
+    }
+}
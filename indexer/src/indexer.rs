@@ -0,0 +1,144 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use subxt::{blocks::Block, OnlineClient, PolkadotConfig};
+use xcc_sdk::EmittedEvent;
+
+use crate::{
+    config::Contracts,
+    decode::{self, IndexedBlock, IndexedEvent},
+    store::Store,
+};
+
+type Client = OnlineClient<PolkadotConfig>;
+
+/// Delay before reconnecting to the chain
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// Follows the chain and writes the contracts' events to the store
+///
+/// Best blocks are indexed as they are imported, so the API is current
+/// within a block; rows carry their block so that queries can tell which
+/// are finalized. When a new block does not build on the indexed one at
+/// its parent's height, the indexed blocks are rolled back to the common
+/// ancestor and the new branch indexed instead. Blocks missed while
+/// disconnected are caught up the same way.
+pub struct Indexer {
+    endpoint: String,
+    contracts: Contracts,
+    start_block: Option<u32>,
+    store: Arc<Mutex<Store>>,
+}
+
+impl Indexer {
+    pub fn new(endpoint: String, contracts: Contracts, start_block: Option<u32>, store: Arc<Mutex<Store>>) -> Self {
+        Self {
+            endpoint,
+            contracts,
+            start_block,
+            store,
+        }
+    }
+
+    fn store(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Follow the chain forever, reconnecting whenever the connection drops
+    pub async fn run(self) {
+        loop {
+            if let Err(error) = self.follow().await {
+                tracing::warn!("connection lost: {error:#}");
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn follow(&self) -> anyhow::Result<()> {
+        let api = Client::from_url(&self.endpoint).await?;
+        tracing::info!(endpoint = %self.endpoint, "following best blocks");
+
+        let mut heads = api.blocks().subscribe_best().await?;
+        while let Some(head) = heads.next().await {
+            self.sync_to(&api, head?).await?;
+
+            // `at_latest` is the latest finalized block
+            let finalized = api.blocks().at_latest().await?.number();
+            self.store().set_finalized(finalized)?;
+        }
+
+        Err(anyhow!("block subscription ended"))
+    }
+
+    /// Index `head` and every block between it and the indexed chain
+    async fn sync_to(&self, api: &Client, head: Block<PolkadotConfig, Client>) -> anyhow::Result<()> {
+        // Lowest block to index; everything above it is re-indexed on demand
+        let floor = match self.store().first_block()? {
+            Some(first) => first,
+            None => self.start_block.unwrap_or(head.number()),
+        };
+        if head.number() < floor || self.store().has_block(head.number(), &head.hash())? {
+            return Ok(());
+        }
+
+        // Walk back until the branch joins the indexed chain
+        let mut branch = vec![head];
+        loop {
+            let oldest = branch.last().expect("branch starts with the head");
+            let number = oldest.number();
+            let parent = oldest.header().parent_hash;
+            if number == floor || self.store().has_block(number - 1, &parent)? {
+                break;
+            }
+            branch.push(api.blocks().at(parent).await?);
+        }
+
+        let fork = branch.last().expect("branch starts with the head").number();
+        if self.store().block_hash(fork)?.is_some() {
+            tracing::info!(block = fork, "re-org, rolling back");
+            self.store().rollback(fork)?;
+        }
+
+        for block in branch.iter().rev() {
+            let indexed = self.index(block).await?;
+            if !indexed.events.is_empty() {
+                tracing::debug!(block = indexed.number, events = indexed.events.len(), "indexed");
+            }
+            self.store().insert_block(&indexed)?;
+        }
+        Ok(())
+    }
+
+    async fn index(&self, block: &Block<PolkadotConfig, Client>) -> anyhow::Result<IndexedBlock> {
+        let mut events = Vec::new();
+        for (index, event) in block.events().await?.iter().enumerate() {
+            let Some(emitted) = EmittedEvent::from_details(&event?)? else {
+                continue;
+            };
+            let Some(role) = self.contracts.role(&emitted.contract) else {
+                continue;
+            };
+
+            let (kind, rows) = match decode::decode(role, &emitted) {
+                Some((kind, rows)) => (Some(kind), rows),
+                None => (None, Vec::new()),
+            };
+            events.push(IndexedEvent {
+                index: index as u32,
+                emitted,
+                kind,
+                rows,
+            });
+        }
+
+        Ok(IndexedBlock {
+            number: block.number(),
+            hash: block.hash(),
+            parent_hash: block.header().parent_hash,
+            events,
+        })
+    }
+}
@@ -0,0 +1,54 @@
+//! Event indexer for the 0xCC contracts
+//!
+//! Follows a chain, stores the events of the configured contracts in SQLite
+//! and serves account histories, bills and cross-chain payments as JSON.
+//!
+//! Usage: `xcc-indexer [--config <path>]`, `indexer.toml` by default.
+
+mod api;
+mod config;
+mod decode;
+mod indexer;
+mod store;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Context};
+
+use crate::{config::Config, indexer::Indexer, store::Store};
+
+fn config_path() -> anyhow::Result<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), args.next()) {
+        (None, _) => Ok(PathBuf::from("indexer.toml")),
+        (Some("--config" | "-c"), Some(path)) => Ok(PathBuf::from(path)),
+        _ => bail!("usage: xcc-indexer [--config <path>]"),
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config = Config::load(&config_path()?)?;
+    let store = Store::open(&config.database).with_context(|| format!("opening {}", config.database.display()))?;
+    let store = Arc::new(Mutex::new(store));
+
+    let shared = Arc::clone(&store);
+    tokio::spawn(async move {
+        if let Err(error) = api::serve(config.listen, shared).await {
+            tracing::error!("query server stopped: {error}");
+        }
+    });
+
+    let indexer = Indexer::new(config.endpoint, config.contracts, config.start_block, store);
+    tokio::spawn(indexer.run());
+
+    tokio::signal::ctrl_c().await?;
+    tracing::info!("shutting down");
+
+    Ok(())
+}
@@ -0,0 +1,501 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension, Row as SqlRow};
+use serde::Serialize;
+use subxt::utils::H256;
+
+use crate::decode::{IndexedBlock, Row};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER PRIMARY KEY,
+    hash TEXT NOT NULL,
+    parent_hash TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS events (
+    block_number INTEGER NOT NULL,
+    event_index INTEGER NOT NULL,
+    contract TEXT NOT NULL,
+    kind TEXT,
+    topics TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (block_number, event_index)
+);
+CREATE TABLE IF NOT EXISTS activity (
+    block_number INTEGER NOT NULL,
+    event_index INTEGER NOT NULL,
+    account TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    counterparty TEXT,
+    amount TEXT NOT NULL,
+    reference INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS activity_by_account ON activity (account, block_number, event_index);
+CREATE TABLE IF NOT EXISTS bills (
+    bill_id INTEGER PRIMARY KEY,
+    block_number INTEGER NOT NULL,
+    creator TEXT NOT NULL,
+    total_amount TEXT NOT NULL,
+    participant_count INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS bill_payments (
+    block_number INTEGER NOT NULL,
+    bill_id INTEGER NOT NULL,
+    payer TEXT NOT NULL,
+    amount TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS bill_payments_by_bill ON bill_payments (bill_id);
+CREATE TABLE IF NOT EXISTS bill_closures (
+    block_number INTEGER NOT NULL,
+    bill_id INTEGER NOT NULL,
+    outcome TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS xcm_payments (
+    payment_id INTEGER PRIMARY KEY,
+    block_number INTEGER NOT NULL,
+    sender TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    beneficiary TEXT NOT NULL,
+    asset_id INTEGER NOT NULL,
+    amount TEXT NOT NULL,
+    source_chain INTEGER NOT NULL,
+    destination_chain INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS xcm_status (
+    block_number INTEGER NOT NULL,
+    event_index INTEGER NOT NULL,
+    payment_id INTEGER NOT NULL,
+    status TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS xcm_status_by_payment ON xcm_status (payment_id, block_number, event_index);
+";
+
+/// Tables rolled back on a re-org, all keyed by `block_number`
+const BLOCK_TABLES: [&str; 8] = [
+    "blocks",
+    "events",
+    "activity",
+    "bills",
+    "bill_payments",
+    "bill_closures",
+    "xcm_payments",
+    "xcm_status",
+];
+
+/// Statuses of cross-chain payments that may still be executed
+const OPEN_STATUSES: &str = "('Pending', 'InTransit')";
+
+/// An entry of an account's history
+#[derive(Debug, Serialize)]
+pub struct ActivityRecord {
+    pub block_number: u32,
+    pub finalized: bool,
+    pub kind: String,
+    pub counterparty: Option<String>,
+    pub amount: String,
+    pub reference: u32,
+}
+
+/// A bill and how far it was paid
+#[derive(Debug, Serialize)]
+pub struct BillRecord {
+    pub bill_id: u32,
+    pub block_number: u32,
+    pub finalized: bool,
+    pub creator: String,
+    pub total_amount: String,
+    pub participant_count: u32,
+    pub paid_count: u32,
+    pub total_paid: String,
+    /// `open`, `completed` or `cancelled`
+    pub status: String,
+}
+
+/// A cross-chain payment with its latest status
+#[derive(Debug, Serialize)]
+pub struct XcmPaymentRecord {
+    pub payment_id: u32,
+    pub block_number: u32,
+    pub finalized: bool,
+    pub sender: String,
+    pub recipient: String,
+    pub beneficiary: String,
+    pub asset_id: u32,
+    pub amount: String,
+    pub source_chain: u32,
+    pub destination_chain: u32,
+    pub created_at: u64,
+    pub status: String,
+}
+
+/// Indexing progress
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub indexed_block: Option<u32>,
+    pub finalized_block: Option<u32>,
+}
+
+fn hex_hash(hash: &H256) -> String {
+    format!("0x{}", hex::encode(hash.0))
+}
+
+/// The SQLite database the indexer writes and the API reads
+///
+/// Every row records the block it came from, so that the rows of blocks
+/// dropped by a re-org can be deleted again.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Hash of the indexed block at `number`
+    pub fn block_hash(&self, number: u32) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row("SELECT hash FROM blocks WHERE number = ?1", [number], |row| row.get(0))
+            .optional()
+    }
+
+    /// Whether `hash` is the indexed block at `number`
+    pub fn has_block(&self, number: u32, hash: &H256) -> rusqlite::Result<bool> {
+        Ok(self.block_hash(number)?.as_deref() == Some(hex_hash(hash).as_str()))
+    }
+
+    /// The lowest indexed block
+    pub fn first_block(&self) -> rusqlite::Result<Option<u32>> {
+        self.conn.query_row("SELECT MIN(number) FROM blocks", [], |row| row.get(0))
+    }
+
+    pub fn status(&self) -> rusqlite::Result<Status> {
+        Ok(Status {
+            indexed_block: self.conn.query_row("SELECT MAX(number) FROM blocks", [], |row| row.get(0))?,
+            finalized_block: self.finalized()?,
+        })
+    }
+
+    fn finalized(&self) -> rusqlite::Result<Option<u32>> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = 'finalized'", [], |row| row.get(0))
+            .optional()
+    }
+
+    pub fn set_finalized(&self, number: u32) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES ('finalized', ?1) ON CONFLICT (key) DO UPDATE SET value = ?1",
+            [number],
+        )?;
+        Ok(())
+    }
+
+    /// Drop every block from `number` on, with all rows derived from them
+    pub fn rollback(&mut self, number: u32) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        for table in BLOCK_TABLES {
+            let column = if table == "blocks" { "number" } else { "block_number" };
+            tx.execute(&format!("DELETE FROM {table} WHERE {column} >= ?1"), [number])?;
+        }
+        tx.commit()
+    }
+
+    /// Store a block and the rows of its events, replacing any block at its height
+    pub fn insert_block(&mut self, block: &IndexedBlock) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        let number = block.number;
+        for table in BLOCK_TABLES {
+            let column = if table == "blocks" { "number" } else { "block_number" };
+            tx.execute(&format!("DELETE FROM {table} WHERE {column} = ?1"), [number])?;
+        }
+        tx.execute(
+            "INSERT INTO blocks (number, hash, parent_hash) VALUES (?1, ?2, ?3)",
+            params![number, hex_hash(&block.hash), hex_hash(&block.parent_hash)],
+        )?;
+
+        for event in &block.events {
+            let topics: Vec<String> = event.emitted.topics.iter().map(hex_hash).collect();
+            tx.execute(
+                "INSERT INTO events (block_number, event_index, contract, kind, topics, data) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    number,
+                    event.index,
+                    event.emitted.contract.to_string(),
+                    event.kind,
+                    topics.join(","),
+                    format!("0x{}", hex::encode(&event.emitted.data)),
+                ],
+            )?;
+
+            for row in &event.rows {
+                match row {
+                    Row::Activity { account, kind, counterparty, amount, reference } => tx.execute(
+                        "INSERT INTO activity (block_number, event_index, account, kind, counterparty, amount, reference)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![number, event.index, account, kind, counterparty, amount.to_string(), reference],
+                    )?,
+                    Row::BillCreated { bill_id, creator, total_amount, participant_count } => tx.execute(
+                        "INSERT OR REPLACE INTO bills (bill_id, block_number, creator, total_amount, participant_count)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![bill_id, number, creator, total_amount.to_string(), participant_count],
+                    )?,
+                    Row::BillPaid { bill_id, payer, amount } => tx.execute(
+                        "INSERT INTO bill_payments (block_number, bill_id, payer, amount) VALUES (?1, ?2, ?3, ?4)",
+                        params![number, bill_id, payer, amount.to_string()],
+                    )?,
+                    Row::BillClosed { bill_id, outcome } => tx.execute(
+                        "INSERT INTO bill_closures (block_number, bill_id, outcome) VALUES (?1, ?2, ?3)",
+                        params![number, bill_id, outcome],
+                    )?,
+                    Row::XcmPaymentCreated {
+                        payment_id,
+                        sender,
+                        recipient,
+                        beneficiary,
+                        asset_id,
+                        amount,
+                        source_chain,
+                        destination_chain,
+                        created_at,
+                    } => tx.execute(
+                        "INSERT OR REPLACE INTO xcm_payments (payment_id, block_number, sender, recipient, beneficiary,
+                         asset_id, amount, source_chain, destination_chain, created_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        params![
+                            payment_id,
+                            number,
+                            sender,
+                            recipient,
+                            beneficiary,
+                            asset_id,
+                            amount.to_string(),
+                            source_chain,
+                            destination_chain,
+                            created_at,
+                        ],
+                    )?,
+                    Row::XcmStatus { payment_id, status } => tx.execute(
+                        "INSERT INTO xcm_status (block_number, event_index, payment_id, status) VALUES (?1, ?2, ?3, ?4)",
+                        params![number, event.index, payment_id, status],
+                    )?,
+                };
+            }
+        }
+        tx.commit()
+    }
+
+    /// An account's history, newest first, before block `before` if given
+    pub fn history(&self, account: &str, before: Option<u32>, limit: u32) -> rusqlite::Result<Vec<ActivityRecord>> {
+        let finalized = self.finalized()?;
+        let mut statement = self.conn.prepare(
+            "SELECT block_number, kind, counterparty, amount, reference FROM activity
+             WHERE account = ?1 AND block_number < ?2
+             ORDER BY block_number DESC, event_index DESC LIMIT ?3",
+        )?;
+        let rows = statement.query_map(params![account, before.unwrap_or(u32::MAX), limit], |row| {
+            let block_number = row.get(0)?;
+            Ok(ActivityRecord {
+                block_number,
+                finalized: is_final(finalized, block_number),
+                kind: row.get(1)?,
+                counterparty: row.get(2)?,
+                amount: row.get(3)?,
+                reference: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn bill(&self, bill_id: u32) -> rusqlite::Result<Option<BillRecord>> {
+        let finalized = self.finalized()?;
+        let Some((block_number, creator, total_amount, participant_count)) = self
+            .conn
+            .query_row(
+                "SELECT block_number, creator, total_amount, participant_count FROM bills WHERE bill_id = ?1",
+                [bill_id],
+                |row| Ok((row.get::<_, u32>(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let mut statement = self.conn.prepare("SELECT amount FROM bill_payments WHERE bill_id = ?1")?;
+        let payments = statement
+            .query_map([bill_id], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let total_paid: u128 = payments.iter().filter_map(|amount| amount.parse::<u128>().ok()).sum();
+        let status = self
+            .conn
+            .query_row("SELECT outcome FROM bill_closures WHERE bill_id = ?1", [bill_id], |row| row.get(0))
+            .optional()?
+            .unwrap_or_else(|| "open".to_string());
+
+        Ok(Some(BillRecord {
+            bill_id,
+            block_number,
+            finalized: is_final(finalized, block_number),
+            creator,
+            total_amount,
+            participant_count,
+            paid_count: payments.len() as u32,
+            total_paid: total_paid.to_string(),
+            status,
+        }))
+    }
+
+    /// Cross-chain payments, with their latest status
+    fn xcm_payments(&self, filter: &str, args: &[&dyn rusqlite::ToSql]) -> rusqlite::Result<Vec<XcmPaymentRecord>> {
+        let finalized = self.finalized()?;
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT * FROM (
+                SELECT payment_id, block_number, sender, recipient, beneficiary, asset_id, amount,
+                    source_chain, destination_chain, created_at,
+                    (SELECT status FROM xcm_status s WHERE s.payment_id = p.payment_id
+                     ORDER BY block_number DESC, event_index DESC LIMIT 1) AS status
+                FROM xcm_payments p
+            ) WHERE {filter} ORDER BY payment_id"
+        ))?;
+        let rows = statement.query_map(args, |row| xcm_payment(row, finalized))?;
+        rows.collect()
+    }
+
+    pub fn xcm_payment(&self, payment_id: u32) -> rusqlite::Result<Option<XcmPaymentRecord>> {
+        Ok(self.xcm_payments("payment_id = ?1", &[&payment_id])?.pop())
+    }
+
+    /// Cross-chain payments not yet executed, cancelled or refunded, of one sender if given
+    pub fn pending_xcm_payments(&self, sender: Option<&str>) -> rusqlite::Result<Vec<XcmPaymentRecord>> {
+        match sender {
+            Some(sender) => self.xcm_payments(&format!("status IN {OPEN_STATUSES} AND sender = ?1"), &[&sender]),
+            None => self.xcm_payments(&format!("status IN {OPEN_STATUSES}"), &[]),
+        }
+    }
+}
+
+fn is_final(finalized: Option<u32>, block_number: u32) -> bool {
+    finalized.is_some_and(|finalized| block_number <= finalized)
+}
+
+fn xcm_payment(row: &SqlRow, finalized: Option<u32>) -> rusqlite::Result<XcmPaymentRecord> {
+    let block_number = row.get(1)?;
+    Ok(XcmPaymentRecord {
+        payment_id: row.get(0)?,
+        block_number,
+        finalized: is_final(finalized, block_number),
+        sender: row.get(2)?,
+        recipient: row.get(3)?,
+        beneficiary: row.get(4)?,
+        asset_id: row.get(5)?,
+        amount: row.get(6)?,
+        source_chain: row.get(7)?,
+        destination_chain: row.get(8)?,
+        created_at: row.get(9)?,
+        status: row.get(10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::IndexedEvent;
+    use subxt::utils::AccountId32;
+    use xcc_sdk::EmittedEvent;
+
+    fn event(index: u32, rows: Vec<Row>) -> IndexedEvent {
+        IndexedEvent {
+            index,
+            emitted: EmittedEvent {
+                contract: AccountId32([9; 32]),
+                topics: Vec::new(),
+                data: Vec::new(),
+            },
+            kind: None,
+            rows,
+        }
+    }
+
+    fn block(number: u32, fork: u8, events: Vec<IndexedEvent>) -> IndexedBlock {
+        IndexedBlock {
+            number,
+            hash: H256([fork; 32]),
+            parent_hash: H256([fork.wrapping_sub(1); 32]),
+            events,
+        }
+    }
+
+    fn created(payment_id: u32) -> Vec<Row> {
+        vec![
+            Row::XcmPaymentCreated {
+                payment_id,
+                sender: "alice".into(),
+                recipient: "bob".into(),
+                beneficiary: "bob".into(),
+                asset_id: 0,
+                amount: 100,
+                source_chain: 1000,
+                destination_chain: 2000,
+                created_at: 0,
+            },
+            Row::XcmStatus { payment_id, status: "Pending".into() },
+        ]
+    }
+
+    #[test]
+    fn rollback_drops_the_rows_of_orphaned_blocks() {
+        let mut store = Store::in_memory().unwrap();
+        store.insert_block(&block(1, 1, vec![event(0, created(0)), event(1, created(1))])).unwrap();
+        let executed = Row::XcmStatus { payment_id: 0, status: "Executed".into() };
+        store.insert_block(&block(2, 2, vec![event(0, vec![executed])])).unwrap();
+        store.set_finalized(1).unwrap();
+
+        let pending = store.pending_xcm_payments(Some("alice")).unwrap();
+        assert_eq!(pending.iter().map(|p| p.payment_id).collect::<Vec<_>>(), vec![1]);
+        assert!(pending[0].finalized);
+
+        // Block 2 is replaced by a fork that did not execute payment 0
+        assert!(store.has_block(2, &H256([2; 32])).unwrap());
+        store.rollback(2).unwrap();
+        assert!(!store.has_block(2, &H256([2; 32])).unwrap());
+        assert_eq!(store.xcm_payment(0).unwrap().unwrap().status, "Pending");
+        assert_eq!(store.status().unwrap().indexed_block, Some(1));
+    }
+
+    #[test]
+    fn bills_track_payments_and_closure() {
+        let mut store = Store::in_memory().unwrap();
+        let creation = Row::BillCreated {
+            bill_id: 4,
+            creator: "alice".into(),
+            total_amount: 300,
+            participant_count: 2,
+        };
+        let paid = |payer: &str| Row::BillPaid { bill_id: 4, payer: payer.into(), amount: 150 };
+        store.insert_block(&block(1, 1, vec![event(0, vec![creation, paid("bob")])])).unwrap();
+
+        let bill = store.bill(4).unwrap().unwrap();
+        assert_eq!((bill.paid_count, bill.total_paid.as_str(), bill.status.as_str()), (1, "150", "open"));
+        assert!(!bill.finalized);
+
+        let closed = Row::BillClosed { bill_id: 4, outcome: "completed" };
+        store.insert_block(&block(2, 2, vec![event(0, vec![paid("carol"), closed])])).unwrap();
+        let bill = store.bill(4).unwrap().unwrap();
+        assert_eq!((bill.paid_count, bill.total_paid.as_str(), bill.status.as_str()), (2, "300", "completed"));
+        assert!(store.bill(5).unwrap().is_none());
+    }
+}
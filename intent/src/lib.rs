@@ -0,0 +1,138 @@
+//! Text encoding of 0xCC payment intents, for QR codes and deeplinks
+//!
+//! An intent is a version byte followed by the SCALE encoding of a
+//! [`PaymentIntent`], written in base58 so it survives QR codes, URLs and
+//! copy-paste. Deeplinks put [`DEEPLINK_PREFIX`] in front. Wallets decode
+//! the intent and pass it as is to BillSplitting's `execute_intent`:
+//!
+//! ```
+//! use xcc_intent::{IntentAsset, PaymentIntent};
+//!
+//! let intent = PaymentIntent {
+//!     recipient: [7; 32].into(),
+//!     amount: 2_500,
+//!     asset: IntentAsset::Native,
+//!     memo: "table 4".into(),
+//!     expires_at: None,
+//!     bill_id: None,
+//! };
+//! let link = xcc_intent::to_deeplink(&intent).unwrap();
+//! assert_eq!(xcc_intent::decode(&link).unwrap(), intent);
+//! ```
+
+use std::fmt;
+
+use codec::{DecodeAll, Encode};
+
+pub use xcc_common::{IntentAsset, PaymentIntent, MAX_MEMO_LEN};
+
+/// Version of the encoding written by this crate
+pub const VERSION: u8 = 1;
+
+/// Prefix of intent deeplinks
+pub const DEEPLINK_PREFIX: &str = "xcc://pay/";
+
+/// Why an intent could not be encoded or decoded
+#[derive(Debug)]
+pub enum Error {
+    /// The memo is longer than `MAX_MEMO_LEN` bytes
+    MemoTooLong,
+    InvalidBase58(bs58::decode::Error),
+    /// Written by an unknown version of the encoding
+    UnsupportedVersion(u8),
+    Malformed(codec::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MemoTooLong => write!(f, "memo longer than {MAX_MEMO_LEN} bytes"),
+            Error::InvalidBase58(error) => write!(f, "invalid base58: {error}"),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported intent version {version}"),
+            Error::Malformed(error) => write!(f, "malformed intent: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Encode an intent as base58 text
+pub fn encode(intent: &PaymentIntent) -> Result<String, Error> {
+    if intent.memo.len() > MAX_MEMO_LEN {
+        return Err(Error::MemoTooLong);
+    }
+    let mut bytes = vec![VERSION];
+    intent.encode_to(&mut bytes);
+    Ok(bs58::encode(bytes).into_string())
+}
+
+/// Encode an intent as a deeplink
+pub fn to_deeplink(intent: &PaymentIntent) -> Result<String, Error> {
+    Ok(format!("{DEEPLINK_PREFIX}{}", encode(intent)?))
+}
+
+/// Decode an intent from base58 text, with or without the deeplink prefix
+pub fn decode(text: &str) -> Result<PaymentIntent, Error> {
+    let text = text.trim();
+    let text = text.strip_prefix(DEEPLINK_PREFIX).unwrap_or(text);
+    let bytes = bs58::decode(text).into_vec().map_err(Error::InvalidBase58)?;
+
+    let Some((&version, mut payload)) = bytes.split_first() else {
+        return Err(Error::Malformed("empty intent".into()));
+    };
+    if version != VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    let intent = PaymentIntent::decode_all(&mut payload).map_err(Error::Malformed)?;
+    if intent.memo.len() > MAX_MEMO_LEN {
+        return Err(Error::MemoTooLong);
+    }
+    Ok(intent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent() -> PaymentIntent {
+        PaymentIntent {
+            recipient: [1; 32].into(),
+            amount: 1_000_000_000_000,
+            asset: IntentAsset::Ledger([2; 32].into()),
+            memo: "rent, March".into(),
+            expires_at: Some(1_767_225_600_000),
+            bill_id: Some(42),
+        }
+    }
+
+    #[test]
+    fn intents_round_trip() {
+        let intent = intent();
+        let text = encode(&intent).unwrap();
+        assert_eq!(decode(&text).unwrap(), intent);
+
+        let link = to_deeplink(&intent).unwrap();
+        assert_eq!(link, format!("{DEEPLINK_PREFIX}{text}"));
+        assert_eq!(decode(&link).unwrap(), intent);
+    }
+
+    #[test]
+    fn invalid_intents_are_rejected() {
+        let mut bytes = vec![VERSION + 1];
+        intent().encode_to(&mut bytes);
+        let text = bs58::encode(&bytes).into_string();
+        assert!(matches!(decode(&text), Err(Error::UnsupportedVersion(2))));
+
+        // Trailing bytes are not silently dropped
+        bytes[0] = VERSION;
+        bytes.push(0);
+        let text = bs58::encode(&bytes).into_string();
+        assert!(matches!(decode(&text), Err(Error::Malformed(_))));
+
+        assert!(matches!(decode("0OIl"), Err(Error::InvalidBase58(_))));
+        assert!(matches!(decode(""), Err(Error::Malformed(_))));
+
+        let long_memo = PaymentIntent { memo: "x".repeat(MAX_MEMO_LEN + 1), ..intent() };
+        assert!(matches!(encode(&long_memo), Err(Error::MemoTooLong)));
+    }
+}
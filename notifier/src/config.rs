@@ -0,0 +1,173 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use subxt::utils::AccountId32;
+
+use crate::notification::Kind;
+
+/// Notifier configuration file
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// WebSocket RPC endpoint
+    pub endpoint: String,
+    #[serde(default)]
+    pub delivery: DeliverySettings,
+    pub contracts: Contracts,
+    pub subscriptions: Vec<Subscription>,
+}
+
+/// Retry settings shared by all sinks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DeliverySettings {
+    /// Attempts per notification and sink before giving up
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every attempt
+    pub retry_delay_ms: u64,
+    /// Timeout of each HTTP request
+    pub timeout_ms: u64,
+}
+
+impl Default for DeliverySettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_delay_ms: 2_000,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+impl DeliverySettings {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// SS58 addresses of the watched contracts
+#[derive(Debug, Clone, Deserialize)]
+pub struct Contracts {
+    pub bill_splitting: Option<AccountId32>,
+    pub xcm_handler: Option<AccountId32>,
+}
+
+/// Where and which notifications of some accounts are delivered
+#[derive(Debug, Deserialize)]
+pub struct Subscription {
+    pub name: String,
+    pub accounts: Vec<AccountId32>,
+    /// Kinds of notifications to deliver, all if empty
+    #[serde(default)]
+    pub kinds: Vec<Kind>,
+    pub webhook: Option<WebhookSink>,
+    pub matrix: Option<MatrixSink>,
+    pub email: Option<EmailSink>,
+}
+
+impl Subscription {
+    pub fn wants(&self, kind: Kind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+}
+
+/// An HTTP endpoint notifications are POSTed to as JSON
+#[derive(Debug, Deserialize)]
+pub struct WebhookSink {
+    pub url: String,
+    /// Key to sign the body with, e.g. for local testing
+    pub secret: Option<String>,
+    /// Environment variable holding the key
+    pub secret_env: Option<String>,
+}
+
+/// A Matrix room notifications are posted to
+#[derive(Debug, Deserialize)]
+pub struct MatrixSink {
+    pub homeserver: String,
+    pub room_id: String,
+    /// Environment variable holding the access token of the posting user
+    pub access_token_env: String,
+    #[serde(skip)]
+    pub access_token: String,
+}
+
+/// A mailbox notifications are sent to through sendmail
+#[derive(Debug, Deserialize)]
+pub struct EmailSink {
+    pub from: String,
+    pub to: String,
+    #[serde(default = "default_sendmail")]
+    pub sendmail: PathBuf,
+}
+
+fn default_sendmail() -> PathBuf {
+    PathBuf::from("/usr/sbin/sendmail")
+}
+
+fn env_var(var: &str) -> anyhow::Result<String> {
+    std::env::var(var).with_context(|| format!("reading ${var}"))
+}
+
+impl Config {
+    /// Read and check a configuration file, resolving secrets from the environment
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut config: Config = toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+
+        if config.contracts.bill_splitting.is_none() && config.contracts.xcm_handler.is_none() {
+            bail!("no contracts configured");
+        }
+        if config.delivery.max_attempts == 0 {
+            bail!("max_attempts must be at least 1");
+        }
+        if config.subscriptions.is_empty() {
+            bail!("no subscriptions configured");
+        }
+        for subscription in &mut config.subscriptions {
+            let name = &subscription.name;
+            if subscription.accounts.is_empty() {
+                bail!("{name}: no accounts configured");
+            }
+            if subscription.webhook.is_none() && subscription.matrix.is_none() && subscription.email.is_none() {
+                bail!("{name}: no webhook, matrix or email sink configured");
+            }
+            if let Some(webhook) = &mut subscription.webhook {
+                if let Some(var) = &webhook.secret_env {
+                    webhook.secret = Some(env_var(var).with_context(|| format!("{name}: webhook secret"))?);
+                }
+            }
+            if let Some(matrix) = &mut subscription.matrix {
+                matrix.access_token =
+                    env_var(&matrix.access_token_env).with_context(|| format!("{name}: matrix access token"))?;
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_parses() {
+        std::env::set_var("XCC_WEBHOOK_SECRET", "hook-secret");
+        std::env::set_var("XCC_MATRIX_TOKEN", "matrix-token");
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("config.example.toml");
+        let config = Config::load(&path).unwrap();
+
+        assert_eq!(config.delivery.max_attempts, 5);
+        let [backend, alice] = &config.subscriptions[..] else {
+            panic!("two subscriptions expected");
+        };
+        assert_eq!(backend.webhook.as_ref().unwrap().secret.as_deref(), Some("hook-secret"));
+        assert!(backend.wants(Kind::XcmExecuted));
+        assert!(!alice.wants(Kind::XcmExecuted));
+        assert_eq!(alice.matrix.as_ref().unwrap().access_token, "matrix-token");
+        assert_eq!(alice.email.as_ref().unwrap().sendmail, default_sendmail());
+    }
+}
@@ -0,0 +1,45 @@
+//! Notifications for 0xCC payment and bill events
+//!
+//! Watches the BillSplitting and XcmHandler contracts and notifies
+//! subscribed accounts through webhooks, Matrix or email when they are added
+//! to a bill, their share of a bill falls due or a cross-chain payment of
+//! theirs is executed.
+//!
+//! Usage: `xcc-notifier [--config <path>]`, `notifier.toml` by default.
+
+mod config;
+mod notification;
+mod sink;
+mod watcher;
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::bail;
+
+use crate::{config::Config, notification::Subscriptions, sink::Dispatcher, watcher::Watcher};
+
+fn config_path() -> anyhow::Result<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), args.next()) {
+        (None, _) => Ok(PathBuf::from("notifier.toml")),
+        (Some("--config" | "-c"), Some(path)) => Ok(PathBuf::from(path)),
+        _ => bail!("usage: xcc-notifier [--config <path>]"),
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config = Config::load(&config_path()?)?;
+    let dispatcher = Arc::new(Dispatcher::new(config.delivery)?);
+    let subscriptions = Subscriptions::new(config.subscriptions);
+
+    let watcher = Watcher::new(config.endpoint, config.contracts, subscriptions, dispatcher);
+    tokio::spawn(watcher.run());
+
+    tokio::signal::ctrl_c().await?;
+    tracing::info!("shutting down");
+
+    Ok(())
+}
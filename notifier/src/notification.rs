@@ -0,0 +1,175 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use subxt::utils::AccountId32;
+
+use crate::config::Subscription;
+
+/// What a notification is about, as used in subscription filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    BillAdded,
+    PaymentDue,
+    XcmExecuted,
+}
+
+/// The event a notification reports, serialized with its `kind`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    /// The account was added to a bill, at its creation or later
+    BillAdded {
+        bill_id: u32,
+        /// Share of the account
+        amount: String,
+    },
+    /// The account's share of a bill is due soon, or overdue
+    PaymentDue {
+        bill_id: u32,
+        amount_due: String,
+        /// Deadline of the bill, in milliseconds since the Unix epoch
+        deadline: u64,
+        overdue: bool,
+    },
+    /// A cross-chain payment the account sent or receives was executed
+    XcmExecuted {
+        payment_id: u32,
+        sender: String,
+        recipient: String,
+        amount: String,
+        destination_chain: u32,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Event::BillAdded { .. } => Kind::BillAdded,
+            Event::PaymentDue { .. } => Kind::PaymentDue,
+            Event::XcmExecuted { .. } => Kind::XcmExecuted,
+        }
+    }
+}
+
+/// A notification for one account, as POSTed to webhooks
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Unique per event and account, for receivers to drop redelivered notifications
+    pub id: String,
+    pub account: String,
+    pub block_number: u32,
+    pub block_hash: String,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl Notification {
+    /// One-line subject, for mail and chat
+    pub fn subject(&self) -> String {
+        match &self.event {
+            Event::BillAdded { bill_id, .. } => format!("You were added to bill #{bill_id}"),
+            Event::PaymentDue { bill_id, overdue: false, .. } => format!("Your share of bill #{bill_id} is due soon"),
+            Event::PaymentDue { bill_id, overdue: true, .. } => format!("Your share of bill #{bill_id} is overdue"),
+            Event::XcmExecuted { payment_id, .. } => format!("Cross-chain payment #{payment_id} was executed"),
+        }
+    }
+
+    /// Human-readable text, for mail and chat
+    pub fn text(&self) -> String {
+        let details = match &self.event {
+            Event::BillAdded { amount, .. } => format!("Your share is {amount}."),
+            Event::PaymentDue { amount_due, deadline, .. } => {
+                format!("{amount_due} is due by {deadline} (ms since the Unix epoch).")
+            }
+            Event::XcmExecuted { sender, recipient, amount, destination_chain, .. } => {
+                format!("{amount} from {sender} to {recipient} arrived on chain {destination_chain}.")
+            }
+        };
+        format!("{}. {details}\nAccount: {}\nBlock: #{}", self.subject(), self.account, self.block_number)
+    }
+}
+
+/// The subscriptions of every watched account
+pub struct Subscriptions {
+    by_account: BTreeMap<AccountId32, Vec<Arc<Subscription>>>,
+}
+
+impl Subscriptions {
+    pub fn new(subscriptions: Vec<Subscription>) -> Self {
+        let mut by_account: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for subscription in subscriptions.into_iter().map(Arc::new) {
+            for account in &subscription.accounts {
+                by_account.entry(account.clone()).or_default().push(Arc::clone(&subscription));
+            }
+        }
+        Self { by_account }
+    }
+
+    pub fn watches(&self, account: &AccountId32) -> bool {
+        self.by_account.contains_key(account)
+    }
+
+    /// The subscriptions of `account` that want notifications of `kind`
+    pub fn matching(&self, account: &AccountId32, kind: Kind) -> impl Iterator<Item = &Arc<Subscription>> {
+        self.by_account
+            .get(account)
+            .into_iter()
+            .flatten()
+            .filter(move |subscription| subscription.wants(kind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(name: &str, accounts: Vec<AccountId32>, kinds: Vec<Kind>) -> Subscription {
+        Subscription {
+            name: name.into(),
+            accounts,
+            kinds,
+            webhook: None,
+            matrix: None,
+            email: None,
+        }
+    }
+
+    #[test]
+    fn subscriptions_filter_by_account_and_kind() {
+        let (alice, bob) = (AccountId32([1; 32]), AccountId32([2; 32]));
+        let subscriptions = Subscriptions::new(vec![
+            subscription("everything", vec![alice.clone(), bob.clone()], Vec::new()),
+            subscription("reminders", vec![alice.clone()], vec![Kind::PaymentDue]),
+        ]);
+        let names = |account, kind| {
+            subscriptions.matching(account, kind).map(|s| s.name.as_str()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&alice, Kind::PaymentDue), vec!["everything", "reminders"]);
+        assert_eq!(names(&alice, Kind::BillAdded), vec!["everything"]);
+        assert_eq!(names(&bob, Kind::PaymentDue), vec!["everything"]);
+        assert!(!subscriptions.watches(&AccountId32([3; 32])));
+    }
+
+    #[test]
+    fn notifications_serialize_with_their_kind() {
+        let notification = Notification {
+            id: "0xab-3-5Grw".into(),
+            account: "5Grw".into(),
+            block_number: 12,
+            block_hash: "0xab".into(),
+            event: Event::PaymentDue {
+                bill_id: 4,
+                amount_due: "1500".into(),
+                deadline: 1_700_000_000_000,
+                overdue: true,
+            },
+        };
+
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(json["kind"], "payment_due");
+        assert_eq!(json["amount_due"], "1500");
+        assert_eq!(notification.subject(), "Your share of bill #4 is overdue");
+    }
+}
@@ -0,0 +1,165 @@
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use anyhow::{bail, Context};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    config::{DeliverySettings, EmailSink, MatrixSink, Subscription, WebhookSink},
+    notification::Notification,
+};
+
+/// Header carrying the webhook body's HMAC-SHA256, as `sha256=<hex>`
+const SIGNATURE_HEADER: &str = "X-Xcc-Signature";
+
+/// Hex HMAC-SHA256 of a webhook body
+pub fn signature(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// One of a subscription's sinks
+#[derive(Clone, Copy)]
+enum Sink<'a> {
+    Webhook(&'a WebhookSink),
+    Matrix(&'a MatrixSink),
+    Email(&'a EmailSink),
+}
+
+impl Sink<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Sink::Webhook(_) => "webhook",
+            Sink::Matrix(_) => "matrix",
+            Sink::Email(_) => "email",
+        }
+    }
+}
+
+/// Delivers notifications to the sinks of subscriptions, retrying failures
+pub struct Dispatcher {
+    http: reqwest::Client,
+    settings: DeliverySettings,
+}
+
+impl Dispatcher {
+    pub fn new(settings: DeliverySettings) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder().timeout(settings.timeout()).build()?;
+        Ok(Self { http, settings })
+    }
+
+    /// Deliver a notification to every sink of a subscription in the background
+    pub fn dispatch(self: &Arc<Self>, subscription: Arc<Subscription>, notification: Arc<Notification>) {
+        let dispatcher = Arc::clone(self);
+        tokio::spawn(async move {
+            let sinks = [
+                subscription.webhook.as_ref().map(Sink::Webhook),
+                subscription.matrix.as_ref().map(Sink::Matrix),
+                subscription.email.as_ref().map(Sink::Email),
+            ];
+            for sink in sinks.into_iter().flatten() {
+                dispatcher.deliver(&subscription.name, sink, &notification).await;
+            }
+        });
+    }
+
+    /// Deliver to one sink, retrying with exponential backoff
+    async fn deliver(&self, subscription: &str, sink: Sink<'_>, notification: &Notification) {
+        let mut delay = Duration::from_millis(self.settings.retry_delay_ms);
+        for attempt in 1..=self.settings.max_attempts {
+            let result = match sink {
+                Sink::Webhook(webhook) => self.webhook(webhook, notification).await,
+                Sink::Matrix(matrix) => self.matrix(matrix, subscription, notification).await,
+                Sink::Email(email) => email_notification(email, notification).await,
+            };
+            match result {
+                Ok(()) => {
+                    tracing::info!(subscription, sink = sink.name(), id = %notification.id, "delivered");
+                    return;
+                }
+                Err(error) if attempt < self.settings.max_attempts => {
+                    tracing::warn!(subscription, sink = sink.name(), attempt, "delivery failed, retrying: {error:#}");
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+                Err(error) => {
+                    tracing::error!(subscription, sink = sink.name(), id = %notification.id, "giving up: {error:#}");
+                }
+            }
+        }
+    }
+
+    async fn webhook(&self, webhook: &WebhookSink, notification: &Notification) -> anyhow::Result<()> {
+        let body = serde_json::to_vec(notification)?;
+        let mut request = self
+            .http
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, format!("sha256={}", signature(secret.as_bytes(), &body)));
+        }
+        request.body(body).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    async fn matrix(&self, matrix: &MatrixSink, subscription: &str, notification: &Notification) -> anyhow::Result<()> {
+        // A fixed transaction ID makes the homeserver drop retried duplicates
+        let txn_id = format!("xcc-{subscription}-{}", notification.id);
+        let mut url = reqwest::Url::parse(&matrix.homeserver).context("invalid homeserver URL")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("invalid homeserver URL"))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", &matrix.room_id, "send", "m.room.message", &txn_id]);
+
+        let message = serde_json::json!({ "msgtype": "m.text", "body": notification.text() });
+        self.http
+            .put(url)
+            .bearer_auth(&matrix.access_token)
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Hand a mail to the local MTA through `sendmail -t`
+async fn email_notification(email: &EmailSink, notification: &Notification) -> anyhow::Result<()> {
+    let message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+        email.from,
+        email.to,
+        notification.subject(),
+        notification.text().replace('\n', "\r\n"),
+    );
+
+    let mut child = tokio::process::Command::new(&email.sendmail)
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("running {}", email.sendmail.display()))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(message.as_bytes()).await?;
+    drop(stdin);
+
+    let status = child.wait().await?;
+    if !status.success() {
+        bail!("{} exited with {status}", email.sendmail.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_signature_is_hmac_sha256() {
+        assert_eq!(
+            signature(b"key", b"The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
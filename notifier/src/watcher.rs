@@ -0,0 +1,173 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use subxt::{
+    blocks::Block,
+    utils::AccountId32,
+    OnlineClient, PolkadotConfig,
+};
+use xcc_sdk::{events::*, types::AccountId, EmittedEvent};
+
+use crate::{
+    config::Contracts,
+    notification::{Event, Notification, Subscriptions},
+    sink::Dispatcher,
+};
+
+type Client = OnlineClient<PolkadotConfig>;
+
+/// Delay before reconnecting to the chain
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+fn account(id: &AccountId) -> AccountId32 {
+    AccountId32(*AsRef::<[u8; 32]>::as_ref(id))
+}
+
+/// Turns the contracts' finalized events into notifications for subscribed accounts
+///
+/// Only finalized blocks are watched, as a notification cannot be taken
+/// back. Blocks finalized while disconnected are caught up on reconnect;
+/// those finalized while the notifier was not running are not.
+pub struct Watcher {
+    endpoint: String,
+    contracts: Contracts,
+    subscriptions: Subscriptions,
+    dispatcher: Arc<Dispatcher>,
+    /// Last block processed
+    last: Option<u32>,
+}
+
+impl Watcher {
+    pub fn new(endpoint: String, contracts: Contracts, subscriptions: Subscriptions, dispatcher: Arc<Dispatcher>) -> Self {
+        Self {
+            endpoint,
+            contracts,
+            subscriptions,
+            dispatcher,
+            last: None,
+        }
+    }
+
+    /// Watch the chain forever, reconnecting whenever the connection drops
+    pub async fn run(mut self) {
+        loop {
+            if let Err(error) = self.watch().await {
+                tracing::warn!("connection lost: {error:#}");
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn watch(&mut self) -> anyhow::Result<()> {
+        let api = Client::from_url(&self.endpoint).await?;
+        let mut client = xcc_sdk::Client::from_api(api.clone());
+        if let Some(bill_splitting) = &self.contracts.bill_splitting {
+            client = client.with_bill_splitting(bill_splitting.clone());
+        }
+        tracing::info!(endpoint = %self.endpoint, "watching finalized blocks");
+
+        let mut blocks = api.blocks().subscribe_finalized().await?;
+        while let Some(block) = blocks.next().await {
+            let block = block?;
+            let last = self.last.unwrap_or(block.number().saturating_sub(1));
+            if block.number() <= last {
+                continue;
+            }
+
+            // Walk back to the blocks finalized while disconnected
+            let mut branch = vec![block];
+            while let Some(oldest) = branch.last().filter(|oldest| oldest.number() > last + 1) {
+                let parent = oldest.header().parent_hash;
+                branch.push(api.blocks().at(parent).await?);
+            }
+
+            for block in branch.iter().rev() {
+                self.process(&client, block).await?;
+                self.last = Some(block.number());
+            }
+        }
+
+        Err(anyhow!("block subscription ended"))
+    }
+
+    async fn process(&self, client: &xcc_sdk::Client, block: &Block<PolkadotConfig, Client>) -> anyhow::Result<()> {
+        let block_hash = format!("0x{}", hex::encode(block.hash().0));
+        for (index, event) in block.events().await?.iter().enumerate() {
+            let Some(emitted) = EmittedEvent::from_details(&event?)? else {
+                continue;
+            };
+
+            for (account, event) in self.notifications(client, &emitted).await? {
+                let mut subscriptions = self.subscriptions.matching(&account, event.kind()).peekable();
+                if subscriptions.peek().is_none() {
+                    continue;
+                }
+
+                let notification = Arc::new(Notification {
+                    id: format!("{block_hash}-{index}-{account}"),
+                    account: account.to_string(),
+                    block_number: block.number(),
+                    block_hash: block_hash.clone(),
+                    event,
+                });
+                for subscription in subscriptions {
+                    self.dispatcher.dispatch(Arc::clone(subscription), Arc::clone(&notification));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The notifications an event raises for watched accounts
+    async fn notifications(&self, client: &xcc_sdk::Client, emitted: &EmittedEvent) -> anyhow::Result<Vec<(AccountId32, Event)>> {
+        let mut notifications = Vec::new();
+        if Some(&emitted.contract) == self.contracts.bill_splitting.as_ref() {
+            if let Some(created) = emitted.decode::<BillCreated>() {
+                // The event does not list the participants
+                let bills = client.bill_splitting();
+                let participants = bills.get_bill_participants(created.bill_id).fetch().await?.unwrap_or_default();
+                for participant in participants.iter().filter(|p| **p != created.creator).map(account) {
+                    if !self.subscriptions.watches(&participant) {
+                        continue;
+                    }
+                    let amount = bills.get_amount_due(created.bill_id, participant.clone()).fetch().await?;
+                    let event = Event::BillAdded {
+                        bill_id: created.bill_id,
+                        amount: amount.unwrap_or_default().to_string(),
+                    };
+                    notifications.push((participant, event));
+                }
+            } else if let Some(added) = emitted.decode::<ParticipantAdded>() {
+                let event = Event::BillAdded {
+                    bill_id: added.bill_id,
+                    amount: added.amount.to_string(),
+                };
+                notifications.push((account(&added.participant), event));
+            } else if let Some(due) = emitted.decode::<PaymentDue>() {
+                let event = Event::PaymentDue {
+                    bill_id: due.bill_id,
+                    amount_due: due.amount_due.to_string(),
+                    deadline: due.deadline,
+                    overdue: due.overdue,
+                };
+                notifications.push((account(&due.participant), event));
+            }
+        } else if Some(&emitted.contract) == self.contracts.xcm_handler.as_ref() {
+            if let Some(executed) = emitted.decode::<CrossChainPaymentExecuted>() {
+                let (sender, recipient) = (account(&executed.sender), account(&executed.recipient));
+                let event = Event::XcmExecuted {
+                    payment_id: executed.payment_id,
+                    sender: sender.to_string(),
+                    recipient: recipient.to_string(),
+                    amount: executed.amount.to_string(),
+                    destination_chain: executed.destination_chain,
+                };
+                if recipient != sender {
+                    notifications.push((recipient, event.clone()));
+                }
+                notifications.push((sender, event));
+            }
+        }
+        Ok(notifications)
+    }
+}
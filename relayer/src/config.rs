@@ -0,0 +1,137 @@
+use std::{net::SocketAddr, path::{Path, PathBuf}, str::FromStr};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use subxt::utils::AccountId32;
+use subxt_signer::{sr25519::Keypair, SecretUri};
+
+/// Relayer configuration file
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub signer: SignerConfig,
+    #[serde(default)]
+    pub relayer: RelayerSettings,
+    pub chains: Vec<ChainConfig>,
+}
+
+/// Key the relayer signs its transactions with
+#[derive(Debug, Deserialize)]
+pub struct SignerConfig {
+    /// Secret URI, e.g. `//Alice` or a mnemonic
+    pub suri: Option<String>,
+    /// Environment variable holding the secret URI
+    pub suri_env: Option<String>,
+}
+
+/// Delivery settings shared by all chains
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RelayerSettings {
+    /// Attempts per payment before giving up
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every attempt
+    pub retry_delay_ms: u64,
+    /// Gas limit of each contract call
+    pub gas_ref_time: u64,
+    pub gas_proof_size: u64,
+    /// Address to serve Prometheus metrics on
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+impl Default for RelayerSettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_delay_ms: 6_000,
+            gas_ref_time: 10_000_000_000,
+            gas_proof_size: 256 * 1024,
+            metrics_addr: None,
+        }
+    }
+}
+
+/// A chain whose XcmHandler the relayer watches
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainConfig {
+    pub name: String,
+    /// Parachain ID of the chain, as used by the contracts
+    pub chain_id: u32,
+    /// WebSocket RPC endpoint
+    pub endpoint: String,
+    /// SS58 address of the XcmHandler contract
+    pub xcm_handler: String,
+    /// Contract metadata emitted by `cargo contract build`
+    pub metadata: PathBuf,
+    /// Destination chains the relayer delivers payments to
+    pub destinations: Vec<u32>,
+}
+
+impl ChainConfig {
+    /// The XcmHandler address
+    pub fn contract(&self) -> anyhow::Result<AccountId32> {
+        AccountId32::from_str(&self.xcm_handler)
+            .map_err(|e| anyhow::anyhow!("{}: invalid xcm_handler address: {e:?}", self.name))
+    }
+}
+
+impl Config {
+    /// Read and check a configuration file
+    ///
+    /// Relative metadata paths are resolved against the file's directory.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut config: Config = toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        for chain in &mut config.chains {
+            if chain.metadata.is_relative() {
+                chain.metadata = base.join(&chain.metadata);
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.chains.is_empty() {
+            bail!("no chains configured");
+        }
+        if self.relayer.max_attempts == 0 {
+            bail!("max_attempts must be at least 1");
+        }
+        for chain in &self.chains {
+            chain.contract()?;
+            if chain.destinations.is_empty() {
+                bail!("{}: no destinations configured", chain.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// The relayer's signing key
+    pub fn keypair(&self) -> anyhow::Result<Keypair> {
+        let suri = match (&self.signer.suri, &self.signer.suri_env) {
+            (_, Some(var)) => std::env::var(var).with_context(|| format!("reading signer from ${var}"))?,
+            (Some(suri), None) => suri.clone(),
+            (None, None) => bail!("signer needs `suri` or `suri_env`"),
+        };
+        let uri = SecretUri::from_str(&suri).map_err(|e| anyhow::anyhow!("invalid signer: {e}"))?;
+        Keypair::from_uri(&uri).map_err(|e| anyhow::anyhow!("invalid signer: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_parses() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("config.example.toml");
+        let config = Config::load(&path).unwrap();
+
+        assert_eq!(config.relayer.max_attempts, 5);
+        assert_eq!(config.chains[0].destinations, vec![2000, 2004]);
+        assert!(config.chains[0].metadata.starts_with(env!("CARGO_MANIFEST_DIR")));
+    }
+}
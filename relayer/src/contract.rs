@@ -0,0 +1,130 @@
+//! The parts of the XcmHandler ABI the relayer uses
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use codec::{Decode, Encode};
+use serde::Deserialize;
+use subxt::utils::H256;
+
+/// Status a payment is created with when the contract holds its funds
+pub const STATUS_PENDING: u8 = 0;
+
+/// Selectors and event topics read from the contract metadata
+#[derive(Debug, Clone)]
+pub struct XcmHandlerAbi {
+    /// Signature topic of `CrossChainPaymentCreated`
+    pub payment_created: H256,
+    /// Selector of `execute_cross_chain_payment`
+    pub execute_selector: [u8; 4],
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    spec: Spec,
+}
+
+#[derive(Deserialize)]
+struct Spec {
+    messages: Vec<Labelled>,
+    events: Vec<Labelled>,
+}
+
+#[derive(Deserialize)]
+struct Labelled {
+    label: String,
+    selector: Option<String>,
+    signature_topic: Option<String>,
+}
+
+impl XcmHandlerAbi {
+    /// Load the ABI from the metadata file emitted by `cargo contract build`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let metadata: Metadata = serde_json::from_str(text)?;
+
+        let event = metadata
+            .spec
+            .events
+            .iter()
+            .find(|event| event.label == "CrossChainPaymentCreated")
+            .ok_or_else(|| anyhow!("no CrossChainPaymentCreated event"))?;
+        let message = metadata
+            .spec
+            .messages
+            .iter()
+            .find(|message| message.label == "execute_cross_chain_payment")
+            .ok_or_else(|| anyhow!("no execute_cross_chain_payment message"))?;
+
+        Ok(Self {
+            payment_created: H256(decode_hex(event.signature_topic.as_deref())?),
+            execute_selector: decode_hex(message.selector.as_deref())?,
+        })
+    }
+
+    /// Input data of an `execute_cross_chain_payment` call
+    pub fn execute_call(&self, payment_id: u32) -> Vec<u8> {
+        let mut data = self.execute_selector.to_vec();
+        payment_id.encode_to(&mut data);
+        data
+    }
+}
+
+fn decode_hex<const N: usize>(value: Option<&str>) -> anyhow::Result<[u8; N]> {
+    let value = value.ok_or_else(|| anyhow!("missing selector or topic"))?;
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(value.trim_start_matches("0x"), &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Where a cross-chain payment is delivered
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub enum Beneficiary {
+    AccountId32([u8; 32]),
+    AccountKey20([u8; 20]),
+}
+
+/// Data of a `CrossChainPaymentCreated` event, in field order
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+pub struct PaymentCreated {
+    pub payment_id: u32,
+    pub sender: [u8; 32],
+    pub recipient: [u8; 32],
+    pub beneficiary: Beneficiary,
+    pub asset_id: u32,
+    pub amount: u128,
+    pub message_type: u8,
+    pub source_chain: u32,
+    pub destination_chain: u32,
+    pub relayer_fee: u128,
+    pub delivery_fee: u128,
+    pub created_at: u64,
+    pub status: u8,
+}
+
+/// The contract address and data of a `Contracts::ContractEmitted` event
+pub fn decode_contract_emitted(mut field_bytes: &[u8]) -> Result<([u8; 32], Vec<u8>), codec::Error> {
+    <([u8; 32], Vec<u8>)>::decode(&mut field_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abi_parses_from_metadata() {
+        let metadata = r#"{"spec": {
+            "messages": [{"label": "execute_cross_chain_payment", "selector": "0x0a0b0c0d"}],
+            "events": [{"label": "CrossChainPaymentCreated", "signature_topic": "0x1111111111111111111111111111111111111111111111111111111111111111"}]
+        }}"#;
+        let abi = XcmHandlerAbi::parse(metadata).unwrap();
+
+        assert_eq!(abi.payment_created, H256([0x11; 32]));
+        assert_eq!(abi.execute_call(7), vec![0x0a, 0x0b, 0x0c, 0x0d, 7, 0, 0, 0]);
+        assert!(XcmHandlerAbi::parse(r#"{"spec": {"messages": [], "events": []}}"#).is_err());
+    }
+}
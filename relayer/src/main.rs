@@ -0,0 +1,54 @@
+//! Off-chain relayer for the 0xCC XcmHandler
+//!
+//! Watches the configured chains for new cross-chain payments and executes
+//! the ones towards the destinations it serves.
+//!
+//! Usage: `xcc-relayer [--config <path>]`, `relayer.toml` by default.
+
+mod config;
+mod contract;
+mod metrics;
+mod relayer;
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::bail;
+
+use crate::{config::Config, metrics::Metrics, relayer::ChainRelayer};
+
+fn config_path() -> anyhow::Result<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), args.next()) {
+        (None, _) => Ok(PathBuf::from("relayer.toml")),
+        (Some("--config" | "-c"), Some(path)) => Ok(PathBuf::from(path)),
+        _ => bail!("usage: xcc-relayer [--config <path>]"),
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config = Config::load(&config_path()?)?;
+    let signer = config.keypair()?;
+    let metrics = Arc::new(Metrics::default());
+
+    if let Some(addr) = config.relayer.metrics_addr {
+        let metrics = Arc::clone(&metrics);
+        tokio::spawn(async move {
+            if let Err(error) = metrics.serve(addr).await {
+                tracing::error!("metrics server stopped: {error}");
+            }
+        });
+    }
+
+    for chain in config.chains {
+        let relayer = ChainRelayer::new(chain, config.relayer.clone(), signer.clone(), Arc::clone(&metrics))?;
+        tokio::spawn(Arc::new(relayer).run());
+    }
+
+    tokio::signal::ctrl_c().await?;
+    tracing::info!("shutting down");
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Relayer counters, exposed in the Prometheus text format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Payments created towards a served destination
+    pub payments_seen: AtomicU64,
+    /// Payments whose execution was included on chain
+    pub payments_executed: AtomicU64,
+    /// Payments given up on after their last attempt or a contract rejection
+    pub payments_failed: AtomicU64,
+    /// Submissions retried after a failure
+    pub retries: AtomicU64,
+    /// Payments being delivered
+    pub in_flight: AtomicU64,
+    /// Connections to a chain lost and re-established
+    pub reconnects: AtomicU64,
+}
+
+impl Metrics {
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(counter: &AtomicU64) {
+        counter.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn render(&self) -> String {
+        let metrics = [
+            ("xcc_relayer_payments_seen_total", "counter", &self.payments_seen),
+            ("xcc_relayer_payments_executed_total", "counter", &self.payments_executed),
+            ("xcc_relayer_payments_failed_total", "counter", &self.payments_failed),
+            ("xcc_relayer_retries_total", "counter", &self.retries),
+            ("xcc_relayer_in_flight", "gauge", &self.in_flight),
+            ("xcc_relayer_reconnects_total", "counter", &self.reconnects),
+        ];
+
+        metrics
+            .iter()
+            .map(|(name, kind, value)| format!("# TYPE {name} {kind}\n{name} {}\n", value.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Answer every HTTP request on `addr` with the current metrics
+    pub async fn serve(&self, addr: SocketAddr) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("serving metrics on {addr}");
+
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            // The request itself is not inspected
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+
+            let body = self.render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            if let Err(error) = stream.write_all(response.as_bytes()).await {
+                tracing::debug!("metrics request failed: {error}");
+            }
+        }
+    }
+}
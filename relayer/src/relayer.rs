@@ -0,0 +1,240 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use codec::Decode;
+use subxt::{
+    config::PolkadotExtrinsicParamsBuilder,
+    dynamic::Value,
+    error::DispatchError,
+    utils::AccountId32,
+    OnlineClient, PolkadotConfig,
+};
+use subxt_signer::sr25519::Keypair;
+use tokio::sync::Mutex;
+
+use crate::{
+    config::{ChainConfig, RelayerSettings},
+    contract::{decode_contract_emitted, PaymentCreated, XcmHandlerAbi, STATUS_PENDING},
+    metrics::Metrics,
+};
+
+type Client = OnlineClient<PolkadotConfig>;
+
+/// Delay before reconnecting to a chain
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// Why a delivery attempt failed
+enum Failure {
+    /// The contract rejected the call, e.g. as the payment is no longer pending
+    Rejected(String),
+    /// The call did not make it on chain and may succeed later
+    Transient(subxt::Error),
+}
+
+/// Executes the payments created on one chain's XcmHandler
+///
+/// Payments are picked up from finalized `CrossChainPaymentCreated` events
+/// towards the configured destinations. Payments the contract already
+/// dispatched through XCM are created in transit and left to XCM.
+pub struct ChainRelayer {
+    chain: ChainConfig,
+    settings: RelayerSettings,
+    contract: AccountId32,
+    abi: XcmHandlerAbi,
+    signer: Keypair,
+    metrics: Arc<Metrics>,
+    /// Next nonce of the relayer account, `None` until fetched from the chain
+    nonce: Mutex<Option<u64>>,
+}
+
+impl ChainRelayer {
+    pub fn new(
+        chain: ChainConfig,
+        settings: RelayerSettings,
+        signer: Keypair,
+        metrics: Arc<Metrics>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            contract: chain.contract()?,
+            abi: XcmHandlerAbi::load(&chain.metadata)?,
+            chain,
+            settings,
+            signer,
+            metrics,
+            nonce: Mutex::new(None),
+        })
+    }
+
+    /// Watch the chain forever, reconnecting whenever the connection drops
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            if let Err(error) = self.watch().await {
+                tracing::warn!(chain = %self.chain.name, "connection lost: {error:#}");
+            }
+            Metrics::inc(&self.metrics.reconnects);
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn watch(self: &Arc<Self>) -> anyhow::Result<()> {
+        let api = Client::from_url(&self.chain.endpoint).await?;
+        // The account may have been used elsewhere while disconnected
+        *self.nonce.lock().await = None;
+        tracing::info!(
+            chain = %self.chain.name,
+            chain_id = self.chain.chain_id,
+            contract = %self.contract,
+            "watching payments"
+        );
+
+        let mut blocks = api.blocks().subscribe_finalized().await?;
+        while let Some(block) = blocks.next().await {
+            let events = block?.events().await?;
+            for event in events.iter() {
+                let event = event?;
+                if event.pallet_name() != "Contracts"
+                    || event.variant_name() != "ContractEmitted"
+                    || event.topics().first() != Some(&self.abi.payment_created)
+                {
+                    continue;
+                }
+
+                match self.decode_payment(event.field_bytes()) {
+                    Ok(Some(payment)) => self.spawn_delivery(&api, payment),
+                    Ok(None) => {}
+                    Err(error) => tracing::warn!(chain = %self.chain.name, "undecodable payment event: {error}"),
+                }
+            }
+        }
+
+        Err(anyhow!("block subscription ended"))
+    }
+
+    /// The payment of a `ContractEmitted` event, if it is ours to deliver
+    fn decode_payment(&self, field_bytes: &[u8]) -> Result<Option<PaymentCreated>, codec::Error> {
+        let (contract, data) = decode_contract_emitted(field_bytes)?;
+        if contract != self.contract.0 {
+            return Ok(None);
+        }
+
+        let payment = PaymentCreated::decode(&mut &data[..])?;
+        if !self.chain.destinations.contains(&payment.destination_chain) {
+            return Ok(None);
+        }
+        if payment.status != STATUS_PENDING {
+            tracing::debug!(payment_id = payment.payment_id, "dispatched through XCM, skipping");
+            return Ok(None);
+        }
+
+        Ok(Some(payment))
+    }
+
+    fn spawn_delivery(self: &Arc<Self>, api: &Client, payment: PaymentCreated) {
+        tracing::info!(
+            chain = %self.chain.name,
+            payment_id = payment.payment_id,
+            destination = payment.destination_chain,
+            amount = payment.amount,
+            "payment created"
+        );
+        Metrics::inc(&self.metrics.payments_seen);
+
+        let relayer = Arc::clone(self);
+        let api = api.clone();
+        tokio::spawn(async move { relayer.deliver(&api, payment.payment_id).await });
+    }
+
+    /// Execute a payment, retrying with exponential backoff
+    async fn deliver(&self, api: &Client, payment_id: u32) {
+        Metrics::inc(&self.metrics.in_flight);
+        let mut delay = Duration::from_millis(self.settings.retry_delay_ms);
+
+        for attempt in 1..=self.settings.max_attempts {
+            match self.execute(api, payment_id).await {
+                Ok(()) => {
+                    tracing::info!(chain = %self.chain.name, payment_id, "payment executed");
+                    Metrics::inc(&self.metrics.payments_executed);
+                    break;
+                }
+                Err(Failure::Rejected(reason)) => {
+                    tracing::warn!(chain = %self.chain.name, payment_id, "execution rejected: {reason}");
+                    Metrics::inc(&self.metrics.payments_failed);
+                    break;
+                }
+                Err(Failure::Transient(error)) if attempt < self.settings.max_attempts => {
+                    tracing::warn!(chain = %self.chain.name, payment_id, attempt, "execution failed, retrying: {error}");
+                    Metrics::inc(&self.metrics.retries);
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+                Err(Failure::Transient(error)) => {
+                    tracing::error!(chain = %self.chain.name, payment_id, "giving up: {error}");
+                    Metrics::inc(&self.metrics.payments_failed);
+                }
+            }
+        }
+
+        Metrics::dec(&self.metrics.in_flight);
+    }
+
+    /// Submit `execute_cross_chain_payment` and wait for it to be finalized
+    async fn execute(&self, api: &Client, payment_id: u32) -> Result<(), Failure> {
+        let call = subxt::dynamic::tx(
+            "Contracts",
+            "call",
+            vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(self.contract.0)]),
+                Value::u128(0),
+                Value::named_composite([
+                    ("ref_time", Value::u128(self.settings.gas_ref_time.into())),
+                    ("proof_size", Value::u128(self.settings.gas_proof_size.into())),
+                ]),
+                Value::unnamed_variant("None", []),
+                Value::from_bytes(self.abi.execute_call(payment_id)),
+            ],
+        );
+
+        // Sign and submit under the lock, so concurrent deliveries use consecutive nonces
+        let progress = {
+            let mut nonce = self.nonce.lock().await;
+            let next = match *nonce {
+                Some(next) => next,
+                None => api
+                    .tx()
+                    .account_nonce(&self.signer.public_key().into())
+                    .await
+                    .map_err(Failure::Transient)?,
+            };
+
+            let params = PolkadotExtrinsicParamsBuilder::new().nonce(next).build();
+            let tx = api
+                .tx()
+                .create_signed_offline(&call, &self.signer, params)
+                .map_err(Failure::Transient)?;
+            match tx.submit_and_watch().await {
+                Ok(progress) => {
+                    *nonce = Some(next + 1);
+                    progress
+                }
+                Err(error) => {
+                    // Resync, as the nonce may be stale
+                    *nonce = None;
+                    return Err(Failure::Transient(error));
+                }
+            }
+        };
+
+        match progress.wait_for_finalized_success().await {
+            Ok(_) => Ok(()),
+            Err(subxt::Error::Runtime(DispatchError::Module(error))) => {
+                let details = error.details_string();
+                if details == "Contracts::ContractReverted" {
+                    Err(Failure::Rejected(details))
+                } else {
+                    Err(Failure::Transient(subxt::Error::Runtime(DispatchError::Module(error))))
+                }
+            }
+            Err(error) => Err(Failure::Transient(error)),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use ink::selector_bytes;
+use subxt::utils::AccountId32;
+
+use crate::{
+    types::{AccountId, Balance, BillMetadata, BillSplittingError, IntentAsset, PaymentIntent},
+    Call, Client, Query,
+};
+
+/// A bill as returned by `get_bill_info`: creator, total, participant count,
+/// paid count, completion, deadline and metadata
+pub type BillInfo = (AccountId, Balance, u32, u32, bool, u64, BillMetadata);
+
+/// Bindings of the BillSplitting contract
+pub struct BillSplitting<'a> {
+    client: &'a Client,
+    address: Result<AccountId32, &'static str>,
+}
+
+impl<'a> BillSplitting<'a> {
+    pub(crate) fn new(client: &'a Client, address: Result<AccountId32, &'static str>) -> Self {
+        Self { client, address }
+    }
+
+    fn call<R: crate::MessageOutput>(&self, selector: [u8; 4], args: impl codec::Encode) -> Call<'a, R> {
+        Call::new(self.client, self.address.clone(), selector, args)
+    }
+
+    fn query<T: codec::Decode>(&self, selector: [u8; 4], args: impl codec::Encode) -> Query<'a, T> {
+        Query::new(self.client, self.address.clone(), selector, args)
+    }
+
+    /// Create a bill with a share per participant, returning its ID
+    pub fn create_bill(
+        &self,
+        total_amount: Balance,
+        participants: Vec<AccountId32>,
+        individual_amounts: Vec<Balance>,
+        deadline: u64,
+    ) -> Call<'a, Result<u32, BillSplittingError>> {
+        self.call(
+            selector_bytes!("create_bill"),
+            (total_amount, participants, individual_amounts, deadline),
+        )
+    }
+
+    /// Create a bill split evenly, the first participants covering any remainder
+    pub fn create_equal_bill(
+        &self,
+        total_amount: Balance,
+        participants: Vec<AccountId32>,
+        deadline: u64,
+    ) -> Call<'a, Result<u32, BillSplittingError>> {
+        let shares = equal_shares(total_amount, participants.len());
+        self.create_bill(total_amount, participants, shares, deadline)
+    }
+
+    /// Pay the caller's share plus any late fee, transferring it natively
+    ///
+    /// For bills settled on a Payment ledger, reset the value with `.value(0)`.
+    pub fn pay_bill(&self, bill_id: u32, amount: Balance) -> Call<'a, Result<(), BillSplittingError>> {
+        self.call(selector_bytes!("pay_bill"), (bill_id, amount)).value(amount)
+    }
+
+    /// Execute a payment intent, transferring the amount of native intents
+    pub fn execute_intent(&self, intent: PaymentIntent) -> Call<'a, Result<(), BillSplittingError>> {
+        let value = if intent.asset == IntentAsset::Native { intent.amount } else { 0 };
+        self.call(selector_bytes!("execute_intent"), intent).value(value)
+    }
+
+    pub fn cancel_bill(&self, bill_id: u32) -> Call<'a, Result<(), BillSplittingError>> {
+        self.call(selector_bytes!("cancel_bill"), bill_id)
+    }
+
+    /// Deposit native funds that auto-pay settles shares from
+    pub fn deposit(&self, amount: Balance) -> Call<'a, Result<(), BillSplittingError>> {
+        self.call(selector_bytes!("deposit"), ()).value(amount)
+    }
+
+    pub fn withdraw(&self, amount: Balance) -> Call<'a, Result<(), BillSplittingError>> {
+        self.call(selector_bytes!("withdraw"), amount)
+    }
+
+    /// Enable auto-pay of bills created by `creator` up to `per_bill_cap` per share, or disable it with `None`
+    pub fn set_auto_pay(
+        &self,
+        creator: AccountId32,
+        per_bill_cap: Option<Balance>,
+    ) -> Call<'a, Result<(), BillSplittingError>> {
+        self.call(selector_bytes!("set_auto_pay"), (creator, per_bill_cap))
+    }
+
+    /// Settle every open share of the caller that auto-pay covers, returning how many were paid
+    pub fn auto_settle_all(&self) -> Call<'a, Result<u32, BillSplittingError>> {
+        self.call(selector_bytes!("auto_settle_all"), ())
+    }
+
+    pub fn get_bill_info(&self, bill_id: u32) -> Query<'a, Option<BillInfo>> {
+        self.query(selector_bytes!("get_bill_info"), bill_id)
+    }
+
+    /// Participants of a bill, in the order of their shares
+    pub fn get_bill_participants(&self, bill_id: u32) -> Query<'a, Option<Vec<AccountId>>> {
+        self.query(selector_bytes!("get_bill_participants"), bill_id)
+    }
+
+    /// What a participant still owes on a bill, late fee included
+    pub fn get_amount_due(&self, bill_id: u32, participant: AccountId32) -> Query<'a, Option<Balance>> {
+        self.query(selector_bytes!("get_amount_due"), (bill_id, participant))
+    }
+}
+
+fn equal_shares(total: Balance, participants: usize) -> Vec<Balance> {
+    let count = participants.max(1) as Balance;
+    let (share, remainder) = (total / count, total % count);
+    (0..participants as Balance)
+        .map(|index| share + Balance::from(index < remainder))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_shares_cover_the_total() {
+        assert_eq!(equal_shares(100, 3), vec![34, 33, 33]);
+        assert_eq!(equal_shares(90, 3), vec![30, 30, 30]);
+        assert!(equal_shares(90, 0).is_empty());
+    }
+}
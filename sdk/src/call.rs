@@ -0,0 +1,344 @@
+//! Builders for contract calls and queries
+
+use std::{convert::Infallible, marker::PhantomData};
+
+use codec::{Decode, Encode};
+use subxt::{
+    dynamic::Value,
+    utils::{AccountId32, H256},
+};
+use subxt_signer::sr25519::Keypair;
+
+use crate::{events::EmittedEvent, Client, ContractEvent, Error};
+
+/// Set by ink! when a message failed and its changes were reverted
+const FLAG_REVERT: u32 = 1;
+
+/// Weight of a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub struct Weight {
+    #[codec(compact)]
+    pub ref_time: u64,
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+/// What a message returns, split into its success and error types
+pub trait MessageOutput: Decode {
+    type Ok;
+    type Err;
+
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+}
+
+impl<T: Decode, E: Decode> MessageOutput for Result<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+impl MessageOutput for () {
+    type Ok = ();
+    type Err = Infallible;
+
+    fn into_result(self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Decode)]
+enum StorageDeposit {
+    Refund(u128),
+    Charge(u128),
+}
+
+#[derive(Debug, Decode)]
+struct ExecReturnValue {
+    flags: u32,
+    data: Vec<u8>,
+}
+
+/// `sp_runtime::DispatchError`, as far as needed to describe it
+#[allow(dead_code)] // Fields are only shown through `Debug`
+#[derive(Debug, Decode)]
+enum DispatchError {
+    Other,
+    CannotLookup,
+    BadOrigin,
+    Module { index: u8, error: [u8; 4] },
+    ConsumerRemaining,
+    NoProviders,
+    TooManyConsumers,
+    Token(u8),
+    Arithmetic(u8),
+    Transactional(u8),
+    Exhausted,
+    Corruption,
+    Unavailable,
+    RootNotAllowed,
+}
+
+/// Result of the `ContractsApi_call` runtime API, up to the fields used here
+#[derive(Debug, Decode)]
+struct ContractExecResult {
+    #[allow(dead_code)]
+    gas_consumed: Weight,
+    gas_required: Weight,
+    storage_deposit: StorageDeposit,
+    #[allow(dead_code)]
+    debug_message: Vec<u8>,
+    result: Result<ExecReturnValue, DispatchError>,
+}
+
+/// Outcome of running a message without submitting it
+#[derive(Debug)]
+pub struct DryRun<T> {
+    pub output: T,
+    pub gas_required: Weight,
+    /// Storage deposit charged, negative when refunded
+    pub storage_deposit: i128,
+}
+
+/// A submitted call, included in a finalized block
+#[derive(Debug)]
+pub struct Receipt<T> {
+    pub block_hash: H256,
+    pub extrinsic_hash: H256,
+    /// What the message returned in the dry run right before submission
+    pub output: T,
+    /// Events emitted by contracts during the call
+    pub events: Vec<EmittedEvent>,
+}
+
+impl<T> Receipt<T> {
+    /// The emitted events of type `E`
+    pub fn events_of<E: ContractEvent>(&self) -> Vec<E> {
+        self.events.iter().filter_map(EmittedEvent::decode).collect()
+    }
+}
+
+/// A contract and the encoded message to send it
+struct Message<'a> {
+    client: &'a Client,
+    contract: Result<AccountId32, &'static str>,
+    input: Vec<u8>,
+}
+
+impl<'a> Message<'a> {
+    fn new(client: &'a Client, contract: Result<AccountId32, &'static str>, selector: [u8; 4], args: impl Encode) -> Self {
+        let mut input = selector.to_vec();
+        args.encode_to(&mut input);
+        Self { client, contract, input }
+    }
+
+    async fn dry_run<R: MessageOutput>(
+        &self,
+        origin: AccountId32,
+        value: u128,
+    ) -> Result<DryRun<R::Ok>, Error<R::Err>> {
+        let contract = self.contract.clone().map_err(Error::ContractNotSet)?;
+        let params = (origin, contract, value, None::<Weight>, None::<u128>, &self.input).encode();
+        let exec: ContractExecResult = self
+            .client
+            .api()
+            .runtime_api()
+            .at_latest()
+            .await?
+            .call_raw("ContractsApi_call", Some(&params))
+            .await?;
+
+        let returned = exec
+            .result
+            .map_err(|error| Error::Dispatch(self.client.describe(error)))?;
+        let output = match <Result<R, ink::LangError>>::decode(&mut &returned.data[..])? {
+            Ok(output) => output.into_result().map_err(Error::Contract)?,
+            Err(error) => return Err(Error::Lang(error)),
+        };
+        // Messages without a `Result` only revert by trapping
+        if returned.flags & FLAG_REVERT != 0 {
+            return Err(Error::Dispatch("reverted".into()));
+        }
+
+        Ok(DryRun {
+            output,
+            gas_required: exec.gas_required,
+            storage_deposit: match exec.storage_deposit {
+                StorageDeposit::Charge(amount) => amount as i128,
+                StorageDeposit::Refund(amount) => -(amount as i128),
+            },
+        })
+    }
+}
+
+/// A state-changing message, dry-run before it is submitted
+#[must_use = "calls do nothing until dry-run or submitted"]
+pub struct Call<'a, R> {
+    message: Message<'a>,
+    value: u128,
+    gas_limit: Option<Weight>,
+    signer: Option<&'a Keypair>,
+    output: PhantomData<fn() -> R>,
+}
+
+impl<'a, R: MessageOutput> Call<'a, R> {
+    pub(crate) fn new(
+        client: &'a Client,
+        contract: Result<AccountId32, &'static str>,
+        selector: [u8; 4],
+        args: impl Encode,
+    ) -> Self {
+        Self {
+            message: Message::new(client, contract, selector, args),
+            value: 0,
+            gas_limit: None,
+            signer: None,
+            output: PhantomData,
+        }
+    }
+
+    /// Transfer `value` with the call, for payable messages
+    pub fn value(mut self, value: u128) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Use this gas limit instead of the dry run's estimate
+    pub fn gas_limit(mut self, gas_limit: Weight) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Sign the call with `signer`
+    pub fn signed(mut self, signer: &'a Keypair) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Run the call without submitting it, from the signer or a zero account
+    pub async fn dry_run(&self) -> Result<DryRun<R::Ok>, Error<R::Err>> {
+        let origin = self
+            .signer
+            .map(|signer| signer.public_key().into())
+            .unwrap_or(AccountId32([0; 32]));
+        self.message.dry_run::<R>(origin, self.value).await
+    }
+
+    /// Dry-run the call, then submit it and wait for it to be finalized
+    ///
+    /// Calls failing in the dry run are not submitted.
+    pub async fn submit(self) -> Result<Receipt<R::Ok>, Error<R::Err>> {
+        let signer = self.signer.ok_or(Error::NoSigner)?;
+        let dry_run = self.dry_run().await?;
+        let contract = self.message.contract.clone().map_err(Error::ContractNotSet)?;
+        let gas_limit = self.gas_limit.unwrap_or(dry_run.gas_required);
+
+        let call = subxt::dynamic::tx(
+            "Contracts",
+            "call",
+            vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(contract.0)]),
+                Value::u128(self.value),
+                Value::named_composite([
+                    ("ref_time", Value::u128(gas_limit.ref_time.into())),
+                    ("proof_size", Value::u128(gas_limit.proof_size.into())),
+                ]),
+                Value::unnamed_variant("None", []),
+                Value::from_bytes(&self.message.input),
+            ],
+        );
+
+        let events = self
+            .message
+            .client
+            .api()
+            .tx()
+            .sign_and_submit_then_watch_default(&call, signer)
+            .await?
+            .wait_for_finalized_success()
+            .await?;
+
+        let mut emitted = Vec::new();
+        for event in events.iter() {
+            emitted.extend(EmittedEvent::from_details(&event?)?);
+        }
+
+        Ok(Receipt {
+            block_hash: events.block_hash(),
+            extrinsic_hash: events.extrinsic_hash(),
+            output: dry_run.output,
+            events: emitted,
+        })
+    }
+}
+
+/// A read-only message
+#[must_use = "queries do nothing until fetched"]
+pub struct Query<'a, T> {
+    message: Message<'a>,
+    origin: AccountId32,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Decode> Query<'a, T> {
+    pub(crate) fn new(
+        client: &'a Client,
+        contract: Result<AccountId32, &'static str>,
+        selector: [u8; 4],
+        args: impl Encode,
+    ) -> Self {
+        Self {
+            message: Message::new(client, contract, selector, args),
+            origin: AccountId32([0; 32]),
+            output: PhantomData,
+        }
+    }
+
+    /// Query as `origin`, for messages depending on the caller
+    pub fn from(mut self, origin: AccountId32) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Run the query against the latest block
+    pub async fn fetch(&self) -> Result<T, Error> {
+        let dry_run = self.message.dry_run::<Plain<T>>(self.origin.clone(), 0).await?;
+        Ok(dry_run.output)
+    }
+}
+
+/// Output of a message not returning a `Result`
+struct Plain<T>(T);
+
+impl<T: Decode> Decode for Plain<T> {
+    fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+        T::decode(input).map(Plain)
+    }
+}
+
+impl<T: Decode> MessageOutput for Plain<T> {
+    type Ok = T;
+    type Err = Infallible;
+
+    fn into_result(self) -> Result<T, Infallible> {
+        Ok(self.0)
+    }
+}
+
+impl Client {
+    fn describe(&self, error: DispatchError) -> String {
+        let DispatchError::Module { index, error } = error else {
+            return format!("{error:?}");
+        };
+        let metadata = self.api().metadata();
+        metadata
+            .pallet_by_index(index)
+            .and_then(|pallet| {
+                let variant = pallet.error_variant_by_index(error[0])?;
+                Some(format!("{}::{}", pallet.name(), variant.name))
+            })
+            .unwrap_or_else(|| format!("module error {index}:{}", error[0]))
+    }
+}
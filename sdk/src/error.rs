@@ -0,0 +1,46 @@
+use std::{convert::Infallible, fmt};
+
+/// Failure of a contract call or query, `E` being the contract's error type
+#[derive(Debug)]
+pub enum Error<E = Infallible> {
+    /// The message returned an error
+    Contract(E),
+    /// ink! could not dispatch the message, e.g. as the selector is unknown
+    Lang(ink::LangError),
+    /// The contract trapped or the extrinsic failed
+    Dispatch(String),
+    /// No address was configured for the contract
+    ContractNotSet(&'static str),
+    /// Submitting needs a signer, see `Call::signed`
+    NoSigner,
+    Rpc(subxt::Error),
+    Decode(codec::Error),
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Contract(error) => write!(f, "contract error: {error:?}"),
+            Error::Lang(error) => write!(f, "message not dispatched: {error:?}"),
+            Error::Dispatch(error) => write!(f, "dispatch failed: {error}"),
+            Error::ContractNotSet(name) => write!(f, "no {name} contract address configured"),
+            Error::NoSigner => write!(f, "submitting needs a signer"),
+            Error::Rpc(error) => write!(f, "rpc error: {error}"),
+            Error::Decode(error) => write!(f, "decoding failed: {error}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+impl<E> From<subxt::Error> for Error<E> {
+    fn from(error: subxt::Error) -> Self {
+        Error::Rpc(error)
+    }
+}
+
+impl<E> From<codec::Error> for Error<E> {
+    fn from(error: codec::Error) -> Self {
+        Error::Decode(error)
+    }
+}
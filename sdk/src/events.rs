@@ -0,0 +1,272 @@
+//! Typed contract events
+//!
+//! Each event mirrors the fields of the contract's event, in order, and is
+//! recognised by the signature topic ink! derives for it.
+
+use codec::Decode;
+use ink::primitives::{AccountId, Hash};
+use subxt::{
+    events::EventDetails,
+    utils::{AccountId32, H256},
+    PolkadotConfig,
+};
+use xcm_handler::{Beneficiary, PaymentStatus, XcmMessageType};
+
+type Balance = u128;
+
+/// An event emitted by a contract, as deposited by `Contracts::ContractEmitted`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedEvent {
+    pub contract: AccountId32,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+impl EmittedEvent {
+    /// The contract event in a runtime event, if it is `Contracts::ContractEmitted`
+    pub fn from_details(event: &EventDetails<PolkadotConfig>) -> Result<Option<Self>, codec::Error> {
+        if event.pallet_name() != "Contracts" || event.variant_name() != "ContractEmitted" {
+            return Ok(None);
+        }
+        let (contract, data) = <(AccountId32, Vec<u8>)>::decode(&mut event.field_bytes())?;
+        Ok(Some(Self {
+            contract,
+            topics: event.topics().to_vec(),
+            data,
+        }))
+    }
+
+    /// Decode the event as `E`, if it is one
+    pub fn decode<E: ContractEvent>(&self) -> Option<E> {
+        if self.topics.first()?.0 != E::SIGNATURE_TOPIC {
+            return None;
+        }
+        E::decode(&mut &self.data[..]).ok()
+    }
+}
+
+/// An event of one of the contracts
+pub trait ContractEvent: Decode {
+    /// First topic of every emission of the event
+    const SIGNATURE_TOPIC: [u8; 32];
+}
+
+const fn signature_topic<E: ink::env::Event>() -> [u8; 32] {
+    match E::SIGNATURE_TOPIC {
+        Some(topic) => topic,
+        None => panic!("anonymous events have no signature topic"),
+    }
+}
+
+macro_rules! contract_event {
+    ($(#[$doc:meta])* $name:ident = $source:path { $($field:ident: $ty:ty,)* }) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq, Decode)]
+        pub struct $name {
+            $(pub $field: $ty,)*
+        }
+
+        impl ContractEvent for $name {
+            const SIGNATURE_TOPIC: [u8; 32] = signature_topic::<$source>();
+        }
+    };
+}
+
+contract_event! {
+    /// Ledger balance was sent
+    PaymentSent = payment::PaymentSent {
+        from: AccountId,
+        to: AccountId,
+        amount: Balance,
+        transaction_id: u32,
+    }
+}
+
+contract_event! {
+    /// Ledger balance was locked for a transfer to another chain
+    CrossChainPaymentInitiated = payment::CrossChainPaymentInitiated {
+        transaction_id: u32,
+        from: AccountId,
+        to: AccountId,
+        amount: Balance,
+        destination_chain: u32,
+    }
+}
+
+contract_event! {
+    /// A bill was created
+    BillCreated = bill_splitting::BillCreated {
+        bill_id: u32,
+        creator: AccountId,
+        total_amount: Balance,
+        participant_count: u32,
+    }
+}
+
+contract_event! {
+    /// A participant paid their share of a bill
+    BillPaid = bill_splitting::BillPaid {
+        bill_id: u32,
+        payer: AccountId,
+        amount: Balance,
+    }
+}
+
+contract_event! {
+    /// Every share of a bill was settled
+    BillCompleted = bill_splitting::BillCompleted {
+        bill_id: u32,
+        creator: AccountId,
+        total_paid: Balance,
+    }
+}
+
+contract_event! {
+    /// A bill was cancelled and its escrowed payments refunded
+    BillCancelled = bill_splitting::BillCancelled {
+        bill_id: u32,
+        creator: AccountId,
+        refunded_amount: Balance,
+    }
+}
+
+contract_event! {
+    /// A participant was added to an open bill
+    ParticipantAdded = bill_splitting::ParticipantAdded {
+        bill_id: u32,
+        participant: AccountId,
+        amount: Balance,
+        new_total: Balance,
+    }
+}
+
+contract_event! {
+    /// A participant was reminded of their unpaid share of a bill
+    PaymentDue = bill_splitting::PaymentDue {
+        bill_id: u32,
+        participant: AccountId,
+        amount_due: Balance,
+        deadline: u64,
+        overdue: bool,
+    }
+}
+
+contract_event! {
+    /// A cross-chain payment was created
+    CrossChainPaymentCreated = xcm_handler::CrossChainPaymentCreated {
+        payment_id: u32,
+        sender: AccountId,
+        recipient: AccountId,
+        beneficiary: Beneficiary,
+        asset_id: u32,
+        amount: Balance,
+        message_type: XcmMessageType,
+        source_chain: u32,
+        destination_chain: u32,
+        relayer_fee: Balance,
+        delivery_fee: Balance,
+        created_at: u64,
+        status: PaymentStatus,
+    }
+}
+
+contract_event! {
+    /// A cross-chain payment was executed on its destination
+    CrossChainPaymentExecuted = xcm_handler::CrossChainPaymentExecuted {
+        payment_id: u32,
+        sender: AccountId,
+        recipient: AccountId,
+        beneficiary: Beneficiary,
+        asset_id: u32,
+        amount: Balance,
+        message_type: XcmMessageType,
+        source_chain: u32,
+        destination_chain: u32,
+        executor: AccountId,
+        relayer_fee: Balance,
+        executed_at: u64,
+        status: PaymentStatus,
+    }
+}
+
+contract_event! {
+    /// A payment from another chain was credited by its relayer
+    InboundPaymentReceived = xcm_handler::InboundPaymentReceived {
+        source_chain: u32,
+        message_hash: Hash,
+        sender: AccountId,
+        recipient: AccountId,
+        asset_id: u32,
+        amount: Balance,
+        nonce: u64,
+        relayer: AccountId,
+    }
+}
+
+contract_event! {
+    /// A cross-chain payment moved to another status
+    PaymentStatusChanged = xcm_handler::PaymentStatusChanged {
+        payment_id: u32,
+        from: PaymentStatus,
+        to: PaymentStatus,
+        timestamp: u64,
+    }
+}
+
+contract_event! {
+    /// A pending cross-chain payment was cancelled by its sender
+    CrossChainPaymentCancelled = xcm_handler::CrossChainPaymentCancelled {
+        payment_id: u32,
+        sender: AccountId,
+        amount: Balance,
+        status: PaymentStatus,
+    }
+}
+
+contract_event! {
+    /// A timed-out cross-chain payment was refunded to its sender
+    CrossChainPaymentRefunded = xcm_handler::CrossChainPaymentRefunded {
+        payment_id: u32,
+        sender: AccountId,
+        amount: Balance,
+        status: PaymentStatus,
+    }
+}
+
+contract_event! {
+    /// A dispatched cross-chain payment was not confirmed in time
+    CrossChainPaymentFailed = xcm_handler::CrossChainPaymentFailed {
+        payment_id: u32,
+        sender: AccountId,
+        amount: Balance,
+        status: PaymentStatus,
+        error_code: u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+
+    #[test]
+    fn events_decode_by_signature_topic() {
+        let (from, to) = (AccountId::from([1; 32]), AccountId::from([2; 32]));
+        let sent = PaymentSent {
+            from,
+            to,
+            amount: 500,
+            transaction_id: 7,
+        };
+        let mut emitted = EmittedEvent {
+            contract: AccountId32([9; 32]),
+            topics: vec![H256(PaymentSent::SIGNATURE_TOPIC)],
+            data: (from, to, 500u128, 7u32).encode(),
+        };
+        assert_eq!(emitted.decode::<PaymentSent>(), Some(sent));
+        assert_eq!(emitted.decode::<BillPaid>(), None);
+
+        emitted.topics = vec![H256(BillPaid::SIGNATURE_TOPIC)];
+        assert_eq!(emitted.decode::<PaymentSent>(), None);
+    }
+}
@@ -0,0 +1,70 @@
+use ink::selector_bytes;
+use subxt::utils::AccountId32;
+
+use crate::{
+    types::{Balance, PaymentError},
+    Call, Client, Query,
+};
+
+/// Bindings of the Payment ledger
+pub struct Payment<'a> {
+    client: &'a Client,
+    address: Result<AccountId32, &'static str>,
+}
+
+impl<'a> Payment<'a> {
+    pub(crate) fn new(client: &'a Client, address: Result<AccountId32, &'static str>) -> Self {
+        Self { client, address }
+    }
+
+    fn call<R: crate::MessageOutput>(&self, selector: [u8; 4], args: impl codec::Encode) -> Call<'a, R> {
+        Call::new(self.client, self.address.clone(), selector, args)
+    }
+
+    fn query<T: codec::Decode>(&self, selector: [u8; 4], args: impl codec::Encode) -> Query<'a, T> {
+        Query::new(self.client, self.address.clone(), selector, args)
+    }
+
+    /// Send ledger balance, returning the transaction ID
+    pub fn send(&self, to: AccountId32, amount: Balance) -> Call<'a, Result<u32, PaymentError>> {
+        self.call(selector_bytes!("send_payment"), (to, amount))
+    }
+
+    /// Send ledger balance to the holder of a handle, returning the transaction ID
+    pub fn send_to_handle(&self, handle: String, amount: Balance) -> Call<'a, Result<u32, PaymentError>> {
+        self.call(selector_bytes!("send_payment_to_handle"), (handle, amount))
+    }
+
+    /// Lock ledger balance for a transfer to another chain, returning the transaction ID
+    pub fn initiate_cross_chain(
+        &self,
+        to: AccountId32,
+        amount: Balance,
+        destination_chain: u32,
+    ) -> Call<'a, Result<u32, PaymentError>> {
+        self.call(selector_bytes!("initiate_cross_chain_payment"), (to, amount, destination_chain))
+    }
+
+    /// Allow `spender` to move up to `amount` of the caller's balance
+    pub fn approve(&self, spender: AccountId32, amount: Balance) -> Call<'a, Result<(), PaymentError>> {
+        self.call(selector_bytes!("approve"), (spender, amount))
+    }
+
+    /// Send balance of `from` through the caller's allowance, returning the transaction ID
+    pub fn transfer_from(
+        &self,
+        from: AccountId32,
+        to: AccountId32,
+        amount: Balance,
+    ) -> Call<'a, Result<u32, PaymentError>> {
+        self.call(selector_bytes!("transfer_from"), (from, to, amount))
+    }
+
+    pub fn balance_of(&self, account: AccountId32) -> Query<'a, Balance> {
+        self.query(selector_bytes!("balance_of"), account)
+    }
+
+    pub fn allowance(&self, owner: AccountId32, spender: AccountId32) -> Query<'a, Balance> {
+        self.query(selector_bytes!("allowance"), (owner, spender))
+    }
+}
@@ -0,0 +1,120 @@
+//! Typed Rust bindings for the 0xCC contracts
+//!
+//! Calls to the Payment, BillSplitting and XcmHandler contracts are built
+//! from typed arguments, dry-run against the latest block and submitted over
+//! subxt, with return values decoded into the contracts' own types:
+//!
+//! ```no_run
+//! # async fn example(payment: subxt::utils::AccountId32, bob: subxt::utils::AccountId32) -> Result<(), Box<dyn std::error::Error>> {
+//! use xcc_sdk::{events::PaymentSent, Client};
+//!
+//! let client = Client::connect("ws://127.0.0.1:9944").await?.with_payment(payment);
+//! let alice = subxt_signer::sr25519::dev::alice();
+//!
+//! let receipt = client.payment().send(bob.clone(), 1_000).signed(&alice).submit().await?;
+//! for sent in receipt.events_of::<PaymentSent>() {
+//!     println!("transaction {} sent {}", sent.transaction_id, sent.amount);
+//! }
+//! let balance = client.payment().balance_of(bob).fetch().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod bills;
+mod call;
+mod error;
+pub mod events;
+mod ledger;
+mod xcm;
+
+use subxt::{utils::AccountId32, OnlineClient, PolkadotConfig};
+
+pub use crate::{
+    bills::BillSplitting,
+    call::{Call, DryRun, MessageOutput, Query, Receipt, Weight},
+    error::Error,
+    events::{ContractEvent, EmittedEvent},
+    ledger::Payment,
+    xcm::XcmHandler,
+};
+
+/// Types the contracts take and return
+pub mod types {
+    pub use bill_splitting::{BillCategory, BillMetadata, Error as BillSplittingError};
+    pub use ink::primitives::{AccountId, Hash};
+    pub use payment::Error as PaymentError;
+    pub use xcc_common::{IntentAsset, PaymentIntent, XcmMessageType};
+    pub use xcm_handler::{
+        Beneficiary, Error as XcmHandlerError, EthereumTransfer, ExecutionProof, PaymentStatus, XcmPaymentInfo,
+    };
+
+    pub type Balance = u128;
+}
+
+/// A connection to a chain and the addresses of the contracts deployed on it
+#[derive(Clone)]
+pub struct Client {
+    api: OnlineClient<PolkadotConfig>,
+    payment: Option<AccountId32>,
+    bill_splitting: Option<AccountId32>,
+    xcm_handler: Option<AccountId32>,
+}
+
+impl Client {
+    /// Connect to a node's WebSocket endpoint
+    pub async fn connect(url: &str) -> Result<Self, subxt::Error> {
+        Ok(Self::from_api(OnlineClient::from_url(url).await?))
+    }
+
+    /// Use an existing subxt client
+    pub fn from_api(api: OnlineClient<PolkadotConfig>) -> Self {
+        Self {
+            api,
+            payment: None,
+            bill_splitting: None,
+            xcm_handler: None,
+        }
+    }
+
+    pub fn with_payment(mut self, address: AccountId32) -> Self {
+        self.payment = Some(address);
+        self
+    }
+
+    pub fn with_bill_splitting(mut self, address: AccountId32) -> Self {
+        self.bill_splitting = Some(address);
+        self
+    }
+
+    pub fn with_xcm_handler(mut self, address: AccountId32) -> Self {
+        self.xcm_handler = Some(address);
+        self
+    }
+
+    /// The underlying subxt client
+    pub fn api(&self) -> &OnlineClient<PolkadotConfig> {
+        &self.api
+    }
+
+    pub fn payment(&self) -> Payment<'_> {
+        Payment::new(self, self.payment.clone().ok_or("Payment"))
+    }
+
+    pub fn bill_splitting(&self) -> BillSplitting<'_> {
+        BillSplitting::new(self, self.bill_splitting.clone().ok_or("BillSplitting"))
+    }
+
+    pub fn xcm_handler(&self) -> XcmHandler<'_> {
+        XcmHandler::new(self, self.xcm_handler.clone().ok_or("XcmHandler"))
+    }
+
+    /// The contract events emitted in a block, for following the contracts
+    pub async fn contract_events(&self, block_hash: subxt::utils::H256) -> Result<Vec<EmittedEvent>, Error> {
+        let events = self.api.events().at(block_hash).await?;
+        let mut emitted = Vec::new();
+        for event in events.iter() {
+            emitted.extend(EmittedEvent::from_details(&event?)?);
+        }
+        Ok(emitted)
+    }
+}
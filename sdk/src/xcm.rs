@@ -0,0 +1,140 @@
+use ink::selector_bytes;
+use subxt::utils::AccountId32;
+
+use crate::{
+    types::{
+        Balance, EthereumTransfer, ExecutionProof, Hash, PaymentStatus, XcmHandlerError, XcmMessageType,
+        XcmPaymentInfo,
+    },
+    Call, Client, Query,
+};
+
+/// Bindings of the XcmHandler contract
+pub struct XcmHandler<'a> {
+    client: &'a Client,
+    address: Result<AccountId32, &'static str>,
+}
+
+impl<'a> XcmHandler<'a> {
+    pub(crate) fn new(client: &'a Client, address: Result<AccountId32, &'static str>) -> Self {
+        Self { client, address }
+    }
+
+    fn call<R: crate::MessageOutput>(&self, selector: [u8; 4], args: impl codec::Encode) -> Call<'a, R> {
+        Call::new(self.client, self.address.clone(), selector, args)
+    }
+
+    fn query<T: codec::Decode>(&self, selector: [u8; 4], args: impl codec::Encode) -> Query<'a, T> {
+        Query::new(self.client, self.address.clone(), selector, args)
+    }
+
+    /// Deposit native funds to pay cross-chain payments from
+    pub fn deposit(&self, amount: Balance) -> Call<'a, ()> {
+        self.call(selector_bytes!("deposit"), ()).value(amount)
+    }
+
+    pub fn withdraw(&self, amount: Balance) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(selector_bytes!("withdraw"), amount)
+    }
+
+    /// Create a cross-chain payment from the deposited balance, returning its ID
+    pub fn create_payment(
+        &self,
+        recipient: AccountId32,
+        asset_id: u32,
+        amount: Balance,
+        destination_chain: u32,
+        message_type: XcmMessageType,
+    ) -> Call<'a, Result<u32, XcmHandlerError>> {
+        self.call(
+            selector_bytes!("create_cross_chain_payment"),
+            (recipient, asset_id, amount, destination_chain, message_type),
+        )
+    }
+
+    /// Execute a payment, or attest its execution as a relayer
+    pub fn execute_payment(&self, payment_id: u32) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(selector_bytes!("execute_cross_chain_payment"), payment_id)
+    }
+
+    /// Cancel a pending payment, returning its funds to the sender
+    pub fn cancel_payment(&self, payment_id: u32) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(selector_bytes!("cancel_cross_chain_payment"), payment_id)
+    }
+
+    /// Claim a pending payment for delivery, as a relayer of its destination chain
+    pub fn claim_payment(&self, payment_id: u32) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(selector_bytes!("claim_payment"), payment_id)
+    }
+
+    /// Confirm a claimed payment executed on its destination chain, as its claimant
+    pub fn confirm_execution(&self, payment_id: u32, proof: ExecutionProof) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(selector_bytes!("confirm_execution"), (payment_id, proof))
+    }
+
+    /// Credit a payment sent to this chain, as a relayer of its source chain
+    ///
+    /// The proof must match the source chain's verification mode; on `Trusted`
+    /// chains the payment is credited once enough relayers attested it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn receive_payment(
+        &self,
+        source_chain: u32,
+        message_hash: Hash,
+        sender: AccountId32,
+        recipient: AccountId32,
+        asset_id: u32,
+        amount: Balance,
+        nonce: u64,
+        proof: ExecutionProof,
+    ) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(
+            selector_bytes!("receive_cross_chain_payment"),
+            (source_chain, message_hash, sender, recipient, asset_id, amount, nonce, proof),
+        )
+    }
+
+    /// The hash `receive_payment` expects for its other arguments
+    pub fn inbound_message_hash(
+        &self,
+        source_chain: u32,
+        sender: AccountId32,
+        recipient: AccountId32,
+        asset_id: u32,
+        amount: Balance,
+        nonce: u64,
+    ) -> Query<'a, Hash> {
+        self.query(
+            selector_bytes!("inbound_message_hash"),
+            (source_chain, sender, recipient, asset_id, amount, nonce),
+        )
+    }
+
+    /// Support a chain or stop supporting it, optionally adding a relayer of it (owner only)
+    pub fn configure_chain(
+        &self,
+        chain_id: u32,
+        supported: bool,
+        relayer: Option<AccountId32>,
+    ) -> Call<'a, Result<(), XcmHandlerError>> {
+        self.call(selector_bytes!("configure_chain"), (chain_id, supported, relayer))
+    }
+
+    pub fn get_payment(&self, payment_id: u32) -> Query<'a, Option<XcmPaymentInfo>> {
+        self.query(selector_bytes!("get_payment"), payment_id)
+    }
+
+    pub fn get_payment_status(&self, payment_id: u32) -> Query<'a, Option<PaymentStatus>> {
+        self.query(selector_bytes!("get_payment_status"), payment_id)
+    }
+
+    /// Snowbridge message of a payment to Ethereum, for relayers delivering it
+    pub fn get_ethereum_transfer(&self, payment_id: u32) -> Query<'a, Option<EthereumTransfer>> {
+        self.query(selector_bytes!("get_ethereum_transfer"), payment_id)
+    }
+
+    /// Deposited balance of an account
+    pub fn get_balance(&self, account: AccountId32) -> Query<'a, Balance> {
+        self.query(selector_bytes!("get_balance"), account)
+    }
+}